@@ -17,8 +17,11 @@
 //! Account management.
 
 use std::fmt;
+use std::cmp;
 use std::collections::HashMap;
-use util::{Address as H160, H256, H520, RwLock};
+use std::time::{Instant, Duration};
+use crossbeam;
+use util::{Address as H160, H256, H520, Mutex, RwLock};
 use ethstore::{SecretStore, Error as SSError, SafeAccount, EthStore};
 use ethstore::dir::{KeyDirectory};
 use ethstore::ethkey::{Address as SSAddress, Message as SSMessage, Secret as SSSecret, Random, Generator};
@@ -32,6 +35,70 @@ enum Unlock {
 	/// Account unlocked permantently can always sign message.
 	/// Use with caution.
 	Perm,
+	/// Account unlocked with a timeout. It's locked again once the deadline passes.
+	Timed(Instant),
+}
+
+/// How long an account should stay unlocked.
+#[derive(Debug, Clone, PartialEq)]
+pub enum UnlockDuration {
+	/// Until the provider is dropped.
+	Permanent,
+	/// For a single signing.
+	Once,
+	/// For given amount of time.
+	Timed(Duration),
+}
+
+impl From<UnlockDuration> for Unlock {
+	fn from(d: UnlockDuration) -> Self {
+		match d {
+			UnlockDuration::Permanent => Unlock::Perm,
+			UnlockDuration::Once => Unlock::Temp,
+			UnlockDuration::Timed(duration) => Unlock::Timed(Instant::now() + duration),
+		}
+	}
+}
+
+/// Throttling of password attempts, mitigating brute-forcing of account passwords.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnlockPolicy {
+	/// Number of failed attempts for an account before further ones are delayed.
+	/// Zero disables the throttling.
+	pub max_attempts: usize,
+	/// Delay after `max_attempts` failures. Doubled with every further failure.
+	pub delay: Duration,
+	/// Maximal delay.
+	pub max_delay: Duration,
+}
+
+impl Default for UnlockPolicy {
+	fn default() -> Self {
+		UnlockPolicy {
+			max_attempts: 5,
+			delay: Duration::from_secs(2),
+			max_delay: Duration::from_secs(3600),
+		}
+	}
+}
+
+impl UnlockPolicy {
+	// delay after given number of consecutive failures, if any.
+	fn delay(&self, failures: usize) -> Option<Duration> {
+		if self.max_attempts == 0 || failures < self.max_attempts {
+			return None;
+		}
+		let exponent = cmp::min(failures - self.max_attempts, 31);
+		let delay = Duration::from_secs(self.delay.as_secs().saturating_mul(1 << exponent));
+		Some(cmp::min(delay, self.max_delay))
+	}
+}
+
+/// Failed password attempts for an account.
+#[derive(Default)]
+struct Failures {
+	count: usize,
+	locked_until: Option<Instant>,
 }
 
 /// Data associated with account.
@@ -48,6 +115,9 @@ pub enum Error {
 	NotUnlocked,
 	/// Returned when signing fails.
 	SStore(SSError),
+	/// Returned when there were too many failed password attempts for the account.
+	/// Contains the time left until the next attempt is allowed.
+	Throttled(Duration),
 }
 
 impl fmt::Display for Error {
@@ -55,6 +125,7 @@ impl fmt::Display for Error {
 		match *self {
 			Error::NotUnlocked => write!(f, "Account is locked"),
 			Error::SStore(ref e) => write!(f, "{}", e),
+			Error::Throttled(ref left) => write!(f, "Too many failed password attempts, try again in {} seconds", left.as_secs() + 1),
 		}
 	}
 }
@@ -128,22 +199,36 @@ impl KeyDirectory for NullDir {
 pub struct AccountProvider {
 	unlocked: RwLock<HashMap<SSAddress, AccountData>>,
 	sstore: Box<SecretStore>,
+	unlock_policy: UnlockPolicy,
+	failures: Mutex<HashMap<SSAddress, Failures>>,
 }
 
 impl AccountProvider {
-	/// Creates new account provider.
+	/// Creates new account provider signing with the given backend: a local key store,
+	/// or a `RemoteSigner` keeping the keys off the node.
 	pub fn new(sstore: Box<SecretStore>) -> Self {
 		AccountProvider {
 			unlocked: RwLock::new(HashMap::new()),
 			sstore: sstore,
+			unlock_policy: UnlockPolicy::default(),
+			failures: Mutex::new(HashMap::new()),
 		}
 	}
 
+	/// Sets the throttling of password attempts made through `unlock_account_*`
+	/// and `sign_with_password`.
+	pub fn with_unlock_policy(mut self, policy: UnlockPolicy) -> Self {
+		self.unlock_policy = policy;
+		self
+	}
+
 	/// Creates not disk backed provider.
 	pub fn transient_provider() -> Self {
 		AccountProvider {
 			unlocked: RwLock::new(HashMap::new()),
-			sstore: Box::new(EthStore::open(Box::new(NullDir)).unwrap())
+			sstore: Box::new(EthStore::open(Box::new(NullDir)).unwrap()),
+			unlock_policy: UnlockPolicy::default(),
+			failures: Mutex::new(HashMap::new()),
 		}
 	}
 
@@ -167,13 +252,63 @@ impl AccountProvider {
 		self.sstore.accounts().into_iter().map(|a| H160(a.into())).collect()
 	}
 
+	/// Makes a password attempt for the account unless it's throttled, noting failed attempts.
+	fn attempt<T, F>(&self, account: &SSAddress, f: F) -> Result<T, Error> where F: FnOnce() -> Result<T, SSError> {
+		let now = Instant::now();
+		{
+			let mut failures = self.failures.lock();
+			let entry = failures.entry(account.clone()).or_insert_with(Failures::default);
+			if let Some(until) = entry.locked_until {
+				if now < until {
+					return Err(Error::Throttled(until - now));
+				}
+			}
+			// the attempt counts as failed until it succeeds, so that parallel attempts
+			// can't all pass the check above before any failure is noted.
+			entry.count += 1;
+			entry.locked_until = self.unlock_policy.delay(entry.count).map(|delay| now + delay);
+		}
+
+		let result = f();
+		let mut failures = self.failures.lock();
+		match result {
+			Ok(value) => {
+				failures.remove(account);
+				Ok(value)
+			},
+			Err(SSError::InvalidPassword) => {
+				if let Some(entry) = failures.get(account) {
+					if let Some(delay) = self.unlock_policy.delay(entry.count) {
+						warn!(target: "account", "{} failed password attempts for account {:?}, further attempts delayed by {} seconds", entry.count, H160(account.clone().into()), delay.as_secs());
+					}
+				}
+				Err(SSError::InvalidPassword.into())
+			},
+			Err(e) => {
+				// not a password failure, so the attempt isn't counted after all.
+				let forget = match failures.get_mut(account) {
+					Some(entry) => {
+						entry.count = entry.count.saturating_sub(1);
+						entry.locked_until = self.unlock_policy.delay(entry.count).map(|delay| now + delay);
+						entry.count == 0
+					},
+					None => false,
+				};
+				if forget {
+					failures.remove(account);
+				}
+				Err(e.into())
+			},
+		}
+	}
+
 	/// Helper method used for unlocking accounts.
 	fn unlock_account<A>(&self, account: A, password: String, unlock: Unlock) -> Result<(), Error> where Address: From<A> {
 		let a = Address::from(account);
 		let account = a.into();
 		// verify password by signing dump message
 		// result may be discarded
-		let _ = try!(self.sstore.sign(&account, &password, &Default::default()));
+		let _ = try!(self.attempt(&account, || self.sstore.sign(&account, &password, &Default::default())));
 
 		// check if account is already unlocked pernamently, if it is, do nothing
 		{
@@ -205,11 +340,72 @@ impl AccountProvider {
 		self.unlock_account(account, password, Unlock::Temp)
 	}
 
+	/// Unlocks account for the given duration.
+	pub fn unlock_account_timed<A>(&self, account: A, password: String, duration: Duration) -> Result<(), Error> where Address: From<A> {
+		self.unlock_account(account, password, Unlock::Timed(Instant::now() + duration))
+	}
+
+	/// Unlocks many accounts at once, trying each of the `passwords` for every account.
+	/// Key derivation for different accounts runs in parallel on up to `threads` threads.
+	/// Wrong passwords count as failed attempts, so an account may be throttled before
+	/// all the passwords were tried.
+	/// Returns accounts which could not be unlocked with any of the passwords.
+	pub fn unlock_accounts(&self, requests: Vec<(H160, UnlockDuration)>, passwords: &[String], threads: usize) -> Vec<H160> {
+		let requests = requests.into_iter()
+			.map(|(account, duration)| (Address::from(account).into(), duration))
+			.collect::<Vec<(SSAddress, UnlockDuration)>>();
+		let chunk_size = cmp::max(1, (requests.len() + threads - 1) / cmp::max(1, threads));
+
+		let results = crossbeam::scope(|scope| {
+			let handles = requests.chunks(chunk_size).map(|chunk| scope.spawn(move || {
+				chunk.iter().map(|&(ref account, ref duration)| {
+					let mut password = None;
+					for p in passwords {
+						// verify password by signing dump message
+						match self.attempt(account, || self.sstore.sign(account, p, &Default::default())) {
+							Ok(_) => {
+								password = Some(p.clone());
+								break;
+							},
+							Err(Error::Throttled(_)) => break,
+							Err(_) => {},
+						}
+					}
+					(account.clone(), duration.clone(), password)
+				}).collect::<Vec<_>>()
+			})).collect::<Vec<_>>();
+
+			handles.into_iter().flat_map(|handle| handle.join()).collect::<Vec<_>>()
+		});
+
+		let mut unlocked = self.unlocked.write();
+		results.into_iter().filter_map(|(account, duration, password)| match password {
+			Some(password) => {
+				let is_perm = match unlocked.get(&account) {
+					Some(&AccountData { unlock: Unlock::Perm, .. }) => true,
+					_ => false,
+				};
+				if !is_perm {
+					unlocked.insert(account, AccountData {
+						unlock: duration.into(),
+						password: password,
+					});
+				}
+				None
+			},
+			None => Some(H160(account.into())),
+		}).collect()
+	}
+
 	/// Checks if given account is unlocked
 	pub fn is_unlocked<A>(&self, account: A) -> bool where Address: From<A> {
 		let account = Address::from(account).into();
 		let unlocked = self.unlocked.read();
-		unlocked.get(&account).is_some()
+		match unlocked.get(&account) {
+			Some(&AccountData { unlock: Unlock::Timed(ref end), .. }) => Instant::now() <= *end,
+			Some(_) => true,
+			None => false,
+		}
 	}
 
 	/// Signs the message. Account must be unlocked.
@@ -222,9 +418,27 @@ impl AccountProvider {
 			try!(unlocked.get(&account).ok_or(Error::NotUnlocked)).clone()
 		};
 
-		if let Unlock::Temp = data.unlock {
-			let mut unlocked = self.unlocked.write();
-			unlocked.remove(&account).expect("data exists: so key must exist: qed");
+		match data.unlock {
+			Unlock::Temp => {
+				let mut unlocked = self.unlocked.write();
+				// another signing may have used the unlock up since it was read.
+				if unlocked.remove(&account).is_none() {
+					return Err(Error::NotUnlocked);
+				}
+			},
+			Unlock::Timed(ref end) if Instant::now() > *end => {
+				let mut unlocked = self.unlocked.write();
+				// the entry may have been removed or the account unlocked again since it was read.
+				let expired = match unlocked.get(&account) {
+					Some(&AccountData { unlock: Unlock::Timed(ref end), .. }) => Instant::now() > *end,
+					_ => false,
+				};
+				if expired {
+					unlocked.remove(&account);
+				}
+				return Err(Error::NotUnlocked);
+			},
+			Unlock::Timed(_) | Unlock::Perm => {},
 		}
 
 		let signature = try!(self.sstore.sign(&account, &data.password, &message));
@@ -232,17 +446,22 @@ impl AccountProvider {
 	}
 
 	/// Unlocks an account, signs the message, and locks it again.
+	/// Returned signature is recoverable (`r`, `s`, `v`).
 	pub fn sign_with_password<A, M>(&self, account: A, password: String, message: M) -> Result<H520, Error> where Address: From<A>, Message: From<M> {
 		let account = Address::from(account).into();
 		let message = Message::from(message).into();
-		let signature = try!(self.sstore.sign(&account, &password, &message));
+		let signature = try!(self.attempt(&account, || self.sstore.sign_with_password(&account, &password, &message)));
 		Ok(H520(signature.into()))
 	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::AccountProvider;
+	use super::{AccountProvider, UnlockDuration, UnlockPolicy, Error};
+	use std::sync::Arc;
+	use std::thread;
+	use std::time::Duration;
+	use util::Address;
 	use ethstore::ethkey::{Generator, Random};
 
 	#[test]
@@ -269,4 +488,148 @@ mod tests {
 		assert!(ap.sign(kp.address(), [0u8; 32]).is_ok());
 		assert!(ap.sign(kp.address(), [0u8; 32]).is_ok());
 	}
+
+	#[test]
+	fn sign_with_password_does_not_unlock() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.sign_with_password(kp.address(), "test1".into(), [0u8; 32]).is_err());
+		assert!(ap.sign_with_password(kp.address(), "test".into(), [0u8; 32]).is_ok());
+		assert!(!ap.is_unlocked(kp.address()));
+		assert!(ap.sign(kp.address(), [0u8; 32]).is_err());
+	}
+
+	#[test]
+	fn unlock_account_timer() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider();
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.unlock_account_timed(kp.address(), "test1".into(), Duration::from_secs(60)).is_err());
+		assert!(ap.unlock_account_timed(kp.address(), "test".into(), Duration::from_secs(60)).is_ok());
+		assert!(ap.sign(kp.address(), [0u8; 32]).is_ok());
+		assert!(ap.sign(kp.address(), [0u8; 32]).is_ok());
+		assert!(ap.unlock_account_timed(kp.address(), "test".into(), Duration::from_secs(0)).is_ok());
+		::std::thread::sleep(Duration::from_millis(10));
+		assert!(!ap.is_unlocked(kp.address()));
+		assert!(ap.sign(kp.address(), [0u8; 32]).is_err());
+	}
+
+	#[test]
+	fn concurrent_signing_uses_unlock_once() {
+		let kp = Random.generate().unwrap();
+		let ap = Arc::new(AccountProvider::transient_provider());
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.unlock_account_temporarily(kp.address(), "test".into()).is_ok());
+
+		let signed = (0..8).map(|_| {
+			let ap = ap.clone();
+			let address = kp.address();
+			thread::spawn(move || ap.sign(address, [0u8; 32]).is_ok())
+		}).collect::<Vec<_>>().into_iter().filter(|handle| handle.join().unwrap()).count();
+
+		assert_eq!(signed, 1);
+	}
+
+	#[test]
+	fn concurrent_signing_with_expired_unlock_fails() {
+		let kp = Random.generate().unwrap();
+		let ap = Arc::new(AccountProvider::transient_provider());
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.unlock_account_timed(kp.address(), "test".into(), Duration::from_secs(0)).is_ok());
+		thread::sleep(Duration::from_millis(10));
+
+		let handles = (0..8).map(|_| {
+			let ap = ap.clone();
+			let address = kp.address();
+			thread::spawn(move || match ap.sign(address, [0u8; 32]) {
+				Err(Error::NotUnlocked) => {},
+				_ => panic!("expired unlock should be refused"),
+			})
+		}).collect::<Vec<_>>();
+
+		for handle in handles {
+			handle.join().unwrap();
+		}
+	}
+
+	#[test]
+	fn throttles_failed_attempts() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider().with_unlock_policy(UnlockPolicy {
+			max_attempts: 2,
+			delay: Duration::from_secs(60),
+			max_delay: Duration::from_secs(600),
+		});
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		assert!(ap.unlock_account_temporarily(kp.address(), "test1".into()).is_err());
+		assert!(ap.unlock_account_temporarily(kp.address(), "test".into()).is_ok());
+		assert!(ap.unlock_account_temporarily(kp.address(), "test1".into()).is_err());
+		assert!(ap.sign_with_password(kp.address(), "test1".into(), [0u8; 32]).is_err());
+
+		// even the right password is refused until the delay passes.
+		match ap.sign_with_password(kp.address(), "test".into(), [0u8; 32]) {
+			Err(Error::Throttled(left)) => assert!(left <= Duration::from_secs(60)),
+			_ => panic!("attempt should be throttled"),
+		}
+		assert!(!ap.is_unlocked(kp.address()));
+	}
+
+	#[test]
+	fn throttling_delay_grows() {
+		let policy = UnlockPolicy {
+			max_attempts: 3,
+			delay: Duration::from_secs(2),
+			max_delay: Duration::from_secs(10),
+		};
+		assert_eq!(policy.delay(2), None);
+		assert_eq!(policy.delay(3), Some(Duration::from_secs(2)));
+		assert_eq!(policy.delay(4), Some(Duration::from_secs(4)));
+		assert_eq!(policy.delay(6), Some(Duration::from_secs(10)));
+		assert_eq!(policy.delay(100), Some(Duration::from_secs(10)));
+		assert_eq!(UnlockPolicy { max_attempts: 0, ..policy }.delay(100), None);
+	}
+
+	#[test]
+	fn unlock_many_accounts() {
+		let ap = AccountProvider::transient_provider();
+		let accounts = (0..4).map(|i| {
+			let kp = Random.generate().unwrap();
+			assert!(ap.insert_account(kp.secret().clone(), &format!("test{}", i % 2)).is_ok());
+			Address(kp.address().into())
+		}).collect::<Vec<_>>();
+		let requests = accounts.iter().map(|a| (a.clone(), UnlockDuration::Permanent)).collect();
+		let passwords = vec!["test0".to_owned(), "invalid".to_owned()];
+
+		let mut failed = ap.unlock_accounts(requests, &passwords, 2);
+		failed.sort();
+
+		let mut expected = vec![accounts[1].clone(), accounts[3].clone()];
+		expected.sort();
+		assert_eq!(failed, expected);
+		assert!(ap.sign(accounts[0].clone(), [0u8; 32]).is_ok());
+		assert!(ap.sign(accounts[1].clone(), [0u8; 32]).is_err());
+		assert!(ap.sign(accounts[2].clone(), [0u8; 32]).is_ok());
+	}
+
+	#[test]
+	fn unlock_many_accounts_is_throttled() {
+		let kp = Random.generate().unwrap();
+		let ap = AccountProvider::transient_provider().with_unlock_policy(UnlockPolicy {
+			max_attempts: 2,
+			delay: Duration::from_secs(60),
+			max_delay: Duration::from_secs(600),
+		});
+		assert!(ap.insert_account(kp.secret().clone(), "test").is_ok());
+		let account = Address(kp.address().into());
+		let passwords = vec!["invalid0".to_owned(), "invalid1".to_owned(), "test".to_owned()];
+
+		// the right password comes only after the attempts ran out.
+		let failed = ap.unlock_accounts(vec![(account.clone(), UnlockDuration::Permanent)], &passwords, 1);
+		assert_eq!(failed, vec![account.clone()]);
+		match ap.unlock_account_permanently(account, "test".into()) {
+			Err(Error::Throttled(_)) => {},
+			_ => panic!("attempt should be throttled"),
+		}
+	}
 }
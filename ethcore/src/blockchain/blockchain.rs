@@ -24,6 +24,7 @@ use super::extras::*;
 use transaction::*;
 use views::*;
 use receipt::Receipt;
+use log_entry::LogEntry;
 use blooms::{Bloom, BloomGroup};
 use blockchain::block_info::{BlockInfo, BlockLocation, BranchBecomingCanonChainData};
 use blockchain::best_block::BestBlock;
@@ -106,6 +107,25 @@ pub trait BlockProvider {
 
 	/// Returns numbers of blocks containing given bloom.
 	fn blocks_with_bloom(&self, bloom: &H2048, from_block: BlockNumber, to_block: BlockNumber) -> Vec<BlockNumber>;
+
+	/// Returns numbers of blocks containing any of given blooms, in ascending order.
+	fn blocks_with_blooms(&self, blooms: &[H2048], from_block: BlockNumber, to_block: BlockNumber) -> Vec<BlockNumber>;
+}
+
+/// Any of a set of blooms within a range of blocks.
+struct BloomsFilter<'a> {
+	blooms: &'a [H2048],
+	range: ::std::ops::Range<bc::Number>,
+}
+
+impl<'a> bc::Filter for BloomsFilter<'a> {
+	fn bloom_possibilities(&self) -> Vec<bc::Bloom> {
+		self.blooms.iter().map(|bloom| Bloom::from(bloom.clone()).into()).collect()
+	}
+
+	fn range(&self) -> ::std::ops::Range<bc::Number> {
+		self.range.clone()
+	}
 }
 
 #[derive(Debug, Hash, Eq, PartialEq, Clone)]
@@ -123,6 +143,41 @@ struct CacheManager {
 	in_use: HashSet<CacheID>,
 }
 
+/// Author of the block and the statistics of that single block.
+fn author_stats_of(block_bytes: &[u8], receipts: &[Receipt]) -> (Address, AuthorStats) {
+	let block = BlockView::new(block_bytes);
+	let mut previous_gas_used = U256::zero();
+	let fees = block.transaction_views().iter().zip(receipts.iter()).fold(U256::zero(), |fees, (tx, receipt)| {
+		let gas_used = receipt.gas_used - previous_gas_used;
+		previous_gas_used = receipt.gas_used;
+		fees + gas_used * tx.gas_price()
+	});
+
+	(block.header_view().author(), AuthorStats {
+		blocks: 1,
+		uncles: block.uncles_count() as u64,
+		fees: fees,
+	})
+}
+
+/// Sender and nonce index entries of the transactions in a block with given hash.
+/// Senders which are not given are recovered from the transactions' signatures.
+fn sender_nonces_of(hash: &H256, block_bytes: &[u8], senders: Option<&[Address]>) -> Vec<(SenderNonce, TransactionAddress)> {
+	BlockView::new(block_bytes).transactions().into_iter().enumerate().filter_map(|(i, tx)| {
+		let sender = match senders.and_then(|senders| senders.get(i)) {
+			Some(sender) => sender.clone(),
+			None => match tx.sender() {
+				Ok(sender) => sender,
+				Err(_) => return None,
+			},
+		};
+		Some((SenderNonce { sender: sender, nonce: tx.nonce.clone() }, TransactionAddress {
+			block_hash: hash.clone(),
+			index: i,
+		}))
+	}).collect()
+}
+
 impl bc::group::BloomGroupDatabase for BlockChain {
 	fn blooms_at(&self, position: &bc::group::GroupPosition) -> Option<bc::group::BloomGroup> {
 		let position = LogGroupPosition::from(position.clone());
@@ -213,7 +268,17 @@ impl BlockProvider for BlockChain {
 	/// Get receipts of block with given hash.
 	fn block_receipts(&self, hash: &H256) -> Option<BlockReceipts> {
 		self.note_used(CacheID::BlockReceipts(hash.clone()));
-		self.extras_db.read_with_cache(&self.block_receipts, hash)
+		{
+			let read = self.block_receipts.read();
+			if let Some(v) = read.get(hash) {
+				return Some(v.clone());
+			}
+		}
+
+		self.read_block_receipts(hash).map(|receipts| {
+			self.block_receipts.write().insert(hash.clone(), receipts.clone());
+			receipts
+		})
 	}
 
 	/// Returns numbers of blocks containing given bloom.
@@ -225,10 +290,32 @@ impl BlockProvider for BlockChain {
 			.map(|b| b as BlockNumber)
 			.collect()
 	}
+
+	/// Returns numbers of blocks containing any of given blooms, in ascending order.
+	/// Each level of the index is walked only once, whatever the number of blooms.
+	fn blocks_with_blooms(&self, blooms: &[H2048], from_block: BlockNumber, to_block: BlockNumber) -> Vec<BlockNumber> {
+		let filter = BloomsFilter {
+			blooms: blooms,
+			range: from_block as bc::Number..to_block as bc::Number,
+		};
+		let chain = bc::group::BloomGroupChain::new(self.blooms_config, self);
+		let mut numbers = chain.filter(&filter)
+			.into_iter()
+			.map(|b| b as BlockNumber)
+			.collect::<Vec<_>>();
+		numbers.sort();
+		numbers.dedup();
+		numbers
+	}
 }
 
 const COLLECTION_QUEUE_SIZE: usize = 8;
 
+/// Key of the number of blocks, counting from genesis, which may still lack
+/// sender and author statistics index entries, having been imported before
+/// those indexes were introduced.
+const INDEX_BACKFILL_KEY: &'static [u8] = b"index_backfill";
+
 pub struct AncestryIter<'a> {
 	current: H256,
 	chain: &'a BlockChain,
@@ -248,6 +335,16 @@ impl<'a> Iterator for AncestryIter<'a> {
 }
 
 impl BlockChain {
+	/// Returns the database storing block headers and bodies.
+	pub fn blocks_db(&self) -> &Database {
+		&self.blocks_db
+	}
+
+	/// Returns the database storing block details, hashes, receipts and blooms.
+	pub fn extras_db(&self) -> &Database {
+		&self.extras_db
+	}
+
 	/// Create new instance of blockchain from given Genesis
 	pub fn new(config: Config, genesis: &[u8], path: &Path) -> BlockChain {
 		// open extras db
@@ -335,12 +432,20 @@ impl BlockChain {
 				batch.write(&hash, &details);
 				batch.write(&header.number(), &hash);
 				batch.put(b"best", &hash).unwrap();
+				batch.put(INDEX_BACKFILL_KEY, &encode(&0u64)).unwrap();
 				bc.extras_db.write(batch).unwrap();
 
 				hash
 			}
 		};
 
+		// a database created before the sender and author statistics indexes
+		// needs them to be backfilled for all of its blocks.
+		if bc.extras_db.get(INDEX_BACKFILL_KEY).unwrap().is_none() {
+			let pending = bc.block_number(&best_block_hash).map_or(0, |number| number + 1);
+			bc.extras_db.put(INDEX_BACKFILL_KEY, &encode(&pending)).unwrap();
+		}
+
 		{
 			let mut best_block = bc.best_block.write();
 			best_block.number = bc.block_number(&best_block_hash).unwrap();
@@ -492,11 +597,21 @@ impl BlockChain {
 		}
 	}
 
-	#[cfg_attr(feature="dev", allow(similar_names))]
 	/// Inserts the block into backing cache database.
 	/// Expects the block to be valid and already verified.
 	/// If the block is already known, does nothing.
 	pub fn insert_block(&self, bytes: &[u8], receipts: Vec<Receipt>) -> ImportRoute {
+		self.insert_block_with_senders(bytes, receipts, None)
+	}
+
+	/// Same as `insert_block`, but takes the senders of the block's transactions,
+	/// already recovered during verification, so they aren't recovered again.
+	pub fn insert_verified_block(&self, bytes: &[u8], receipts: Vec<Receipt>, senders: &[Address]) -> ImportRoute {
+		self.insert_block_with_senders(bytes, receipts, Some(senders))
+	}
+
+	#[cfg_attr(feature="dev", allow(similar_names))]
+	fn insert_block_with_senders(&self, bytes: &[u8], receipts: Vec<Receipt>, senders: Option<&[Address]>) -> ImportRoute {
 		// create views onto rlp
 		let block = BlockView::new(bytes);
 		let header = block.header_view();
@@ -511,13 +626,17 @@ impl BlockChain {
 		self.blocks_db.put(&hash, &bytes).unwrap();
 
 		let info = self.block_info(bytes);
+		let block_hashes = self.prepare_block_hashes_update(bytes, &info);
+		let author_stats = self.prepare_author_stats_update(bytes, &receipts, &info, &block_hashes);
 
 		self.apply_update(ExtrasUpdate {
-			block_hashes: self.prepare_block_hashes_update(bytes, &info),
+			block_hashes: block_hashes,
 			block_details: self.prepare_block_details_update(bytes, &info),
 			block_receipts: self.prepare_block_receipts_update(receipts, &info),
 			transactions_addresses: self.prepare_transaction_addresses_update(bytes, &info),
 			blocks_blooms: self.prepare_block_blooms_update(bytes, &info),
+			sender_nonces: self.prepare_sender_nonces_update(bytes, senders, &info),
+			author_stats: author_stats,
 			info: info.clone(),
 		});
 
@@ -538,8 +657,22 @@ impl BlockChain {
 		}
 
 		{
+			// receipts are stored without their logs, which are written one by one.
 			let mut write_receipts = self.block_receipts.write();
-			batch.extend_with_cache(&mut *write_receipts, update.block_receipts, CacheUpdatePolicy::Remove);
+			for (hash, receipts) in &update.block_receipts {
+				batch.write(hash, &BlockReceiptHeaders::from(receipts));
+				for (transaction_index, receipt) in receipts.receipts.iter().enumerate() {
+					for (log_index, log) in receipt.logs.iter().enumerate() {
+						let address = LogAddress {
+							block_hash: hash.clone(),
+							transaction_index: transaction_index,
+							log_index: log_index,
+						};
+						batch.write(&address, log);
+					}
+				}
+				write_receipts.remove(hash);
+			}
 		}
 
 		{
@@ -547,6 +680,14 @@ impl BlockChain {
 			batch.extend_with_cache(&mut *write_blocks_blooms, update.blocks_blooms, CacheUpdatePolicy::Remove);
 		}
 
+		for (sender_nonce, address) in &update.sender_nonces {
+			batch.write(sender_nonce, address);
+		}
+
+		for (position, bucket) in &update.author_stats {
+			batch.write(position, bucket);
+		}
+
 		// These cached values must be updated last with all three locks taken to avoid
 		// cache decoherence
 		{
@@ -587,6 +728,36 @@ impl BlockChain {
 		}
 	}
 
+	/// Read `first` and up to `count - 1` of its ancestors together with their receipts
+	/// straight from the database, without going through the caches. Returns
+	/// `(block, receipts)` RLP pairs, from `first` backwards.
+	/// Stops early at the genesis block or at the first block not in the database.
+	pub fn ancestry_with_receipts(&self, first: H256, count: usize) -> Vec<(Bytes, Bytes)> {
+		let mut pairs = Vec::with_capacity(count);
+		let mut hash = first;
+		while pairs.len() < count {
+			let block = match self.blocks_db.get(&hash).unwrap() {
+				Some(block) => block.to_vec(),
+				None => break,
+			};
+			let receipts = match self.read_block_receipts(&hash) {
+				Some(receipts) => encode(&receipts).to_vec(),
+				None => break,
+			};
+			let is_genesis = {
+				let header = BlockView::new(&block).header_view();
+				hash = header.parent_hash();
+				header.number() == 0
+			};
+
+			pairs.push((block, receipts));
+			if is_genesis {
+				break;
+			}
+		}
+		pairs
+	}
+
 	/// Given a block's `parent`, find every block header which represents a valid possible uncle.
 	pub fn find_uncle_headers(&self, parent: &H256, uncle_generations: usize) -> Option<Vec<Header>> {
 		self.find_uncle_hashes(parent, uncle_generations).map(|v| v.into_iter().filter_map(|h| self.block_header(&h)).collect())
@@ -728,6 +899,97 @@ impl BlockChain {
 			})
 	}
 
+	/// This function returns modified canon transactions by sender and nonce.
+	/// Only blocks becoming part of the canon chain are indexed, so that an entry
+	/// left over from a retracted block is replaced once its nonce is reused.
+	/// `senders`, if given, are those of the inserted block's transactions.
+	fn prepare_sender_nonces_update(&self, block_bytes: &[u8], senders: Option<&[Address]>, info: &BlockInfo) -> HashMap<SenderNonce, TransactionAddress> {
+		let mut sender_nonces = HashMap::new();
+		match info.location {
+			BlockLocation::Branch => (),
+			BlockLocation::CanonChain => sender_nonces.extend(sender_nonces_of(&info.hash, block_bytes, senders)),
+			BlockLocation::BranchBecomingCanonChain(ref data) => {
+				for hash in &data.enacted {
+					let bytes = self.block(hash).expect("Enacted blocks are known; qed");
+					sender_nonces.extend(sender_nonces_of(hash, &bytes, None));
+				}
+				sender_nonces.extend(sender_nonces_of(&info.hash, block_bytes, senders));
+			}
+		}
+
+		sender_nonces
+	}
+
+	/// Writes the sender and author statistics index entries of one bucket of
+	/// `AUTHOR_STATS_BUCKET_SIZE` canon blocks imported before those indexes existed,
+	/// starting with the most recent ones. Blocks are read bypassing the caches.
+	/// Returns `true` if there are still blocks left to index.
+	pub fn backfill_indexes(&self) -> bool {
+		let _lock = self.insert_lock.lock();
+		let pending: BlockNumber = match self.extras_db.get(INDEX_BACKFILL_KEY).unwrap() {
+			Some(pending) => decode(&pending),
+			None => 0,
+		};
+		if pending == 0 {
+			return false;
+		}
+
+		let position = AuthorStatsPosition::of_block(pending - 1);
+		let batch = DBTransaction::new();
+		// a bucket with a block missing (e.g. after a snapshot restoration) is left unindexed.
+		let mut bucket = Some(AuthorStatsBucket::default());
+		for number in position.first_block()..pending {
+			let hash: Option<H256> = self.extras_db.read(&number);
+			let block = hash.and_then(|hash| self.blocks_db.get(&hash).unwrap().map(|bytes| (hash, bytes.to_vec())));
+			let (hash, bytes) = match block {
+				Some(block) => block,
+				None => {
+					bucket = None;
+					continue;
+				}
+			};
+
+			for (sender_nonce, address) in sender_nonces_of(&hash, &bytes, None) {
+				batch.write(&sender_nonce, &address);
+			}
+
+			if let Some(ref mut bucket) = bucket {
+				let receipts = self.read_block_receipts(&hash).map(|r| r.receipts).unwrap_or_else(Vec::new);
+				let (author, stats) = author_stats_of(&bytes, &receipts);
+				bucket.authors.entry(author).or_insert_with(AuthorStats::default).accrue(&stats);
+			}
+		}
+
+		// a bucket which isn't complete yet is built once its last block is imported.
+		if let (Some(bucket), true) = (bucket, pending - 1 == position.last_block()) {
+			batch.write(&position, &bucket);
+		}
+
+		let remaining = position.first_block();
+		batch.put(INDEX_BACKFILL_KEY, &encode(&remaining)).unwrap();
+		self.extras_db.write(batch).unwrap();
+		remaining != 0
+	}
+
+	/// Returns the location of the canon transaction sent by `sender` with given `nonce`.
+	pub fn transaction_address_by_sender_nonce(&self, sender: &Address, nonce: &U256) -> Option<TransactionAddress> {
+		let key = SenderNonce {
+			sender: sender.clone(),
+			nonce: nonce.clone(),
+		};
+		self.extras_db.read(&key).and_then(|address: TransactionAddress| {
+			// a retracted block may still be referenced if the nonce hasn't been reused.
+			let number = match self.block_number(&address.block_hash) {
+				Some(number) => number,
+				None => return None,
+			};
+			match self.block_hash(number) == Some(address.block_hash.clone()) {
+				true => Some(address),
+				false => None,
+			}
+		})
+	}
+
 	/// This functions returns modified blocks blooms.
 	///
 	/// To accelerate blooms lookups, blomms are stored in multiple
@@ -778,6 +1040,72 @@ impl BlockChain {
 			.collect()
 	}
 
+	/// This function returns modified author statistics.
+	///
+	/// Statistics are kept only for buckets of `AUTHOR_STATS_BUCKET_SIZE` blocks
+	/// which are entirely part of the canon chain. A bucket is (re)built whenever
+	/// a block within it becomes canon while the bucket is complete, which covers
+	/// both the bucket's last block being imported and reorganisations.
+	fn prepare_author_stats_update(&self, block_bytes: &[u8], receipts: &[Receipt], info: &BlockInfo, block_hashes: &HashMap<BlockNumber, H256>) -> HashMap<AuthorStatsPosition, AuthorStatsBucket> {
+		let positions: HashSet<AuthorStatsPosition> = block_hashes.keys()
+			.map(|number| AuthorStatsPosition::of_block(*number))
+			.filter(|position| position.last_block() <= info.number)
+			.collect();
+
+		positions.into_iter().map(|position| {
+			let mut bucket = AuthorStatsBucket::default();
+			for number in position.first_block()..position.last_block() + 1 {
+				let hash = block_hashes.get(&number).cloned()
+					.or_else(|| self.block_hash(number))
+					.expect("Canon chain is complete up to the best block; qed");
+				let (author, stats) = match hash == info.hash {
+					true => author_stats_of(block_bytes, receipts),
+					false => {
+						let bytes = self.block(&hash).expect("Canon blocks are known; qed");
+						let receipts = self.block_receipts(&hash).map(|r| r.receipts).unwrap_or_else(Vec::new);
+						author_stats_of(&bytes, &receipts)
+					},
+				};
+				bucket.authors.entry(author).or_insert_with(AuthorStats::default).accrue(&stats);
+			}
+			(position, bucket)
+		}).collect()
+	}
+
+	/// Returns statistics of the authors of canon blocks `from` to `to` inclusive.
+	/// Returns `None` if any of those blocks is not known.
+	pub fn author_stats(&self, from: BlockNumber, to: BlockNumber) -> Option<BTreeMap<Address, AuthorStats>> {
+		let mut authors = BTreeMap::new();
+		let mut number = from;
+		while number <= to {
+			let position = AuthorStatsPosition::of_block(number);
+			if number == position.first_block() && position.last_block() <= to {
+				if let Some(bucket) = self.extras_db.read::<AuthorStatsBucket, _>(&position) {
+					for (author, stats) in bucket.authors {
+						authors.entry(author).or_insert_with(AuthorStats::default).accrue(&stats);
+					}
+					number = position.last_block() + 1;
+					continue;
+				}
+			}
+
+			// fall back to reading the block itself.
+			let hash = match self.block_hash(number) {
+				Some(hash) => hash,
+				None => return None,
+			};
+			let bytes = match self.block(&hash) {
+				Some(bytes) => bytes,
+				None => return None,
+			};
+			let receipts = self.block_receipts(&hash).map(|r| r.receipts).unwrap_or_else(Vec::new);
+			let (author, stats) = author_stats_of(&bytes, &receipts);
+			authors.entry(author).or_insert_with(AuthorStats::default).accrue(&stats);
+			number += 1;
+		}
+		Some(authors)
+	}
+
 	/// Get best block hash.
 	pub fn best_block_hash(&self) -> H256 {
 		self.best_block.read().hash.clone()
@@ -793,6 +1121,35 @@ impl BlockChain {
 		self.best_block.read().total_difficulty
 	}
 
+	/// Get receipts of block with given hash, without their logs.
+	pub fn receipt_headers(&self, hash: &H256) -> Option<BlockReceiptHeaders> {
+		self.extras_db.read(hash)
+	}
+
+	/// Get a single log entry.
+	pub fn log_entry(&self, address: &LogAddress) -> Option<LogEntry> {
+		self.extras_db.read(address)
+	}
+
+	// read receipts of block with given hash together with their logs, bypassing the cache.
+	fn read_block_receipts(&self, hash: &H256) -> Option<BlockReceipts> {
+		let headers = match self.receipt_headers(hash) {
+			Some(headers) => headers,
+			None => return None,
+		};
+
+		let receipts = headers.headers.into_iter().enumerate().map(|(transaction_index, header)| {
+			let logs = (0..header.log_count).map(|log_index| self.log_entry(&LogAddress {
+				block_hash: hash.clone(),
+				transaction_index: transaction_index,
+				log_index: log_index,
+			})).collect::<Option<Vec<_>>>();
+			logs.map(|logs| header.into_receipt(logs))
+		}).collect::<Option<Vec<_>>>();
+
+		receipts.map(BlockReceipts::new)
+	}
+
 	/// Get current cache size.
 	pub fn cache_size(&self) -> CacheSize {
 		CacheSize {
@@ -1228,6 +1585,12 @@ mod tests {
 		assert_eq!(blocks_b1, vec![1]);
 		assert_eq!(blocks_b2, vec![2]);
 		assert_eq!(blocks_ba, vec![3]);
+
+		// any of several blooms
+		assert_eq!(bc.blocks_with_blooms(&[bloom_ba.clone(), bloom_b1.clone()], 0, 5), vec![1, 3]);
+		assert_eq!(bc.blocks_with_blooms(&[bloom_b1.clone(), bloom_b2.clone(), bloom_ba.clone()], 0, 5), vec![1, 2, 3]);
+		assert_eq!(bc.blocks_with_blooms(&[bloom_b1.clone(), bloom_b2.clone()], 2, 5), vec![2]);
+		assert_eq!(bc.blocks_with_blooms(&[], 0, 5), vec![]);
 	}
 
 	#[test]
@@ -1282,4 +1645,130 @@ mod tests {
 		assert_eq!(bc.rewind(), Some(genesis_hash.clone()));
 		assert_eq!(bc.rewind(), None);
 	}
+
+	#[test]
+	fn indexes_author_stats() {
+		use blockchain::extras::{AuthorStatsPosition, AuthorStatsBucket, AUTHOR_STATS_BUCKET_SIZE};
+		use db::Readable;
+
+		let mut canon_chain = ChainGenerator::default();
+		let mut finalizer = BlockFinalizer::default();
+		let genesis = canon_chain.generate(&mut finalizer).unwrap();
+
+		let temp = RandomTempPath::new();
+		let bc = BlockChain::new(Config::default(), &genesis, temp.as_path());
+		let count = AUTHOR_STATS_BUCKET_SIZE * 2 + 10;
+		for _ in 0..count {
+			bc.insert_block(&canon_chain.generate(&mut finalizer).unwrap(), vec![]);
+		}
+
+		assert!(bc.extras_db.read::<AuthorStatsBucket, _>(&AuthorStatsPosition(1)).is_some());
+		assert!(bc.extras_db.read::<AuthorStatsBucket, _>(&AuthorStatsPosition(2)).is_none());
+
+		let stats = bc.author_stats(5, count).unwrap();
+		assert_eq!(stats.len(), 1);
+		assert_eq!(stats[&Address::new()].blocks, count - 4);
+		assert_eq!(bc.author_stats(count - 1, count + 1), None);
+	}
+
+	#[test]
+	fn backfills_author_stats() {
+		use blockchain::extras::{AuthorStatsPosition, AuthorStatsBucket, AUTHOR_STATS_BUCKET_SIZE};
+		use db::{Readable, Key};
+		use util::rlp::encode;
+		use util::kvdb::DBTransaction;
+		use super::INDEX_BACKFILL_KEY;
+
+		let mut canon_chain = ChainGenerator::default();
+		let mut finalizer = BlockFinalizer::default();
+		let genesis = canon_chain.generate(&mut finalizer).unwrap();
+
+		let temp = RandomTempPath::new();
+		let bc = BlockChain::new(Config::default(), &genesis, temp.as_path());
+		assert!(!bc.backfill_indexes());
+
+		let count = AUTHOR_STATS_BUCKET_SIZE * 2 + 10;
+		for _ in 0..count {
+			bc.insert_block(&canon_chain.generate(&mut finalizer).unwrap(), vec![]);
+		}
+		let first = bc.extras_db.read::<AuthorStatsBucket, _>(&AuthorStatsPosition(0)).unwrap();
+
+		// pretend the blocks were imported before the index existed.
+		let batch = DBTransaction::new();
+		batch.delete(&AuthorStatsPosition(0).key()).unwrap();
+		batch.delete(&AuthorStatsPosition(1).key()).unwrap();
+		batch.put(INDEX_BACKFILL_KEY, &encode(&(count + 1))).unwrap();
+		bc.extras_db.write(batch).unwrap();
+
+		assert!(bc.backfill_indexes());
+		assert!(bc.extras_db.read::<AuthorStatsBucket, _>(&AuthorStatsPosition(2)).is_none());
+		assert!(bc.backfill_indexes());
+		assert!(bc.extras_db.read::<AuthorStatsBucket, _>(&AuthorStatsPosition(1)).is_some());
+		assert!(!bc.backfill_indexes());
+		assert_eq!(bc.extras_db.read::<AuthorStatsBucket, _>(&AuthorStatsPosition(0)), Some(first));
+		assert!(!bc.backfill_indexes());
+	}
+
+	#[test]
+	fn reads_ancestry_with_receipts() {
+		let mut canon_chain = ChainGenerator::default();
+		let mut finalizer = BlockFinalizer::default();
+		let genesis = canon_chain.generate(&mut finalizer).unwrap();
+
+		let temp = RandomTempPath::new();
+		let bc = BlockChain::new(Config::default(), &genesis, temp.as_path());
+		for _ in 0..5 {
+			bc.insert_block(&canon_chain.generate(&mut finalizer).unwrap(), vec![]);
+		}
+
+		let numbers = |pairs: Vec<(Vec<u8>, Vec<u8>)>| pairs.iter()
+			.map(|&(ref block, _)| BlockView::new(block).header_view().number())
+			.collect::<Vec<_>>();
+		assert_eq!(numbers(bc.ancestry_with_receipts(bc.best_block_hash(), 3)), vec![5, 4, 3]);
+		// genesis has no receipts stored.
+		assert_eq!(numbers(bc.ancestry_with_receipts(bc.best_block_hash(), 100)), vec![5, 4, 3, 2, 1]);
+	}
+
+	#[test]
+	fn stores_logs_separately() {
+		use util::U256;
+		use log_entry::LogEntry;
+		use receipt::Receipt;
+		use blockchain::extras::LogAddress;
+
+		let mut canon_chain = ChainGenerator::default();
+		let mut finalizer = BlockFinalizer::default();
+		let genesis = canon_chain.generate(&mut finalizer).unwrap();
+		let b1 = canon_chain.generate(&mut finalizer).unwrap();
+		let b1_hash = BlockView::new(&b1).header_view().sha3();
+
+		let log = |n: u64| LogEntry { address: Address::from(n), topics: vec![H256::from(n)], data: vec![n as u8] };
+		let receipts = vec![
+			Receipt::new(H256::from(1), U256::from(21000), vec![log(1), log(2)]),
+			Receipt::new(H256::from(2), U256::from(42000), vec![]),
+			Receipt::new(H256::from(3), U256::from(63000), vec![log(3)]),
+		];
+
+		let temp = RandomTempPath::new();
+		let bc = BlockChain::new(Config::default(), &genesis, temp.as_path());
+		bc.insert_block(&b1, receipts.clone());
+
+		let headers = bc.receipt_headers(&b1_hash).unwrap().headers;
+		assert_eq!(headers.iter().map(|h| h.log_count).collect::<Vec<_>>(), vec![2, 0, 1]);
+		assert_eq!(headers[0].log_bloom, receipts[0].log_bloom);
+
+		let address = |transaction_index, log_index| LogAddress {
+			block_hash: b1_hash.clone(),
+			transaction_index: transaction_index,
+			log_index: log_index,
+		};
+		assert_eq!(bc.log_entry(&address(0, 1)), Some(log(2)));
+		assert_eq!(bc.log_entry(&address(2, 0)), Some(log(3)));
+		assert_eq!(bc.log_entry(&address(1, 0)), None);
+
+		let stored = bc.block_receipts(&b1_hash).unwrap().receipts;
+		assert_eq!(stored.len(), 3);
+		assert_eq!(stored[0].logs, vec![log(1), log(2)]);
+		assert_eq!(stored[2].gas_used, U256::from(63000));
+	}
 }
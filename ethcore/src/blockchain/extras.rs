@@ -19,6 +19,8 @@
 use bloomchain;
 use util::*;
 use header::BlockNumber;
+use basic_types::LogBloom;
+use log_entry::LogEntry;
 use receipt::Receipt;
 use db::Key;
 use blooms::{GroupPosition, BloomGroup};
@@ -36,6 +38,12 @@ pub enum ExtrasIndex {
 	BlocksBlooms = 3,
 	/// Block receipts index
 	BlockReceipts = 4,
+	/// Block author statistics index
+	AuthorStats = 5,
+	/// Canon transaction by sender and nonce index
+	SenderNonce = 6,
+	/// Log entry index
+	LogEntry = 7,
 }
 
 fn with_index(hash: &H256, i: ExtrasIndex) -> H264 {
@@ -125,7 +133,7 @@ impl Key<TransactionAddress> for H256 {
 	}
 }
 
-impl Key<BlockReceipts> for H256 {
+impl Key<BlockReceiptHeaders> for H256 {
 	type Target = H264;
 
 	fn key(&self) -> H264 {
@@ -133,6 +141,123 @@ impl Key<BlockReceipts> for H256 {
 	}
 }
 
+pub struct LogAddressKey([u8; 41]);
+
+impl Deref for LogAddressKey {
+	type Target = [u8];
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+/// Represents address of certain log: the block, the transaction within the block
+/// and the log within the transaction's receipt.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct LogAddress {
+	/// Block hash
+	pub block_hash: H256,
+	/// Transaction index within the block
+	pub transaction_index: usize,
+	/// Log index within the transaction
+	pub log_index: usize,
+}
+
+impl Key<LogEntry> for LogAddress {
+	type Target = LogAddressKey;
+
+	fn key(&self) -> Self::Target {
+		let mut result = [0u8; 41];
+		result[0] = ExtrasIndex::LogEntry as u8;
+		result[1..33].clone_from_slice(&self.block_hash);
+		for i in 0..4 {
+			result[33 + i] = (self.transaction_index >> (8 * (3 - i))) as u8;
+			result[37 + i] = (self.log_index >> (8 * (3 - i))) as u8;
+		}
+		LogAddressKey(result)
+	}
+}
+
+pub struct SenderNonceKey([u8; 53]);
+
+impl Deref for SenderNonceKey {
+	type Target = [u8];
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+/// Sender and nonce of a transaction, which identify it uniquely within a chain.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct SenderNonce {
+	/// Transaction sender.
+	pub sender: Address,
+	/// Transaction nonce.
+	pub nonce: U256,
+}
+
+impl Key<TransactionAddress> for SenderNonce {
+	type Target = SenderNonceKey;
+
+	fn key(&self) -> Self::Target {
+		let mut result = [0u8; 53];
+		result[0] = ExtrasIndex::SenderNonce as u8;
+		result[1..21].clone_from_slice(&self.sender);
+		self.nonce.to_big_endian(&mut result[21..]);
+		SenderNonceKey(result)
+	}
+}
+
+pub struct AuthorStatsKey([u8; 5]);
+
+impl Deref for AuthorStatsKey {
+	type Target = [u8];
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+/// Number of consecutive blocks whose authors are summarised in a single index entry.
+pub const AUTHOR_STATS_BUCKET_SIZE: BlockNumber = 256;
+
+/// Position of an author statistics entry: the number of its first block
+/// divided by `AUTHOR_STATS_BUCKET_SIZE`.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct AuthorStatsPosition(pub u64);
+
+impl AuthorStatsPosition {
+	/// Position of the entry containing block with given number.
+	pub fn of_block(number: BlockNumber) -> Self {
+		AuthorStatsPosition(number / AUTHOR_STATS_BUCKET_SIZE)
+	}
+
+	/// Number of the first block of the entry.
+	pub fn first_block(&self) -> BlockNumber {
+		self.0 * AUTHOR_STATS_BUCKET_SIZE
+	}
+
+	/// Number of the last block of the entry.
+	pub fn last_block(&self) -> BlockNumber {
+		self.first_block() + AUTHOR_STATS_BUCKET_SIZE - 1
+	}
+}
+
+impl Key<AuthorStatsBucket> for AuthorStatsPosition {
+	type Target = AuthorStatsKey;
+
+	fn key(&self) -> Self::Target {
+		let mut result = [0u8; 5];
+		result[0] = ExtrasIndex::AuthorStats as u8;
+		result[1] = (self.0 >> 24) as u8;
+		result[2] = (self.0 >> 16) as u8;
+		result[3] = (self.0 >> 8) as u8;
+		result[4] = self.0 as u8;
+		AuthorStatsKey(result)
+	}
+}
+
 /// Familial details concerning a block
 #[derive(Debug, Clone)]
 pub struct BlockDetails {
@@ -241,3 +366,147 @@ impl HeapSizeOf for BlockReceipts {
 		self.receipts.heap_size_of_children()
 	}
 }
+
+/// Receipt as stored in the database. Its logs are stored separately, each under its `LogAddress`,
+/// so that single logs can be read without decoding the whole receipt.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceiptHeader {
+	/// The state root after executing the transaction.
+	pub state_root: H256,
+	/// The total gas used in the block following execution of the transaction.
+	pub gas_used: U256,
+	/// The OR-wide combination of all logs' blooms for this transaction.
+	pub log_bloom: LogBloom,
+	/// Number of logs stemming from this transaction.
+	pub log_count: usize,
+}
+
+impl ReceiptHeader {
+	/// Rebuild the full receipt given its logs.
+	pub fn into_receipt(self, logs: Vec<LogEntry>) -> Receipt {
+		Receipt {
+			state_root: self.state_root,
+			gas_used: self.gas_used,
+			log_bloom: self.log_bloom,
+			logs: logs,
+		}
+	}
+}
+
+impl<'a> From<&'a Receipt> for ReceiptHeader {
+	fn from(receipt: &'a Receipt) -> Self {
+		ReceiptHeader {
+			state_root: receipt.state_root.clone(),
+			gas_used: receipt.gas_used.clone(),
+			log_bloom: receipt.log_bloom.clone(),
+			log_count: receipt.logs.len(),
+		}
+	}
+}
+
+impl Decodable for ReceiptHeader {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		let header = ReceiptHeader {
+			state_root: try!(d.val_at(0)),
+			gas_used: try!(d.val_at(1)),
+			log_bloom: try!(d.val_at(2)),
+			log_count: try!(d.val_at(3)),
+		};
+		Ok(header)
+	}
+}
+
+impl Encodable for ReceiptHeader {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(4);
+		s.append(&self.state_root);
+		s.append(&self.gas_used);
+		s.append(&self.log_bloom);
+		s.append(&self.log_count);
+	}
+}
+
+/// Contains headers of all block receipts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockReceiptHeaders {
+	pub headers: Vec<ReceiptHeader>,
+}
+
+impl<'a> From<&'a BlockReceipts> for BlockReceiptHeaders {
+	fn from(receipts: &'a BlockReceipts) -> Self {
+		BlockReceiptHeaders {
+			headers: receipts.receipts.iter().map(ReceiptHeader::from).collect(),
+		}
+	}
+}
+
+impl Decodable for BlockReceiptHeaders {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		Ok(BlockReceiptHeaders {
+			headers: try!(Decodable::decode(decoder))
+		})
+	}
+}
+
+impl Encodable for BlockReceiptHeaders {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.append(&self.headers);
+	}
+}
+
+/// Summary of the canon blocks authored by a single address.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AuthorStats {
+	/// Number of blocks authored.
+	pub blocks: u64,
+	/// Number of uncles included in those blocks.
+	pub uncles: u64,
+	/// Transaction fees paid in those blocks.
+	pub fees: U256,
+}
+
+impl AuthorStats {
+	/// Add other statistics to these.
+	pub fn accrue(&mut self, other: &AuthorStats) {
+		self.blocks += other.blocks;
+		self.uncles += other.uncles;
+		self.fees = self.fees + other.fees;
+	}
+}
+
+/// Statistics of the authors of `AUTHOR_STATS_BUCKET_SIZE` consecutive canon blocks.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct AuthorStatsBucket {
+	pub authors: BTreeMap<Address, AuthorStats>,
+}
+
+impl Decodable for AuthorStatsBucket {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		let mut authors = BTreeMap::new();
+		for entry in d.iter() {
+			authors.insert(try!(entry.val_at(0)), AuthorStats {
+				blocks: try!(entry.val_at(1)),
+				uncles: try!(entry.val_at(2)),
+				fees: try!(entry.val_at(3)),
+			});
+		}
+		Ok(AuthorStatsBucket {
+			authors: authors,
+		})
+	}
+}
+
+impl Encodable for AuthorStatsBucket {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(self.authors.len());
+		for (author, stats) in &self.authors {
+			s.begin_list(4);
+			s.append(author);
+			s.append(&stats.blocks);
+			s.append(&stats.uncles);
+			s.append(&stats.fees);
+		}
+	}
+}
@@ -3,7 +3,7 @@ use util::numbers::H256;
 use header::BlockNumber;
 use blockchain::block_info::BlockInfo;
 use blooms::BloomGroup;
-use super::extras::{BlockDetails, BlockReceipts, TransactionAddress, LogGroupPosition};
+use super::extras::{BlockDetails, BlockReceipts, TransactionAddress, LogGroupPosition, AuthorStatsPosition, AuthorStatsBucket, SenderNonce};
 
 /// Block extras update info.
 pub struct ExtrasUpdate {
@@ -19,4 +19,8 @@ pub struct ExtrasUpdate {
 	pub transactions_addresses: HashMap<H256, TransactionAddress>,
 	/// Modified blocks blooms.
 	pub blocks_blooms: HashMap<LogGroupPosition, BloomGroup>,
+	/// Modified canon transactions by sender and nonce.
+	pub sender_nonces: HashMap<SenderNonce, TransactionAddress>,
+	/// Modified author statistics.
+	pub author_stats: HashMap<AuthorStatsPosition, AuthorStatsBucket>,
 }
@@ -19,6 +19,8 @@ use std::ops::Deref;
 use std::sync::{Arc, Weak};
 use std::path::{Path, PathBuf};
 use std::fmt;
+use std::fs;
+use std::io::{Read, Write};
 use std::sync::atomic::{AtomicUsize, AtomicBool, Ordering as AtomicOrdering};
 use std::time::{Instant};
 use time::precise_time_ns;
@@ -32,11 +34,12 @@ use util::panics::*;
 use util::io::*;
 use util::sha3::*;
 use util::kvdb::*;
+use util::trie::{RangeProof, TrieError};
 
 // other
 use views::BlockView;
-use error::{ImportError, ExecutionError, BlockError, ImportResult};
-use header::BlockNumber;
+use error::{ImportError, ExecutionError, CallError, ReplayError, BlockError, ImportResult, Error as EthcoreError};
+use header::{BlockNumber, Header};
 use state::State;
 use spec::Spec;
 use basic_types::Seal;
@@ -48,21 +51,30 @@ use verification;
 use verification::{PreverifiedBlock, Verifier};
 use block::*;
 use transaction::{LocalizedTransaction, SignedTransaction, Action};
-use blockchain::extras::TransactionAddress;
+use blockchain::extras::{LogAddress, TransactionAddress};
 use types::filter::Filter;
 use log_entry::LocalizedLogEntry;
 use block_queue::{BlockQueue, BlockQueueInfo};
 use blockchain::{BlockChain, BlockProvider, TreeRoute, ImportRoute};
 use client::{BlockID, TransactionID, UncleID, TraceId, ClientConfig,
-	DatabaseCompactionProfile, BlockChainClient, MiningBlockChainClient,
+	DatabaseCompactionProfile, SnapshotConfig, BlockChainClient, MiningBlockChainClient,
 	TraceFilter, CallAnalytics, BlockImportError, Mode, ChainNotify};
 use client::Error as ClientError;
+use client::forensics;
+use client::state_map::StateMap;
+use types::database_stats::{DatabaseColumn, DatabaseStats};
+use types::storage_proof::StorageRangeProof;
+use types::miner_stats::MinerStats;
+use types::account_summary::AccountSummary;
+use types::snapshot_status::SnapshotStatus;
+use types::state_override::StateOverride;
+use snapshot;
 use env_info::EnvInfo;
 use executive::{Executive, Executed, TransactOptions, contract_address};
 use receipt::LocalizedReceipt;
 use trace::{TraceDB, ImportRequest as TraceImportRequest, LocalizedTrace, Database as TraceDatabase};
 use trace;
-use evm::Factory as EvmFactory;
+use evm::{Factory as EvmFactory, Profiler};
 use miner::{Miner, MinerService};
 use util::TrieFactory;
 
@@ -73,6 +85,27 @@ pub use blockchain::CacheSize as BlockChainCacheSize;
 
 const MAX_TX_QUEUE_SIZE: usize = 4096;
 const MAX_QUEUE_SIZE_TO_SLEEP_ON: usize = 2;
+// number of state nodes checked for presence on each tick while healing.
+const HEALING_SCAN_NODES: usize = 4096;
+// number of blocks whose traces are re-indexed between progress reports.
+const REINDEX_BATCH: u64 = 1000;
+// number of buckets of old blocks whose sender and author statistics indexes are backfilled on each tick.
+const BACKFILL_BUCKETS: usize = 4;
+// number of batches of old blocks whose traces are pruned on each tick.
+const TRACE_PRUNE_BATCHES: usize = 4;
+
+// finds the canonical blocks whose state is in the database.
+fn probe_state_map(chain: &BlockChain, state_db: &JournalDB) -> StateMap {
+	let history = match state_db.is_pruned() {
+		true => Some(HISTORY),
+		false => None,
+	};
+	StateMap::probe(chain.best_block_number(), history, |number| {
+		chain.block_hash(number)
+			.and_then(|hash| chain.block_header(&hash))
+			.map_or(false, |header| state_db.contains(header.state_root()))
+	})
+}
 
 impl fmt::Display for BlockChainInfo {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -102,6 +135,17 @@ impl ClientReport {
 	}
 }
 
+/// Size on disk of the client's databases, in bytes.
+#[derive(Default, Clone, Debug, Eq, PartialEq)]
+pub struct DiskUsage {
+	/// State database.
+	pub state: u64,
+	/// Blocks and extras databases.
+	pub blocks: u64,
+	/// Trace database.
+	pub traces: u64,
+}
+
 struct SleepState {
 	last_activity: Option<Instant>,
 	last_autosleep: Option<Instant>,
@@ -138,6 +182,16 @@ pub struct Client {
 	notify: RwLock<Vec<Weak<ChainNotify>>>,
 	queue_transactions: AtomicUsize,
 	last_hashes: RwLock<VecDeque<H256>>,
+	snapshot_config: SnapshotConfig,
+	db_path: PathBuf,
+	snapshot_root: PathBuf,
+	last_snapshot: AtomicUsize,
+	snapshot_progress: RwLock<Option<(BlockNumber, Arc<snapshot::Progress>)>>,
+	// healer of the state of the block with given number.
+	state_healer: Mutex<Option<(BlockNumber, snapshot::StateHealer)>>,
+	restoring_snapshot: AtomicBool,
+	state_map: RwLock<StateMap>,
+	state_replay_budget: BlockNumber,
 }
 
 const HISTORY: u64 = 1200;
@@ -158,6 +212,19 @@ pub fn get_db_path(path: &Path, pruning: journaldb::Algorithm, genesis_hash: H25
 	dir
 }
 
+// total size of the files in a directory and its subdirectories. Unreadable entries are skipped.
+fn dir_size(path: &Path) -> u64 {
+	let entries = match fs::read_dir(path) {
+		Ok(entries) => entries,
+		Err(_) => return 0,
+	};
+	entries.filter_map(|entry| entry.ok()).fold(0, |size, entry| match entry.metadata() {
+		Ok(ref metadata) if metadata.is_dir() => size + dir_size(&entry.path()),
+		Ok(ref metadata) => size + metadata.len(),
+		Err(_) => size,
+	})
+}
+
 /// Append a path element to the given path and return the string.
 pub fn append_path(path: &Path, item: &str) -> String {
 	let mut p = path.to_path_buf();
@@ -165,11 +232,16 @@ pub fn append_path(path: &Path, item: &str) -> String {
 	p.to_str().unwrap().to_owned()
 }
 
+fn read_file(path: &Path) -> Option<Bytes> {
+	let mut bytes = Vec::new();
+	fs::File::open(path).and_then(|mut file| file.read_to_end(&mut bytes)).ok().map(|_| bytes)
+}
+
 impl Client {
 	///  Create a new client with given spec and DB path and custom verifier.
 	pub fn new(
 		config: ClientConfig,
-		spec: Spec,
+		spec: &Spec,
 		path: &Path,
 		miner: Arc<Miner>,
 		message_channel: IoChannel<ClientIoMessage>,
@@ -202,19 +274,29 @@ impl Client {
 			warn!("State root not found for block #{} ({})", chain.best_block_number(), chain.best_block_hash().hex());
 		}
 
+		let state_map = probe_state_map(&chain, &*state_db);
+
 		/* TODO: enable this once the best block issue is resolved
 		while !chain.block_header(&chain.best_block_hash()).map_or(true, |h| state_db.contains(h.state_root())) {
 			warn!("State root not found for block #{} ({}), recovering...", chain.best_block_number(), chain.best_block_hash().hex());
 			chain.rewind();
 		}*/
 
-		let engine = Arc::new(spec.engine);
+		let engine = spec.engine.clone();
 
 		let block_queue = BlockQueue::new(config.queue, engine.clone(), message_channel.clone());
 		let panic_handler = PanicHandler::new_in_arc();
 		panic_handler.forward_from(&block_queue);
 
+		let snapshot_root = path.join("snapshots");
+		let last_snapshot = snapshot::periodic::list(&snapshot_root).last().cloned().unwrap_or(0);
+
 		let awake = match config.mode { Mode::Dark(..) => false, _ => true };
+		let vm_factory = match config.evm_profiling {
+			true => EvmFactory::new(config.vm_type).with_profiler(Arc::new(Profiler::new())),
+			false => EvmFactory::new(config.vm_type),
+		};
+
 		let client = Client {
 			sleep_state: Mutex::new(SleepState::new(awake)),
 			liveness: AtomicBool::new(awake),
@@ -228,18 +310,28 @@ impl Client {
 			import_lock: Mutex::new(()),
 			panic_handler: panic_handler,
 			verifier: verification::new(config.verifier_type),
-			vm_factory: Arc::new(EvmFactory::new(config.vm_type)),
+			vm_factory: Arc::new(vm_factory),
 			trie_factory: TrieFactory::new(config.trie_spec),
 			miner: miner,
 			io_channel: message_channel,
 			notify: RwLock::new(Vec::new()),
 			queue_transactions: AtomicUsize::new(0),
 			last_hashes: RwLock::new(VecDeque::new()),
+			snapshot_config: config.snapshot,
+			db_path: path.clone(),
+			snapshot_root: snapshot_root,
+			last_snapshot: AtomicUsize::new(last_snapshot as usize),
+			snapshot_progress: RwLock::new(None),
+			state_healer: Mutex::new(None),
+			restoring_snapshot: AtomicBool::new(false),
+			state_map: RwLock::new(state_map),
+			state_replay_budget: config.state_replay_budget,
 		};
 		Ok(Arc::new(client))
 	}
 
-	/// Adds an actor to be notified on certain events
+	/// Adds an actor to be notified on certain events.
+	/// Only a weak reference is kept, so the caller has to keep the actor alive for as long as it should be notified.
 	pub fn add_notify(&self, target: Arc<ChainNotify>) {
 		self.notify.write().push(Arc::downgrade(&target));
 	}
@@ -260,6 +352,95 @@ impl Client {
 		}
 	}
 
+	/// Whether transactions of imported blocks are traced.
+	pub fn tracing_enabled(&self) -> bool {
+		self.tracedb.tracing_enabled()
+	}
+
+	/// Re-creates the traces of canon blocks imported while tracing was off, by enacting them
+	/// again on the state of their parents, and resumes where an interrupted run stopped.
+	/// `progress` is called with the number of the last re-indexed block and the best block number.
+	/// Requires the state of all blocks, i.e. a database synced with archive pruning.
+	pub fn reindex_traces<F>(&self, mut progress: F) -> Result<(), String> where F: FnMut(BlockNumber, BlockNumber) {
+		let from = match self.tracedb.reindex_from() {
+			Some(from) => from,
+			None => return Ok(()),
+		};
+		let engine = self.engine.deref().deref();
+		let best = self.chain.best_block_number();
+
+		for number in from..best + 1 {
+			let hash = try!(self.chain.block_hash(number).ok_or_else(|| format!("Block #{} not found.", number)));
+			let bytes = try!(self.chain.block(&hash).ok_or_else(|| format!("Block #{} not found.", number)));
+			let parent_hash = BlockView::new(&bytes).header_view().parent_hash();
+			let parent = try!(self.chain.block_header(&parent_hash).ok_or_else(|| format!("Parent of block #{} not found.", number)));
+			if self.state_at(BlockID::Hash(parent_hash.clone())).is_none() {
+				return Err(format!("State of block #{} is not available. Re-indexing traces requires a database synced with --pruning archive.", number - 1));
+			}
+
+			let last_hashes = self.build_last_hashes(parent_hash);
+			let db = self.state_db.lock().boxed_clone();
+			let block = try!(enact_bytes(&bytes, engine, true, db, &parent, last_hashes, &self.vm_factory, self.trie_factory.clone())
+				.map_err(|e| format!("Cannot enact block #{}: {:?}", number, e)));
+
+			self.tracedb.import(TraceImportRequest {
+				traces: From::from(block.traces().clone().unwrap_or_else(Vec::new)),
+				block_hash: hash.clone(),
+				block_number: number,
+				enacted: vec![hash],
+				retracted: vec![],
+			});
+
+			if number % REINDEX_BATCH == 0 || number == best {
+				self.tracedb.set_reindex_from(Some(number + 1));
+				progress(number, best);
+			}
+		}
+
+		self.tracedb.set_reindex_from(None);
+		Ok(())
+	}
+
+	// re-execute the first `count` transactions of a block on its parent state, returning
+	// the results of those from `first` on, which are executed with given analytics.
+	fn replay_in_block(&self, block: &[u8], first: usize, count: usize, analytics: CallAnalytics) -> Result<Vec<Executed>, ReplayError> {
+		let view = BlockView::new(block);
+		let header = view.header_view();
+		let mut state = try!(self.state_at(BlockID::Hash(header.parent_hash())).ok_or(ReplayError::StatePruned));
+		let mut env_info = EnvInfo {
+			number: header.number(),
+			author: header.author(),
+			timestamp: header.timestamp(),
+			difficulty: header.difficulty(),
+			last_hashes: self.build_last_hashes(header.parent_hash()),
+			gas_used: U256::zero(),
+			gas_limit: header.gas_limit(),
+		};
+
+		let mut replayed = Vec::with_capacity(count - first);
+		for (i, t) in view.transactions().into_iter().take(count).enumerate() {
+			let analytics = match i < first {
+				// only brings the state up to the point the first transaction was executed at.
+				true => CallAnalytics::default(),
+				false => analytics,
+			};
+			let original_state = match analytics.state_diffing {
+				true => Some(state.clone()),
+				false => None,
+			};
+			let options = TransactOptions { tracing: analytics.transaction_tracing, vm_tracing: analytics.vm_tracing, check_nonce: true };
+			let mut executed = try!(Executive::new(&mut state, &env_info, self.engine.deref().deref(), &self.vm_factory).transact(&t, options));
+			env_info.gas_used = executed.cumulative_gas_used;
+			if let Some(original) = original_state {
+				executed.state_diff = Some(state.diff_from(original));
+			}
+			if i >= first {
+				replayed.push(executed);
+			}
+		}
+		Ok(replayed)
+	}
+
 	fn build_last_hashes(&self, parent_hash: H256) -> LastHashes {
 		{
 			let hashes = self.last_hashes.read();
@@ -315,7 +496,7 @@ impl Client {
 		let last_hashes = self.build_last_hashes(header.parent_hash.clone());
 		let db = self.state_db.lock().boxed_clone();
 
-		let enact_result = enact_verified(&block, engine, self.tracedb.tracing_enabled(), db, &parent, last_hashes, &self.vm_factory, self.trie_factory.clone());
+		let enact_result = enact_verified(&block, engine, self.tracedb.tracing_enabled(), db, &parent, last_hashes.clone(), &self.vm_factory, self.trie_factory.clone());
 		if let Err(e) = enact_result {
 			warn!(target: "client", "Block import failed for #{} ({})\nError: {:?}", header.number(), header.hash(), e);
 			return Err(());
@@ -325,12 +506,29 @@ impl Client {
 		let locked_block = enact_result.unwrap();
 		if let Err(e) = self.verifier.verify_block_final(&header, locked_block.block().header()) {
 			warn!(target: "client", "Stage 4 block verification failed for #{} ({})\nError: {:?}", header.number(), header.hash(), e);
+			self.report_bad_block(block, &parent, last_hashes, &locked_block, &e);
 			return Err(());
 		}
 
 		Ok(locked_block)
 	}
 
+	// write details of a block failing final verification, re-enacting it with tracing if needed.
+	fn report_bad_block(&self, block: &PreverifiedBlock, parent: &Header, last_hashes: LastHashes, enacted: &LockedBlock, error: &EthcoreError) {
+		let traced = match enacted.traces().is_some() {
+			true => None,
+			false => {
+				let db = self.state_db.lock().boxed_clone();
+				enact_verified(block, self.engine.deref().deref(), true, db, parent, last_hashes, &self.vm_factory, self.trie_factory.clone()).ok()
+			}
+		};
+		let enacted = traced.as_ref().unwrap_or(enacted);
+		match forensics::write_report(&self.db_path.join("forensics"), &block.header, &block.bytes, parent, enacted, error) {
+			Ok(path) => warn!(target: "client", "Details of the failure written to {}. Please attach this file when reporting the issue.", path.display()),
+			Err(e) => warn!(target: "client", "Cannot write details of the failure: {}", e),
+		}
+	}
+
 	fn calculate_enacted_retracted(&self, import_results: &[ImportRoute]) -> (Vec<H256>, Vec<H256>) {
 		fn map_to_vec(map: Vec<(H256, bool)>) -> Vec<H256> {
 			map.into_iter().map(|(k, _v)| k).collect()
@@ -450,21 +648,36 @@ impl Client {
 		// Commit results
 		let receipts = block.receipts().to_owned();
 		let traces = From::from(block.traces().clone().unwrap_or_else(Vec::new));
+		// senders were recovered and cached during verification.
+		let senders = block.transactions().iter().map(|t| t.sender()).collect::<Result<Vec<_>, _>>().ok();
 
 		// CHECK! I *think* this is fine, even if the state_root is equal to another
 		// already-imported block of the same number.
 		// TODO: Prove it with a test.
-		block.drain().commit(number, hash, ancient).expect("State DB commit failed.");
+		let pruned_era = ancient.as_ref().map(|&(era, _)| era);
+		let mut state_db = block.drain();
+		let pruned = state_db.is_pruned();
+		state_db.commit(number, hash, ancient).expect("State DB commit failed.");
+		{
+			let mut state_map = self.state_map.write();
+			state_map.insert(number);
+			if let (true, Some(era)) = (pruned, pruned_era) {
+				state_map.prune_before(era + 1);
+			}
+		}
 
 		// And update the chain after commit to prevent race conditions
 		// (when something is in chain but you are not able to fetch details)
-		let route = self.chain.insert_block(block_data, receipts);
+		let route = match senders {
+			Some(ref senders) => self.chain.insert_verified_block(block_data, receipts, senders),
+			None => self.chain.insert_block(block_data, receipts),
+		};
 		self.tracedb.import(TraceImportRequest {
 			traces: traces,
 			block_hash: hash.clone(),
 			block_number: number,
 			enacted: route.enacted.clone(),
-			retracted: route.retracted.len()
+			retracted: route.retracted.clone(),
 		});
 		self.update_last_hashes(&parent, hash);
 		route
@@ -507,19 +720,65 @@ impl Client {
 		};
 
 		self.block_header(id).and_then(|header| {
-			let db = self.state_db.lock().boxed_clone();
-
-			// early exit for pruned blocks
-			if db.is_pruned() && self.chain.best_block_number() >= block_number + HISTORY {
-				return None;
+			if !self.state_map.read().contains(block_number) {
+				return self.replay_state(&header);
 			}
 
+			let db = self.state_db.lock().boxed_clone();
 			let root = HeaderView::new(&header).state_root();
 
 			State::from_existing(db, root, self.engine.account_start_nonce(), self.trie_factory.clone()).ok()
 		})
 	}
 
+	// rebuild a state that's not available by re-executing the blocks leading to it from the nearest
+	// ancestor with state available, provided there are at most `state_replay_budget` of them.
+	fn replay_state(&self, header: &[u8]) -> Option<State> {
+		let header = HeaderView::new(header);
+		let mut hashes = vec![header.hash()];
+		let mut parent = None;
+		{
+			let state_map = self.state_map.read();
+			let mut hash = header.parent_hash();
+			while hashes.len() as u64 <= self.state_replay_budget {
+				let ancestor = match self.chain.block_header(&hash) {
+					Some(ancestor) => ancestor,
+					None => return None,
+				};
+				if state_map.contains(ancestor.number()) {
+					parent = Some(ancestor);
+					break;
+				}
+				hash = ancestor.parent_hash().clone();
+				hashes.push(ancestor.hash());
+			}
+		}
+		let mut parent = match parent {
+			Some(parent) => parent,
+			None => return None,
+		};
+
+		debug!(target: "client", "Replaying {} blocks from #{} to rebuild a pruned state", hashes.len(), parent.number());
+		let mut db = self.state_db.lock().boxed_clone();
+		for hash in hashes.into_iter().rev() {
+			let block = match self.chain.block(&hash) {
+				Some(block) => block,
+				None => return None,
+			};
+			let last_hashes = self.build_last_hashes(parent.hash());
+			let enacted = match enact_bytes(&block, self.engine.deref().deref(), false, db, &parent, last_hashes, &self.vm_factory, self.trie_factory.clone()) {
+				Ok(enacted) => enacted,
+				Err(e) => {
+					warn!(target: "client", "Cannot replay block {} to rebuild a pruned state: {:?}", hash, e);
+					return None;
+				}
+			};
+			parent = BlockView::new(&block).header();
+			db = enacted.drain();
+		}
+		State::from_existing(db, parent.state_root().clone(), self.engine.account_start_nonce(), self.trie_factory.clone()).ok()
+	}
+
 	/// Get a copy of the best block's state.
 	pub fn state(&self) -> State {
 		State::from_existing(
@@ -530,11 +789,39 @@ impl Client {
 		.expect("State root of best block header always valid.")
 	}
 
+	/// Produce proofs of at most `limit` accounts of the state at given block, starting at hashed address `from`.
+	/// Each proof proves a single account against the state root of the block.
+	/// Returns `None` if the state is not available.
+	pub fn prove_accounts(&self, from: &[u8], limit: usize, id: BlockID) -> Option<Vec<RangeProof>> {
+		self.state_at(id).and_then(|s| match s.prove_accounts(from, limit) {
+			Ok(proofs) => Some(proofs),
+			Err(e) => {
+				warn!("Error proving accounts: {}", e);
+				if let TrieError::IncompleteDatabase(_) = e {
+					self.heal_state();
+				}
+				None
+			}
+		})
+	}
+
 	/// Get info on the cache.
 	pub fn blockchain_cache_info(&self) -> BlockChainCacheSize {
 		self.chain.cache_size()
 	}
 
+	fn with_database<F, T>(&self, column: DatabaseColumn, f: F) -> T where F: FnOnce(&Database) -> T {
+		match column {
+			DatabaseColumn::Blocks => f(self.chain.blocks_db()),
+			DatabaseColumn::Extras => f(self.chain.extras_db()),
+			DatabaseColumn::State => {
+				let db = self.state_db.lock().backing().clone();
+				f(&db)
+			},
+			DatabaseColumn::Traces => f(self.tracedb.db()),
+		}
+	}
+
 	/// Get the report.
 	pub fn report(&self) -> ClientReport {
 		let mut report = self.report.read().clone();
@@ -542,11 +829,23 @@ impl Client {
 		report
 	}
 
+	/// Get the size of the databases on disk. Walks the database directories, so it shouldn't be called too often.
+	pub fn disk_usage(&self) -> DiskUsage {
+		DiskUsage {
+			state: dir_size(&self.db_path.join("state")),
+			blocks: dir_size(&self.db_path.join("blocks")) + dir_size(&self.db_path.join("extras")),
+			traces: dir_size(&self.db_path.join("tracedb")),
+		}
+	}
+
 	/// Tick the client.
 	// TODO: manage by real events.
 	pub fn tick(&self) {
 		self.chain.collect_garbage();
 		self.block_queue.collect_garbage();
+		self.advance_state_healing();
+		self.backfill_indexes();
+		self.prune_traces();
 
 		match self.mode {
 			Mode::Dark(timeout) => {
@@ -580,6 +879,244 @@ impl Client {
 		}
 	}
 
+	/// Start checking the state of the best block for missing nodes, to be fetched from peers.
+	/// Does nothing if the state is already being healed.
+	pub fn heal_state(&self) {
+		let mut healer = self.state_healer.lock();
+		if healer.is_none() {
+			*healer = Some(self.best_state_healer());
+		}
+	}
+
+	fn best_state_healer(&self) -> (BlockNumber, snapshot::StateHealer) {
+		let header = self.best_block_header();
+		let header = HeaderView::new(&header);
+		info!("Checking state {} of block #{} for missing nodes", header.state_root(), header.number());
+		(header.number(), snapshot::StateHealer::new(header.state_root()))
+	}
+
+	/// Restore state and blocks described by the manifest from previously fed chunks.
+	/// Called from the IO service for restorations queued with `restore_snapshot`. Returns `true` on success.
+	pub fn restore_fed_snapshot(&self, manifest: &[u8]) -> bool {
+		let result = self.restore_snapshot_chunks(manifest);
+		self.restoring_snapshot.store(false, AtomicOrdering::SeqCst);
+		result
+	}
+
+	fn restore_snapshot_chunks(&self, manifest: &[u8]) -> bool {
+		let manifest = match snapshot::ManifestData::from_rlp(manifest) {
+			Ok(manifest) => manifest,
+			Err(e) => {
+				warn!("Invalid snapshot manifest: {:?}", e);
+				return false;
+			}
+		};
+
+		let _import_lock = self.import_lock.lock();
+		let path = snapshot::periodic::restoration_path(&self.snapshot_root);
+		info!("Restoring snapshot at block #{} ({})", manifest.block_number, manifest.block_hash.hex());
+		let state_db = self.state_db.lock().boxed_clone();
+		let result = snapshot::restore(&self.chain, state_db, &manifest, &path);
+		let _ = fs::remove_dir_all(&path);
+		match result {
+			Ok(()) => {
+				info!("Snapshot restored, best block #{}", self.chain.best_block_number());
+				self.reprobe_state_map();
+				*self.state_healer.lock() = None;
+				self.heal_state();
+				true
+			},
+			Err(e) => {
+				warn!("Error restoring snapshot: {}", e);
+				false
+			}
+		}
+	}
+
+	/// Get the progress of state healing, if the state is being healed.
+	pub fn state_healing_status(&self) -> Option<snapshot::HealingStatus> {
+		self.state_healer.lock().as_ref().map(|&(_, ref healer)| healer.status())
+	}
+
+	fn advance_state_healing(&self) {
+		let mut healer_guard = self.state_healer.lock();
+		// with pruning, nodes of an old state are removed and peers don't serve them anymore,
+		// so the state of a recent block is checked instead. Nodes healed so far are kept.
+		let stale = match *healer_guard {
+			Some((number, _)) => self.state_db.lock().is_pruned() && number + HISTORY < self.chain.best_block_number(),
+			None => false,
+		};
+		if stale {
+			*healer_guard = Some(self.best_state_healer());
+		}
+
+		let done = match *healer_guard {
+			Some((_, ref mut healer)) => {
+				let state_db = self.state_db.lock().boxed_clone();
+				healer.scan(state_db.as_hashdb(), HEALING_SCAN_NODES);
+
+				let status = healer.status();
+				if status.missing > 0 {
+					info!("Healing state: {} nodes checked, {} fetched, {} missing", status.checked, status.healed, status.missing);
+				}
+				if healer.is_complete() {
+					info!("State {} is complete: {} nodes checked, {} fetched", healer.root(), status.checked, status.healed);
+				}
+				healer.is_complete()
+			}
+			None => false,
+		};
+		if done {
+			*healer_guard = None;
+			self.reprobe_state_map();
+		}
+	}
+
+	// rebuilds the index of available states after the state database changed other than by import.
+	fn reprobe_state_map(&self) {
+		let state_map = probe_state_map(&self.chain, &**self.state_db.lock());
+		*self.state_map.write() = state_map;
+	}
+
+	fn backfill_indexes(&self) {
+		for _ in 0..BACKFILL_BUCKETS {
+			if !self.chain.backfill_indexes() {
+				return;
+			}
+		}
+		trace!(target: "client", "Backfilling sender and author statistics indexes");
+	}
+
+	fn prune_traces(&self) {
+		for _ in 0..TRACE_PRUNE_BATCHES {
+			// pruning rewrites trace blooms, so it must not interleave with block import.
+			let _import_lock = self.import_lock.lock();
+			if !self.tracedb.prune(self.chain.best_block_number()) {
+				return;
+			}
+		}
+	}
+
+	// logs of a block matching the filter. Only logs of receipts matching one of `blooms` are read.
+	fn logs_of_block(&self, hash: &H256, number: BlockNumber, filter: &Filter, blooms: &[H2048]) -> Vec<LocalizedLogEntry> {
+		let headers = match self.chain.receipt_headers(hash) {
+			Some(headers) => headers.headers,
+			None => return Vec::new(),
+		};
+
+		let mut logs = Vec::new();
+		let mut transaction_hashes = None;
+		let mut first_log_index = 0;
+		for (transaction_index, header) in headers.into_iter().enumerate() {
+			let log_index = first_log_index;
+			first_log_index += header.log_count;
+			if !blooms.iter().any(|bloom| header.log_bloom.contains(bloom)) {
+				continue;
+			}
+
+			for i in 0..header.log_count {
+				let address = LogAddress {
+					block_hash: hash.clone(),
+					transaction_index: transaction_index,
+					log_index: i,
+				};
+				let entry = match self.chain.log_entry(&address) {
+					Some(entry) => entry,
+					None => continue,
+				};
+				if !filter.matches(&entry) {
+					continue;
+				}
+
+				if transaction_hashes.is_none() {
+					transaction_hashes = Some(self.chain.block(hash).map_or_else(Vec::new, |b| BlockView::new(&b).transaction_hashes()));
+				}
+				let transaction_hash = transaction_hashes.as_ref().and_then(|hashes| hashes.get(transaction_index).cloned());
+
+				logs.push(LocalizedLogEntry {
+					entry: entry,
+					block_hash: hash.clone(),
+					block_number: number,
+					transaction_hash: transaction_hash.unwrap_or_else(H256::new),
+					transaction_index: transaction_index,
+					log_index: log_index + i,
+				});
+			}
+		}
+		logs
+	}
+
+	/// Returns the number of the latest checkpoint block if a periodic snapshot of it is due.
+	/// Checkpoints whose state has already been pruned are skipped.
+	pub fn snapshot_due(&self) -> Option<BlockNumber> {
+		let checkpoint = match self.snapshot_config.checkpoint(self.chain.best_block_number()) {
+			Some(checkpoint) if checkpoint > self.last_snapshot.load(AtomicOrdering::Relaxed) as BlockNumber => checkpoint,
+			_ => return None,
+		};
+		if !self.state_map.read().contains(checkpoint) {
+			warn!("State at block #{} has been pruned, skipping its snapshot. Take snapshots more often than the pruning history.", checkpoint);
+			self.last_snapshot.store(checkpoint as usize, AtomicOrdering::Relaxed);
+			return None;
+		}
+		Some(checkpoint)
+	}
+
+	/// Take a snapshot at the given canonical block and remove the ones no longer kept.
+	/// A snapshot which failed is due again on the next call to `snapshot_due`.
+	/// Must not be called concurrently.
+	pub fn take_periodic_snapshot(&self, number: BlockNumber) -> Result<PathBuf, EthcoreError> {
+		// an unfinished snapshot of the same block left by a crash is resumed.
+		let temp_path = snapshot::periodic::temp_path(&self.snapshot_root, number);
+		let path = snapshot::periodic::snapshot_path(&self.snapshot_root, number);
+
+		let writer = try!(snapshot::io::LooseWriter::new(temp_path.clone()));
+		match self.take_snapshot(BlockID::Number(number), writer) {
+			// a cancelled snapshot is kept to be resumed later.
+			Err(e @ EthcoreError::Snapshot(snapshot::SnapshotError::Cancelled)) => return Err(e),
+			Err(e) => {
+				let _ = ::std::fs::remove_dir_all(&temp_path);
+				return Err(e);
+			}
+			Ok(()) => {}
+		}
+		try!(::std::fs::rename(&temp_path, &path));
+		self.last_snapshot.store(number as usize, AtomicOrdering::Relaxed);
+		try!(snapshot::periodic::prune(&self.snapshot_root, self.snapshot_config.history));
+		Ok(path)
+	}
+
+	/// Take a snapshot at the given block, handing the chunks to `writer`.
+	/// The state at that block must still be in the database, so with pruning enabled
+	/// only recent blocks can be used. Chunks are produced with the configured chunk options.
+	/// Must not be called concurrently.
+	pub fn take_snapshot<W: snapshot::io::SnapshotWriter + Send>(&self, at: BlockID, writer: W) -> Result<(), EthcoreError> {
+		let hash = try!(Self::block_hash(&self.chain, at.clone()).ok_or(snapshot::SnapshotError::InvalidStartingBlock(at.clone())));
+		let number = try!(self.chain.block_number(&hash).ok_or(snapshot::SnapshotError::InvalidStartingBlock(at)));
+		let state_db = self.state_db.lock().boxed_clone();
+
+		let progress = Arc::new(snapshot::Progress::new());
+		*self.snapshot_progress.write() = Some((number, progress.clone()));
+		let result = snapshot::take_snapshot(&self.chain, hash, &*state_db, writer, &self.snapshot_config.chunks, &progress);
+		*self.snapshot_progress.write() = None;
+		result
+	}
+
+	/// Returns the block number and progress of the snapshot being taken, if any.
+	pub fn snapshot_progress(&self) -> Option<(BlockNumber, Arc<snapshot::Progress>)> {
+		self.snapshot_progress.read().clone()
+	}
+
+	/// Cancels the snapshot being taken. Returns `false` if there was none.
+	pub fn cancel_snapshot(&self) -> bool {
+		match *self.snapshot_progress.read() {
+			Some((_, ref progress)) => {
+				progress.cancel();
+				true
+			},
+			None => false,
+		}
+	}
+
 	/// Set up the cache behaviour.
 	pub fn configure_cache(&self, pref_cache_size: usize, max_cache_size: usize) {
 		self.chain.configure_cache(pref_cache_size, max_cache_size);
@@ -639,8 +1176,8 @@ impl Client {
 }
 
 impl BlockChainClient for Client {
-	fn call(&self, t: &SignedTransaction, analytics: CallAnalytics) -> Result<Executed, ExecutionError> {
-		let header = self.block_header(BlockID::Latest).unwrap();
+	fn call(&self, t: &SignedTransaction, block: BlockID, analytics: CallAnalytics, state_override: &StateOverride) -> Result<Executed, CallError> {
+		let header = try!(self.block_header(block.clone()).ok_or(CallError::UnknownBlock));
 		let view = HeaderView::new(&header);
 		let last_hashes = self.build_last_hashes(view.hash());
 		let env_info = EnvInfo {
@@ -653,7 +1190,12 @@ impl BlockChainClient for Client {
 			gas_limit: U256::max_value(),
 		};
 		// that's just a copy of the state.
-		let mut state = self.state();
+		let mut state = try!(self.state_at(block).ok_or(CallError::StatePruned));
+		state.apply_override(state_override);
+		let original_state = match analytics.state_diffing {
+			true => Some(state.clone()),
+			false => None,
+		};
 		let sender = try!(t.sender().map_err(|e| {
 			let message = format!("Transaction malformed: {:?}", e);
 			ExecutionError::TransactionMalformed(message)
@@ -665,15 +1207,29 @@ impl BlockChainClient for Client {
 			state.add_balance(&sender, &(needed_balance - balance));
 		}
 		let options = TransactOptions { tracing: analytics.transaction_tracing, vm_tracing: analytics.vm_tracing, check_nonce: false };
-		let mut ret = Executive::new(&mut state, &env_info, self.engine.deref().deref(), &self.vm_factory).transact(t, options);
+		let mut ret = try!(Executive::new(&mut state, &env_info, self.engine.deref().deref(), &self.vm_factory).transact(t, options));
 
 		// TODO gav move this into Executive.
-		if analytics.state_diffing {
-			if let Ok(ref mut x) = ret {
-				x.state_diff = Some(state.diff_from(self.state()));
-			}
+		if let Some(original) = original_state {
+			ret.state_diff = Some(state.diff_from(original));
 		}
-		ret
+		Ok(ret)
+	}
+
+	fn replay(&self, id: TransactionID, analytics: CallAnalytics) -> Result<Executed, ReplayError> {
+		let address = try!(self.transaction_address(id).ok_or(ReplayError::TransactionNotFound));
+		let block = try!(self.chain.block(&address.block_hash).ok_or(ReplayError::TransactionNotFound));
+		if address.index >= BlockView::new(&block).transactions_count() {
+			return Err(ReplayError::TransactionNotFound);
+		}
+		let mut executed = try!(self.replay_in_block(&block, address.index, address.index + 1, analytics));
+		Ok(executed.pop().expect("one transaction replayed with analytics; qed"))
+	}
+
+	fn replay_block(&self, id: BlockID, analytics: CallAnalytics) -> Result<Vec<Executed>, ReplayError> {
+		let block = try!(self.block(id).ok_or(ReplayError::BlockNotFound));
+		let count = BlockView::new(&block).transactions_count();
+		self.replay_in_block(&block, 0, count, analytics)
 	}
 
 	fn keep_alive(&self) {
@@ -747,10 +1303,39 @@ impl BlockChainClient for Client {
 		self.state_at(id).map(|s| s.storage_at(address, position))
 	}
 
+	fn storage_at_many(&self, address: &Address, positions: Vec<H256>, id: BlockID) -> Option<Vec<H256>> {
+		self.state_at(id).map(|s| positions.iter().map(|position| s.storage_at(address, position)).collect())
+	}
+
+	fn accounts(&self, addresses: Vec<Address>, id: BlockID) -> Option<Vec<AccountSummary>> {
+		self.state_at(id).map(|s| addresses.iter().map(|address| AccountSummary {
+			balance: s.balance(address),
+			nonce: s.nonce(address),
+			code: s.code(address).unwrap_or_else(Vec::new),
+		}).collect())
+	}
+
+	fn prove_storage_range(&self, address: &Address, from: &H256, limit: usize, id: BlockID) -> Option<StorageRangeProof> {
+		self.state_at(id).and_then(|s| match s.prove_storage_range(address, from, limit) {
+			Ok(proof) => proof,
+			Err(e) => {
+				warn!("Error proving storage of {}: {}", address, e);
+				if let TrieError::IncompleteDatabase(_) = e {
+					self.heal_state();
+				}
+				None
+			}
+		})
+	}
+
 	fn transaction(&self, id: TransactionID) -> Option<LocalizedTransaction> {
 		self.transaction_address(id).and_then(|address| self.chain.transaction(&address))
 	}
 
+	fn transaction_by_sender_and_nonce(&self, sender: &Address, nonce: &U256) -> Option<LocalizedTransaction> {
+		self.chain.transaction_address_by_sender_nonce(sender, nonce).and_then(|address| self.chain.transaction(&address))
+	}
+
 	fn uncle(&self, id: UncleID) -> Option<Bytes> {
 		let index = id.position;
 		self.block(id.block).and_then(|block| BlockView::new(&block).uncle_rlp_at(index))
@@ -851,6 +1436,10 @@ impl BlockChainClient for Client {
 		}
 	}
 
+	fn cache_size(&self) -> usize {
+		self.blockchain_cache_info().total()
+	}
+
 	fn blocks_with_bloom(&self, bloom: &H2048, from_block: BlockID, to_block: BlockID) -> Option<Vec<BlockNumber>> {
 		match (self.block_number(from_block), self.block_number(to_block)) {
 			(Some(from), Some(to)) => Some(self.chain.blocks_with_bloom(bloom, from, to)),
@@ -861,42 +1450,59 @@ impl BlockChainClient for Client {
 	fn logs(&self, filter: Filter) -> Vec<LocalizedLogEntry> {
 		// TODO: lock blockchain only once
 
-		let mut blocks = filter.bloom_possibilities().iter()
-			.filter_map(|bloom| self.blocks_with_bloom(bloom, filter.from_block.clone(), filter.to_block.clone()))
-			.flat_map(|m| m)
-			// remove duplicate elements
-			.collect::<HashSet<u64>>()
-			.into_iter()
-			.collect::<Vec<u64>>();
-
-		blocks.sort();
-
-		blocks.into_iter()
-			.filter_map(|number| self.chain.block_hash(number).map(|hash| (number, hash)))
-			.filter_map(|(number, hash)| self.chain.block_receipts(&hash).map(|r| (number, hash, r.receipts)))
-			.filter_map(|(number, hash, receipts)| self.chain.block(&hash).map(|ref b| (number, hash, receipts, BlockView::new(b).transaction_hashes())))
-			.flat_map(|(number, hash, receipts, hashes)| {
-				let mut log_index = 0;
-				receipts.into_iter()
-					.enumerate()
-					.flat_map(|(index, receipt)| {
-						log_index += receipt.logs.len();
-						receipt.logs.into_iter()
-							.enumerate()
-							.filter(|tuple| filter.matches(&tuple.1))
-							.map(|(i, log)| LocalizedLogEntry {
-								entry: log,
-								block_hash: hash.clone(),
-								block_number: number,
-								transaction_hash: hashes.get(index).cloned().unwrap_or_else(H256::new),
-								transaction_index: index,
-								log_index: log_index + i
-							})
-							.collect::<Vec<LocalizedLogEntry>>()
-					})
-					.collect::<Vec<LocalizedLogEntry>>()
-			})
-			.collect()
+		let blooms = filter.bloom_possibilities();
+		let blocks = match (self.block_number(filter.from_block.clone()), self.block_number(filter.to_block.clone())) {
+			(Some(from), Some(to)) => self.chain.blocks_with_blooms(&blooms, from, to),
+			_ => Vec::new(),
+		};
+
+		let mut logs = Vec::new();
+		for number in blocks {
+			if let Some(hash) = self.chain.block_hash(number) {
+				logs.extend(self.logs_of_block(&hash, number, &filter, &blooms));
+			}
+		}
+		logs
+	}
+
+	fn block_logs(&self, hash: &H256, filter: &Filter) -> Vec<LocalizedLogEntry> {
+		match self.chain.block_number(hash) {
+			Some(number) => self.logs_of_block(hash, number, filter, &filter.bloom_possibilities()),
+			None => Vec::new(),
+		}
+	}
+
+	fn log(&self, id: BlockID, transaction_index: usize, log_index: usize) -> Option<LocalizedLogEntry> {
+		let hash = match Self::block_hash(&self.chain, id) {
+			Some(hash) => hash,
+			None => return None,
+		};
+		let headers = match self.chain.receipt_headers(&hash) {
+			Some(headers) => headers.headers,
+			None => return None,
+		};
+		if headers.get(transaction_index).map_or(true, |header| log_index >= header.log_count) {
+			return None;
+		}
+		// index of the log within the block.
+		let block_log_index = headers[..transaction_index].iter().fold(log_index, |index, header| index + header.log_count);
+
+		let address = LogAddress {
+			block_hash: hash.clone(),
+			transaction_index: transaction_index,
+			log_index: log_index,
+		};
+		self.chain.log_entry(&address).and_then(|entry| self.chain.block(&hash).map(|bytes| {
+			let block = BlockView::new(&bytes);
+			LocalizedLogEntry {
+				entry: entry,
+				block_hash: hash.clone(),
+				block_number: block.header_view().number(),
+				transaction_hash: block.transaction_hashes().get(transaction_index).cloned().unwrap_or_else(H256::new),
+				transaction_index: transaction_index,
+				log_index: block_log_index,
+			}
+		}))
 	}
 
 	fn filter_traces(&self, filter: TraceFilter) -> Option<Vec<LocalizedTrace>> {
@@ -904,13 +1510,17 @@ impl BlockChainClient for Client {
 		let end = self.block_number(filter.range.end);
 
 		if start.is_some() && end.is_some() {
+			let include_retracted = filter.include_retracted;
 			let filter = trace::Filter {
 				range: start.unwrap() as usize..end.unwrap() as usize,
 				from_address: From::from(filter.from_address),
 				to_address: From::from(filter.to_address),
 			};
 
-			let traces = self.tracedb.filter(&filter);
+			let mut traces = self.tracedb.filter(&filter);
+			if include_retracted {
+				traces.extend(self.tracedb.filter_retracted(&filter));
+			}
 			Some(traces)
 		} else {
 			None
@@ -943,6 +1553,96 @@ impl BlockChainClient for Client {
 		self.build_last_hashes(self.chain.best_block_hash())
 	}
 
+	fn miner_stats(&self, from: BlockID, to: BlockID) -> Option<Vec<MinerStats>> {
+		let best = self.chain.best_block_number();
+		let from = match self.block_number(from) {
+			Some(from) if from <= best => from,
+			_ => return None,
+		};
+		let to = match self.block_number(to) {
+			Some(to) if to <= best => to,
+			_ => return None,
+		};
+		if from > to {
+			return Some(Vec::new());
+		}
+
+		self.chain.author_stats(from, to).map(|authors| {
+			let mut stats: Vec<_> = authors.into_iter().map(|(author, stats)| MinerStats {
+				author: author,
+				blocks: stats.blocks,
+				uncles: stats.uncles,
+				fees: stats.fees,
+				reward: self.engine.author_reward(stats.blocks, stats.uncles),
+			}).collect();
+			stats.sort_by(|a, b| b.blocks.cmp(&a.blocks));
+			stats
+		})
+	}
+
+	fn snapshot_status(&self) -> SnapshotStatus {
+		let last_snapshot = snapshot::periodic::list(&self.snapshot_root).last().cloned();
+		match self.snapshot_progress() {
+			Some((number, progress)) => SnapshotStatus {
+				creating: Some(number),
+				accounts: progress.accounts() as u64,
+				blocks: progress.blocks() as u64,
+				size: progress.size() as u64,
+				last_snapshot: last_snapshot,
+			},
+			None => SnapshotStatus {
+				last_snapshot: last_snapshot,
+				..Default::default()
+			},
+		}
+	}
+
+	fn evm_profile(&self) -> Option<Vec<ContractProfile>> {
+		self.vm_factory.profiler().map(|profiler| profiler.contracts())
+	}
+
+	fn request_snapshot(&self, id: BlockID) -> Option<BlockNumber> {
+		if self.snapshot_progress.read().is_some() {
+			return None;
+		}
+		let number = match Self::block_hash(&self.chain, id).and_then(|hash| self.chain.block_number(&hash)) {
+			Some(number) => number,
+			None => return None,
+		};
+		match self.io_channel.send(ClientIoMessage::TakeSnapshot(number)) {
+			Ok(_) => Some(number),
+			Err(e) => {
+				debug!("Ignoring snapshot request: {:?}", e);
+				None
+			},
+		}
+	}
+
+	fn missing_state_nodes(&self, max: usize) -> Vec<H256> {
+		self.state_healer.lock().as_ref().map_or_else(Vec::new, |&(_, ref healer)| healer.missing(max))
+	}
+
+	fn feed_state_node(&self, node: Bytes) -> bool {
+		let mut healer = self.state_healer.lock();
+		let healer = match *healer {
+			Some((_, ref mut healer)) => healer,
+			None => return false,
+		};
+
+		let mut state_db = self.state_db.lock().boxed_clone();
+		if !healer.feed(state_db.as_hashdb_mut(), &node) {
+			return false;
+		}
+		// healed nodes belong to the state whatever the era, so they bypass the journal.
+		let batch = DBTransaction::new();
+		let written = state_db.inject(&batch).map_err(|e| format!("{:?}", e))
+			.and_then(|_| state_db.backing().write(batch));
+		if let Err(e) = written {
+			warn!("Error writing state node: {}", e);
+		}
+		true
+	}
+
 	fn queue_transactions(&self, transactions: Vec<Bytes>) {
 		if self.queue_transactions.load(AtomicOrdering::Relaxed) > MAX_TX_QUEUE_SIZE {
 			debug!("Ignoring {} transactions: queue is full", transactions.len());
@@ -962,6 +1662,81 @@ impl BlockChainClient for Client {
 	fn pending_transactions(&self) -> Vec<SignedTransaction> {
 		self.miner.pending_transactions()
 	}
+
+	fn transactions_to_rebroadcast(&self) -> Vec<SignedTransaction> {
+		self.miner.transactions_to_rebroadcast()
+	}
+
+	fn database_stats(&self) -> Vec<DatabaseStats> {
+		DatabaseColumn::all().into_iter().map(|column| self.with_database(column, |db| {
+			// the property is only answered for the levels the database is configured with.
+			let mut files_per_level = Vec::new();
+			while let Some(files) = db.property_int(&format!("rocksdb.num-files-at-level{}", files_per_level.len())) {
+				files_per_level.push(files);
+			}
+			DatabaseStats {
+				column: column,
+				keys: db.property_int("rocksdb.estimate-num-keys").unwrap_or(0),
+				live_data_bytes: db.property_int("rocksdb.estimate-live-data-size").unwrap_or(0),
+				sst_files_bytes: db.property_int("rocksdb.total-sst-files-size").unwrap_or(0),
+				files_per_level: files_per_level,
+				compaction_pending: db.property_int("rocksdb.compaction-pending").map_or(false, |pending| pending != 0),
+			}
+		})).collect()
+	}
+
+	fn database_get(&self, column: DatabaseColumn, key: Bytes) -> Option<Bytes> {
+		self.with_database(column, |db| {
+			db.get(&key).expect("Low-level database error. Some issue with your hard disk?").map(|v| v.to_vec())
+		})
+	}
+
+	fn snapshot_manifest(&self) -> Option<Bytes> {
+		snapshot::periodic::list(&self.snapshot_root).last()
+			.and_then(|number| read_file(&snapshot::periodic::snapshot_path(&self.snapshot_root, *number).join("MANIFEST")))
+	}
+
+	fn snapshot_chunk(&self, hash: &H256) -> Option<Bytes> {
+		snapshot::periodic::list(&self.snapshot_root).last()
+			.and_then(|number| read_file(&snapshot::periodic::snapshot_path(&self.snapshot_root, *number).join(hash.hex())))
+	}
+
+	fn feed_snapshot_chunk(&self, hash: &H256, chunk: Bytes) {
+		let path = snapshot::periodic::restoration_path(&self.snapshot_root);
+		let result = fs::create_dir_all(&path)
+			.and_then(|_| fs::File::create(path.join(hash.hex())))
+			.and_then(|mut file| file.write_all(&chunk));
+		if let Err(e) = result {
+			warn!("Error storing snapshot chunk {}: {}", hash, e);
+		}
+	}
+
+	fn restore_snapshot(&self, manifest: Bytes) -> bool {
+		if self.restoring_snapshot.compare_and_swap(false, true, AtomicOrdering::SeqCst) {
+			debug!("Ignoring snapshot restoration request: already restoring");
+			return false;
+		}
+		match self.io_channel.send(ClientIoMessage::RestoreSnapshot(manifest)) {
+			Ok(_) => true,
+			Err(e) => {
+				warn!("Error queueing snapshot restoration: {:?}", e);
+				self.restoring_snapshot.store(false, AtomicOrdering::SeqCst);
+				false
+			},
+		}
+	}
+
+	fn is_restoring_snapshot(&self) -> bool {
+		self.restoring_snapshot.load(AtomicOrdering::SeqCst)
+	}
+
+	fn verify_header_seal(&self, header: Bytes) -> bool {
+		let header: Header = match UntrustedRlp::new(&header).as_val() {
+			Ok(header) => header,
+			Err(_) => return false,
+		};
+		self.engine.verify_block_basic(&header, None).is_ok() && self.engine.verify_block_unordered(&header, None).is_ok()
+	}
 }
 
 impl MiningBlockChainClient for Client {
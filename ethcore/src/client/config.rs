@@ -19,6 +19,7 @@ pub use block_queue::BlockQueueConfig;
 pub use blockchain::Config as BlockChainConfig;
 pub use trace::{Config as TraceConfig, Switch};
 pub use evm::VMType;
+pub use snapshot::Config as SnapshotConfig;
 pub use verification::VerifierType;
 use util::journaldb;
 use util::trie::TrieSpec;
@@ -78,4 +79,11 @@ pub struct ClientConfig {
 	pub mode: Mode,
 	/// Type of block verifier used by client.
 	pub verifier_type: VerifierType,
+	/// Periodic snapshot configuration.
+	pub snapshot: SnapshotConfig,
+	/// Profile the gas used by instructions of each contract.
+	pub evm_profiling: bool,
+	/// Maximal number of blocks re-executed to get a state that has been pruned.
+	/// Pruned states are not replayed if it's 0.
+	pub state_replay_budget: u64,
 }
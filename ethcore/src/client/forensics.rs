@@ -0,0 +1,110 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reports on blocks failing final verification, e.g. on a state or receipts root mismatch.
+
+use std::fmt::Write as FmtWrite;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use rustc_serialize::hex::ToHex;
+use block::{IsBlock, LockedBlock};
+use error::Error;
+use header::Header;
+use transaction::Action;
+
+/// Writes a report on a block whose enactment doesn't match its header to a file in `dir`.
+/// `enacted` should have been enacted with tracing on. Returns the path of the file.
+pub fn write_report(dir: &Path, expected: &Header, bytes: &[u8], parent: &Header, enacted: &LockedBlock, error: &Error) -> io::Result<PathBuf> {
+	try!(fs::create_dir_all(dir));
+	let path = dir.join(format!("block-{}-{}.txt", expected.number(), expected.hash().hex()));
+	let mut file = try!(File::create(&path));
+	try!(file.write_all(report(expected, bytes, parent, enacted, error).as_bytes()));
+	Ok(path)
+}
+
+fn report(expected: &Header, bytes: &[u8], parent: &Header, enacted: &LockedBlock, error: &Error) -> String {
+	let header = enacted.header();
+	let mut out = String::new();
+	// writing to a string can't fail.
+	let _ = writeln!(out, "Block #{} {}", expected.number(), expected.hash());
+	let _ = writeln!(out, "Error: {}", error);
+	let _ = writeln!(out, "Parent: #{} {}, state root {}", parent.number(), parent.hash(), parent.state_root());
+	let _ = writeln!(out, "Expected: state root {}, receipts root {}, gas used {}", expected.state_root(), expected.receipts_root(), expected.gas_used);
+	let _ = writeln!(out, "Enacted:  state root {}, receipts root {}, gas used {}", header.state_root(), header.receipts_root(), header.gas_used);
+	let _ = writeln!(out, "Expected log bloom: {}", expected.log_bloom);
+	let _ = writeln!(out, "Enacted log bloom:  {}", header.log_bloom);
+	let _ = writeln!(out, "\nBlock RLP:\n{}", bytes.to_hex());
+
+	let traces = enacted.traces().as_ref();
+	for (i, (transaction, receipt)) in enacted.transactions().iter().zip(enacted.receipts()).enumerate() {
+		let _ = writeln!(out, "\nTransaction {} {}", i, transaction.hash());
+		let _ = writeln!(out, "  from {} to {}, nonce {}, value {}, gas {}, gas price {}",
+			transaction.sender().map(|sender| format!("{}", sender)).unwrap_or_else(|e| format!("<invalid: {}>", e)),
+			match transaction.action {
+				Action::Create => "<create>".to_owned(),
+				Action::Call(ref to) => format!("{}", to),
+			},
+			transaction.nonce, transaction.value, transaction.gas, transaction.gas_price
+		);
+		let _ = writeln!(out, "  input: {}", transaction.data.to_hex());
+		let _ = writeln!(out, "  state root after: {}, cumulative gas used: {}", receipt.state_root, receipt.gas_used);
+		for log in &receipt.logs {
+			let _ = writeln!(out, "  log: {} topics [{}] data {}", log.address,
+				log.topics.iter().map(|t| format!("{}", t)).collect::<Vec<_>>().join(", "), log.data.to_hex());
+		}
+		if let Some(trace) = traces.and_then(|traces| traces.get(i)) {
+			let _ = writeln!(out, "  trace: {:#?}", trace);
+		}
+	}
+	out
+}
+
+#[cfg(test)]
+mod tests {
+	use std::ops::Deref;
+	use std::io::Read;
+	use std::fs::File;
+	use devtools::RandomTempPath;
+	use util::{Address, H256, JournalDB, Mismatch};
+	use block::{OpenBlock, IsBlock};
+	use error::{Error, BlockError};
+	use spec::Spec;
+	use tests::helpers::get_temp_journal_db;
+	use super::write_report;
+
+	#[test]
+	fn writes_report() {
+		let spec = Spec::new_test();
+		let engine = &spec.engine;
+		let genesis_header = spec.genesis_header();
+		let mut db_result = get_temp_journal_db();
+		let mut db = db_result.take();
+		spec.ensure_db_good(db.as_hashdb_mut());
+		let vm_factory = Default::default();
+		let enacted = OpenBlock::new(engine.deref(), &vm_factory, Default::default(), true, db, &genesis_header, vec![genesis_header.hash()], Address::zero(), (3141562.into(), 31415620.into()), vec![]).unwrap()
+			.close_and_lock();
+
+		let error = Error::Block(BlockError::InvalidStateRoot(Mismatch { expected: H256::from(1), found: enacted.header().state_root().clone() }));
+		let dir = RandomTempPath::new();
+		let path = write_report(dir.as_path(), enacted.header(), &[0xc0], &genesis_header, &enacted, &error).unwrap();
+
+		let mut report = String::new();
+		File::open(&path).unwrap().read_to_string(&mut report).unwrap();
+		assert!(report.starts_with("Block #1 "));
+		assert!(report.contains("Block RLP:\nc0"));
+	}
+}
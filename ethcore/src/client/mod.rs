@@ -21,13 +21,22 @@ mod error;
 mod test_client;
 mod trace;
 mod client;
+mod forensics;
+mod state_map;
 
 pub use self::client::*;
-pub use self::config::{Mode, ClientConfig, DatabaseCompactionProfile, BlockQueueConfig, BlockChainConfig, Switch, VMType};
+pub use self::config::{Mode, ClientConfig, DatabaseCompactionProfile, BlockQueueConfig, BlockChainConfig, SnapshotConfig, Switch, VMType};
 pub use self::error::Error;
 pub use types::ids::*;
 pub use self::test_client::{TestBlockChainClient, EachBlockWith};
 pub use types::trace_filter::Filter as TraceFilter;
+pub use types::database_stats::{DatabaseColumn, DatabaseStats};
+pub use types::storage_proof::StorageRangeProof;
+pub use types::miner_stats::MinerStats;
+pub use types::snapshot_status::SnapshotStatus;
+pub use types::evm_profile::{ContractProfile, InstructionProfile};
+pub use types::account_summary::AccountSummary;
+pub use types::state_override::{AccountOverride, StateOverride};
 pub use executive::{Executed, Executive, TransactOptions};
 pub use env_info::{LastHashes, EnvInfo};
 pub use self::chain_notify::{ChainNotify, ChainNotifyClient};
@@ -0,0 +1,134 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Index of the canonical blocks whose state is present in the state database.
+
+use std::cmp;
+use header::BlockNumber;
+
+/// Ranges of block numbers with state available.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct StateMap {
+	// sorted, disjoint and non-adjacent inclusive ranges.
+	ranges: Vec<(BlockNumber, BlockNumber)>,
+}
+
+impl StateMap {
+	/// Builds the map by checking which of the blocks up to `best` have their state available.
+	/// With `history` given, only that many latest blocks are checked, as older states are pruned.
+	/// Otherwise states are expected to be kept from the earliest available one on.
+	pub fn probe<F>(best: BlockNumber, history: Option<BlockNumber>, has_state: F) -> Self where F: Fn(BlockNumber) -> bool {
+		let mut map = StateMap::default();
+		match history {
+			Some(history) => {
+				for number in (best + 1).saturating_sub(history)..best + 1 {
+					if has_state(number) {
+						map.insert(number);
+					}
+				}
+			},
+			None => {
+				if !has_state(best) {
+					return map;
+				}
+				// binary search for the earliest state.
+				let (mut low, mut high) = (0, best);
+				while low < high {
+					let mid = low + (high - low) / 2;
+					match has_state(mid) {
+						true => high = mid,
+						false => low = mid + 1,
+					}
+				}
+				map.insert_range(low, best);
+			}
+		}
+		map
+	}
+
+	/// Note that the state of given block is available.
+	pub fn insert(&mut self, number: BlockNumber) {
+		self.insert_range(number, number);
+	}
+
+	fn insert_range(&mut self, from: BlockNumber, to: BlockNumber) {
+		let (mut from, mut to) = (from, to);
+		let mut ranges = Vec::with_capacity(self.ranges.len() + 1);
+		for &(start, end) in &self.ranges {
+			if end + 1 < from || to + 1 < start {
+				ranges.push((start, end));
+			} else {
+				from = cmp::min(from, start);
+				to = cmp::max(to, end);
+			}
+		}
+		ranges.push((from, to));
+		ranges.sort();
+		self.ranges = ranges;
+	}
+
+	/// Note that the states of blocks before given one have been pruned.
+	pub fn prune_before(&mut self, number: BlockNumber) {
+		self.ranges.retain(|&(_, end)| end >= number);
+		if let Some(first) = self.ranges.first_mut() {
+			first.0 = cmp::max(first.0, number);
+		}
+	}
+
+	/// Whether the state of given block is available.
+	pub fn contains(&self, number: BlockNumber) -> bool {
+		self.ranges.iter().any(|&(start, end)| start <= number && number <= end)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::StateMap;
+
+	#[test]
+	fn merges_and_prunes_ranges() {
+		let mut map = StateMap::default();
+		map.insert(5);
+		map.insert(7);
+		map.insert(6);
+		map.insert(10);
+		assert_eq!(map.ranges, vec![(5, 7), (10, 10)]);
+
+		map.prune_before(6);
+		assert!(!map.contains(5));
+		assert!(map.contains(6));
+		assert!(!map.contains(8));
+		assert!(map.contains(10));
+
+		map.prune_before(9);
+		assert_eq!(map.ranges, vec![(10, 10)]);
+	}
+
+	#[test]
+	fn probes_available_states() {
+		let archive = StateMap::probe(100, None, |n| n >= 40);
+		assert_eq!(archive.ranges, vec![(40, 100)]);
+
+		let pruned = StateMap::probe(100, Some(10), |n| n % 2 == 0);
+		assert!(pruned.contains(100));
+		assert!(pruned.contains(92));
+		assert!(!pruned.contains(90));
+		assert!(!pruned.contains(93));
+
+		let young = StateMap::probe(3, Some(10), |_| true);
+		assert_eq!(young.ranges, vec![(0, 3)]);
+	}
+}
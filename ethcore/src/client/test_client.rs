@@ -23,7 +23,7 @@ use transaction::{Transaction, LocalizedTransaction, SignedTransaction, Action};
 use blockchain::TreeRoute;
 use client::{BlockChainClient, MiningBlockChainClient, BlockChainInfo, BlockStatus, BlockID,
 	TransactionID, UncleID, TraceId, TraceFilter, LastHashes, CallAnalytics,
-	BlockImportError};
+	BlockImportError, StateOverride};
 use header::{Header as BlockHeader, BlockNumber};
 use filter::Filter;
 use log_entry::LocalizedLogEntry;
@@ -33,12 +33,20 @@ use error::{ImportResult};
 use evm::{Factory as EvmFactory, VMType};
 use miner::{Miner, MinerService};
 use spec::Spec;
+use snapshot::ManifestData;
 
 use block_queue::BlockQueueInfo;
 use block::{OpenBlock, SealedBlock};
 use executive::Executed;
-use error::ExecutionError;
+use error::{CallError, ReplayError};
 use trace::LocalizedTrace;
+use types::database_stats::{DatabaseColumn, DatabaseStats};
+use types::storage_proof::StorageRangeProof;
+use types::miner_stats::MinerStats;
+use types::account_summary::AccountSummary;
+use types::snapshot_status::SnapshotStatus;
+use types::evm_profile::ContractProfile;
+use views::BlockView;
 
 /// Test client.
 pub struct TestBlockChainClient {
@@ -72,6 +80,24 @@ pub struct TestBlockChainClient {
 	pub spec: Spec,
 	/// VM Factory
 	pub vm_factory: EvmFactory,
+	/// Snapshot manifest served to peers.
+	pub snapshot_manifest: RwLock<Option<Bytes>>,
+	/// Snapshot chunks, either served to peers or fed for restoration.
+	pub snapshot_chunks: RwLock<HashMap<H256, Bytes>>,
+	/// Manifest of the restored snapshot.
+	pub restored_manifest: RwLock<Option<Bytes>>,
+	/// Snapshot creation status.
+	pub snapshot_status: RwLock<SnapshotStatus>,
+	/// Block number of the last snapshot requested.
+	pub requested_snapshot: RwLock<Option<BlockNumber>>,
+	/// State nodes reported missing, by hash.
+	pub missing_state_nodes: RwLock<Vec<H256>>,
+	/// State nodes fed.
+	pub state_nodes: RwLock<Vec<Bytes>>,
+	/// Traces returned for any transaction or block.
+	pub traces: RwLock<Option<Vec<LocalizedTrace>>>,
+	/// Instruction profile of contracts, if profiling.
+	pub evm_profile: RwLock<Option<Vec<ContractProfile>>>,
 }
 
 #[derive(Clone)]
@@ -110,9 +136,18 @@ impl TestBlockChainClient {
 			execution_result: RwLock::new(None),
 			receipts: RwLock::new(HashMap::new()),
 			queue_size: AtomicUsize::new(0),
-			miner: Arc::new(Miner::with_spec(Spec::new_test())),
+			miner: Arc::new(Miner::with_spec(&Spec::new_test())),
 			spec: Spec::new_test(),
 			vm_factory: EvmFactory::new(VMType::Interpreter),
+			snapshot_manifest: RwLock::new(None),
+			snapshot_chunks: RwLock::new(HashMap::new()),
+			restored_manifest: RwLock::new(None),
+			snapshot_status: RwLock::new(SnapshotStatus::default()),
+			requested_snapshot: RwLock::new(None),
+			missing_state_nodes: RwLock::new(Vec::new()),
+			state_nodes: RwLock::new(Vec::new()),
+			traces: RwLock::new(None),
+			evm_profile: RwLock::new(None),
 		};
 		client.add_blocks(1, EachBlockWith::Nothing); // add genesis block
 		client.genesis_hash = client.last_hash.read().clone();
@@ -288,10 +323,18 @@ impl MiningBlockChainClient for TestBlockChainClient {
 }
 
 impl BlockChainClient for TestBlockChainClient {
-	fn call(&self, _t: &SignedTransaction, _analytics: CallAnalytics) -> Result<Executed, ExecutionError> {
+	fn call(&self, _t: &SignedTransaction, _block: BlockID, _analytics: CallAnalytics, _state_override: &StateOverride) -> Result<Executed, CallError> {
 		Ok(self.execution_result.read().clone().unwrap())
 	}
 
+	fn replay(&self, _id: TransactionID, _analytics: CallAnalytics) -> Result<Executed, ReplayError> {
+		self.execution_result.read().clone().ok_or(ReplayError::TransactionNotFound)
+	}
+
+	fn replay_block(&self, _id: BlockID, _analytics: CallAnalytics) -> Result<Vec<Executed>, ReplayError> {
+		self.execution_result.read().clone().map(|executed| vec![executed]).ok_or(ReplayError::BlockNotFound)
+	}
+
 	fn block_total_difficulty(&self, _id: BlockID) -> Option<U256> {
 		Some(U256::zero())
 	}
@@ -335,10 +378,38 @@ impl BlockChainClient for TestBlockChainClient {
 		}
 	}
 
+	fn storage_at_many(&self, address: &Address, positions: Vec<H256>, id: BlockID) -> Option<Vec<H256>> {
+		positions.iter().map(|position| self.storage_at(address, position, id.clone())).collect()
+	}
+
+	fn accounts(&self, addresses: Vec<Address>, id: BlockID) -> Option<Vec<AccountSummary>> {
+		addresses.iter().map(|address| self.balance(address, id.clone()).map(|balance| AccountSummary {
+			balance: balance,
+			nonce: self.latest_nonce(address),
+			code: self.code(address).unwrap_or_else(Vec::new),
+		})).collect()
+	}
+
+	fn prove_storage_range(&self, _address: &Address, _from: &H256, _limit: usize, _id: BlockID) -> Option<StorageRangeProof> {
+		None
+	}
+
 	fn transaction(&self, _id: TransactionID) -> Option<LocalizedTransaction> {
 		unimplemented!();
 	}
 
+	fn transaction_by_sender_and_nonce(&self, sender: &Address, nonce: &U256) -> Option<LocalizedTransaction> {
+		let numbers = self.numbers.read();
+		(0..numbers.len()).filter_map(|n| self.blocks.read().get(&numbers[&n]).cloned())
+			.filter_map(|block| {
+				let view = BlockView::new(&block);
+				view.transactions().iter()
+					.position(|tx| tx.nonce == *nonce && tx.sender().ok().as_ref() == Some(sender))
+					.and_then(|index| view.localized_transaction_at(index))
+			})
+			.next()
+	}
+
 	fn uncle(&self, _id: UncleID) -> Option<Bytes> {
 		unimplemented!();
 	}
@@ -355,10 +426,48 @@ impl BlockChainClient for TestBlockChainClient {
 		unimplemented!();
 	}
 
+	fn block_logs(&self, _hash: &H256, _filter: &Filter) -> Vec<LocalizedLogEntry> {
+		Vec::new()
+	}
+
+	fn log(&self, _id: BlockID, _transaction_index: usize, _log_index: usize) -> Option<LocalizedLogEntry> {
+		None
+	}
+
 	fn last_hashes(&self) -> LastHashes {
 		unimplemented!();
 	}
 
+	fn miner_stats(&self, from: BlockID, to: BlockID) -> Option<Vec<MinerStats>> {
+		let number = |id| self.block(id).map(|block| BlockView::new(&block).header_view().number());
+		let (from, to) = match (number(from), number(to)) {
+			(Some(from), Some(to)) => (from, to),
+			_ => return None,
+		};
+
+		let mut authors: BTreeMap<Address, MinerStats> = BTreeMap::new();
+		for number in from..to + 1 {
+			let block = match self.block(BlockID::Number(number)) {
+				Some(block) => block,
+				None => return None,
+			};
+			let view = BlockView::new(&block);
+			let author = view.header_view().author();
+			let stats = authors.entry(author.clone()).or_insert_with(|| MinerStats {
+				author: author,
+				blocks: 0,
+				uncles: 0,
+				fees: U256::zero(),
+				reward: U256::zero(),
+			});
+			stats.blocks += 1;
+			stats.uncles += view.uncles_count() as u64;
+		}
+		let mut stats: Vec<_> = authors.into_iter().map(|(_, stats)| stats).collect();
+		stats.sort_by(|a, b| b.blocks.cmp(&a.blocks));
+		Some(stats)
+	}
+
 	fn block_header(&self, id: BlockID) -> Option<Bytes> {
 		self.block_hash(id).and_then(|hash| self.blocks.read().get(&hash).map(|r| Rlp::new(r).at(0).as_raw().to_vec()))
 	}
@@ -420,6 +529,9 @@ impl BlockChainClient for TestBlockChainClient {
 
 	// TODO: returns just hashes instead of node state rlp(?)
 	fn state_data(&self, hash: &H256) -> Option<Bytes> {
+		if let Some(node) = self.state_nodes.read().iter().find(|node| &node.sha3() == hash) {
+			return Some(node.clone());
+		}
 		// starts with 'f' ?
 		if *hash > H256::from("f000000000000000000000000000000000000000000000000000000000000000") {
 			let mut rlp = RlpStream::new();
@@ -512,20 +624,24 @@ impl BlockChainClient for TestBlockChainClient {
 		}
 	}
 
+	fn cache_size(&self) -> usize {
+		0
+	}
+
 	fn filter_traces(&self, _filter: TraceFilter) -> Option<Vec<LocalizedTrace>> {
-		unimplemented!();
+		self.traces.read().clone()
 	}
 
-	fn trace(&self, _trace: TraceId) -> Option<LocalizedTrace> {
-		unimplemented!();
+	fn trace(&self, trace: TraceId) -> Option<LocalizedTrace> {
+		self.traces.read().as_ref().and_then(|traces| traces.iter().find(|t| t.trace_address == trace.address).cloned())
 	}
 
 	fn transaction_traces(&self, _trace: TransactionID) -> Option<Vec<LocalizedTrace>> {
-		unimplemented!();
+		self.traces.read().clone()
 	}
 
 	fn block_traces(&self, _trace: BlockID) -> Option<Vec<LocalizedTrace>> {
-		unimplemented!();
+		self.traces.read().clone()
 	}
 
 	fn queue_transactions(&self, transactions: Vec<Bytes>) {
@@ -537,4 +653,86 @@ impl BlockChainClient for TestBlockChainClient {
 	fn pending_transactions(&self) -> Vec<SignedTransaction> {
 		self.miner.pending_transactions()
 	}
+
+	fn transactions_to_rebroadcast(&self) -> Vec<SignedTransaction> {
+		self.miner.transactions_to_rebroadcast()
+	}
+
+	fn database_stats(&self) -> Vec<DatabaseStats> {
+		Vec::new()
+	}
+
+	fn database_get(&self, _column: DatabaseColumn, _key: Bytes) -> Option<Bytes> {
+		None
+	}
+
+	fn snapshot_manifest(&self) -> Option<Bytes> {
+		self.snapshot_manifest.read().clone()
+	}
+
+	fn snapshot_chunk(&self, hash: &H256) -> Option<Bytes> {
+		self.snapshot_chunks.read().get(hash).cloned()
+	}
+
+	fn feed_snapshot_chunk(&self, hash: &H256, chunk: Bytes) {
+		self.snapshot_chunks.write().insert(hash.clone(), chunk);
+	}
+
+	fn restore_snapshot(&self, manifest: Bytes) -> bool {
+		let complete = match ManifestData::from_rlp(&manifest) {
+			Ok(data) => {
+				let chunks = self.snapshot_chunks.read();
+				data.state_hashes.iter().chain(data.block_hashes.iter()).all(|h| chunks.contains_key(h))
+			},
+			Err(_) => false,
+		};
+		if complete {
+			*self.restored_manifest.write() = Some(manifest);
+		}
+		complete
+	}
+
+	fn is_restoring_snapshot(&self) -> bool {
+		false
+	}
+
+	fn verify_header_seal(&self, _header: Bytes) -> bool {
+		true
+	}
+
+	fn snapshot_status(&self) -> SnapshotStatus {
+		self.snapshot_status.read().clone()
+	}
+
+	fn evm_profile(&self) -> Option<Vec<ContractProfile>> {
+		self.evm_profile.read().clone()
+	}
+
+	fn request_snapshot(&self, id: BlockID) -> Option<BlockNumber> {
+		if self.snapshot_status.read().creating.is_some() {
+			return None;
+		}
+		let number = self.block(id).map(|block| BlockView::new(&block).header_view().number());
+		if number.is_some() {
+			*self.requested_snapshot.write() = number;
+		}
+		number
+	}
+
+	fn missing_state_nodes(&self, max: usize) -> Vec<H256> {
+		self.missing_state_nodes.read().iter().take(max).cloned().collect()
+	}
+
+	fn feed_state_node(&self, node: Bytes) -> bool {
+		let hash = node.sha3();
+		let mut missing = self.missing_state_nodes.write();
+		match missing.iter().position(|h| h == &hash) {
+			Some(index) => {
+				missing.remove(index);
+				self.state_nodes.write().push(node);
+				true
+			},
+			None => false,
+		}
+	}
 }
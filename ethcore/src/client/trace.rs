@@ -24,4 +24,12 @@ impl TraceDatabaseExtras for BlockChain {
 			})
 			.map(|tx| tx.hash())
 	}
+
+	fn block_transaction_hash(&self, block_hash: &H256, tx_position: usize) -> Option<H256> {
+		let tx_address = TransactionAddress {
+			block_hash: block_hash.clone(),
+			index: tx_position
+		};
+		self.transaction(&tx_address).map(|tx| tx.hash())
+	}
 }
@@ -26,7 +26,7 @@ use transaction::{LocalizedTransaction, SignedTransaction};
 use log_entry::LocalizedLogEntry;
 use filter::Filter;
 use views::{BlockView};
-use error::{ImportResult, ExecutionError};
+use error::{ImportResult, CallError, ReplayError};
 use receipt::LocalizedReceipt;
 use trace::LocalizedTrace;
 use evm::Factory as EvmFactory;
@@ -35,12 +35,18 @@ use types::trace_filter::Filter as TraceFilter;
 use executive::Executed;
 use env_info::LastHashes;
 use types::call_analytics::CallAnalytics;
+use types::state_override::StateOverride;
 use block_import_error::BlockImportError;
-use std::mem;
 use std::collections::VecDeque;
 use ipc::{IpcConfig, BinaryConvertError};
 use types::blockchain_info::BlockChainInfo;
 use types::block_status::BlockStatus;
+use types::database_stats::{DatabaseColumn, DatabaseStats};
+use types::storage_proof::StorageRangeProof;
+use types::miner_stats::MinerStats;
+use types::account_summary::AccountSummary;
+use types::snapshot_status::SnapshotStatus;
+use types::evm_profile::ContractProfile;
 
 #[derive(Ipc)]
 #[ipc(client_ident="RemoteClient")]
@@ -110,9 +116,30 @@ pub trait BlockChainClient : Sync + Send {
 			Therefore storage_at has returned Some; qed")
 	}
 
+	/// Get values of the storage at given positions at the given block's state.
+	/// The state and the account are loaded once; each position is still looked up in the storage trie separately.
+	///
+	/// Returns None if and only if the block's root hash has been pruned from the DB.
+	fn storage_at_many(&self, address: &Address, positions: Vec<H256>, id: BlockID) -> Option<Vec<H256>>;
+
+	/// Get balances, nonces and code of given accounts at the given block's state,
+	/// in the order of the addresses.
+	///
+	/// Returns None if and only if the block's root hash has been pruned from the DB.
+	fn accounts(&self, addresses: Vec<Address>, id: BlockID) -> Option<Vec<AccountSummary>>;
+
+	/// Get a proof of at most `limit` storage items of the account at `address`, starting at
+	/// hashed storage position `from`, in the given block's state.
+	///
+	/// Returns None if the account doesn't exist or the block's state has been pruned from the DB.
+	fn prove_storage_range(&self, address: &Address, from: &H256, limit: usize, id: BlockID) -> Option<StorageRangeProof>;
+
 	/// Get transaction with given hash.
 	fn transaction(&self, id: TransactionID) -> Option<LocalizedTransaction>;
 
+	/// Get canon transaction sent by `sender` with given `nonce`.
+	fn transaction_by_sender_and_nonce(&self, sender: &Address, nonce: &U256) -> Option<LocalizedTransaction>;
+
 	/// Get uncle with given id.
 	fn uncle(&self, id: UncleID) -> Option<Bytes>;
 
@@ -144,6 +171,9 @@ pub trait BlockChainClient : Sync + Send {
 	/// Get blockchain information.
 	fn chain_info(&self) -> BlockChainInfo;
 
+	/// Get the memory used by the blockchain caches in bytes.
+	fn cache_size(&self) -> usize;
+
 	/// Get the best block header.
 	fn best_block_header(&self) -> Bytes {
 		// TODO: lock blockchain only once
@@ -156,9 +186,25 @@ pub trait BlockChainClient : Sync + Send {
 	/// Returns logs matching given filter.
 	fn logs(&self, filter: Filter) -> Vec<LocalizedLogEntry>;
 
-	/// Makes a non-persistent transaction call.
-	// TODO: should be able to accept blockchain location for call.
-	fn call(&self, t: &SignedTransaction, analytics: CallAnalytics) -> Result<Executed, ExecutionError>;
+	/// Returns logs of the block with given hash matching given filter, whether the block
+	/// is canonical or not. The block range of the filter is ignored.
+	fn block_logs(&self, hash: &H256, filter: &Filter) -> Vec<LocalizedLogEntry>;
+
+	/// Returns a single log, addressed by its block, the index of its transaction within
+	/// the block and its index among the logs of that transaction.
+	fn log(&self, id: BlockID, transaction_index: usize, log_index: usize) -> Option<LocalizedLogEntry>;
+
+	/// Makes a non-persistent transaction call on the state of given block,
+	/// with `state_override` applied to a copy of that state beforehand.
+	fn call(&self, t: &SignedTransaction, block: BlockID, analytics: CallAnalytics, state_override: &StateOverride) -> Result<Executed, CallError>;
+
+	/// Re-execute a transaction of the chain on the state it was originally executed on,
+	/// e.g. to get a VM trace of it.
+	fn replay(&self, id: TransactionID, analytics: CallAnalytics) -> Result<Executed, ReplayError>;
+
+	/// Re-execute all transactions of a block of the chain on the state they were originally
+	/// executed on, e.g. to get the state diff of each of them.
+	fn replay_block(&self, id: BlockID, analytics: CallAnalytics) -> Result<Vec<Executed>, ReplayError>;
 
 	/// Returns traces matching given filter.
 	fn filter_traces(&self, filter: TraceFilter) -> Option<Vec<LocalizedTrace>>;
@@ -175,12 +221,65 @@ pub trait BlockChainClient : Sync + Send {
 	/// Get last hashes starting from best block.
 	fn last_hashes(&self) -> LastHashes;
 
+	/// Returns statistics of the authors of canon blocks `from` to `to` inclusive,
+	/// most productive first. Returns None if any of the blocks is not known.
+	fn miner_stats(&self, from: BlockID, to: BlockID) -> Option<Vec<MinerStats>>;
+
+	/// Returns statistics of all databases used by the client, as estimated by RocksDB,
+	/// including the number of table files at each compaction level.
+	fn database_stats(&self) -> Vec<DatabaseStats>;
+
+	/// Returns raw value stored under `key` in given database.
+	fn database_get(&self, column: DatabaseColumn, key: Bytes) -> Option<Bytes>;
+
+	/// Get the manifest of the latest snapshot available to serve, if any.
+	fn snapshot_manifest(&self) -> Option<Bytes>;
+
+	/// Get a compressed chunk of the latest snapshot by its hash.
+	fn snapshot_chunk(&self, hash: &H256) -> Option<Bytes>;
+
+	/// Store a compressed snapshot chunk for a later restoration.
+	fn feed_snapshot_chunk(&self, hash: &H256, chunk: Bytes);
+
+	/// Queue restoration of state and blocks described by the manifest from previously fed chunks.
+	/// Only possible while the chain contains just the genesis block. Returns `false` if the restoration
+	/// could not be queued.
+	fn restore_snapshot(&self, manifest: Bytes) -> bool;
+
+	/// Check if a queued snapshot restoration is still in progress.
+	fn is_restoring_snapshot(&self) -> bool;
+
+	/// Check the seal of an RLP-encoded block header. Does not require the parent to be known.
+	fn verify_header_seal(&self, header: Bytes) -> bool;
+
+	/// Get the state of snapshot creation.
+	fn snapshot_status(&self) -> SnapshotStatus;
+
+	/// Get the gas used by instructions of each contract since the client started,
+	/// or `None` if profiling is disabled.
+	fn evm_profile(&self) -> Option<Vec<ContractProfile>>;
+
+	/// Request a snapshot at the given canon block to be taken in background.
+	/// Returns the number of the block, or `None` if the block is not known
+	/// or a snapshot is being taken already.
+	fn request_snapshot(&self, id: BlockID) -> Option<BlockNumber>;
+
+	/// Get hashes of at most `max` state nodes known to be missing from the database.
+	fn missing_state_nodes(&self, max: usize) -> Vec<H256>;
+
+	/// Write a state node fetched from a peer to the database.
+	/// Returns `true` if it was one of the missing ones.
+	fn feed_state_node(&self, node: Bytes) -> bool;
+
 	/// Queue transactions for importing.
 	fn queue_transactions(&self, transactions: Vec<Bytes>);
 
 	/// list all transactions
 	fn pending_transactions(&self) -> Vec<SignedTransaction>;
 
+	/// Get local transactions due to be sent out to peers again.
+	fn transactions_to_rebroadcast(&self) -> Vec<SignedTransaction>;
+
 	/// Get the gas price distribution.
 	fn gas_price_statistics(&self, sample_size: usize, distribution_size: usize) -> Result<Vec<U256>, ()> {
 		let mut h = self.chain_info().best_block_hash;
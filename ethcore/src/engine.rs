@@ -59,6 +59,9 @@ pub trait Engine : Sync + Send {
 	fn on_new_block(&self, _block: &mut ExecutedBlock) {}
 	/// Block transformation functions, after the transactions.
 	fn on_close_block(&self, _block: &mut ExecutedBlock) {}
+	/// Total reward, excluding transaction fees, paid to the authors of `blocks` blocks
+	/// which include `uncles` uncles altogether.
+	fn author_reward(&self, _blocks: u64, _uncles: u64) -> U256 { U256::zero() }
 
 	/// Attempt to seal the block internally.
 	///
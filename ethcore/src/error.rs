@@ -17,49 +17,12 @@
 //! General error types for use in ethcore.
 
 use util::*;
-use header::BlockNumber;
-use basic_types::LogBloom;
 use client::Error as ClientError;
-use ipc::binary::{BinaryConvertError, BinaryConvertable};
+use snapshot::SnapshotError;
 use types::block_import_error::BlockImportError;
-pub use types::executed::ExecutionError;
-
-#[derive(Debug, PartialEq, Clone)]
-/// Errors concerning transaction processing.
-pub enum TransactionError {
-	/// Transaction is already imported to the queue
-	AlreadyImported,
-	/// Transaction is not valid anymore (state already has higher nonce)
-	Old,
-	/// Transaction has too low fee
-	/// (there is already a transaction with the same sender-nonce but higher gas price)
-	TooCheapToReplace,
-	/// Transaction was not imported to the queue because limit has been reached.
-	LimitReached,
-	/// Transaction's gas price is below threshold.
-	InsufficientGasPrice {
-		/// Minimal expected gas price
-		minimal: U256,
-		/// Transaction gas price
-		got: U256,
-	},
-	/// Sender doesn't have enough funds to pay for this transaction
-	InsufficientBalance {
-		/// Senders balance
-		balance: U256,
-		/// Transaction cost
-		cost: U256,
-	},
-	/// Transactions gas is higher then current gas limit
-	GasLimitExceeded {
-		/// Current gas limit
-		limit: U256,
-		/// Declared transaction gas
-		got: U256,
-	},
-	/// Transaction's gas limit (aka gas) is invalid.
-	InvalidGasLimit(OutOfBounds<U256>),
-}
+pub use types::executed::{ExecutionError, CallError, ReplayError};
+pub use types::transaction_error::TransactionError;
+pub use types::block_error::{BlockError, ImportError};
 
 impl fmt::Display for TransactionError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -83,67 +46,6 @@ impl fmt::Display for TransactionError {
 	}
 }
 
-#[derive(Debug, PartialEq, Eq)]
-/// Errors concerning block processing.
-pub enum BlockError {
-	/// Block has too many uncles.
-	TooManyUncles(OutOfBounds<usize>),
-	/// Extra data is of an invalid length.
-	ExtraDataOutOfBounds(OutOfBounds<usize>),
-	/// Seal is incorrect format.
-	InvalidSealArity(Mismatch<usize>),
-	/// Block has too much gas used.
-	TooMuchGasUsed(OutOfBounds<U256>),
-	/// Uncles hash in header is invalid.
-	InvalidUnclesHash(Mismatch<H256>),
-	/// An uncle is from a generation too old.
-	UncleTooOld(OutOfBounds<BlockNumber>),
-	/// An uncle is from the same generation as the block.
-	UncleIsBrother(OutOfBounds<BlockNumber>),
-	/// An uncle is already in the chain.
-	UncleInChain(H256),
-	/// An uncle has a parent not in the chain.
-	UncleParentNotInChain(H256),
-	/// State root header field is invalid.
-	InvalidStateRoot(Mismatch<H256>),
-	/// Gas used header field is invalid.
-	InvalidGasUsed(Mismatch<U256>),
-	/// Transactions root header field is invalid.
-	InvalidTransactionsRoot(Mismatch<H256>),
-	/// Difficulty is out of range; this can be used as an looser error prior to getting a definitive
-	/// value for difficulty. This error needs only provide bounds of which it is out.
-	DifficultyOutOfBounds(OutOfBounds<U256>),
-	/// Difficulty header field is invalid; this is a strong error used after getting a definitive
-	/// value for difficulty (which is provided).
-	InvalidDifficulty(Mismatch<U256>),
-	/// Seal element of type H256 (max_hash for Ethash, but could be something else for
-	/// other seal engines) is out of bounds.
-	MismatchedH256SealElement(Mismatch<H256>),
-	/// Proof-of-work aspect of seal, which we assume is a 256-bit value, is invalid.
-	InvalidProofOfWork(OutOfBounds<U256>),
-	/// Some low-level aspect of the seal is incorrect.
-	InvalidSeal,
-	/// Gas limit header field is invalid.
-	InvalidGasLimit(OutOfBounds<U256>),
-	/// Receipts trie root header field is invalid.
-	InvalidReceiptsRoot(Mismatch<H256>),
-	/// Timestamp header field is invalid.
-	InvalidTimestamp(OutOfBounds<u64>),
-	/// Log bloom header field is invalid.
-	InvalidLogBloom(Mismatch<LogBloom>),
-	/// Parent hash field of header is invalid; this is an invalid error indicating a logic flaw in the codebase.
-	/// TODO: remove and favour an assert!/panic!.
-	InvalidParentHash(Mismatch<H256>),
-	/// Number field of header is invalid.
-	InvalidNumber(Mismatch<BlockNumber>),
-	/// Block number isn't sensible.
-	RidiculousNumber(OutOfBounds<BlockNumber>),
-	/// Parent given is unknown.
-	UnknownParent(H256),
-	/// Uncle parent given is unknown.
-	UnknownUncleParent(H256),
-}
-
 impl fmt::Display for BlockError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		use self::BlockError::*;
@@ -181,17 +83,6 @@ impl fmt::Display for BlockError {
 	}
 }
 
-#[derive(Debug, PartialEq)]
-/// Import to the block queue result
-pub enum ImportError {
-	/// Already in the block chain.
-	AlreadyInChain,
-	/// Already in the block queue.
-	AlreadyQueued,
-	/// Already marked as bad from a previous import (could mean parent is bad).
-	KnownBad,
-}
-
 impl fmt::Display for ImportError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		let msg = match *self {
@@ -231,6 +122,8 @@ pub enum Error {
 	Io(::std::io::Error),
 	/// Snappy error.
 	Snappy(::util::snappy::InvalidInput),
+	/// Snapshot error.
+	Snapshot(SnapshotError),
 }
 
 impl fmt::Display for Error {
@@ -249,6 +142,7 @@ impl fmt::Display for Error {
 			Error::Trie(ref err) => f.write_fmt(format_args!("{}", err)),
 			Error::Io(ref err) => f.write_fmt(format_args!("{}", err)),
 			Error::Snappy(ref err) => f.write_fmt(format_args!("{}", err)),
+			Error::Snapshot(ref err) => f.write_fmt(format_args!("{}", err)),
 		}
 	}
 }
@@ -328,6 +222,12 @@ impl From<::util::snappy::InvalidInput> for Error {
 	}
 }
 
+impl From<SnapshotError> for Error {
+	fn from(err: SnapshotError) -> Error {
+		Error::Snapshot(err)
+	}
+}
+
 impl From<BlockImportError> for Error {
 	fn from(err: BlockImportError) -> Error {
 		match err {
@@ -338,10 +238,6 @@ impl From<BlockImportError> for Error {
 	}
 }
 
-binary_fixed_size!(BlockError);
-binary_fixed_size!(ImportError);
-binary_fixed_size!(TransactionError);
-
 // TODO: uncomment below once https://github.com/rust-lang/rust/issues/27336 sorted.
 /*#![feature(concat_idents)]
 macro_rules! assimilate {
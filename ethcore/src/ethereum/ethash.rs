@@ -20,6 +20,8 @@ use block::*;
 use spec::CommonParams;
 use engine::*;
 use evm::Schedule;
+use trace::Trace;
+use trace::trace::{Action, Res, Reward, RewardType};
 use ethjson;
 
 /// Ethash params.
@@ -151,19 +153,42 @@ impl Engine for Ethash {
 
 	/// Apply the block reward on finalisation of the block.
 	/// This assumes that all uncles are valid uncles (i.e. of at least one generation before the current).
+	/// When tracing, each reward is traced after the block's transactions.
 	fn on_close_block(&self, block: &mut ExecutedBlock) {
 		let reward = self.ethash_params.block_reward;
+		let author_reward = self.author_reward(1, block.uncles().len() as u64);
 		let fields = block.fields_mut();
+		let mut rewards = vec![(fields.header.author.clone(), author_reward, RewardType::Block)];
 
 		// Bestow block reward
-		fields.state.add_balance(&fields.header.author, &(reward + reward / U256::from(32) * U256::from(fields.uncles.len())));
+		fields.state.add_balance(&fields.header.author, &author_reward);
 
 		// Bestow uncle rewards
 		let current_number = fields.header.number();
 		for u in fields.uncles.iter() {
-			fields.state.add_balance(u.author(), &(reward * U256::from(8 + u.number() - current_number) / U256::from(8)));
+			let uncle_reward = reward * U256::from(8 + u.number() - current_number) / U256::from(8);
+			fields.state.add_balance(u.author(), &uncle_reward);
+			rewards.push((u.author().clone(), uncle_reward, RewardType::Uncle));
 		}
 		fields.state.commit();
+
+		if let Some(ref mut traces) = *fields.traces {
+			traces.extend(rewards.into_iter().map(|(author, value, reward_type)| Trace {
+				depth: 0,
+				action: Action::Reward(Reward {
+					author: author,
+					value: value,
+					reward_type: reward_type,
+				}),
+				subs: vec![],
+				result: Res::None,
+			}));
+		}
+	}
+
+	fn author_reward(&self, blocks: u64, uncles: u64) -> U256 {
+		let reward = self.ethash_params.block_reward;
+		reward * U256::from(blocks) + reward / U256::from(32) * U256::from(uncles)
 	}
 
 	fn verify_block_basic(&self, header: &Header, _block: Option<&[u8]>) -> result::Result<(), Error> {
@@ -344,6 +369,7 @@ mod tests {
 	use tests::helpers::*;
 	use super::super::new_morden;
 	use super::Ethash;
+	use trace::trace::{Action, Reward, RewardType};
 
 	#[test]
 	fn on_close_block() {
@@ -381,6 +407,37 @@ mod tests {
 		assert_eq!(b.state().balance(&uncle_author), U256::from_str("3cb71f51fc558000").unwrap());
 	}
 
+	#[test]
+	fn on_close_block_traces_rewards() {
+		let spec = new_morden();
+		let engine = &spec.engine;
+		let genesis_header = spec.genesis_header();
+		let mut db_result = get_temp_journal_db();
+		let mut db = db_result.take();
+		spec.ensure_db_good(db.as_hashdb_mut());
+		let last_hashes = vec![genesis_header.hash()];
+		let vm_factory = Default::default();
+		let mut b = OpenBlock::new(engine.deref(), &vm_factory, Default::default(), true, db, &genesis_header, last_hashes, Address::zero(), (3141562.into(), 31415620.into()), vec![]).unwrap();
+		let mut uncle = Header::new();
+		let uncle_author = address_from_hex("ef2d6d194084c2de36e0dabfce45d046b37d1106");
+		uncle.author = uncle_author.clone();
+		b.push_uncle(uncle).unwrap();
+
+		let b = b.close();
+		let traces = b.traces().clone().unwrap();
+		assert_eq!(traces.len(), 2);
+		assert_eq!(traces[0].action, Action::Reward(Reward {
+			author: Address::zero(),
+			value: U256::from_str("478eae0e571ba000").unwrap(),
+			reward_type: RewardType::Block,
+		}));
+		assert_eq!(traces[1].action, Action::Reward(Reward {
+			author: uncle_author,
+			value: U256::from_str("3cb71f51fc558000").unwrap(),
+			reward_type: RewardType::Uncle,
+		}));
+	}
+
 	#[test]
 	fn has_valid_metadata() {
 		let engine = new_morden().engine;
@@ -18,7 +18,8 @@
 //!
 //! TODO: consider spliting it into two separate files.
 use std::fmt;
-use evm::Evm;
+use std::sync::Arc;
+use evm::{Evm, Profiler};
 use util::{U256, Uint};
 
 #[derive(Debug, Clone)]
@@ -81,7 +82,8 @@ impl VMType {
 
 /// Evm factory. Creates appropriate Evm.
 pub struct Factory {
-	evm: VMType
+	evm: VMType,
+	profiler: Option<Arc<Profiler>>,
 }
 
 impl Factory {
@@ -94,9 +96,9 @@ impl Factory {
 				Box::new(super::jit::JitEvm::default())
 			},
 			VMType::Interpreter => if Self::can_fit_in_usize(gas) {
-				Box::new(super::interpreter::Interpreter::<usize>::default())
+				Box::new(super::interpreter::Interpreter::<usize>::new(self.profiler.clone()))
 			} else {
-				Box::new(super::interpreter::Interpreter::<U256>::default())
+				Box::new(super::interpreter::Interpreter::<U256>::new(self.profiler.clone()))
 			}
 		}
 	}
@@ -107,9 +109,9 @@ impl Factory {
 	pub fn create(&self, gas: U256) -> Box<Evm> {
 		match self.evm {
 			VMType::Interpreter => if Self::can_fit_in_usize(gas) {
-				Box::new(super::interpreter::Interpreter::<usize>::default())
+				Box::new(super::interpreter::Interpreter::<usize>::new(self.profiler.clone()))
 			} else {
-				Box::new(super::interpreter::Interpreter::<U256>::default())
+				Box::new(super::interpreter::Interpreter::<U256>::new(self.profiler.clone()))
 			}
 		}
 	}
@@ -117,10 +119,22 @@ impl Factory {
 	/// Create new instance of specific `VMType` factory
 	pub fn new(evm: VMType) -> Self {
 		Factory {
-			evm: evm
+			evm: evm,
+			profiler: None,
 		}
 	}
 
+	/// Profile the instructions executed by the interpreter. The JIT is not profiled.
+	pub fn with_profiler(mut self, profiler: Arc<Profiler>) -> Self {
+		self.profiler = Some(profiler);
+		self
+	}
+
+	/// The profiler of executed instructions, if profiling.
+	pub fn profiler(&self) -> Option<&Arc<Profiler>> {
+		self.profiler.as_ref()
+	}
+
 	fn can_fit_in_usize(gas: U256) -> bool {
 		gas == U256::from(gas.low_u64() as usize)
 	}
@@ -131,7 +145,8 @@ impl Default for Factory {
 	#[cfg(feature = "jit")]
 	fn default() -> Factory {
 		Factory {
-			evm: VMType::Jit
+			evm: VMType::Jit,
+			profiler: None,
 		}
 	}
 
@@ -139,7 +154,8 @@ impl Default for Factory {
 	#[cfg(not(feature = "jit"))]
 	fn default() -> Factory {
 		Factory {
-			evm: VMType::Interpreter
+			evm: VMType::Interpreter,
+			profiler: None,
 		}
 	}
 }
@@ -39,6 +39,7 @@ use self::memory::Memory;
 use std::marker::PhantomData;
 use common::*;
 use super::instructions::{self, Instruction, InstructionInfo};
+use super::profiler::{Profiler, ExecutionProfile};
 use evm::{self, MessageCallResult, ContractCreateResult, GasLeft, CostType};
 
 #[cfg(feature = "evm-debug")]
@@ -88,11 +89,32 @@ enum InstructionResult<Gas> {
 #[derive(Default)]
 pub struct Interpreter<Cost: CostType> {
 	mem: Vec<u8>,
+	profiler: Option<Arc<Profiler>>,
 	_type: PhantomData<Cost>,
 }
 
 impl<Cost: CostType> evm::Evm for Interpreter<Cost> {
 	fn exec(&mut self, params: ActionParams, ext: &mut evm::Ext) -> evm::Result<GasLeft> {
+		let mut profile = self.profiler.as_ref().map(|_| ExecutionProfile::new());
+		let result = self.run(&params, ext, profile.as_mut());
+		if let (Some(profiler), Some(profile)) = (self.profiler.as_ref(), profile) {
+			profiler.record(&params.code_address, profile);
+		}
+		result
+	}
+}
+
+impl<Cost: CostType> Interpreter<Cost> {
+	/// Create an interpreter, reporting executed instructions to `profiler` if given.
+	pub fn new(profiler: Option<Arc<Profiler>>) -> Self {
+		Interpreter {
+			mem: Vec::new(),
+			profiler: profiler,
+			_type: PhantomData,
+		}
+	}
+
+	fn run(&mut self, params: &ActionParams, ext: &mut evm::Ext, mut profile: Option<&mut ExecutionProfile>) -> evm::Result<GasLeft> {
 		self.mem.clear();
 
 		let code = &params.code.as_ref().unwrap();
@@ -121,6 +143,9 @@ impl<Cost: CostType> evm::Evm for Interpreter<Cost> {
 			try!(gasometer.verify_gas(&gas_cost));
 			self.mem.expand(mem_size);
 			gasometer.current_gas = gasometer.current_gas - gas_cost;
+			if let Some(ref mut profile) = profile {
+				profile.executed(instruction, gas_cost.as_u256());
+			}
 
 			evm_debug!({
 				println!("[0x{:x}][{}(0x{:x}) Gas: {:x}\n  Gas Before: {:x}",
@@ -139,13 +164,23 @@ impl<Cost: CostType> evm::Evm for Interpreter<Cost> {
 
 			// Execute instruction
 			let result = try!(self.exec_instruction(
-				gasometer.current_gas, &params, ext, instruction, &mut reader, &mut stack
+				gasometer.current_gas, params, ext, instruction, &mut reader, &mut stack
 			));
 
 			if trace_executed {
 				ext.trace_executed(gasometer.current_gas.as_u256(), stack.peek_top(info.ret), mem_written.map(|(o, s)| (o, &(self.mem[o..(o + s)]))), store_written);
 			}
 
+			if let Some(ref mut profile) = profile {
+				match result {
+					InstructionResult::UnusedGas(gas) => profile.refunded(instruction, gas.as_u256()),
+					InstructionResult::UseAllGas => profile.consumed(instruction, gasometer.current_gas.as_u256()),
+					InstructionResult::GasLeft(gas_left) if gas_left < gasometer.current_gas =>
+						profile.consumed(instruction, (gasometer.current_gas - gas_left).as_u256()),
+					_ => {},
+				}
+			}
+
 			// Advance
 			match result {
 				InstructionResult::Ok => {},
@@ -171,9 +206,7 @@ impl<Cost: CostType> evm::Evm for Interpreter<Cost> {
 
 		Ok(GasLeft::Known(gasometer.current_gas.as_u256()))
 	}
-}
 
-impl<Cost: CostType> Interpreter<Cost> {
 
 	fn verify_instruction(&self, ext: &evm::Ext, instruction: Instruction, info: &InstructionInfo, stack: &Stack<U256>) -> evm::Result<()> {
 		let schedule = ext.schedule();
@@ -22,6 +22,7 @@ pub mod interpreter;
 #[macro_use]
 pub mod factory;
 pub mod schedule;
+pub mod profiler;
 mod instructions;
 #[cfg(feature = "jit" )]
 mod jit;
@@ -35,3 +36,4 @@ pub use self::evm::{Evm, Error, Finalize, GasLeft, Result, CostType};
 pub use self::ext::{Ext, ContractCreateResult, MessageCallResult};
 pub use self::factory::{Factory, VMType};
 pub use self::schedule::Schedule;
+pub use self::profiler::Profiler;
@@ -0,0 +1,139 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Instruction-level gas profiling of executed contracts.
+
+use std::collections::HashMap;
+use util::{Address, U256, Uint, Mutex};
+use types::evm_profile::{ContractProfile, InstructionProfile};
+use super::instructions::{Instruction, INSTRUCTIONS};
+
+/// Instructions executed by a single run of the interpreter, indexed by opcode.
+pub struct ExecutionProfile {
+	counts: Vec<u64>,
+	gas: Vec<U256>,
+}
+
+impl ExecutionProfile {
+	/// Create an empty profile.
+	pub fn new() -> Self {
+		ExecutionProfile {
+			counts: vec![0; 0x100],
+			gas: vec![U256::zero(); 0x100],
+		}
+	}
+
+	/// Note an execution of `instruction` costing `gas`.
+	pub fn executed(&mut self, instruction: Instruction, gas: U256) {
+		self.counts[instruction as usize] += 1;
+		self.gas[instruction as usize] = self.gas[instruction as usize] + gas;
+	}
+
+	/// Note gas used by `instruction` after it was executed, e.g. gas passed to a contract creation.
+	pub fn consumed(&mut self, instruction: Instruction, gas: U256) {
+		self.gas[instruction as usize] = self.gas[instruction as usize] + gas;
+	}
+
+	/// Note gas returned to `instruction` after it was executed, e.g. gas left by a call.
+	pub fn refunded(&mut self, instruction: Instruction, gas: U256) {
+		let used = self.gas[instruction as usize];
+		self.gas[instruction as usize] = if used > gas { used - gas } else { U256::zero() };
+	}
+}
+
+/// Accumulates the instructions executed in the code of each contract.
+#[derive(Default)]
+pub struct Profiler {
+	contracts: Mutex<HashMap<Address, ExecutionProfile>>,
+}
+
+impl Profiler {
+	/// Create a new profiler.
+	pub fn new() -> Self {
+		Profiler::default()
+	}
+
+	/// Add a profile of the execution of the code at `address`.
+	pub fn record(&self, address: &Address, profile: ExecutionProfile) {
+		let mut contracts = self.contracts.lock();
+		let total = contracts.entry(address.clone()).or_insert_with(ExecutionProfile::new);
+		for i in 0..0x100 {
+			total.counts[i] += profile.counts[i];
+			total.gas[i] = total.gas[i] + profile.gas[i];
+		}
+	}
+
+	/// Get the profiles of all contracts, the one using the most gas first.
+	pub fn contracts(&self) -> Vec<ContractProfile> {
+		let contracts = self.contracts.lock();
+		let mut profiles: Vec<_> = contracts.iter().map(|(address, profile)| {
+			let mut instructions: Vec<_> = (0..0x100)
+				.filter(|&i| profile.counts[i] > 0)
+				.map(|i| InstructionProfile {
+					name: INSTRUCTIONS[i].name.to_owned(),
+					count: profile.counts[i],
+					gas: profile.gas[i],
+				})
+				.collect();
+			instructions.sort_by(|a, b| b.gas.cmp(&a.gas));
+			ContractProfile {
+				address: address.clone(),
+				gas: instructions.iter().fold(U256::zero(), |acc, i| acc + i.gas),
+				instructions: instructions,
+			}
+		}).collect();
+		profiles.sort_by(|a, b| b.gas.cmp(&a.gas));
+		profiles
+	}
+
+	/// Forget all profiles.
+	pub fn clear(&self) {
+		self.contracts.lock().clear();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::{Address, U256};
+	use evm::instructions;
+	use super::{ExecutionProfile, Profiler};
+
+	#[test]
+	fn accumulates_executions() {
+		let profiler = Profiler::new();
+		let mut profile = ExecutionProfile::new();
+		profile.executed(instructions::PUSH1, U256::from(3));
+		profile.executed(instructions::PUSH1, U256::from(3));
+		profile.executed(instructions::SSTORE, U256::from(20000));
+		profiler.record(&Address::from(1), profile);
+
+		let mut profile = ExecutionProfile::new();
+		profile.executed(instructions::CALL, U256::from(10000));
+		profile.refunded(instructions::CALL, U256::from(9000));
+		profiler.record(&Address::from(2), profile);
+
+		let contracts = profiler.contracts();
+		assert_eq!(contracts.len(), 2);
+		assert_eq!(contracts[0].address, Address::from(1));
+		assert_eq!(contracts[0].gas, U256::from(20006));
+		assert_eq!(contracts[0].instructions[0].name, "SSTORE".to_owned());
+		assert_eq!(contracts[0].instructions[1].count, 2);
+		assert_eq!(contracts[1].gas, U256::from(1000));
+
+		profiler.clear();
+		assert!(profiler.contracts().is_empty());
+	}
+}
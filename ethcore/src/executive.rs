@@ -21,7 +21,7 @@ use engine::*;
 use evm::{self, Ext, Factory, Finalize};
 use externalities::*;
 use substate::*;
-use trace::{Trace, Tracer, NoopTracer, ExecutiveTracer, VMTrace, VMTracer, ExecutiveVMTracer, NoopVMTracer};
+use trace::{Trace, Tracer, NoopTracer, ExecutiveTracer, VMTrace, VMTracer, ExecutiveVMTracer, NoopVMTracer, TraceError};
 use crossbeam;
 pub use types::executed::{Executed, ExecutionResult};
 
@@ -287,7 +287,7 @@ impl<'a> Executive<'a> {
 				false => {
 					self.state.revert_snapshot();
 
-					tracer.trace_failed_call(trace_info, self.depth, vec![], delegate_call);
+					tracer.trace_failed_call(trace_info, self.depth, vec![], delegate_call, TraceError::OutOfGas);
 
 					Err(evm::Error::OutOfGas)
 				}
@@ -324,7 +324,7 @@ impl<'a> Executive<'a> {
 						traces,
 						delegate_call
 					),
-					_ => tracer.trace_failed_call(trace_info, self.depth, traces, delegate_call),
+					Err(ref e) => tracer.trace_failed_call(trace_info, self.depth, traces, delegate_call, e.into()),
 				};
 
 				trace!(target: "executive", "substate={:?}; unconfirmed_substate={:?}\n", substate, unconfirmed_substate);
@@ -390,7 +390,7 @@ impl<'a> Executive<'a> {
 				self.depth,
 				subtracer.traces()
 			),
-			_ => tracer.trace_failed_create(trace_info, self.depth, subtracer.traces())
+			Err(ref e) => tracer.trace_failed_create(trace_info, self.depth, subtracer.traces(), e.into())
 		};
 
 		self.enact_result(&res, substate, unconfirmed_substate);
@@ -451,6 +451,7 @@ impl<'a> Executive<'a> {
 					trace: trace,
 					vm_trace: vm_trace,
 					state_diff: None,
+					exception: true,
 				})
 			},
 			_ => {
@@ -465,6 +466,7 @@ impl<'a> Executive<'a> {
 					trace: trace,
 					vm_trace: vm_trace,
 					state_diff: None,
+					exception: false,
 				})
 			},
 		}
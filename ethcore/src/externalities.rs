@@ -327,7 +327,7 @@ mod tests {
 
 	struct TestSetup {
 		state: GuardedTempResult<State>,
-		engine: Box<Engine>,
+		engine: Arc<Box<Engine>>,
 		sub_state: Substate,
 		env_info: EnvInfo
 	}
@@ -20,6 +20,8 @@ use util::*;
 use basic_types::*;
 use time::get_time;
 
+pub use basic_types::Seal;
+
 /// Type for Block number
 pub type BlockNumber = u64;
 
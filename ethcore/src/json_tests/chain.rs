@@ -58,11 +58,12 @@ pub fn json_chain_test(json_data: &[u8], era: ChainEra) -> Vec<String> {
 
 			let temp = RandomTempPath::new();
 			{
+				let spec = spec(&blockchain);
 				let client = Client::new(
 					ClientConfig::default(),
-					spec(&blockchain),
+					&spec,
 					temp.as_path(),
-					Arc::new(Miner::with_spec(spec(&blockchain))),
+					Arc::new(Miner::with_spec(&spec)),
 					IoChannel::disconnected()
 				).unwrap();
 				for b in &blockchain.blocks_rlp() {
@@ -98,6 +98,7 @@ pub extern crate ethstore;
 extern crate semver;
 extern crate ethcore_ipc_nano as nanoipc;
 extern crate ethcore_devtools as devtools;
+extern crate flate2;
 
 #[cfg(feature = "jit" )] extern crate evmjit;
 
@@ -114,6 +115,7 @@ pub mod trace;
 pub mod spec;
 pub mod views;
 pub mod pod_state;
+pub mod remote_signer;
 pub mod engine;
 pub mod migrations;
 pub mod miner;
@@ -17,5 +17,7 @@
 //! Extras database migrations.
 
 mod v6;
+mod v8;
 
-pub use self::v6::ToV6;
\ No newline at end of file
+pub use self::v6::ToV6;
+pub use self::v8::ToV8;
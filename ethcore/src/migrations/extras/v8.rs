@@ -0,0 +1,71 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! This migration splits stored receipts: the receipts of a block are stored without their logs,
+//! and each log is stored under its own key, made of the block hash, the index of the transaction
+//! within the block and the index of the log within the transaction.
+
+use util::hash::{FixedHash, H256};
+use util::kvdb::Database;
+use util::migration::{Batch, Config, Error, Migration};
+use util::rlp::{decode, encode};
+use blockchain::extras::{BlockReceipts, BlockReceiptHeaders, LogAddress};
+use db::Key;
+use log_entry::LogEntry;
+
+// extras index of block receipts at v7.
+const V7_BLOCK_RECEIPTS_INDEX: u8 = 4;
+
+/// Moves logs out of the stored receipts.
+pub struct ToV8;
+
+impl Migration for ToV8 {
+	fn version(&self) -> u32 { 8 }
+
+	fn migrate(&mut self, source: &Database, config: &Config, dest: &mut Database) -> Result<(), Error> {
+		let mut batch = Batch::new(config);
+
+		let mut count = 0;
+		for (key, value) in source.iter() {
+			count += 1;
+			if count == 100_000 {
+				count = 0;
+				flush!(".");
+			}
+
+			if key.len() != 33 || key[0] != V7_BLOCK_RECEIPTS_INDEX {
+				try!(batch.insert(key.into_vec(), value.into_vec(), dest));
+				continue;
+			}
+
+			let receipts: BlockReceipts = decode(&value);
+			for (transaction_index, receipt) in receipts.receipts.iter().enumerate() {
+				for (log_index, log) in receipt.logs.iter().enumerate() {
+					let address = LogAddress {
+						block_hash: H256::from_slice(&key[1..]),
+						transaction_index: transaction_index,
+						log_index: log_index,
+					};
+					let log_key = <LogAddress as Key<LogEntry>>::key(&address);
+					try!(batch.insert(log_key.to_vec(), encode(log).to_vec(), dest));
+				}
+			}
+			try!(batch.insert(key.into_vec(), encode(&BlockReceiptHeaders::from(&receipts)).to_vec(), dest));
+		}
+
+		batch.commit(dest)
+	}
+}
@@ -16,6 +16,8 @@
 
 use rayon::prelude::*;
 use std::sync::atomic::{self, AtomicBool};
+use std::sync::mpsc;
+use std::thread;
 use std::time::{Instant, Duration};
 
 use util::*;
@@ -23,7 +25,7 @@ use util::using_queue::{UsingQueue, GetAction};
 use account_provider::AccountProvider;
 use views::{BlockView, HeaderView};
 use state::State;
-use client::{MiningBlockChainClient, Executive, Executed, EnvInfo, TransactOptions, BlockID, CallAnalytics};
+use client::{MiningBlockChainClient, Executive, Executed, EnvInfo, TransactOptions, BlockID, CallAnalytics, StateOverride};
 use block::{ClosedBlock, IsBlock, Block};
 use error::*;
 use transaction::SignedTransaction;
@@ -32,8 +34,10 @@ use spec::Spec;
 use engine::Engine;
 use miner::{MinerService, MinerStatus, TransactionQueue, AccountDetails, TransactionOrigin};
 use miner::work_notify::WorkPoster;
+use miner::verification_cache::VerificationCache;
+use miner::rebroadcast::{Rebroadcaster, RebroadcastPolicy};
 use client::TransactionImportResult;
-use miner::price_info::PriceInfo;
+use miner::price_info::{PriceInfo, FetchOptions};
 
 /// Different possible definitions for pending transaction set.
 #[derive(Debug)]
@@ -70,6 +74,8 @@ pub struct MinerOptions {
 	pub work_queue_size: usize,
 	/// Can we submit two different solutions for the same block and expect both to result in an import?
 	pub enable_resubmission: bool,
+	/// Re-broadcasting of local transactions not yet seen on the network.
+	pub tx_rebroadcast: RebroadcastPolicy,
 }
 
 impl Default for MinerOptions {
@@ -85,6 +91,7 @@ impl Default for MinerOptions {
 			reseal_min_period: Duration::from_secs(0),
 			work_queue_size: 20,
 			enable_resubmission: true,
+			tx_rebroadcast: RebroadcastPolicy::default(),
 		}
 	}
 }
@@ -110,7 +117,7 @@ impl GasPriceCalibrator {
 		if Instant::now() >= self.next_calibration {
 			let usd_per_tx = self.options.usd_per_tx;
 			trace!(target: "miner", "Getting price info");
-			if let Ok(_) = PriceInfo::get(move |price: PriceInfo| {
+			if let Ok(_) = PriceInfo::get(FetchOptions::default(), move |price: PriceInfo| {
 				trace!(target: "miner", "Price info arrived: {:?}", price);
 				let usd_per_eth = price.ethusd;
 				let wei_per_usd: f32 = 1.0e18 / usd_per_eth;
@@ -171,16 +178,71 @@ pub struct Miner {
 	gas_range_target: RwLock<(U256, U256)>,
 	author: RwLock<Address>,
 	extra_data: RwLock<Bytes>,
-	spec: Spec,
+	engine: Arc<Box<Engine>>,
 
 	accounts: Option<Arc<AccountProvider>>,
 	work_poster: Option<WorkPoster>,
 	gas_pricer: Mutex<GasPricer>,
+	verification_cache: Mutex<VerificationCache>,
+	rebroadcaster: Mutex<Rebroadcaster>,
+	transaction_listener: TransactionNotifier,
+}
+
+type TransactionListener = Box<Fn(&[H256]) + Send + Sync>;
+
+/// Calls the transaction listeners on a thread of its own, started with the first
+/// listener, so that imports don't wait for them.
+struct TransactionNotifier {
+	listeners: Arc<RwLock<Vec<TransactionListener>>>,
+	sender: Mutex<Option<mpsc::Sender<Vec<H256>>>>,
+}
+
+impl TransactionNotifier {
+	fn new() -> Self {
+		TransactionNotifier {
+			listeners: Arc::new(RwLock::new(Vec::new())),
+			sender: Mutex::new(None),
+		}
+	}
+
+	fn add(&self, f: TransactionListener) {
+		self.listeners.write().push(f);
+
+		let mut sender = self.sender.lock();
+		if sender.is_some() {
+			return;
+		}
+		let (tx, rx) = mpsc::channel::<Vec<H256>>();
+		let listeners = self.listeners.clone();
+		// the thread stops once the miner, holding the sender, is dropped.
+		let spawned = thread::Builder::new().name("Transaction listeners".into()).spawn(move || {
+			for hashes in rx {
+				for listener in listeners.read().iter() {
+					listener(&hashes);
+				}
+			}
+		});
+		match spawned {
+			Ok(_) => *sender = Some(tx),
+			Err(e) => warn!(target: "miner", "Unable to start transaction listeners thread: {}", e),
+		}
+	}
+
+	fn notify(&self, hashes: Vec<H256>) {
+		if hashes.is_empty() {
+			return;
+		}
+		if let Some(ref sender) = *self.sender.lock() {
+			if sender.send(hashes).is_err() {
+				warn!(target: "miner", "Transaction listeners thread has stopped");
+			}
+		}
+	}
 }
 
 impl Miner {
 	/// Creates new instance of miner without accounts, but with given spec.
-	pub fn with_spec(spec: Spec) -> Miner {
+	pub fn with_spec(spec: &Spec) -> Miner {
 		Miner {
 			transaction_queue: Arc::new(Mutex::new(TransactionQueue::new())),
 			options: Default::default(),
@@ -192,16 +254,20 @@ impl Miner {
 			author: RwLock::new(Address::default()),
 			extra_data: RwLock::new(Vec::new()),
 			accounts: None,
-			spec: spec,
+			engine: spec.engine.clone(),
 			work_poster: None,
 			gas_pricer: Mutex::new(GasPricer::new_fixed(20_000_000_000u64.into())),
+			verification_cache: Mutex::new(VerificationCache::new(VERIFICATION_CACHE_SIZE, Duration::from_secs(VERIFICATION_CACHE_TTL_SEC))),
+			rebroadcaster: Mutex::new(Rebroadcaster::new(RebroadcastPolicy::default())),
+			transaction_listener: TransactionNotifier::new(),
 		}
 	}
 
 	/// Creates new instance of miner
-	pub fn new(options: MinerOptions, gas_pricer: GasPricer, spec: Spec, accounts: Option<Arc<AccountProvider>>) -> Arc<Miner> {
+	pub fn new(options: MinerOptions, gas_pricer: GasPricer, spec: &Spec, accounts: Option<Arc<AccountProvider>>) -> Arc<Miner> {
 		let work_poster = if !options.new_work_notify.is_empty() { Some(WorkPoster::new(&options.new_work_notify)) } else { None };
 		let txq = Arc::new(Mutex::new(TransactionQueue::with_limits(options.tx_queue_size, options.tx_gas_limit)));
+		let rebroadcaster = Rebroadcaster::new(options.tx_rebroadcast.clone());
 		Arc::new(Miner {
 			transaction_queue: txq,
 			sealing_enabled: AtomicBool::new(options.force_sealing || !options.new_work_notify.is_empty()),
@@ -213,14 +279,28 @@ impl Miner {
 			extra_data: RwLock::new(Vec::new()),
 			options: options,
 			accounts: accounts,
-			spec: spec,
+			engine: spec.engine.clone(),
 			work_poster: work_poster,
 			gas_pricer: Mutex::new(gas_pricer),
+			verification_cache: Mutex::new(VerificationCache::new(VERIFICATION_CACHE_SIZE, Duration::from_secs(VERIFICATION_CACHE_TTL_SEC))),
+			rebroadcaster: Mutex::new(rebroadcaster),
+			transaction_listener: TransactionNotifier::new(),
 		})
 	}
 
+	/// Set a callback to be notified about hashes of transactions entering the current queue.
+	/// Callbacks are called on a separate thread, after the import has finished.
+	pub fn add_transactions_listener(&self, f: Box<Fn(&[H256]) + Send + Sync>) {
+		self.transaction_listener.add(f);
+	}
+
 	fn engine(&self) -> &Engine {
-		self.spec.engine.deref()
+		&**self.engine
+	}
+
+	/// Whether blocks are being prepared for sealing, either forced or because of recent work requests.
+	pub fn is_sealing(&self) -> bool {
+		self.sealing_enabled.load(atomic::Ordering::Relaxed)
 	}
 
 	fn forced_sealing(&self) -> bool {
@@ -425,16 +505,43 @@ impl Miner {
 			.collect()
 	}
 
+	/// Adds transactions received from the network, reusing the outcome of recent imports
+	/// of the same transactions instead of verifying them again.
+	fn add_external_transactions_to_queue(&self, chain: &MiningBlockChainClient, transactions: Vec<SignedTransaction>, transaction_queue: &mut TransactionQueue) ->
+		Vec<Result<TransactionImportResult, Error>> {
+
+		let mut verification_cache = self.verification_cache.lock();
+		transactions.into_iter()
+			.map(|tx| {
+				let hash = tx.hash();
+				if let Some(result) = verification_cache.get(&hash) {
+					trace!(target: "miner", "Skipping verification of recently seen transaction {:?}", hash);
+					return result;
+				}
+				let result = self.add_transactions_to_queue(chain, vec![tx], TransactionOrigin::External, transaction_queue)
+					.pop()
+					.expect("one transaction added; one result returned; qed");
+				verification_cache.note(hash, &result);
+				result
+			})
+			.collect()
+	}
+
 	/// Are we allowed to do a non-mandatory reseal?
 	fn tx_reseal_allowed(&self) -> bool { Instant::now() > *self.next_allowed_reseal.lock() }
 }
 
 const SEALING_TIMEOUT_IN_BLOCKS : u64 = 5;
+/// Maximal number of remembered import outcomes of network transactions.
+const VERIFICATION_CACHE_SIZE: usize = 8192;
+/// How long an import outcome of a network transaction is remembered.
+const VERIFICATION_CACHE_TTL_SEC: u64 = 60;
 
 impl MinerService for Miner {
 
 	fn clear_and_reset(&self, chain: &MiningBlockChainClient) {
 		self.transaction_queue.lock().clear();
+		self.verification_cache.lock().clear();
 		// --------------------------------------------------------------------------
 		// | NOTE Code below requires transaction_queue and sealing_work locks.     |
 		// | Make sure to release the locks before calling that method.             |
@@ -452,7 +559,7 @@ impl MinerService for Miner {
 		}
 	}
 
-	fn call(&self, chain: &MiningBlockChainClient, t: &SignedTransaction, analytics: CallAnalytics) -> Result<Executed, ExecutionError> {
+	fn call(&self, chain: &MiningBlockChainClient, t: &SignedTransaction, analytics: CallAnalytics, state_override: &StateOverride) -> Result<Executed, ExecutionError> {
 		let sealing_work = self.sealing_work.lock();
 		match sealing_work.peek_last_ref() {
 			Some(work) => {
@@ -472,6 +579,11 @@ impl MinerService for Miner {
 				};
 				// that's just a copy of the state.
 				let mut state = block.state().clone();
+				state.apply_override(state_override);
+				let original_state = match analytics.state_diffing {
+					true => Some(state.clone()),
+					false => None,
+				};
 				let sender = try!(t.sender().map_err(|e| {
 					let message = format!("Transaction malformed: {:?}", e);
 					ExecutionError::TransactionMalformed(message)
@@ -486,15 +598,19 @@ impl MinerService for Miner {
 				let mut ret = Executive::new(&mut state, &env_info, self.engine(), chain.vm_factory()).transact(t, options);
 
 				// TODO gav move this into Executive.
-				if analytics.state_diffing {
+				if let Some(original) = original_state {
 					if let Ok(ref mut x) = ret {
-						x.state_diff = Some(state.diff_from(block.state().clone()));
+						x.state_diff = Some(state.diff_from(original));
 					}
 				}
 				ret
 			},
 			None => {
-				chain.call(t, analytics)
+				chain.call(t, BlockID::Latest, analytics, state_override).map_err(|e| match e {
+					CallError::Execution(e) => e,
+					// latest state is always available.
+					CallError::UnknownBlock | CallError::StatePruned => ExecutionError::Internal,
+				})
 			}
 		}
 	}
@@ -597,13 +713,30 @@ impl MinerService for Miner {
 		transactions: Vec<SignedTransaction>
 	) -> Vec<Result<TransactionImportResult, Error>> {
 
+		let hashes = transactions.iter().map(|tx| tx.hash()).collect::<Vec<_>>();
+		{
+			// transactions announced by peers have made it to the network.
+			let mut rebroadcaster = self.rebroadcaster.lock();
+			for hash in &hashes {
+				rebroadcaster.note_seen(hash);
+			}
+		}
+
 		let results = {
 			let mut transaction_queue = self.transaction_queue.lock();
-			self.add_transactions_to_queue(
-				chain, transactions, TransactionOrigin::External, &mut transaction_queue
-			)
+			self.add_external_transactions_to_queue(chain, transactions, &mut transaction_queue)
 		};
 
+		let current = hashes.into_iter()
+			.zip(results.iter())
+			.filter(|&(_, result)| match *result {
+				Ok(TransactionImportResult::Current) => true,
+				_ => false,
+			})
+			.map(|(hash, _)| hash)
+			.collect::<Vec<_>>();
+		self.transaction_listener.notify(current);
+
 		if !results.is_empty() && self.options.reseal_on_external_tx &&	self.tx_reseal_allowed() {
 			// --------------------------------------------------------------------------
 			// | NOTE Code below requires transaction_queue and sealing_work locks.     |
@@ -634,6 +767,7 @@ impl MinerService for Miner {
 				Ok(ref res) => {
 					trace!(target: "own_tx", "Imported transaction to {:?} (hash: {:?})", res, hash);
 					trace!(target: "own_tx", "Status: {:?}", transaction_queue.status());
+					self.rebroadcaster.lock().track(hash.clone(), Instant::now());
 				},
 				Err(ref e) => {
 					trace!(target: "own_tx", "Failed to import transaction {:?} (hash: {:?})", e, hash);
@@ -644,6 +778,10 @@ impl MinerService for Miner {
 			import
 		};
 
+		if let Ok(TransactionImportResult::Current) = imported {
+			self.transaction_listener.notify(vec![hash]);
+		}
+
 		// --------------------------------------------------------------------------
 		// | NOTE Code below requires transaction_queue and sealing_work locks.     |
 		// | Make sure to release the locks before calling that method.             |
@@ -662,6 +800,21 @@ impl MinerService for Miner {
 		imported
 	}
 
+	fn transactions_to_rebroadcast(&self) -> Vec<SignedTransaction> {
+		let queue = self.transaction_queue.lock();
+		let due = self.rebroadcaster.lock().due(Instant::now(), |hash| queue.find(hash).is_some());
+		due.iter().filter_map(|hash| queue.find(hash)).collect()
+	}
+
+	fn set_transaction_rebroadcast(&self, hash: &H256, policy: RebroadcastPolicy) -> bool {
+		let queue = self.transaction_queue.lock();
+		if queue.find(hash).is_none() {
+			return false;
+		}
+		self.rebroadcaster.lock().set_policy(hash.clone(), policy, Instant::now());
+		true
+	}
+
 	fn all_transactions(&self) -> Vec<SignedTransaction> {
 		let queue = self.transaction_queue.lock();
 		queue.top_transactions()
@@ -694,6 +847,19 @@ impl MinerService for Miner {
 		}
 	}
 
+	fn remove_pending_transaction(&self, chain: &MiningBlockChainClient, hash: &H256) -> Option<SignedTransaction> {
+		let mut queue = self.transaction_queue.lock();
+		let transaction = queue.find(hash);
+		if transaction.is_some() {
+			let fetch_account = |a: &Address| AccountDetails {
+				nonce: chain.latest_nonce(a),
+				balance: chain.latest_balance(a),
+			};
+			queue.remove_invalid(hash, &fetch_account);
+		}
+		transaction
+	}
+
 	fn transaction(&self, hash: &H256) -> Option<SignedTransaction> {
 		let queue = self.transaction_queue.lock();
 		let sw = self.sealing_work.lock();
@@ -803,6 +969,9 @@ impl MinerService for Miner {
 		// First update gas limit in transaction queue
 		self.update_gas_limit(chain);
 
+		// Rejected transactions may be valid in the new state
+		self.verification_cache.lock().clear_rejected();
+
 		// Then import all transactions...
 		{
 			let out_of_chain = retracted
@@ -848,7 +1017,7 @@ impl MinerService for Miner {
 mod tests {
 
 	use std::time::Duration;
-	use super::super::MinerService;
+	use super::super::{MinerService, RebroadcastPolicy};
 	use super::*;
 	use util::*;
 	use client::{TestBlockChainClient, EachBlockWith};
@@ -861,7 +1030,7 @@ mod tests {
 	fn should_prepare_block_to_seal() {
 		// given
 		let client = TestBlockChainClient::default();
-		let miner = Miner::with_spec(Spec::new_test());
+		let miner = Miner::with_spec(&Spec::new_test());
 
 		// when
 		let sealing_work = miner.map_sealing_work(&client, |_| ());
@@ -872,7 +1041,7 @@ mod tests {
 	fn should_still_work_after_a_couple_of_blocks() {
 		// given
 		let client = TestBlockChainClient::default();
-		let miner = Miner::with_spec(Spec::new_test());
+		let miner = Miner::with_spec(&Spec::new_test());
 
 		let res = miner.map_sealing_work(&client, |b| b.block().fields().header.hash());
 		assert!(res.is_some());
@@ -902,9 +1071,10 @@ mod tests {
 				pending_set: PendingSet::AlwaysSealing,
 				work_queue_size: 5,
 				enable_resubmission: true,
+				tx_rebroadcast: RebroadcastPolicy::default(),
 			},
 			GasPricer::new_fixed(0u64.into()),
-			Spec::new_test(),
+			&Spec::new_test(),
 			None, // accounts provider
 		)).ok().expect("Miner was just created.")
 	}
@@ -968,4 +1138,66 @@ mod tests {
 		// This method will let us know if pending block was created (before calling that method)
 		assert_eq!(miner.enable_and_prepare_sealing(&client), true);
 	}
+
+	#[test]
+	fn should_remove_pending_transaction() {
+		// given
+		let client = TestBlockChainClient::default();
+		let miner = miner();
+		let transaction = {
+			let keypair = KeyPair::create().unwrap();
+			Transaction {
+				action: Action::Create,
+				value: U256::zero(),
+				data: "3331600055".from_hex().unwrap(),
+				gas: U256::from(100_000),
+				gas_price: U256::zero(),
+				nonce: U256::zero(),
+			}.sign(keypair.secret())
+		};
+		let hash = transaction.hash();
+		miner.import_external_transactions(&client, vec![transaction]).pop().unwrap().unwrap();
+
+		// when
+		let removed = miner.remove_pending_transaction(&client, &hash);
+
+		// then
+		assert_eq!(removed.map(|t| t.hash()), Some(hash));
+		assert_eq!(miner.all_transactions().len(), 0);
+		assert!(miner.remove_pending_transaction(&client, &hash).is_none());
+	}
+
+	#[test]
+	fn should_notify_listeners_about_imported_transactions() {
+		// given
+		let client = TestBlockChainClient::default();
+		let miner = miner();
+		let notified = Arc::new(Mutex::new(Vec::new()));
+		let n = notified.clone();
+		miner.add_transactions_listener(Box::new(move |hashes: &[H256]| n.lock().extend_from_slice(hashes)));
+		let transaction = {
+			let keypair = KeyPair::create().unwrap();
+			Transaction {
+				action: Action::Create,
+				value: U256::zero(),
+				data: "3331600055".from_hex().unwrap(),
+				gas: U256::from(100_000),
+				gas_price: U256::zero(),
+				nonce: U256::zero(),
+			}.sign(keypair.secret())
+		};
+		let hash = transaction.hash();
+
+		// when
+		miner.import_external_transactions(&client, vec![transaction]).pop().unwrap().unwrap();
+
+		// then
+		for _ in 0..100 {
+			if !notified.lock().is_empty() {
+				break;
+			}
+			::std::thread::sleep(::std::time::Duration::from_millis(10));
+		}
+		assert_eq!(*notified.lock(), vec![hash]);
+	}
 }
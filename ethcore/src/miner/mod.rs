@@ -33,7 +33,7 @@
 //! use ethcore::miner::{Miner, MinerService};
 //!
 //! fn main() {
-//!		let miner: Miner = Miner::with_spec(ethereum::new_frontier());
+//!		let miner: Miner = Miner::with_spec(&ethereum::new_frontier());
 //!		// get status
 //!		assert_eq!(miner.status().transactions_in_pending_queue, 0);
 //!
@@ -47,15 +47,18 @@ mod external;
 mod transaction_queue;
 mod work_notify;
 mod price_info;
+mod verification_cache;
+mod rebroadcast;
 
 pub use self::transaction_queue::{TransactionQueue, AccountDetails, TransactionOrigin};
 pub use self::miner::{Miner, MinerOptions, PendingSet, GasPricer, GasPriceCalibratorOptions};
 pub use self::external::{ExternalMiner, ExternalMinerService};
+pub use self::rebroadcast::RebroadcastPolicy;
 pub use client::TransactionImportResult;
 
 use std::collections::BTreeMap;
 use util::{H256, U256, Address, Bytes};
-use client::{MiningBlockChainClient, Executed, CallAnalytics};
+use client::{MiningBlockChainClient, Executed, CallAnalytics, StateOverride};
 use block::ClosedBlock;
 use receipt::Receipt;
 use error::{Error, ExecutionError};
@@ -118,6 +121,10 @@ pub trait MinerService : Send + Sync {
 	/// Returns hashes of transactions currently in pending
 	fn pending_transactions_hashes(&self) -> Vec<H256>;
 
+	/// Removes transaction from the queue. Subsequent transactions from the same sender are kept
+	/// in the future part of the queue. Returns removed transaction if it was found.
+	fn remove_pending_transaction(&self, chain: &MiningBlockChainClient, hash: &H256) -> Option<SignedTransaction>;
+
 	/// Removes all transactions from the queue and restart mining operation.
 	fn clear_and_reset(&self, chain: &MiningBlockChainClient);
 
@@ -141,6 +148,14 @@ pub trait MinerService : Send + Sync {
 	/// Get a list of all transactions.
 	fn all_transactions(&self) -> Vec<SignedTransaction>;
 
+	/// Get local transactions due to be re-broadcast, as they haven't been mined
+	/// or announced back to us by peers yet.
+	fn transactions_to_rebroadcast(&self) -> Vec<SignedTransaction>;
+
+	/// Override the re-broadcast policy of a queued transaction.
+	/// Returns `false` if the transaction is not in the queue.
+	fn set_transaction_rebroadcast(&self, hash: &H256, policy: RebroadcastPolicy) -> bool;
+
 	/// Get a list of all pending transactions.
 	fn pending_transactions(&self) -> Vec<SignedTransaction>;
 
@@ -159,8 +174,8 @@ pub trait MinerService : Send + Sync {
 	/// Latest account balance in pending state.
 	fn balance(&self, chain: &MiningBlockChainClient, address: &Address) -> U256;
 
-	/// Call into contract code using pending state.
-	fn call(&self, chain: &MiningBlockChainClient, t: &SignedTransaction, analytics: CallAnalytics) -> Result<Executed, ExecutionError>;
+	/// Call into contract code using pending state, with `state_override` applied to a copy of it.
+	fn call(&self, chain: &MiningBlockChainClient, t: &SignedTransaction, analytics: CallAnalytics, state_override: &StateOverride) -> Result<Executed, ExecutionError>;
 
 	/// Get storage value in pending state.
 	fn storage_at(&self, chain: &MiningBlockChainClient, address: &Address, position: &H256) -> H256;
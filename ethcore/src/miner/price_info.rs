@@ -24,59 +24,163 @@ use hyper::client::{Handler, Request, Response, Client};
 use hyper::{Next, Encoder, Decoder};
 use hyper::net::HttpStream;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct PriceInfo {
 	pub ethusd: f32,
 }
 
-pub struct SetPriceHandler<F: Fn(PriceInfo) + Sync + Send + 'static> {
-	set_price: F,
-	channel: mpsc::Sender<()>,
+/// Web service providing the price of Ether.
+pub struct Endpoint {
+	/// Url to query.
+	pub url: &'static str,
+	/// Path to the price in the returned JSON. If the value found is an array, its first element is used.
+	pub path: &'static [&'static str],
 }
 
-impl<F: Fn(PriceInfo) + Sync + Send + 'static> Drop for SetPriceHandler<F> {
+/// Endpoints queried in turn until one of them returns a valid price.
+pub const ENDPOINTS: &'static [Endpoint] = &[
+	Endpoint {
+		url: "http://api.etherscan.io/api?module=stats&action=ethprice",
+		path: &["result", "ethusd"],
+	},
+	Endpoint {
+		url: "http://api.kraken.com/0/public/Ticker?pair=XETHZUSD",
+		path: &["result", "XETHZUSD", "c"],
+	},
+];
+
+/// Options of the price fetcher.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FetchOptions {
+	/// Timeout of every single step of a request.
+	pub timeout: Duration,
+	/// Number of rounds over all endpoints before giving up.
+	pub retries: u32,
+	/// Delay before the second round. Doubled after every failed round.
+	pub backoff: Duration,
+}
+
+impl Default for FetchOptions {
+	fn default() -> Self {
+		FetchOptions {
+			timeout: Duration::from_secs(3),
+			retries: 3,
+			backoff: Duration::from_secs(5),
+		}
+	}
+}
+
+struct PriceHandler {
+	path: &'static [&'static str],
+	timeout: Duration,
+	price: Option<PriceInfo>,
+	channel: mpsc::Sender<Option<PriceInfo>>,
+}
+
+impl Drop for PriceHandler {
 	fn drop(&mut self) {
-		let _ = self.channel.send(());
+		let _ = self.channel.send(self.price.take());
 	}
 }
 
-impl<F: Fn(PriceInfo) + Sync + Send + 'static> Handler<HttpStream> for SetPriceHandler<F> {
-	fn on_request(&mut self, _: &mut Request) -> Next { Next::read().timeout(Duration::from_secs(3)) }
-	fn on_request_writable(&mut self, _: &mut Encoder<HttpStream>) -> Next { Next::read().timeout(Duration::from_secs(3)) }
-	fn on_response(&mut self, _: Response) -> Next { Next::read().timeout(Duration::from_secs(3)) }
+impl Handler<HttpStream> for PriceHandler {
+	fn on_request(&mut self, _: &mut Request) -> Next { Next::read().timeout(self.timeout) }
+	fn on_request_writable(&mut self, _: &mut Encoder<HttpStream>) -> Next { Next::read().timeout(self.timeout) }
+	fn on_response(&mut self, _: Response) -> Next { Next::read().timeout(self.timeout) }
 
 	fn on_response_readable(&mut self, r: &mut Decoder<HttpStream>) -> Next {
 		let mut body = String::new();
-		let _ = r.read_to_string(&mut body).ok()
-			.and_then(|_| Json::from_str(&body).ok())
-			.and_then(|json| json.find_path(&["result", "ethusd"])
-				.and_then(|obj| match *obj {
-					Json::String(ref s) => Some((self.set_price)(PriceInfo {
-						ethusd: FromStr::from_str(s).unwrap()
-					})),
-					_ => None,
-				}));
+		self.price = r.read_to_string(&mut body).ok()
+			.and_then(|_| PriceInfo::from_json(&body, self.path));
 		Next::end()
 	}
-
 }
 
 impl PriceInfo {
-	pub fn get<F: Fn(PriceInfo) + Sync + Send + 'static>(set_price: F) -> Result<(), ()> {
+	/// Extracts the price from JSON returned by an endpoint.
+	fn from_json(body: &str, path: &[&str]) -> Option<PriceInfo> {
+		let json = match Json::from_str(body) {
+			Ok(json) => json,
+			Err(_) => return None,
+		};
+		let value = match json.find_path(path) {
+			Some(&Json::Array(ref values)) => values.first(),
+			other => other,
+		};
+		let ethusd = match value {
+			Some(&Json::String(ref s)) => f32::from_str(s).ok(),
+			Some(&Json::F64(f)) => Some(f as f32),
+			_ => None,
+		};
+		ethusd
+			.and_then(|ethusd| match ethusd > 0f32 && ethusd.is_finite() {
+				true => Some(PriceInfo { ethusd: ethusd }),
+				false => None,
+			})
+	}
+
+	/// Queries single endpoint. Blocks until the response arrives or the request times out.
+	fn fetch(client: &Client<PriceHandler>, endpoint: &Endpoint, timeout: Duration) -> Option<PriceInfo> {
+		let url = match FromStr::from_str(endpoint.url) {
+			Ok(url) => url,
+			Err(_) => return None,
+		};
+		let (tx, rx) = mpsc::channel();
+		let handler = PriceHandler {
+			path: endpoint.path,
+			timeout: timeout,
+			price: None,
+			channel: tx,
+		};
+		client.request(url, handler).ok()
+			.and_then(|_| rx.recv().ok())
+			.and_then(|price| price)
+	}
+
+	/// Fetches the price in background, trying all `ENDPOINTS` in turn and retrying with exponential backoff.
+	/// `set_price` is called once a valid price arrives; if all attempts fail it's not called at all.
+	pub fn get<F: Fn(PriceInfo) + Sync + Send + 'static>(options: FetchOptions, set_price: F) -> Result<(), ()> {
 		// TODO: Handle each error type properly
 		let client = try!(Client::new().map_err(|_| ()));
 		thread::spawn(move || {
-			let (tx, rx) = mpsc::channel();
-			let _ = client.request(FromStr::from_str("http://api.etherscan.io/api?module=stats&action=ethprice").unwrap(), SetPriceHandler {
-				set_price: set_price,
-				channel: tx,
-			}).ok().and_then(|_| rx.recv().ok());
+			let mut backoff = options.backoff;
+			for round in 0..options.retries {
+				if round > 0 {
+					trace!(target: "miner", "Price info unavailable, retrying in {:?}", backoff);
+					thread::sleep(backoff);
+					backoff = backoff * 2;
+				}
+
+				let price = ENDPOINTS.iter().filter_map(|endpoint| {
+					let price = PriceInfo::fetch(&client, endpoint, options.timeout);
+					if price.is_none() {
+						debug!(target: "miner", "Unable to fetch price info from {}", endpoint.url);
+					}
+					price
+				}).next();
+
+				if let Some(price) = price {
+					set_price(price);
+					break;
+				}
+			}
 			client.close();
 		});
 		Ok(())
 	}
 }
 
+#[test]
+fn should_parse_price_from_endpoints() {
+	let etherscan = r#"{"status":"1","message":"OK","result":{"ethbtc":"0.0211","ethusd":"12.34"}}"#;
+	let kraken = r#"{"error":[],"result":{"XETHZUSD":{"c":["12.50000","1.0"]}}}"#;
+
+	assert_eq!(PriceInfo::from_json(etherscan, ENDPOINTS[0].path), Some(PriceInfo { ethusd: 12.34 }));
+	assert_eq!(PriceInfo::from_json(kraken, ENDPOINTS[1].path), Some(PriceInfo { ethusd: 12.5 }));
+	assert_eq!(PriceInfo::from_json(r#"{"result":{"ethusd":"0"}}"#, ENDPOINTS[0].path), None);
+	assert_eq!(PriceInfo::from_json("not json", ENDPOINTS[0].path), None);
+}
+
 #[test]
 fn should_get_price_info() {
 	use std::sync::Arc;
@@ -88,7 +192,7 @@ fn should_get_price_info() {
 	let done = Arc::new((Mutex::new(PriceInfo { ethusd: 0f32 }), Condvar::new()));
 	let rdone = done.clone();
 
-	PriceInfo::get(move |price| { let mut p = rdone.0.lock(); *p = price; rdone.1.notify_one(); }).unwrap();
+	PriceInfo::get(FetchOptions::default(), move |price| { let mut p = rdone.0.lock(); *p = price; rdone.1.notify_one(); }).unwrap();
 	let mut p = done.0.lock();
 	let t = done.1.wait_for(&mut p, Duration::from_millis(10000));
 	assert!(!t.timed_out());
@@ -0,0 +1,210 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Re-broadcasting of local transactions.
+//!
+//! Transactions sent from this node may fail to reach the network, e.g. when peers
+//! drop them or connectivity is poor. Until a local transaction is mined or announced
+//! back to us by a peer, it is periodically handed over to sync to be sent out again,
+//! with the delay between attempts growing by a constant factor.
+
+use std::cmp::min;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use util::H256;
+
+// Longest delay between two attempts.
+const MAX_DELAY_SECS: u64 = 24 * 60 * 60;
+
+/// When and how many times a transaction is re-broadcast.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RebroadcastPolicy {
+	/// Delay before the first re-broadcast.
+	pub interval: Duration,
+	/// Factor the delay is multiplied by after each attempt.
+	pub backoff: u32,
+	/// Maximal number of re-broadcasts. Zero disables re-broadcasting.
+	pub max_attempts: usize,
+}
+
+impl Default for RebroadcastPolicy {
+	fn default() -> Self {
+		RebroadcastPolicy {
+			interval: Duration::from_secs(60),
+			backoff: 2,
+			max_attempts: 5,
+		}
+	}
+}
+
+impl RebroadcastPolicy {
+	/// Policy which never re-broadcasts.
+	pub fn disabled() -> Self {
+		RebroadcastPolicy {
+			interval: Duration::from_secs(0),
+			backoff: 1,
+			max_attempts: 0,
+		}
+	}
+
+	// Delay after the given number of attempts.
+	fn delay(&self, attempts: usize) -> Duration {
+		let secs = (0..attempts).fold(self.interval.as_secs(), |secs, _| secs.saturating_mul(self.backoff as u64));
+		Duration::from_secs(min(secs, MAX_DELAY_SECS))
+	}
+}
+
+struct Entry {
+	policy: RebroadcastPolicy,
+	attempts: usize,
+	next: Instant,
+}
+
+/// Keeps track of local transactions due to be re-broadcast.
+pub struct Rebroadcaster {
+	default_policy: RebroadcastPolicy,
+	entries: HashMap<H256, Entry>,
+}
+
+impl Rebroadcaster {
+	/// Creates a tracker using given policy for newly tracked transactions.
+	pub fn new(default_policy: RebroadcastPolicy) -> Self {
+		Rebroadcaster {
+			default_policy: default_policy,
+			entries: HashMap::new(),
+		}
+	}
+
+	/// Start tracking a local transaction with the default policy.
+	pub fn track(&mut self, hash: H256, now: Instant) {
+		let policy = self.default_policy.clone();
+		self.set_policy(hash, policy, now);
+	}
+
+	/// Track a transaction with given policy, restarting its attempts count.
+	pub fn set_policy(&mut self, hash: H256, policy: RebroadcastPolicy, now: Instant) {
+		if policy.max_attempts == 0 {
+			self.entries.remove(&hash);
+			return;
+		}
+		let next = now + policy.interval;
+		self.entries.insert(hash, Entry {
+			policy: policy,
+			attempts: 0,
+			next: next,
+		});
+	}
+
+	/// Stop tracking a transaction, as it has been seen on the network.
+	pub fn note_seen(&mut self, hash: &H256) {
+		self.entries.remove(hash);
+	}
+
+	/// Returns hashes of transactions to re-broadcast now and schedules their next attempt.
+	/// Transactions for which `is_pending` is false are no longer tracked.
+	pub fn due<F>(&mut self, now: Instant, is_pending: F) -> Vec<H256> where F: Fn(&H256) -> bool {
+		let stale: Vec<H256> = self.entries.keys().filter(|hash| !is_pending(hash)).cloned().collect();
+		for hash in &stale {
+			self.entries.remove(hash);
+		}
+
+		let mut due = Vec::new();
+		let mut exhausted = Vec::new();
+		for (hash, entry) in &mut self.entries {
+			if entry.next > now {
+				continue;
+			}
+			due.push(hash.clone());
+			entry.attempts += 1;
+			match entry.attempts >= entry.policy.max_attempts {
+				true => exhausted.push(hash.clone()),
+				false => entry.next = now + entry.policy.delay(entry.attempts),
+			}
+		}
+		for hash in &exhausted {
+			self.entries.remove(hash);
+		}
+		due
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Duration, Instant};
+	use util::H256;
+	use super::{Rebroadcaster, RebroadcastPolicy};
+
+	fn policy() -> RebroadcastPolicy {
+		RebroadcastPolicy {
+			interval: Duration::from_secs(10),
+			backoff: 2,
+			max_attempts: 3,
+		}
+	}
+
+	#[test]
+	fn rebroadcasts_with_backoff() {
+		let start = Instant::now();
+		let at = |secs| start + Duration::from_secs(secs);
+		let hash = H256::from(1);
+		let mut rebroadcaster = Rebroadcaster::new(policy());
+		rebroadcaster.track(hash.clone(), start);
+
+		assert!(rebroadcaster.due(at(9), |_| true).is_empty());
+		assert_eq!(rebroadcaster.due(at(10), |_| true), vec![hash.clone()]);
+		// next attempt 20s later, then 40s.
+		assert!(rebroadcaster.due(at(29), |_| true).is_empty());
+		assert_eq!(rebroadcaster.due(at(30), |_| true), vec![hash.clone()]);
+		assert_eq!(rebroadcaster.due(at(70), |_| true), vec![hash.clone()]);
+		// attempts exhausted.
+		assert!(rebroadcaster.due(at(1000), |_| true).is_empty());
+	}
+
+	#[test]
+	fn stops_tracking_seen_and_mined_transactions() {
+		let start = Instant::now();
+		let later = start + Duration::from_secs(100);
+		let mut rebroadcaster = Rebroadcaster::new(policy());
+		rebroadcaster.track(H256::from(1), start);
+		rebroadcaster.track(H256::from(2), start);
+		rebroadcaster.track(H256::from(3), start);
+
+		rebroadcaster.note_seen(&H256::from(1));
+		let due = rebroadcaster.due(later, |hash| *hash != H256::from(2));
+		assert_eq!(due, vec![H256::from(3)]);
+	}
+
+	#[test]
+	fn overrides_policy() {
+		let start = Instant::now();
+		let hash = H256::from(1);
+		let mut rebroadcaster = Rebroadcaster::new(policy());
+		rebroadcaster.track(hash.clone(), start);
+		rebroadcaster.set_policy(hash.clone(), RebroadcastPolicy {
+			interval: Duration::from_secs(1),
+			backoff: 1,
+			max_attempts: 1,
+		}, start);
+
+		assert_eq!(rebroadcaster.due(start + Duration::from_secs(1), |_| true), vec![hash.clone()]);
+		assert!(rebroadcaster.due(start + Duration::from_secs(100), |_| true).is_empty());
+
+		rebroadcaster.track(hash.clone(), start);
+		rebroadcaster.set_policy(hash.clone(), RebroadcastPolicy::disabled(), start);
+		assert!(rebroadcaster.due(start + Duration::from_secs(100), |_| true).is_empty());
+	}
+}
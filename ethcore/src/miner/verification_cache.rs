@@ -0,0 +1,171 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Cache of recent import outcomes of transactions received from the network.
+//!
+//! The same transaction usually arrives from many peers. Remembering the outcome of the
+//! first import lets us skip signature recovery and queue checks for the copies.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use util::H256;
+use client::TransactionImportResult;
+use error::{Error, TransactionError};
+
+/// Outcome of importing a transaction.
+#[derive(Debug, Clone, PartialEq)]
+enum Outcome {
+	/// Transaction was accepted to the queue.
+	Imported,
+	/// Transaction was rejected.
+	Rejected(TransactionError),
+}
+
+/// Import outcomes of recently seen transactions, keyed by transaction hash.
+pub struct VerificationCache {
+	outcomes: HashMap<H256, (Outcome, Instant)>,
+	/// Hashes in insertion order, used to evict the oldest entries.
+	order: VecDeque<H256>,
+	limit: usize,
+	ttl: Duration,
+}
+
+impl VerificationCache {
+	/// Creates a cache holding at most `limit` outcomes for `ttl` each.
+	pub fn new(limit: usize, ttl: Duration) -> Self {
+		VerificationCache {
+			outcomes: HashMap::new(),
+			order: VecDeque::new(),
+			limit: limit,
+			ttl: ttl,
+		}
+	}
+
+	/// Returns the result to report for a transaction whose outcome is still remembered.
+	pub fn get(&self, hash: &H256) -> Option<Result<TransactionImportResult, Error>> {
+		match self.outcomes.get(hash) {
+			Some(&(ref outcome, ref time)) if time.elapsed() < self.ttl => Some(match *outcome {
+				Outcome::Imported => Err(Error::Transaction(TransactionError::AlreadyImported)),
+				Outcome::Rejected(ref err) => Err(Error::Transaction(err.clone())),
+			}),
+			_ => None,
+		}
+	}
+
+	/// Remembers the outcome of importing a transaction.
+	/// Only rejections with a `TransactionError` reason are cached.
+	pub fn note(&mut self, hash: H256, result: &Result<TransactionImportResult, Error>) {
+		let outcome = match *result {
+			Ok(_) => Outcome::Imported,
+			Err(Error::Transaction(ref err)) => Outcome::Rejected(err.clone()),
+			Err(_) => return,
+		};
+
+		if self.outcomes.insert(hash.clone(), (outcome, Instant::now())).is_none() {
+			self.order.push_back(hash);
+		}
+
+		while self.outcomes.len() > self.limit {
+			match self.order.pop_front() {
+				Some(oldest) => { self.outcomes.remove(&oldest); },
+				None => break,
+			}
+		}
+	}
+
+	/// Forgets all rejections. Called when the chain changes, since most of the
+	/// rejection reasons depend on the state.
+	pub fn clear_rejected(&mut self) {
+		let rejected: Vec<H256> = self.outcomes.iter()
+			.filter(|&(_, &(ref outcome, _))| *outcome != Outcome::Imported)
+			.map(|(hash, _)| hash.clone())
+			.collect();
+		for hash in &rejected {
+			self.outcomes.remove(hash);
+		}
+		let outcomes = &self.outcomes;
+		self.order.retain(|hash| outcomes.contains_key(hash));
+	}
+
+	/// Forgets everything.
+	pub fn clear(&mut self) {
+		self.outcomes.clear();
+		self.order.clear();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::time::Duration;
+	use util::H256;
+	use client::TransactionImportResult;
+	use error::{Error, TransactionError};
+	use super::VerificationCache;
+
+	fn rejected() -> Result<TransactionImportResult, Error> {
+		Err(Error::Transaction(TransactionError::Old))
+	}
+
+	fn unwrap_err(res: Option<Result<TransactionImportResult, Error>>) -> TransactionError {
+		match res {
+			Some(Err(Error::Transaction(e))) => e,
+			other => panic!("Unexpected result: {:?}", other),
+		}
+	}
+
+	#[test]
+	fn should_remember_outcomes() {
+		let mut cache = VerificationCache::new(10, Duration::from_secs(60));
+		cache.note(H256::from(1), &Ok(TransactionImportResult::Current));
+		cache.note(H256::from(2), &rejected());
+
+		assert_eq!(unwrap_err(cache.get(&H256::from(1))), TransactionError::AlreadyImported);
+		assert_eq!(unwrap_err(cache.get(&H256::from(2))), TransactionError::Old);
+		assert!(cache.get(&H256::from(3)).is_none());
+	}
+
+	#[test]
+	fn should_expire_outcomes() {
+		let mut cache = VerificationCache::new(10, Duration::from_secs(0));
+		cache.note(H256::from(1), &rejected());
+
+		assert!(cache.get(&H256::from(1)).is_none());
+	}
+
+	#[test]
+	fn should_evict_oldest_outcomes() {
+		let mut cache = VerificationCache::new(2, Duration::from_secs(60));
+		for i in 1..4 {
+			cache.note(H256::from(i), &rejected());
+		}
+
+		assert!(cache.get(&H256::from(1)).is_none());
+		assert!(cache.get(&H256::from(2)).is_some());
+		assert!(cache.get(&H256::from(3)).is_some());
+	}
+
+	#[test]
+	fn should_clear_rejections() {
+		let mut cache = VerificationCache::new(10, Duration::from_secs(60));
+		cache.note(H256::from(1), &Ok(TransactionImportResult::Future));
+		cache.note(H256::from(2), &rejected());
+		cache.clear_rejected();
+
+		assert!(cache.get(&H256::from(1)).is_some());
+		assert!(cache.get(&H256::from(2)).is_none());
+	}
+}
@@ -0,0 +1,456 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Secret store backed by an external signing service.
+//!
+//! Keys never touch the node: listing accounts and signing are forwarded to the service as
+//! JSON-RPC requests, either over HTTP authenticated with a bearer token, or over a Unix
+//! domain socket (`ipc://PATH`) guarded by the socket's permissions. Since neither the token
+//! nor the passwords are encrypted, an HTTP service must listen on a loopback address; a service
+//! on another host can be reached through a local TLS tunnel.
+//!
+//! The service needs to provide two methods:
+//!
+//! - `signer_accounts` with no params returns a list of hex addresses,
+//! - `signer_sign` with params `[address, message, password]` returns a 65-byte hex signature.
+//!
+//! A wrong password must be reported as an error with code `-32021`, so that failed attempts
+//! are throttled like they are for local accounts.
+//! Signatures are checked to recover to the requested account before they're used.
+//! Accounts can't be created, removed or changed through the node.
+
+use std::io::{Read, Write};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use hyper::client::{Client, Handler, Request, Response};
+use hyper::header::ContentType;
+use hyper::method::Method;
+use hyper::net::HttpStream;
+use hyper::{self, Decoder, Encoder, Next, Url};
+use rustc_serialize::json::Json;
+use util::{Mutex, RwLock};
+
+use ethstore::{Error, SecretStore};
+use ethstore::ethkey::{Address, Message, Signature, Secret, verify_address};
+
+/// Remote signer configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RemoteSignerOptions {
+	/// URL of the signing service.
+	pub url: String,
+	/// Token sent with every request.
+	pub token: String,
+	/// Timeout of every single step of a request.
+	pub timeout: Duration,
+}
+
+/// Number of seconds the list of accounts fetched from the service is used before it's fetched again.
+const ACCOUNTS_CACHE_SECS: u64 = 30;
+/// Error code the service reports a wrong password with.
+const PASSWORD_INVALID: i64 = -32021;
+/// Prefix of the path to the service's Unix domain socket.
+const IPC_PREFIX: &'static str = "ipc://";
+
+struct RequestHandler {
+	body: String,
+	token: String,
+	timeout: Duration,
+	status_ok: bool,
+	response: Option<String>,
+	channel: mpsc::Sender<Option<String>>,
+}
+
+impl Drop for RequestHandler {
+	fn drop(&mut self) {
+		let _ = self.channel.send(self.response.take());
+	}
+}
+
+impl Handler<HttpStream> for RequestHandler {
+	fn on_request(&mut self, request: &mut Request) -> Next {
+		request.set_method(Method::Post);
+		request.headers_mut().set(ContentType::json());
+		request.headers_mut().set_raw("Authorization", vec![format!("Bearer {}", self.token).into_bytes()]);
+		Next::write().timeout(self.timeout)
+	}
+
+	fn on_request_writable(&mut self, encoder: &mut Encoder<HttpStream>) -> Next {
+		if let Err(e) = encoder.write_all(self.body.as_bytes()) {
+			debug!(target: "signer", "Error sending request to remote signer: {}", e);
+			return Next::end();
+		}
+		encoder.close();
+		Next::read().timeout(self.timeout)
+	}
+
+	fn on_response(&mut self, response: Response) -> Next {
+		self.status_ok = response.status().is_success();
+		if !self.status_ok {
+			warn!(target: "signer", "Remote signer responded with {}", response.status());
+		}
+		Next::read().timeout(self.timeout)
+	}
+
+	fn on_response_readable(&mut self, decoder: &mut Decoder<HttpStream>) -> Next {
+		let mut body = String::new();
+		if self.status_ok && decoder.read_to_string(&mut body).is_ok() {
+			self.response = Some(body);
+		}
+		Next::end()
+	}
+
+	fn on_error(&mut self, err: hyper::Error) -> Next {
+		debug!(target: "signer", "Error talking to remote signer: {}", err);
+		Next::end()
+	}
+}
+
+// how requests reach the service.
+enum Transport {
+	Http {
+		url: Url,
+		token: String,
+		client: Mutex<Client<RequestHandler>>,
+	},
+	Ipc(PathBuf),
+}
+
+/// Secret store forwarding all signing to a remote service.
+pub struct RemoteSigner {
+	transport: Transport,
+	timeout: Duration,
+	// accounts and the time they were fetched.
+	accounts: RwLock<Option<(Instant, Vec<Address>)>>,
+}
+
+// the token and passwords are sent in the clear, so they must not leave the host.
+fn is_loopback(url: &Url) -> bool {
+	match url.host_str() {
+		Some("localhost") => true,
+		Some(host) => match host.trim_left_matches('[').trim_right_matches(']').parse() {
+			Ok(IpAddr::V4(ip)) => ip.is_loopback(),
+			Ok(IpAddr::V6(ip)) => ip.is_loopback(),
+			Err(_) => false,
+		},
+		None => false,
+	}
+}
+
+// sends the request over a Unix domain socket and reads a single JSON response.
+#[cfg(not(windows))]
+fn ipc_request(path: &PathBuf, body: &str, timeout: Duration) -> Result<String, Error> {
+	use std::os::unix::net::UnixStream;
+
+	let unreachable = |e: ::std::io::Error| Error::Custom(format!("Unable to reach remote signer: {}", e));
+	let mut stream = try!(UnixStream::connect(path).map_err(&unreachable));
+	try!(stream.set_read_timeout(Some(timeout)).and_then(|_| stream.set_write_timeout(Some(timeout))).map_err(&unreachable));
+	try!(stream.write_all(body.as_bytes()).and_then(|_| stream.write_all(b"\n")).map_err(&unreachable));
+
+	let mut response = Vec::new();
+	let mut buf = [0u8; 4096];
+	loop {
+		let read = try!(stream.read(&mut buf).map_err(&unreachable));
+		response.extend_from_slice(&buf[..read]);
+		// responses aren't framed, so the response is complete once it parses.
+		let text = String::from_utf8_lossy(&response).into_owned();
+		if Json::from_str(text.trim()).is_ok() {
+			return Ok(text);
+		}
+		if read == 0 {
+			return Err(Error::Custom("No response from remote signer".into()));
+		}
+	}
+}
+
+#[cfg(windows)]
+fn ipc_request(_path: &PathBuf, _body: &str, _timeout: Duration) -> Result<String, Error> {
+	Err(Error::Custom("The remote signer can't be reached over IPC on Windows".into()))
+}
+
+impl RemoteSigner {
+	/// Create a new remote signer. Fails if the URL is invalid, or is neither an `http` URL
+	/// on a loopback address nor an `ipc://` path.
+	pub fn new(options: RemoteSignerOptions) -> Result<Self, Error> {
+		if options.url.starts_with(IPC_PREFIX) {
+			let path = &options.url[IPC_PREFIX.len()..];
+			if path.is_empty() {
+				return Err(Error::Custom("Invalid remote signer URL: empty socket path".into()));
+			}
+			return Ok(RemoteSigner {
+				transport: Transport::Ipc(PathBuf::from(path)),
+				timeout: options.timeout,
+				accounts: RwLock::new(None),
+			});
+		}
+
+		let url = try!(Url::parse(&options.url).map_err(|e| Error::Custom(format!("Invalid remote signer URL: {}", e))));
+		if url.scheme() != "http" || !is_loopback(&url) {
+			return Err(Error::Custom("The remote signer must be reached over IPC or over http on a loopback address. Use a local TLS tunnel to reach a signer on another host.".into()));
+		}
+		let client = try!(Client::new().map_err(|e| Error::Custom(format!("Unable to create HTTP client: {}", e))));
+		Ok(RemoteSigner {
+			transport: Transport::Http {
+				url: url,
+				token: options.token,
+				client: Mutex::new(client),
+			},
+			timeout: options.timeout,
+			accounts: RwLock::new(None),
+		})
+	}
+
+	fn fetch_accounts(&self) -> Result<Vec<Address>, Error> {
+		match try!(self.request("signer_accounts", &[])) {
+			Json::Array(accounts) => Ok(accounts.iter()
+				.filter_map(|account| account.as_string().and_then(|a| Address::from_str(clean_0x(a)).ok()))
+				.collect()),
+			_ => Err(Error::Custom("Invalid list of accounts from remote signer".into())),
+		}
+	}
+
+	// Send a JSON-RPC request and return the result. Blocks until the response arrives or the request times out.
+	fn request(&self, method: &str, params: &[String]) -> Result<Json, Error> {
+		let params: Vec<String> = params.iter().map(|p| Json::String(p.clone()).to_string()).collect();
+		let body = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"{}","params":[{}]}}"#, method, params.join(","));
+
+		let response = try!(match self.transport {
+			Transport::Http { ref url, ref token, ref client } => self.http_request(url, token, client, body),
+			Transport::Ipc(ref path) => ipc_request(path, &body, self.timeout),
+		});
+		let json = try!(Json::from_str(response.trim()).map_err(|_| Error::Custom("Invalid response from remote signer".into())));
+
+		match json.find("result") {
+			Some(result) => Ok(result.clone()),
+			None if json.find_path(&["error", "code"]).and_then(Json::as_i64) == Some(PASSWORD_INVALID) => Err(Error::InvalidPassword),
+			None => {
+				let message = json.find_path(&["error", "message"]).and_then(Json::as_string).unwrap_or("unknown error").to_owned();
+				Err(Error::Custom(format!("Remote signer error: {}", message)))
+			},
+		}
+	}
+
+	fn http_request(&self, url: &Url, token: &str, client: &Mutex<Client<RequestHandler>>, body: String) -> Result<String, Error> {
+		let (tx, rx) = mpsc::channel();
+		let handler = RequestHandler {
+			body: body,
+			token: token.to_owned(),
+			timeout: self.timeout,
+			status_ok: false,
+			response: None,
+			channel: tx,
+		};
+		try!(client.lock().request(url.clone(), handler)
+			.map_err(|_| Error::Custom("Unable to reach remote signer".into())));
+
+		rx.recv().ok()
+			.and_then(|response| response)
+			.ok_or_else(|| Error::Custom("No response from remote signer".into()))
+	}
+
+	fn unsupported() -> Error {
+		Error::Custom("Accounts are managed by the remote signer".into())
+	}
+}
+
+fn clean_0x(s: &str) -> &str {
+	match s.starts_with("0x") {
+		true => &s[2..],
+		false => s,
+	}
+}
+
+impl SecretStore for RemoteSigner {
+	fn insert_account(&self, _secret: Secret, _password: &str) -> Result<Address, Error> {
+		Err(Self::unsupported())
+	}
+
+	fn accounts(&self) -> Vec<Address> {
+		// accounts are listed on RPC and mining paths, which shouldn't wait for the service every time.
+		if let Some((fetched, ref accounts)) = *self.accounts.read() {
+			if fetched.elapsed() < Duration::from_secs(ACCOUNTS_CACHE_SECS) {
+				return accounts.clone();
+			}
+		}
+
+		match self.fetch_accounts() {
+			Ok(accounts) => {
+				*self.accounts.write() = Some((Instant::now(), accounts.clone()));
+				accounts
+			},
+			Err(e) => {
+				warn!(target: "signer", "Unable to list accounts: {}", e);
+				// keep using the last known accounts until the service is back.
+				self.accounts.read().as_ref().map_or_else(Vec::new, |&(_, ref accounts)| accounts.clone())
+			},
+		}
+	}
+
+	fn change_password(&self, _account: &Address, _old_password: &str, _new_password: &str) -> Result<(), Error> {
+		Err(Self::unsupported())
+	}
+
+	fn remove_account(&self, _account: &Address, _password: &str) -> Result<(), Error> {
+		Err(Self::unsupported())
+	}
+
+	fn sign(&self, account: &Address, password: &str, message: &Message) -> Result<Signature, Error> {
+		let params = [format!("0x{}", account), format!("0x{}", message), password.to_owned()];
+		let signature = match try!(self.request("signer_sign", &params)) {
+			Json::String(ref s) => try!(Signature::from_str(clean_0x(s))),
+			_ => return Err(Error::Custom("Invalid signature from remote signer".into())),
+		};
+
+		// never hand out a signature for someone else's key.
+		match verify_address(account, &signature, message) {
+			Ok(true) => Ok(signature),
+			_ => Err(Error::InvalidAccount),
+		}
+	}
+
+	fn sign_with_password(&self, account: &Address, password: &str, message: &Message) -> Result<Signature, Error> {
+		self.sign(account, password, message)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::{Read, Write};
+	use std::net::TcpListener;
+	use std::thread;
+	use std::time::Duration;
+	use account_provider::{AccountProvider, Error as AccountError, UnlockPolicy};
+	use ethstore::{Error, SecretStore};
+	use ethstore::ethkey::{Generator, Random};
+	use super::{RemoteSigner, RemoteSignerOptions};
+
+	// reads from the stream until the whole request for `method` arrived.
+	fn read_request<R: Read>(stream: &mut R, method: &str) -> bool {
+		let mut request = Vec::new();
+		let mut buf = [0u8; 1024];
+		while !String::from_utf8_lossy(&request).contains(method) || !request.ends_with(b"]}") && !request.ends_with(b"]}\n") {
+			match stream.read(&mut buf) {
+				Ok(0) | Err(_) => return false,
+				Ok(read) => request.extend_from_slice(&buf[..read]),
+			}
+		}
+		true
+	}
+
+	// answer `times` requests for `method` with given JSON and return the URL to send them to.
+	fn serve(method: &'static str, response: &'static str, times: usize) -> String {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let url = format!("http://{}", listener.local_addr().unwrap());
+		thread::spawn(move || {
+			for _ in 0..times {
+				let (mut stream, _) = listener.accept().unwrap();
+				if read_request(&mut stream, method) {
+					let _ = write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", response.len(), response);
+				}
+			}
+		});
+		url
+	}
+
+	fn options(url: &str) -> RemoteSignerOptions {
+		RemoteSignerOptions {
+			url: url.into(),
+			token: "token".into(),
+			timeout: Duration::from_millis(500),
+		}
+	}
+
+	#[test]
+	fn rejects_invalid_url() {
+		assert!(RemoteSigner::new(options("not a url")).is_err());
+	}
+
+	#[test]
+	fn rejects_url_off_the_host() {
+		assert!(RemoteSigner::new(options("http://10.0.0.1:8545")).is_err());
+		assert!(RemoteSigner::new(options("http://example.com")).is_err());
+		assert!(RemoteSigner::new(options("https://127.0.0.1:8545")).is_err());
+		assert!(RemoteSigner::new(options("http://localhost:8545")).is_ok());
+		assert!(RemoteSigner::new(options("http://[::1]:8545")).is_ok());
+	}
+
+	#[test]
+	fn does_not_manage_accounts() {
+		let signer = RemoteSigner::new(options("http://127.0.0.1:8545")).unwrap();
+		assert!(signer.insert_account(Default::default(), "").is_err());
+		assert!(signer.remove_account(&Default::default(), "").is_err());
+		assert!(signer.change_password(&Default::default(), "", "").is_err());
+	}
+
+	#[test]
+	fn caches_accounts() {
+		let url = serve("signer_accounts", r#"{"jsonrpc":"2.0","id":1,"result":["0x00000000000000000000000000000000000000aa"]}"#, 1);
+		let signer = RemoteSigner::new(options(&url)).unwrap();
+		let accounts = signer.accounts();
+		assert_eq!(accounts.len(), 1);
+		// the service only answers once.
+		assert_eq!(signer.accounts(), accounts);
+	}
+
+	#[test]
+	fn throttles_wrong_passwords() {
+		let url = serve("signer_sign", r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32021,"message":"Invalid password"}}"#, 1);
+		let signer = RemoteSigner::new(options(&url)).unwrap();
+		let provider = AccountProvider::new(Box::new(signer)).with_unlock_policy(UnlockPolicy {
+			max_attempts: 1,
+			delay: Duration::from_secs(60),
+			max_delay: Duration::from_secs(60),
+		});
+		let account = Random.generate().unwrap().address();
+
+		match provider.unlock_account_temporarily(account.clone(), "wrong".into()) {
+			Err(AccountError::SStore(Error::InvalidPassword)) => {},
+			_ => panic!("wrong password should be reported"),
+		}
+		// the service isn't asked again.
+		match provider.unlock_account_temporarily(account, "wrong".into()) {
+			Err(AccountError::Throttled(_)) => {},
+			_ => panic!("attempt should be throttled"),
+		}
+	}
+
+	#[cfg(not(windows))]
+	#[test]
+	fn signs_over_ipc() {
+		use std::os::unix::net::UnixListener;
+		use devtools::RandomTempPath;
+		use ethstore::ethkey::sign;
+
+		let temp = RandomTempPath::create_dir();
+		let socket = temp.as_path().join("signer.ipc");
+		let listener = UnixListener::bind(&socket).unwrap();
+		let keypair = Random.generate().unwrap();
+		let message = Default::default();
+		let response = format!(r#"{{"jsonrpc":"2.0","id":1,"result":"0x{}"}}"#, sign(keypair.secret(), &message).unwrap());
+		thread::spawn(move || {
+			let (mut stream, _) = listener.accept().unwrap();
+			if read_request(&mut stream, "signer_sign") {
+				let _ = stream.write_all(response.as_bytes());
+			}
+		});
+
+		let signer = RemoteSigner::new(options(&format!("ipc://{}", socket.display()))).unwrap();
+		assert!(signer.sign(&keypair.address(), "password", &message).is_ok());
+	}
+}
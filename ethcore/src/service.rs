@@ -21,8 +21,9 @@ use util::panics::*;
 use spec::Spec;
 use error::*;
 use client::{Client, ClientConfig, ChainNotify};
+use header::BlockNumber;
 use miner::Miner;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 
 #[cfg(feature="ipc")]
 use nanoipc;
@@ -38,12 +39,23 @@ pub enum ClientIoMessage {
 	BlockVerified,
 	/// New transaction RLPs are ready to be imported
 	NewTransactions(Vec<Bytes>),
+	/// Take a snapshot at the given block
+	TakeSnapshot(BlockNumber),
+	/// Restore the snapshot with the given manifest from the fed chunks
+	RestoreSnapshot(Bytes),
+}
+
+/// Tells whether the node is catching up with the network.
+pub trait SyncOracle: Send + Sync {
+	/// Returns true while a major sync is in progress.
+	fn is_major_syncing(&self) -> bool;
 }
 
 /// Client service setup. Creates and registers client and network services with the IO subsystem.
 pub struct ClientService {
 	io_service: Arc<IoService<ClientIoMessage>>,
 	client: Arc<Client>,
+	sync_oracle: Arc<RwLock<Option<Arc<SyncOracle>>>>,
 	panic_handler: Arc<PanicHandler>,
 	_stop_guard: ::devtools::StopGuard,
 }
@@ -52,7 +64,7 @@ impl ClientService {
 	/// Start the service in a separate thread.
 	pub fn start(
 		config: ClientConfig,
-		spec: Spec,
+		spec: &Spec,
 		db_path: &Path,
 		miner: Arc<Miner>,
 		) -> Result<ClientService, Error>
@@ -64,8 +76,11 @@ impl ClientService {
 		info!("Configured for {} using {} engine", Colour::White.bold().paint(spec.name.clone()), Colour::Yellow.bold().paint(spec.engine.name()));
 		let client = try!(Client::new(config, spec, db_path, miner, io_service.channel()));
 		panic_handler.forward_from(client.deref());
+		let sync_oracle = Arc::new(RwLock::new(None));
 		let client_io = Arc::new(ClientIoHandler {
-			client: client.clone()
+			client: client.clone(),
+			sync_oracle: sync_oracle.clone(),
+			snapshotting: Arc::new(AtomicBool::new(false)),
 		});
 		try!(io_service.register_handler(client_io));
 
@@ -75,6 +90,7 @@ impl ClientService {
 		Ok(ClientService {
 			io_service: Arc::new(io_service),
 			client: client,
+			sync_oracle: sync_oracle,
 			panic_handler: panic_handler,
 			_stop_guard: stop_guard,
 		})
@@ -100,10 +116,22 @@ impl ClientService {
 		self.io_service.clone()
 	}
 
-	/// Set the actor to be notified on certain chain events
+	/// Add an actor to be notified on certain chain events. The client keeps only a weak reference to it.
 	pub fn add_notify(&self, notify: Arc<ChainNotify>) {
 		self.client.add_notify(notify);
 	}
+
+	/// Set the oracle asked before taking a periodic snapshot. No snapshots are taken during a major sync.
+	pub fn set_sync_oracle(&self, oracle: Arc<SyncOracle>) {
+		*self.sync_oracle.write() = Some(oracle);
+	}
+}
+
+impl Drop for ClientService {
+	fn drop(&mut self) {
+		// don't keep the snapshot thread busy after shutdown.
+		self.client.cancel_snapshot();
+	}
 }
 
 impl MayPanic for ClientService {
@@ -114,20 +142,61 @@ impl MayPanic for ClientService {
 
 /// IO interface for the Client handler
 struct ClientIoHandler {
-	client: Arc<Client>
+	client: Arc<Client>,
+	sync_oracle: Arc<RwLock<Option<Arc<SyncOracle>>>>,
+	snapshotting: Arc<AtomicBool>,
+}
+
+impl ClientIoHandler {
+	// Takes a periodic snapshot if one is due and the node isn't catching up with the chain.
+	fn snapshot(&self) {
+		if self.sync_oracle.read().as_ref().map_or(false, |oracle| oracle.is_major_syncing()) {
+			return;
+		}
+		if let Some(number) = self.client.snapshot_due() {
+			self.take_snapshot(number);
+		}
+	}
+
+	// Takes a snapshot at the given block in a background thread unless one is in progress already.
+	fn take_snapshot(&self, number: BlockNumber) {
+		if self.snapshotting.compare_and_swap(false, true, AtomicOrdering::SeqCst) {
+			return;
+		}
+
+		let client = self.client.clone();
+		let snapshotting = self.snapshotting.clone();
+		let spawned = ::std::thread::Builder::new().name("Snapshot".into()).spawn(move || {
+			info!("Taking snapshot at block #{}", number);
+			match client.take_periodic_snapshot(number) {
+				Ok(path) => info!("Snapshot at block #{} written to {}", number, path.display()),
+				Err(e) => warn!("Failed to take snapshot at block #{}: {}", number, e),
+			}
+			snapshotting.store(false, AtomicOrdering::SeqCst);
+		});
+		if let Err(e) = spawned {
+			warn!("Unable to start snapshot thread: {}", e);
+			self.snapshotting.store(false, AtomicOrdering::SeqCst);
+		}
+	}
 }
 
 const CLIENT_TICK_TIMER: TimerToken = 0;
 const CLIENT_TICK_MS: u64 = 5000;
+const SNAPSHOT_TICK_TIMER: TimerToken = 1;
+const SNAPSHOT_TICK_MS: u64 = 10000;
 
 impl IoHandler<ClientIoMessage> for ClientIoHandler {
 	fn initialize(&self, io: &IoContext<ClientIoMessage>) {
 		io.register_timer(CLIENT_TICK_TIMER, CLIENT_TICK_MS).expect("Error registering client timer");
+		io.register_timer(SNAPSHOT_TICK_TIMER, SNAPSHOT_TICK_MS).expect("Error registering snapshot timer");
 	}
 
 	fn timeout(&self, _io: &IoContext<ClientIoMessage>, timer: TimerToken) {
-		if timer == CLIENT_TICK_TIMER {
-			self.client.tick();
+		match timer {
+			CLIENT_TICK_TIMER => self.client.tick(),
+			SNAPSHOT_TICK_TIMER => self.snapshot(),
+			_ => {}
 		}
 	}
 
@@ -136,6 +205,8 @@ impl IoHandler<ClientIoMessage> for ClientIoHandler {
 		match *net_message {
 			ClientIoMessage::BlockVerified => { self.client.import_verified_blocks(); }
 			ClientIoMessage::NewTransactions(ref transactions) => { self.client.import_queued_transactions(&transactions); }
+			ClientIoMessage::TakeSnapshot(number) => self.take_snapshot(number),
+			ClientIoMessage::RestoreSnapshot(ref manifest) => { self.client.restore_fed_snapshot(manifest); },
 			_ => {} // ignore other messages
 		}
 	}
@@ -171,9 +242,9 @@ mod tests {
 		let temp_path = RandomTempPath::new();
 		let service = ClientService::start(
 			ClientConfig::default(),
-			get_test_spec(),
+			&get_test_spec(),
 			&temp_path.as_path(),
-			Arc::new(Miner::with_spec(get_test_spec())),
+			Arc::new(Miner::with_spec(&get_test_spec())),
 		);
 		assert!(service.is_ok());
 	}
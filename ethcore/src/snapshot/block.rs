@@ -16,9 +16,6 @@
 
 //! Block RLP compression.
 
-// TODO [rob] remove when BlockRebuilder done.
-#![allow(dead_code)]
-
 use block::Block;
 use header::Header;
 
@@ -0,0 +1,167 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compression of snapshot chunks.
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use util::snappy;
+
+use error::Error;
+
+// Size of chunks produced unless configured otherwise: around 16MB before compression.
+const DEFAULT_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// Compression applied to snapshot chunks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+	/// Snappy compression. Fast, the default.
+	Snappy,
+	/// Zlib compression. Slower, but produces smaller chunks.
+	Zlib,
+	/// No compression.
+	None,
+}
+
+impl Codec {
+	/// Identifier of the codec recorded in snapshot manifests.
+	pub fn id(&self) -> u8 {
+		match *self {
+			Codec::Snappy => 0,
+			Codec::Zlib => 1,
+			Codec::None => 2,
+		}
+	}
+
+	/// Codec with given identifier, if known.
+	pub fn from_id(id: u8) -> Option<Self> {
+		match id {
+			0 => Some(Codec::Snappy),
+			1 => Some(Codec::Zlib),
+			2 => Some(Codec::None),
+			_ => None,
+		}
+	}
+
+	/// Compress `raw` into `buffer`, returning the length of the compressed data.
+	pub fn compress_into(&self, raw: &[u8], buffer: &mut Vec<u8>) -> Result<usize, Error> {
+		match *self {
+			Codec::Snappy => Ok(snappy::compress_into(raw, buffer)),
+			Codec::Zlib => {
+				buffer.clear();
+				let mut encoder = ZlibEncoder::new(buffer, Compression::Default);
+				try!(encoder.write_all(raw));
+				Ok(try!(encoder.finish()).len())
+			},
+			Codec::None => {
+				buffer.clear();
+				buffer.extend_from_slice(raw);
+				Ok(raw.len())
+			},
+		}
+	}
+
+	/// Decompress `compressed` into `buffer`, returning the length of the decompressed data.
+	pub fn decompress_into(&self, compressed: &[u8], buffer: &mut Vec<u8>) -> Result<usize, Error> {
+		match *self {
+			Codec::Snappy => Ok(try!(snappy::decompress_into(compressed, buffer))),
+			Codec::Zlib => {
+				buffer.clear();
+				Ok(try!(ZlibDecoder::new(compressed).read_to_end(buffer)))
+			},
+			Codec::None => {
+				buffer.clear();
+				buffer.extend_from_slice(compressed);
+				Ok(compressed.len())
+			},
+		}
+	}
+}
+
+impl Default for Codec {
+	fn default() -> Self {
+		Codec::Snappy
+	}
+}
+
+impl fmt::Display for Codec {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(match *self {
+			Codec::Snappy => "snappy",
+			Codec::Zlib => "zlib",
+			Codec::None => "none",
+		})
+	}
+}
+
+impl FromStr for Codec {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"snappy" => Ok(Codec::Snappy),
+			"zlib" => Ok(Codec::Zlib),
+			"none" => Ok(Codec::None),
+			other => Err(format!("Unknown snapshot codec: {}", other)),
+		}
+	}
+}
+
+/// How snapshot chunks are produced.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkOptions {
+	/// Preferred size of a chunk before compression, in bytes. Chunks end once
+	/// they reach it, unless a single item is larger.
+	pub chunk_size: usize,
+	/// Compression applied to the chunks.
+	pub codec: Codec,
+}
+
+impl Default for ChunkOptions {
+	fn default() -> Self {
+		ChunkOptions {
+			chunk_size: DEFAULT_CHUNK_SIZE,
+			codec: Codec::default(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Codec;
+
+	#[test]
+	fn codecs_roundtrip() {
+		let raw: Vec<u8> = (0..10000u32).map(|i| (i % 7) as u8).collect();
+		for codec in &[Codec::Snappy, Codec::Zlib, Codec::None] {
+			let mut compressed = Vec::new();
+			let len = codec.compress_into(&raw, &mut compressed).unwrap();
+			let mut decompressed = Vec::new();
+			let decompressed_len = codec.decompress_into(&compressed[..len], &mut decompressed).unwrap();
+			assert_eq!(&decompressed[..decompressed_len], &raw[..]);
+
+			assert_eq!(Codec::from_id(codec.id()), Some(*codec));
+			assert_eq!(format!("{}", codec).parse::<Codec>(), Ok(*codec));
+		}
+		assert!(Codec::from_id(3).is_none());
+		assert!("lz4".parse::<Codec>().is_err());
+	}
+}
@@ -0,0 +1,78 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Snapshot-related errors.
+
+use std::fmt;
+
+use ids::BlockID;
+use util::{H256, U256};
+
+/// Snapshot-related errors.
+#[derive(Debug)]
+pub enum Error {
+	/// Snapshots can only be restored into a chain containing just the genesis block.
+	ChainNotEmpty,
+	/// A chunk listed in the manifest could not be found.
+	MissingChunk(H256),
+	/// Chunk contents don't match the hash listed in the manifest. (expected, found)
+	ChunkHashMismatch(H256, H256),
+	/// Block chunk doesn't continue the chain restored so far. (expected parent, actual parent)
+	UnlinkedChunk(H256, H256),
+	/// Block chunk doesn't start at the block following its parent. (expected, found)
+	WrongChunkNumber(u64, u64),
+	/// Total difficulty recorded in a block chunk doesn't match the chain. (expected, found)
+	WrongTotalDifficulty(U256, U256),
+	/// Receipts of a block don't match its header. (expected root, found root)
+	WrongReceiptsRoot(H256, H256),
+	/// Restored state root doesn't match the manifest. (expected, found)
+	WrongStateRoot(H256, H256),
+	/// Restored chain doesn't end with the manifest block. (expected, found)
+	WrongBlockHash(H256, H256),
+	/// Snapshot creation was cancelled.
+	Cancelled,
+	/// Block to take the snapshot at is not known.
+	InvalidStartingBlock(BlockID),
+	/// State at the block to take the snapshot at has been pruned. (block number)
+	MissingState(u64),
+}
+
+impl fmt::Display for Error {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Error::ChainNotEmpty => f.write_str("Snapshot can only be restored into an empty chain."),
+			Error::MissingChunk(ref hash) => f.write_fmt(format_args!("Missing snapshot chunk {}", hash)),
+			Error::ChunkHashMismatch(ref expected, ref found) =>
+				f.write_fmt(format_args!("Snapshot chunk hash mismatch: expected {}, found {}", expected, found)),
+			Error::UnlinkedChunk(ref expected, ref found) =>
+				f.write_fmt(format_args!("Unlinked block chunk: expected parent {}, found {}", expected, found)),
+			Error::WrongChunkNumber(expected, found) =>
+				f.write_fmt(format_args!("Block chunk starts at wrong block: expected #{}, found #{}", expected, found)),
+			Error::WrongTotalDifficulty(ref expected, ref found) =>
+				f.write_fmt(format_args!("Block chunk has wrong parent total difficulty: expected {}, found {}", expected, found)),
+			Error::WrongReceiptsRoot(ref expected, ref found) =>
+				f.write_fmt(format_args!("Block receipts root mismatch: expected {}, found {}", expected, found)),
+			Error::WrongStateRoot(ref expected, ref found) =>
+				f.write_fmt(format_args!("Restored state root mismatch: expected {}, found {}", expected, found)),
+			Error::WrongBlockHash(ref expected, ref found) =>
+				f.write_fmt(format_args!("Restored chain head mismatch: expected {}, found {}", expected, found)),
+			Error::Cancelled => f.write_str("Snapshot creation was cancelled."),
+			Error::InvalidStartingBlock(ref id) => f.write_fmt(format_args!("Unknown block to take snapshot at: {:?}", id)),
+			Error::MissingState(number) =>
+				f.write_fmt(format_args!("State at block #{} is not available. It may have been pruned.", number)),
+		}
+	}
+}
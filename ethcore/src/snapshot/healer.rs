@@ -0,0 +1,324 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Healing of an incomplete state database.
+//!
+//! The healer walks the account trie along with the storage tries and code of all accounts,
+//! depth-first and a bounded number of nodes at a time, noting the nodes missing from the database.
+//! Missing nodes are fetched from peers by hash; once one arrives it's written to the database
+//! and the walk carries on below it. This lets a node recover from a handful of missing nodes,
+//! e.g. after an interrupted snapshot restoration, without restoring the whole state again.
+
+use std::collections::HashMap;
+
+use account_db::{AccountDB, AccountDBMut};
+
+use util::{HashDB, SHA3_EMPTY, SHA3_NULL_RLP};
+use util::hash::{FixedHash, H256};
+use util::nibbleslice::NibbleSlice;
+use util::rlp::{UntrustedRlp, View};
+use util::sha3::Hashable;
+
+// Where a node belongs.
+#[derive(Debug, Clone, PartialEq)]
+enum NodeKind {
+	// node of the account trie, along with the nibbles of the key leading to it.
+	State(Vec<u8>),
+	// node of the storage trie of the account with given address hash.
+	Storage(H256),
+	// code of the account with given address hash.
+	Code(H256),
+}
+
+/// Progress of state healing.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HealingStatus {
+	/// Number of nodes checked so far.
+	pub checked: usize,
+	/// Number of missing nodes written to the database so far.
+	pub healed: usize,
+	/// Number of nodes currently known to be missing.
+	pub missing: usize,
+}
+
+/// Finds the nodes missing from the state with a given root and writes them as they are fed in.
+pub struct StateHealer {
+	root: H256,
+	// nodes left to check, the next one last.
+	pending: Vec<(H256, NodeKind)>,
+	// missing nodes by hash. Identical storage tries of different accounts share nodes.
+	missing: HashMap<H256, Vec<NodeKind>>,
+	checked: usize,
+	healed: usize,
+}
+
+impl StateHealer {
+	/// Create a healer for the state with given root.
+	pub fn new(root: H256) -> Self {
+		StateHealer {
+			root: root.clone(),
+			pending: vec![(root, NodeKind::State(Vec::new()))],
+			missing: HashMap::new(),
+			checked: 0,
+			healed: 0,
+		}
+	}
+
+	/// Root of the state being healed.
+	pub fn root(&self) -> &H256 {
+		&self.root
+	}
+
+	/// Check up to `max` nodes of the state, noting the missing ones.
+	pub fn scan(&mut self, db: &HashDB, max: usize) {
+		for _ in 0..max {
+			let (hash, kind) = match self.pending.pop() {
+				Some(next) => next,
+				None => break,
+			};
+			self.checked += 1;
+
+			let node = match kind {
+				NodeKind::State(_) => db.get(&hash).map(|node| node.to_vec()),
+				NodeKind::Storage(ref account) | NodeKind::Code(ref account) =>
+					AccountDB::from_hash(db, account.clone()).get(&hash).map(|node| node.to_vec()),
+			};
+			match node {
+				Some(node) => self.expand(&node, &kind),
+				None => self.missing.entry(hash).or_insert_with(Vec::new).push(kind),
+			}
+		}
+	}
+
+	/// Hashes of at most `max` missing nodes.
+	pub fn missing(&self, max: usize) -> Vec<H256> {
+		self.missing.keys().take(max).cloned().collect()
+	}
+
+	/// Write a node received from a peer to the database, if it's one of the missing ones.
+	/// Returns `true` if it was.
+	pub fn feed(&mut self, db: &mut HashDB, node: &[u8]) -> bool {
+		let hash = node.sha3();
+		let kinds = match self.missing.remove(&hash) {
+			Some(kinds) => kinds,
+			None => return false,
+		};
+
+		for kind in kinds {
+			match kind {
+				NodeKind::State(_) => db.emplace(hash.clone(), node.to_vec()),
+				NodeKind::Storage(ref account) | NodeKind::Code(ref account) =>
+					AccountDBMut::from_hash(db, account.clone()).emplace(hash.clone(), node.to_vec()),
+			}
+			self.expand(node, &kind);
+		}
+		self.healed += 1;
+		true
+	}
+
+	/// Whether the whole state has been checked and no nodes are missing anymore.
+	pub fn is_complete(&self) -> bool {
+		self.pending.is_empty() && self.missing.is_empty()
+	}
+
+	/// Get the progress of healing.
+	pub fn status(&self) -> HealingStatus {
+		HealingStatus {
+			checked: self.checked,
+			healed: self.healed,
+			missing: self.missing.len(),
+		}
+	}
+
+	// queue the nodes referenced by a node found in the database.
+	fn expand(&mut self, node: &[u8], kind: &NodeKind) {
+		match *kind {
+			NodeKind::State(ref path) => self.expand_trie_node(node, path.clone(), None),
+			NodeKind::Storage(ref account) => self.expand_trie_node(node, Vec::new(), Some(account)),
+			NodeKind::Code(_) => {},
+		}
+	}
+
+	// queue the nodes referenced by a trie node. `account` is `None` for the account trie,
+	// whose leaves are accounts, and the address hash of the owner for storage tries.
+	fn expand_trie_node(&mut self, node: &[u8], mut path: Vec<u8>, account: Option<&H256>) {
+		let rlp = UntrustedRlp::new(node);
+		if !rlp.is_list() {
+			return;
+		}
+
+		match rlp.item_count() {
+			2 => {
+				let encoded = match rlp.at(0) {
+					Ok(encoded) => encoded,
+					Err(_) => return,
+				};
+				let (partial, is_leaf) = match encoded.data() {
+					Ok(data) if !data.is_empty() => NibbleSlice::from_encoded(data),
+					_ => return,
+				};
+				for i in 0..partial.len() {
+					path.push(partial.at(i));
+				}
+
+				match (rlp.at(1), is_leaf, account) {
+					(Ok(value), true, None) => if let Ok(value) = value.data() {
+						self.queue_account(&path, value);
+					},
+					(Ok(child), false, _) => self.queue_child(&child, path, account),
+					_ => {},
+				}
+			},
+			17 => {
+				for i in 0..16 {
+					if let Ok(child) = rlp.at(i) {
+						let mut child_path = path.clone();
+						child_path.push(i as u8);
+						self.queue_child(&child, child_path, account);
+					}
+				}
+			},
+			_ => {},
+		}
+	}
+
+	// queue a child reference: either the hash of a node or a small node inlined in its parent.
+	fn queue_child(&mut self, child: &UntrustedRlp, path: Vec<u8>, account: Option<&H256>) {
+		if child.is_empty() {
+			return;
+		}
+
+		if child.is_list() {
+			self.expand_trie_node(child.as_raw(), path, account);
+		} else if let Ok(hash) = child.as_val::<H256>() {
+			let kind = match account {
+				Some(account) => NodeKind::Storage(account.clone()),
+				None => NodeKind::State(path),
+			};
+			self.pending.push((hash, kind));
+		}
+	}
+
+	// queue the storage trie and code of an account found at the given path of the account trie.
+	fn queue_account(&mut self, path: &[u8], account_rlp: &[u8]) {
+		if path.len() != 64 {
+			return;
+		}
+		let mut key = [0u8; 32];
+		for (i, byte) in key.iter_mut().enumerate() {
+			*byte = (path[2 * i] << 4) | path[2 * i + 1];
+		}
+		let account_hash = H256::from_slice(&key);
+
+		let account = UntrustedRlp::new(account_rlp);
+		if let Ok(code_hash) = account.val_at::<H256>(3) {
+			if code_hash != SHA3_EMPTY {
+				self.pending.push((code_hash, NodeKind::Code(account_hash.clone())));
+			}
+		}
+		if let Ok(storage_root) = account.val_at::<H256>(2) {
+			if storage_root != SHA3_NULL_RLP {
+				self.pending.push((storage_root, NodeKind::Storage(account_hash)));
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+
+	use account_db::AccountDBMut;
+
+	use util::{Bytes, HashDB, MemoryDB, SHA3_EMPTY, SHA3_NULL_RLP, U256};
+	use util::hash::{Address, FixedHash, H256};
+	use util::rlp::{RlpStream, Stream};
+	use util::sha3::Hashable;
+	use util::trie::{Alphabet, StandardMap, SecTrieDBMut, TrieMut, ValueMode};
+
+	use super::StateHealer;
+
+	#[test]
+	fn heals_missing_nodes() {
+		let mut source = MemoryDB::new();
+		let addr = Address::random();
+		let code = vec![0x60, 0x00, 0x60, 0x00, 0xf3];
+
+		let mut storage_root = H256::new();
+		let code_hash = {
+			let mut db = AccountDBMut::new(&mut source, &addr);
+			{
+				let mut trie = SecTrieDBMut::new(&mut db, &mut storage_root);
+				let map = StandardMap {
+					alphabet: Alphabet::All,
+					min_key: 6,
+					journal_key: 6,
+					value_mode: ValueMode::Random,
+					count: 50,
+				};
+				for (k, v) in map.make() {
+					trie.insert(&k, &v);
+				}
+			}
+			db.insert(&code)
+		};
+
+		let mut state_root = H256::new();
+		{
+			let mut stream = RlpStream::new_list(4);
+			stream.append(&U256::from(1)).append(&U256::from(1000)).append(&storage_root).append(&code_hash);
+
+			let mut trie = SecTrieDBMut::new(&mut source, &mut state_root);
+			trie.insert(&addr, &stream.out());
+
+			let mut stream = RlpStream::new_list(4);
+			stream.append(&U256::from(0)).append(&U256::from(1)).append(&SHA3_NULL_RLP).append(&SHA3_EMPTY);
+			trie.insert(&Address::random(), &stream.out());
+		}
+
+		// copy everything but the state root, the storage root and the code.
+		let skipped = vec![state_root.clone(), storage_root.clone(), code_hash.clone()];
+		let mut nodes: HashMap<H256, Bytes> = HashMap::new();
+		let mut target = MemoryDB::new();
+		let source_keys: Vec<H256> = source.keys().keys().cloned().collect();
+		for (key, (value, _)) in source.drain() {
+			let hash = value.sha3();
+			if !skipped.contains(&hash) {
+				target.emplace(key, value.clone());
+			}
+			nodes.insert(hash, value);
+		}
+
+		let mut healer = StateHealer::new(state_root);
+		for _ in 0..100 {
+			healer.scan(&target, 10);
+			for hash in healer.missing(2) {
+				assert!(healer.feed(&mut target, &nodes[&hash]));
+			}
+			if healer.is_complete() { break }
+		}
+
+		assert!(healer.is_complete());
+		assert_eq!(healer.status().healed, 3);
+		assert!(!healer.feed(&mut target, &nodes[&state_root]));
+
+		let mut target_keys: Vec<H256> = target.keys().keys().cloned().collect();
+		let mut source_keys = source_keys;
+		target_keys.sort();
+		source_keys.sort();
+		assert_eq!(target_keys, source_keys);
+	}
+}
@@ -0,0 +1,283 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Snapshot output.
+//!
+//! Chunks can be written into a directory, one file per chunk, or packed one after
+//! another into any `io::Write`: a single file, a socket or stdout.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+
+use util::hash::H256;
+use util::rlp::{RlpStream, Stream};
+use util::Hashable;
+
+use super::{ManifestData, ResumeState};
+
+const RESUME_FILE: &'static str = "RESUME";
+
+/// Something which can write snapshots.
+/// Writing the same chunk multiple times will lead to implementation-defined behavior,
+/// and is not advised.
+pub trait SnapshotWriter {
+	/// Write a compressed state chunk.
+	fn write_state_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()>;
+
+	/// Write a compressed block chunk.
+	fn write_block_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()>;
+
+	/// Complete writing. The manifest's chunk lists must be consistent
+	/// with the chunks written.
+	fn finish(self, manifest: ManifestData) -> io::Result<()> where Self: Sized;
+
+	/// Record the progress of the snapshot after chunks have been written.
+	/// Writers which keep their chunks across runs may persist it and hand it back from
+	/// `resume_state`, so that an interrupted snapshot can be resumed.
+	fn checkpoint(&mut self, _state: &ResumeState) -> io::Result<()> { Ok(()) }
+
+	/// Progress of an unfinished snapshot written earlier, if any.
+	fn resume_state(&self) -> Option<ResumeState> { None }
+}
+
+/// A writer which writes each chunk into a separate file named by the chunk hash,
+/// and the manifest into a file called `MANIFEST`, all in the same directory.
+///
+/// Until the manifest is written, the progress of the snapshot is kept in a file called
+/// `RESUME`, so a snapshot interrupted by a crash can be resumed by a writer for the same directory.
+pub struct LooseWriter {
+	dir: PathBuf,
+}
+
+impl LooseWriter {
+	/// Create a new writer, creating the directory if it doesn't exist yet.
+	pub fn new(path: PathBuf) -> io::Result<Self> {
+		try!(fs::create_dir_all(&path));
+		Ok(LooseWriter {
+			dir: path,
+		})
+	}
+
+	fn write_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()> {
+		let mut file = try!(File::create(self.dir.join(hash.hex())));
+		file.write_all(chunk)
+	}
+}
+
+impl SnapshotWriter for LooseWriter {
+	fn write_state_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()> {
+		self.write_chunk(hash, chunk)
+	}
+
+	fn write_block_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()> {
+		self.write_chunk(hash, chunk)
+	}
+
+	fn finish(self, manifest: ManifestData) -> io::Result<()> {
+		{
+			let mut file = try!(File::create(self.dir.join("MANIFEST")));
+			try!(file.write_all(&manifest.into_rlp()));
+		}
+		match fs::remove_file(self.dir.join(RESUME_FILE)) {
+			Err(ref e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+			other => other,
+		}
+	}
+
+	fn checkpoint(&mut self, state: &ResumeState) -> io::Result<()> {
+		// write aside and rename, so a crash never leaves a partial file behind.
+		let temp = self.dir.join(format!("{}.tmp", RESUME_FILE));
+		{
+			let mut file = try!(File::create(&temp));
+			try!(file.write_all(&state.to_rlp()));
+		}
+		fs::rename(temp, self.dir.join(RESUME_FILE))
+	}
+
+	fn resume_state(&self) -> Option<ResumeState> {
+		let mut raw = Vec::new();
+		match File::open(self.dir.join(RESUME_FILE)).and_then(|mut file| file.read_to_end(&mut raw)) {
+			Ok(_) => ResumeState::from_rlp(&raw).ok(),
+			Err(_) => None,
+		}
+	}
+}
+
+/// Location of a chunk within a packed snapshot.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkInfo {
+	/// Hash of the chunk.
+	pub hash: H256,
+	/// Length of the chunk in bytes.
+	pub len: u64,
+	/// Offset of the chunk from the start of the snapshot.
+	pub offset: u64,
+}
+
+impl ChunkInfo {
+	fn append_to(&self, stream: &mut RlpStream) {
+		stream.begin_list(3).append(&self.hash).append(&self.len).append(&self.offset);
+	}
+}
+
+/// A writer which packs all chunks into a single stream, one after another, in the order
+/// they were written. The chunks are followed by an RLP list of
+/// `[state chunk infos, block chunk infos, manifest]`, each chunk info being `[hash, len, offset]`,
+/// and finally by the offset of that list as 8 little-endian bytes.
+///
+/// Nothing is ever read back, so this works with any `io::Write`.
+pub struct PackedWriter<W: Write> {
+	out: W,
+	state_chunks: Vec<ChunkInfo>,
+	block_chunks: Vec<ChunkInfo>,
+	cur_len: u64,
+}
+
+impl<W: Write> PackedWriter<W> {
+	/// Create a new writer packing the snapshot into `out`.
+	pub fn new(out: W) -> Self {
+		PackedWriter {
+			out: out,
+			state_chunks: Vec::new(),
+			block_chunks: Vec::new(),
+			cur_len: 0,
+		}
+	}
+
+	fn write_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<ChunkInfo> {
+		try!(self.out.write_all(chunk));
+		let info = ChunkInfo {
+			hash: hash,
+			len: chunk.len() as u64,
+			offset: self.cur_len,
+		};
+		self.cur_len += chunk.len() as u64;
+		Ok(info)
+	}
+}
+
+impl<W: Write> SnapshotWriter for PackedWriter<W> {
+	fn write_state_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()> {
+		let info = try!(self.write_chunk(hash, chunk));
+		self.state_chunks.push(info);
+		Ok(())
+	}
+
+	fn write_block_chunk(&mut self, hash: H256, chunk: &[u8]) -> io::Result<()> {
+		let info = try!(self.write_chunk(hash, chunk));
+		self.block_chunks.push(info);
+		Ok(())
+	}
+
+	fn finish(mut self, manifest: ManifestData) -> io::Result<()> {
+		let mut stream = RlpStream::new_list(3);
+		stream.begin_list(self.state_chunks.len());
+		for info in &self.state_chunks {
+			info.append_to(&mut stream);
+		}
+		stream.begin_list(self.block_chunks.len());
+		for info in &self.block_chunks {
+			info.append_to(&mut stream);
+		}
+		stream.append(&manifest.into_rlp());
+
+		let offset = self.cur_len;
+		let mut offset_bytes = [0u8; 8];
+		for (i, byte) in offset_bytes.iter_mut().enumerate() {
+			*byte = (offset >> (8 * i)) as u8;
+		}
+
+		try!(self.out.write_all(&stream.out()));
+		try!(self.out.write_all(&offset_bytes));
+		self.out.flush()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::hash::H256;
+	use util::rlp::{UntrustedRlp, View};
+	use util::Hashable;
+	use devtools::RandomTempPath;
+	use snapshot::{Codec, ManifestData, ResumeState, MANIFEST_VERSION};
+	use super::{SnapshotWriter, LooseWriter, PackedWriter};
+
+	fn manifest() -> ManifestData {
+		ManifestData {
+			version: MANIFEST_VERSION,
+			codec: Codec::Snappy,
+			state_hashes: vec![],
+			block_hashes: vec![],
+			state_root: H256::from(1),
+			block_number: 2,
+			block_hash: H256::from(3),
+		}
+	}
+
+	#[test]
+	fn loose_writer_keeps_progress_until_finished() {
+		let path = RandomTempPath::new();
+		let mut writer = LooseWriter::new(path.as_path().to_owned()).unwrap();
+		assert_eq!(writer.resume_state(), None);
+
+		let mut state = ResumeState::new(H256::from(3), Default::default());
+		state.blocks.hashes.push(H256::from(4));
+		writer.checkpoint(&state).unwrap();
+		assert_eq!(writer.resume_state(), Some(state.clone()));
+		assert_eq!(LooseWriter::new(path.as_path().to_owned()).unwrap().resume_state(), Some(state));
+
+		writer.finish(manifest()).unwrap();
+		assert_eq!(LooseWriter::new(path.as_path().to_owned()).unwrap().resume_state(), None);
+	}
+
+	#[test]
+	fn packs_chunks_with_index() {
+		let state_chunk = b"state chunk".to_vec();
+		let block_chunk = b"block chunk data".to_vec();
+
+		let mut out = Vec::new();
+		{
+			let mut writer = PackedWriter::new(&mut out);
+			writer.write_block_chunk(block_chunk.sha3(), &block_chunk).unwrap();
+			writer.write_state_chunk(state_chunk.sha3(), &state_chunk).unwrap();
+			writer.finish(ManifestData {
+				version: MANIFEST_VERSION,
+				codec: Codec::Snappy,
+				state_hashes: vec![state_chunk.sha3()],
+				block_hashes: vec![block_chunk.sha3()],
+				state_root: H256::from(1),
+				block_number: 2,
+				block_hash: H256::from(3),
+			}).unwrap();
+		}
+
+		let (rest, offset_bytes) = out.split_at(out.len() - 8);
+		let offset = offset_bytes.iter().rev().fold(0u64, |acc, &b| (acc << 8) | b as u64) as usize;
+		assert_eq!(offset, block_chunk.len() + state_chunk.len());
+
+		let index = UntrustedRlp::new(&rest[offset..]);
+		let state_info = index.at(0).unwrap().at(0).unwrap();
+		let state_offset: u64 = state_info.val_at(2).unwrap();
+		let state_len: u64 = state_info.val_at(1).unwrap();
+		assert_eq!(state_info.val_at::<H256>(0).unwrap(), state_chunk.sha3());
+		assert_eq!(&rest[state_offset as usize..(state_offset + state_len) as usize], &state_chunk[..]);
+
+		let manifest_rlp: Vec<u8> = index.val_at(2).unwrap();
+		let manifest = ManifestData::from_rlp(&manifest_rlp).unwrap();
+		assert_eq!(manifest.block_number, 2);
+	}
+}
@@ -14,191 +14,330 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-//! Snapshot creation helpers.
+//! Snapshot creation and restoration helpers.
 
-use std::collections::VecDeque;
-use std::fs::{create_dir_all, File};
-use std::io::Write;
-use std::path::{Path, PathBuf};
+use std::cmp::{max, min};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering as AtomicOrdering};
 
 use account_db::{AccountDB, AccountDBMut};
-use client::BlockChainClient;
+use basic_types::Seal;
+use blockchain::{BlockChain, BlockProvider};
 use error::Error;
 use ids::BlockID;
-use views::{BlockView, HeaderView};
+use receipt::Receipt;
+use views::BlockView;
 
-use util::{Bytes, Hashable, HashDB, JournalDB, snappy, TrieDB, TrieDBMut, TrieMut};
+use util::{Bytes, Hashable, HashDB, JournalDB, Mutex, TrieDB, TrieDBMut, TrieMut, U256, ordered_trie_root};
 use util::hash::{FixedHash, H256};
-use util::rlp::{DecoderError, RlpStream, Stream, UntrustedRlp, View};
+use util::rlp::{DecoderError, Encodable, RlpStream, Stream, UntrustedRlp, View};
 
 use self::account::Account;
 use self::block::AbridgedBlock;
+use self::io::SnapshotWriter;
 
 use crossbeam::{scope, ScopedJoinHandle};
 
+pub use self::error::Error as SnapshotError;
+pub use self::periodic::Config;
+pub use self::codec::{ChunkOptions, Codec};
+pub use self::healer::{HealingStatus, StateHealer};
+pub use self::resume::{ChunkerProgress, ResumeState};
+
 mod account;
 mod block;
+mod codec;
+mod error;
+mod healer;
+mod resume;
+pub mod io;
+pub mod periodic;
+
+// Number of blocks read from the database at once when chunking blocks.
+const BLOCK_READ_BATCH: usize = 64;
+
+// Number of account trie partitions chunked in parallel, one for each first nibble of the address hash.
+const STATE_PARTITIONS: u8 = 16;
+
+/// A progress indicator for snapshot creation, shared with the chunkers.
+#[derive(Debug, Default)]
+pub struct Progress {
+	accounts: AtomicUsize,
+	blocks: AtomicUsize,
+	size: AtomicUsize,
+	done: AtomicBool,
+	cancelled: AtomicBool,
+}
+
+impl Progress {
+	/// Create a new progress indicator.
+	pub fn new() -> Self {
+		Progress::default()
+	}
+
+	/// Get the number of accounts snapshotted thus far.
+	pub fn accounts(&self) -> usize { self.accounts.load(AtomicOrdering::Relaxed) }
+
+	/// Get the number of blocks snapshotted thus far.
+	pub fn blocks(&self) -> usize { self.blocks.load(AtomicOrdering::Relaxed) }
+
+	/// Get the written size of the snapshot in bytes.
+	pub fn size(&self) -> usize { self.size.load(AtomicOrdering::Relaxed) }
+
+	/// Whether the snapshot is complete.
+	pub fn done(&self) -> bool { self.done.load(AtomicOrdering::SeqCst) }
+
+	/// Ask the chunkers to stop. The snapshot will fail with `SnapshotError::Cancelled`.
+	pub fn cancel(&self) { self.cancelled.store(true, AtomicOrdering::SeqCst) }
 
-// Try to have chunks be around 16MB (before compression)
-const PREFERRED_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+	/// Whether the snapshot has been cancelled.
+	pub fn cancelled(&self) -> bool { self.cancelled.load(AtomicOrdering::SeqCst) }
 
-/// Take a snapshot using the given client and database, writing into `path`.
-pub fn take_snapshot(client: &BlockChainClient, mut path: PathBuf, state_db: &HashDB) -> Result<(), Error> {
-	let chain_info = client.chain_info();
+	fn check_cancelled(&self) -> Result<(), Error> {
+		match self.cancelled() {
+			true => Err(SnapshotError::Cancelled.into()),
+			false => Ok(()),
+		}
+	}
+}
 
-	let genesis_hash = chain_info.genesis_hash;
-	let best_header_raw = client.best_block_header();
-	let best_header = HeaderView::new(&best_header_raw);
-	let state_root = best_header.state_root();
+/// Take a snapshot of the chain up to the given block and of the state at that block,
+/// handing the chunks and the manifest to `writer`. The state at that block must not have
+/// been pruned from the database yet.
+///
+/// If the writer holds the progress of an unfinished snapshot of the same block,
+/// the chunks already written are kept and chunking picks up where it stopped.
+pub fn take_snapshot<W: SnapshotWriter + Send>(chain: &BlockChain, block_at: H256, state_db: &JournalDB, writer: W, options: &ChunkOptions, progress: &Progress) -> Result<(), Error> {
+	let genesis_hash = chain.genesis_hash();
+	let header = match chain.block_header(&block_at) {
+		Some(header) => header,
+		None => return Err(SnapshotError::InvalidStartingBlock(BlockID::Hash(block_at)).into()),
+	};
+	let state_root = header.state_root().clone();
 
-	trace!(target: "snapshot", "Taking snapshot starting at block {}", best_header.number());
+	if !state_db.contains(&state_root) {
+		return Err(SnapshotError::MissingState(header.number()).into());
+	}
 
-	let _ = create_dir_all(&path);
+	trace!(target: "snapshot", "Taking snapshot starting at block {}", header.number());
+
+	// chunks of an interrupted snapshot can only be reused if they were produced the same way.
+	let resume = match writer.resume_state() {
+		Some(state) => match state.block_hash == block_at && &state.options == options {
+			true => {
+				trace!(target: "snapshot", "resuming snapshot: {} block chunks and {} state partitions done",
+					state.blocks.hashes.len(), state.state.iter().filter(|partition| partition.done).count());
+				state
+			},
+			false => ResumeState::new(block_at.clone(), *options),
+		},
+		None => ResumeState::new(block_at.clone(), *options),
+	};
 
-	let state_hashes = try!(chunk_state(state_db, &state_root, &path));
-	let block_hashes = try!(chunk_blocks(client, best_header.hash(), genesis_hash, &path));
+	let writer = Mutex::new(writer);
+	let resume = Mutex::new(resume);
+	let state_hashes = try!(chunk_state(state_db, &state_root, &writer, &resume, options, progress));
+	let block_hashes = try!(chunk_blocks(chain, block_at.clone(), genesis_hash, &writer, &resume, options, progress));
 
 	trace!(target: "snapshot", "produced {} state chunks and {} block chunks.", state_hashes.len(), block_hashes.len());
 
 	let manifest_data = ManifestData {
+		version: MANIFEST_VERSION,
+		codec: options.codec,
 		state_hashes: state_hashes,
 		block_hashes: block_hashes,
 		state_root: state_root,
-		block_number: chain_info.best_block_number,
-		block_hash: chain_info.best_block_hash,
+		block_number: header.number(),
+		block_hash: block_at,
 	};
 
-	path.push("MANIFEST");
-
-	let mut manifest_file = try!(File::create(&path));
-
-	try!(manifest_file.write_all(&manifest_data.into_rlp()));
+	try!(writer.into_inner().finish(manifest_data));
+	progress.done.store(true, AtomicOrdering::SeqCst);
 
 	Ok(())
 }
 
 // shared portion of write_chunk
+// compresses the data and hands it to `write`.
 // returns either a (hash, compressed_size) pair or an io error.
-fn write_chunk(raw_data: &[u8], compression_buffer: &mut Vec<u8>, path: &Path) -> Result<(H256, usize), Error> {
-	let compressed_size = snappy::compress_into(raw_data, compression_buffer);
+fn write_chunk<F>(raw_data: &[u8], codec: Codec, compression_buffer: &mut Vec<u8>, write: F) -> Result<(H256, usize), Error>
+	where F: FnOnce(H256, &[u8]) -> ::std::io::Result<()>
+{
+	let compressed_size = try!(codec.compress_into(raw_data, compression_buffer));
 	let compressed = &compression_buffer[..compressed_size];
 	let hash = compressed.sha3();
 
-	let mut file_path = path.to_owned();
-	file_path.push(hash.hex());
-
-	let mut file = try!(File::create(file_path));
-	try!(file.write_all(compressed));
+	try!(write(hash.clone(), compressed));
 
 	Ok((hash, compressed_size))
 }
 
+// update the progress of the snapshot and hand it to the writer.
+fn checkpoint<W: SnapshotWriter, F>(resume: &Mutex<ResumeState>, writer: &Mutex<W>, update: F) -> Result<(), Error>
+	where F: FnOnce(&mut ResumeState)
+{
+	let mut resume = resume.lock();
+	update(&mut resume);
+	try!(writer.lock().checkpoint(&resume));
+	Ok(())
+}
+
 /// Used to build block chunks.
-struct BlockChunker<'a> {
-	client: &'a BlockChainClient,
-	// block, receipt rlp pairs.
-	rlps: VecDeque<Bytes>,
+///
+/// Blocks are read from the database in batches, walking backwards from the starting block.
+/// The encoded (block, receipts) pairs of the chunk being built are kept back to back in a
+/// single buffer, which together with the chunk encoding and compression buffers is
+/// allocated once and reused for every chunk, so memory use stays bounded by a few
+/// multiples of the configured chunk size however long the chain is.
+struct BlockChunker<'a, W: 'a> {
+	chain: &'a BlockChain,
+	// abridged block, receipts rlp pairs of the current chunk, newest first.
+	pairs: Vec<u8>,
+	// end offsets of the pairs within `pairs`.
+	pair_ends: Vec<usize>,
 	current_hash: H256,
 	hashes: Vec<H256>,
-	snappy_buffer: Vec<u8>,
+	chunk_stream: RlpStream,
+	compression_buffer: Vec<u8>,
+	options: ChunkOptions,
+	writer: &'a Mutex<W>,
+	resume: &'a Mutex<ResumeState>,
+	progress: &'a Progress,
 }
 
-impl<'a> BlockChunker<'a> {
+impl<'a, W: SnapshotWriter> BlockChunker<'a, W> {
 	// Repeatedly fill the buffers and writes out chunks, moving backwards from starting block hash.
 	// Loops until we reach the genesis, and writes out the remainder.
-	fn chunk_all(&mut self, genesis_hash: H256, path: &Path) -> Result<(), Error> {
-		let mut loaded_size = 0;
-
+	fn chunk_all(&mut self, genesis_hash: H256) -> Result<(), Error> {
 		while self.current_hash != genesis_hash {
-			let block = self.client.block(BlockID::Hash(self.current_hash))
-				.expect("started from the head of chain and walking backwards; client stores full chain; qed");
-			let view = BlockView::new(&block);
-			let abridged_rlp = AbridgedBlock::from_block_view(&view).into_inner();
-
-			let receipts = self.client.block_receipts(&self.current_hash)
-				.expect("started from head of chain and walking backwards; client stores full chain; qed");
-
-			let pair = {
-				let mut pair_stream = RlpStream::new_list(2);
-				pair_stream.append(&abridged_rlp).append(&receipts);
-				pair_stream.out()
-			};
-
-			let new_loaded_size = loaded_size + pair.len();
-
-			// cut off the chunk if too large
-			if new_loaded_size > PREFERRED_CHUNK_SIZE {
-				let header = view.header_view();
-				try!(self.write_chunk(header.parent_hash(), header.number(), path));
-				loaded_size = pair.len();
-			} else {
-				loaded_size = new_loaded_size;
+			try!(self.progress.check_cancelled());
+			let batch = self.chain.ancestry_with_receipts(self.current_hash.clone(), BLOCK_READ_BATCH);
+			if batch.is_empty() {
+				panic!("started from the head of chain and walking backwards; client stores full chain; qed");
 			}
 
-			self.rlps.push_front(pair);
-			self.current_hash = view.header_view().parent_hash();
+			for (block, receipts) in batch {
+				if self.current_hash == genesis_hash {
+					break;
+				}
+
+				let view = BlockView::new(&block);
+				let abridged_rlp = AbridgedBlock::from_block_view(&view).into_inner();
+				let pair = {
+					let mut pair_stream = RlpStream::new_list(2);
+					pair_stream.append(&abridged_rlp).append(&receipts);
+					pair_stream.out()
+				};
+
+				// cut off the chunk if too large
+				if !self.pair_ends.is_empty() && self.pairs.len() + pair.len() > self.options.chunk_size {
+					// the buffered blocks are the descendants of the current one.
+					let header = view.header_view();
+					try!(self.write_chunk(header.sha3(), header.number() + 1));
+				}
+
+				self.pairs.extend_from_slice(&pair);
+				self.pair_ends.push(self.pairs.len());
+				self.current_hash = view.header_view().parent_hash();
+				self.progress.blocks.fetch_add(1, AtomicOrdering::Relaxed);
+			}
 		}
 
-		if loaded_size != 0 {
+		if !self.pair_ends.is_empty() {
 			// we don't store the genesis block, so once we get to this point,
 			// the "first" block will be number 1.
-			try!(self.write_chunk(genesis_hash, 1, path));
+			try!(self.write_chunk(genesis_hash, 1));
 		}
 
-		Ok(())
+		checkpoint(self.resume, self.writer, |state| state.blocks.done = true)
 	}
 
-	// write out the data in the buffers to a chunk on disk
-	fn write_chunk(&mut self, parent_hash: H256, number: u64, path: &Path) -> Result<(), Error> {
-		trace!(target: "snapshot", "prepared block chunk with {} blocks", self.rlps.len());
-		let mut rlp_stream = RlpStream::new_list(self.rlps.len() + 2);
-		rlp_stream.append(&parent_hash).append(&number);
-		for pair in self.rlps.drain(..) {
-			rlp_stream.append_raw(&pair, 1);
+	// write out the data in the buffers to a chunk
+	fn write_chunk(&mut self, parent_hash: H256, number: u64) -> Result<(), Error> {
+		trace!(target: "snapshot", "prepared block chunk with {} blocks", self.pair_ends.len());
+		self.chunk_stream.clear();
+		let parent_td = self.chain.block_details(&parent_hash)
+			.expect("parent is an ancestor of the block chunking started at; client stores full chain; qed")
+			.total_difficulty;
+		self.chunk_stream.begin_list(self.pair_ends.len() + 3);
+		self.chunk_stream.append(&parent_hash).append(&number).append(&parent_td);
+		// pairs are buffered newest first, but chunks list them oldest first.
+		for i in (0..self.pair_ends.len()).rev() {
+			let start = if i == 0 { 0 } else { self.pair_ends[i - 1] };
+			self.chunk_stream.append_raw(&self.pairs[start..self.pair_ends[i]], 1);
 		}
+		self.pairs.clear();
+		self.pair_ends.clear();
 
-		let raw_data = rlp_stream.out();
-		let (hash, size) = try!(write_chunk(&raw_data, &mut self.snappy_buffer, path));
+		let writer = self.writer;
+		let raw_data = self.chunk_stream.as_raw();
+		let (hash, size) = try!(write_chunk(raw_data, self.options.codec, &mut self.compression_buffer, |hash, chunk| writer.lock().write_block_chunk(hash, chunk)));
 		trace!(target: "snapshot", "wrote block chunk. hash: {}, size: {}, uncompressed size: {}", hash.hex(), size, raw_data.len());
+		self.progress.size.fetch_add(size, AtomicOrdering::Relaxed);
 
-		self.hashes.push(hash);
-		Ok(())
+		self.hashes.push(hash.clone());
+		// the chunks written so far hold all descendants of the parent.
+		checkpoint(self.resume, writer, |state| {
+			state.blocks.hashes.push(hash);
+			state.blocks.resume_from = Some(parent_hash);
+		})
 	}
 }
 
-/// Create and write out all block chunks to disk, returning a vector of all
+/// Create and write out all block chunks, returning a vector of all
 /// the hashes of block chunks created.
 ///
-/// The path parameter is the directory to store the block chunks in.
-/// This function assumes the directory exists already.
-pub fn chunk_blocks(client: &BlockChainClient, best_block_hash: H256, genesis_hash: H256, path: &Path) -> Result<Vec<H256>, Error> {
+/// Continues from the progress recorded in `resume`, if any.
+pub fn chunk_blocks<W: SnapshotWriter>(chain: &BlockChain, best_block_hash: H256, genesis_hash: H256, writer: &Mutex<W>, resume: &Mutex<ResumeState>, options: &ChunkOptions, progress: &Progress) -> Result<Vec<H256>, Error> {
+	let (current_hash, hashes) = {
+		let resume = resume.lock();
+		if resume.blocks.done {
+			return Ok(resume.blocks.hashes.clone());
+		}
+		(resume.blocks.resume_from.clone().unwrap_or(best_block_hash), resume.blocks.hashes.clone())
+	};
+
 	let mut chunker = BlockChunker {
-		client: client,
-		rlps: VecDeque::new(),
-		current_hash: best_block_hash,
-		hashes: Vec::new(),
-		snappy_buffer: vec![0; snappy::max_compressed_len(PREFERRED_CHUNK_SIZE)],
+		chain: chain,
+		pairs: Vec::with_capacity(options.chunk_size),
+		pair_ends: Vec::new(),
+		current_hash: current_hash,
+		hashes: hashes,
+		chunk_stream: RlpStream::new(),
+		compression_buffer: Vec::new(),
+		options: *options,
+		writer: writer,
+		resume: resume,
+		progress: progress,
 	};
 
-	try!(chunker.chunk_all(genesis_hash, path));
+	try!(chunker.chunk_all(genesis_hash));
 
 	Ok(chunker.hashes)
 }
 
 /// State trie chunker.
-struct StateChunker<'a> {
+struct StateChunker<'a, W: 'a> {
+	partition: usize,
 	hashes: Vec<H256>,
 	rlps: Vec<Bytes>,
+	// address hash of the last account pushed.
+	last_key: H256,
 	cur_size: usize,
-	snapshot_path: &'a Path,
-	snappy_buffer: Vec<u8>,
+	writer: &'a Mutex<W>,
+	resume: &'a Mutex<ResumeState>,
+	compression_buffer: Vec<u8>,
+	options: ChunkOptions,
+	progress: &'a Progress,
 }
 
-impl<'a> StateChunker<'a> {
+impl<'a, W: SnapshotWriter> StateChunker<'a, W> {
 	// Push a key, value pair to be encoded.
 	//
 	// If the buffer is greater than the desired chunk size,
-	// this will write out the data to disk.
+	// this will write out the data.
 	fn push(&mut self, account_hash: Bytes, data: Bytes) -> Result<(), Error> {
 		let pair = {
 			let mut stream = RlpStream::new_list(2);
@@ -206,17 +345,19 @@ impl<'a> StateChunker<'a> {
 			stream.out()
 		};
 
-		if self.cur_size + pair.len() >= PREFERRED_CHUNK_SIZE {
+		if self.cur_size + pair.len() >= self.options.chunk_size {
 			try!(self.write_chunk());
 		}
 
 		self.cur_size += pair.len();
 		self.rlps.push(pair);
+		self.last_key = H256::from_slice(&account_hash);
+		self.progress.accounts.fetch_add(1, AtomicOrdering::Relaxed);
 
 		Ok(())
 	}
 
-	// Write out the buffer to disk, pushing the created chunk's hash to
+	// Write out the buffer, pushing the created chunk's hash to
 	// the list.
 	fn write_chunk(&mut self) -> Result<(), Error> {
 		let mut stream = RlpStream::new_list(self.rlps.len());
@@ -225,36 +366,109 @@ impl<'a> StateChunker<'a> {
 		}
 
 		let raw_data = stream.out();
-		let (hash, compressed_size) = try!(write_chunk(&raw_data, &mut self.snappy_buffer, self.snapshot_path));
+		let writer = self.writer;
+		let (hash, compressed_size) = try!(write_chunk(&raw_data, self.options.codec, &mut self.compression_buffer, |hash, chunk| writer.lock().write_state_chunk(hash, chunk)));
 		trace!(target: "snapshot", "wrote state chunk. size: {}, uncompressed size: {}", compressed_size, raw_data.len());
+		self.progress.size.fetch_add(compressed_size, AtomicOrdering::Relaxed);
 
-		self.hashes.push(hash);
+		self.hashes.push(hash.clone());
 		self.cur_size = 0;
 
-		Ok(())
+		let (partition, last_key) = (self.partition, self.last_key.clone());
+		checkpoint(self.resume, writer, |state| {
+			state.state[partition].hashes.push(hash);
+			state.state[partition].resume_from = Some(last_key);
+		})
 	}
 }
 
 /// Walk the given state database starting from the given root,
 /// creating chunks and writing them out.
 ///
+/// The account trie is split into partitions by the first nibble of the address hash,
+/// which are chunked in parallel. The number of partitions doesn't depend on the number
+/// of threads, so the same state always produces the same chunks.
+///
 /// Returns a list of hashes of chunks created, or any error it may
 /// have encountered.
-pub fn chunk_state(db: &HashDB, root: &H256, path: &Path) -> Result<Vec<H256>, Error> {
-	let account_view = try!(TrieDB::new(db, &root));
+/// Partitions already chunked according to `resume` are skipped, and the others continue
+/// after the last account written.
+pub fn chunk_state<W: SnapshotWriter + Send>(db: &JournalDB, root: &H256, writer: &Mutex<W>, resume: &Mutex<ResumeState>, options: &ChunkOptions, progress: &Progress) -> Result<Vec<H256>, Error> {
+	// fail early if the state root is missing.
+	try!(TrieDB::new(db.as_hashdb(), root));
+
+	let next_partition = AtomicUsize::new(0);
+	let threads = min(::num_cpus::get(), STATE_PARTITIONS as usize);
+
+	trace!(target: "snapshot", "beginning state chunking with {} threads", threads);
+
+	let mut partitions: Vec<(u8, Vec<H256>)> = try!(scope(|scope| {
+		let mut handles = Vec::new();
+		for _ in 0..threads {
+			let next_partition = &next_partition;
+			let handle: ScopedJoinHandle<Result<Vec<(u8, Vec<H256>)>, Error>> = scope.spawn(move || {
+				let mut done = Vec::new();
+				loop {
+					let partition = next_partition.fetch_add(1, AtomicOrdering::SeqCst);
+					if partition >= STATE_PARTITIONS as usize {
+						break;
+					}
+					let partition = partition as u8;
+					done.push((partition, try!(chunk_state_partition(db, root, partition, writer, resume, options, progress))));
+				}
+				Ok(done)
+			});
+
+			handles.push(handle);
+		}
+
+		let mut partitions = Vec::new();
+		for handle in handles {
+			partitions.extend(try!(handle.join()));
+		}
+
+		Ok::<_, Error>(partitions)
+	}));
+
+	partitions.sort_by_key(|&(partition, _)| partition);
+	Ok(partitions.into_iter().flat_map(|(_, hashes)| hashes).collect())
+}
+
+// chunk the accounts whose address hash starts with the given nibble.
+fn chunk_state_partition<W: SnapshotWriter>(db: &JournalDB, root: &H256, partition: u8, writer: &Mutex<W>, resume: &Mutex<ResumeState>, options: &ChunkOptions, progress: &Progress) -> Result<Vec<H256>, Error> {
+	let (resume_from, hashes) = {
+		let resume = resume.lock();
+		let partition_state = &resume.state[partition as usize];
+		if partition_state.done {
+			return Ok(partition_state.hashes.clone());
+		}
+		(partition_state.resume_from.clone(), partition_state.hashes.clone())
+	};
+
+	let db = db.as_hashdb();
+	let account_view = try!(TrieDB::new(db, root));
 
 	let mut chunker = StateChunker {
-		hashes: Vec::new(),
+		partition: partition as usize,
+		hashes: hashes,
 		rlps: Vec::new(),
+		last_key: H256::new(),
 		cur_size: 0,
-		snapshot_path: path,
-		snappy_buffer: vec![0; snappy::max_compressed_len(PREFERRED_CHUNK_SIZE)],
+		writer: writer,
+		resume: resume,
+		compression_buffer: Vec::new(),
+		options: *options,
+		progress: progress,
 	};
 
-	trace!(target: "snapshot", "beginning state chunking");
-
 	// account_key here is the address' hash.
-	for (account_key, account_data) in account_view.iter() {
+	for (account_key, account_data) in account_view.iter_first_nibbles(partition, partition + 1) {
+		try!(progress.check_cancelled());
+		// skip the accounts written out before the snapshot was interrupted.
+		if resume_from.as_ref().map_or(false, |last| account_key.as_slice() <= last.as_slice()) {
+			continue;
+		}
+
 		let account = Account::from_thin_rlp(account_data);
 		let account_key_hash = H256::from_slice(&account_key);
 
@@ -267,12 +481,63 @@ pub fn chunk_state(db: &HashDB, root: &H256, path: &Path) -> Result<Vec<H256>, E
 	if chunker.cur_size != 0 {
 		try!(chunker.write_chunk());
 	}
+	try!(checkpoint(resume, writer, |state| state.state[partition as usize].done = true));
 
+	trace!(target: "snapshot", "state partition {} done: {} chunks", partition, chunker.hashes.len());
 	Ok(chunker.hashes)
 }
 
+/// Version of the snapshot format produced by this client.
+/// Manifests of version 1 carry no version field; their chunks are read the same way as those of version 2.
+/// From version 3 on, block chunks record the total difficulty of the parent of their first block.
+/// From version 4 on, the manifest records the codec chunks are compressed with; older snapshots use snappy.
+pub const MANIFEST_VERSION: u64 = 4;
+
+// Version from which block chunks record the parent total difficulty.
+const BLOCK_CHUNK_TD_VERSION: u64 = 3;
+
+// Start of a block chunk: the parent of the first block, the first block number and,
+// for newer versions, the total difficulty of the parent.
+struct BlockChunkHeader {
+	parent_hash: H256,
+	first_number: u64,
+	parent_td: Option<U256>,
+	// number of list items taken by the header.
+	len: usize,
+}
+
+impl BlockChunkHeader {
+	fn decode(rlp: &UntrustedRlp, version: u64) -> Result<Self, DecoderError> {
+		let with_td = version >= BLOCK_CHUNK_TD_VERSION;
+		Ok(BlockChunkHeader {
+			parent_hash: try!(rlp.val_at(0)),
+			first_number: try!(rlp.val_at(1)),
+			parent_td: if with_td { Some(try!(rlp.val_at(2))) } else { None },
+			len: if with_td { 3 } else { 2 },
+		})
+	}
+}
+
+// decode the pair of an abridged block and its receipts, checking the receipts against the header.
+fn decode_block_pair(pair: &UntrustedRlp, parent_hash: H256, number: u64) -> Result<(::block::Block, Vec<Receipt>), Error> {
+	let abridged_rlp: Bytes = try!(pair.val_at(0));
+	let receipts_rlp: Bytes = try!(pair.val_at(1));
+	let receipts: Vec<Receipt> = try!(UntrustedRlp::new(&receipts_rlp).as_val());
+	let block = try!(AbridgedBlock::from_raw(abridged_rlp).to_block(parent_hash, number));
+
+	let receipts_root = ordered_trie_root(receipts.iter().map(|r| r.rlp_bytes().to_vec()).collect());
+	if receipts_root != block.header.receipts_root {
+		return Err(SnapshotError::WrongReceiptsRoot(block.header.receipts_root, receipts_root).into());
+	}
+	Ok((block, receipts))
+}
+
 /// Manifest data.
 pub struct ManifestData {
+	/// Snapshot format version.
+	pub version: u64,
+	/// Codec the chunks are compressed with.
+	pub codec: Codec,
 	/// List of state chunk hashes.
 	pub state_hashes: Vec<H256>,
 	/// List of block chunk hashes.
@@ -288,27 +553,45 @@ pub struct ManifestData {
 impl ManifestData {
 	/// Encode the manifest data to rlp.
 	pub fn into_rlp(self) -> Bytes {
-		let mut stream = RlpStream::new_list(5);
+		let mut stream = RlpStream::new_list(7);
+		stream.append(&self.version);
 		stream.append(&self.state_hashes);
 		stream.append(&self.block_hashes);
 		stream.append(&self.state_root);
 		stream.append(&self.block_number);
 		stream.append(&self.block_hash);
+		stream.append(&self.codec.id());
 
 		stream.out()
 	}
 
 	/// Try to restore manifest data from raw bytes, interpreted as RLP.
+	/// Fails for manifests of a version newer than `MANIFEST_VERSION`.
 	pub fn from_rlp(raw: &[u8]) -> Result<Self, DecoderError> {
 		let decoder = UntrustedRlp::new(raw);
 
-		let state_hashes: Vec<H256> = try!(decoder.val_at(0));
-		let block_hashes: Vec<H256> = try!(decoder.val_at(1));
-		let state_root: H256 = try!(decoder.val_at(2));
-		let block_number: u64 = try!(decoder.val_at(3));
-		let block_hash: H256 = try!(decoder.val_at(4));
+		let (version, start) = match decoder.item_count() {
+			5 => (1, 0),
+			6 | 7 => (try!(decoder.val_at(0)), 1),
+			_ => return Err(DecoderError::RlpIncorrectListLen),
+		};
+		if version == 0 || version > MANIFEST_VERSION {
+			return Err(DecoderError::Custom("Unsupported snapshot manifest version"));
+		}
+
+		let state_hashes: Vec<H256> = try!(decoder.val_at(start));
+		let block_hashes: Vec<H256> = try!(decoder.val_at(start + 1));
+		let state_root: H256 = try!(decoder.val_at(start + 2));
+		let block_number: u64 = try!(decoder.val_at(start + 3));
+		let block_hash: H256 = try!(decoder.val_at(start + 4));
+		let codec = match decoder.item_count() {
+			7 => try!(Codec::from_id(try!(decoder.val_at(6))).ok_or(DecoderError::Custom("Unknown snapshot codec"))),
+			_ => Codec::Snappy,
+		};
 
 		Ok(ManifestData {
+			version: version,
+			codec: codec,
 			state_hashes: state_hashes,
 			block_hashes: block_hashes,
 			state_root: state_root,
@@ -322,23 +605,26 @@ impl ManifestData {
 pub struct StateRebuilder {
 	db: Box<JournalDB>,
 	state_root: H256,
-	snappy_buffer: Vec<u8>
+	codec: Codec,
+	decompression_buffer: Vec<u8>
 }
 
 impl StateRebuilder {
-	/// Create a new state rebuilder to write into the given backing DB.
-	pub fn new(db: Box<JournalDB>) -> Self {
+	/// Create a new state rebuilder to write into the given backing DB,
+	/// reading chunks compressed with the given codec.
+	pub fn new(db: Box<JournalDB>, codec: Codec) -> Self {
 		StateRebuilder {
 			db: db,
 			state_root: H256::zero(),
-			snappy_buffer: Vec::new(),
+			codec: codec,
+			decompression_buffer: Vec::new(),
 		}
 	}
 
 	/// Feed a compressed state chunk into the rebuilder.
 	pub fn feed(&mut self, compressed: &[u8]) -> Result<(), Error> {
-		let len = try!(snappy::decompress_into(compressed, &mut self.snappy_buffer));
-		let rlp = UntrustedRlp::new(&self.snappy_buffer[..len]);
+		let len = try!(self.codec.decompress_into(compressed, &mut self.decompression_buffer));
+		let rlp = UntrustedRlp::new(&self.decompression_buffer[..len]);
 		let account_fat_rlps: Vec<_> = rlp.iter().map(|r| r.as_raw()).collect();
 		let mut pairs = Vec::with_capacity(rlp.item_count());
 
@@ -347,7 +633,7 @@ impl StateRebuilder {
 			pairs.push((H256::new(), Vec::new()));
 		}
 
-		let chunk_size = account_fat_rlps.len() / ::num_cpus::get();
+		let chunk_size = max(account_fat_rlps.len() / ::num_cpus::get(), 1);
 
 		// build account tries in parallel.
 		try!(scope(|scope| {
@@ -415,3 +701,274 @@ fn rebuild_account_trie(db: &mut HashDB, account_chunk: &[&[u8]], out_chunk: &mu
 	}
 	Ok(())
 }
+
+/// Used to rebuild the chain from block chunks.
+///
+/// Chunks must be fed in ascending order, starting with the one following the genesis block.
+pub struct BlockRebuilder<'a> {
+	chain: &'a BlockChain,
+	version: u64,
+	codec: Codec,
+	decompression_buffer: Vec<u8>,
+}
+
+impl<'a> BlockRebuilder<'a> {
+	/// Create a new block rebuilder inserting blocks into the given chain,
+	/// reading chunks of the given snapshot format version compressed with the given codec.
+	pub fn new(chain: &'a BlockChain, version: u64, codec: Codec) -> Self {
+		BlockRebuilder {
+			chain: chain,
+			version: version,
+			codec: codec,
+			decompression_buffer: Vec::new(),
+		}
+	}
+
+	/// Feed a compressed block chunk into the rebuilder.
+	pub fn feed(&mut self, compressed: &[u8]) -> Result<(), Error> {
+		let len = try!(self.codec.decompress_into(compressed, &mut self.decompression_buffer));
+		let rlp = UntrustedRlp::new(&self.decompression_buffer[..len]);
+		let header = try!(BlockChunkHeader::decode(&rlp, self.version));
+
+		let best_hash = self.chain.best_block_hash();
+		if header.parent_hash != best_hash {
+			return Err(SnapshotError::UnlinkedChunk(best_hash, header.parent_hash).into());
+		}
+		let expected_number = self.chain.best_block_number() + 1;
+		if header.first_number != expected_number {
+			return Err(SnapshotError::WrongChunkNumber(expected_number, header.first_number).into());
+		}
+		if let Some(parent_td) = header.parent_td {
+			let best_td = self.chain.best_block_total_difficulty();
+			if parent_td != best_td {
+				return Err(SnapshotError::WrongTotalDifficulty(best_td, parent_td).into());
+			}
+		}
+
+		let mut parent_hash = header.parent_hash;
+		let mut number = header.first_number;
+		for pair in rlp.iter().skip(header.len) {
+			let (block, receipts) = try!(decode_block_pair(&pair, parent_hash, number));
+			self.chain.insert_block(&block.rlp_bytes(Seal::With), receipts);
+
+			parent_hash = block.header.hash();
+			number += 1;
+		}
+
+		Ok(())
+	}
+}
+
+// read a compressed chunk from the snapshot directory, checking it against its hash.
+fn read_chunk(path: &Path, hash: &H256) -> Result<Bytes, Error> {
+	let mut file = try!(File::open(path.join(hash.hex()))
+		.map_err(|_| SnapshotError::MissingChunk(hash.clone())));
+	let mut chunk = Vec::new();
+	try!(file.read_to_end(&mut chunk));
+
+	let found = chunk.sha3();
+	if &found != hash {
+		return Err(SnapshotError::ChunkHashMismatch(hash.clone(), found).into());
+	}
+	Ok(chunk)
+}
+
+// Range of blocks contained in a block chunk.
+struct BlockChunkInfo {
+	parent_hash: H256,
+	first_number: u64,
+	last_hash: H256,
+	last_number: u64,
+	// total difficulties of the parent and of the last block, if recorded.
+	parent_td: Option<U256>,
+	last_td: Option<U256>,
+}
+
+// decode all blocks in a compressed block chunk, reconstructing their hashes and checking their receipts.
+fn block_chunk_info(compressed: &[u8], version: u64, codec: Codec, buffer: &mut Vec<u8>) -> Result<BlockChunkInfo, Error> {
+	let len = try!(codec.decompress_into(compressed, buffer));
+	let rlp = UntrustedRlp::new(&buffer[..len]);
+	let header = try!(BlockChunkHeader::decode(&rlp, version));
+
+	let mut last_hash = header.parent_hash.clone();
+	let mut last_td = header.parent_td;
+	let mut number = header.first_number;
+	for pair in rlp.iter().skip(header.len) {
+		let (block, _) = try!(decode_block_pair(&pair, last_hash, number));
+		last_hash = block.header.hash();
+		last_td = last_td.map(|td| td + block.header.difficulty);
+		number += 1;
+	}
+
+	Ok(BlockChunkInfo {
+		parent_hash: header.parent_hash,
+		first_number: header.first_number,
+		last_hash: last_hash,
+		last_number: number.saturating_sub(1),
+		parent_td: header.parent_td,
+		last_td: last_td,
+	})
+}
+
+/// Verify the snapshot described by `manifest` and stored in `path` without restoring it.
+///
+/// Checks every chunk against its hash, rebuilds the state into `state_db` (which should be a
+/// scratch database) to compare against the manifest state root, and checks that the block chunks
+/// form a single chain ending with the manifest block.
+pub fn verify(manifest: &ManifestData, path: &Path, state_db: Box<JournalDB>) -> Result<(), Error> {
+	trace!(target: "snapshot", "verifying {} state chunks", manifest.state_hashes.len());
+	let mut state = StateRebuilder::new(state_db, manifest.codec);
+	for hash in &manifest.state_hashes {
+		try!(state.feed(&try!(read_chunk(path, hash))));
+	}
+	if state.state_root() != manifest.state_root {
+		return Err(SnapshotError::WrongStateRoot(manifest.state_root, state.state_root()).into());
+	}
+
+	trace!(target: "snapshot", "verifying {} block chunks", manifest.block_hashes.len());
+	let mut buffer = Vec::new();
+	let mut chunks = Vec::with_capacity(manifest.block_hashes.len());
+	for hash in &manifest.block_hashes {
+		chunks.push(try!(block_chunk_info(&try!(read_chunk(path, hash)), manifest.version, manifest.codec, &mut buffer)));
+	}
+	chunks.sort_by_key(|info| info.first_number);
+
+	let mut last: Option<(H256, u64, Option<U256>)> = None;
+	for info in &chunks {
+		if let Some((ref last_hash, last_number, ref last_td)) = last {
+			if &info.parent_hash != last_hash {
+				return Err(SnapshotError::UnlinkedChunk(last_hash.clone(), info.parent_hash.clone()).into());
+			}
+			if info.first_number != last_number + 1 {
+				return Err(SnapshotError::WrongChunkNumber(last_number + 1, info.first_number).into());
+			}
+			if let (&Some(ref expected), &Some(ref found)) = (last_td, &info.parent_td) {
+				if expected != found {
+					return Err(SnapshotError::WrongTotalDifficulty(expected.clone(), found.clone()).into());
+				}
+			}
+		}
+		last = Some((info.last_hash.clone(), info.last_number, info.last_td));
+	}
+
+	match last {
+		Some((hash, number, _)) if hash == manifest.block_hash && number == manifest.block_number => Ok(()),
+		Some((hash, _, _)) => Err(SnapshotError::WrongBlockHash(manifest.block_hash, hash).into()),
+		None => Err(SnapshotError::WrongBlockHash(manifest.block_hash, H256::zero()).into()),
+	}
+}
+
+/// Restore the state and the chain described by `manifest` from the chunks stored in `path`.
+///
+/// The chain must not contain any blocks beyond the genesis.
+/// Chunks are checked against their hashes from the manifest, but block seals and transactions
+/// are not re-verified; chunk contents are trusted as long as they produce the state root and
+/// the head block named in the manifest.
+pub fn restore(chain: &BlockChain, state_db: Box<JournalDB>, manifest: &ManifestData, path: &Path) -> Result<(), Error> {
+	if chain.best_block_number() != 0 {
+		return Err(SnapshotError::ChainNotEmpty.into());
+	}
+
+	trace!(target: "snapshot", "restoring {} state chunks", manifest.state_hashes.len());
+	let mut state = StateRebuilder::new(state_db, manifest.codec);
+	for hash in &manifest.state_hashes {
+		try!(state.feed(&try!(read_chunk(path, hash))));
+	}
+	if state.state_root() != manifest.state_root {
+		return Err(SnapshotError::WrongStateRoot(manifest.state_root, state.state_root()).into());
+	}
+
+	// block chunks are listed from the head backwards; order them by their first block.
+	let mut buffer = Vec::new();
+	let mut block_chunks = Vec::with_capacity(manifest.block_hashes.len());
+	for hash in &manifest.block_hashes {
+		let len = try!(manifest.codec.decompress_into(&try!(read_chunk(path, hash)), &mut buffer));
+		let first: u64 = try!(UntrustedRlp::new(&buffer[..len]).val_at(1));
+		block_chunks.push((first, hash));
+	}
+	block_chunks.sort_by_key(|&(first, _)| first);
+
+	trace!(target: "snapshot", "restoring {} block chunks", block_chunks.len());
+	let mut blocks = BlockRebuilder::new(chain, manifest.version, manifest.codec);
+	for (_, hash) in block_chunks {
+		try!(blocks.feed(&try!(read_chunk(path, hash))));
+	}
+
+	let best_hash = chain.best_block_hash();
+	if best_hash != manifest.block_hash {
+		return Err(SnapshotError::WrongBlockHash(manifest.block_hash, best_hash).into());
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use util::hash::H256;
+	use util::U256;
+	use util::rlp::{RlpStream, Stream, UntrustedRlp};
+	use super::{BlockChunkHeader, Codec, ManifestData, MANIFEST_VERSION};
+
+	fn manifest(version: u64) -> ManifestData {
+		ManifestData {
+			version: version,
+			codec: Codec::Zlib,
+			state_hashes: vec![H256::from(1)],
+			block_hashes: vec![H256::from(2)],
+			state_root: H256::from(3),
+			block_number: 4,
+			block_hash: H256::from(5),
+		}
+	}
+
+	#[test]
+	fn manifest_roundtrip() {
+		let data = ManifestData::from_rlp(&manifest(MANIFEST_VERSION).into_rlp()).unwrap();
+		assert_eq!(data.version, MANIFEST_VERSION);
+		assert_eq!(data.codec, Codec::Zlib);
+		assert_eq!(data.state_hashes, vec![H256::from(1)]);
+		assert_eq!(data.block_number, 4);
+		assert_eq!(data.block_hash, H256::from(5));
+	}
+
+	#[test]
+	fn reads_unversioned_manifest() {
+		let mut stream = RlpStream::new_list(5);
+		stream.append(&vec![H256::from(1)]);
+		stream.append(&vec![H256::from(2)]);
+		stream.append(&H256::from(3));
+		stream.append(&4u64);
+		stream.append(&H256::from(5));
+
+		let data = ManifestData::from_rlp(&stream.out()).unwrap();
+		assert_eq!(data.version, 1);
+		assert_eq!(data.codec, Codec::Snappy);
+		assert_eq!(data.state_root, H256::from(3));
+		assert_eq!(data.block_hash, H256::from(5));
+	}
+
+	#[test]
+	fn rejects_unknown_manifest_version() {
+		assert!(ManifestData::from_rlp(&manifest(MANIFEST_VERSION + 1).into_rlp()).is_err());
+		assert!(ManifestData::from_rlp(&manifest(0).into_rlp()).is_err());
+	}
+
+	#[test]
+	fn reads_block_chunk_headers_of_all_versions() {
+		let mut stream = RlpStream::new_list(3);
+		stream.append(&H256::from(1)).append(&5u64).append(&U256::from(1000));
+		let raw = stream.out();
+		let rlp = UntrustedRlp::new(&raw);
+
+		let header = BlockChunkHeader::decode(&rlp, MANIFEST_VERSION).unwrap();
+		assert_eq!(header.parent_hash, H256::from(1));
+		assert_eq!(header.first_number, 5);
+		assert_eq!(header.parent_td, Some(U256::from(1000)));
+		assert_eq!(header.len, 3);
+
+		// older chunks carry no total difficulty.
+		let header = BlockChunkHeader::decode(&rlp, 2).unwrap();
+		assert_eq!(header.parent_td, None);
+		assert_eq!(header.len, 2);
+	}
+}
@@ -0,0 +1,143 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Bookkeeping for snapshots taken periodically by the running client.
+//!
+//! Every snapshot is stored in a directory named after the number of the block it was taken at.
+//! Snapshots in progress are written to a directory with a `.tmp` suffix and renamed once complete.
+//! A snapshot interrupted by a crash is resumed from there when the same block is due again.
+//! Chunks downloaded for restoration are kept in a separate `restoration` directory.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use header::BlockNumber;
+use super::ChunkOptions;
+
+const TEMP_SUFFIX: &'static str = ".tmp";
+
+/// Periodic snapshot configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+	/// Take a snapshot every `period` blocks. Zero disables periodic snapshots.
+	pub period: u64,
+	/// Number of most recent snapshots to keep.
+	pub history: usize,
+	/// How the snapshot chunks are produced.
+	pub chunks: ChunkOptions,
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Config {
+			period: 0,
+			history: 2,
+			chunks: ChunkOptions::default(),
+		}
+	}
+}
+
+impl Config {
+	/// Returns the most recent checkpoint not later than `best_block`, if any.
+	pub fn checkpoint(&self, best_block: BlockNumber) -> Option<BlockNumber> {
+		match self.period {
+			0 => None,
+			period => match best_block / period * period {
+				0 => None,
+				checkpoint => Some(checkpoint),
+			},
+		}
+	}
+}
+
+/// Path of the complete snapshot taken at block `number`.
+pub fn snapshot_path(root: &Path, number: BlockNumber) -> PathBuf {
+	root.join(format!("{}", number))
+}
+
+/// Path of the snapshot at block `number` while it's being written.
+pub fn temp_path(root: &Path, number: BlockNumber) -> PathBuf {
+	root.join(format!("{}{}", number, TEMP_SUFFIX))
+}
+
+/// Path where chunks of a snapshot being restored are collected.
+pub fn restoration_path(root: &Path) -> PathBuf {
+	root.join("restoration")
+}
+
+/// Numbers of blocks of all complete snapshots found in `root`, in ascending order.
+pub fn list(root: &Path) -> Vec<BlockNumber> {
+	let mut numbers: Vec<BlockNumber> = match fs::read_dir(root) {
+		Ok(entries) => entries
+			.flat_map(Result::ok)
+			.filter(|entry| entry.path().is_dir())
+			.filter_map(|entry| entry.file_name().to_str().and_then(|name| name.parse().ok()))
+			.collect(),
+		Err(_) => Vec::new(),
+	};
+	numbers.sort();
+	numbers
+}
+
+/// Removes all but `history` most recent snapshots, as well as unfinished ones.
+pub fn prune(root: &Path, history: usize) -> io::Result<()> {
+	let numbers = list(root);
+	let obsolete = numbers.len().saturating_sub(history);
+	for number in &numbers[..obsolete] {
+		trace!(target: "snapshot", "removing snapshot at block {}", number);
+		try!(fs::remove_dir_all(snapshot_path(root, *number)));
+	}
+
+	for entry in try!(fs::read_dir(root)).flat_map(Result::ok) {
+		let unfinished = entry.file_name().to_str().map_or(false, |name| name.ends_with(TEMP_SUFFIX));
+		if unfinished {
+			try!(fs::remove_dir_all(entry.path()));
+		}
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs;
+	use devtools::RandomTempPath;
+	use super::{Config, list, prune, snapshot_path, temp_path};
+
+	#[test]
+	fn checkpoints() {
+		let config = Config { period: 1000, history: 2, ..Default::default() };
+		assert_eq!(config.checkpoint(999), None);
+		assert_eq!(config.checkpoint(1000), Some(1000));
+		assert_eq!(config.checkpoint(2999), Some(2000));
+		assert_eq!(Config::default().checkpoint(5000), None);
+	}
+
+	#[test]
+	fn prunes_old_snapshots() {
+		let temp = RandomTempPath::create_dir();
+		let root = temp.as_path();
+		for number in &[3000, 1000, 2000] {
+			fs::create_dir_all(snapshot_path(root, *number)).unwrap();
+		}
+		fs::create_dir_all(temp_path(root, 4000)).unwrap();
+
+		assert_eq!(list(root), vec![1000, 2000, 3000]);
+		prune(root, 2).unwrap();
+		assert_eq!(list(root), vec![2000, 3000]);
+		assert!(!temp_path(root, 4000).exists());
+	}
+}
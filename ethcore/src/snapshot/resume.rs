@@ -0,0 +1,160 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Progress of snapshot creation, for resuming interrupted snapshots.
+//!
+//! After every chunk written, the chunkers record how far they've got: the key of the last
+//! account of each state partition and the hash of the next block to chunk, along with the
+//! hashes of the chunks written so far. A writer which keeps its chunks across runs can
+//! persist this, so that a later snapshot of the same block skips the work already done.
+//! The chunk options are recorded too, as chunks produced with other options can't be reused.
+
+use util::Bytes;
+use util::hash::H256;
+use util::rlp::{DecoderError, RlpStream, Stream, UntrustedRlp, View};
+
+use super::{ChunkOptions, Codec, STATE_PARTITIONS};
+
+/// Progress of a single chunker.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChunkerProgress {
+	/// Hashes of the chunks written so far, in order.
+	pub hashes: Vec<H256>,
+	/// Where to pick up again: the key of the last account written for state partitions,
+	/// the hash of the next block to chunk for blocks. `None` if no chunk was written yet.
+	pub resume_from: Option<H256>,
+	/// Whether the chunker has finished.
+	pub done: bool,
+}
+
+impl ChunkerProgress {
+	fn append_to(&self, stream: &mut RlpStream) {
+		let resume_from: Vec<H256> = self.resume_from.iter().cloned().collect();
+		stream.begin_list(3)
+			.append(&self.hashes)
+			.append(&resume_from)
+			.append(&(self.done as u8));
+	}
+
+	fn decode(rlp: &UntrustedRlp) -> Result<Self, DecoderError> {
+		let resume_from: Vec<H256> = try!(rlp.val_at(1));
+		if resume_from.len() > 1 {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+		let done: u8 = try!(rlp.val_at(2));
+
+		Ok(ChunkerProgress {
+			hashes: try!(rlp.val_at(0)),
+			resume_from: resume_from.into_iter().next(),
+			done: done != 0,
+		})
+	}
+}
+
+/// Progress of a snapshot being taken.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResumeState {
+	/// Hash of the block the snapshot is taken at.
+	pub block_hash: H256,
+	/// Options the chunks are produced with.
+	pub options: ChunkOptions,
+	/// Progress of each state partition, in partition order.
+	pub state: Vec<ChunkerProgress>,
+	/// Progress of block chunking.
+	pub blocks: ChunkerProgress,
+}
+
+impl ResumeState {
+	/// Progress of a snapshot at the given block which hasn't started yet.
+	pub fn new(block_hash: H256, options: ChunkOptions) -> Self {
+		ResumeState {
+			block_hash: block_hash,
+			options: options,
+			state: vec![ChunkerProgress::default(); STATE_PARTITIONS as usize],
+			blocks: ChunkerProgress::default(),
+		}
+	}
+
+	/// Encode the progress to rlp.
+	pub fn to_rlp(&self) -> Bytes {
+		let mut stream = RlpStream::new_list(5);
+		stream.append(&self.block_hash);
+		stream.begin_list(self.state.len());
+		for partition in &self.state {
+			partition.append_to(&mut stream);
+		}
+		self.blocks.append_to(&mut stream);
+		stream.append(&self.options.codec.id());
+		stream.append(&(self.options.chunk_size as u64));
+
+		stream.out()
+	}
+
+	/// Try to restore the progress from raw bytes, interpreted as RLP.
+	/// Progress recorded without chunk options is taken to use the default ones.
+	pub fn from_rlp(raw: &[u8]) -> Result<Self, DecoderError> {
+		let decoder = UntrustedRlp::new(raw);
+		let options = match decoder.item_count() {
+			3 => ChunkOptions::default(),
+			5 => {
+				let id: u8 = try!(decoder.val_at(3));
+				let chunk_size: u64 = try!(decoder.val_at(4));
+				ChunkOptions {
+					chunk_size: chunk_size as usize,
+					codec: try!(Codec::from_id(id).ok_or(DecoderError::Custom("Unknown snapshot codec"))),
+				}
+			},
+			_ => return Err(DecoderError::RlpIncorrectListLen),
+		};
+		let partitions = try!(decoder.at(1));
+		if partitions.item_count() != STATE_PARTITIONS as usize {
+			return Err(DecoderError::RlpIncorrectListLen);
+		}
+
+		let mut state = Vec::with_capacity(STATE_PARTITIONS as usize);
+		for partition in partitions.iter() {
+			state.push(try!(ChunkerProgress::decode(&partition)));
+		}
+
+		Ok(ResumeState {
+			block_hash: try!(decoder.val_at(0)),
+			options: options,
+			state: state,
+			blocks: try!(ChunkerProgress::decode(&try!(decoder.at(2)))),
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::hash::H256;
+	use super::ResumeState;
+	use super::super::{ChunkOptions, Codec};
+
+	#[test]
+	fn resume_state_roundtrip() {
+		let options = ChunkOptions { chunk_size: 1024, codec: Codec::Zlib };
+		let mut state = ResumeState::new(H256::from(1), options);
+		state.state[3].hashes = vec![H256::from(2), H256::from(3)];
+		state.state[3].resume_from = Some(H256::from(4));
+		state.state[5].done = true;
+		state.blocks.hashes = vec![H256::from(5)];
+		state.blocks.resume_from = Some(H256::from(6));
+
+		assert_eq!(ResumeState::from_rlp(&state.to_rlp()).unwrap(), state);
+		assert!(ResumeState::from_rlp(&[0xc0]).is_err());
+	}
+}
@@ -56,8 +56,8 @@ impl From<ethjson::spec::Params> for CommonParams {
 pub struct Spec {
 	/// User friendly spec name
 	pub name: String,
-	/// What engine are we using for this?
-	pub engine: Box<Engine>,
+	/// What engine are we using for this? Shared by the client and the miner.
+	pub engine: Arc<Box<Engine>>,
 
 	/// Known nodes on the network in enode format.
 	pub nodes: Vec<String>,
@@ -104,7 +104,7 @@ impl From<ethjson::spec::Spec> for Spec {
 		Spec {
 			name: s.name.into(),
 			params: params.clone(),
-			engine: Spec::engine(s.engine, params, builtins),
+			engine: Arc::new(Spec::engine(s.engine, params, builtins)),
 			nodes: s.nodes.unwrap_or_else(Vec::new),
 			parent_hash: g.parent_hash,
 			transactions_root: g.transactions_root,
@@ -23,6 +23,8 @@ use trace::Trace;
 use pod_account::*;
 use pod_state::{self, PodState};
 use types::state_diff::StateDiff;
+use types::storage_proof::StorageRangeProof;
+use types::state_override::StateOverride;
 
 /// Used to return information about an `State::apply` operation.
 pub struct ApplyOutcome {
@@ -180,6 +182,39 @@ impl State {
 		self.get(address, false).as_ref().map_or(H256::new(), |a|a.storage_at(&AccountDB::new(self.db.as_hashdb(), address), key))
 	}
 
+	/// Produce a proof of at most `limit` storage items of account `address`, starting
+	/// at hashed position `from`. Returns `None` if the account doesn't exist.
+	/// Only committed changes are covered by the proof.
+	pub fn prove_storage_range(&self, address: &Address, from: &H256, limit: usize) -> Result<Option<StorageRangeProof>, TrieError> {
+		let account_key = address.sha3();
+		let account = try!(prove_range(self.db.as_hashdb(), &self.root, &account_key, 1));
+		let account_rlp = match account.items.into_iter().next() {
+			Some((key, rlp)) => if key == account_key.to_vec() { rlp } else { return Ok(None) },
+			None => return Ok(None),
+		};
+
+		let storage_root: H256 = UntrustedRlp::new(&account_rlp).val_at(2).expect("account RLP from the state trie is valid; qed");
+		let storage = try!(prove_range(&AccountDB::new(self.db.as_hashdb(), address), &storage_root, from, limit));
+
+		Ok(Some(StorageRangeProof {
+			account: account_rlp,
+			account_proof: account.nodes,
+			keys: storage.items.iter().map(|&(ref key, _)| H256::from_slice(key)).collect(),
+			values: storage.items.iter().map(|&(_, ref value)| H256::from(decode::<U256>(value))).collect(),
+			storage_proof: storage.nodes,
+		}))
+	}
+
+	/// Produce proofs of at most `limit` accounts with hashed addresses not smaller than `from`,
+	/// each proving a single account against the state root.
+	/// Only committed changes are covered by the proofs.
+	pub fn prove_accounts(&self, from: &[u8], limit: usize) -> Result<Vec<RangeProof>, TrieError> {
+		let range = try!(prove_range(self.db.as_hashdb(), &self.root, from, limit));
+		range.items.iter()
+			.map(|&(ref key, _)| prove_range(self.db.as_hashdb(), &self.root, key, 1))
+			.collect()
+	}
+
 	/// Mutate storage of account `a` so that it is `value` for `key`.
 	pub fn code(&self, a: &Address) -> Option<Bytes> {
 		self.get(a, true).as_ref().map_or(None, |a|a.code().map(|x|x.to_vec()))
@@ -224,6 +259,26 @@ impl State {
 		self.require_or_from(a, true, || Account::new_contract(0.into(), self.account_start_nonce), |_|{}).reset_code(code);
 	}
 
+	/// Patch balances, code and storage of the given accounts, e.g. to simulate a call on
+	/// a modified state. Changes are kept in the cache until the state is committed.
+	pub fn apply_override(&mut self, state_override: &StateOverride) {
+		for (address, account) in state_override {
+			if let Some(balance) = account.balance {
+				let current = self.balance(address);
+				match balance > current {
+					true => self.add_balance(address, &(balance - current)),
+					false => self.sub_balance(address, &(current - balance)),
+				}
+			}
+			if let Some(ref code) = account.code {
+				self.reset_code(address, code.clone());
+			}
+			for (key, value) in &account.storage {
+				self.set_storage(address, key.clone(), value.clone());
+			}
+		}
+	}
+
 	/// Execute a given transaction.
 	/// This will change the state accordingly.
 	pub fn apply(&mut self, env_info: &EnvInfo, engine: &Engine, vm_factory: &EvmFactory, t: &SignedTransaction, tracing: bool) -> ApplyResult {
@@ -403,6 +458,8 @@ use transaction::*;
 use util::log::init_log;
 use trace::trace;
 use trace::trace::{Trace};
+use trace::TraceError;
+use types::state_override::{AccountOverride, StateOverride};
 
 #[test]
 fn should_apply_create_transaction() {
@@ -496,7 +553,7 @@ fn should_trace_failed_create_transaction() {
 			gas: 78792.into(),
 			init: vec![91, 96, 0, 86],
 		}),
-		result: trace::Res::FailedCreate,
+		result: trace::Res::FailedCreate(TraceError::OutOfGas),
 		subs: vec![]
 	});
 
@@ -792,7 +849,7 @@ fn should_trace_failed_call_transaction() {
 			gas: 79000.into(),
 			input: vec![],
 		}),
-		result: trace::Res::FailedCall,
+		result: trace::Res::FailedCall(TraceError::OutOfGas),
 		subs: vec![]
 	});
 
@@ -1003,7 +1060,7 @@ fn should_trace_failed_subcall_transaction() {
 				gas: 78934.into(),
 				input: vec![],
 			}),
-			result: trace::Res::FailedCall,
+			result: trace::Res::FailedCall(TraceError::OutOfGas),
 			subs: vec![]
 		}]
 	});
@@ -1132,7 +1189,7 @@ fn should_trace_failed_subcall_with_subcall_transaction() {
 				gas: 78934.into(),
 				input: vec![],
 			}),
-			result: trace::Res::FailedCall,
+			result: trace::Res::FailedCall(TraceError::OutOfGas),
 			subs: vec![Trace {
 				depth: 2,
 				action: trace::Action::Call(trace::Call {
@@ -1239,6 +1296,66 @@ fn storage_at_from_database() {
 	assert_eq!(s.storage_at(&a, &H256::from(&U256::from(01u64))), H256::from(&U256::from(69u64)));
 }
 
+#[test]
+fn applies_state_override() {
+	let (a, b) = (Address::from(1), Address::from(2));
+	let temp = RandomTempPath::new();
+	let mut state = get_temp_state_in(temp.as_path());
+	state.add_balance(&a, &U256::from(100u64));
+	state.init_code(&a, vec![1, 2, 3]);
+	state.set_storage(&a, H256::from(&U256::from(1u64)), H256::from(&U256::from(10u64)));
+	state.set_storage(&a, H256::from(&U256::from(2u64)), H256::from(&U256::from(20u64)));
+	state.commit();
+	let root = state.root().clone();
+
+	let mut storage = BTreeMap::new();
+	storage.insert(H256::from(&U256::from(2u64)), H256::from(&U256::from(69u64)));
+	let mut state_override = StateOverride::new();
+	state_override.insert(a, AccountOverride { balance: Some(U256::from(40u64)), code: Some(vec![4, 5]), storage: storage });
+	state_override.insert(b, AccountOverride { balance: Some(U256::from(7u64)), code: None, storage: BTreeMap::new() });
+
+	let mut copy = state.clone();
+	copy.apply_override(&state_override);
+	assert_eq!(copy.balance(&a), U256::from(40u64));
+	assert_eq!(copy.code(&a), Some(vec![4, 5]));
+	assert_eq!(copy.storage_at(&a, &H256::from(&U256::from(1u64))), H256::from(&U256::from(10u64)));
+	assert_eq!(copy.storage_at(&a, &H256::from(&U256::from(2u64))), H256::from(&U256::from(69u64)));
+	assert_eq!(copy.balance(&b), U256::from(7u64));
+
+	assert_eq!(state.root(), &root);
+	assert_eq!(state.balance(&a), U256::from(100u64));
+	assert_eq!(state.code(&a), Some(vec![1, 2, 3]));
+	assert_eq!(state.storage_at(&a, &H256::from(&U256::from(2u64))), H256::from(&U256::from(20u64)));
+	assert_eq!(state.balance(&b), U256::zero());
+}
+
+#[test]
+fn proves_storage_range() {
+	let a = Address::from(1);
+	let temp = RandomTempPath::new();
+	let mut state = get_temp_state_in(temp.as_path());
+	for i in 1..20u64 {
+		state.set_storage(&a, H256::from(&U256::from(i)), H256::from(&U256::from(i * 100)));
+	}
+	state.add_balance(&Address::from(2), &U256::from(69u64));
+	state.commit();
+
+	let proof = state.prove_storage_range(&a, &H256::new(), 5).unwrap().unwrap();
+	assert_eq!(proof.keys.len(), 5);
+	for (key, value) in proof.keys.iter().zip(proof.values.iter()) {
+		let position = (1..20u64).map(|i| H256::from(&U256::from(i))).find(|p| &p.sha3() == key).unwrap();
+		assert_eq!(value, &state.storage_at(&a, &position));
+	}
+	proof.verify(state.root(), &a, &H256::new()).unwrap();
+	assert!(proof.verify(state.root(), &Address::from(2), &H256::new()).is_err());
+
+	let mut altered = proof.clone();
+	altered.values[2] = H256::from(&U256::from(1u64));
+	assert!(altered.verify(state.root(), &a, &H256::new()).is_err());
+
+	assert!(state.prove_storage_range(&Address::from(3), &H256::new(), 5).unwrap().is_none());
+}
+
 #[test]
 fn get_from_database() {
 	let a = Address::zero();
@@ -14,17 +14,20 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use client::{BlockChainClient, MiningBlockChainClient, Client, ClientConfig, BlockID};
+use client::{BlockChainClient, MiningBlockChainClient, Client, ClientConfig, BlockID, DatabaseColumn, SnapshotConfig};
 use block::IsBlock;
 use tests::helpers::*;
 use common::*;
 use devtools::*;
 use miner::Miner;
+use snapshot::{self, ChunkerProgress, ManifestData, ResumeState, SnapshotError};
+use snapshot::io::{LooseWriter, PackedWriter, SnapshotWriter};
+use error::Error;
 
 #[test]
 fn imports_from_empty() {
 	let dir = RandomTempPath::new();
-	let client = Client::new(ClientConfig::default(), get_test_spec(), dir.as_path(), Arc::new(Miner::with_spec(get_test_spec())), IoChannel::disconnected()).unwrap();
+	let client = Client::new(ClientConfig::default(), &get_test_spec(), dir.as_path(), Arc::new(Miner::with_spec(&get_test_spec())), IoChannel::disconnected()).unwrap();
 	client.import_verified_blocks();
 	client.flush_queue();
 }
@@ -42,7 +45,7 @@ fn returns_state_root_basic() {
 #[test]
 fn imports_good_block() {
 	let dir = RandomTempPath::new();
-	let client = Client::new(ClientConfig::default(), get_test_spec(), dir.as_path(), Arc::new(Miner::with_spec(get_test_spec())), IoChannel::disconnected()).unwrap();
+	let client = Client::new(ClientConfig::default(), &get_test_spec(), dir.as_path(), Arc::new(Miner::with_spec(&get_test_spec())), IoChannel::disconnected()).unwrap();
 	let good_block = get_good_dummy_block();
 	if let Err(_) = client.import_block(good_block) {
 		panic!("error importing block being good by definition");
@@ -54,10 +57,180 @@ fn imports_good_block() {
 	assert!(!block.is_empty());
 }
 
+#[test]
+fn returns_database_stats() {
+	let client_result = generate_dummy_client(6);
+	let client = client_result.reference();
+	let stats = client.database_stats();
+
+	assert_eq!(stats.len(), DatabaseColumn::all().len());
+	let blocks = stats.iter().find(|s| s.column == DatabaseColumn::Blocks).unwrap();
+	assert!(blocks.keys > 0);
+	assert!(!blocks.files_per_level.is_empty());
+
+	let best = client.chain_info().best_block_hash;
+	assert!(client.database_get(DatabaseColumn::Blocks, best.to_vec()).is_some());
+	assert!(client.database_get(DatabaseColumn::Blocks, vec![0x01]).is_none());
+}
+
+#[test]
+fn takes_periodic_snapshots() {
+	let dir = RandomTempPath::new();
+	let mut config = ClientConfig::default();
+	config.snapshot = SnapshotConfig { period: 4, history: 1, ..Default::default() };
+	let client = Client::new(config, &get_test_spec(), dir.as_path(), Arc::new(Miner::with_spec(&get_test_spec())), IoChannel::disconnected()).unwrap();
+	for block in get_good_dummy_block_seq(5) {
+		if let Err(_) = client.import_block(block) {
+			panic!("panic importing block which is well-formed");
+		}
+	}
+	client.flush_queue();
+	client.import_verified_blocks();
+
+	assert_eq!(client.snapshot_due(), Some(4));
+	let path = client.take_periodic_snapshot(4).unwrap();
+	assert!(path.join("MANIFEST").exists());
+	assert_eq!(client.snapshot_due(), None);
+	assert!(client.snapshot_progress().is_none());
+	assert!(!client.cancel_snapshot());
+}
+
+#[test]
+fn restores_snapshot_from_chunks() {
+	let dir = RandomTempPath::new();
+	let mut config = ClientConfig::default();
+	config.snapshot = SnapshotConfig { period: 4, history: 1, ..Default::default() };
+	let client = Client::new(config, &get_test_spec(), dir.as_path(), Arc::new(Miner::with_spec(&get_test_spec())), IoChannel::disconnected()).unwrap();
+	for block in get_good_dummy_block_seq(5) {
+		if let Err(_) = client.import_block(block) {
+			panic!("panic importing block which is well-formed");
+		}
+	}
+	client.flush_queue();
+	client.import_verified_blocks();
+	client.take_periodic_snapshot(4).unwrap();
+
+	let manifest = client.snapshot_manifest().unwrap();
+	let data = ManifestData::from_rlp(&manifest).unwrap();
+
+	let restored_dir = RandomTempPath::new();
+	let restored = Client::new(ClientConfig::default(), &get_test_spec(), restored_dir.as_path(), Arc::new(Miner::with_spec(&get_test_spec())), IoChannel::disconnected()).unwrap();
+	for hash in data.state_hashes.iter().chain(data.block_hashes.iter()) {
+		restored.feed_snapshot_chunk(hash, client.snapshot_chunk(hash).unwrap());
+	}
+
+	assert!(restored.restore_fed_snapshot(&manifest));
+	assert!(!restored.is_restoring_snapshot());
+	assert_eq!(restored.chain_info().best_block_number, 4);
+	assert_eq!(restored.block_hash(BlockID::Number(4)), client.block_hash(BlockID::Number(4)));
+	assert_eq!(Some(restored.chain_info().total_difficulty), client.block_total_difficulty(BlockID::Number(4)));
+	// only an empty chain can be restored
+	assert!(!restored.restore_fed_snapshot(&manifest));
+}
+
+#[test]
+fn verifies_snapshot_chunks() {
+	use std::io::Write;
+
+	let dir = RandomTempPath::new();
+	let mut config = ClientConfig::default();
+	config.snapshot = SnapshotConfig { period: 4, history: 1, ..Default::default() };
+	let client = Client::new(config, &get_test_spec(), dir.as_path(), Arc::new(Miner::with_spec(&get_test_spec())), IoChannel::disconnected()).unwrap();
+	for block in get_good_dummy_block_seq(5) {
+		if let Err(_) = client.import_block(block) {
+			panic!("panic importing block which is well-formed");
+		}
+	}
+	client.flush_queue();
+	client.import_verified_blocks();
+	let path = client.take_periodic_snapshot(4).unwrap();
+	let manifest = ManifestData::from_rlp(&client.snapshot_manifest().unwrap()).unwrap();
+
+	let scratch = RandomTempPath::new();
+	assert!(snapshot::verify(&manifest, &path, get_temp_journal_db_in(scratch.as_path())).is_ok());
+
+	let corrupted = &manifest.block_hashes[0];
+	::std::fs::File::create(path.join(corrupted.hex())).unwrap().write_all(b"corrupted").unwrap();
+	let scratch = RandomTempPath::new();
+	match snapshot::verify(&manifest, &path, get_temp_journal_db_in(scratch.as_path())) {
+		Err(Error::Snapshot(SnapshotError::ChunkHashMismatch(ref expected, _))) => assert_eq!(expected, corrupted),
+		other => panic!("Unexpected verification result: {:?}", other),
+	}
+}
+
+#[test]
+fn takes_snapshot_at_given_block() {
+	use std::io::Read;
+
+	let dir = RandomTempPath::new();
+	let client = Client::new(ClientConfig::default(), &get_test_spec(), dir.as_path(), Arc::new(Miner::with_spec(&get_test_spec())), IoChannel::disconnected()).unwrap();
+	for block in get_good_dummy_block_seq(5) {
+		if let Err(_) = client.import_block(block) {
+			panic!("panic importing block which is well-formed");
+		}
+	}
+	client.flush_queue();
+	client.import_verified_blocks();
+
+	let path = RandomTempPath::new();
+	client.take_snapshot(BlockID::Number(2), LooseWriter::new(path.as_path().to_owned()).unwrap()).unwrap();
+	let mut manifest_rlp = Vec::new();
+	::std::fs::File::open(path.as_path().join("MANIFEST")).unwrap().read_to_end(&mut manifest_rlp).unwrap();
+	let manifest = ManifestData::from_rlp(&manifest_rlp).unwrap();
+	assert_eq!(manifest.block_number, 2);
+	assert_eq!(manifest.block_hash, client.block_hash(BlockID::Number(2)).unwrap());
+
+	let scratch = RandomTempPath::new();
+	assert!(snapshot::verify(&manifest, path.as_path(), get_temp_journal_db_in(scratch.as_path())).is_ok());
+
+	match client.take_snapshot(BlockID::Number(100), PackedWriter::new(Vec::new())) {
+		Err(Error::Snapshot(SnapshotError::InvalidStartingBlock(BlockID::Number(100)))) => {},
+		other => panic!("Unexpected snapshot result: {:?}", other),
+	}
+	assert!(client.snapshot_progress().is_none());
+}
+
+#[test]
+fn resumes_interrupted_snapshot() {
+	fn read_manifest(path: &Path) -> ManifestData {
+		use std::io::Read;
+
+		let mut manifest_rlp = Vec::new();
+		::std::fs::File::open(path.join("MANIFEST")).unwrap().read_to_end(&mut manifest_rlp).unwrap();
+		ManifestData::from_rlp(&manifest_rlp).unwrap()
+	}
+
+	let client_result = generate_dummy_client(5);
+	let client = client_result.reference();
+
+	let path = RandomTempPath::new();
+	client.take_snapshot(BlockID::Number(3), LooseWriter::new(path.as_path().to_owned()).unwrap()).unwrap();
+	let manifest = read_manifest(path.as_path());
+
+	// pretend an earlier run got as far as writing all block chunks.
+	let resumed = RandomTempPath::new();
+	let mut writer = LooseWriter::new(resumed.as_path().to_owned()).unwrap();
+	let mut state = ResumeState::new(manifest.block_hash.clone(), Default::default());
+	state.blocks = ChunkerProgress {
+		hashes: manifest.block_hashes.clone(),
+		resume_from: client.block_hash(BlockID::Number(0)),
+		done: true,
+	};
+	writer.checkpoint(&state).unwrap();
+	client.take_snapshot(BlockID::Number(3), writer).unwrap();
+
+	let resumed_manifest = read_manifest(resumed.as_path());
+	assert_eq!(resumed_manifest.state_hashes, manifest.state_hashes);
+	assert_eq!(resumed_manifest.block_hashes, manifest.block_hashes);
+	// block chunks weren't written again.
+	assert!(!resumed.as_path().join(manifest.block_hashes[0].hex()).exists());
+	assert!(LooseWriter::new(resumed.as_path().to_owned()).unwrap().resume_state().is_none());
+}
+
 #[test]
 fn query_none_block() {
 	let dir = RandomTempPath::new();
-	let client = Client::new(ClientConfig::default(), get_test_spec(), dir.as_path(), Arc::new(Miner::with_spec(get_test_spec())), IoChannel::disconnected()).unwrap();
+	let client = Client::new(ClientConfig::default(), &get_test_spec(), dir.as_path(), Arc::new(Miner::with_spec(&get_test_spec())), IoChannel::disconnected()).unwrap();
 
     let non_existant = client.block_header(BlockID::Number(188));
 	assert!(non_existant.is_none());
@@ -125,6 +298,20 @@ fn can_generate_gas_price_statistics() {
 	assert_eq!(s, vec_into![0, 1, 3, 5, 7, 9, 11, 13, 15]);
 }
 
+#[test]
+fn finds_transaction_by_sender_and_nonce() {
+	let client_result = generate_dummy_client_with_data(3, 2, &vec_into![1]);
+	let client = client_result.reference();
+	let sender = KeyPair::from_secret("".sha3()).unwrap().address();
+
+	let tx = client.transaction_by_sender_and_nonce(&sender, &5.into()).unwrap();
+	assert_eq!(tx.block_number, 3);
+	assert_eq!(tx.transaction_index, 1);
+	assert_eq!(tx.nonce, 5.into());
+	assert!(client.transaction_by_sender_and_nonce(&sender, &6.into()).is_none());
+	assert!(client.transaction_by_sender_and_nonce(&Address::zero(), &0.into()).is_none());
+}
+
 #[test]
 fn can_handle_long_fork() {
 	let client_result = generate_dummy_client(1200);
@@ -154,3 +341,13 @@ fn can_mine() {
 
 	assert_eq!(*b.block().header().parent_hash(), BlockView::new(&dummy_blocks[0]).header_view().sha3());
 }
+
+#[test]
+fn reports_disk_usage() {
+	let client_result = generate_dummy_client(6);
+	let client = client_result.reference();
+
+	let usage = client.disk_usage();
+	assert!(usage.state > 0);
+	assert!(usage.blocks > 0);
+}
@@ -50,6 +50,11 @@ pub struct Config {
 	pub blooms: BloomConfig,
 	/// Database cache-size if not default
 	pub db_cache_size: Option<usize>,
+	/// Number of most recent blocks whose traces are kept. All traces are kept if None.
+	pub retention: Option<u64>,
+	/// Enables tracing of a database synced without it, so that traces of
+	/// already imported blocks can be re-indexed.
+	pub reindex: bool,
 }
 
 impl Default for Config {
@@ -61,6 +66,8 @@ impl Default for Config {
 				elements_per_index: 16,
 			},
 			db_cache_size: None,
+			retention: None,
+			reindex: false,
 		}
 	}
 }
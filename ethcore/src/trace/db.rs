@@ -15,14 +15,17 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Trace database.
-use std::ops::{Deref, DerefMut};
+use std::cmp;
+use std::ops::{Deref, DerefMut, Range};
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::path::Path;
 use bloomchain::{Number, Config as BloomConfig};
 use bloomchain::group::{BloomGroupDatabase, BloomGroupChain, GroupPosition, BloomGroup};
 use util::{H256, H264, Database, DatabaseConfig, DBTransaction, RwLock};
+use util::rlp::{encode, decode};
 use header::BlockNumber;
+use basic_types::LogBloom;
 use trace::{BlockTraces, LocalizedTrace, Config, Switch, Filter, Database as TraceDatabase, ImportRequest, DatabaseExtras, Error};
 use db::{Key, Writable, Readable, CacheUpdatePolicy};
 use blooms;
@@ -30,6 +33,10 @@ use super::flat::{FlatTrace, FlatBlockTraces, FlatTransactionTraces};
 
 
 const TRACE_DB_VER: &'static [u8] = b"1.0";
+const PRUNED_KEY: &'static [u8] = b"pruned";
+const REINDEX_KEY: &'static [u8] = b"reindex";
+/// Maximal number of block numbers pruned by a single call to `prune`.
+const PRUNE_BATCH: u64 = 32;
 
 #[derive(Debug, Copy, Clone)]
 #[cfg_attr(feature="dev", allow(enum_variant_names))]
@@ -38,6 +45,10 @@ enum TraceDBIndex {
 	BlockTraces = 0,
 	/// Trace bloom group index.
 	BloomGroups = 1,
+	/// Retracted blocks index.
+	RetractedBlocks = 2,
+	/// Side-chain blocks index.
+	SideBlocks = 3,
 }
 
 impl Key<FlatBlockTraces> for H256 {
@@ -88,6 +99,54 @@ impl Key<blooms::BloomGroup> for TraceGroupPosition {
 	}
 }
 
+/// Number of blocks which were retracted by reorganisations.
+///
+/// Their traces are kept in the database, but the blooms only index the canonical chain.
+#[derive(Debug, PartialEq, Clone)]
+struct RetractedPosition(BlockNumber);
+
+/// Helper data structure created cause [u8; 9] does not implement Deref to &[u8].
+pub struct RetractedKey([u8; 9]);
+
+impl Deref for RetractedKey {
+	type Target = [u8];
+
+	fn deref(&self) -> &Self::Target {
+		&self.0
+	}
+}
+
+impl Key<Vec<H256>> for RetractedPosition {
+	type Target = RetractedKey;
+
+	fn key(&self) -> Self::Target {
+		number_key(TraceDBIndex::RetractedBlocks, self.0)
+	}
+}
+
+/// Number of blocks which were imported on a side chain and have never been canonical.
+///
+/// Their traces are only kept to be removed once the number falls out of the retention window.
+#[derive(Debug, PartialEq, Clone)]
+struct SidePosition(BlockNumber);
+
+impl Key<Vec<H256>> for SidePosition {
+	type Target = RetractedKey;
+
+	fn key(&self) -> Self::Target {
+		number_key(TraceDBIndex::SideBlocks, self.0)
+	}
+}
+
+fn number_key(index: TraceDBIndex, number: BlockNumber) -> RetractedKey {
+	let mut result = [0u8; 9];
+	result[0] = index as u8;
+	for i in 0..8 {
+		result[8 - i] = (number >> (i * 8)) as u8;
+	}
+	RetractedKey(result)
+}
+
 /// Trace database.
 pub struct TraceDB<T> where T: DatabaseExtras {
 	// cache
@@ -99,6 +158,10 @@ pub struct TraceDB<T> where T: DatabaseExtras {
 	bloom_config: BloomConfig,
 	// tracing enabled
 	enabled: bool,
+	// number of latest blocks whose traces are kept
+	retention: Option<u64>,
+	// traces of blocks below this number were pruned
+	pruned: RwLock<BlockNumber>,
 	// extras
 	extras: Arc<T>,
 }
@@ -130,7 +193,19 @@ impl<T> TraceDB<T> where T: DatabaseExtras {
 			None => Switch::Auto,
 		};
 
-		let enabled = try!(old_tracing.turn_to(config.enabled));
+		// number of the next block to re-index, if re-indexing is in progress.
+		let reindex_from = tracesdb.get(REINDEX_KEY).unwrap().map(|value| decode::<BlockNumber>(&value));
+
+		let enabled = match (config.reindex, reindex_from.is_some()) {
+			(true, _) => true,
+			(false, true) => return Err(Error::ReindexRequired),
+			(false, false) => try!(old_tracing.turn_to(config.enabled)),
+		};
+
+		// traces of all blocks but the genesis are missing unless tracing was on before.
+		if config.reindex && reindex_from.is_none() && old_tracing != Switch::On {
+			tracesdb.put(REINDEX_KEY, &encode(&(1 as BlockNumber))).unwrap();
+		}
 
 		let encoded_tracing = match enabled {
 			true => [0x1],
@@ -140,18 +215,41 @@ impl<T> TraceDB<T> where T: DatabaseExtras {
 		tracesdb.put(b"enabled", &encoded_tracing).unwrap();
 		tracesdb.put(b"version", TRACE_DB_VER).unwrap();
 
+		let pruned = tracesdb.get(PRUNED_KEY).unwrap().map_or(0, |value| decode::<BlockNumber>(&value));
+
 		let db = TraceDB {
 			traces: RwLock::new(HashMap::new()),
 			blooms: RwLock::new(HashMap::new()),
 			tracesdb: tracesdb,
 			bloom_config: config.blooms,
 			enabled: enabled,
+			retention: config.retention,
+			pruned: RwLock::new(pruned),
 			extras: extras,
 		};
 
 		Ok(db)
 	}
 
+	/// Returns the underlying database.
+	pub fn db(&self) -> &Database {
+		&self.tracesdb
+	}
+
+	/// Returns the number of the next block whose traces should be re-indexed,
+	/// or `None` if there's nothing to re-index.
+	pub fn reindex_from(&self) -> Option<BlockNumber> {
+		self.tracesdb.get(REINDEX_KEY).unwrap().map(|value| decode::<BlockNumber>(&value))
+	}
+
+	/// Notes the progress of re-indexing. `None` marks it as finished.
+	pub fn set_reindex_from(&self, number: Option<BlockNumber>) {
+		match number {
+			Some(number) => self.tracesdb.put(REINDEX_KEY, &encode(&number)).unwrap(),
+			None => self.tracesdb.delete(REINDEX_KEY).unwrap(),
+		}
+	}
+
 	/// Returns traces for block with hash.
 	fn traces(&self, block_hash: &H256) -> Option<FlatBlockTraces> {
 		self.tracesdb.read_with_cache(&self.traces, block_hash)
@@ -162,6 +260,73 @@ impl<T> TraceDB<T> where T: DatabaseExtras {
 		self.traces(block_hash).map(Into::into)
 	}
 
+	/// Removes traces of up to `PRUNE_BATCH` block numbers which fell out of the retention
+	/// window, given the best block. Traces of retracted and side-chain blocks with these
+	/// numbers are removed as well, and the blooms of the pruned range are cleared.
+	///
+	/// Must not run concurrently with `import`. Returns true if more blocks are left to prune.
+	pub fn prune(&self, latest: BlockNumber) -> bool {
+		let retention = match self.retention {
+			Some(retention) if self.enabled && latest >= retention => retention,
+			_ => return false,
+		};
+
+		let start = *self.pruned.read();
+		let target = latest + 1 - retention;
+		let end = cmp::min(target, start + PRUNE_BATCH);
+		if end <= start {
+			return false;
+		}
+
+		let batch = DBTransaction::new();
+		{
+			let mut traces = self.traces.write();
+			for number in start..end {
+				let mut hashes = self.retracted_blocks(number);
+				hashes.extend(self.side_blocks(number));
+				hashes.extend(self.extras.block_hash(number));
+				for hash in hashes {
+					traces.remove(&hash);
+					batch.delete(&Key::<FlatBlockTraces>::key(&hash)).unwrap();
+				}
+				batch.delete(&RetractedPosition(number).key()).unwrap();
+				batch.delete(&SidePosition(number).key()).unwrap();
+			}
+		}
+
+		{
+			let range: Range<Number> = start as Number..end as Number;
+			let empty_blooms = range.clone()
+				.map(|_| blooms::Bloom::from(LogBloom::default()))
+				.map(Into::into)
+				.collect();
+
+			let chain = BloomGroupChain::new(self.bloom_config, self);
+			let trace_blooms = chain.replace(&range, empty_blooms);
+			let blooms_to_insert = trace_blooms.into_iter()
+				.map(|p| (From::from(p.0), From::from(p.1)))
+				.collect::<HashMap<TraceGroupPosition, blooms::BloomGroup>>();
+
+			let mut blooms = self.blooms.write();
+			batch.extend_with_cache(blooms.deref_mut(), blooms_to_insert, CacheUpdatePolicy::Remove);
+		}
+
+		batch.put(PRUNED_KEY, &encode(&end)).unwrap();
+		self.tracesdb.write(batch).unwrap();
+		*self.pruned.write() = end;
+		end < target
+	}
+
+	/// Returns hashes of blocks with given number which were retracted.
+	fn retracted_blocks(&self, block_number: BlockNumber) -> Vec<H256> {
+		self.tracesdb.read(&RetractedPosition(block_number)).unwrap_or_else(Vec::new)
+	}
+
+	/// Returns hashes of blocks with given number which were imported on a side chain.
+	fn side_blocks(&self, block_number: BlockNumber) -> Vec<H256> {
+		self.tracesdb.read(&SidePosition(block_number)).unwrap_or_else(Vec::new)
+	}
+
 	fn matching_block_traces(
 		&self,
 		filter: &Filter,
@@ -172,6 +337,8 @@ impl<T> TraceDB<T> where T: DatabaseExtras {
 		let tx_traces: Vec<FlatTransactionTraces> = traces.into();
 		tx_traces.into_iter()
 			.enumerate()
+			// skip transactions which certainly have no matching traces.
+			.filter(|&(_, ref tx_trace)| filter.matches_bloom(&tx_trace.bloom()))
 			.flat_map(|(tx_number, tx_trace)| {
 				self.matching_transaction_traces(filter, tx_trace, block_hash.clone(), block_number, tx_number)
 			})
@@ -186,8 +353,9 @@ impl<T> TraceDB<T> where T: DatabaseExtras {
 		block_number: BlockNumber,
 		tx_number: usize
 	) -> Vec<LocalizedTrace> {
-		let tx_hash = self.extras.transaction_hash(block_number, tx_number)
-			.expect("Expected to find transaction hash. Database is probably corrupted");
+		// traces past the last transaction are block rewards.
+		let tx_hash = self.extras.block_transaction_hash(&block_hash, tx_number);
+		let tx_number = tx_hash.as_ref().map(|_| tx_number);
 
 		let flat_traces: Vec<FlatTrace> = traces.into();
 		flat_traces.into_iter()
@@ -236,7 +404,7 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 		// now let's rebuild the blooms
 		{
 			let range_start = request.block_number as Number + 1 - request.enacted.len();
-			let range_end = range_start + request.retracted;
+			let range_end = range_start + request.retracted.len();
 			let replaced_range = range_start..range_end;
 			let enacted_blooms = request.enacted
 				.iter()
@@ -259,6 +427,53 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 			batch.extend_with_cache(blooms.deref_mut(), blooms_to_insert, CacheUpdatePolicy::Remove);
 		}
 
+		// note the retracted blocks, so their traces can still be found, and that the
+		// enacted ones are canonical again.
+		if !request.retracted.is_empty() {
+			let range_start = request.block_number + 1 - request.enacted.len() as BlockNumber;
+			let mut changed = HashMap::new();
+			for (i, hash) in request.retracted.iter().enumerate() {
+				let number = range_start + i as BlockNumber;
+				let retracted = changed.entry(number).or_insert_with(|| self.retracted_blocks(number));
+				if !retracted.contains(hash) {
+					retracted.push(hash.clone());
+				}
+			}
+			for (i, hash) in request.enacted.iter().enumerate() {
+				let number = range_start + i as BlockNumber;
+				changed.entry(number).or_insert_with(|| self.retracted_blocks(number)).retain(|h| h != hash);
+			}
+			for (number, retracted) in changed {
+				batch.write(&RetractedPosition(number), &retracted);
+			}
+		}
+
+		// note blocks imported on a side chain, so their traces can be pruned, and that
+		// the enacted ones are not on a side chain anymore.
+		if self.retention.is_some() {
+			let range_start = request.block_number + 1 - request.enacted.len() as BlockNumber;
+			let mut changed = HashMap::new();
+			if !request.enacted.contains(&request.block_hash) {
+				let side = changed.entry(request.block_number).or_insert_with(|| self.side_blocks(request.block_number));
+				if !side.contains(&request.block_hash) {
+					side.push(request.block_hash.clone());
+				}
+			}
+			// only a reorganisation enacts more than the imported block.
+			if !request.retracted.is_empty() {
+				for (i, hash) in request.enacted.iter().enumerate() {
+					let number = range_start + i as BlockNumber;
+					let side = self.side_blocks(number);
+					if side.contains(hash) {
+						changed.entry(number).or_insert(side).retain(|h| h != hash);
+					}
+				}
+			}
+			for (number, side) in changed {
+				batch.write(&SidePosition(number), &side);
+			}
+		}
+
 		self.tracesdb.write(batch).unwrap();
 	}
 
@@ -270,15 +485,14 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 				// this may and should be optimized
 				.and_then(|traces| traces.into_iter().find(|trace| trace.trace_address == trace_position))
 				.map(|trace| {
-					let tx_hash = self.extras.transaction_hash(block_number, tx_position)
-						.expect("Expected to find transaction hash. Database is probably corrupted");
+					let tx_hash = self.extras.transaction_hash(block_number, tx_position);
 
 					LocalizedTrace {
 						action: trace.action,
 						result: trace.result,
 						subtraces: trace.subtraces,
 						trace_address: trace.trace_address,
-						transaction_number: tx_position,
+						transaction_number: tx_hash.as_ref().map(|_| tx_position),
 						transaction_hash: tx_hash,
 						block_number: block_number,
 						block_hash: block_hash,
@@ -293,8 +507,7 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 				.and_then(|traces| traces.into_iter().nth(tx_position))
 				.map(Into::<Vec<FlatTrace>>::into)
 				.map(|traces| {
-					let tx_hash = self.extras.transaction_hash(block_number, tx_position)
-						.expect("Expected to find transaction hash. Database is probably corrupted");
+					let tx_hash = self.extras.transaction_hash(block_number, tx_position);
 
 					traces.into_iter()
 					.map(|trace| LocalizedTrace {
@@ -302,7 +515,7 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 						result: trace.result,
 						subtraces: trace.subtraces,
 						trace_address: trace.trace_address,
-						transaction_number: tx_position,
+						transaction_number: tx_hash.as_ref().map(|_| tx_position),
 						transaction_hash: tx_hash.clone(),
 						block_number: block_number,
 						block_hash: block_hash
@@ -320,8 +533,7 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 						.map(Into::<Vec<FlatTrace>>::into)
 						.enumerate()
 						.flat_map(|(tx_position, traces)| {
-							let tx_hash = self.extras.transaction_hash(block_number, tx_position)
-								.expect("Expected to find transaction hash. Database is probably corrupted");
+							let tx_hash = self.extras.transaction_hash(block_number, tx_position);
 
 							traces.into_iter()
 								.map(|trace| LocalizedTrace {
@@ -329,7 +541,7 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 									result: trace.result,
 									subtraces: trace.subtraces,
 									trace_address: trace.trace_address,
-									transaction_number: tx_position,
+									transaction_number: tx_hash.as_ref().map(|_| tx_position),
 									transaction_hash: tx_hash.clone(),
 									block_number: block_number,
 									block_hash: block_hash,
@@ -344,7 +556,10 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 	fn filter(&self, filter: &Filter) -> Vec<LocalizedTrace> {
 		let chain = BloomGroupChain::new(self.bloom_config, self);
 		let numbers = chain.filter(filter);
+		let pruned = *self.pruned.read();
 		numbers.into_iter()
+			// blooms of blocks enacted below the pruned ones by a deep reorganisation may remain.
+			.filter(|n| *n as BlockNumber >= pruned)
 			.flat_map(|n| {
 				let number = n as BlockNumber;
 				let hash = self.extras.block_hash(number)
@@ -355,6 +570,18 @@ impl<T> TraceDatabase for TraceDB<T> where T: DatabaseExtras {
 			})
 			.collect()
 	}
+
+	fn filter_retracted(&self, filter: &Filter) -> Vec<LocalizedTrace> {
+		(filter.range.start..filter.range.end + 1)
+			.flat_map(|n| {
+				let number = n as BlockNumber;
+				self.retracted_blocks(number).into_iter()
+					.filter_map(|hash| self.traces(&hash).map(|traces| (hash, traces)))
+					.flat_map(|(hash, traces)| self.matching_block_traces(filter, traces, hash, number))
+					.collect::<Vec<_>>()
+			})
+			.collect()
+	}
 }
 
 #[cfg(test)]
@@ -364,9 +591,11 @@ mod tests {
 	use util::{Address, U256, H256};
 	use devtools::RandomTempPath;
 	use header::BlockNumber;
-	use trace::{Config, Switch, TraceDB, Database, DatabaseExtras, ImportRequest};
-	use trace::{BlockTraces, Trace, Filter, LocalizedTrace, AddressesFilter};
+	use trace::{Config, Switch, TraceDB, Database, DatabaseExtras, ImportRequest, Error};
+	use trace::{BlockTraces, Trace, Filter, LocalizedTrace, AddressesFilter, TraceError};
 	use trace::trace::{Call, Action, Res};
+	use trace::flat::FlatBlockTraces;
+	use db::Key;
 
 	struct NoopExtras;
 
@@ -378,11 +607,16 @@ mod tests {
 		fn transaction_hash(&self, _block_number: BlockNumber, _tx_position: usize) -> Option<H256> {
 			unimplemented!();
 		}
+
+		fn block_transaction_hash(&self, _block_hash: &H256, _tx_position: usize) -> Option<H256> {
+			unimplemented!();
+		}
 	}
 
 	struct Extras {
 		block_hashes: HashMap<BlockNumber, H256>,
 		transaction_hashes: HashMap<BlockNumber, Vec<H256>>,
+		retracted_transaction_hashes: HashMap<H256, Vec<H256>>,
 	}
 
 	impl Default for Extras {
@@ -390,6 +624,7 @@ mod tests {
 			Extras {
 				block_hashes: HashMap::new(),
 				transaction_hashes: HashMap::new(),
+				retracted_transaction_hashes: HashMap::new(),
 			}
 		}
 	}
@@ -403,6 +638,14 @@ mod tests {
 			self.transaction_hashes.get(&block_number)
 				.and_then(|hashes| hashes.iter().cloned().nth(tx_position))
 		}
+
+		fn block_transaction_hash(&self, block_hash: &H256, tx_position: usize) -> Option<H256> {
+			match self.block_hashes.iter().find(|&(_, hash)| hash == block_hash) {
+				Some((number, _)) => self.transaction_hash(*number, tx_position),
+				None => self.retracted_transaction_hashes.get(block_hash)
+					.and_then(|hashes| hashes.iter().cloned().nth(tx_position)),
+			}
+		}
 	}
 
 	#[test]
@@ -482,6 +725,47 @@ mod tests {
 		TraceDB::new(config.clone(), temp.as_path(), Arc::new(NoopExtras)).unwrap(); // should panic!
 	}
 
+	#[test]
+	fn test_reindex() {
+		let temp = RandomTempPath::new();
+		let mut config = Config::default();
+		config.enabled = Switch::Off;
+
+		{
+			let tracedb = TraceDB::new(config.clone(), temp.as_path(), Arc::new(NoopExtras)).unwrap();
+			assert_eq!(tracedb.tracing_enabled(), false);
+			assert_eq!(tracedb.reindex_from(), None);
+		}
+
+		config.reindex = true;
+		{
+			let tracedb = TraceDB::new(config.clone(), temp.as_path(), Arc::new(NoopExtras)).unwrap();
+			assert_eq!(tracedb.tracing_enabled(), true);
+			assert_eq!(tracedb.reindex_from(), Some(1));
+			tracedb.set_reindex_from(Some(5));
+		}
+
+		// interrupted re-indexing has to be finished first.
+		config.reindex = false;
+		config.enabled = Switch::On;
+		match TraceDB::new(config.clone(), temp.as_path(), Arc::new(NoopExtras)) {
+			Err(Error::ReindexRequired) => {},
+			_ => panic!("re-indexing should be required"),
+		}
+
+		config.reindex = true;
+		{
+			let tracedb = TraceDB::new(config.clone(), temp.as_path(), Arc::new(NoopExtras)).unwrap();
+			assert_eq!(tracedb.reindex_from(), Some(5));
+			tracedb.set_reindex_from(None);
+		}
+
+		config.reindex = false;
+		let tracedb = TraceDB::new(config.clone(), temp.as_path(), Arc::new(NoopExtras)).unwrap();
+		assert_eq!(tracedb.tracing_enabled(), true);
+		assert_eq!(tracedb.reindex_from(), None);
+	}
+
 	fn create_simple_import_request(block_number: BlockNumber, block_hash: H256) -> ImportRequest {
 		ImportRequest {
 			traces: BlockTraces::from(vec![Trace {
@@ -493,13 +777,13 @@ mod tests {
 					gas: U256::from(4),
 					input: vec![],
 				}),
-				result: Res::FailedCall,
+				result: Res::FailedCall(TraceError::OutOfGas),
 				subs: vec![],
 			}]),
 			block_hash: block_hash.clone(),
 			block_number: block_number,
 			enacted: vec![block_hash],
-			retracted: 0,
+			retracted: vec![],
 		}
 	}
 
@@ -512,11 +796,11 @@ mod tests {
 				gas: U256::from(4),
 				input: vec![],
 			}),
-			result: Res::FailedCall,
+			result: Res::FailedCall(TraceError::OutOfGas),
 			trace_address: vec![],
 			subtraces: 0,
-			transaction_number: 0,
-			transaction_hash: tx_hash,
+			transaction_number: Some(0),
+			transaction_hash: Some(tx_hash),
 			block_number: block_number,
 			block_hash: block_hash,
 		}
@@ -593,4 +877,101 @@ mod tests {
 		assert_eq!(tracedb.trace(0, 0, vec![]).unwrap(), create_simple_localized_trace(0, block_0.clone(), tx_0.clone()));
 		assert_eq!(tracedb.trace(1, 0, vec![]).unwrap(), create_simple_localized_trace(1, block_1.clone(), tx_1.clone()));
 	}
+
+	#[test]
+	fn test_retention() {
+		let temp = RandomTempPath::new();
+		let mut config = Config::default();
+		config.enabled = Switch::On;
+		config.retention = Some(2);
+
+		let mut extras = Extras::default();
+		for i in 0..4 {
+			extras.block_hashes.insert(i, H256::from(0xa0 + i));
+			extras.transaction_hashes.insert(i, vec![H256::from(0xf0 + i)]);
+		}
+		let extras = Arc::new(extras);
+
+		{
+			let tracedb = TraceDB::new(config.clone(), temp.as_path(), extras.clone()).unwrap();
+			for i in 0..4 {
+				tracedb.import(create_simple_import_request(i, H256::from(0xa0 + i)));
+			}
+			// a side-chain block at a pruned number.
+			let mut request = create_simple_import_request(1, H256::from(0xb1));
+			request.enacted = vec![];
+			tracedb.import(request);
+			assert!(tracedb.db().get(&Key::<FlatBlockTraces>::key(&H256::from(0xb1))).unwrap().is_some());
+
+			assert!(!tracedb.prune(3));
+		}
+
+		// pruning is remembered after reopening.
+		let tracedb = TraceDB::new(config, temp.as_path(), extras).unwrap();
+		let filter = Filter {
+			range: (0..3),
+			from_address: AddressesFilter::from(vec![Address::from(1)]),
+			to_address: AddressesFilter::from(vec![]),
+		};
+
+		let traces = tracedb.filter(&filter);
+		assert_eq!(traces.len(), 2);
+		assert_eq!(traces[0], create_simple_localized_trace(2, H256::from(0xa2), H256::from(0xf2)));
+		assert_eq!(traces[1], create_simple_localized_trace(3, H256::from(0xa3), H256::from(0xf3)));
+
+		assert_eq!(None, tracedb.block_traces(0));
+		assert_eq!(None, tracedb.block_traces(1));
+		assert!(tracedb.block_traces(2).is_some());
+		assert!(tracedb.db().get(&Key::<FlatBlockTraces>::key(&H256::from(0xb1))).unwrap().is_none());
+	}
+
+	#[test]
+	fn test_filter_retracted() {
+		let temp = RandomTempPath::new();
+		let mut config = Config::default();
+		config.enabled = Switch::On;
+		let block_0 = H256::from(0xa1);
+		let block_1a = H256::from(0xa2);
+		let block_1b = H256::from(0xa3);
+		let tx_0 = H256::from(0xff);
+		let tx_1a = H256::from(0xaf);
+		let tx_1b = H256::from(0xbf);
+
+		let mut extras = Extras::default();
+		extras.block_hashes.insert(0, block_0.clone());
+		extras.block_hashes.insert(1, block_1b.clone());
+		extras.transaction_hashes.insert(0, vec![tx_0.clone()]);
+		extras.transaction_hashes.insert(1, vec![tx_1b.clone()]);
+		extras.retracted_transaction_hashes.insert(block_1a.clone(), vec![tx_1a.clone()]);
+
+		let tracedb = TraceDB::new(config, temp.as_path(), Arc::new(extras)).unwrap();
+		tracedb.import(create_simple_import_request(0, block_0.clone()));
+		tracedb.import(create_simple_import_request(1, block_1a.clone()));
+
+		// block 1b replaces block 1a.
+		let mut request = create_simple_import_request(1, block_1b.clone());
+		request.retracted = vec![block_1a.clone()];
+		tracedb.import(request);
+
+		let filter = Filter {
+			range: (0..1),
+			from_address: AddressesFilter::from(vec![Address::from(1)]),
+			to_address: AddressesFilter::from(vec![]),
+		};
+
+		let traces = tracedb.filter(&filter);
+		assert_eq!(traces.len(), 2);
+		assert_eq!(traces[1], create_simple_localized_trace(1, block_1b.clone(), tx_1b.clone()));
+
+		let traces = tracedb.filter_retracted(&filter);
+		assert_eq!(traces, vec![create_simple_localized_trace(1, block_1a.clone(), tx_1a.clone())]);
+
+		// block 1a becomes canonical again.
+		let mut request = create_simple_import_request(1, block_1a.clone());
+		request.retracted = vec![block_1b.clone()];
+		tracedb.import(request);
+
+		let traces = tracedb.filter_retracted(&filter);
+		assert_eq!(traces, vec![create_simple_localized_trace(1, block_1b.clone(), tx_1b.clone())]);
+	}
 }
@@ -24,7 +24,18 @@ To use Parity with transaction tracing, you'll need to resync with tracing.
 To do this, remove or move away your current database and restart parity. e.g.:
 
 > mv ~/.parity/906a34e69aec8c0d /tmp
-> parity";
+> parity
+
+If the database was synced with --pruning archive, you can re-index the traces
+of imported blocks instead:
+
+> parity trace reindex";
+
+const REINDEX_ERR: &'static str =
+"Re-indexing of transaction traces has not finished yet.
+To continue it, run:
+
+> parity trace reindex";
 
 /// `TraceDB` errors.
 #[derive(Debug)]
@@ -32,10 +43,16 @@ pub enum Error {
 	/// Returned when tracing is enabled,
 	/// but database does not contain traces of old transactions.
 	ResyncRequired,
+	/// Returned when re-indexing of traces was started, but hasn't finished.
+	ReindexRequired,
 }
 
 impl Display for Error {
 	fn fmt(&self, f: &mut Formatter) -> Result<(), FmtError> {
-		write!(f, "{}", RESYNC_ERR)
+		let msg = match *self {
+			Error::ResyncRequired => RESYNC_ERR,
+			Error::ReindexRequired => REINDEX_ERR,
+		};
+		write!(f, "{}", msg)
 	}
 }
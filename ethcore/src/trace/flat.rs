@@ -168,7 +168,7 @@ mod tests {
 	use super::{FlatBlockTraces, FlatTransactionTraces, FlatTrace};
 	use util::{U256, Address};
 	use trace::trace::{Action, Res, CallResult, Call, Create, Trace};
-	use trace::BlockTraces;
+	use trace::{BlockTraces, TraceError};
 
 	#[test]
 	fn test_block_from() {
@@ -201,7 +201,7 @@ mod tests {
 							}),
 							subs: vec![
 							],
-							result: Res::FailedCreate
+							result: Res::FailedCreate(TraceError::OutOfGas)
 						},
 						Trace {
 							depth: 3,
@@ -213,10 +213,10 @@ mod tests {
 							}),
 							subs: vec![
 							],
-							result: Res::FailedCreate
+							result: Res::FailedCreate(TraceError::OutOfGas)
 						}
 					],
-					result: Res::FailedCreate
+					result: Res::FailedCreate(TraceError::OutOfGas)
 				},
 				Trace {
 					depth: 3,
@@ -227,7 +227,7 @@ mod tests {
 						init: vec![0x9]
 					}),
 					subs: vec![],
-					result: Res::FailedCreate,
+					result: Res::FailedCreate(TraceError::OutOfGas),
 				}
 			],
 			result: Res::Call(CallResult {
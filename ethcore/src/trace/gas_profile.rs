@@ -0,0 +1,125 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-contract gas profiling aggregated from traces.
+
+use std::collections::BTreeMap;
+use util::{Address, U256};
+use super::trace::{Action, Res};
+
+/// Calls made to a single contract and the gas they used.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ContractGas {
+	/// Number of calls made to the contract, including the one creating it.
+	pub calls: usize,
+	/// Number of those calls which failed.
+	pub failed: usize,
+	/// Cumulative gas used by the calls, including the gas of the calls they made in turn.
+	pub gas_used: U256,
+}
+
+/// Gas used by each contract over a range of traces.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct GasProfile {
+	contracts: BTreeMap<Address, ContractGas>,
+}
+
+impl GasProfile {
+	/// Create an empty profile.
+	pub fn new() -> Self {
+		GasProfile::default()
+	}
+
+	/// Account for a single trace. Failed actions are charged all the gas they were given,
+	/// suicides and rewards are ignored.
+	pub fn accrue(&mut self, action: &Action, result: &Res) {
+		let (address, gas_used, failed) = match (action, result) {
+			(&Action::Call(ref call), &Res::Call(ref res)) => (call.to.clone(), res.gas_used, false),
+			(&Action::Call(ref call), &Res::FailedCall(_)) => (call.to.clone(), call.gas, true),
+			(&Action::Create(_), &Res::Create(ref res)) => (res.address.clone(), res.gas_used, false),
+			_ => return,
+		};
+
+		let contract = self.contracts.entry(address).or_insert_with(ContractGas::default);
+		contract.calls += 1;
+		contract.gas_used = contract.gas_used + gas_used;
+		if failed {
+			contract.failed += 1;
+		}
+	}
+
+	/// Profile of each contract, the one using the most gas first.
+	pub fn contracts(&self) -> Vec<(Address, ContractGas)> {
+		let mut contracts: Vec<_> = self.contracts.iter().map(|(a, c)| (a.clone(), c.clone())).collect();
+		contracts.sort_by(|a, b| b.1.gas_used.cmp(&a.1.gas_used));
+		contracts
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::{Address, U256};
+	use trace::trace::{Action, Res, Call, CallResult, Create, CreateResult, Reward, RewardType};
+	use trace::TraceError;
+	use super::{GasProfile, ContractGas};
+
+	fn call(to: Address, gas: u64) -> Action {
+		Action::Call(Call {
+			from: Address::from(1),
+			to: to,
+			value: U256::zero(),
+			gas: gas.into(),
+			input: vec![],
+		})
+	}
+
+	fn success(gas_used: u64) -> Res {
+		Res::Call(CallResult {
+			gas_used: gas_used.into(),
+			output: vec![],
+		})
+	}
+
+	#[test]
+	fn aggregates_by_contract() {
+		let mut profile = GasProfile::new();
+		profile.accrue(&call(Address::from(10), 1000), &success(300));
+		profile.accrue(&call(Address::from(10), 1000), &success(200));
+		profile.accrue(&call(Address::from(10), 1000), &Res::FailedCall(TraceError::OutOfGas));
+		profile.accrue(&call(Address::from(20), 5000), &success(2000));
+		profile.accrue(&Action::Create(Create {
+			from: Address::from(1),
+			value: U256::zero(),
+			gas: 100000.into(),
+			init: vec![],
+		}), &Res::Create(CreateResult {
+			gas_used: 50000.into(),
+			code: vec![],
+			address: Address::from(30),
+		}));
+		profile.accrue(&Action::Reward(Reward {
+			author: Address::from(10),
+			value: 100.into(),
+			reward_type: RewardType::Block,
+		}), &Res::None);
+
+		assert_eq!(profile.contracts(), vec![
+			(Address::from(30), ContractGas { calls: 1, failed: 0, gas_used: 50000.into() }),
+			(Address::from(20), ContractGas { calls: 1, failed: 0, gas_used: 2000.into() }),
+			(Address::from(10), ContractGas { calls: 3, failed: 1, gas_used: 1500.into() }),
+		]);
+	}
+}
@@ -31,6 +31,8 @@ pub struct ImportRequest {
 	///
 	/// They should be ordered from oldest to newest.
 	pub enacted: Vec<H256>,
-	/// Number of blocks retracted by this import.
-	pub retracted: usize,
+	/// Blocks retracted by this import.
+	///
+	/// They should be ordered from oldest to newest.
+	pub retracted: Vec<H256>,
 }
@@ -23,6 +23,7 @@ mod db;
 mod error;
 mod executive_tracer;
 pub mod flat;
+mod gas_profile;
 mod import;
 mod noop_tracer;
 
@@ -30,11 +31,13 @@ pub use types::trace_types::*;
 pub use self::block::BlockTraces;
 pub use self::config::{Config, Switch};
 pub use self::db::TraceDB;
+pub use self::gas_profile::{GasProfile, ContractGas};
 pub use self::error::Error;
 pub use types::trace_types::trace::{Trace, VMTrace, VMOperation, VMExecutedOperation, MemoryDiff, StorageDiff};
 pub use self::noop_tracer::{NoopTracer, NoopVMTracer};
 pub use self::executive_tracer::{ExecutiveTracer, ExecutiveVMTracer};
 pub use types::trace_types::filter::{Filter, AddressesFilter};
+pub use types::trace_types::error::TraceError;
 pub use self::import::ImportRequest;
 pub use self::localized::LocalizedTrace;
 use util::{Bytes, Address, U256, H256};
@@ -76,10 +79,10 @@ pub trait Tracer: Send {
 	);
 
 	/// Stores failed call trace.
-	fn trace_failed_call(&mut self, call: Option<Call>, depth: usize, subs: Vec<Trace>, delegate_call: bool);
+	fn trace_failed_call(&mut self, call: Option<Call>, depth: usize, subs: Vec<Trace>, delegate_call: bool, error: TraceError);
 
 	/// Stores failed create trace.
-	fn trace_failed_create(&mut self, create: Option<Create>, depth: usize, subs: Vec<Trace>);
+	fn trace_failed_create(&mut self, create: Option<Create>, depth: usize, subs: Vec<Trace>, error: TraceError);
 
 	/// Stores suicide info.
 	fn trace_suicide(&mut self, address: Address, balance: U256, refund_address: Address, depth: usize);
@@ -118,6 +121,9 @@ pub trait DatabaseExtras {
 
 	/// Returns hash of transaction at given position.
 	fn transaction_hash(&self, block_number: BlockNumber, tx_position: usize) -> Option<H256>;
+
+	/// Returns hash of transaction at given position of a block, which may not be canonical.
+	fn block_transaction_hash(&self, block_hash: &H256, tx_position: usize) -> Option<H256>;
 }
 
 /// Db provides an interface to query tracesdb.
@@ -139,4 +145,8 @@ pub trait Database {
 
 	/// Filter traces matching given filter.
 	fn filter(&self, filter: &Filter) -> Vec<LocalizedTrace>;
+
+	/// Filter traces matching given filter among those of blocks in the filter's range
+	/// which were retracted by reorganisations.
+	fn filter_retracted(&self, filter: &Filter) -> Vec<LocalizedTrace>;
 }
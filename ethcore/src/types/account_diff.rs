@@ -22,7 +22,6 @@ use std::fmt;
 use ipc::binary::{BinaryConvertError, BinaryConvertable};
 use util::Bytes;
 use std::collections::{VecDeque, BTreeMap};
-use std::mem;
 
 #[derive(Debug, PartialEq, Eq, Clone, Binary)]
 /// Diff type for specifying a change (or not).
@@ -0,0 +1,32 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Account summary type definition.
+
+use std::collections::VecDeque;
+use util::{Bytes, U256};
+use ipc::binary::BinaryConvertError;
+
+/// Balance, nonce and code of an account at some block.
+#[derive(Debug, Clone, PartialEq, Binary)]
+pub struct AccountSummary {
+	/// Balance of the account.
+	pub balance: U256,
+	/// Nonce of the account.
+	pub nonce: U256,
+	/// Code of the account, empty if it has none.
+	pub code: Bytes,
+}
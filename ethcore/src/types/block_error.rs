@@ -0,0 +1,96 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Block error types
+
+use util::numbers::{U256, H256};
+use util::error::{Mismatch, OutOfBounds};
+use header::BlockNumber;
+use basic_types::LogBloom;
+use ipc::binary::BinaryConvertError;
+use std::collections::VecDeque;
+
+#[derive(Debug, PartialEq, Eq, Binary)]
+/// Errors concerning block processing.
+pub enum BlockError {
+	/// Block has too many uncles.
+	TooManyUncles(OutOfBounds<usize>),
+	/// Extra data is of an invalid length.
+	ExtraDataOutOfBounds(OutOfBounds<usize>),
+	/// Seal is incorrect format.
+	InvalidSealArity(Mismatch<usize>),
+	/// Block has too much gas used.
+	TooMuchGasUsed(OutOfBounds<U256>),
+	/// Uncles hash in header is invalid.
+	InvalidUnclesHash(Mismatch<H256>),
+	/// An uncle is from a generation too old.
+	UncleTooOld(OutOfBounds<BlockNumber>),
+	/// An uncle is from the same generation as the block.
+	UncleIsBrother(OutOfBounds<BlockNumber>),
+	/// An uncle is already in the chain.
+	UncleInChain(H256),
+	/// An uncle has a parent not in the chain.
+	UncleParentNotInChain(H256),
+	/// State root header field is invalid.
+	InvalidStateRoot(Mismatch<H256>),
+	/// Gas used header field is invalid.
+	InvalidGasUsed(Mismatch<U256>),
+	/// Transactions root header field is invalid.
+	InvalidTransactionsRoot(Mismatch<H256>),
+	/// Difficulty is out of range; this can be used as an looser error prior to getting a definitive
+	/// value for difficulty. This error needs only provide bounds of which it is out.
+	DifficultyOutOfBounds(OutOfBounds<U256>),
+	/// Difficulty header field is invalid; this is a strong error used after getting a definitive
+	/// value for difficulty (which is provided).
+	InvalidDifficulty(Mismatch<U256>),
+	/// Seal element of type H256 (max_hash for Ethash, but could be something else for
+	/// other seal engines) is out of bounds.
+	MismatchedH256SealElement(Mismatch<H256>),
+	/// Proof-of-work aspect of seal, which we assume is a 256-bit value, is invalid.
+	InvalidProofOfWork(OutOfBounds<U256>),
+	/// Some low-level aspect of the seal is incorrect.
+	InvalidSeal,
+	/// Gas limit header field is invalid.
+	InvalidGasLimit(OutOfBounds<U256>),
+	/// Receipts trie root header field is invalid.
+	InvalidReceiptsRoot(Mismatch<H256>),
+	/// Timestamp header field is invalid.
+	InvalidTimestamp(OutOfBounds<u64>),
+	/// Log bloom header field is invalid.
+	InvalidLogBloom(Mismatch<LogBloom>),
+	/// Parent hash field of header is invalid; this is an invalid error indicating a logic flaw in the codebase.
+	/// TODO: remove and favour an assert!/panic!.
+	InvalidParentHash(Mismatch<H256>),
+	/// Number field of header is invalid.
+	InvalidNumber(Mismatch<BlockNumber>),
+	/// Block number isn't sensible.
+	RidiculousNumber(OutOfBounds<BlockNumber>),
+	/// Parent given is unknown.
+	UnknownParent(H256),
+	/// Uncle parent given is unknown.
+	UnknownUncleParent(H256),
+}
+
+#[derive(Debug, PartialEq, Binary)]
+/// Import to the block queue result
+pub enum ImportError {
+	/// Already in the block chain.
+	AlreadyInChain,
+	/// Already in the block queue.
+	AlreadyQueued,
+	/// Already marked as bad from a previous import (could mean parent is bad).
+	KnownBad,
+}
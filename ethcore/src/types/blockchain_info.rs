@@ -19,7 +19,6 @@
 use util::numbers::*;
 use header::BlockNumber;
 use ipc::binary::BinaryConvertError;
-use std::mem;
 use std::collections::VecDeque;
 
 /// Information about the blockchain gathered together.
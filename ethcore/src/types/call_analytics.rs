@@ -16,7 +16,6 @@
 
 //! Call analytics related types
 
-use std::mem;
 use ipc::binary::{BinaryConvertError};
 use std::collections::VecDeque;
 
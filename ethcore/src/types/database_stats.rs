@@ -0,0 +1,78 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Database statistics types
+
+use std::str::FromStr;
+use ipc::binary::BinaryConvertError;
+use std::collections::VecDeque;
+
+/// Database used by the client.
+#[derive(Debug, Clone, Copy, PartialEq, Binary)]
+pub enum DatabaseColumn {
+	/// Block headers and bodies.
+	Blocks,
+	/// Block details, hashes, receipts and blooms.
+	Extras,
+	/// State trie and its journal.
+	State,
+	/// Transaction traces.
+	Traces,
+}
+
+impl DatabaseColumn {
+	/// Returns all databases used by the client.
+	pub fn all() -> Vec<DatabaseColumn> {
+		vec![DatabaseColumn::Blocks, DatabaseColumn::Extras, DatabaseColumn::State, DatabaseColumn::Traces]
+	}
+
+	/// Returns the name of the database.
+	pub fn name(&self) -> &'static str {
+		match *self {
+			DatabaseColumn::Blocks => "blocks",
+			DatabaseColumn::Extras => "extras",
+			DatabaseColumn::State => "state",
+			DatabaseColumn::Traces => "traces",
+		}
+	}
+}
+
+impl FromStr for DatabaseColumn {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		DatabaseColumn::all().into_iter()
+			.find(|c| c.name() == s)
+			.ok_or_else(|| format!("Unknown database: {}", s))
+	}
+}
+
+/// Statistics of a single database, as estimated by RocksDB.
+#[derive(Debug, Clone, PartialEq, Binary)]
+pub struct DatabaseStats {
+	/// Database described.
+	pub column: DatabaseColumn,
+	/// Estimated number of keys.
+	pub keys: u64,
+	/// Estimated size of live data in bytes.
+	pub live_data_bytes: u64,
+	/// Total size of the table files in bytes.
+	pub sst_files_bytes: u64,
+	/// Number of table files at each compaction level, starting at level 0.
+	pub files_per_level: Vec<u64>,
+	/// Whether RocksDB is waiting to run a compaction.
+	pub compaction_pending: bool,
+}
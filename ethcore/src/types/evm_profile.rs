@@ -0,0 +1,43 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! EVM profile type definitions.
+
+use std::collections::VecDeque;
+use util::{Address, U256};
+use ipc::binary::BinaryConvertError;
+
+/// Number of executions and gas used by a single instruction.
+#[derive(Debug, Clone, PartialEq, Binary)]
+pub struct InstructionProfile {
+	/// Name of the instruction.
+	pub name: String,
+	/// Number of times the instruction was executed.
+	pub count: u64,
+	/// Gas used by the instruction. For calls and creations, includes the gas used by the callee.
+	pub gas: U256,
+}
+
+/// Instructions executed in the code of a single contract.
+#[derive(Debug, Clone, PartialEq, Binary)]
+pub struct ContractProfile {
+	/// Address of the contract whose code was executed.
+	pub address: Address,
+	/// Gas used by all the instructions.
+	pub gas: U256,
+	/// Executed instructions, the one using the most gas first.
+	pub instructions: Vec<InstructionProfile>,
+}
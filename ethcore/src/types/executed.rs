@@ -23,7 +23,6 @@ use types::log_entry::LogEntry;
 use types::state_diff::StateDiff;
 use ipc::binary::BinaryConvertError;
 use std::fmt;
-use std::mem;
 use std::collections::VecDeque;
 
 /// Transaction execution receipt.
@@ -64,6 +63,9 @@ pub struct Executed {
 	pub vm_trace: Option<VMTrace>,
 	/// The state diff, if we traced it.
 	pub state_diff: Option<StateDiff>,
+	/// True if the execution ended with an exception (e.g. out of gas),
+	/// in which case all gas paid up front has been consumed.
+	pub exception: bool,
 }
 
 /// Result of executing the transaction.
@@ -133,5 +135,66 @@ impl fmt::Display for ExecutionError {
 	}
 }
 
+/// Error replaying a transaction of the chain.
+#[derive(Debug, PartialEq, Clone, Binary)]
+pub enum ReplayError {
+	/// Transaction is not in the chain.
+	TransactionNotFound,
+	/// Block is not in the chain.
+	BlockNotFound,
+	/// State the transaction was executed on is not available, e.g. it has been pruned.
+	StatePruned,
+	/// Error executing the transaction.
+	Execution(ExecutionError),
+}
+
+impl fmt::Display for ReplayError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use self::ReplayError::*;
+
+		match *self {
+			TransactionNotFound => f.write_str("Transaction not found."),
+			BlockNotFound => f.write_str("Block not found."),
+			StatePruned => f.write_str("State of the transaction's block is not available."),
+			Execution(ref err) => err.fmt(f),
+		}
+	}
+}
+
+impl From<ExecutionError> for ReplayError {
+	fn from(err: ExecutionError) -> Self {
+		ReplayError::Execution(err)
+	}
+}
+
+/// Error making a non-persistent transaction call.
+#[derive(Debug, PartialEq, Clone, Binary)]
+pub enum CallError {
+	/// The block the call was made on is not known.
+	UnknownBlock,
+	/// State of the block the call was made on is not available, e.g. it has been pruned.
+	StatePruned,
+	/// Error executing the call.
+	Execution(ExecutionError),
+}
+
+impl fmt::Display for CallError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use self::CallError::*;
+
+		match *self {
+			UnknownBlock => f.write_str("Unknown block."),
+			StatePruned => f.write_str("State of the block is not available."),
+			Execution(ref err) => err.fmt(f),
+		}
+	}
+}
+
+impl From<ExecutionError> for CallError {
+	fn from(err: ExecutionError) -> Self {
+		CallError::Execution(err)
+	}
+}
+
 /// Transaction execution result.
 pub type ExecutionResult = Result<Executed, ExecutionError>;
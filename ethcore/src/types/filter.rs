@@ -21,7 +21,6 @@ use util::sha3::*;
 use client::BlockID;
 use log_entry::LogEntry;
 use ipc::binary::BinaryConvertError;
-use std::mem;
 use std::collections::VecDeque;
 
 /// Blockchain Filter.
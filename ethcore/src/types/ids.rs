@@ -19,7 +19,6 @@
 use util::hash::H256;
 use header::BlockNumber;
 use ipc::binary::BinaryConvertError;
-use std::mem;
 use std::collections::VecDeque;
 
 /// Uniquely identifies block.
@@ -27,14 +26,19 @@ use std::collections::VecDeque;
 pub enum BlockID {
 	/// Block's sha3.
 	/// Querying by hash is always faster.
+	#[ipc(tag = "0")]
 	Hash(H256),
 	/// Block number within canon blockchain.
+	#[ipc(tag = "1")]
 	Number(BlockNumber),
 	/// Earliest block (genesis).
+	#[ipc(tag = "2")]
 	Earliest,
 	/// Latest mined block.
+	#[ipc(tag = "3")]
 	Latest,
 	/// Pending block.
+	#[ipc(tag = "4")]
 	Pending,
 }
 
@@ -42,9 +46,11 @@ pub enum BlockID {
 #[derive(Debug, PartialEq, Clone, Hash, Eq, Binary)]
 pub enum TransactionID {
 	/// Transaction's sha3.
+	#[ipc(tag = "0")]
 	Hash(H256),
 	/// Block id and transaction index within this block.
 	/// Querying by block position is always faster.
+	#[ipc(tag = "1")]
 	Location(BlockID, usize)
 }
 
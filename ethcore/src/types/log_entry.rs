@@ -26,7 +26,6 @@ use basic_types::LogBloom;
 use header::BlockNumber;
 use ethjson;
 use ipc::binary::BinaryConvertError;
-use std::mem;
 use std::collections::VecDeque;
 
 /// A record of execution for a `LOG` operation.
@@ -0,0 +1,36 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Block author statistics type definition.
+
+use std::collections::VecDeque;
+use util::{Address, U256};
+use ipc::binary::BinaryConvertError;
+
+/// Summary of the blocks authored by a single address within a range of canon blocks.
+#[derive(Debug, Clone, PartialEq, Binary)]
+pub struct MinerStats {
+	/// Author of the blocks.
+	pub author: Address,
+	/// Number of blocks authored.
+	pub blocks: u64,
+	/// Number of uncles included in those blocks.
+	pub uncles: u64,
+	/// Transaction fees paid in those blocks.
+	pub fees: U256,
+	/// Block and uncle inclusion rewards, excluding the fees.
+	pub reward: U256,
+}
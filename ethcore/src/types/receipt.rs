@@ -23,7 +23,6 @@ use basic_types::LogBloom;
 use header::BlockNumber;
 use log_entry::{LogEntry, LocalizedLogEntry};
 use ipc::binary::BinaryConvertError;
-use std::mem;
 use std::collections::VecDeque;
 
 /// Information describing execution of a transaction.
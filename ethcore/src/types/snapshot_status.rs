@@ -0,0 +1,35 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Snapshot creation status type definition.
+
+use std::collections::VecDeque;
+use ipc::binary::BinaryConvertError;
+
+/// State of snapshot creation.
+#[derive(Debug, Clone, Default, PartialEq, Binary)]
+pub struct SnapshotStatus {
+	/// Number of the block a snapshot is being taken at, if any.
+	pub creating: Option<u64>,
+	/// Number of accounts written to the snapshot being taken.
+	pub accounts: u64,
+	/// Number of blocks written to the snapshot being taken.
+	pub blocks: u64,
+	/// Compressed size of the chunks written to the snapshot being taken.
+	pub size: u64,
+	/// Number of the block of the latest complete snapshot, if any.
+	pub last_snapshot: Option<u64>,
+}
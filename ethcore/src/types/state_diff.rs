@@ -19,7 +19,6 @@
 use util::numbers::*;
 use account_diff::*;
 use ipc::binary::BinaryConvertError;
-use std::mem;
 use std::fmt;
 use std::ops::*;
 use std::collections::{VecDeque, BTreeMap};
@@ -0,0 +1,35 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! State override type definitions.
+
+use std::collections::{BTreeMap, VecDeque};
+use util::{Address, Bytes, H256, U256};
+use ipc::binary::BinaryConvertError;
+
+/// Changes applied to a single account before executing a call.
+#[derive(Debug, Default, Clone, PartialEq, Binary)]
+pub struct AccountOverride {
+	/// New balance of the account.
+	pub balance: Option<U256>,
+	/// New code of the account.
+	pub code: Option<Bytes>,
+	/// Storage entries to set. Other entries are left intact.
+	pub storage: BTreeMap<H256, H256>,
+}
+
+/// Account changes applied to a copy of the state before executing a call.
+pub type StateOverride = BTreeMap<Address, AccountOverride>;
@@ -0,0 +1,70 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Storage range proof type definition.
+
+use std::collections::VecDeque;
+use util::{Address, Bytes, H256, U256, Hashable};
+use util::rlp::{encode, UntrustedRlp, View};
+use util::trie::{RangeProof, TrieError, verify_range};
+use ipc::binary::BinaryConvertError;
+
+/// Contents of a contiguous range of a contract's storage, with the trie nodes proving
+/// them against the state root of a block.
+///
+/// Storage is keyed by the Keccak hash of the storage position, so the range
+/// is a range of hashed positions.
+#[derive(Debug, Clone, PartialEq, Binary)]
+pub struct StorageRangeProof {
+	/// RLP of the account.
+	pub account: Bytes,
+	/// State trie nodes proving the account.
+	pub account_proof: Vec<Bytes>,
+	/// Hashed storage positions in the range, in order.
+	pub keys: Vec<H256>,
+	/// Storage values at the positions.
+	pub values: Vec<H256>,
+	/// Storage trie nodes proving the values.
+	pub storage_proof: Vec<Bytes>,
+}
+
+impl StorageRangeProof {
+	/// Storage root of the account.
+	pub fn storage_root(&self) -> Result<H256, TrieError> {
+		UntrustedRlp::new(&self.account).val_at(2).map_err(|_| TrieError::InvalidProof)
+	}
+
+	/// Check the proof of the storage of `address` from hashed position `from` on
+	/// against the given state root.
+	pub fn verify(&self, state_root: &H256, address: &Address, from: &H256) -> Result<(), TrieError> {
+		let account_key = address.sha3();
+		try!(verify_range(state_root, &account_key, &RangeProof {
+			items: vec![(account_key.to_vec(), self.account.clone())],
+			nodes: self.account_proof.clone(),
+		}));
+
+		if self.keys.len() != self.values.len() {
+			return Err(TrieError::InvalidProof);
+		}
+		let items = self.keys.iter().zip(self.values.iter())
+			.map(|(key, value)| (key.to_vec(), encode(&U256::from(value.as_slice())).to_vec()))
+			.collect();
+		verify_range(&try!(self.storage_root()), from, &RangeProof {
+			items: items,
+			nodes: self.storage_proof.clone(),
+		})
+	}
+}
@@ -16,7 +16,6 @@
 
 //! Trace filter related types
 
-use std::mem;
 use ipc::binary::{BinaryConvertError};
 use std::collections::VecDeque;
 use std::ops::Range;
@@ -32,4 +31,6 @@ pub struct Filter {
 	pub from_address: Vec<Address>,
 	/// To address.
 	pub to_address: Vec<Address>,
+	/// Whether to include traces of blocks retracted by reorganisations.
+	pub include_retracted: bool,
 }
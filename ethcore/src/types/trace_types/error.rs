@@ -0,0 +1,120 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Trace errors.
+
+use std::fmt;
+use util::rlp::{Encodable, RlpStream, Decodable, Decoder, DecoderError, Stream, View};
+use evm::Error as EvmError;
+
+/// Trace evm errors.
+#[derive(Debug, PartialEq, Clone, Binary)]
+pub enum TraceError {
+	/// `OutOfGas` is returned when transaction execution runs out of gas.
+	OutOfGas,
+	/// `BadJumpDestination` is returned when execution tried to move
+	/// to position that wasn't marked with JUMPDEST instruction
+	BadJumpDestination,
+	/// `BadInstructions` is returned when given instruction is not supported
+	BadInstruction,
+	/// `StackUnderflow` when there is not enough stack elements to execute instruction
+	StackUnderflow,
+	/// When execution would exceed defined Stack Limit
+	OutOfStack,
+	/// Returned on evm internal error.
+	Internal,
+}
+
+impl<'a> From<&'a EvmError> for TraceError {
+	fn from(e: &'a EvmError) -> Self {
+		match *e {
+			EvmError::OutOfGas => TraceError::OutOfGas,
+			EvmError::BadJumpDestination { .. } => TraceError::BadJumpDestination,
+			EvmError::BadInstruction { .. } => TraceError::BadInstruction,
+			EvmError::StackUnderflow { .. } => TraceError::StackUnderflow,
+			EvmError::OutOfStack { .. } => TraceError::OutOfStack,
+			EvmError::Internal => TraceError::Internal,
+		}
+	}
+}
+
+impl fmt::Display for TraceError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use self::TraceError::*;
+		let message = match *self {
+			OutOfGas => "Out of gas",
+			BadJumpDestination => "Bad jump destination",
+			BadInstruction => "Bad instruction",
+			StackUnderflow => "Stack underflow",
+			OutOfStack => "Out of stack",
+			Internal => "Internal error",
+		};
+		message.fmt(f)
+	}
+}
+
+impl Encodable for TraceError {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		let value = match *self {
+			TraceError::OutOfGas => 0u8,
+			TraceError::BadJumpDestination => 1,
+			TraceError::BadInstruction => 2,
+			TraceError::StackUnderflow => 3,
+			TraceError::OutOfStack => 4,
+			TraceError::Internal => 5,
+		};
+		s.append(&value);
+	}
+}
+
+impl Decodable for TraceError {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		let value: u8 = try!(d.as_val());
+		match value {
+			0 => Ok(TraceError::OutOfGas),
+			1 => Ok(TraceError::BadJumpDestination),
+			2 => Ok(TraceError::BadInstruction),
+			3 => Ok(TraceError::StackUnderflow),
+			4 => Ok(TraceError::OutOfStack),
+			5 => Ok(TraceError::Internal),
+			_ => Err(DecoderError::Custom("Invalid error type")),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use util::rlp::{encode, decode};
+	use super::TraceError;
+
+	#[test]
+	fn encode_error() {
+		let errors = vec![
+			TraceError::OutOfGas,
+			TraceError::BadJumpDestination,
+			TraceError::BadInstruction,
+			TraceError::StackUnderflow,
+			TraceError::OutOfStack,
+			TraceError::Internal,
+		];
+		for error in errors {
+			let encoded = encode(&error);
+			let decoded: TraceError = decode(&encoded);
+			assert_eq!(decoded, error);
+		}
+	}
+}
@@ -24,7 +24,6 @@ use basic_types::LogBloom;
 use trace::flat::FlatTrace;
 use types::trace_types::trace::{Action, Res};
 use ipc::binary::BinaryConvertError;
-use std::mem;
 use std::collections::VecDeque;
 
 /// Addresses filter.
@@ -108,6 +107,11 @@ impl Filter {
 		self.to_address.with_blooms(self.from_address.blooms())
 	}
 
+	/// Returns true if given bloom may contain traces matching the filter.
+	pub fn matches_bloom(&self, bloom: &LogBloom) -> bool {
+		self.bloom_possibilities().iter().any(|possibility| bloom.contains(possibility))
+	}
+
 	/// Returns true if given trace matches the filter.
 	pub fn matches(&self, trace: &FlatTrace) -> bool {
 		let action = match trace.action {
@@ -125,6 +129,11 @@ impl Filter {
 				let from_matches = self.from_address.matches(&suicide.address);
 				let to_matches = self.to_address.matches(&suicide.refund_address);
 				from_matches && to_matches
+			},
+			Action::Reward(ref reward) => {
+				let from_matches = self.from_address.matches_all();
+				let to_matches = self.to_address.matches(&reward.author);
+				from_matches && to_matches
 			}
 		};
 
@@ -139,9 +148,9 @@ impl Filter {
 mod tests {
 	use util::{FixedHash, Address};
 	use util::sha3::Hashable;
-	use trace::trace::{Action, Call, Res, Create, CreateResult, Suicide};
+	use trace::trace::{Action, Call, Res, Create, CreateResult, Suicide, Reward, RewardType};
 	use trace::flat::FlatTrace;
-	use trace::{Filter, AddressesFilter};
+	use trace::{Filter, AddressesFilter, TraceError};
 	use basic_types::LogBloom;
 
 	#[test]
@@ -286,7 +295,7 @@ mod tests {
 				gas: 4.into(),
 				input: vec![0x5],
 			}),
-			result: Res::FailedCall,
+			result: Res::FailedCall(TraceError::OutOfGas),
 			trace_address: vec![0],
 			subtraces: 0,
 		};
@@ -341,5 +350,62 @@ mod tests {
 		assert!(f4.matches(&trace));
 		assert!(f5.matches(&trace));
 		assert!(!f6.matches(&trace));
+
+		let trace = FlatTrace {
+			action: Action::Reward(Reward {
+				author: 2.into(),
+				value: 3.into(),
+				reward_type: RewardType::Block,
+			}),
+			result: Res::None,
+			trace_address: vec![],
+			subtraces: 0
+		};
+
+		assert!(!f0.matches(&trace));
+		assert!(!f1.matches(&trace));
+		assert!(f2.matches(&trace));
+		assert!(f3.matches(&trace));
+		assert!(f4.matches(&trace));
+		assert!(!f5.matches(&trace));
+		assert!(!f6.matches(&trace));
+	}
+
+	#[test]
+	fn filter_matches_bloom() {
+		let filter = Filter {
+			range: (0..0),
+			from_address: AddressesFilter::from(vec![Address::from(1)]),
+			to_address: AddressesFilter::from(vec![Address::from(2), Address::from(3)]),
+		};
+
+		let trace = FlatTrace {
+			action: Action::Call(Call {
+				from: 1.into(),
+				to: 3.into(),
+				value: 3.into(),
+				gas: 4.into(),
+				input: vec![0x5],
+			}),
+			result: Res::FailedCall(TraceError::OutOfGas),
+			trace_address: vec![0],
+			subtraces: 0,
+		};
+		assert!(filter.matches_bloom(&trace.bloom()));
+
+		let trace = FlatTrace {
+			action: Action::Call(Call {
+				from: 2.into(),
+				to: 3.into(),
+				value: 3.into(),
+				gas: 4.into(),
+				input: vec![0x5],
+			}),
+			result: Res::FailedCall(TraceError::OutOfGas),
+			trace_address: vec![0],
+			subtraces: 0,
+		};
+		assert!(!filter.matches_bloom(&trace.bloom()));
+		assert!(!filter.matches_bloom(&LogBloom::new()));
 	}
 }
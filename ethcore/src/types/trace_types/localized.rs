@@ -20,11 +20,10 @@ use util::H256;
 use super::trace::{Action, Res};
 use header::BlockNumber;
 use ipc::binary::BinaryConvertError;
-use std::mem;
 use std::collections::VecDeque;
 
 /// Localized trace.
-#[derive(Debug, PartialEq, Binary)]
+#[derive(Debug, PartialEq, Clone, Binary)]
 pub struct LocalizedTrace {
 	/// Type of action performed by a transaction.
 	pub action: Action,
@@ -36,10 +35,10 @@ pub struct LocalizedTrace {
 	///
 	/// [index in root, index in first CALL, index in second CALL, ...]
 	pub trace_address: Vec<usize>,
-	/// Transaction number within the block.
-	pub transaction_number: usize,
-	/// Signed transaction hash.
-	pub transaction_hash: H256,
+	/// Transaction number within the block, `None` for block rewards.
+	pub transaction_number: Option<usize>,
+	/// Signed transaction hash, `None` for block rewards.
+	pub transaction_hash: Option<H256>,
 	/// Block number.
 	pub block_number: BlockNumber,
 	/// Block hash.
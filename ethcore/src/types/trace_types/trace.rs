@@ -21,8 +21,8 @@ use util::rlp::*;
 use util::sha3::Hashable;
 use action_params::ActionParams;
 use basic_types::LogBloom;
+use super::error::TraceError;
 use ipc::binary::BinaryConvertError;
-use std::mem;
 use std::collections::VecDeque;
 
 /// `Call` result.
@@ -246,15 +246,85 @@ impl Decodable for Suicide {
 		let res = Suicide {
 			address: try!(d.val_at(0)),
 			refund_address: try!(d.val_at(1)),
-			balance: try!(d.val_at(3)),
+			balance: try!(d.val_at(2)),
 		};
 
 		Ok(res)
 	}
 }
 
+/// Kind of a reward.
+#[derive(Debug, Clone, PartialEq, Binary)]
+pub enum RewardType {
+	/// Reward for the author of a block.
+	Block,
+	/// Reward for the author of an uncle included in a block.
+	Uncle,
+}
+
+impl Encodable for RewardType {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		let v = match *self {
+			RewardType::Block => 0u8,
+			RewardType::Uncle => 1u8,
+		};
+		s.append(&v);
+	}
+}
 
-/// Description of an action that we trace; will be either a call or a create.
+impl Decodable for RewardType {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let v: u8 = try!(decoder.as_rlp().as_val());
+		match v {
+			0 => Ok(RewardType::Block),
+			1 => Ok(RewardType::Uncle),
+			_ => Err(DecoderError::Custom("Invalid reward type.")),
+		}
+	}
+}
+
+/// Reward action, paid out when a block is closed.
+#[derive(Debug, Clone, PartialEq, Binary)]
+pub struct Reward {
+	/// Rewarded author.
+	pub author: Address,
+	/// Value of the reward.
+	pub value: U256,
+	/// Kind of the reward.
+	pub reward_type: RewardType,
+}
+
+impl Reward {
+	/// Return reward action bloom.
+	pub fn bloom(&self) -> LogBloom {
+		LogBloom::from_bloomed(&self.author.sha3())
+	}
+}
+
+impl Encodable for Reward {
+	fn rlp_append(&self, s: &mut RlpStream) {
+		s.begin_list(3);
+		s.append(&self.author);
+		s.append(&self.value);
+		s.append(&self.reward_type);
+	}
+}
+
+impl Decodable for Reward {
+	fn decode<D>(decoder: &D) -> Result<Self, DecoderError> where D: Decoder {
+		let d = decoder.as_rlp();
+		let res = Reward {
+			author: try!(d.val_at(0)),
+			value: try!(d.val_at(1)),
+			reward_type: try!(d.val_at(2)),
+		};
+
+		Ok(res)
+	}
+}
+
+
+/// Description of an action that we trace.
 #[derive(Debug, Clone, PartialEq, Binary)]
 pub enum Action {
 	/// It's a call action.
@@ -263,6 +333,8 @@ pub enum Action {
 	Create(Create),
 	/// Suicide.
 	Suicide(Suicide),
+	/// Block or uncle reward.
+	Reward(Reward),
 }
 
 impl Encodable for Action {
@@ -280,6 +352,10 @@ impl Encodable for Action {
 			Action::Suicide(ref suicide) => {
 				s.append(&2u8);
 				s.append(suicide);
+			},
+			Action::Reward(ref reward) => {
+				s.append(&3u8);
+				s.append(reward);
 			}
 		}
 	}
@@ -292,7 +368,8 @@ impl Decodable for Action {
 		match action_type {
 			0 => d.val_at(1).map(Action::Call),
 			1 => d.val_at(1).map(Action::Create),
-			2 => d.val_at(2).map(Action::Suicide),
+			2 => d.val_at(1).map(Action::Suicide),
+			3 => d.val_at(1).map(Action::Reward),
 			_ => Err(DecoderError::Custom("Invalid action type.")),
 		}
 	}
@@ -305,6 +382,7 @@ impl Action {
 			Action::Call(ref call) => call.bloom(),
 			Action::Create(ref create) => create.bloom(),
 			Action::Suicide(ref suicide) => suicide.bloom(),
+			Action::Reward(ref reward) => reward.bloom(),
 		}
 	}
 }
@@ -317,9 +395,9 @@ pub enum Res {
 	/// Successful create action result.
 	Create(CreateResult),
 	/// Failed call.
-	FailedCall,
+	FailedCall(TraceError),
 	/// Failed create.
-	FailedCreate,
+	FailedCreate(TraceError),
 	/// None
 	None,
 }
@@ -337,13 +415,15 @@ impl Encodable for Res {
 				s.append(&1u8);
 				s.append(create);
 			},
-			Res::FailedCall => {
-				s.begin_list(1);
+			Res::FailedCall(ref error) => {
+				s.begin_list(2);
 				s.append(&2u8);
+				s.append(error);
 			},
-			Res::FailedCreate => {
-				s.begin_list(1);
+			Res::FailedCreate(ref error) => {
+				s.begin_list(2);
 				s.append(&3u8);
+				s.append(error);
 			},
 			Res::None => {
 				s.begin_list(1);
@@ -360,20 +440,28 @@ impl Decodable for Res {
 		match action_type {
 			0 => d.val_at(1).map(Res::Call),
 			1 => d.val_at(1).map(Res::Create),
-			2 => Ok(Res::FailedCall),
-			3 => Ok(Res::FailedCreate),
+			2 => failure_reason(d).map(Res::FailedCall),
+			3 => failure_reason(d).map(Res::FailedCreate),
 			4 => Ok(Res::None),
 			_ => Err(DecoderError::Custom("Invalid result type.")),
 		}
 	}
 }
 
+// traces recorded before failure reasons were added don't carry one.
+fn failure_reason(d: &UntrustedRlp) -> Result<TraceError, DecoderError> {
+	match d.item_count() {
+		1 => Ok(TraceError::Internal),
+		_ => d.val_at(1),
+	}
+}
+
 impl Res {
 	/// Returns result bloom.
 	pub fn bloom(&self) -> LogBloom {
 		match *self {
 			Res::Create(ref create) => create.bloom(),
-			Res::Call(_) | Res::FailedCall | Res::FailedCreate | Res::None => Default::default(),
+			Res::Call(_) | Res::FailedCall(_) | Res::FailedCreate(_) | Res::None => Default::default(),
 		}
 	}
 }
@@ -592,7 +680,23 @@ mod tests {
 	use util::{Address, U256, FixedHash};
 	use util::rlp::{encode, decode};
 	use util::sha3::Hashable;
-	use trace::trace::{Call, CallResult, Create, Res, Action, Trace, Suicide, CreateResult};
+	use trace::trace::{Call, CallResult, Create, Res, Action, Trace, Suicide, CreateResult, Reward, RewardType};
+	use trace::TraceError;
+
+	#[test]
+	fn failed_results_rlp() {
+		use util::rlp::{RlpStream, Stream};
+
+		let result = Res::FailedCall(TraceError::BadJumpDestination);
+		let decoded: Res = decode(&encode(&result));
+		assert_eq!(decoded, result);
+
+		// without a reason, as recorded before reasons were added.
+		let mut stream = RlpStream::new_list(1);
+		stream.append(&3u8);
+		let decoded: Res = decode(&stream.out());
+		assert_eq!(decoded, Res::FailedCreate(TraceError::Internal));
+	}
 
 	#[test]
 	fn traces_rlp() {
@@ -615,7 +719,7 @@ mod tests {
 						init: vec![0x9]
 					}),
 					subs: vec![],
-					result: Res::FailedCreate
+					result: Res::FailedCreate(TraceError::OutOfGas)
 				}
 			],
 			result: Res::Call(CallResult {
@@ -685,4 +789,24 @@ mod tests {
 		assert!(bloom.contains_bloomed(&Address::from(102).sha3()));
 		assert!(!bloom.contains_bloomed(&Address::from(103).sha3()));
 	}
+
+	#[test]
+	fn suicide_and_reward_rlp() {
+		let suicide = Action::Suicide(Suicide {
+			address: 101.into(),
+			refund_address: 102.into(),
+			balance: 103.into(),
+		});
+		let decoded: Action = decode(&encode(&suicide));
+		assert_eq!(decoded, suicide);
+
+		let reward = Action::Reward(Reward {
+			author: 104.into(),
+			value: 105.into(),
+			reward_type: RewardType::Uncle,
+		});
+		let decoded: Action = decode(&encode(&reward));
+		assert_eq!(decoded, reward);
+		assert!(reward.bloom().contains_bloomed(&Address::from(104).sha3()));
+	}
 }
@@ -27,7 +27,6 @@ use evm::Schedule;
 use header::BlockNumber;
 use ethjson;
 use ipc::binary::BinaryConvertError;
-use std::mem;
 use std::collections::VecDeque;
 
 #[derive(Debug, Clone, PartialEq, Eq, Binary)]
@@ -16,13 +16,11 @@
 
 //! Transaction import result related types
 
-use ipc::binary::{BinaryConvertError, BinaryConvertable};
+use ipc::binary::BinaryConvertError;
 use std::collections::VecDeque;
 use error::{TransactionError, Error};
-use std::mem;
-use util::Populatable;
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Binary)]
 /// Represents the result of importing transaction.
 pub enum TransactionImportResult {
 	/// Transaction was imported to current queue.
@@ -31,8 +29,6 @@ pub enum TransactionImportResult {
 	Future
 }
 
-binary_fixed_size!(TransactionImportResult);
-
 /// Api-level error for transaction import
 #[derive(Debug, Clone, Binary)]
 pub enum TransactionImportError {
@@ -19,7 +19,6 @@
 use util::numbers::H256;
 use ipc::BinaryConvertError;
 use std::collections::VecDeque;
-use std::mem;
 
 /// Represents a tree route between `from` block and `to` block:
 #[derive(Debug, Binary)]
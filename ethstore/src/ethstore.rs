@@ -19,7 +19,7 @@ use std::sync::RwLock;
 use ethkey::KeyPair;
 use crypto::KEY_ITERATIONS;
 use random::Random;
-use ethkey::{Signature, Address, Message, Secret};
+use ethkey::{Signature, Address, Message, Secret, verify_address};
 use dir::KeyDirectory;
 use account::SafeAccount;
 use {Error, SecretStore};
@@ -105,4 +105,15 @@ impl SecretStore for EthStore {
 		let account = try!(cache.get(account).ok_or(Error::InvalidAccount));
 		account.sign(password, message)
 	}
+
+	fn sign_with_password(&self, account: &Address, password: &str, message: &Message) -> Result<Signature, Error> {
+		// keep the cache locked, so the account can't be removed or re-encrypted in the meantime
+		let cache = self.cache.read().unwrap();
+		let safe_account = try!(cache.get(account).ok_or(Error::InvalidAccount));
+		let signature = try!(safe_account.sign(password, message));
+		if !try!(verify_address(account, &signature, message)) {
+			return Err(Error::InvalidSecret);
+		}
+		Ok(signature)
+	}
 }
@@ -27,5 +27,9 @@ pub trait SecretStore: Send + Sync {
 	fn remove_account(&self, account: &Address, password: &str) -> Result<(), Error>;
 
 	fn sign(&self, account: &Address, password: &str, message: &Message) -> Result<Signature, Error>;
+
+	/// Decrypts the account, signs the message and drops the secret again in a single step.
+	/// Returned signature is guaranteed to recover to `account`.
+	fn sign_with_password(&self, account: &Address, password: &str, message: &Message) -> Result<Signature, Error>;
 }
 
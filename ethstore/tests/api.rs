@@ -21,7 +21,7 @@ mod util;
 
 use std::str::FromStr;
 use ethstore::{SecretStore, EthStore};
-use ethstore::ethkey::{Random, Generator, Secret, Address};
+use ethstore::ethkey::{Random, Generator, Secret, Address, verify_address};
 use ethstore::dir::DiskDirectory;
 use util::TransientDir;
 
@@ -64,6 +64,20 @@ fn secret_store_sign() {
 	assert!(store.sign(&accounts[0], "1", &Default::default()).is_err());
 }
 
+#[test]
+fn secret_store_sign_with_password() {
+	let dir = TransientDir::create().unwrap();
+	let store = EthStore::open(Box::new(dir)).unwrap();
+	assert!(store.insert_account(random_secret(), "").is_ok());
+	let accounts = store.accounts();
+	assert_eq!(accounts.len(), 1);
+	let message = Default::default();
+	let signature = store.sign_with_password(&accounts[0], "", &message).unwrap();
+	assert_eq!(signature.len(), 65);
+	assert!(verify_address(&accounts[0], &signature, &message).unwrap());
+	assert!(store.sign_with_password(&accounts[0], "1", &message).is_err());
+}
+
 #[test]
 fn secret_store_change_password() {
 	let dir = TransientDir::create().unwrap();
@@ -163,17 +163,15 @@ struct Dispatch {
 
 //	This is the expanded version of this:
 //
-//	let invoke_serialize_stmt = quote_stmt!(cx, {
-//		::bincode::serde::serialize(& $output_type_id { payload: self. $function_name ($hand_param_a, $hand_param_b) }, ::bincode::SizeLimit::Infinite).unwrap()
-//  });
+//	let invoke_call_expr = quote_expr!(cx, self. $function_name ($hand_param_a, $hand_param_b));
 //
 // But the above does not allow comma-separated expressions for arbitrary number
 // of parameters ...$hand_param_a, $hand_param_b, ... $hand_param_n
-fn implement_dispatch_arm_invoke_stmt(
+fn implement_dispatch_call_expr(
 	cx: &ExtCtxt,
 	builder: &aster::AstBuilder,
 	dispatch: &Dispatch,
-) -> ast::Stmt
+) -> P<ast::Expr>
 {
 	let function_name = builder.id(dispatch.function_name.as_str());
 
@@ -185,26 +183,13 @@ fn implement_dispatch_arm_invoke_stmt(
 	}).collect::<Vec<P<ast::Expr>>>();
 
 	let ext_cx = &*cx;
-	::quasi::parse_stmt_panic(&mut ::syntax::parse::new_parser_from_tts(
+	::quasi::parse_expr_panic(&mut ::syntax::parse::new_parser_from_tts(
 		ext_cx.parse_sess(),
 		ext_cx.cfg(),
 		{
 			let _sp = ext_cx.call_site();
 			let mut tt = ::std::vec::Vec::new();
 
-			tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::OpenDelim(::syntax::parse::token::Brace)));
-
-			if dispatch.return_type_ty.is_some() {
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::ModSep));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::Ident(ext_cx.ident_of("ipc"))));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::ModSep));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::Ident(ext_cx.ident_of("binary"))));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::ModSep));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::Ident(ext_cx.ident_of("serialize"))));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::OpenDelim(::syntax::parse::token::Paren)));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::BinOp(::syntax::parse::token::And)));
-			}
-
 			tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::Ident(ext_cx.ident_of("self"))));
 			tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::Dot));
 			tt.extend(::quasi::ToTokens::to_tokens(&function_name, ext_cx).into_iter());
@@ -217,26 +202,26 @@ fn implement_dispatch_arm_invoke_stmt(
 
 			tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::CloseDelim(::syntax::parse::token::Paren)));
 
-			if dispatch.return_type_ty.is_some() {
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::CloseDelim(::syntax::parse::token::Paren)));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::Dot));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::Ident(ext_cx.ident_of("unwrap"))));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::OpenDelim(::syntax::parse::token::Paren)));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::CloseDelim(::syntax::parse::token::Paren)));
-			}
-			else {
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::Semi));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::Ident(ext_cx.ident_of("Vec"))));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::ModSep));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::Ident(ext_cx.ident_of("new"))));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::OpenDelim(::syntax::parse::token::Paren)));
-				tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::CloseDelim(::syntax::parse::token::Paren)));
-
-			}
-			tt.push(::syntax::ast::TokenTree::Token(_sp, ::syntax::parse::token::CloseDelim(::syntax::parse::token::Brace)));
-
 			tt
-		})).unwrap()
+		}))
+}
+
+/// invokes the method and serializes the result, if any (streamed for `Vec` and `Bytes` results)
+fn implement_dispatch_arm_invoke_stmt(
+	cx: &ExtCtxt,
+	builder: &aster::AstBuilder,
+	dispatch: &Dispatch,
+) -> ast::Stmt
+{
+	let call_expr = implement_dispatch_call_expr(cx, builder, dispatch);
+	match dispatch.return_type_ty {
+		Some(ref return_ty) if is_streamable(return_ty) =>
+			quote_stmt!(cx, { ::ipc::binary::serialize_streamed(& $call_expr).unwrap() }).unwrap(),
+		Some(_) =>
+			quote_stmt!(cx, { ::ipc::binary::serialize(& $call_expr).unwrap() }).unwrap(),
+		None =>
+			quote_stmt!(cx, { $call_expr; Vec::new() }).unwrap(),
+	}
 }
 
 fn implement_dispatch_arm_invoke(
@@ -247,16 +232,22 @@ fn implement_dispatch_arm_invoke(
 ) -> P<ast::Expr>
 {
 	let deserialize_expr = if buffer {
-		quote_expr!(cx, ::ipc::binary::deserialize(buf).expect("ipc deserialization error, aborting"))
+		quote_expr!(cx, ::ipc::binary::deserialize(buf))
 	} else {
-		quote_expr!(cx, ::ipc::binary::deserialize_from(r).expect("ipc deserialization error, aborting"))
+		quote_expr!(cx, ::ipc::binary::deserialize_from(r))
 	};
 
 	let invoke_serialize_stmt = implement_dispatch_arm_invoke_stmt(cx, builder, dispatch);
 	dispatch.input_type_name.as_ref().map(|val| {
 			let input_type_id = builder.id(val.clone().as_str());
 			quote_expr!(cx, {
-				let input: $input_type_id = $deserialize_expr;
+				let input: $input_type_id = match $deserialize_expr {
+					Ok(input) => input,
+					Err(e) => {
+						warn!(target: "ipc", "Malformed IPC request: {:?}", e);
+						return vec![];
+					}
+				};
 				$invoke_serialize_stmt
 			})
 		}).unwrap_or(quote_expr!(cx, { $invoke_serialize_stmt }))
@@ -293,6 +284,48 @@ fn implement_dispatch_arms(
 		.map(|dispatch| { index = index + 1; implement_dispatch_arm(cx, builder, index as u32, dispatch, buffer) }).collect()
 }
 
+/// generates dispatch match arms writing streamed results straight to `w`,
+/// for the methods with streamed results only
+fn implement_stream_dispatch_arms(
+	cx: &ExtCtxt,
+	builder: &aster::AstBuilder,
+	dispatches: &[Dispatch],
+) -> Vec<ast::Arm>
+{
+	dispatches.iter().enumerate()
+		.filter(|&(_, dispatch)| dispatch.return_type_ty.as_ref().map_or(false, is_streamable))
+		.map(|(index, dispatch)| {
+			let index_ident = builder.id(format!("{}", index as u32 + (RESERVED_MESSAGE_IDS as u32)).as_str());
+			let dispatching_trace = "Dispatching: ".to_owned() + &dispatch.function_name;
+			let dispatching_trace_literal = builder.expr().lit().str::<&str>(&dispatching_trace);
+			let call_expr = implement_dispatch_call_expr(cx, builder, dispatch);
+			let write_expr = quote_expr!(cx,
+				::ipc::binary::BinaryStreamable::write_stream(& $call_expr, w)
+					.map_err(|e| ::std::io::Error::new(::std::io::ErrorKind::Other, format!("{:?}", e)))
+			);
+			let invoke_expr = match dispatch.input_type_name {
+				Some(ref input_type_name) => {
+					let input_type_id = builder.id(input_type_name.as_str());
+					quote_expr!(cx, {
+						let input: $input_type_id = match ::ipc::binary::deserialize_from(r) {
+							Ok(input) => input,
+							Err(e) => {
+								warn!(target: "ipc", "Malformed IPC request: {:?}", e);
+								return Ok(());
+							}
+						};
+						$write_expr
+					})
+				},
+				None => write_expr,
+			};
+			quote_arm!(cx, $index_ident => {
+				trace!(target: "ipc", $dispatching_trace_literal);
+				$invoke_expr
+			})
+		}).collect()
+}
+
 pub fn strip_ptr(ty: &P<ast::Ty>) -> P<ast::Ty> {
 	if let ast::TyKind::Rptr(_, ref ptr_mut) = ty.node {
 		ptr_mut.ty.clone()
@@ -307,6 +340,17 @@ pub fn has_ptr(ty: &P<ast::Ty>) -> bool {
 	else { false }
 }
 
+/// whether results of the type are streamed (see `ipc::binary::BinaryStreamable`):
+/// `Vec<T>` and `Bytes`, optionally wrapped in `Option`
+pub fn is_streamable(ty: &P<ast::Ty>) -> bool {
+	let ty = ::syntax::print::pprust::ty_to_string(ty).replace(" ", "");
+	let inner = match ty.starts_with("Option<") && ty.ends_with(">") {
+		true => &ty["Option<".len()..ty.len() - 1],
+		false => &ty[..],
+	};
+	inner == "Bytes" || inner.ends_with("::Bytes") || (inner.starts_with("Vec<") && inner.ends_with(">"))
+}
+
 /// returns an expression with the body for single operation that is being sent to server
 /// operation itself serializes input, writes to socket and waits for socket to respond
 /// (the latter only if original method signature returns anyting)
@@ -429,9 +473,14 @@ fn implement_client_method_body(
 	let invocation_trace_literal = builder.expr().lit().str::<&str>(&invocation_trace);
 
 	if let Some(ref return_ty) = dispatch.return_type_ty {
-		let return_expr = quote_expr!(cx,
-			::ipc::binary::deserialize_from::<$return_ty, _>(&mut *socket).unwrap()
-		);
+		let return_expr = match is_streamable(return_ty) {
+			true => quote_expr!(cx,
+				<$return_ty as ::ipc::binary::BinaryStreamable>::read_stream(&mut *socket).unwrap()
+			),
+			false => quote_expr!(cx,
+				::ipc::binary::deserialize_from::<$return_ty, _>(&mut *socket).unwrap()
+			),
+		};
 		quote_expr!(cx, {
 			trace!(target: "ipc", $invocation_trace_literal);
 			$request
@@ -539,6 +588,7 @@ fn push_client_struct(cx: &ExtCtxt, builder: &aster::AstBuilder, interface_map:
 	let client_struct_item = quote_item!(cx,
 		pub struct $client_short_ident $generics {
 			socket: ::std::sync::RwLock<S>,
+			negotiation: ::std::sync::RwLock<::ipc::Negotiation>,
 			phantom: $phantom,
 		});
 
@@ -574,6 +624,7 @@ fn push_with_socket_client_implementation(
 			fn init(socket: S) -> $client_ident {
 				$client_short_ident {
 					socket: ::std::sync::RwLock::new(socket),
+					negotiation: ::std::sync::RwLock::new(::ipc::Negotiation::default()),
 					phantom: ::std::marker::PhantomData,
 				}
 			}
@@ -604,20 +655,23 @@ fn push_client_implementation(
 				protocol_version: $endpoint::protocol_version(),
 				api_version: $endpoint::api_version(),
 			};
+			let local = ::ipc::Negotiation::local::<$endpoint>();
+
+			// negotiation follows the handshake, hosts not aware of it ignore it
+			let mut request = ::ipc::binary::serialize(&::ipc::BinHandshake::from(payload)).unwrap();
+			request.extend_from_slice(&::ipc::binary::serialize(&local).unwrap());
+			::ipc::invoke(0, &Some(request), &mut *self.socket.write().unwrap());
+
+			let mut result = vec![0u8; 17];
+			let len = try!(self.socket.write().unwrap().read(&mut result).map_err(|_| ::ipc::Error::HandshakeFailed));
+			let agreed = try!(::ipc::read_handshake_reply(&result[..len], &local));
+			*self.negotiation.write().unwrap() = agreed;
+			Ok(())
+		}).unwrap();
 
-			::ipc::invoke(
-				0,
-				&Some(::ipc::binary::serialize(&::ipc::BinHandshake::from(payload)).unwrap()),
-				&mut *self.socket.write().unwrap());
-
-			let mut result = vec![0u8; 1];
-			if try!(self.socket.write().unwrap().read(&mut result).map_err(|_| ::ipc::Error::HandshakeFailed)) == 1 {
-				match result[0] {
-					1 => Ok(()),
-					_ => Err(::ipc::Error::RemoteServiceUnsupported),
-				}
-			}
-			else { Err(::ipc::Error::HandshakeFailed) }
+	let negotiation_item = quote_impl_item!(cx,
+		pub fn negotiation(&self) -> ::ipc::Negotiation {
+			*self.negotiation.read().unwrap()
 		}).unwrap();
 
 	let socket_item = quote_impl_item!(cx,
@@ -626,7 +680,7 @@ fn push_client_implementation(
 			&self.socket
 		}).unwrap();
 
-	let generic_items = vec![P(handshake_item), P(socket_item)];
+	let generic_items = vec![P(handshake_item), P(negotiation_item), P(socket_item)];
 
 	if interface_map.impl_trait.is_some() {
 		let trait_ty = builder.id(
@@ -671,15 +725,24 @@ fn implement_handshake_arm(
 ) -> (ast::Arm, ast::Arm)
 {
 	let handshake_deserialize = quote_stmt!(&cx,
-		let handshake_payload = ::ipc::binary::deserialize_from::<::ipc::BinHandshake, _>(r).unwrap();
+		let (handshake_payload, negotiation) = match ::ipc::binary::deserialize_handshake_from(r) {
+			Ok(payload) => payload,
+			Err(e) => { warn!(target: "ipc", "Malformed IPC handshake: {:?}", e); return vec![]; }
+		};
 	);
 
 	let handshake_deserialize_buf = quote_stmt!(&cx,
-		let handshake_payload = ::ipc::binary::deserialize::<::ipc::BinHandshake>(buf).unwrap();
+		let (handshake_payload, negotiation) = match ::ipc::binary::deserialize_handshake(buf) {
+			Ok(payload) => payload,
+			Err(e) => { warn!(target: "ipc", "Malformed IPC handshake: {:?}", e); return vec![]; }
+		};
 	);
 
 	let handshake_serialize = quote_expr!(&cx,
-		::ipc::binary::serialize::<bool>(&Self::handshake(&handshake_payload.to_semver())).unwrap()
+		::ipc::handshake_reply(
+			Self::handshake(&handshake_payload.to_semver()),
+			&::ipc::Negotiation::local::<Self>(),
+			negotiation.as_ref())
 	);
 
 	(
@@ -695,13 +758,13 @@ fn implement_handshake_arm(
 }
 
 
-fn get_str_from_lit(cx: &ExtCtxt, name: &str, lit: &ast::Lit) -> Result<String, ()> {
+pub fn get_str_from_lit(cx: &ExtCtxt, name: &str, lit: &ast::Lit) -> Result<String, ()> {
 	match lit.node {
 		ast::LitKind::Str(ref s, _) => Ok(format!("{}", s)),
 		_ => {
 			cx.span_err(
 				lit.span,
-				&format!("ipc client_ident annotation `{}` must be a string, not `{}`",
+				&format!("ipc annotation `{}` must be a string, not `{}`",
 					name,
 					::syntax::print::pprust::lit_to_string(lit)));
 
@@ -834,6 +897,7 @@ fn implement_interface(
 
 	let dispatch_arms = implement_dispatch_arms(cx, builder, &dispatch_table, false);
 	let dispatch_arms_buffered = implement_dispatch_arms(cx, builder, &dispatch_table, true);
+	let dispatch_arms_streamed = implement_stream_dispatch_arms(cx, builder, &dispatch_table);
 
 	let (handshake_arm, handshake_arm_buf) = implement_handshake_arm(cx);
 
@@ -849,10 +913,9 @@ fn implement_interface(
 				where R: ::std::io::Read
 			{
 				let mut method_num = vec![0u8;2];
-				match r.read(&mut method_num) {
-					Ok(size) if size == 0 => { panic!("method id not supplied" ); }
-					Err(e) => { panic!("ipc read error: {:?}, aborting", e); }
-					_ => { }
+				if let Err(e) = r.read_exact(&mut method_num) {
+					warn!(target: "ipc", "Cannot read IPC method id: {:?}", e);
+					return vec![];
 				}
 
 				// method_num is a 16-bit little-endian unsigned number
@@ -873,6 +936,23 @@ fn implement_interface(
 					_ => vec![]
 				}
 			}
+
+			fn dispatch_into<R, W>(&self, r: &mut R, w: &mut W) -> ::std::io::Result<()>
+				where R: ::std::io::Read, W: ::std::io::Write
+			{
+				let mut method_num = vec![0u8;2];
+				try!(r.read_exact(&mut method_num));
+
+				match method_num[1] as u16 + (method_num[0] as u16)*256 {
+					// methods with streamed results
+					$dispatch_arms_streamed
+					method_num => {
+						let mut buf = Vec::new();
+						try!(r.read_to_end(&mut buf));
+						w.write_all(&self.dispatch_buf(method_num, &buf))
+					}
+				}
+			}
 		}
 	).unwrap();
 
@@ -86,8 +86,8 @@ fn serialize_item(
 		&generics,
 		ty.clone()));
 
-	let (size_expr, read_expr, write_expr) =
-		(binary_expressions.size, binary_expressions.read, binary_expressions.write);
+	let (size_expr, read_expr, write_expr, empty_expr) =
+		(binary_expressions.size, binary_expressions.read, binary_expressions.write, binary_expressions.empty);
 
 	match quote_item!(cx,
 		impl $generics ::ipc::BinaryConvertable for $ty $where_clause {
@@ -103,6 +103,10 @@ fn serialize_item(
 				$read_expr
 			}
 
+			fn from_empty_bytes() -> Result<Self, BinaryConvertError> {
+				$empty_expr
+			}
+
 			fn len_params() -> usize {
 				1
 			}
@@ -131,6 +135,7 @@ fn binary_expr(
 			binary_expr_item_struct(
 				cx,
 				builder,
+				item.ident,
 				impl_generics,
 				ty,
 				item.span,
@@ -145,6 +150,7 @@ fn binary_expr(
 				impl_generics,
 				ty,
 				item.span,
+				&item.attrs,
 				enum_def,
 			)
 		},
@@ -160,6 +166,8 @@ struct BinaryExpressions {
 	pub size: P<ast::Expr>,
 	pub write: P<ast::Expr>,
 	pub read: P<ast::Expr>,
+	// value read from an empty payload, which only unit structs serialize to.
+	pub empty: P<ast::Expr>,
 }
 
 fn replace_qualified(s: &str) -> String {
@@ -172,6 +180,14 @@ fn replace_qualified(s: &str) -> String {
 	else { s.to_owned() }
 }
 
+// member of `value_ident` holding given field: by name for named fields, by position for tuple fields.
+fn member_ident(builder: &aster::AstBuilder, field: &ast::StructField, index: usize) -> Ident {
+	match field.ident {
+		Some(ident) => builder.id(ident),
+		None => builder.id(format!("{}", index)),
+	}
+}
+
 fn binary_expr_struct(
 	cx: &ExtCtxt,
 	builder: &aster::AstBuilder,
@@ -190,7 +206,7 @@ fn binary_expr_struct(
 			},
 			"[u8]" => {
 				value_ident.and_then(|x| {
-						let field_id = builder.id(field.ident.unwrap());
+						let field_id = member_ident(builder, field, index);
 						Some(quote_expr!(cx, $x. $field_id .len()))
 					})
 					.unwrap_or_else(|| {
@@ -199,24 +215,21 @@ fn binary_expr_struct(
 				)
 			}
 			_ => {
-				let field_type_ident = builder.id(
-					&::syntax::print::pprust::ty_to_string(&codegen::strip_ptr(&field.ty)));
-
 				let field_type_ident_qualified = builder.id(
 					replace_qualified(&::syntax::print::pprust::ty_to_string(&codegen::strip_ptr(&field.ty))));
 
 				value_ident.and_then(|x|
 					{
-						let field_id = builder.id(field.ident.unwrap());
+						let field_id = member_ident(builder, field, index);
 						Some(quote_expr!(cx,
 							match $field_type_ident_qualified::len_params() {
-								0 => mem::size_of::<$field_type_ident>(),
+								0 => $field_type_ident_qualified::fixed_size(),
 								_ => $x. $field_id .size(),
 							}))
 					})
 					.unwrap_or_else(|| {
 						quote_expr!(cx, match $field_type_ident_qualified::len_params() {
-							0 => mem::size_of::<$field_type_ident>(),
+							0 => $field_type_ident_qualified::fixed_size(),
 							_ => $index_ident .size(),
 						})
 					})
@@ -224,17 +237,22 @@ fn binary_expr_struct(
 		}
 	}).collect();
 
-	let first_size_expr = size_exprs[0].clone();
-	let mut total_size_expr = quote_expr!(cx, 0usize + $first_size_expr);
-	for index in 1..size_exprs.len() {
-		let next_expr = size_exprs[index].clone();
-		total_size_expr = quote_expr!(cx, $total_size_expr + $next_expr);
+	let mut total_size_expr = quote_expr!(cx, 0usize);
+	for size_expr in size_exprs {
+		total_size_expr = quote_expr!(cx, $total_size_expr + $size_expr);
 	}
 
 	let mut write_stmts = Vec::<ast::Stmt>::new();
 	write_stmts.push(quote_stmt!(cx, let mut offset = 0usize;).unwrap());
 
+	let type_name = match instance_ident {
+		Some(ident) => format!("{}", ident),
+		None => ::syntax::print::pprust::ty_to_string(&ty),
+	};
+	let type_name_literal = builder.expr().lit().str::<&str>(&type_name);
+
 	let mut map_stmts = Vec::<ast::Stmt>::new();
+	map_stmts.push(quote_stmt!(cx, let type_name = $type_name_literal;).unwrap());
 	let field_amount = builder.id(&format!("{}",fields.len()));
 	map_stmts.push(quote_stmt!(cx, let mut map = vec![0usize; $field_amount];).unwrap());
 	map_stmts.push(quote_stmt!(cx, let mut total = 0usize;).unwrap());
@@ -242,15 +260,12 @@ fn binary_expr_struct(
 	let mut post_write_stmts = Vec::<ast::Stmt>::new();
 
 	for (index, field) in fields.iter().enumerate() {
-		let field_type_ident = builder.id(
-			&::syntax::print::pprust::ty_to_string(&codegen::strip_ptr(&field.ty)));
-
 		let field_type_ident_qualified = builder.id(
 			replace_qualified(&::syntax::print::pprust::ty_to_string(&codegen::strip_ptr(&field.ty))));
 
 		let member_expr = match value_ident {
 			Some(x) => {
-				let field_id = builder.id(field.ident.unwrap());
+				let field_id = member_ident(builder, field, index);
 				quote_expr!(cx, $x . $field_id)
 			},
 			None => {
@@ -279,7 +294,7 @@ fn binary_expr_struct(
 			}
 			_ => {
 				write_stmts.push(quote_stmt!(cx, let next_line = offset + match $field_type_ident_qualified::len_params() {
-						0 => mem::size_of::<$field_type_ident>(),
+						0 => $field_type_ident_qualified::fixed_size(),
 						_ => { let size = $member_expr .size(); length_stack.push_back(size); size },
 					}).unwrap());
 				write_stmts.push(quote_stmt!(cx, let $range_ident = offset..next_line; ).unwrap());
@@ -301,29 +316,38 @@ fn binary_expr_struct(
 
 		match raw_ident.as_ref() {
 			"u8" => {
-				map_stmts.push(quote_stmt!(cx, total += 1;).unwrap());
+				map_stmts.push(quote_stmt!(cx, total = try!(total.checked_add(1)
+					.ok_or_else(|| BinaryConvertError::inner(type_name, BinaryConvertError::size(1, 0).at(total))));).unwrap());
 			},
 			"[u8]" => {
-				map_stmts.push(quote_stmt!(cx, let size = length_stack.pop_front().unwrap();).unwrap());
-				map_stmts.push(quote_stmt!(cx, total += size;).unwrap());
+				map_stmts.push(quote_stmt!(cx, let size = try!(length_stack.pop_front()
+					.ok_or_else(|| BinaryConvertError::inner(type_name, BinaryConvertError::MissingLength { offset: total })));).unwrap());
+				map_stmts.push(quote_stmt!(cx, total = try!(total.checked_add(size)
+					.ok_or_else(|| BinaryConvertError::inner(type_name, BinaryConvertError::size(size, buffer.len().saturating_sub(total)).at(total))));).unwrap());
 			},
 			_ => {
 				map_stmts.push(quote_stmt!(cx, let size = match $field_type_ident_qualified::len_params() {
-						0 => mem::size_of::<$field_type_ident>(),
-						_ => length_stack.pop_front().unwrap(),
+						0 => $field_type_ident_qualified::fixed_size(),
+						_ => try!(length_stack.pop_front()
+							.ok_or_else(|| BinaryConvertError::inner(type_name, BinaryConvertError::MissingLength { offset: total }))),
 					}).unwrap());
-				map_stmts.push(quote_stmt!(cx, total += size;).unwrap());
+				map_stmts.push(quote_stmt!(cx, total = try!(total.checked_add(size)
+					.ok_or_else(|| BinaryConvertError::inner(type_name, BinaryConvertError::size(size, buffer.len().saturating_sub(total)).at(total))));).unwrap());
 			}
 		}
 	};
+	// fields are sliced out of the buffer by their offsets, which must all lie within it.
+	map_stmts.push(quote_stmt!(cx, if total > buffer.len() {
+		return Err(BinaryConvertError::inner(type_name, BinaryConvertError::size(total, buffer.len())));
+	}).unwrap());
 
 	let read_expr = match fields.iter().any(|f| codegen::has_ptr(&f.ty)) {
 		true => {
 			// cannot create structs with pointers
-			quote_expr!(cx, Err(::ipc::binary::BinaryConvertError))
+			quote_expr!(cx, Err(::ipc::binary::BinaryConvertError::invalid($type_name_literal)))
 		},
 		false => {
-			if value_ident.is_some() {
+			if value_ident.is_some() && fields.iter().all(|f| f.ident.is_some()) {
 				let instance_create = named_fields_sequence(cx, &ty, fields);
 				quote_expr!(cx, { $map_stmts; $instance_create; Ok(result) })
 			}
@@ -338,6 +362,7 @@ fn binary_expr_struct(
 		size: total_size_expr,
 		write: quote_expr!(cx, { $write_stmts; $post_write_stmts; Ok(()) } ),
 		read: read_expr,
+		empty: quote_expr!(cx, Err(BinaryConvertError::invalid($type_name_literal))),
 	})
 }
 
@@ -345,9 +370,10 @@ fn binary_expr_struct(
 fn binary_expr_item_struct(
 	cx: &ExtCtxt,
 	builder: &aster::AstBuilder,
+	type_ident: Ident,
 	_impl_generics: &ast::Generics,
 	ty: P<ast::Ty>,
-	span: Span,
+	_span: Span,
 	variant_data: &ast::VariantData,
 ) -> Result<BinaryExpressions, Error> {
 	match *variant_data {
@@ -358,7 +384,7 @@ fn binary_expr_item_struct(
 				ty,
 				fields,
 				Some(builder.id("self")),
-				None,
+				Some(type_ident),
 			)
 		},
 		ast::VariantData::Struct(ref fields, _) => {
@@ -371,15 +397,116 @@ fn binary_expr_item_struct(
 				None,
 			)
 		},
-		_ => {
-			cx.span_bug(span,
-				&format!("#[derive(Binary)] Unsupported struct content, expected tuple/struct, found: {:?}",
-					variant_data));
-			Err(Error)
+		// a single zero byte, so that a unit struct can't be confused with an absent value,
+		// e.g. `Some` with `None` for `Option<UnitStruct>`.
+		ast::VariantData::Unit(_) => {
+			Ok(BinaryExpressions {
+				size: quote_expr!(cx, 1usize),
+				write: quote_expr!(cx, { buffer[0] = 0u8; Ok(()) }),
+				read: quote_expr!(cx, match buffer.len() {
+					1 => Ok($type_ident),
+					found => Err(BinaryConvertError::size(1, found)),
+				}),
+				empty: quote_expr!(cx, Err(BinaryConvertError::size(1, 0))),
+			})
 		},
 	}
 }
 
+// Width in bytes of the enum tags, set with `#[ipc(discriminant = "u16")]`; one byte by default.
+fn enum_tag_width(cx: &ExtCtxt, attrs: &[ast::Attribute]) -> Result<usize, Error> {
+	let mut width = 1;
+	for meta_items in attrs.iter().filter_map(codegen::get_ipc_meta_items) {
+		for meta_item in meta_items {
+			match meta_item.node {
+				ast::MetaItemKind::NameValue(ref name, ref lit) if name == &"discriminant" => {
+					width = match codegen::get_str_from_lit(cx, name, lit).as_ref().map(|s| s.as_str()) {
+						Ok("u8") => 1,
+						Ok("u16") => 2,
+						_ => {
+							cx.span_err(meta_item.span, "ipc discriminant must be \"u8\" or \"u16\"");
+							return Err(Error);
+						},
+					};
+				},
+				_ => {
+					cx.span_err(
+						meta_item.span,
+						&format!("unknown ipc enum attribute `{}`",
+								 ::syntax::print::pprust::meta_item_to_string(meta_item)));
+					return Err(Error);
+				}
+			}
+		}
+	}
+	Ok(width)
+}
+
+// Tags of the variants, which are written to the wire and must not change between releases.
+// A variant takes the tag of its `#[ipc(tag = "N")]` attribute, or its explicit discriminant,
+// or else the tag following the one of the previous variant, starting from 0.
+fn enum_tags(cx: &ExtCtxt, enum_def: &ast::EnumDef, width: usize) -> Result<Vec<u16>, Error> {
+	let max_tag = match width { 1 => 0xff, _ => 0xffff };
+	let mut tags = Vec::<u16>::new();
+	let mut next_tag = 0u64;
+
+	for variant in &enum_def.variants {
+		let mut tag = None;
+		for meta_items in variant.node.attrs.iter().filter_map(codegen::get_ipc_meta_items) {
+			for meta_item in meta_items {
+				match meta_item.node {
+					ast::MetaItemKind::NameValue(ref name, ref lit) if name == &"tag" => {
+						match codegen::get_str_from_lit(cx, name, lit).ok().and_then(|s| s.parse::<u64>().ok()) {
+							Some(value) => tag = Some(value),
+							None => {
+								cx.span_err(meta_item.span, "ipc tag must be a number");
+								return Err(Error);
+							},
+						}
+					},
+					_ => {
+						cx.span_err(
+							meta_item.span,
+							&format!("unknown ipc variant attribute `{}`",
+									 ::syntax::print::pprust::meta_item_to_string(meta_item)));
+						return Err(Error);
+					}
+				}
+			}
+		}
+
+		if tag.is_none() {
+			if let Some(ref disr_expr) = variant.node.disr_expr {
+				match disr_expr.node {
+					ast::ExprKind::Lit(ref lit) => match lit.node {
+						ast::LitKind::Int(value, _) => tag = Some(value),
+						_ => {},
+					},
+					_ => {},
+				}
+				if tag.is_none() {
+					cx.span_err(disr_expr.span, "`#[derive(Binary)]` needs discriminants to be integer literals");
+					return Err(Error);
+				}
+			}
+		}
+
+		let tag = tag.unwrap_or(next_tag);
+		if tag > max_tag {
+			cx.span_err(variant.span, &format!("tag {} doesn't fit the enum discriminant", tag));
+			return Err(Error);
+		}
+		if tags.contains(&(tag as u16)) {
+			cx.span_err(variant.span, &format!("tag {} is used by more than one variant", tag));
+			return Err(Error);
+		}
+		tags.push(tag as u16);
+		next_tag = tag + 1;
+	}
+
+	Ok(tags)
+}
+
 fn binary_expr_enum(
 	cx: &ExtCtxt,
 	builder: &aster::AstBuilder,
@@ -387,12 +514,16 @@ fn binary_expr_enum(
 	impl_generics: &ast::Generics,
 	ty: P<ast::Ty>,
 	span: Span,
+	attrs: &[ast::Attribute],
 	enum_def: &ast::EnumDef,
 ) -> Result<BinaryExpressions, Error> {
+	let width = try!(enum_tag_width(cx, attrs));
+	let tags = try!(enum_tags(cx, enum_def, width));
+
 	let arms: Vec<_> = try!(
 		enum_def.variants.iter()
-			.enumerate()
-			.map(|(variant_index, variant)| {
+			.zip(tags.iter())
+			.map(|(variant, tag)| {
 				binary_expr_variant(
 					cx,
 					builder,
@@ -401,23 +532,29 @@ fn binary_expr_enum(
 					ty.clone(),
 					span,
 					variant,
-					variant_index,
+					*tag,
+					width,
 				)
 			})
 			.collect()
 	);
+	let width_ident = builder.id(format!("{}", width));
 
 	let (size_arms, write_arms, mut read_arms) = (
 		arms.iter().map(|x| x.size.clone()).collect::<Vec<ast::Arm>>(),
 		arms.iter().map(|x| x.write.clone()).collect::<Vec<ast::Arm>>(),
 		arms.iter().map(|x| x.read.clone()).collect::<Vec<ast::Arm>>());
 
-	read_arms.push(quote_arm!(cx, _ => { Err(BinaryConvertError) } ));
+	read_arms.push(quote_arm!(cx, tag => { Err(BinaryConvertError::unknown_variant(tag)) } ));
 
 	Ok(BinaryExpressions {
-		size: quote_expr!(cx, 1usize + match *self { $size_arms }),
+		size: quote_expr!(cx, $width_ident + match *self { $size_arms }),
 		write: quote_expr!(cx, match *self { $write_arms }; ),
-		read: quote_expr!(cx, match buffer[0] { $read_arms }),
+		read: quote_expr!(cx, match ::ipc::binary::tag_from_bytes(buffer, $width_ident) {
+			Ok(variant) => match variant { $read_arms },
+			Err(e) => Err(e),
+		}),
+		empty: quote_expr!(cx, Err(BinaryConvertError::size($width_ident, 0))),
 	})
 }
 
@@ -497,42 +634,7 @@ fn fields_sequence(
 					continue;
 				}
 
-				tt.push(Token(_sp, token::Ident(ext_cx.ident_of("try!"))));
-				tt.push(Token(_sp, token::OpenDelim(token::Paren)));
-				tt.push(
-					Token(
-						_sp,
-						token::Ident(ext_cx.ident_of(&replace_qualified(&::syntax::print::pprust::ty_to_string(&field.ty))))
-					));
-				tt.push(Token(_sp, token::ModSep));
-				tt.push(Token(_sp, token::Ident(ext_cx.ident_of("from_bytes"))));
-				tt.push(Token(_sp, token::OpenDelim(token::Paren)));
-
-				tt.push(Token(_sp, token::BinOp(token::And)));
-
-				tt.push(Token(_sp, token::Ident(ext_cx.ident_of("buffer"))));
-
-				tt.push(Token(_sp, token::OpenDelim(token::Bracket)));
-				tt.push(Token(_sp, token::Ident(ext_cx.ident_of("map"))));
-				tt.push(Token(_sp, token::OpenDelim(token::Bracket)));
-				tt.push(Token(_sp, token::Ident(ext_cx.ident_of(&format!("{}", idx)))));
-				tt.push(Token(_sp, token::CloseDelim(token::Bracket)));
-				tt.push(Token(_sp, token::DotDot));
-
-				if idx+1 != fields.len() {
-					tt.push(Token(_sp, token::Ident(ext_cx.ident_of("map"))));
-					tt.push(Token(_sp, token::OpenDelim(token::Bracket)));
-					tt.push(Token(_sp, token::Ident(ext_cx.ident_of(&format!("{}", idx+1)))));
-					tt.push(Token(_sp, token::CloseDelim(token::Bracket)));
-				}
-
-				tt.push(Token(_sp, token::CloseDelim(token::Bracket)));
-
-				tt.push(Token(_sp, token::Comma));
-				tt.push(Token(_sp, token::Ident(ext_cx.ident_of("length_stack"))));
-
-				tt.push(Token(_sp, token::CloseDelim(token::Paren)));
-				tt.push(Token(_sp, token::CloseDelim(token::Paren)));
+				push_member_read(ext_cx, &mut tt, idx, fields.len());
 				tt.push(Token(_sp, token::Comma));
 			}
 			if named_members {
@@ -546,6 +648,53 @@ fn fields_sequence(
 	).unwrap()
 }
 
+// tokens of `try!(::ipc::binary::member_from_bytes(buffer, map[idx], <end>, length_stack, type_name))`,
+// reading the member at `idx`; it ends where the next one starts or at the end of the buffer.
+fn push_member_read(ext_cx: &ExtCtxt, tt: &mut Vec<::syntax::ast::TokenTree>, idx: usize, fields_len: usize) {
+	use syntax::parse::token;
+	use syntax::ast::TokenTree::Token;
+
+	let _sp = ext_cx.call_site();
+	let ident = |name: &str| Token(_sp, token::Ident(ext_cx.ident_of(name)));
+
+	tt.push(ident("try!"));
+	tt.push(Token(_sp, token::OpenDelim(token::Paren)));
+	for name in &["ipc", "binary", "member_from_bytes"] {
+		tt.push(Token(_sp, token::ModSep));
+		tt.push(ident(name));
+	}
+	tt.push(Token(_sp, token::OpenDelim(token::Paren)));
+
+	tt.push(ident("buffer"));
+	tt.push(Token(_sp, token::Comma));
+
+	for bound in &[Some(idx), if idx + 1 != fields_len { Some(idx + 1) } else { None }] {
+		match *bound {
+			Some(bound) => {
+				tt.push(ident("map"));
+				tt.push(Token(_sp, token::OpenDelim(token::Bracket)));
+				tt.push(ident(&format!("{}", bound)));
+				tt.push(Token(_sp, token::CloseDelim(token::Bracket)));
+			},
+			None => {
+				tt.push(ident("buffer"));
+				tt.push(Token(_sp, token::Dot));
+				tt.push(ident("len"));
+				tt.push(Token(_sp, token::OpenDelim(token::Paren)));
+				tt.push(Token(_sp, token::CloseDelim(token::Paren)));
+			},
+		}
+		tt.push(Token(_sp, token::Comma));
+	}
+
+	tt.push(ident("length_stack"));
+	tt.push(Token(_sp, token::Comma));
+	tt.push(ident("type_name"));
+
+	tt.push(Token(_sp, token::CloseDelim(token::Paren)));
+	tt.push(Token(_sp, token::CloseDelim(token::Paren)));
+}
+
 fn named_fields_sequence(
 	ext_cx: &ExtCtxt,
 	ty: &P<ast::Ty>,
@@ -567,7 +716,7 @@ fn named_fields_sequence(
 			tt.push(Token(
 				_sp,
 			  	token::Ident(
-					ext_cx.ident_of(&::syntax::print::pprust::ty_to_string(ty))
+					ext_cx.ident_of(&replace_qualified(&::syntax::print::pprust::ty_to_string(ty)))
 			 		)));
 
 			tt.push(Token(_sp, token::OpenDelim(token::Brace)));
@@ -615,41 +764,7 @@ fn named_fields_sequence(
 					continue;
 				}
 
-				tt.push(Token(_sp, token::Ident(ext_cx.ident_of("try!"))));
-				tt.push(Token(_sp, token::OpenDelim(token::Paren)));
-				tt.push(Token(
-					_sp,
-					token::Ident(
-						ext_cx.ident_of(&replace_qualified(&::syntax::print::pprust::ty_to_string(&field.ty)))
-						)));
-				tt.push(Token(_sp, token::ModSep));
-				tt.push(Token(_sp, token::Ident(ext_cx.ident_of("from_bytes"))));
-				tt.push(Token(_sp, token::OpenDelim(token::Paren)));
-
-				tt.push(Token(_sp, token::BinOp(token::And)));
-				tt.push(Token(_sp, token::Ident(ext_cx.ident_of("buffer"))));
-
-				tt.push(Token(_sp, token::OpenDelim(token::Bracket)));
-				tt.push(Token(_sp, token::Ident(ext_cx.ident_of("map"))));
-				tt.push(Token(_sp, token::OpenDelim(token::Bracket)));
-				tt.push(Token(_sp, token::Ident(ext_cx.ident_of(&format!("{}", idx)))));
-				tt.push(Token(_sp, token::CloseDelim(token::Bracket)));
-				tt.push(Token(_sp, token::DotDot));
-				if idx + 1 != fields.len() {
-					tt.push(Token(_sp, token::Ident(ext_cx.ident_of("map"))));
-					tt.push(Token(_sp, token::OpenDelim(token::Bracket)));
-					tt.push(Token(_sp, token::Ident(ext_cx.ident_of(&format!("{}", idx+1)))));
-					tt.push(Token(_sp, token::CloseDelim(token::Bracket)));
-				}
-
-				tt.push(Token(_sp, token::CloseDelim(token::Bracket)));
-
-				tt.push(Token(_sp, token::Comma));
-				tt.push(Token(_sp, token::Ident(ext_cx.ident_of("length_stack"))));
-
-
-				tt.push(Token(_sp, token::CloseDelim(token::Paren)));
-				tt.push(Token(_sp, token::CloseDelim(token::Paren)));
+				push_member_read(ext_cx, &mut tt, idx, fields.len());
 				tt.push(Token(_sp, token::Comma));
 			}
 
@@ -667,10 +782,12 @@ fn binary_expr_variant(
 	ty: P<ast::Ty>,
 	_span: Span,
 	variant: &ast::Variant,
-	variant_index: usize,
+	tag: u16,
+	width: usize,
 ) -> Result<BinaryArm, Error> {
 	let variant_ident = variant.node.name;
-	let variant_index_ident = builder.id(format!("{}", variant_index));
+	let tag_ident = builder.id(format!("{}", tag));
+	let width_ident = builder.id(format!("{}", width));
 
 	match variant.node.data {
 		ast::VariantData::Unit(_) => {
@@ -682,8 +799,8 @@ fn binary_expr_variant(
 
 			Ok(BinaryArm {
 				size: quote_arm!(cx, $pat => { 0usize } ),
-				write: quote_arm!(cx, $pat => { buffer[0] = $variant_index_ident; Ok(()) } ),
-				read: quote_arm!(cx, $variant_index_ident => { Ok($variant_val) } ),
+				write: quote_arm!(cx, $pat => { ::ipc::binary::tag_to_bytes($tag_ident, $width_ident, buffer) } ),
+				read: quote_arm!(cx, $tag_ident => { Ok($variant_val) } ),
 			})
 		},
 		ast::VariantData::Tuple(ref fields, _) => {
@@ -713,14 +830,17 @@ fn binary_expr_variant(
 				size: quote_arm!(cx, $pat => { $size_expr } ),
 				write: quote_arm!(cx,
 					$pat => {
-						buffer[0] = $variant_index_ident;
-						let buffer = &mut buffer[1..];
+						try!(::ipc::binary::tag_to_bytes($tag_ident, $width_ident, buffer));
+						let buffer = &mut buffer[$width_ident..];
 						$write_expr
 				}),
 				read: quote_arm!(cx,
-					$variant_index_ident => {
-					 	let buffer = &buffer[1..];
-						$read_expr
+					$tag_ident => {
+						// errors of the members point past the variant tag.
+						let read_variant = |buffer: &[u8], length_stack: &mut VecDeque<usize>| -> Result<Self, BinaryConvertError> {
+							$read_expr
+						};
+						read_variant(&buffer[$width_ident..], length_stack).map_err(|e| e.at($width_ident))
 					}
 				),
 			})
@@ -753,14 +873,17 @@ fn binary_expr_variant(
 				size: quote_arm!(cx, $pat => { $size_expr } ),
 				write: quote_arm!(cx,
 					$pat => {
-						buffer[0] = $variant_index_ident;
-						let buffer = &mut buffer[1..];
+						try!(::ipc::binary::tag_to_bytes($tag_ident, $width_ident, buffer));
+						let buffer = &mut buffer[$width_ident..];
 						$write_expr
 				}),
 				read: quote_arm!(cx,
-					$variant_index_ident => {
-					 	let buffer = &buffer[1..];
-						$read_expr
+					$tag_ident => {
+						// errors of the members point past the variant tag.
+						let read_variant = |buffer: &[u8], length_stack: &mut VecDeque<usize>| -> Result<Self, BinaryConvertError> {
+							$read_expr
+						};
+						read_variant(&buffer[$width_ident..], length_stack).map_err(|e| e.at($width_ident))
 					}
 				),
 			})
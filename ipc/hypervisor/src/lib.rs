@@ -20,15 +20,23 @@
 
 extern crate ethcore_ipc as ipc;
 extern crate ethcore_ipc_nano as nanoipc;
+#[cfg(not(windows))]
+extern crate ethcore_ipc_mio as mioipc;
 extern crate semver;
 #[macro_use] extern crate log;
+#[cfg(test)]
+extern crate ethcore_devtools as devtools;
 
 pub mod service;
 
 /// Default value for hypervisor ipc listener
 pub const HYPERVISOR_IPC_URL: &'static str = "ipc:///tmp/parity-internal-hyper-status.ipc";
 
-use std::sync::{Arc,RwLock};
+/// Name of the pipelined hypervisor listener socket, within the data directory
+pub const HYPERVISOR_MIO_SOCKET: &'static str = "hypervisor-mio.ipc";
+
+use std::path::Path;
+use std::sync::{Arc,RwLock,Mutex};
 use service::{HypervisorService, IpcModuleId};
 use std::process::{Command,Child};
 use std::collections::HashMap;
@@ -37,10 +45,35 @@ pub use service::{HypervisorServiceClient, CLIENT_MODULE_ID, SYNC_MODULE_ID};
 
 pub type BinaryId = &'static str;
 
+#[cfg(not(windows))]
+type MioWorker = mioipc::WorkerHandle;
+
+/// Socket path of the pipelined hypervisor listener for the data directory `data_dir`
+pub fn mio_socket_path(data_dir: &str) -> String {
+	Path::new(data_dir).join(HYPERVISOR_MIO_SOCKET).to_string_lossy().into_owned()
+}
+
+#[cfg(not(windows))]
+fn start_mio_worker(service: Arc<HypervisorService>, path: &str) -> Option<MioWorker> {
+	// a socket left behind by a previous run is replaced, but one still in use is not
+	Some(mioipc::start_worker(service, path).unwrap_or_else(|e| panic!("Hypervisor ipc worker can not start - critical! ({:?})", e)))
+}
+
+#[cfg(windows)]
+type MioWorker = ();
+
+#[cfg(windows)]
+fn start_mio_worker(_service: Arc<HypervisorService>, path: &str) -> Option<MioWorker> {
+	warn!(target: "hypervisor", "Pipelined ipc listener at {} is only supported on unix", path);
+	None
+}
+
 pub struct Hypervisor {
 	ipc_addr: String,
 	service: Arc<HypervisorService>,
 	ipc_worker: RwLock<nanoipc::Worker<HypervisorService>>,
+	mio_path: Option<String>,
+	mio_worker: Mutex<Option<MioWorker>>,
 	processes: RwLock<HashMap<BinaryId, Child>>,
 	modules: HashMap<IpcModuleId, (BinaryId, BootArgs)>,
 }
@@ -74,9 +107,21 @@ impl BootArgs {
 }
 
 impl Hypervisor {
-	/// initializes the Hypervisor service with the open ipc socket for incoming clients
-	pub fn new() -> Hypervisor {
-		Hypervisor::with_url(HYPERVISOR_IPC_URL)
+	/// initializes the Hypervisor service with the open ipc socket for incoming clients,
+	/// and the pipelined listener within the data directory `data_dir`
+	pub fn new(data_dir: &str) -> Hypervisor {
+		Hypervisor::with_url(HYPERVISOR_IPC_URL).mio_path(&mio_socket_path(data_dir))
+	}
+
+	/// Also serve the modules over the pipelined transport, at the unix socket `path`
+	pub fn mio_path(mut self, path: &str) -> Hypervisor {
+		self.mio_path = Some(path.to_owned());
+		self
+	}
+
+	/// Unix socket of the pipelined transport, if it's served
+	pub fn mio_socket(&self) -> Option<&str> {
+		self.mio_path.as_ref().map(|path| path.as_str())
 	}
 
 	pub fn module(mut self, module_id: IpcModuleId, binary_id: BinaryId, args: BootArgs) -> Hypervisor {
@@ -99,6 +144,8 @@ impl Hypervisor {
 			ipc_addr: addr.to_owned(),
 			service: service,
 			ipc_worker: RwLock::new(worker),
+			mio_path: None,
+			mio_worker: Mutex::new(None),
 			processes: RwLock::new(HashMap::new()),
 			modules: HashMap::new(),
 		}
@@ -114,6 +161,9 @@ impl Hypervisor {
 	pub fn start(&self) {
 		let mut worker = self.ipc_worker.write().unwrap();
 		worker.add_reqrep(&self.ipc_addr).unwrap_or_else(|e| panic!("Hypervisor ipc worker can not start - critical! ({:?})", e));
+		if let Some(ref path) = self.mio_path {
+			*self.mio_worker.lock().unwrap() = start_mio_worker(self.service.clone(), path);
+		}
 
 		for module_id in self.service.module_ids() {
 			self.start_module(module_id);
@@ -189,6 +239,7 @@ impl Hypervisor {
 			trace!(target: "hypervisor", "Stopping process module: {}", binary);
 			child.kill().unwrap();
 		}
+		self.mio_worker.lock().unwrap().take();
 	}
 }
 
@@ -237,4 +288,31 @@ mod tests {
 
 		assert_eq!(true, hypervisor.modules_ready());
 	}
+
+	#[test]
+	#[cfg(not(windows))]
+	fn can_wait_for_startup_over_mio() {
+		use mioipc;
+		use devtools::RandomTempPath;
+
+		let url = "ipc:///tmp/test-parity-hypervisor-30.ipc";
+		let temp = RandomTempPath::create_dir();
+		let path = mio_socket_path(temp.as_str());
+		let test_module_id = 8080u64;
+
+		let hypervisor = Hypervisor::with_url(url).mio_path(&path).local_module(test_module_id);
+		assert_eq!(Some(path.as_str()), hypervisor.mio_socket());
+		hypervisor.start();
+
+		::std::thread::spawn(move || {
+			let transport = mioipc::Transport::connect(&path).unwrap();
+			let client = mioipc::init_client::<HypervisorServiceClient<_>>(&transport);
+			client.handshake().unwrap();
+			client.module_ready(test_module_id);
+		});
+
+		hypervisor.wait_for_startup();
+
+		assert_eq!(true, hypervisor.modules_ready());
+	}
 }
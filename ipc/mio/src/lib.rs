@@ -0,0 +1,684 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Non-blocking IPC transport over unix sockets
+//!
+//! Every message is framed with a request id, so a client can have many calls in
+//! flight over a single connection and match the responses as they arrive.
+//! The service side is a single event loop thread serving all the connections.
+
+extern crate ethcore_ipc as ipc;
+extern crate ethcore_util as util;
+extern crate mio;
+#[macro_use] extern crate log;
+#[cfg(test)]
+extern crate ethcore_devtools as devtools;
+
+pub use ipc::{WithSocket, IpcInterface, IpcConfig, IpcSocket};
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::collections::HashMap;
+use std::sync::{Arc, mpsc};
+use std::thread::{self, JoinHandle};
+use mio::{EventLoop, Handler, Token, EventSet, PollOpt, Sender, TryRead, TryWrite};
+use mio::unix::{UnixListener, UnixStream};
+use ipc::binary::{self, BinaryConvertError};
+use util::Mutex;
+
+/// Frame header: request id (u64) followed by the payload length (u32)
+const FRAME_HEADER_LEN: usize = 12;
+/// Largest frame payload accepted
+pub const MAX_FRAME_PAYLOAD: usize = 64 * 1024 * 1024;
+
+const LISTENER: Token = Token(0);
+const CONNECTION: Token = Token(1);
+const READ_CHUNK: usize = 16 * 1024;
+
+/// Error of the transport
+#[derive(Debug)]
+pub enum TransportError {
+	/// Socket or event loop error
+	Io(io::Error),
+	/// Peer sent a frame which could not be read
+	MalformedFrame(BinaryConvertError),
+	/// Peer sent a frame larger than `MAX_FRAME_PAYLOAD`
+	FrameTooLarge(usize),
+	/// Connection closed before the response arrived
+	Disconnected,
+}
+
+impl From<io::Error> for TransportError {
+	fn from(err: io::Error) -> Self {
+		TransportError::Io(err)
+	}
+}
+
+/// Writes `payload` framed with request `id`
+fn encode_frame(id: u64, payload: &[u8], out: &mut Vec<u8>) {
+	out.extend_from_slice(&binary::serialize(&id).expect("u64 is fixed-size; qed"));
+	out.extend_from_slice(&binary::serialize(&(payload.len() as u32)).expect("u32 is fixed-size; qed"));
+	out.extend_from_slice(payload);
+}
+
+/// Accumulates bytes read from a stream and splits them into frames
+#[derive(Default)]
+struct FrameReader {
+	buf: Vec<u8>,
+}
+
+impl FrameReader {
+	fn push(&mut self, data: &[u8]) {
+		self.buf.extend_from_slice(data);
+	}
+
+	/// Next complete frame, `None` if more bytes are needed
+	fn next_frame(&mut self) -> Result<Option<(u64, Vec<u8>)>, TransportError> {
+		if self.buf.len() < FRAME_HEADER_LEN {
+			return Ok(None);
+		}
+		let id = try!(binary::deserialize::<u64>(&self.buf[0..8]).map_err(TransportError::MalformedFrame));
+		let len = try!(binary::deserialize::<u32>(&self.buf[8..12]).map_err(TransportError::MalformedFrame)) as usize;
+		if len > MAX_FRAME_PAYLOAD {
+			return Err(TransportError::FrameTooLarge(len));
+		}
+		if self.buf.len() < FRAME_HEADER_LEN + len {
+			return Ok(None);
+		}
+		let payload = self.buf[FRAME_HEADER_LEN..FRAME_HEADER_LEN + len].to_vec();
+		self.buf.drain(..FRAME_HEADER_LEN + len);
+		Ok(Some((id, payload)))
+	}
+}
+
+/// Non-blocking stream with its read and write buffers
+struct Connection {
+	stream: UnixStream,
+	reader: FrameReader,
+	outgoing: Vec<u8>,
+}
+
+impl Connection {
+	fn new(stream: UnixStream) -> Self {
+		Connection {
+			stream: stream,
+			reader: FrameReader::default(),
+			outgoing: Vec::new(),
+		}
+	}
+
+	fn interest(&self) -> EventSet {
+		match self.outgoing.is_empty() {
+			true => EventSet::readable() | EventSet::hup(),
+			false => EventSet::readable() | EventSet::writable() | EventSet::hup(),
+		}
+	}
+
+	fn send(&mut self, id: u64, payload: &[u8]) {
+		encode_frame(id, payload, &mut self.outgoing);
+	}
+
+	/// Reads all the available data, returning the complete frames and
+	/// whether the peer closed the connection
+	fn readable(&mut self) -> Result<(Vec<(u64, Vec<u8>)>, bool), TransportError> {
+		let mut chunk = [0u8; READ_CHUNK];
+		let mut closed = false;
+		loop {
+			match try!(self.stream.try_read(&mut chunk)) {
+				Some(0) => { closed = true; break; },
+				Some(len) => self.reader.push(&chunk[..len]),
+				None => break,
+			}
+		}
+		let mut frames = Vec::new();
+		while let Some(frame) = try!(self.reader.next_frame()) {
+			frames.push(frame);
+		}
+		Ok((frames, closed))
+	}
+
+	/// Writes as much of the outgoing data as the socket takes
+	fn writable(&mut self) -> io::Result<()> {
+		while !self.outgoing.is_empty() {
+			match try!(self.stream.try_write(&self.outgoing)) {
+				Some(0) | None => break,
+				Some(len) => { self.outgoing.drain(..len); },
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Control messages of the event loops
+#[derive(Debug)]
+pub enum WorkerMessage {
+	/// Stop the event loop
+	Shutdown,
+}
+
+/// Event loop serving `service` to all the clients connected to the socket
+struct Worker<S: ?Sized> where S: IpcInterface {
+	service: Arc<S>,
+	listener: UnixListener,
+	connections: HashMap<Token, Connection>,
+	next_token: usize,
+}
+
+impl<S: ?Sized> Worker<S> where S: IpcInterface {
+	fn accept(&mut self, event_loop: &mut EventLoop<Self>) {
+		loop {
+			let stream = match self.listener.accept() {
+				Ok(Some(stream)) => stream,
+				Ok(None) => break,
+				Err(e) => {
+					warn!(target: "ipc", "Error accepting IPC connection: {:?}", e);
+					break;
+				}
+			};
+			let token = Token(self.next_token);
+			self.next_token = self.next_token + 1;
+			let connection = Connection::new(stream);
+			if let Err(e) = event_loop.register(&connection.stream, token, connection.interest(), PollOpt::level()) {
+				warn!(target: "ipc", "Error registering IPC connection: {:?}", e);
+				continue;
+			}
+			self.connections.insert(token, connection);
+		}
+	}
+
+	fn connection_ready(&mut self, event_loop: &mut EventLoop<Self>, token: Token, events: EventSet) {
+		let keep = match self.connections.get_mut(&token) {
+			None => return,
+			Some(connection) => Self::serve(&*self.service, connection, events),
+		};
+		match keep {
+			true => {
+				let connection = &self.connections[&token];
+				if let Err(e) = event_loop.reregister(&connection.stream, token, connection.interest(), PollOpt::level()) {
+					warn!(target: "ipc", "Error registering IPC connection: {:?}", e);
+				}
+			},
+			false => {
+				if let Some(connection) = self.connections.remove(&token) {
+					event_loop.deregister(&connection.stream).unwrap_or_else(|e| trace!(target: "ipc", "Error deregistering IPC connection: {:?}", e));
+				}
+			},
+		}
+	}
+
+	/// Dispatches the requests read from the connection, `false` once it has to be dropped
+	fn serve(service: &S, connection: &mut Connection, events: EventSet) -> bool {
+		let mut closed = events.is_hup() || events.is_error();
+		if events.is_readable() {
+			match connection.readable() {
+				Ok((frames, peer_closed)) => {
+					for (id, request) in frames {
+						if request.len() < 2 {
+							warn!(target: "ipc", "Failed to read method signature of request {}: unexpected message length({})", id, request.len());
+							continue;
+						}
+						let method_num = request[0] as u16 * 256 + request[1] as u16;
+						let response = service.dispatch_buf(method_num, &request[2..]);
+						connection.send(id, &response);
+					}
+					closed = closed || peer_closed;
+				},
+				Err(e) => {
+					warn!(target: "ipc", "Dropping IPC connection: {:?}", e);
+					return false;
+				}
+			}
+		}
+		if let Err(e) = connection.writable() {
+			warn!(target: "ipc", "Failed to write response: {:?}", e);
+			return false;
+		}
+		!closed
+	}
+}
+
+impl<S: ?Sized> Handler for Worker<S> where S: IpcInterface {
+	type Timeout = ();
+	type Message = WorkerMessage;
+
+	fn ready(&mut self, event_loop: &mut EventLoop<Self>, token: Token, events: EventSet) {
+		match token {
+			LISTENER => self.accept(event_loop),
+			_ => self.connection_ready(event_loop, token, events),
+		}
+	}
+
+	fn notify(&mut self, event_loop: &mut EventLoop<Self>, msg: WorkerMessage) {
+		match msg {
+			WorkerMessage::Shutdown => event_loop.shutdown(),
+		}
+	}
+}
+
+/// Running service event loop, stopped and its socket removed when dropped
+pub struct WorkerHandle {
+	channel: Sender<WorkerMessage>,
+	thread: Option<JoinHandle<()>>,
+	path: String,
+}
+
+impl Drop for WorkerHandle {
+	fn drop(&mut self) {
+		self.channel.send(WorkerMessage::Shutdown).unwrap_or_else(|e| warn!(target: "ipc", "Error on IPC worker shutdown: {:?}", e));
+		if let Some(thread) = self.thread.take() {
+			thread.join().ok();
+		}
+		let _ = fs::remove_file(&self.path);
+	}
+}
+
+/// Binds the unix socket at `path`. A socket left behind by a service which is gone is
+/// replaced, but binding fails with `AddrInUse` while a service still listens there.
+fn bind(path: &str) -> io::Result<UnixListener> {
+	match UnixListener::bind(path) {
+		Err(ref e) if e.kind() == io::ErrorKind::AddrInUse => {
+			if ::std::os::unix::net::UnixStream::connect(path).is_ok() {
+				return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("{} is in use by a running service", path)));
+			}
+			try!(fs::remove_file(path));
+			UnixListener::bind(path)
+		},
+		result => result,
+	}
+}
+
+/// Binds the unix socket at `path` and serves `service` to the clients connecting to it
+/// from a new event loop thread
+pub fn start_worker<S: ?Sized>(service: Arc<S>, path: &str) -> Result<WorkerHandle, TransportError>
+	where S: IpcInterface + Send + Sync + 'static
+{
+	let listener = try!(bind(path));
+	let mut event_loop = try!(EventLoop::new());
+	try!(event_loop.register(&listener, LISTENER, EventSet::readable(), PollOpt::level()));
+	let channel = event_loop.channel();
+
+	let thread = thread::spawn(move || {
+		let mut worker = Worker {
+			service: service,
+			listener: listener,
+			connections: HashMap::new(),
+			next_token: LISTENER.as_usize() + 1,
+		};
+		if let Err(e) = event_loop.run(&mut worker) {
+			warn!(target: "ipc", "IPC worker event loop failed: {:?}", e);
+		}
+	});
+
+	Ok(WorkerHandle {
+		channel: channel,
+		thread: Some(thread),
+		path: path.to_owned(),
+	})
+}
+
+type ResponseSender = mpsc::Sender<Vec<u8>>;
+
+/// Calls in flight, shared by the client event loop and the callers
+#[derive(Default)]
+struct Calls {
+	closed: bool,
+	next_id: u64,
+	pending: HashMap<u64, ResponseSender>,
+}
+
+/// Messages from the callers to the client event loop
+#[derive(Debug)]
+pub enum ClientMessage {
+	/// Send request with given id
+	Send(u64, Vec<u8>),
+	/// Stop the event loop
+	Shutdown,
+}
+
+/// Client event loop, owning the connection
+struct ClientLoop {
+	connection: Connection,
+	calls: Arc<Mutex<Calls>>,
+}
+
+impl ClientLoop {
+	fn close(&mut self, event_loop: &mut EventLoop<Self>) {
+		let mut calls = self.calls.lock();
+		calls.closed = true;
+		// dropping the senders wakes up the callers with `Disconnected`
+		calls.pending.clear();
+		event_loop.shutdown();
+	}
+
+	fn reregister(&self, event_loop: &mut EventLoop<Self>) {
+		event_loop.reregister(&self.connection.stream, CONNECTION, self.connection.interest(), PollOpt::level())
+			.unwrap_or_else(|e| warn!(target: "ipc", "Error registering IPC connection: {:?}", e));
+	}
+}
+
+impl Handler for ClientLoop {
+	type Timeout = ();
+	type Message = ClientMessage;
+
+	fn ready(&mut self, event_loop: &mut EventLoop<Self>, _token: Token, events: EventSet) {
+		let mut closed = events.is_hup() || events.is_error();
+		if events.is_readable() {
+			match self.connection.readable() {
+				Ok((frames, peer_closed)) => {
+					let mut calls = self.calls.lock();
+					for (id, response) in frames {
+						match calls.pending.remove(&id) {
+							Some(sender) => { sender.send(response).ok(); },
+							None => warn!(target: "ipc", "Response to unknown IPC request {}", id),
+						}
+					}
+					closed = closed || peer_closed;
+				},
+				Err(e) => {
+					warn!(target: "ipc", "Dropping IPC connection: {:?}", e);
+					closed = true;
+				}
+			}
+		}
+		if let Err(e) = self.connection.writable() {
+			warn!(target: "ipc", "Failed to write request: {:?}", e);
+			closed = true;
+		}
+		match closed {
+			true => self.close(event_loop),
+			false => self.reregister(event_loop),
+		}
+	}
+
+	fn notify(&mut self, event_loop: &mut EventLoop<Self>, msg: ClientMessage) {
+		match msg {
+			ClientMessage::Send(id, request) => {
+				self.connection.send(id, &request);
+				self.reregister(event_loop);
+			},
+			ClientMessage::Shutdown => self.close(event_loop),
+		}
+	}
+}
+
+/// Connection to a service, shared by any number of callers. Calls are pipelined
+/// over the single socket and don't wait for each other.
+pub struct Transport {
+	channel: Mutex<Sender<ClientMessage>>,
+	calls: Arc<Mutex<Calls>>,
+	thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+/// Response of a call in flight
+pub struct PendingCall {
+	receiver: mpsc::Receiver<Vec<u8>>,
+}
+
+impl PendingCall {
+	/// Blocks until the response arrives
+	pub fn wait(self) -> Result<Vec<u8>, TransportError> {
+		self.receiver.recv().map_err(|_| TransportError::Disconnected)
+	}
+}
+
+impl Transport {
+	/// Connects to the service at unix socket `path`, spawning the event loop thread
+	pub fn connect(path: &str) -> Result<Arc<Transport>, TransportError> {
+		let stream = try!(UnixStream::connect(path));
+		let connection = Connection::new(stream);
+		let mut event_loop = try!(EventLoop::new());
+		try!(event_loop.register(&connection.stream, CONNECTION, connection.interest(), PollOpt::level()));
+		let channel = event_loop.channel();
+		let calls = Arc::new(Mutex::new(Calls::default()));
+
+		let loop_calls = calls.clone();
+		let thread = thread::spawn(move || {
+			let mut client = ClientLoop {
+				connection: connection,
+				calls: loop_calls,
+			};
+			if let Err(e) = event_loop.run(&mut client) {
+				warn!(target: "ipc", "IPC client event loop failed: {:?}", e);
+			}
+		});
+
+		Ok(Arc::new(Transport {
+			channel: Mutex::new(channel),
+			calls: calls,
+			thread: Mutex::new(Some(thread)),
+		}))
+	}
+
+	/// Sends `request` (method number followed by the serialized parameters, as written
+	/// by `ipc::invoke`) without waiting for the response
+	pub fn call(&self, request: Vec<u8>) -> Result<PendingCall, TransportError> {
+		let (sender, receiver) = mpsc::channel();
+		// the lock is held until the request is queued, so that the event loop
+		// can't close in between and leave the call waiting forever
+		let mut calls = self.calls.lock();
+		if calls.closed {
+			return Err(TransportError::Disconnected);
+		}
+		let id = calls.next_id;
+		calls.next_id = id.wrapping_add(1);
+		calls.pending.insert(id, sender);
+		if self.channel.lock().send(ClientMessage::Send(id, request)).is_err() {
+			calls.pending.remove(&id);
+			return Err(TransportError::Disconnected);
+		}
+		Ok(PendingCall { receiver: receiver })
+	}
+
+	/// New socket for a generated client, all of them sharing this connection
+	pub fn socket(transport: &Arc<Transport>) -> TransportSocket {
+		TransportSocket {
+			transport: transport.clone(),
+			pending: Mutex::new(None),
+			response: io::Cursor::new(Vec::new()),
+		}
+	}
+
+	/// Stops the event loop; calls in flight fail with `Disconnected`
+	pub fn close(&self) {
+		self.channel.lock().send(ClientMessage::Shutdown).ok();
+		if let Some(thread) = self.thread.lock().take() {
+			thread.join().ok();
+		}
+	}
+}
+
+impl Drop for Transport {
+	fn drop(&mut self) {
+		self.close();
+	}
+}
+
+/// Initializes client `S` over the shared `transport`
+pub fn init_client<S>(transport: &Arc<Transport>) -> Arc<S> where S: WithSocket<TransportSocket> {
+	Arc::new(S::init(Transport::socket(transport)))
+}
+
+/// Blocking `Read + Write` view of a `Transport` for the generated clients. Every write
+/// is sent as a whole request, as `ipc::invoke` writes them, and the response is waited
+/// for when it's read. Responses which are never read, like the ones of methods without
+/// a return value, are dropped.
+pub struct TransportSocket {
+	transport: Arc<Transport>,
+	// the receiver isn't `Sync`, which sockets have to be
+	pending: Mutex<Option<PendingCall>>,
+	response: io::Cursor<Vec<u8>>,
+}
+
+fn broken_pipe(err: TransportError) -> io::Error {
+	io::Error::new(io::ErrorKind::BrokenPipe, format!("{:?}", err))
+}
+
+impl Read for TransportSocket {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		if let Some(pending) = self.pending.lock().take() {
+			let response = try!(pending.wait().map_err(broken_pipe));
+			self.response = io::Cursor::new(response);
+		}
+		self.response.read(buf)
+	}
+}
+
+impl Write for TransportSocket {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		let pending = try!(self.transport.call(buf.to_vec()).map_err(broken_pipe));
+		*self.pending.lock() = Some(pending);
+		self.response = io::Cursor::new(Vec::new());
+		Ok(buf.len())
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+impl IpcSocket for TransportSocket {}
+
+#[cfg(test)]
+mod tests {
+	use super::{start_worker, Transport, FrameReader, encode_frame, TransportError, MAX_FRAME_PAYLOAD};
+	use ipc::{IpcInterface, IpcConfig};
+	use std::io::{self, Read};
+	use std::sync::Arc;
+	use devtools::RandomTempPath;
+
+	// socket path in a new temporary directory, removed with the returned guard.
+	fn socket_path() -> (RandomTempPath, String) {
+		let temp = RandomTempPath::create_dir();
+		let path = temp.as_path().join("worker.ipc").to_str().expect("temporary paths are valid unicode; qed").to_owned();
+		(temp, path)
+	}
+
+	struct EchoService;
+
+	impl IpcInterface for EchoService {
+		fn dispatch<R>(&self, _r: &mut R) -> Vec<u8> where R: Read {
+			vec![]
+		}
+
+		fn dispatch_buf(&self, method_num: u16, buf: &[u8]) -> Vec<u8> {
+			let mut response = vec![method_num as u8];
+			response.extend_from_slice(buf);
+			response
+		}
+	}
+
+	impl IpcConfig for EchoService {}
+
+	#[test]
+	fn frames_split_across_reads() {
+		let mut bytes = Vec::new();
+		encode_frame(7, &[1, 2, 3], &mut bytes);
+		encode_frame(8, &[], &mut bytes);
+
+		let mut reader = FrameReader::default();
+		reader.push(&bytes[..5]);
+		assert_eq!(None, reader.next_frame().unwrap());
+		reader.push(&bytes[5..]);
+		assert_eq!(Some((7, vec![1, 2, 3])), reader.next_frame().unwrap());
+		assert_eq!(Some((8, vec![])), reader.next_frame().unwrap());
+		assert_eq!(None, reader.next_frame().unwrap());
+	}
+
+	#[test]
+	fn rejects_oversized_frames() {
+		let mut bytes = vec![0u8; 8];
+		bytes.extend_from_slice(&[0xff, 0xff, 0xff, 0xff]);
+		let mut reader = FrameReader::default();
+		reader.push(&bytes);
+		match reader.next_frame() {
+			Err(TransportError::FrameTooLarge(len)) => assert!(len > MAX_FRAME_PAYLOAD),
+			other => panic!("unexpected {:?}", other),
+		}
+	}
+
+	#[test]
+	fn pipelines_calls() {
+		let (_temp, path) = socket_path();
+		let _worker = start_worker(Arc::new(EchoService), &path).unwrap();
+		let transport = Transport::connect(&path).unwrap();
+
+		let calls: Vec<_> = (0..100u8).map(|i| transport.call(vec![0, i, i, i]).unwrap()).collect();
+		for (i, call) in calls.into_iter().enumerate() {
+			assert_eq!(vec![i as u8, i as u8, i as u8], call.wait().unwrap());
+		}
+	}
+
+	#[test]
+	fn socket_reads_response() {
+		use std::io::Write;
+
+		let (_temp, path) = socket_path();
+		let _worker = start_worker(Arc::new(EchoService), &path).unwrap();
+		let transport = Transport::connect(&path).unwrap();
+
+		let mut socket = Transport::socket(&transport);
+		socket.write_all(&[0, 5, 9, 9]).unwrap();
+		let mut response = Vec::new();
+		socket.read_to_end(&mut response).unwrap();
+		assert_eq!(vec![5, 9, 9], response);
+
+		// the response of a request which is never read is dropped
+		socket.write_all(&[0, 1]).unwrap();
+		socket.write_all(&[0, 2, 3]).unwrap();
+		let mut response = Vec::new();
+		socket.read_to_end(&mut response).unwrap();
+		assert_eq!(vec![2, 3], response);
+	}
+
+	#[test]
+	fn fails_calls_once_closed() {
+		let (_temp, path) = socket_path();
+		let worker = start_worker(Arc::new(EchoService), &path).unwrap();
+		let transport = Transport::connect(&path).unwrap();
+		let call = transport.call(vec![0, 1]).unwrap();
+		assert_eq!(vec![1], call.wait().unwrap());
+		drop(worker);
+
+		transport.close();
+		assert!(transport.call(vec![0, 1]).is_err());
+	}
+
+	#[test]
+	fn refuses_socket_in_use() {
+		let (_temp, path) = socket_path();
+		let worker = start_worker(Arc::new(EchoService), &path).unwrap();
+		match start_worker(Arc::new(EchoService), &path) {
+			Err(TransportError::Io(ref e)) if e.kind() == io::ErrorKind::AddrInUse => {},
+			_ => panic!("socket of a running worker should not be taken over"),
+		}
+		// the running worker still serves calls
+		let transport = Transport::connect(&path).unwrap();
+		assert_eq!(vec![1], transport.call(vec![0, 1]).unwrap().wait().unwrap());
+		transport.close();
+		drop(worker);
+		assert!(!::std::path::Path::new(&path).exists());
+	}
+
+	#[test]
+	fn replaces_stale_socket() {
+		let (_temp, path) = socket_path();
+		// bound and closed without removing the socket, as after a crash
+		drop(::std::os::unix::net::UnixListener::bind(&path).unwrap());
+		let _worker = start_worker(Arc::new(EchoService), &path).unwrap();
+		let transport = Transport::connect(&path).unwrap();
+		assert_eq!(vec![1], transport.call(vec![0, 1]).unwrap().wait().unwrap());
+	}
+}
@@ -115,6 +115,8 @@ pub enum SocketError {
 	DuplexLink,
 	/// Error establising duplex (paired) socket and/or endpoint
 	RequestLink,
+	/// Error establising publishing socket and/or endpoint
+	PublishLink,
 }
 
 impl<S: ?Sized> Worker<S> where S: IpcInterface {
@@ -138,7 +140,7 @@ impl<S: ?Sized> Worker<S> where S: IpcInterface {
 		for (fd_index, fd) in request.get_fds().iter().enumerate() {
 			if fd.can_read() {
 				let (ref mut socket, _) = self.sockets[fd_index];
-				unsafe { self.buf.set_len(0); }
+				self.buf.clear();
 				match socket.nb_read_to_end(&mut self.buf) {
 					Ok(method_sign_len) => {
 						if method_sign_len >= 2 {
@@ -233,6 +235,40 @@ pub struct IoHandlerWorker {
 	buf: Vec<u8>,
 }
 
+/// Socket broadcasting messages to all connected subscribers (`Sub` sockets).
+/// Messages are dropped for subscribers which can't keep up.
+pub struct Publisher {
+	socket: Socket,
+	_endpoint: Endpoint,
+}
+
+impl Publisher {
+	/// Binds the publishing socket to `addr`.
+	pub fn bind(addr: &str) -> Result<Publisher, SocketError> {
+		let mut socket = try!(Socket::new(Protocol::Pub).map_err(|e| {
+			warn!(target: "ipc", "Failed to create ipc socket: {:?}", e);
+			SocketError::PublishLink
+		}));
+
+		let endpoint = try!(socket.bind(addr).map_err(|e| {
+			warn!(target: "ipc", "Failed to bind socket to address '{}': {:?}", addr, e);
+			SocketError::PublishLink
+		}));
+
+		Ok(Publisher {
+			socket: socket,
+			_endpoint: endpoint,
+		})
+	}
+
+	/// Sends the message to all subscribers.
+	pub fn publish(&mut self, message: &[u8]) {
+		if let Err(e) = self.socket.nb_write(message) {
+			warn!(target: "ipc", "Failed to publish message: {:?}", e);
+		}
+	}
+}
+
 /// IPC server for json-rpc handler (single thread)
 pub struct IoHandlerServer {
 	is_stopping: Arc<AtomicBool>,
@@ -317,7 +353,7 @@ impl IoHandlerWorker {
 			return;
 		}
 
-		unsafe { self.buf.set_len(0); }
+		self.buf.clear();
 		match self.socket.nb_read_to_end(&mut self.buf) {
 			Ok(0) => {
 				warn!(target: "ipc", "RPC empty message received");
@@ -354,7 +390,7 @@ impl IoHandlerWorker {
 #[cfg(test)]
 mod service_tests {
 
-	use super::{Worker, IoHandlerServer};
+	use super::{Worker, IoHandlerServer, Publisher};
 	use ipc::*;
 	use std::io::{Read, Write};
 	use std::sync::{Arc, RwLock};
@@ -422,6 +458,12 @@ mod service_tests {
 		assert_eq!(1, worker.sockets.len());
 	}
 
+	#[test]
+	fn can_bind_publisher() {
+		let mut publisher = Publisher::bind("ipc:///tmp/parity-test15.ipc").unwrap();
+		publisher.publish(b"message");
+	}
+
 	#[test]
 	fn worker_can_poll_empty() {
 		let service = Arc::new(DummyService::new());
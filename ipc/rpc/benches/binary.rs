@@ -0,0 +1,72 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! benchmarking for ipc serialization buffers
+//! should be started with:
+//! ```bash
+//! multirust run nightly cargo bench
+//! ```
+
+#![feature(test)]
+
+extern crate test;
+extern crate ethcore_ipc as ipc;
+
+use test::Bencher;
+use std::io;
+use ipc::BufferPool;
+use ipc::binary::serialize_into_pooled;
+
+fn block_body() -> Vec<Vec<u8>> {
+	(0..200).map(|i| vec![i as u8; 200]).collect()
+}
+
+#[bench]
+fn bench_serialize_pooled(b: &mut Bencher) {
+	let body = block_body();
+	let pool = BufferPool::default();
+	b.iter(|| {
+		serialize_into_pooled(&body, &mut io::sink(), &pool).unwrap();
+	});
+}
+
+#[bench]
+fn bench_serialize_unpooled(b: &mut Bencher) {
+	let body = block_body();
+	// keeps no buffers, so every call allocates
+	let pool = BufferPool::new(0, 0);
+	b.iter(|| {
+		serialize_into_pooled(&body, &mut io::sink(), &pool).unwrap();
+	});
+}
+
+#[bench]
+fn bench_serialize_small_pooled(b: &mut Bencher) {
+	let value = vec![1u64, 2u64, 3u64];
+	let pool = BufferPool::default();
+	b.iter(|| {
+		serialize_into_pooled(&value, &mut io::sink(), &pool).unwrap();
+	});
+}
+
+#[bench]
+fn bench_serialize_small_unpooled(b: &mut Bencher) {
+	let value = vec![1u64, 2u64, 3u64];
+	let pool = BufferPool::new(0, 0);
+	b.iter(|| {
+		serialize_into_pooled(&value, &mut io::sink(), &pool).unwrap();
+	});
+}
@@ -16,19 +16,132 @@
 
 //! Binary representation of types
 
-use util::bytes::Populatable;
-use util::numbers::{U256, U512, H256, H2048, Address};
+use util::numbers::{U256, U512, H256, H2048, Address, Uint, FixedHash};
+use util::error::{Mismatch, OutOfBounds};
+use std::cmp;
+use std::fmt;
 use std::mem;
-use std::collections::{VecDeque, BTreeMap};
+use std::collections::{VecDeque, BTreeMap, HashMap, HashSet};
+use std::hash::Hash;
 use std::ops::Range;
-use super::Handshake;
+use super::{Handshake, Negotiation};
+use pool::{BufferPool, with_local_pool};
+
+/// Error serializing or deserializing a value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BinaryConvertError {
+	/// Buffer doesn't have the size the value takes.
+	SizeMismatch {
+		/// Offset of the value in the buffer.
+		offset: usize,
+		/// Number of bytes the value takes.
+		expected: usize,
+		/// Number of bytes available.
+		found: usize,
+	},
+	/// No length left on the length stack for a variable-size value.
+	MissingLength {
+		/// Offset of the value in the buffer.
+		offset: usize,
+	},
+	/// Unknown enum variant tag.
+	UnknownVariant {
+		/// Offset of the tag in the buffer.
+		offset: usize,
+		/// The tag read.
+		tag: u16,
+	},
+	/// Bytes that don't encode a value of the type, or a value that can't be encoded.
+	InvalidValue {
+		/// Offset of the value in the buffer.
+		offset: usize,
+		/// Name of the type.
+		type_name: &'static str,
+	},
+	/// Error in a member of a value of given type.
+	Inner {
+		/// Name of the type.
+		type_name: &'static str,
+		/// Error in the member, at an offset in the buffer of the outer value.
+		error: Box<BinaryConvertError>,
+	},
+	/// Reading from or writing to a stream failed.
+	Io(String),
+}
+
+impl BinaryConvertError {
+	/// Buffer of `found` bytes where `expected` are needed.
+	pub fn size(expected: usize, found: usize) -> Self {
+		BinaryConvertError::SizeMismatch { offset: 0, expected: expected, found: found }
+	}
+
+	/// Missing length on the length stack.
+	pub fn missing_length() -> Self {
+		BinaryConvertError::MissingLength { offset: 0 }
+	}
+
+	/// Unknown variant tag.
+	pub fn unknown_variant(tag: u16) -> Self {
+		BinaryConvertError::UnknownVariant { offset: 0, tag: tag }
+	}
 
-#[derive(Debug)]
-pub struct BinaryConvertError;
+	/// Invalid value of given type.
+	pub fn invalid(type_name: &'static str) -> Self {
+		BinaryConvertError::InvalidValue { offset: 0, type_name: type_name }
+	}
+
+	/// Error in a member of a value of given type.
+	pub fn inner(type_name: &'static str, error: BinaryConvertError) -> Self {
+		BinaryConvertError::Inner { type_name: type_name, error: Box::new(error) }
+	}
+
+	/// Stream error.
+	pub fn io(error: ::std::io::Error) -> Self {
+		BinaryConvertError::Io(format!("{}", error))
+	}
+
+	/// The same error for a value `offset` bytes further into the buffer, so that errors
+	/// of nested values point into the buffer of the outermost one.
+	pub fn at(self, offset: usize) -> Self {
+		use self::BinaryConvertError::*;
+		match self {
+			SizeMismatch { offset: o, expected, found } => SizeMismatch { offset: o + offset, expected: expected, found: found },
+			MissingLength { offset: o } => MissingLength { offset: o + offset },
+			UnknownVariant { offset: o, tag } => UnknownVariant { offset: o + offset, tag: tag },
+			InvalidValue { offset: o, type_name } => InvalidValue { offset: o + offset, type_name: type_name },
+			Inner { type_name, error } => Inner { type_name: type_name, error: Box::new(error.at(offset)) },
+			Io(e) => Io(e),
+		}
+	}
+
+	/// Offset in the buffer where the error occurred.
+	pub fn offset(&self) -> usize {
+		use self::BinaryConvertError::*;
+		match *self {
+			SizeMismatch { offset, .. } | MissingLength { offset } | UnknownVariant { offset, .. } | InvalidValue { offset, .. } => offset,
+			Inner { ref error, .. } => error.offset(),
+			Io(_) => 0,
+		}
+	}
+}
+
+impl fmt::Display for BinaryConvertError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		use self::BinaryConvertError::*;
+		match *self {
+			SizeMismatch { offset, expected, found } => write!(f, "Expected {} bytes at offset {}, found {}", expected, offset, found),
+			MissingLength { offset } => write!(f, "Missing length of the value at offset {}", offset),
+			UnknownVariant { offset, tag } => write!(f, "Unknown variant {} at offset {}", tag, offset),
+			InvalidValue { offset, type_name } => write!(f, "Invalid {} at offset {}", type_name, offset),
+			Inner { type_name, ref error } => write!(f, "{} in {}", error, type_name),
+			Io(ref e) => write!(f, "IO error: {}", e),
+		}
+	}
+}
 
 pub trait BinaryConvertable : Sized {
 	fn size(&self) -> usize {
-		mem::size_of::<Self>()
+		Self::fixed_size()
 	}
 
 	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError>;
@@ -36,12 +149,34 @@ pub trait BinaryConvertable : Sized {
 	fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError>;
 
 	fn from_empty_bytes() -> Result<Self, BinaryConvertError> {
-		Err(BinaryConvertError)
+		Err(BinaryConvertError::size(Self::fixed_size(), 0))
 	}
 
 	fn len_params() -> usize {
 		0
 	}
+
+	/// Size of the encoding of types with `len_params() == 0`, the same on every host.
+	fn fixed_size() -> usize {
+		mem::size_of::<Self>()
+	}
+
+	/// Writes `items` one after another, as `Vec<Self>` does. Overridden by types
+	/// which can copy whole slices at once.
+	fn slice_to_bytes(items: &[Self], buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+		set_to_bytes(items.iter(), buffer, length_stack)
+	}
+
+	/// Reads items written with `slice_to_bytes`.
+	fn vec_from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Vec<Self>, BinaryConvertError> {
+		let mut result = Vec::with_capacity(
+			match Self::len_params() {
+				0 => buffer.len() / cmp::max(Self::fixed_size(), 1),
+				_ => 128,
+			});
+		try!(set_from_bytes(buffer, length_stack, |item| result.push(item)));
+		Ok(result)
+	}
 }
 
 impl<T> BinaryConvertable for Option<T> where T: BinaryConvertable {
@@ -50,7 +185,7 @@ impl<T> BinaryConvertable for Option<T> where T: BinaryConvertable {
 	}
 
 	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
-		match *self { None => Err(BinaryConvertError), Some(ref val) => val.to_bytes(buffer, length_stack) }
+		match *self { None => Err(BinaryConvertError::invalid("Option")), Some(ref val) => val.to_bytes(buffer, length_stack) }
 	}
 
 	fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
@@ -77,7 +212,7 @@ impl<E: BinaryConvertable> BinaryConvertable for Result<(), E> {
 
 	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
 		match *self {
-			Ok(_) => Err(BinaryConvertError),
+			Ok(_) => Err(BinaryConvertError::invalid("Result")),
 			Err(ref e) => Ok(try!(e.to_bytes(buffer, length_stack))),
 		}
 	}
@@ -107,7 +242,7 @@ impl<R: BinaryConvertable> BinaryConvertable for Result<R, ()> {
 	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
 		match *self {
 			Ok(ref r) => Ok(try!(r.to_bytes(buffer, length_stack))),
-			Err(_) => Err(BinaryConvertError),
+			Err(_) => Err(BinaryConvertError::invalid("Result")),
 		}
 	}
 
@@ -152,15 +287,18 @@ impl<R: BinaryConvertable, E: BinaryConvertable> BinaryConvertable for Result<R,
 	}
 
 	fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		if buffer.is_empty() {
+			return Err(BinaryConvertError::size(1, 0));
+		}
 		match buffer[0] {
 			0 => {
 				match buffer.len() {
-					1 => Ok(Ok(try!(R::from_empty_bytes()))),
-					_ => Ok(Ok(try!(R::from_bytes(&buffer[1..], length_stack)))),
+					1 => Ok(Ok(try!(R::from_empty_bytes().map_err(|e| e.at(1))))),
+					_ => Ok(Ok(try!(R::from_bytes(&buffer[1..], length_stack).map_err(|e| e.at(1))))),
 				}
 			}
-			1 => Ok(Err(try!(E::from_bytes(&buffer[1..], length_stack)))),
-			_ => Err(BinaryConvertError)
+			1 => Ok(Err(try!(E::from_bytes(&buffer[1..], length_stack).map_err(|e| e.at(1))))),
+			tag => Err(BinaryConvertError::unknown_variant(tag as u16)),
 		}
 	}
 
@@ -169,80 +307,187 @@ impl<R: BinaryConvertable, E: BinaryConvertable> BinaryConvertable for Result<R,
 	}
 }
 
+/// Writes an enum variant tag `width` bytes wide (1 or 2), little-endian.
+/// Used by `#[derive(Binary)]`, the variant payload follows the tag.
+pub fn tag_to_bytes(tag: u16, width: usize, buffer: &mut [u8]) -> Result<(), BinaryConvertError> {
+	if buffer.len() < width {
+		return Err(BinaryConvertError::size(width, buffer.len()));
+	}
+	match width {
+		1 if tag <= 0xff => buffer[0] = tag as u8,
+		2 => {
+			buffer[0] = tag as u8;
+			buffer[1] = (tag >> 8) as u8;
+		},
+		_ => return Err(BinaryConvertError::invalid("enum tag")),
+	}
+	Ok(())
+}
+
+/// Reads an enum variant tag written with `tag_to_bytes`.
+pub fn tag_from_bytes(buffer: &[u8], width: usize) -> Result<u16, BinaryConvertError> {
+	if buffer.len() < width {
+		return Err(BinaryConvertError::size(width, buffer.len()));
+	}
+	match width {
+		1 => Ok(buffer[0] as u16),
+		2 => Ok(buffer[0] as u16 | (buffer[1] as u16) << 8),
+		_ => Err(BinaryConvertError::invalid("enum tag")),
+	}
+}
+
+// Maps are encoded as their entries in iteration order, each key followed by its value.
+// The length of a variable-size key or value is pushed to the length stack right before
+// the item itself is written, so lengths of nested items follow it, in the order they are read.
+
+fn map_size<'a, K, V, I>(len: usize, entries: I) -> usize
+	where K: 'a + BinaryConvertable, V: 'a + BinaryConvertable, I: Iterator<Item=(&'a K, &'a V)>
+{
+	match (K::len_params(), V::len_params()) {
+		(0, 0) => (K::fixed_size() + V::fixed_size()) * len,
+		_ => entries.fold(0usize, |acc, (k, v)| acc + item_size(k) + item_size(v)),
+	}
+}
+
+fn map_to_bytes<'a, K, V, I>(entries: I, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError>
+	where K: 'a + BinaryConvertable, V: 'a + BinaryConvertable, I: Iterator<Item=(&'a K, &'a V)>
+{
+	let mut offset = 0usize;
+	for (key, val) in entries {
+		offset = try!(item_to_bytes(key, offset, buffer, length_stack));
+		offset = try!(item_to_bytes(val, offset, buffer, length_stack));
+	}
+	Ok(())
+}
+
+fn map_from_bytes<K, V, F>(buffer: &[u8], length_stack: &mut VecDeque<usize>, mut insert: F) -> Result<(), BinaryConvertError>
+	where K: BinaryConvertable, V: BinaryConvertable, F: FnMut(K, V)
+{
+	if K::len_params() + V::len_params() == 0 && K::fixed_size() + V::fixed_size() == 0 && !buffer.is_empty() {
+		return Err(BinaryConvertError::size(0, buffer.len()));
+	}
+	let mut index = 0;
+	while index < buffer.len() {
+		let (key, next) = try!(item_from_bytes::<K>(index, buffer, length_stack));
+		let (val, next) = try!(item_from_bytes::<V>(next, buffer, length_stack));
+		insert(key, val);
+		index = next;
+	}
+	Ok(())
+}
+
+// Sets are encoded like vectors.
+
+fn set_size<'a, T, I>(len: usize, items: I) -> usize
+	where T: 'a + BinaryConvertable, I: Iterator<Item=&'a T>
+{
+	match T::len_params() {
+		0 => T::fixed_size() * len,
+		_ => items.fold(0usize, |acc, t| acc + t.size()),
+	}
+}
+
+fn set_to_bytes<'a, T, I>(items: I, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError>
+	where T: 'a + BinaryConvertable, I: Iterator<Item=&'a T>
+{
+	let mut offset = 0usize;
+	for item in items {
+		offset = try!(item_to_bytes(item, offset, buffer, length_stack));
+	}
+	Ok(())
+}
+
+fn set_from_bytes<T, F>(buffer: &[u8], length_stack: &mut VecDeque<usize>, mut insert: F) -> Result<(), BinaryConvertError>
+	where T: BinaryConvertable, F: FnMut(T)
+{
+	if T::len_params() == 0 && T::fixed_size() == 0 && !buffer.is_empty() {
+		return Err(BinaryConvertError::size(0, buffer.len()));
+	}
+	let mut index = 0;
+	while index < buffer.len() {
+		let (item, next) = try!(item_from_bytes::<T>(index, buffer, length_stack));
+		insert(item);
+		index = next;
+	}
+	Ok(())
+}
+
+fn item_size<T: BinaryConvertable>(item: &T) -> usize {
+	match T::len_params() {
+		0 => T::fixed_size(),
+		_ => item.size(),
+	}
+}
+
+// writes an item of a collection at `offset`, returning the offset of the next one.
+fn item_to_bytes<T: BinaryConvertable>(item: &T, offset: usize, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<usize, BinaryConvertError> {
+	let size = match T::len_params() {
+		0 => T::fixed_size(),
+		_ => { let size = item.size(); length_stack.push_back(size); size },
+	};
+	if size > 0 {
+		let end = offset + size;
+		if end > buffer.len() {
+			return Err(BinaryConvertError::size(size, buffer.len().saturating_sub(offset)).at(offset));
+		}
+		try!(item.to_bytes(&mut buffer[offset..end], length_stack).map_err(|e| e.at(offset)));
+	}
+	Ok(offset + size)
+}
+
+// reads an item of a collection at `index`, returning it with the index of the next one.
+fn item_from_bytes<T: BinaryConvertable>(index: usize, buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<(T, usize), BinaryConvertError> {
+	let size = match T::len_params() {
+		0 => T::fixed_size(),
+		_ => try!(length_stack.pop_front().ok_or(BinaryConvertError::MissingLength { offset: index })),
+	};
+	let end = match index.checked_add(size) {
+		Some(end) if end <= buffer.len() => end,
+		_ => return Err(BinaryConvertError::size(size, buffer.len() - index).at(index)),
+	};
+	let item = match size {
+		0 => try!(T::from_empty_bytes().map_err(|e| e.at(index))),
+		_ => try!(T::from_bytes(&buffer[index..end], length_stack).map_err(|e| e.at(index))),
+	};
+	Ok((item, end))
+}
+
 impl<K, V> BinaryConvertable for BTreeMap<K, V> where K : BinaryConvertable + Ord, V: BinaryConvertable {
 	fn size(&self) -> usize {
-		0usize + match K::len_params() {
-			0 => mem::size_of::<K>() * self.len(),
-			_ => self.iter().fold(0usize, |acc, (k, _)| acc + k.size())
-		} + match V::len_params() {
-			0 => mem::size_of::<V>() * self.len(),
-			_ => self.iter().fold(0usize, |acc, (_, v)| acc + v.size())
-		}
+		map_size(self.len(), self.iter())
 	}
 
 	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
-		let mut offset = 0usize;
-		for (key, val) in self.iter() {
-			let key_size = match K::len_params() {
-				0 => mem::size_of::<K>(),
-				_ => { let size = key.size(); length_stack.push_back(size); size }
-			};
-			let val_size = match K::len_params() {
-				0 => mem::size_of::<V>(),
-				_ => { let size = val.size(); length_stack.push_back(size); size }
-			};
-
-			if key_size > 0 {
-				let item_end = offset + key_size;
-				try!(key.to_bytes(&mut buffer[offset..item_end], length_stack));
-				offset = item_end;
-			}
-
-			if val_size > 0 {
-				let item_end = offset + key_size;
-				try!(val.to_bytes(&mut buffer[offset..item_end], length_stack));
-				offset = item_end;
-			}
-		}
-		Ok(())
+		map_to_bytes(self.iter(), buffer, length_stack)
 	}
 
 	fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
-		let mut index = 0;
 		let mut result = Self::new();
+		try!(map_from_bytes(buffer, length_stack, |k, v| { result.insert(k, v); }));
+		Ok(result)
+	}
 
-		if buffer.len() == 0 { return Ok(result); }
-
-		loop {
-			let key_size = match K::len_params() {
-				0 => mem::size_of::<K>(),
-				_ => try!(length_stack.pop_front().ok_or(BinaryConvertError)),
-			};
-			let key = if key_size == 0 {
-				try!(K::from_empty_bytes())
-			} else {
-				try!(K::from_bytes(&buffer[index..index+key_size], length_stack))
-			};
-			index = index + key_size;
-
-			let val_size = match V::len_params() {
-				0 => mem::size_of::<V>(),
-				_ => try!(length_stack.pop_front().ok_or(BinaryConvertError)),
-			};
-			let val = if val_size == 0 {
-				try!(V::from_empty_bytes())
-			} else {
-				try!(V::from_bytes(&buffer[index..index+val_size], length_stack))
-			};
-			result.insert(key, val);
-			index = index + val_size;
-
-			if index == buffer.len() { break; }
-			if index > buffer.len() {
-				return Err(BinaryConvertError)
-			}
-		}
+	fn from_empty_bytes() -> Result<Self, BinaryConvertError> {
+		Ok(Self::new())
+	}
+
+	fn len_params() -> usize {
+		1
+	}
+}
 
+impl<K, V> BinaryConvertable for HashMap<K, V> where K : BinaryConvertable + Eq + Hash, V: BinaryConvertable {
+	fn size(&self) -> usize {
+		map_size(self.len(), self.iter())
+	}
+
+	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+		map_to_bytes(self.iter(), buffer, length_stack)
+	}
+
+	fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		let mut result = Self::new();
+		try!(map_from_bytes(buffer, length_stack, |k, v| { result.insert(k, v); }));
 		Ok(result)
 	}
 
@@ -255,62 +500,140 @@ impl<K, V> BinaryConvertable for BTreeMap<K, V> where K : BinaryConvertable + Or
 	}
 }
 
-impl<T> BinaryConvertable for Vec<T> where T: BinaryConvertable {
+impl<T> BinaryConvertable for HashSet<T> where T: BinaryConvertable + Eq + Hash {
 	fn size(&self) -> usize {
-		match T::len_params() {
-			0 => mem::size_of::<T>() * self.len(),
-			_ => self.iter().fold(0usize, |acc, t| acc + t.size()),
-		}
+		set_size(self.len(), self.iter())
 	}
 
 	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
-		let mut offset = 0usize;
-		for item in self.iter() {
-			let next_size = match T::len_params() {
-				0 => mem::size_of::<T>(),
-				_ => { let size = item.size(); length_stack.push_back(size); size },
-			};
-			if next_size > 0 {
-				let item_end = offset + next_size;
-				try!(item.to_bytes(&mut buffer[offset..item_end], length_stack));
-				offset = item_end;
+		set_to_bytes(self.iter(), buffer, length_stack)
+	}
+
+	fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		let mut result = Self::new();
+		try!(set_from_bytes(buffer, length_stack, |t| { result.insert(t); }));
+		Ok(result)
+	}
+
+	fn from_empty_bytes() -> Result<Self, BinaryConvertError> {
+		Ok(Self::new())
+	}
+
+	fn len_params() -> usize {
+		1
+	}
+}
+
+// Tuples and arrays are encoded as their elements one after another, like collections.
+
+macro_rules! binary_tuple {
+	($($name: ident $var: ident),+) => {
+		impl<$($name),+> BinaryConvertable for ($($name),+) where $($name: BinaryConvertable),+ {
+			fn size(&self) -> usize {
+				let ($(ref $var),+) = *self;
+				0usize $(+ item_size($var))+
+			}
+
+			fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+				let ($(ref $var),+) = *self;
+				let offset = 0usize;
+				$(let offset = try!(item_to_bytes($var, offset, buffer, length_stack));)+
+				match offset == buffer.len() {
+					true => Ok(()),
+					false => Err(BinaryConvertError::size(offset, buffer.len())),
+				}
+			}
+
+			fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+				let index = 0usize;
+				$(let ($var, index) = try!(item_from_bytes::<$name>(index, buffer, length_stack));)+
+				match index == buffer.len() {
+					true => Ok(($($var),+)),
+					false => Err(BinaryConvertError::size(index, buffer.len())),
+				}
+			}
+
+			fn from_empty_bytes() -> Result<Self, BinaryConvertError> {
+				Ok(($(try!($name::from_empty_bytes())),+))
+			}
+
+			fn len_params() -> usize {
+				1
 			}
 		}
-		Ok(())
 	}
+}
 
-	fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
-		let mut index = 0;
-		let mut result = Self::with_capacity(
-			match T::len_params() {
-				0 => buffer.len() /  mem::size_of::<T>(),
-				_ => 128,
-			});
+binary_tuple!(A a, B b);
+binary_tuple!(A a, B b, C c);
 
-		if buffer.len() == 0 { return Ok(result); }
+// Arrays are built from a list of one token per element, as elements are read in place.
+macro_rules! binary_array {
+	($($len: expr => ($($element: tt)+)),+) => { $(
+		impl<T> BinaryConvertable for [T; $len] where T: BinaryConvertable {
+			fn size(&self) -> usize {
+				set_size(self.len(), self.iter())
+			}
 
-		loop {
-			let next_size = match T::len_params() {
-				0 => mem::size_of::<T>(),
-				_ => try!(length_stack.pop_front().ok_or(BinaryConvertError)),
-			};
-			let item = if next_size == 0 {
-				try!(T::from_empty_bytes())
+			fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+				set_to_bytes(self.iter(), buffer, length_stack)
 			}
-			else {
-				try!(T::from_bytes(&buffer[index..index+next_size], length_stack))
-			};
-			result.push(item);
-
-			index = index + next_size;
-			if index == buffer.len() { break; }
-			if index > buffer.len() {
-				return Err(BinaryConvertError)
+
+			fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+				let mut index = 0usize;
+				let result = [$({
+					let _ = stringify!($element);
+					let (item, next) = try!(item_from_bytes::<T>(index, buffer, length_stack));
+					index = next;
+					item
+				}),+];
+				match index == buffer.len() {
+					true => Ok(result),
+					false => Err(BinaryConvertError::size(index, buffer.len())),
+				}
 			}
 
+			fn from_empty_bytes() -> Result<Self, BinaryConvertError> {
+				Ok([$({
+					let _ = stringify!($element);
+					try!(T::from_empty_bytes())
+				}),+])
+			}
+
+			fn len_params() -> usize {
+				1
+			}
 		}
+	)+ }
+}
 
-		Ok(result)
+binary_array! {
+	1 => (x),
+	2 => (x x),
+	3 => (x x x),
+	4 => (x x x x),
+	5 => (x x x x x),
+	6 => (x x x x x x),
+	7 => (x x x x x x x),
+	8 => (x x x x x x x x),
+	16 => (x x x x x x x x x x x x x x x x),
+	32 => (x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x x)
+}
+
+impl<T> BinaryConvertable for Vec<T> where T: BinaryConvertable {
+	fn size(&self) -> usize {
+		match T::len_params() {
+			0 => T::fixed_size() * self.len(),
+			_ => self.iter().fold(0usize, |acc, t| acc + t.size()),
+		}
+	}
+
+	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+		T::slice_to_bytes(self, buffer, length_stack)
+	}
+
+	fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		T::vec_from_bytes(buffer, length_stack)
 	}
 
 	fn from_empty_bytes() -> Result<Self, BinaryConvertError> {
@@ -337,7 +660,8 @@ impl BinaryConvertable for String {
 	}
 
 	fn from_bytes(buffer: &[u8], _length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
-		Ok(::std::str::from_utf8(buffer).unwrap().to_owned())
+		::std::str::from_utf8(buffer).map(|s| s.to_owned())
+			.map_err(|e| BinaryConvertError::invalid("String").at(e.valid_up_to()))
 	}
 
 	fn len_params() -> usize {
@@ -347,27 +671,41 @@ impl BinaryConvertable for String {
 
 impl<T> BinaryConvertable for Range<T> where T: BinaryConvertable {
 	fn size(&self) -> usize {
-		mem::size_of::<T>() * 2
+		T::fixed_size() * 2
 	}
 
 	fn from_empty_bytes() -> Result<Self, BinaryConvertError> {
-		Err(BinaryConvertError)
+		Err(BinaryConvertError::size(Self::fixed_size(), 0))
 	}
 
 	fn to_bytes(&self, buffer: &mut[u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
-		try!(self.start.to_bytes(&mut buffer[..mem::size_of::<T>()], length_stack));
-		try!(self.end.to_bytes(&mut buffer[mem::size_of::<T>() + 1..], length_stack));
+		let half = T::fixed_size();
+		if buffer.len() < half * 2 {
+			return Err(BinaryConvertError::size(half * 2, buffer.len()));
+		}
+		try!(self.start.to_bytes(&mut buffer[..half], length_stack));
+		try!(self.end.to_bytes(&mut buffer[half..], length_stack).map_err(|e| e.at(half)));
 		Ok(())
 	}
 
 	fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
-		Ok(try!(T::from_bytes(&buffer[..mem::size_of::<T>()], length_stack))..try!(T::from_bytes(&buffer[mem::size_of::<T>()+1..], length_stack)))
+		let half = T::fixed_size();
+		if buffer.len() != half * 2 {
+			return Err(BinaryConvertError::size(half * 2, buffer.len()));
+		}
+		let start = try!(T::from_bytes(&buffer[..half], length_stack));
+		let end = try!(T::from_bytes(&buffer[half..], length_stack).map_err(|e| e.at(half)));
+		Ok(start..end)
 	}
 
 	fn len_params() -> usize {
 		assert_eq!(0, T::len_params());
 		0
 	}
+
+	fn fixed_size() -> usize {
+		T::fixed_size() * 2
+	}
 }
 
 impl<T> BinaryConvertable for ::std::cell::RefCell<T> where T: BinaryConvertable {
@@ -398,259 +736,808 @@ impl<T> BinaryConvertable for ::std::cell::Cell<T> where T: BinaryConvertable +
 		self.get().size()
 	}
 
-	fn from_empty_bytes() -> Result<Self, BinaryConvertError> {
-		Ok(::std::cell::Cell::new(try!(T::from_empty_bytes())))
+	fn from_empty_bytes() -> Result<Self, BinaryConvertError> {
+		Ok(::std::cell::Cell::new(try!(T::from_empty_bytes())))
+	}
+
+	fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		Ok(::std::cell::Cell::new(try!(T::from_bytes(buffer, length_stack))))
+	}
+
+	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+		try!(self.get().to_bytes(buffer, length_stack));
+		Ok(())
+	}
+
+	fn len_params() -> usize {
+		T::len_params()
+	}
+}
+
+/// Reads a member of a value of type `type_name` from `buffer[start..end]`.
+/// Used by `#[derive(Binary)]`; errors point into `buffer`.
+pub fn member_from_bytes<T>(buffer: &[u8], start: usize, end: usize, length_stack: &mut VecDeque<usize>, type_name: &'static str) -> Result<T, BinaryConvertError>
+	where T: BinaryConvertable
+{
+	let result = match end - start {
+		0 => T::from_empty_bytes(),
+		_ => T::from_bytes(&buffer[start..end], length_stack),
+	};
+	result.map_err(|e| BinaryConvertError::inner(type_name, e.at(start)))
+}
+
+/// Reads a value serialized with `serialize_into`. Values of variable size take the rest of the stream.
+pub fn deserialize_from<T, R>(r: &mut R) -> Result<T, BinaryConvertError>
+	where R: ::std::io::Read,
+		T: BinaryConvertable
+{
+	with_local_pool(|pool| {
+		let mut payload = pool.take();
+		match T::len_params() {
+			0 => {
+				payload.resize(T::fixed_size(), 0);
+				try!(r.read_exact(&mut payload).map_err(BinaryConvertError::io));
+			},
+			_ => { try!(r.read_to_end(&mut payload).map_err(BinaryConvertError::io)); },
+		}
+		deserialize(&payload)
+	})
+}
+
+/// Reads a value from the start of `buffer`, without copying it.
+pub fn deserialize<T: BinaryConvertable>(buffer: &[u8]) -> Result<T, BinaryConvertError> {
+	let mut fake_stack = VecDeque::new();
+
+	if T::len_params() == 0 {
+		let fixed_size = T::fixed_size();
+		if buffer.len() < fixed_size {
+			return Err(BinaryConvertError::size(fixed_size, buffer.len()));
+		}
+		return T::from_bytes(&buffer[..fixed_size], &mut fake_stack);
+	}
+
+	if buffer.len() < 8 {
+		return Err(BinaryConvertError::size(8, buffer.len()));
+	}
+	let stack_len = try!(usize::from_bytes(&buffer[0..8], &mut fake_stack));
+	// the header holds the stack length, the stack and the payload size, 8 bytes each.
+	let header_len = try!(stack_len.checked_add(2).and_then(|len| len.checked_mul(8)).ok_or(BinaryConvertError::invalid("length stack")));
+	if header_len > buffer.len() {
+		return Err(BinaryConvertError::size(header_len, buffer.len()));
+	}
+
+	let mut length_stack = VecDeque::<usize>::with_capacity(stack_len);
+	for idx in 0..stack_len {
+		let stack_item = try!(usize::from_bytes(&buffer[8 + idx*8..8 + (idx+1)*8], &mut fake_stack));
+		length_stack.push_back(stack_item);
+	}
+
+	let size = try!(usize::from_bytes(&buffer[header_len - 8..header_len], &mut fake_stack));
+	if size > buffer.len() - header_len {
+		return Err(BinaryConvertError::size(size, buffer.len() - header_len).at(header_len));
+	}
+	match size {
+		0 => T::from_empty_bytes().map_err(|e| e.at(header_len)),
+		_ => T::from_bytes(&buffer[header_len..header_len + size], &mut length_stack).map_err(|e| e.at(header_len)),
+	}
+}
+
+/// Writes a value readable with `deserialize_from`.
+pub fn serialize_into<T, W>(t: &T, w: &mut W) -> Result<(), BinaryConvertError>
+	where W: ::std::io::Write,
+		T: BinaryConvertable
+{
+	with_local_pool(|pool| serialize_into_pooled(t, w, pool))
+}
+
+/// Writes a value readable with `deserialize_from`, using buffers from `pool`.
+pub fn serialize_into_pooled<T, W>(t: &T, w: &mut W, pool: &BufferPool) -> Result<(), BinaryConvertError>
+	where W: ::std::io::Write,
+		T: BinaryConvertable
+{
+	let (mut header, mut payload) = (pool.take(), pool.take());
+	try!(encode(t, &mut header, &mut payload));
+	try!(w.write_all(&header).map_err(BinaryConvertError::io));
+	w.write_all(&payload).map_err(BinaryConvertError::io)
+}
+
+/// Serializes a value. Fixed-size values are written as they are; variable-size ones
+/// are preceded by the length stack and their size.
+pub fn serialize<T: BinaryConvertable>(t: &T) -> Result<Vec<u8>, BinaryConvertError> {
+	with_local_pool(|pool| {
+		let (mut header, mut payload) = (pool.take(), pool.take());
+		try!(encode(t, &mut header, &mut payload));
+		let mut bytes = Vec::with_capacity(header.len() + payload.len());
+		bytes.extend_from_slice(&header);
+		bytes.extend_from_slice(&payload);
+		Ok(bytes)
+	})
+}
+
+// Appends the encoding of `t` to the (empty) buffers: the header, if any, and the payload.
+fn encode<T: BinaryConvertable>(t: &T, header: &mut Vec<u8>, payload: &mut Vec<u8>) -> Result<(), BinaryConvertError> {
+	let mut length_stack = VecDeque::<usize>::new();
+
+	if T::len_params() == 0 {
+		payload.resize(T::fixed_size(), 0);
+		return t.to_bytes(payload, &mut length_stack);
+	}
+
+	let size = t.size();
+	payload.resize(size, 0);
+	if size > 0 {
+		try!(t.to_bytes(payload, &mut length_stack));
+	}
+	encode_header(header, length_stack, size)
+}
+
+// Appends the encoding of `items` to the (empty) buffers, the same as a `Vec` of them would be encoded.
+fn encode_slice<T: BinaryConvertable>(items: &[T], header: &mut Vec<u8>, payload: &mut Vec<u8>) -> Result<(), BinaryConvertError> {
+	let mut length_stack = VecDeque::<usize>::new();
+	let size = items.iter().fold(0usize, |acc, item| acc + encoded_size(item));
+	payload.resize(size, 0);
+	if size > 0 {
+		try!(T::slice_to_bytes(items, payload, &mut length_stack));
+	}
+	encode_header(header, length_stack, size)
+}
+
+fn encode_header(header: &mut Vec<u8>, length_stack: VecDeque<usize>, size: usize) -> Result<(), BinaryConvertError> {
+	try!(push_length(header, length_stack.len()));
+	for length in length_stack {
+		try!(push_length(header, length));
+	}
+	push_length(header, size)
+}
+
+// Size of a single item within a `Vec`.
+fn encoded_size<T: BinaryConvertable>(item: &T) -> usize {
+	match T::len_params() {
+		0 => T::fixed_size(),
+		_ => item.size(),
+	}
+}
+
+fn push_length(bytes: &mut Vec<u8>, length: usize) -> Result<(), BinaryConvertError> {
+	let mut buffer = [0u8; 8];
+	try!(length.to_bytes(&mut buffer, &mut VecDeque::new()));
+	bytes.extend_from_slice(&buffer);
+	Ok(())
+}
+
+// Streamed items are framed as `[1u8][size u64][serialized item]`, the stream ends with `[0u8]`.
+const STREAM_ITEM: u8 = 1;
+const STREAM_END: u8 = 0;
+
+/// Writes a sequence of values one at a time, so that only a single item
+/// has to be held in memory. Read back with `BinaryStreamReader`.
+pub struct BinaryStreamWriter<'a, W: 'a + ::std::io::Write> {
+	writer: &'a mut W,
+	items: usize,
+}
+
+impl<'a, W: ::std::io::Write> BinaryStreamWriter<'a, W> {
+	/// New stream writing to `writer`.
+	pub fn new(writer: &'a mut W) -> Self {
+		BinaryStreamWriter {
+			writer: writer,
+			items: 0,
+		}
+	}
+
+	/// Writes the next item of the stream.
+	pub fn write_item<T: BinaryConvertable>(&mut self, item: &T) -> Result<(), BinaryConvertError> {
+		with_local_pool(|pool| {
+			let (mut header, mut payload) = (pool.take(), pool.take());
+			try!(encode(item, &mut header, &mut payload));
+			self.write_frame(&header, &payload)
+		})
+	}
+
+	/// Writes `items` as the next item of the stream, to be read back as a `Vec`.
+	pub fn write_slice<T: BinaryConvertable>(&mut self, items: &[T]) -> Result<(), BinaryConvertError> {
+		with_local_pool(|pool| {
+			let (mut header, mut payload) = (pool.take(), pool.take());
+			try!(encode_slice(items, &mut header, &mut payload));
+			self.write_frame(&header, &payload)
+		})
+	}
+
+	fn write_frame(&mut self, header: &[u8], payload: &[u8]) -> Result<(), BinaryConvertError> {
+		let mut frame = Vec::with_capacity(9);
+		frame.push(STREAM_ITEM);
+		try!(push_length(&mut frame, header.len() + payload.len()));
+		try!(self.writer.write_all(&frame).map_err(BinaryConvertError::io));
+		try!(self.writer.write_all(header).map_err(BinaryConvertError::io));
+		try!(self.writer.write_all(payload).map_err(BinaryConvertError::io));
+		self.items = self.items + 1;
+		Ok(())
+	}
+
+	/// Ends the stream, returning the number of items written.
+	pub fn finish(self) -> Result<usize, BinaryConvertError> {
+		try!(self.writer.write_all(&[STREAM_END]).map_err(BinaryConvertError::io));
+		try!(self.writer.flush().map_err(BinaryConvertError::io));
+		Ok(self.items)
+	}
+}
+
+/// Reads a sequence of values written by `BinaryStreamWriter` item by item.
+pub struct BinaryStreamReader<'a, R: 'a + ::std::io::Read> {
+	reader: &'a mut R,
+	finished: bool,
+}
+
+impl<'a, R: ::std::io::Read> BinaryStreamReader<'a, R> {
+	/// New stream reading from `reader`.
+	pub fn new(reader: &'a mut R) -> Self {
+		BinaryStreamReader {
+			reader: reader,
+			finished: false,
+		}
+	}
+
+	/// Reads the next item, `None` once the end of the stream is reached.
+	/// Bytes following the end of the stream are left in the reader.
+	pub fn read_item<T: BinaryConvertable>(&mut self) -> Result<Option<T>, BinaryConvertError> {
+		if self.finished {
+			return Ok(None);
+		}
+
+		let mut marker = [0u8; 1];
+		try!(self.reader.read_exact(&mut marker).map_err(BinaryConvertError::io));
+		match marker[0] {
+			STREAM_END => {
+				self.finished = true;
+				return Ok(None);
+			},
+			STREAM_ITEM => {},
+			_ => return Err(BinaryConvertError::invalid("stream frame")),
+		}
+
+		let mut size_buffer = [0u8; 8];
+		try!(self.reader.read_exact(&mut size_buffer).map_err(BinaryConvertError::io));
+		let size = try!(usize::from_bytes(&size_buffer, &mut VecDeque::new()));
+
+		// the frame size is not trusted to preallocate, the read stops at the end of the input
+		let mut bytes = Vec::new();
+		let read = try!(self.reader.by_ref().take(size as u64).read_to_end(&mut bytes).map_err(BinaryConvertError::io));
+		if read != size {
+			return Err(BinaryConvertError::size(size, read).at(9));
+		}
+		deserialize(&bytes).map(Some).map_err(|e| e.at(9))
+	}
+}
+
+/// Writes all `items` as a stream.
+pub fn serialize_stream_into<'i, T, I, W>(items: I, w: &mut W) -> Result<usize, BinaryConvertError>
+	where W: ::std::io::Write,
+		T: 'i + BinaryConvertable,
+		I: IntoIterator<Item=&'i T>
+{
+	let mut stream = BinaryStreamWriter::new(w);
+	for item in items {
+		try!(stream.write_item(item));
+	}
+	stream.finish()
+}
+
+/// Reads a whole stream written by `serialize_stream_into`.
+pub fn deserialize_stream_from<T, R>(r: &mut R) -> Result<Vec<T>, BinaryConvertError>
+	where R: ::std::io::Read,
+		T: BinaryConvertable
+{
+	let mut stream = BinaryStreamReader::new(r);
+	let mut items = Vec::new();
+	while let Some(item) = try!(stream.read_item()) {
+		items.push(item);
+	}
+	Ok(items)
+}
+
+/// Size after which a streamed `Vec` starts a new chunk.
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Values which are written as a stream of chunks, so that neither side holds the whole
+/// encoding in memory. Generated IPC services use it for `Vec` (and `Bytes`) results.
+pub trait BinaryStreamable: Sized {
+	/// Writes the value as a stream.
+	fn write_stream<W: ::std::io::Write>(&self, w: &mut W) -> Result<(), BinaryConvertError>;
+
+	/// Reads a value written with `write_stream`.
+	fn read_stream<R: ::std::io::Read>(r: &mut R) -> Result<Self, BinaryConvertError>;
+}
+
+impl<T> BinaryStreamable for Vec<T> where T: BinaryConvertable {
+	fn write_stream<W: ::std::io::Write>(&self, w: &mut W) -> Result<(), BinaryConvertError> {
+		let mut stream = BinaryStreamWriter::new(w);
+		let (mut start, mut size) = (0, 0);
+		for (index, item) in self.iter().enumerate() {
+			size = size + encoded_size(item);
+			if size >= STREAM_CHUNK_SIZE {
+				try!(stream.write_slice(&self[start..index + 1]));
+				start = index + 1;
+				size = 0;
+			}
+		}
+		if start < self.len() {
+			try!(stream.write_slice(&self[start..]));
+		}
+		stream.finish().map(|_| ())
+	}
+
+	fn read_stream<R: ::std::io::Read>(r: &mut R) -> Result<Self, BinaryConvertError> {
+		let mut stream = BinaryStreamReader::new(r);
+		let mut items = Vec::new();
+		while let Some(chunk) = try!(stream.read_item::<Vec<T>>()) {
+			items.extend(chunk);
+		}
+		Ok(items)
+	}
+}
+
+// `None` is a single `0u8`, `Some` is `1u8` followed by the stream of the value.
+impl<T> BinaryStreamable for Option<Vec<T>> where T: BinaryConvertable {
+	fn write_stream<W: ::std::io::Write>(&self, w: &mut W) -> Result<(), BinaryConvertError> {
+		match *self {
+			None => w.write_all(&[0u8]).map_err(BinaryConvertError::io),
+			Some(ref items) => {
+				try!(w.write_all(&[1u8]).map_err(BinaryConvertError::io));
+				items.write_stream(w)
+			},
+		}
+	}
+
+	fn read_stream<R: ::std::io::Read>(r: &mut R) -> Result<Self, BinaryConvertError> {
+		let mut marker = [0u8; 1];
+		try!(r.read_exact(&mut marker).map_err(BinaryConvertError::io));
+		match marker[0] {
+			0 => Ok(None),
+			1 => Vec::read_stream(r).map(Some).map_err(|e| e.at(1)),
+			_ => Err(BinaryConvertError::invalid("Option")),
+		}
+	}
+}
+
+/// Serializes a value the way `BinaryStreamable::write_stream` writes it.
+pub fn serialize_streamed<T: BinaryStreamable>(t: &T) -> Result<Vec<u8>, BinaryConvertError> {
+	let mut bytes = Vec::new();
+	try!(t.write_stream(&mut bytes));
+	Ok(bytes)
+}
+
+/// Fixed-sized version of Handshake struct
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BinHandshake {
+	api_version: BinVersion,
+	protocol_version: BinVersion,
+}
+
+/// Shorten version of semver Version without `pre` and `build` information
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BinVersion {
+	pub major: u64,
+	pub minor: u64,
+	pub patch: u64,
+}
+
+impl From<Handshake> for BinHandshake {
+	fn from(other: Handshake) -> Self {
+		BinHandshake {
+			api_version: BinVersion::from(other.api_version),
+			protocol_version: BinVersion::from(other.protocol_version),
+		}
+	}
+}
+
+impl BinHandshake {
+	pub fn to_semver(self) -> Handshake {
+		Handshake {
+			api_version: self.api_version.to_semver(),
+			protocol_version: self.protocol_version.to_semver(),
+		}
+	}
+}
+
+impl BinVersion {
+	pub fn to_semver(self) -> ::semver::Version {
+		::semver::Version {
+			major: self.major,
+			minor: self.minor,
+			patch: self.patch,
+			pre: vec![],
+			build: vec![],
+		}
+	}
+}
+
+impl From<::semver::Version> for BinVersion {
+	fn from(other: ::semver::Version) -> Self {
+ 		BinVersion {
+			major: other.major,
+			minor: other.minor,
+			patch: other.patch,
+		}
+	}
+}
+
+// Primitives are encoded little-endian with an explicit width, so that hosts of different
+// endianness and word size agree on the encoding.
+
+macro_rules! binary_integer {
+	($target_ty: ty, $width: expr) => {
+		impl BinaryConvertable for $target_ty {
+			fn from_bytes(bytes: &[u8], _length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+				if bytes.len() != $width {
+					return Err(BinaryConvertError::size($width, bytes.len()));
+				}
+				let mut res: $target_ty = 0;
+				for (i, byte) in bytes.iter().enumerate() {
+					res |= (*byte as $target_ty) << (8 * i);
+				}
+				Ok(res)
+			}
+
+			fn to_bytes(&self, buffer: &mut [u8], _length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+				if buffer.len() < $width {
+					return Err(BinaryConvertError::size($width, buffer.len()));
+				}
+				for i in 0..$width {
+					buffer[i] = (*self >> (8 * i)) as u8;
+				}
+				Ok(())
+			}
+
+			fn fixed_size() -> usize {
+				$width
+			}
+		}
+	}
+}
+
+binary_integer!(u16, 2);
+binary_integer!(u32, 4);
+binary_integer!(u64, 8);
+binary_integer!(i32, 4);
+binary_integer!(i64, 8);
+
+// Byte vectors are copied as they are.
+impl BinaryConvertable for u8 {
+	fn from_bytes(bytes: &[u8], _length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		match bytes.len() {
+			1 => Ok(bytes[0]),
+			len => Err(BinaryConvertError::size(1, len)),
+		}
+	}
+
+	fn to_bytes(&self, buffer: &mut [u8], _length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+		if buffer.is_empty() {
+			return Err(BinaryConvertError::size(1, 0));
+		}
+		buffer[0] = *self;
+		Ok(())
+	}
+
+	fn fixed_size() -> usize {
+		1
+	}
+
+	fn slice_to_bytes(items: &[u8], buffer: &mut [u8], _length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+		if buffer.len() < items.len() {
+			return Err(BinaryConvertError::size(items.len(), buffer.len()));
+		}
+		buffer[..items.len()].clone_from_slice(items);
+		Ok(())
+	}
+
+	fn vec_from_bytes(buffer: &[u8], _length_stack: &mut VecDeque<usize>) -> Result<Vec<u8>, BinaryConvertError> {
+		Ok(buffer.to_vec())
+	}
+}
+
+// Floats are encoded as their IEEE-754 bits.
+macro_rules! binary_float {
+	($target_ty: ty, $bits_ty: ty) => {
+		impl BinaryConvertable for $target_ty {
+			fn from_bytes(bytes: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+				let bits = try!(<$bits_ty>::from_bytes(bytes, length_stack));
+				Ok(unsafe { mem::transmute::<$bits_ty, $target_ty>(bits) })
+			}
+
+			fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+				let bits = unsafe { mem::transmute::<$target_ty, $bits_ty>(*self) };
+				bits.to_bytes(buffer, length_stack)
+			}
+
+			fn fixed_size() -> usize {
+				<$bits_ty>::fixed_size()
+			}
+		}
+	}
+}
+
+binary_float!(f32, u32);
+binary_float!(f64, u64);
+
+// `char` is encoded as its 32-bit code point.
+impl BinaryConvertable for char {
+	fn from_bytes(bytes: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		let code = try!(u32::from_bytes(bytes, length_stack));
+		::std::char::from_u32(code).ok_or(BinaryConvertError::invalid("char"))
+	}
+
+	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+		(*self as u32).to_bytes(buffer, length_stack)
+	}
+
+	fn fixed_size() -> usize {
+		4
 	}
+}
 
-	fn from_bytes(buffer: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
-		Ok(::std::cell::Cell::new(try!(T::from_bytes(buffer, length_stack))))
+// `usize` is always encoded as 64 bits.
+impl BinaryConvertable for usize {
+	fn from_bytes(bytes: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		let value = try!(u64::from_bytes(bytes, length_stack));
+		match value > usize::max_value() as u64 {
+			true => Err(BinaryConvertError::invalid("usize")),
+			false => Ok(value as usize),
+		}
 	}
 
 	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
-		try!(self.get().to_bytes(buffer, length_stack));
-		Ok(())
+		(*self as u64).to_bytes(buffer, length_stack)
 	}
 
-	fn len_params() -> usize {
-		T::len_params()
+	fn fixed_size() -> usize {
+		8
 	}
 }
 
-impl BinaryConvertable for Vec<u8> {
-	fn size(&self) -> usize {
-		self.len()
-	}
-
-	fn from_empty_bytes() -> Result<Self, BinaryConvertError> {
-		Ok(Vec::new())
+impl BinaryConvertable for bool {
+	fn from_bytes(bytes: &[u8], _length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		if bytes.len() != 1 {
+			return Err(BinaryConvertError::size(1, bytes.len()));
+		}
+		match bytes[0] {
+			0 => Ok(false),
+			1 => Ok(true),
+			_ => Err(BinaryConvertError::invalid("bool")),
+		}
 	}
 
 	fn to_bytes(&self, buffer: &mut [u8], _length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
-		buffer[..].clone_from_slice(&self[..]);
+		if buffer.is_empty() {
+			return Err(BinaryConvertError::size(1, 0));
+		}
+		buffer[0] = *self as u8;
 		Ok(())
 	}
 
-	fn from_bytes(buffer: &[u8], _length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
-		let mut res = Self::with_capacity(buffer.len());
-		unsafe { res.set_len(buffer.len()) }
-		res[..].clone_from_slice(&buffer[..]);
-		Ok(res)
-	}
-
-	fn len_params() -> usize {
+	fn fixed_size() -> usize {
 		1
 	}
 }
 
-pub fn deserialize_from<T, R>(r: &mut R) -> Result<T, BinaryConvertError>
-	where R: ::std::io::Read,
-		T: BinaryConvertable
-{
-	let mut fake_stack = VecDeque::new();
-
-	match T::len_params() {
-		0 => {
-			let fixed_size = mem::size_of::<T>();
-			let mut payload_buffer = Vec::with_capacity(fixed_size);
-			unsafe { payload_buffer.set_len(fixed_size); }
-			try!(r.read(&mut payload_buffer).map_err(|_| BinaryConvertError));
-			T::from_bytes(&payload_buffer[..], &mut fake_stack)
-		},
-		_ => {
-			let mut payload = Vec::new();
-			try!(r.read_to_end(&mut payload).map_err(|_| BinaryConvertError));
-
-			let stack_len = try!(u64::from_bytes(&payload[0..8], &mut fake_stack)) as usize;
-			let mut length_stack = VecDeque::<usize>::with_capacity(stack_len);
-
-			if stack_len > 0 {
-				for idx in 0..stack_len {
-					let stack_item = try!(u64::from_bytes(&payload[8 + idx*8..8 + (idx+1)*8], &mut fake_stack));
-					length_stack.push_back(stack_item as usize);
+macro_rules! binary_uint {
+	($target_ty: ident, $width: expr) => {
+		impl BinaryConvertable for $target_ty {
+			fn from_bytes(bytes: &[u8], _length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+				if bytes.len() != $width {
+					return Err(BinaryConvertError::size($width, bytes.len()));
 				}
+				let mut big_endian = bytes.to_vec();
+				big_endian.reverse();
+				Ok($target_ty::from(&big_endian[..]))
 			}
 
-			//try!(r.read(&mut size_buffer).map_err(|_| BinaryConvertError));
-			let size = try!(u64::from_bytes(&payload[8+stack_len*8..16+stack_len*8], &mut fake_stack)) as usize;
-			match size {
-				0 => {
-					T::from_empty_bytes()
-				},
-				_ => {
-					T::from_bytes(&payload[16+stack_len*8..], &mut length_stack)
+			fn to_bytes(&self, buffer: &mut [u8], _length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+				if buffer.len() < $width {
+					return Err(BinaryConvertError::size($width, buffer.len()));
+				}
+				for i in 0..$width {
+					buffer[i] = self.byte(i);
 				}
+				Ok(())
 			}
-		},
-	}
-}
 
-pub fn deserialize<T: BinaryConvertable>(buffer: &[u8]) -> Result<T, BinaryConvertError> {
-	use std::io::Cursor;
-	let mut buff = Cursor::new(buffer);
-	deserialize_from::<T, _>(&mut buff)
+			fn fixed_size() -> usize {
+				$width
+			}
+		}
+	}
 }
 
-pub fn serialize_into<T, W>(t: &T, w: &mut W) -> Result<(), BinaryConvertError>
-	where W: ::std::io::Write,
-		T: BinaryConvertable
-{
-	let mut fake_stack = VecDeque::new();
+binary_uint!(U256, 32);
+binary_uint!(U512, 64);
 
-	match T::len_params() {
-		0 => {
-			let fixed_size = mem::size_of::<T>();
-			let mut buffer = Vec::with_capacity(fixed_size);
-			unsafe { buffer.set_len(fixed_size); }
-			try!(t.to_bytes(&mut buffer[..], &mut fake_stack));
-			try!(w.write(&buffer[..]).map_err(|_| BinaryConvertError));
-			Ok(())
-		},
-		_ => {
-			let mut length_stack = VecDeque::<usize>::new();
-			let mut size_buffer = [0u8; 8];
-
-			let size = t.size();
-			if size == 0 {
-				try!(w.write(&size_buffer).map_err(|_| BinaryConvertError));
-				try!(w.write(&size_buffer).map_err(|_| BinaryConvertError));
-				return Ok(());
+// hashes are byte arrays and encoded as they are.
+macro_rules! binary_hash {
+	($target_ty: ident, $width: expr) => {
+		impl BinaryConvertable for $target_ty {
+			fn from_bytes(bytes: &[u8], _length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+				match bytes.len() == $width {
+					true => Ok($target_ty::from_slice(bytes)),
+					false => Err(BinaryConvertError::size($width, bytes.len())),
+				}
 			}
 
-			let mut buffer = Vec::with_capacity(size);
-			unsafe { buffer.set_len(size); }
-			try!(t.to_bytes(&mut buffer[..], &mut length_stack));
-
-			let stack_len = length_stack.len();
-			try!((stack_len as u64).to_bytes(&mut size_buffer[..], &mut fake_stack));
-			try!(w.write(&size_buffer[..]).map_err(|_| BinaryConvertError));
-			if stack_len > 0 {
-				let mut header_buffer = Vec::with_capacity(stack_len * 8);
-				unsafe {  header_buffer.set_len(stack_len * 8); };
-				try!((stack_len as u64).to_bytes(&mut header_buffer[0..8], &mut fake_stack));
-				let mut idx = 0;
-				loop {
-					match length_stack.pop_front() {
-						Some(val) => try!((val as u64).to_bytes(&mut header_buffer[idx * 8..(idx+1) * 8], &mut fake_stack)),
-						None => { break; }
-					}
-					idx = idx + 1;
+			fn to_bytes(&self, buffer: &mut [u8], _length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+				if buffer.len() < $width {
+					return Err(BinaryConvertError::size($width, buffer.len()));
 				}
-				try!(w.write(&header_buffer[..]).map_err(|_| BinaryConvertError));
+				self.copy_to(&mut buffer[..$width]);
+				Ok(())
 			}
 
-			try!((size as u64).to_bytes(&mut size_buffer[..], &mut fake_stack));
-			try!(w.write(&size_buffer[..]).map_err(|_| BinaryConvertError));
+			fn fixed_size() -> usize {
+				$width
+			}
+		}
+	}
+}
 
-			try!(w.write(&buffer[..]).map_err(|_| BinaryConvertError));
+binary_hash!(H256, 32);
+binary_hash!(H2048, 256);
+binary_hash!(Address, 20);
+
+// Bounds of fixed-size values, used by the block and transaction errors. Both fields
+// take `T::fixed_size()` bytes.
+impl<T> BinaryConvertable for Mismatch<T> where T: BinaryConvertable + fmt::Debug {
+	fn from_bytes(bytes: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		let width = T::fixed_size();
+		if bytes.len() != Self::fixed_size() {
+			return Err(BinaryConvertError::size(Self::fixed_size(), bytes.len()));
+		}
+		Ok(Mismatch {
+			expected: try!(T::from_bytes(&bytes[..width], length_stack)),
+			found: try!(T::from_bytes(&bytes[width..], length_stack).map_err(|e| e.at(width))),
+		})
+	}
 
-			Ok(())
-		},
+	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+		let width = T::fixed_size();
+		if buffer.len() < Self::fixed_size() {
+			return Err(BinaryConvertError::size(Self::fixed_size(), buffer.len()));
+		}
+		try!(self.expected.to_bytes(&mut buffer[..width], length_stack));
+		self.found.to_bytes(&mut buffer[width..2 * width], length_stack)
 	}
-}
 
-pub fn serialize<T: BinaryConvertable>(t: &T) -> Result<Vec<u8>, BinaryConvertError> {
-	use std::io::Cursor;
-	let mut buff = Cursor::new(Vec::new());
-	try!(serialize_into(t, &mut buff));
-	let into_inner = buff.into_inner();
-	Ok(into_inner)
+	fn fixed_size() -> usize {
+		2 * T::fixed_size()
+	}
 }
 
-#[macro_export]
-macro_rules! binary_fixed_size {
-	($target_ty: ty) => {
-		impl BinaryConvertable for $target_ty {
-			fn from_bytes(bytes: &[u8], _length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
-				match bytes.len().cmp(&::std::mem::size_of::<$target_ty>()) {
-					::std::cmp::Ordering::Less => return Err(BinaryConvertError),
-					::std::cmp::Ordering::Greater => return Err(BinaryConvertError),
-					::std::cmp::Ordering::Equal => ()
-				};
-				let mut res: Self = unsafe { ::std::mem::uninitialized() };
-				res.copy_raw(bytes);
-				Ok(res)
-			}
+// Optional bounds are a presence byte followed by `T::fixed_size()` bytes, zeroed when absent.
+impl<T> BinaryConvertable for OutOfBounds<T> where T: BinaryConvertable + fmt::Debug {
+	fn from_bytes(bytes: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		let width = T::fixed_size();
+		if bytes.len() != Self::fixed_size() {
+			return Err(BinaryConvertError::size(Self::fixed_size(), bytes.len()));
+		}
+		let mut bounds = Vec::with_capacity(2);
+		for offset in vec![0, width + 1] {
+			bounds.push(match bytes[offset] {
+				0 => None,
+				1 => Some(try!(T::from_bytes(&bytes[offset + 1..offset + 1 + width], length_stack).map_err(|e| e.at(offset + 1)))),
+				_ => return Err(BinaryConvertError::invalid("OutOfBounds").at(offset)),
+			});
+		}
+		let found_offset = 2 * (width + 1);
+		let found = try!(T::from_bytes(&bytes[found_offset..], length_stack).map_err(|e| e.at(found_offset)));
+		let max = bounds.pop().expect("two bounds are read above; qed");
+		let min = bounds.pop().expect("two bounds are read above; qed");
+		Ok(OutOfBounds { min: min, max: max, found: found })
+	}
 
-			fn to_bytes(&self, buffer: &mut [u8], _length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
-				let sz = ::std::mem::size_of::<$target_ty>();
-				let ip: *const $target_ty = self;
-				let ptr: *const u8 = ip as *const _;
-				unsafe {
-					::std::ptr::copy(ptr, buffer.as_mut_ptr(), sz);
-				}
-				Ok(())
+	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+		let width = T::fixed_size();
+		if buffer.len() < Self::fixed_size() {
+			return Err(BinaryConvertError::size(Self::fixed_size(), buffer.len()));
+		}
+		for (offset, bound) in vec![(0, &self.min), (width + 1, &self.max)] {
+			match *bound {
+				Some(ref value) => {
+					buffer[offset] = 1;
+					try!(value.to_bytes(&mut buffer[offset + 1..offset + 1 + width], length_stack));
+				},
+				None => {
+					for byte in buffer[offset..offset + 1 + width].iter_mut() {
+						*byte = 0;
+					}
+				},
 			}
 		}
+		let found_offset = 2 * (width + 1);
+		self.found.to_bytes(&mut buffer[found_offset..found_offset + width], length_stack)
 	}
-}
 
-/// Fixed-sized version of Handshake struct
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct BinHandshake {
-	api_version: BinVersion,
-	protocol_version: BinVersion,
+	fn fixed_size() -> usize {
+		3 * T::fixed_size() + 2
+	}
 }
 
-/// Shorten version of semver Version without `pre` and `build` information
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct BinVersion {
-	pub major: u64,
-	pub minor: u64,
-	pub patch: u64,
-}
+impl BinaryConvertable for BinHandshake {
+	fn from_bytes(bytes: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		if bytes.len() != Self::fixed_size() {
+			return Err(BinaryConvertError::size(Self::fixed_size(), bytes.len()));
+		}
+		let mut numbers = [0u64; 6];
+		for (i, number) in numbers.iter_mut().enumerate() {
+			*number = try!(u64::from_bytes(&bytes[i * 8..(i + 1) * 8], length_stack));
+		}
+		Ok(BinHandshake {
+			api_version: BinVersion { major: numbers[0], minor: numbers[1], patch: numbers[2] },
+			protocol_version: BinVersion { major: numbers[3], minor: numbers[4], patch: numbers[5] },
+		})
+	}
 
-impl From<Handshake> for BinHandshake {
-	fn from(other: Handshake) -> Self {
-		BinHandshake {
-			api_version: BinVersion::from(other.api_version),
-			protocol_version: BinVersion::from(other.protocol_version),
+	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+		if buffer.len() < Self::fixed_size() {
+			return Err(BinaryConvertError::size(Self::fixed_size(), buffer.len()));
 		}
+		let numbers = [
+			self.api_version.major, self.api_version.minor, self.api_version.patch,
+			self.protocol_version.major, self.protocol_version.minor, self.protocol_version.patch,
+		];
+		for (i, number) in numbers.iter().enumerate() {
+			try!(number.to_bytes(&mut buffer[i * 8..(i + 1) * 8], length_stack));
+		}
+		Ok(())
+	}
+
+	fn fixed_size() -> usize {
+		6 * 8
 	}
 }
 
-impl BinHandshake {
-	pub fn to_semver(self) -> Handshake {
-		Handshake {
-			api_version: self.api_version.to_semver(),
-			protocol_version: self.protocol_version.to_semver(),
+impl BinaryConvertable for Negotiation {
+	fn from_bytes(bytes: &[u8], length_stack: &mut VecDeque<usize>) -> Result<Self, BinaryConvertError> {
+		if bytes.len() != Self::fixed_size() {
+			return Err(BinaryConvertError::size(Self::fixed_size(), bytes.len()));
 		}
+		Ok(Negotiation {
+			encoding_version: try!(u64::from_bytes(&bytes[0..8], length_stack)),
+			features: try!(u64::from_bytes(&bytes[8..16], length_stack).map_err(|e| e.at(8))),
+		})
 	}
-}
 
-impl BinVersion {
-	pub fn to_semver(self) -> ::semver::Version {
-		::semver::Version {
-			major: self.major,
-			minor: self.minor,
-			patch: self.patch,
-			pre: vec![],
-			build: vec![],
+	fn to_bytes(&self, buffer: &mut [u8], length_stack: &mut VecDeque<usize>) -> Result<(), BinaryConvertError> {
+		if buffer.len() < Self::fixed_size() {
+			return Err(BinaryConvertError::size(Self::fixed_size(), buffer.len()));
 		}
+		try!(self.encoding_version.to_bytes(&mut buffer[0..8], length_stack));
+		self.features.to_bytes(&mut buffer[8..16], length_stack)
+	}
+
+	fn fixed_size() -> usize {
+		2 * 8
 	}
 }
 
-impl From<::semver::Version> for BinVersion {
-	fn from(other: ::semver::Version) -> Self {
- 		BinVersion {
-			major: other.major,
-			minor: other.minor,
-			patch: other.patch,
-		}
+/// Reads a handshake, followed by the client negotiation if it sent one.
+pub fn deserialize_handshake(buffer: &[u8]) -> Result<(BinHandshake, Option<Negotiation>), BinaryConvertError> {
+	let handshake = try!(deserialize::<BinHandshake>(buffer));
+	let rest = &buffer[BinHandshake::fixed_size()..];
+	match rest.len() {
+		0 => Ok((handshake, None)),
+		_ => {
+			if rest.len() != Negotiation::fixed_size() {
+				return Err(BinaryConvertError::size(Negotiation::fixed_size(), rest.len()).at(BinHandshake::fixed_size()));
+			}
+			let negotiation = try!(deserialize::<Negotiation>(rest).map_err(|e| e.at(BinHandshake::fixed_size())));
+			Ok((handshake, Some(negotiation)))
+		},
 	}
 }
 
-binary_fixed_size!(u16);
-binary_fixed_size!(u64);
-binary_fixed_size!(u32);
-binary_fixed_size!(usize);
-binary_fixed_size!(i32);
-binary_fixed_size!(bool);
-binary_fixed_size!(U256);
-binary_fixed_size!(U512);
-binary_fixed_size!(H256);
-binary_fixed_size!(H2048);
-binary_fixed_size!(Address);
-binary_fixed_size!(BinHandshake);
+/// Reads a handshake from the stream, taking the rest of it.
+pub fn deserialize_handshake_from<R: ::std::io::Read>(r: &mut R) -> Result<(BinHandshake, Option<Negotiation>), BinaryConvertError> {
+	let mut buffer = Vec::new();
+	try!(r.read_to_end(&mut buffer).map_err(BinaryConvertError::io));
+	deserialize_handshake(&buffer)
+}
 
 #[test]
 fn vec_serialize() {
@@ -658,8 +1545,7 @@ fn vec_serialize() {
 	v.push(5u64);
 	v.push(10u64);
 	let mut length_stack = VecDeque::new();
-	let mut data = Vec::with_capacity(v.size());
-	unsafe { data.set_len(v.size()); }
+	let mut data = vec![0u8; v.size()];
 	let result = v.to_bytes(&mut data[..], &mut length_stack);
 
 	assert!(result.is_ok());
@@ -700,8 +1586,7 @@ fn vec_deserialize_chained() {
 	v.push(Some(12u64));
 
 	let mut length_stack = VecDeque::new();
-	let mut data = Vec::with_capacity(v.size());
-	unsafe { data.set_len(v.size()); }
+	let mut data = vec![0u8; v.size()];
 	let result = v.to_bytes(&mut data[..], &mut length_stack);
 
 	assert!(result.is_ok());
@@ -718,8 +1603,7 @@ fn vec_serialize_deserialize() {
 	v.push(Some(12u64));
 
 
-	let mut data = Vec::with_capacity(v.size());
-	unsafe { data.set_len(v.size()); }
+	let mut data = vec![0u8; v.size()];
 	let mut length_stack = VecDeque::new();
 
 	v.to_bytes(&mut data[..], &mut length_stack).unwrap();
@@ -898,6 +1782,183 @@ fn serialize_btree() {
 	assert_eq!(res[&1u64], 5u64);
 }
 
+#[test]
+fn serialize_btree_variable_size() {
+	let mut btree = BTreeMap::new();
+	btree.insert("one".to_owned(), vec![vec![1u8], vec![]]);
+	btree.insert("two".to_owned(), vec![]);
+	btree.insert("three".to_owned(), vec![vec![3u8, 3u8, 3u8]]);
+
+	let res = deserialize::<BTreeMap<String, Vec<Vec<u8>>>>(&serialize(&btree).unwrap()).unwrap();
+	assert_eq!(res, btree);
+}
+
+#[test]
+fn serialize_hashmap() {
+	let mut map = HashMap::new();
+	map.insert(1u64, "one".to_owned());
+	map.insert(2u64, String::new());
+	map.insert(300u64, "three hundred".to_owned());
+
+	let res = deserialize::<HashMap<u64, String>>(&serialize(&map).unwrap()).unwrap();
+	assert_eq!(res, map);
+
+	let empty = HashMap::<u64, u64>::new();
+	assert_eq!(deserialize::<HashMap<u64, u64>>(&serialize(&empty).unwrap()).unwrap(), empty);
+}
+
+#[test]
+fn serialize_hashset() {
+	let mut set = HashSet::new();
+	set.insert(vec![1u8, 2u8]);
+	set.insert(vec![3u8]);
+	set.insert(vec![4u8, 5u8, 6u8]);
+
+	let res = deserialize::<HashSet<Vec<u8>>>(&serialize(&set).unwrap()).unwrap();
+	assert_eq!(res, set);
+
+	let mut fixed = HashSet::new();
+	fixed.insert(5u32);
+	fixed.insert(6u32);
+	assert_eq!(deserialize::<HashSet<u32>>(&serialize(&fixed).unwrap()).unwrap(), fixed);
+}
+
+#[test]
+fn serialize_tuples() {
+	let pair = (5u64, "five".to_owned());
+	assert_eq!(deserialize::<(u64, String)>(&serialize(&pair).unwrap()).unwrap(), pair);
+
+	let triple = (vec![1u8, 2u8], String::new(), vec![(1u32, String::new()), (2u32, "two".to_owned())]);
+	assert_eq!(deserialize::<(Vec<u8>, String, Vec<(u32, String)>)>(&serialize(&triple).unwrap()).unwrap(), triple);
+
+	let empty = (String::new(), Vec::<u8>::new());
+	assert_eq!(deserialize::<(String, Vec<u8>)>(&serialize(&empty).unwrap()).unwrap(), empty);
+}
+
+#[test]
+fn serialize_arrays() {
+	let fixed = [1u32, 2u32, 3u32, 4u32];
+	assert_eq!(deserialize::<[u32; 4]>(&serialize(&fixed).unwrap()).unwrap(), fixed);
+
+	let strings = ["a".to_owned(), String::new(), "ccc".to_owned()];
+	assert_eq!(deserialize::<[String; 3]>(&serialize(&strings).unwrap()).unwrap(), strings);
+
+	let hashes = [H256::from(1), H256::from(2)];
+	assert_eq!(deserialize::<[H256; 2]>(&serialize(&hashes).unwrap()).unwrap(), hashes);
+
+	let empties = [String::new(), String::new()];
+	assert_eq!(deserialize::<[String; 2]>(&serialize(&empties).unwrap()).unwrap(), empties);
+}
+
+#[test]
+fn encodes_primitives_little_endian() {
+	let mut length_stack = VecDeque::new();
+
+	let mut buffer = [0u8; 4];
+	0x01020304u32.to_bytes(&mut buffer, &mut length_stack).unwrap();
+	assert_eq!(buffer, [4, 3, 2, 1]);
+	assert_eq!(u32::from_bytes(&buffer, &mut length_stack).unwrap(), 0x01020304u32);
+
+	let mut buffer = [0u8; 4];
+	(-2i32).to_bytes(&mut buffer, &mut length_stack).unwrap();
+	assert_eq!(buffer, [0xfe, 0xff, 0xff, 0xff]);
+	assert_eq!(i32::from_bytes(&buffer, &mut length_stack).unwrap(), -2i32);
+
+	assert_eq!(usize::fixed_size(), 8);
+	let mut buffer = [0u8; 8];
+	300usize.to_bytes(&mut buffer, &mut length_stack).unwrap();
+	assert_eq!(buffer, [44, 1, 0, 0, 0, 0, 0, 0]);
+	assert_eq!(usize::from_bytes(&buffer, &mut length_stack).unwrap(), 300usize);
+
+	let mut buffer = [0u8; 32];
+	U256::from(0x0102u64).to_bytes(&mut buffer, &mut length_stack).unwrap();
+	assert_eq!(&buffer[..3], &[2, 1, 0]);
+	assert_eq!(U256::from_bytes(&buffer, &mut length_stack).unwrap(), U256::from(0x0102u64));
+
+	assert!(bool::from_bytes(&[2], &mut length_stack).is_err());
+	assert!(u64::from_bytes(&[1, 2, 3], &mut length_stack).is_err());
+}
+
+#[test]
+fn serialize_more_primitives() {
+	let mut length_stack = VecDeque::new();
+
+	assert_eq!(deserialize::<u8>(&serialize(&7u8).unwrap()).unwrap(), 7u8);
+	assert_eq!(deserialize::<i64>(&serialize(&-5i64).unwrap()).unwrap(), -5i64);
+	assert_eq!(serialize(&-1i64).unwrap(), vec![0xff; 8]);
+	assert_eq!(deserialize::<char>(&serialize(&'λ').unwrap()).unwrap(), 'λ');
+	assert!(char::from_bytes(&[0, 0xd8, 0, 0], &mut length_stack).is_err());
+
+	assert_eq!(serialize(&1.0f32).unwrap(), vec![0, 0, 0x80, 0x3f]);
+	assert_eq!(deserialize::<f32>(&serialize(&-2.5f32).unwrap()).unwrap(), -2.5f32);
+	assert_eq!(serialize(&1.0f64).unwrap(), vec![0, 0, 0, 0, 0, 0, 0xf0, 0x3f]);
+	assert_eq!(deserialize::<f64>(&serialize(&0.1f64).unwrap()).unwrap(), 0.1f64);
+	assert!(deserialize::<f64>(&serialize(&::std::f64::NAN).unwrap()).unwrap().is_nan());
+
+	let bytes = vec![1u8, 2, 3];
+	assert_eq!(deserialize::<Vec<u8>>(&serialize(&bytes).unwrap()).unwrap(), bytes);
+	assert_eq!(deserialize::<Option<u8>>(&serialize(&Some(4u8)).unwrap()).unwrap(), Some(4u8));
+	assert_eq!(deserialize::<[u8; 4]>(&serialize(&[1u8, 2, 3, 4]).unwrap()).unwrap(), [1u8, 2, 3, 4]);
+	let values = vec![1.5f64, -0.25f64];
+	assert_eq!(deserialize::<Vec<f64>>(&serialize(&values).unwrap()).unwrap(), values);
+}
+
+#[test]
+fn serialize_range() {
+	let range = 5usize..10usize;
+	assert_eq!(deserialize::<Range<usize>>(&serialize(&range).unwrap()).unwrap(), range);
+}
+
+#[test]
+fn rejects_malformed_input() {
+	let mut length_stack = VecDeque::new();
+	assert!(String::from_bytes(&[0xff, 0xfe], &mut length_stack).is_err());
+	assert!(Result::<u64, u32>::from_bytes(&[], &mut length_stack).is_err());
+	assert!(Vec::<u64>::from_bytes(&[1, 2, 3], &mut length_stack).is_err());
+
+	let mut length_stack = VecDeque::new();
+	length_stack.push_back(usize::max_value());
+	assert!(Vec::<String>::from_bytes(&[1, 2, 3], &mut length_stack).is_err());
+
+	let serialized = serialize(&vec![String::from("one"), String::from("two")]).unwrap();
+	for len in 0..serialized.len() {
+		assert!(deserialize::<Vec<String>>(&serialized[..len]).is_err());
+	}
+
+	// stack length too large for the payload
+	let mut huge_stack = vec![0xffu8; 8];
+	huge_stack.extend_from_slice(&[0u8; 16]);
+	assert!(deserialize::<Vec<String>>(&huge_stack).is_err());
+
+	assert!(deserialize::<u64>(&[1, 2]).is_err());
+}
+
+#[test]
+fn reports_error_offsets() {
+	let mut serialized = serialize(&vec![String::from("ab"), String::from("cd")]).unwrap();
+	// header of stack length, two lengths and size, followed by "abcd".
+	assert_eq!(serialized.len(), 36);
+	serialized[34] = 0xff;
+	assert_eq!(
+		deserialize::<Vec<String>>(&serialized).unwrap_err(),
+		BinaryConvertError::InvalidValue { offset: 34, type_name: "String" }
+	);
+
+	let mut length_stack = VecDeque::new();
+	assert_eq!(
+		Result::<u64, u32>::from_bytes(&[2, 0, 0, 0, 0], &mut length_stack).unwrap_err(),
+		BinaryConvertError::UnknownVariant { offset: 0, tag: 2 }
+	);
+	assert_eq!(
+		Result::<u64, u32>::from_bytes(&[1, 0, 0], &mut length_stack).unwrap_err(),
+		BinaryConvertError::SizeMismatch { offset: 1, expected: 4, found: 2 }
+	);
+	assert_eq!(
+		Vec::<String>::from_bytes(&[1], &mut length_stack).unwrap_err(),
+		BinaryConvertError::MissingLength { offset: 0 }
+	);
+}
+
 #[test]
 fn serialize_handshake() {
 	use std::io::{Cursor, SeekFrom, Seek};
@@ -917,3 +1978,197 @@ fn serialize_handshake() {
 	assert_eq!(res, handshake);
 
 }
+
+#[test]
+fn serialize_matches_streaming_format() {
+	use std::io::Cursor;
+
+	let v = vec!["a".to_owned(), String::new(), "bc".to_owned()];
+	let bytes = serialize(&v).unwrap();
+
+	let mut buff = Cursor::new(Vec::new());
+	serialize_into(&v, &mut buff).unwrap();
+	assert_eq!(bytes, buff.into_inner());
+	assert_eq!(v, deserialize::<Vec<String>>(&bytes).unwrap());
+
+	let empty = Vec::<String>::new();
+	assert_eq!(vec![0u8; 16], serialize(&empty).unwrap());
+
+	// fixed-size values only use the start of the slice
+	assert_eq!(7u32, deserialize::<u32>(&[7u8, 0, 0, 0, 0xff, 0xff]).unwrap());
+}
+
+#[test]
+fn serialize_stream() {
+	use std::io::Cursor;
+
+	let items = vec![vec![1u64, 2u64], Vec::new(), vec![3u64]];
+	let mut buff = Cursor::new(Vec::new());
+	assert_eq!(3, serialize_stream_into(&items, &mut buff).unwrap());
+	buff.get_mut().push(0xff);
+	buff.set_position(0);
+
+	{
+		let mut stream = BinaryStreamReader::new(&mut buff);
+		assert_eq!(Some(vec![1u64, 2u64]), stream.read_item::<Vec<u64>>().unwrap());
+		assert_eq!(Some(Vec::new()), stream.read_item::<Vec<u64>>().unwrap());
+		assert_eq!(Some(vec![3u64]), stream.read_item::<Vec<u64>>().unwrap());
+		assert_eq!(None, stream.read_item::<Vec<u64>>().unwrap());
+		assert_eq!(None, stream.read_item::<Vec<u64>>().unwrap());
+	}
+	// the reader stops at the end of the stream
+	assert_eq!(buff.position() as usize, buff.get_ref().len() - 1);
+
+	let mut buff = Cursor::new(Vec::new());
+	serialize_stream_into(&Vec::<u32>::new(), &mut buff).unwrap();
+	buff.set_position(0);
+	assert_eq!(Vec::<u32>::new(), deserialize_stream_from::<u32, _>(&mut buff).unwrap());
+}
+
+#[test]
+fn rejects_malformed_stream() {
+	use std::io::Cursor;
+
+	let mut buff = Cursor::new(vec![2u8]);
+	assert!(deserialize_stream_from::<u32, _>(&mut buff).is_err());
+
+	// truncated frame claiming a huge size
+	let mut buff = Cursor::new(vec![1u8, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x7f, 1u8]);
+	assert!(deserialize_stream_from::<u32, _>(&mut buff).is_err());
+
+	// missing end of stream
+	let mut buff = Cursor::new(Vec::new());
+	BinaryStreamWriter::new(&mut buff).write_item(&5u32).unwrap();
+	buff.set_position(0);
+	assert!(deserialize_stream_from::<u32, _>(&mut buff).is_err());
+}
+
+#[test]
+fn stream_vec_in_chunks() {
+	use std::io::Cursor;
+
+	let bytes = (0..STREAM_CHUNK_SIZE * 2 + 10).map(|i| i as u8).collect::<Vec<u8>>();
+	let streamed = serialize_streamed(&bytes).unwrap();
+	{
+		let mut buff = Cursor::new(&streamed[..]);
+		let mut stream = BinaryStreamReader::new(&mut buff);
+		assert_eq!(STREAM_CHUNK_SIZE, stream.read_item::<Vec<u8>>().unwrap().unwrap().len());
+		assert_eq!(STREAM_CHUNK_SIZE, stream.read_item::<Vec<u8>>().unwrap().unwrap().len());
+		assert_eq!(10, stream.read_item::<Vec<u8>>().unwrap().unwrap().len());
+		assert_eq!(None, stream.read_item::<Vec<u8>>().unwrap());
+	}
+	assert_eq!(bytes, Vec::<u8>::read_stream(&mut Cursor::new(&streamed[..])).unwrap());
+
+	let strings = vec!["a".to_owned(), String::new(), "bc".to_owned()];
+	assert_eq!(strings, Vec::<String>::read_stream(&mut Cursor::new(serialize_streamed(&strings).unwrap())).unwrap());
+
+	let empty = Vec::<u64>::new();
+	assert_eq!(vec![0u8], serialize_streamed(&empty).unwrap());
+	assert_eq!(empty, Vec::<u64>::read_stream(&mut Cursor::new(vec![0u8])).unwrap());
+}
+
+#[test]
+fn stream_optional_vec() {
+	use std::io::Cursor;
+
+	let none: Option<Vec<u8>> = None;
+	assert_eq!(vec![0u8], serialize_streamed(&none).unwrap());
+	assert_eq!(none, Option::<Vec<u8>>::read_stream(&mut Cursor::new(vec![0u8])).unwrap());
+
+	let some = Some(vec![1u8, 2, 3]);
+	let bytes = serialize_streamed(&some).unwrap();
+	assert_eq!(some, Option::<Vec<u8>>::read_stream(&mut Cursor::new(bytes)).unwrap());
+	assert_eq!(Some(Vec::new()), Option::<Vec<u8>>::read_stream(&mut Cursor::new(vec![1u8, 0])).unwrap());
+
+	assert!(Option::<Vec<u8>>::read_stream(&mut Cursor::new(vec![2u8])).is_err());
+}
+
+#[test]
+fn negotiate_handshake_features() {
+	use super::{handshake_reply, read_handshake_reply};
+
+	let version = ::semver::Version::parse("1.0.0").unwrap();
+	let handshake = BinHandshake::from(Handshake { protocol_version: version.clone(), api_version: version });
+	let host = Negotiation { encoding_version: 3, features: 1 | 4 };
+	let client = Negotiation { encoding_version: 2, features: 1 };
+
+	// client without negotiation gets the single byte reply, refusing
+	// it as it uses encoding version 1
+	let legacy = serialize(&handshake).unwrap();
+	let (_, remote) = deserialize_handshake(&legacy).unwrap();
+	assert_eq!(None, remote);
+	let reply = handshake_reply(true, &host, remote.as_ref());
+	assert_eq!(vec![0u8], reply);
+	// and a host which doesn't negotiate is refused by the client
+	assert!(read_handshake_reply(&[1u8], &client).is_err());
+
+	let mut request = legacy.clone();
+	request.extend_from_slice(&serialize(&client).unwrap());
+	let (de_handshake, remote) = deserialize_handshake(&request).unwrap();
+	assert_eq!(handshake, de_handshake);
+	let reply = handshake_reply(true, &host, remote.as_ref());
+	let agreed = read_handshake_reply(&reply, &client).unwrap();
+	assert_eq!(2, agreed.encoding_version);
+	assert!(agreed.has(1));
+	assert!(!agreed.has(4));
+
+	// no common encoding version
+	let unsupported = Negotiation { encoding_version: 1, features: 0 };
+	assert_eq!(vec![0u8], handshake_reply(true, &unsupported, Some(&client)));
+	assert_eq!(vec![0u8], handshake_reply(true, &host, Some(&unsupported)));
+	assert_eq!(vec![0u8], handshake_reply(false, &host, Some(&client)));
+	assert!(read_handshake_reply(&[0u8], &client).is_err());
+	assert!(read_handshake_reply(&[], &client).is_err());
+
+	request.push(0);
+	assert!(deserialize_handshake(&request).is_err());
+}
+
+#[test]
+fn enum_tags() {
+	let mut buffer = [0u8; 2];
+	tag_to_bytes(0x1234, 2, &mut buffer).unwrap();
+	assert_eq!([0x34, 0x12], buffer);
+	assert_eq!(0x1234, tag_from_bytes(&buffer, 2).unwrap());
+	assert_eq!(0x34, tag_from_bytes(&buffer, 1).unwrap());
+
+	assert!(tag_to_bytes(0x100, 1, &mut buffer).is_err());
+	assert!(tag_to_bytes(1, 2, &mut buffer[..1]).is_err());
+	assert!(tag_from_bytes(&buffer[..1], 2).is_err());
+	assert!(tag_from_bytes(&buffer, 3).is_err());
+}
+
+#[test]
+fn serialize_reuses_pooled_buffers() {
+	use std::io::Cursor;
+
+	let pool = BufferPool::new(2, 1024);
+	let v = vec![1u64, 2u64, 3u64];
+	let mut buff = Cursor::new(Vec::new());
+	serialize_into_pooled(&v, &mut buff, &pool).unwrap();
+	assert_eq!(2, pool.available());
+	assert_eq!(serialize(&v).unwrap(), buff.into_inner());
+
+	// a pool which keeps nothing allocates every time
+	let pool = BufferPool::new(0, 0);
+	serialize_into_pooled(&v, &mut Cursor::new(Vec::new()), &pool).unwrap();
+	assert_eq!(0, pool.available());
+}
+
+#[test]
+fn serialize_bounds() {
+	let mismatch = Mismatch { expected: 5u64, found: 10u64 };
+	let bytes = serialize(&mismatch).unwrap();
+	assert_eq!(16, bytes.len());
+	assert_eq!(10, bytes[8]);
+	assert_eq!(mismatch, deserialize::<Mismatch<u64>>(&bytes).unwrap());
+
+	let oob = OutOfBounds { min: None, max: Some(3u32), found: 7u32 };
+	let bytes = serialize(&oob).unwrap();
+	assert_eq!(vec![0u8, 0, 0, 0, 0, 1, 3, 0, 0, 0, 7, 0, 0, 0], bytes);
+	assert_eq!(oob, deserialize::<OutOfBounds<u32>>(&bytes).unwrap());
+
+	let mut invalid = bytes.clone();
+	invalid[5] = 2;
+	assert!(deserialize::<OutOfBounds<u32>>(&invalid).is_err());
+}
@@ -18,7 +18,9 @@
 
 use std::io::{Read, Write};
 use std::marker::Sync;
+use std::cmp;
 use semver::Version;
+use binary::{self, BinaryConvertError};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 /// Handshake for client and server to negotiate api/protocol version
@@ -27,6 +29,59 @@ pub struct Handshake {
 	pub api_version: Version,
 }
 
+/// Version of the binary encoding of ipc payloads:
+/// 1 - host-endian primitives, structs copied as they are laid out in memory
+/// 2 - little-endian primitives, structs written field by field, streamed `Vec` and `Bytes` results
+pub const ENCODING_VERSION: u64 = 2;
+
+/// Oldest encoding version still understood
+pub const MIN_ENCODING_VERSION: u64 = 2;
+
+/// Encoding version and optional features agreed on by the client and the host.
+/// Peers which don't negotiate are assumed to use encoding version 1 without
+/// any optional features.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Negotiation {
+	pub encoding_version: u64,
+	pub features: u64,
+}
+
+impl Default for Negotiation {
+	fn default() -> Self {
+		Negotiation {
+			encoding_version: 1,
+			features: 0,
+		}
+	}
+}
+
+impl Negotiation {
+	/// What the service with given config supports
+	pub fn local<C: IpcConfig + ?Sized>() -> Self {
+		Negotiation {
+			encoding_version: C::encoding_version(),
+			features: C::features(),
+		}
+	}
+
+	/// Negotiation acceptable to both sides, `None` if there is no common encoding version
+	pub fn agree(&self, other: &Negotiation) -> Option<Negotiation> {
+		let encoding_version = cmp::min(self.encoding_version, other.encoding_version);
+		if encoding_version < MIN_ENCODING_VERSION {
+			return None;
+		}
+		Some(Negotiation {
+			encoding_version: encoding_version,
+			features: self.features & other.features,
+		})
+	}
+
+	/// Whether all the features of `feature` bitmap were agreed on
+	pub fn has(&self, feature: u64) -> bool {
+		self.features & feature == feature
+	}
+}
+
 /// Allows to configure custom version and custom handshake response for
 /// ipc host
 pub trait IpcConfig {
@@ -42,6 +97,16 @@ pub trait IpcConfig {
 		Version::parse("1.0.0").unwrap()
 	}
 
+	/// Newest encoding version supported
+	fn encoding_version() -> u64 {
+		ENCODING_VERSION
+	}
+
+	/// Bitmap of optional features supported, none are defined yet
+	fn features() -> u64 {
+		0
+	}
+
 	/// Default handshake requires exact versions match
 	fn handshake(handshake: &Handshake) -> bool {
 		handshake.protocol_version == Self::protocol_version() &&
@@ -67,6 +132,45 @@ pub trait IpcInterface : IpcConfig {
 	/// deserializes the payload from buffer, dispatches invoke and returns serialized result
 	/// (for non-blocking io)
 	fn dispatch_buf(&self, method_num: u16, buf: &[u8]) -> Vec<u8>;
+
+	/// reads the message from io, dispatches the call and writes the serialized result to `w`;
+	/// streamed results (`Vec` and `Bytes`) are written chunk by chunk instead of being buffered
+	fn dispatch_into<R, W>(&self, r: &mut R, w: &mut W) -> ::std::io::Result<()> where R: Read, W: Write {
+		let result = self.dispatch(r);
+		w.write_all(&result)
+	}
+}
+
+/// Host response to a handshake. The agreed negotiation follows the result only
+/// if the client sent one, so clients that don't negotiate read a single byte.
+/// They use encoding version 1, so they're refused unless the host still understands it.
+pub fn handshake_reply(accepted: bool, local: &Negotiation, remote: Option<&Negotiation>) -> Vec<u8> {
+	let remote = match remote {
+		Some(remote) => remote,
+		None => return vec![(accepted && local.agree(&Negotiation::default()).is_some()) as u8],
+	};
+	match local.agree(remote) {
+		Some(ref agreed) if accepted => {
+			let mut reply = vec![1u8];
+			reply.extend_from_slice(&binary::serialize(agreed).expect("negotiation is fixed-size; qed"));
+			reply
+		},
+		_ => vec![0u8],
+	}
+}
+
+/// Reads the host response to a handshake sent with `local` negotiation
+pub fn read_handshake_reply(reply: &[u8], local: &Negotiation) -> Result<Negotiation, Error> {
+	match reply.first() {
+		None => return Err(Error::HandshakeFailed),
+		Some(&1) => {},
+		Some(_) => return Err(Error::RemoteServiceUnsupported),
+	}
+	let remote = match reply.len() {
+		1 => Negotiation::default(),
+		_ => try!(binary::deserialize::<Negotiation>(&reply[1..]).map_err(|_: BinaryConvertError| Error::HandshakeFailed)),
+	};
+	local.agree(&remote).ok_or(Error::RemoteServiceUnsupported)
 }
 
 /// serializes method invocation (method_num and parameters) to the stream specified by `w`
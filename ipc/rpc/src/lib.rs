@@ -23,5 +23,7 @@ extern crate ethcore_util as util;
 
 pub mod interface;
 pub mod binary;
-pub use interface::{IpcInterface, IpcSocket, invoke, IpcConfig, Handshake, Error, WithSocket};
-pub use binary::{BinaryConvertable, BinaryConvertError, BinHandshake};
+pub mod pool;
+pub use interface::{IpcInterface, IpcSocket, invoke, IpcConfig, Handshake, Error, WithSocket, Negotiation, handshake_reply, read_handshake_reply};
+pub use binary::{BinaryConvertable, BinaryConvertError, BinHandshake, BinaryStreamWriter, BinaryStreamReader, BinaryStreamable};
+pub use pool::BufferPool;
@@ -0,0 +1,154 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Pool of reusable byte buffers for serialization
+
+use std::mem;
+use std::ops::{Deref, DerefMut};
+use std::sync::Mutex;
+
+/// Buffers kept by the pool of each thread
+pub const DEFAULT_POOL_BUFFERS: usize = 8;
+/// Largest buffer kept by the pool of each thread; bigger ones are freed
+pub const DEFAULT_POOL_CAPACITY: usize = 4 * 1024 * 1024;
+
+/// Buffers returned to the pool when dropped, keeping their allocation for the next user.
+pub struct BufferPool {
+	buffers: Mutex<Vec<Vec<u8>>>,
+	max_buffers: usize,
+	max_capacity: usize,
+}
+
+/// Buffer taken from a pool, dereferences to `Vec<u8>`
+pub struct PooledBuffer<'a> {
+	buffer: Vec<u8>,
+	pool: &'a BufferPool,
+}
+
+impl BufferPool {
+	/// New pool keeping up to `max_buffers` buffers of at most `max_capacity` bytes
+	pub fn new(max_buffers: usize, max_capacity: usize) -> Self {
+		BufferPool {
+			buffers: Mutex::new(Vec::with_capacity(max_buffers)),
+			max_buffers: max_buffers,
+			max_capacity: max_capacity,
+		}
+	}
+
+	/// Empty buffer, reusing a previous allocation if there is one
+	pub fn take(&self) -> PooledBuffer {
+		let buffer = self.buffers.lock().unwrap().pop().unwrap_or_else(Vec::new);
+		PooledBuffer {
+			buffer: buffer,
+			pool: self,
+		}
+	}
+
+	/// Number of buffers available for reuse
+	pub fn available(&self) -> usize {
+		self.buffers.lock().unwrap().len()
+	}
+
+	fn release(&self, mut buffer: Vec<u8>) {
+		if buffer.capacity() == 0 || buffer.capacity() > self.max_capacity {
+			return;
+		}
+		let mut buffers = self.buffers.lock().unwrap();
+		if buffers.len() < self.max_buffers {
+			buffer.clear();
+			buffers.push(buffer);
+		}
+	}
+}
+
+impl Default for BufferPool {
+	fn default() -> Self {
+		BufferPool::new(DEFAULT_POOL_BUFFERS, DEFAULT_POOL_CAPACITY)
+	}
+}
+
+impl<'a> PooledBuffer<'a> {
+	/// Takes the buffer out of the pool for good
+	pub fn into_vec(mut self) -> Vec<u8> {
+		mem::replace(&mut self.buffer, Vec::new())
+	}
+}
+
+impl<'a> Deref for PooledBuffer<'a> {
+	type Target = Vec<u8>;
+
+	fn deref(&self) -> &Vec<u8> {
+		&self.buffer
+	}
+}
+
+impl<'a> DerefMut for PooledBuffer<'a> {
+	fn deref_mut(&mut self) -> &mut Vec<u8> {
+		&mut self.buffer
+	}
+}
+
+impl<'a> Drop for PooledBuffer<'a> {
+	fn drop(&mut self) {
+		self.pool.release(mem::replace(&mut self.buffer, Vec::new()));
+	}
+}
+
+thread_local!(static LOCAL_POOL: BufferPool = BufferPool::default());
+
+/// Runs `f` with the buffer pool of the current thread, used by `binary::serialize`
+/// and friends.
+pub fn with_local_pool<F, R>(f: F) -> R where F: FnOnce(&BufferPool) -> R {
+	LOCAL_POOL.with(f)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::BufferPool;
+
+	#[test]
+	fn reuses_buffers() {
+		let pool = BufferPool::new(2, 1024);
+		{
+			let mut buffer = pool.take();
+			buffer.extend_from_slice(&[1, 2, 3]);
+		}
+		assert_eq!(1, pool.available());
+
+		let buffer = pool.take();
+		assert!(buffer.is_empty());
+		assert!(buffer.capacity() >= 3);
+		assert_eq!(0, pool.available());
+	}
+
+	#[test]
+	fn limits_kept_buffers() {
+		let pool = BufferPool::new(1, 16);
+		{
+			let mut small = pool.take();
+			small.push(1);
+			let mut other = pool.take();
+			other.push(1);
+			let mut large = pool.take();
+			large.resize(32, 0);
+		}
+		assert_eq!(1, pool.available());
+
+		let detached = pool.take().into_vec();
+		assert!(detached.capacity() > 0);
+		assert_eq!(0, pool.available());
+	}
+}
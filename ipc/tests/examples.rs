@@ -43,36 +43,98 @@ mod tests {
 	}
 
 
+	#[test]
+	fn call_service_streamed() {
+		// method_num = 4, len = 3 (method Service::history)
+		let request = vec![
+			0, 20,
+			0, 0, 0, 0, 0, 0, 0, 0,
+			4, 0, 0, 0, 0, 0, 0, 0,
+			3, 0, 0, 0];
+		let expected = ::ipc::binary::serialize_streamed(&vec![0u32, 1, 2]).unwrap();
+
+		let service = Arc::new(Service::new());
+		let result = service.dispatch(&mut TestSocket::new_ready(request.clone()));
+		assert_eq!(expected, result);
+
+		let mut written = Vec::new();
+		service.dispatch_into(&mut TestSocket::new_ready(request), &mut written).unwrap();
+		assert_eq!(expected, written);
+	}
+
 	#[test]
 	fn call_service_handshake() {
 		let mut socket = TestSocket::new_ready(vec![0, 0,
-			// part count = 3
-			3, 0, 0, 0, 0, 0, 0, 0,
-			// part sizes
-			5, 0, 0, 0, 0, 0, 0, 0,
-			5, 0, 0, 0, 0, 0, 0, 0,
-			64, 0, 0, 0, 0, 0, 0, 0,
-			// total payload length
-			70, 0, 0, 0, 0, 0, 0, 0,
-			// protocol version
-			b'1', b'.', b'0', b'.', b'0',
-			// api version
-			b'1', b'.', b'0', b'.', b'0',
-			// reserved
-
-				0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-				0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-				0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-				0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+			// api version 1.0.0
+			1, 0, 0, 0, 0, 0, 0, 0,
+			0, 0, 0, 0, 0, 0, 0, 0,
+			0, 0, 0, 0, 0, 0, 0, 0,
+			// protocol version 1.0.0
+			1, 0, 0, 0, 0, 0, 0, 0,
+			0, 0, 0, 0, 0, 0, 0, 0,
+			0, 0, 0, 0, 0, 0, 0, 0,
+			// encoding version
+			2, 0, 0, 0, 0, 0, 0, 0,
+			// features
+			1, 0, 0, 0, 0, 0, 0, 0,
 			]);
 
 		let service = Arc::new(Service::new());
 		let result = service.dispatch(&mut socket);
 
-		// single `true`
-		assert_eq!(vec![1], result);
+		// `true`, followed by the agreed encoding version and (no) features
+		assert_eq!(vec![1,
+			2, 0, 0, 0, 0, 0, 0, 0,
+			0, 0, 0, 0, 0, 0, 0, 0], result);
 	}
 
+	#[test]
+	fn call_service_mismatched_handshake() {
+		let mut data = vec![0, 0];
+		data.extend_from_slice(&::ipc::binary::serialize(&::ipc::BinHandshake::from(::ipc::Handshake {
+			protocol_version: Version::parse("1.0.0").unwrap(),
+			api_version: Version::parse("1.0.0").unwrap(),
+		})).unwrap());
+		// client only knowing encoding version 1
+		data.extend_from_slice(&::ipc::binary::serialize(&::ipc::Negotiation { encoding_version: 1, features: 0 }).unwrap());
+		let mut socket = TestSocket::new_ready(data);
+
+		let service = Arc::new(Service::new());
+		let result = service.dispatch(&mut socket);
+
+		// `false`, without a negotiation
+		assert_eq!(vec![0], result);
+	}
+
+	#[test]
+	fn call_service_legacy_handshake() {
+		let mut data = vec![0, 0];
+		data.extend_from_slice(&::ipc::binary::serialize(&::ipc::BinHandshake::from(::ipc::Handshake {
+			protocol_version: Version::parse("1.0.0").unwrap(),
+			api_version: Version::parse("1.0.0").unwrap(),
+		})).unwrap());
+		let mut socket = TestSocket::new_ready(data);
+
+		let service = Arc::new(Service::new());
+		let result = service.dispatch(&mut socket);
+
+		// single `false`, as clients which don't negotiate use encoding version 1
+		assert_eq!(vec![0], result);
+	}
+
+	#[test]
+	fn call_service_client_negotiated_handshake() {
+		let mut socket = TestSocket::new();
+		socket.read_buffer = vec![1,
+			2, 0, 0, 0, 0, 0, 0, 0,
+			0, 0, 0, 0, 0, 0, 0, 0];
+		let service_client = ServiceClient::init(socket);
+
+		assert!(service_client.handshake().is_ok());
+		assert_eq!(::ipc::Negotiation { encoding_version: 2, features: 0 }, service_client.negotiation());
+		// handshake with the client negotiation appended
+		assert_eq!(2 + 48 + 16, service_client.socket().write().unwrap().write_buffer.len());
+	}
 
 	#[test]
 	fn call_service_client() {
@@ -91,6 +153,15 @@ mod tests {
 		assert_eq!(10, result);
 	}
 
+	#[test]
+	fn call_service_client_streamed() {
+		let mut socket = TestSocket::new();
+		socket.read_buffer = ::ipc::binary::serialize_streamed(&vec![0u32, 1, 2]).unwrap();
+		let service_client = ServiceClient::init(socket);
+
+		assert_eq!(vec![0u32, 1, 2], service_client.history(3));
+	}
+
 	#[test]
 	fn call_service_client_optional() {
 		let mut socket = TestSocket::new();
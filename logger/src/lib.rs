@@ -23,23 +23,34 @@ extern crate isatty;
 extern crate regex;
 extern crate env_logger;
 extern crate time;
+extern crate serde_json;
 #[macro_use]
 extern crate lazy_static;
 
 use std::env;
+use std::collections::BTreeMap;
 use std::sync::Arc;
-use std::fs::File;
-use std::io::Write;
+use std::fs::{self, File};
+use std::io::{self, Write};
 use isatty::{stderr_isatty, stdout_isatty};
 use env_logger::LogBuilder;
 use regex::Regex;
-use util::RotatingLogger;
+use rlog::{LogLevelFilter, LogRecord};
+use serde_json::Value;
+use util::{RotatingLogger, Mutex};
 use util::log::Colour;
 
 pub struct Settings {
 	pub color: bool,
 	pub init: Option<String>,
 	pub file: Option<String>,
+	/// Targets to log, with their levels. When not empty, other targets are logged
+	/// only if set explicitly with `init` or `RUST_LOG`.
+	pub allow: Vec<(String, LogLevelFilter)>,
+	/// Targets never to log.
+	pub deny: Vec<String>,
+	/// Whether each record is written as a JSON object on its own line.
+	pub json: bool,
 }
 
 impl Settings {
@@ -48,6 +59,9 @@ impl Settings {
 			color: true,
 			init: None,
 			file: None,
+			allow: Vec::new(),
+			deny: Vec::new(),
+			json: false,
 		}
 	}
 
@@ -65,6 +79,72 @@ impl Settings {
 		self.color = false;
 		self
 	}
+
+	pub fn allow(mut self, targets: Vec<(String, LogLevelFilter)>) -> Settings {
+		self.allow = targets;
+		self
+	}
+
+	pub fn deny(mut self, targets: Vec<String>) -> Settings {
+		self.deny = targets;
+		self
+	}
+
+	/// Writes records as JSON objects, with the time inside the object rather than as a prefix.
+	/// Implies no color.
+	pub fn json(mut self) -> Settings {
+		self.json = true;
+		self.color = false;
+		self
+	}
+
+	/// Whether output should be colored. Logs go to stderr, so it's colored only if that's a terminal.
+	/// Anything painting text which ends up in the logs should ask here.
+	pub fn use_color(&self) -> bool {
+		self.color && stderr_isatty()
+	}
+}
+
+lazy_static! {
+	// path of the log file and the file itself.
+	static ref LOG_FILE: Mutex<Option<(String, File)>> = Mutex::new(None);
+}
+
+/// Moves the log file aside, to its path suffixed with the current time, and continues logging to a new file.
+/// Returns the path the old file was moved to, or `None` if not logging to a file.
+pub fn rotate_log_file() -> io::Result<Option<String>> {
+	let mut log_file = LOG_FILE.lock();
+	let path = match *log_file {
+		Some((ref path, _)) => path.clone(),
+		None => return Ok(None),
+	};
+	let rotated = format!("{}.{}", path, time::strftime("%Y%m%d-%H%M%S", &time::now()).expect("format is valid; qed"));
+	try!(fs::rename(&path, &rotated));
+	*log_file = Some((path.clone(), try!(File::create(&path))));
+	Ok(Some(rotated))
+}
+
+/// Parses a comma-separated list of `target[=level]`, the level being `info` if not given.
+pub fn parse_targets(targets: &str) -> Result<Vec<(String, LogLevelFilter)>, String> {
+	targets.split(',')
+		.map(|t| t.trim())
+		.filter(|t| !t.is_empty())
+		.map(|t| {
+			let mut parts = t.splitn(2, '=');
+			let target = parts.next().expect("splitn always yields at least one item; qed");
+			match parts.next() {
+				None => Ok((target.to_owned(), LogLevelFilter::Info)),
+				Some(level) => level.parse()
+					.map(|level| (target.to_owned(), level))
+					.map_err(|_| format!("Invalid log level for target {}: {}", target, level)),
+			}
+		})
+		.collect()
+}
+
+/// Formats targets the way `parse_targets` reads them.
+pub fn format_targets(targets: &[(String, LogLevelFilter)]) -> String {
+	targets.iter().map(|&(ref target, level)| format!("{}={}", target, level)).collect::<Vec<_>>().join(",")
 }
 
 /// Sets up the logger
@@ -73,9 +153,16 @@ pub fn setup_log(settings: &Settings) -> Arc<RotatingLogger> {
 
 	let mut levels = String::new();
 	let mut builder = LogBuilder::new();
-	// Disable ws info logging by default.
-	builder.filter(Some("ws"), LogLevelFilter::Warn);
-	builder.filter(None, LogLevelFilter::Info);
+	if settings.allow.is_empty() {
+		// Disable ws info logging by default.
+		builder.filter(Some("ws"), LogLevelFilter::Warn);
+		builder.filter(None, LogLevelFilter::Info);
+	} else {
+		builder.filter(None, LogLevelFilter::Off);
+		for &(ref target, level) in &settings.allow {
+			builder.filter(Some(target.as_str()), level);
+		}
+	}
 
 	if env::var("RUST_LOG").is_ok() {
 		let lvl = &env::var("RUST_LOG").unwrap();
@@ -89,15 +176,23 @@ pub fn setup_log(settings: &Settings) -> Arc<RotatingLogger> {
 		builder.parse(s);
 	}
 
+	// added last, so they override anything above.
+	for target in &settings.deny {
+		builder.filter(Some(target.as_str()), LogLevelFilter::Off);
+	}
+
 	let isatty = stderr_isatty();
-	let enable_color = settings.color && isatty;
+	let enable_color = settings.use_color();
+	let json = settings.json;
 	let logs = Arc::new(RotatingLogger::new(levels));
 	let logger = logs.clone();
-	let maybe_file = settings.file.as_ref().map(|f| File::create(f).unwrap_or_else(|_| panic!("Cannot write to log file given: {}", f)));
+	*LOG_FILE.lock() = settings.file.as_ref().map(|f| (f.clone(), File::create(f).unwrap_or_else(|_| panic!("Cannot write to log file given: {}", f))));
 	let format = move |record: &LogRecord| {
 		let timestamp = time::strftime("%Y-%m-%d %H:%M:%S %Z", &time::now()).unwrap();
 
-		let with_color = if max_log_level() <= LogLevelFilter::Info {
+		let with_color = if json {
+			json_record(record)
+		} else if max_log_level() <= LogLevelFilter::Info {
 			format!("{}{}", Colour::Black.bold().paint(timestamp), record.args())
 		} else {
 			format!("{}{}:{}: {}", Colour::Black.bold().paint(timestamp), record.level(), record.target(), record.args())
@@ -110,7 +205,7 @@ pub fn setup_log(settings: &Settings) -> Arc<RotatingLogger> {
 			false => removed_color.clone(),
 		};
 
-		if let Some(mut file) = maybe_file.as_ref() {
+		if let Some((_, ref mut file)) = *LOG_FILE.lock() {
 			// ignore errors - there's nothing we can do
 			let _ = file.write_all(removed_color.as_bytes());
 			let _ = file.write_all(b"\n");
@@ -128,6 +223,28 @@ pub fn setup_log(settings: &Settings) -> Arc<RotatingLogger> {
 	logs
 }
 
+// the record as a JSON object. Messages which are JSON objects themselves are extended
+// with the time, level and target, others are put in the `message` field.
+fn json_record(record: &LogRecord) -> String {
+	let message = format!("{}", record.args());
+	let parsed = match message.starts_with('{') {
+		true => serde_json::from_str::<Value>(&message).ok(),
+		false => None,
+	};
+	let mut object = match parsed {
+		Some(Value::Object(object)) => object,
+		_ => {
+			let mut object = BTreeMap::new();
+			object.insert("message".to_owned(), Value::String(message));
+			object
+		},
+	};
+	object.insert("timestamp".to_owned(), Value::String(format!("{}", time::now_utc().rfc3339())));
+	object.insert("level".to_owned(), Value::String(format!("{}", record.level())));
+	object.insert("target".to_owned(), Value::String(record.target().to_owned()));
+	serde_json::to_string(&Value::Object(object)).expect("Value serialization is infallible; qed")
+}
+
 fn kill_color(s: &str) -> String {
 	lazy_static! {
 		static ref RE: Regex = Regex::new("\x1b\\[[^m]+m").unwrap();
@@ -135,6 +252,18 @@ fn kill_color(s: &str) -> String {
 	RE.replace_all(s, "")
 }
 
+#[test]
+fn should_parse_targets() {
+	let targets = parse_targets("sync=debug, import,,own_tx=TRACE").unwrap();
+	assert_eq!(targets, vec![
+		("sync".to_owned(), LogLevelFilter::Debug),
+		("import".to_owned(), LogLevelFilter::Info),
+		("own_tx".to_owned(), LogLevelFilter::Trace),
+	]);
+	assert_eq!(parse_targets(&format_targets(&targets)).unwrap(), targets);
+	assert!(parse_targets("sync=loud").is_err());
+}
+
 #[test]
 fn should_remove_colour() {
 	let before = "test";
@@ -0,0 +1,199 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of all value transfers touching a single address.
+
+use std::io::{self, Write};
+use util::{Address, FixedHash, H256, U256};
+use ethcore::client::{BlockChainClient, BlockID, TraceFilter};
+use ethcore::trace::LocalizedTrace;
+use ethcore::trace::trace::{Action, Res};
+
+/// Output format of the activity export.
+#[derive(Debug, PartialEq)]
+pub enum ActivityFormat {
+	/// Comma separated values with a header row.
+	Csv,
+	/// JSON array of objects.
+	Json,
+}
+
+/// Single value transfer derived from a flat trace.
+#[derive(Debug, PartialEq)]
+pub struct Transfer {
+	/// Number of the block containing the transfer.
+	pub block_number: u64,
+	/// Hash of the block containing the transfer.
+	pub block_hash: H256,
+	/// Position of the transaction within the block, `None` for block rewards.
+	pub transaction_number: Option<usize>,
+	/// Hash of the transaction which caused the transfer, `None` for block rewards.
+	pub transaction_hash: Option<H256>,
+	/// Position of the transfer within transaction's call tree.
+	pub trace_address: Vec<usize>,
+	/// Kind of the action: call, create, suicide or reward.
+	pub kind: &'static str,
+	/// Sender of the value.
+	pub from: Address,
+	/// Receiver of the value.
+	pub to: Address,
+	/// Transferred value in wei.
+	pub value: U256,
+}
+
+impl Transfer {
+	/// Converts a trace into a transfer. Returns `None` if trace did not move any value.
+	fn from_trace(trace: &LocalizedTrace) -> Option<Transfer> {
+		let (kind, from, to, value) = match (&trace.action, &trace.result) {
+			(_, &Res::FailedCall(_)) | (_, &Res::FailedCreate(_)) => return None,
+			(&Action::Call(ref call), _) => ("call", call.from.clone(), call.to.clone(), call.value),
+			(&Action::Create(ref create), &Res::Create(ref result)) => ("create", create.from.clone(), result.address.clone(), create.value),
+			(&Action::Create(_), _) => return None,
+			(&Action::Suicide(ref suicide), _) => ("suicide", suicide.address.clone(), suicide.refund_address.clone(), suicide.balance),
+			(&Action::Reward(ref reward), _) => ("reward", Address::zero(), reward.author.clone(), reward.value),
+		};
+
+		if value.is_zero() {
+			return None;
+		}
+
+		Some(Transfer {
+			block_number: trace.block_number,
+			block_hash: trace.block_hash.clone(),
+			transaction_number: trace.transaction_number,
+			transaction_hash: trace.transaction_hash.clone(),
+			trace_address: trace.trace_address.clone(),
+			kind: kind,
+			from: from,
+			to: to,
+			value: value,
+		})
+	}
+
+	fn trace_address_string(&self) -> String {
+		self.trace_address.iter().map(|i| format!("{}", i)).collect::<Vec<_>>().join("-")
+	}
+
+	fn transaction_hash_string(&self) -> String {
+		self.transaction_hash.as_ref().map_or_else(String::new, |h| format!("0x{:?}", h))
+	}
+}
+
+/// Collects all value transfers from or to `address` between blocks `from` and `to` (inclusive).
+///
+/// Returns `None` if the range could not be queried, e.g. when tracing is disabled.
+pub fn collect(client: &BlockChainClient, address: &Address, from: u64, to: u64) -> Option<Vec<Transfer>> {
+	let query = |from_address: Vec<Address>, to_address: Vec<Address>| client.filter_traces(TraceFilter {
+		range: BlockID::Number(from)..BlockID::Number(to),
+		from_address: from_address,
+		to_address: to_address,
+		include_retracted: false,
+	});
+
+	let outgoing = match query(vec![address.clone()], vec![]) {
+		Some(traces) => traces,
+		None => return None,
+	};
+	let incoming = match query(vec![], vec![address.clone()]) {
+		Some(traces) => traces,
+		None => return None,
+	};
+
+	let mut transfers = outgoing.iter()
+		.chain(incoming.iter())
+		.filter_map(Transfer::from_trace)
+		.filter(|t| &t.from == address || &t.to == address)
+		.collect::<Vec<_>>();
+
+	// rewards are paid after all transactions of a block.
+	let order = |t: &Transfer| (t.block_number, t.transaction_number.is_none(), t.transaction_number, t.trace_address.clone());
+	transfers.sort_by(|a, b| order(a).cmp(&order(b)));
+	// self-transfers are returned by both queries
+	transfers.dedup();
+	Some(transfers)
+}
+
+/// Writes transfers to the output in given format.
+pub fn write<W: Write>(out: &mut W, transfers: &[Transfer], format: ActivityFormat) -> io::Result<()> {
+	match format {
+		ActivityFormat::Csv => {
+			try!(writeln!(out, "block_number,block_hash,transaction_hash,trace_address,kind,from,to,value"));
+			for t in transfers {
+				try!(writeln!(out, "{},0x{:?},{},{},{},0x{:?},0x{:?},{}",
+					t.block_number, t.block_hash, t.transaction_hash_string(), t.trace_address_string(), t.kind, t.from, t.to, t.value
+				));
+			}
+		},
+		ActivityFormat::Json => {
+			try!(write!(out, "["));
+			for (i, t) in transfers.iter().enumerate() {
+				try!(write!(out, "{}\n  {{\"blockNumber\":{},\"blockHash\":\"0x{:?}\",\"transactionHash\":\"{}\",\"traceAddress\":[{}],\"kind\":\"{}\",\"from\":\"0x{:?}\",\"to\":\"0x{:?}\",\"value\":\"{}\"}}",
+					if i == 0 { "" } else { "," },
+					t.block_number, t.block_hash, t.transaction_hash_string(),
+					t.trace_address.iter().map(|i| format!("{}", i)).collect::<Vec<_>>().join(","),
+					t.kind, t.from, t.to, t.value
+				));
+			}
+			try!(writeln!(out, "\n]"));
+		},
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use util::{Address, H256, U256};
+	use super::{Transfer, ActivityFormat, write};
+
+	fn transfer() -> Transfer {
+		Transfer {
+			block_number: 5,
+			block_hash: H256::from(1),
+			transaction_number: Some(0),
+			transaction_hash: Some(H256::from(2)),
+			trace_address: vec![0, 1],
+			kind: "call",
+			from: Address::from(3),
+			to: Address::from(4),
+			value: U256::from(1000),
+		}
+	}
+
+	#[test]
+	fn should_write_csv() {
+		let mut out = Vec::new();
+		write(&mut out, &[transfer()], ActivityFormat::Csv).unwrap();
+		let out = String::from_utf8(out).unwrap();
+		let lines = out.lines().collect::<Vec<_>>();
+
+		assert_eq!(lines.len(), 2);
+		assert_eq!(lines[0], "block_number,block_hash,transaction_hash,trace_address,kind,from,to,value");
+		assert!(lines[1].starts_with("5,0x0000000000000000000000000000000000000000000000000000000000000001,"));
+		assert!(lines[1].ends_with(",0-1,call,0x0000000000000000000000000000000000000003,0x0000000000000000000000000000000000000004,1000"));
+	}
+
+	#[test]
+	fn should_write_json() {
+		let mut out = Vec::new();
+		write(&mut out, &[transfer(), transfer()], ActivityFormat::Json).unwrap();
+		let out = String::from_utf8(out).unwrap();
+
+		assert!(out.starts_with("[\n  {\"blockNumber\":5,"));
+		assert!(out.contains("\"traceAddress\":[0,1],\"kind\":\"call\""));
+		assert!(out.contains("},\n  {"));
+		assert!(out.ends_with("\"value\":\"1000\"}\n]\n"));
+	}
+}
@@ -0,0 +1,73 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Crypto backends benchmark.
+
+use std::time::Instant;
+use util::{H256, FixedHash};
+use util::sha3::Backend;
+use util::crypto::{KeyPair, ec};
+
+/// Input sizes used for hashing: a single hash (trie node key) and a typical trie node.
+const HASH_INPUT_SIZES: [usize; 2] = [32, 532];
+
+fn per_second(iterations: usize, start: Instant) -> f64 {
+	let elapsed = start.elapsed();
+	let secs = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000f64;
+	iterations as f64 / secs
+}
+
+/// Runs the benchmark and prints results to stdout.
+pub fn crypto(iterations: usize) {
+	for backend in Backend::all() {
+		for size in &HASH_INPUT_SIZES {
+			let input = vec![0x41u8; *size];
+			let mut out = H256::new();
+			let start = Instant::now();
+			if let Err(e) = backend.sha3_into(&input, out.as_slice_mut()) {
+				println!("keccak {:>8}: {}", backend, e);
+				break;
+			}
+			for _ in 1..iterations {
+				let _ = backend.sha3_into(&input, out.as_slice_mut());
+			}
+			println!("keccak {:>8}: {:>4} bytes input, {:.0} hashes/s", backend, size, per_second(iterations, start));
+		}
+	}
+
+	// libsecp256k1 is the only secp256k1 implementation in this build.
+	let keypair = KeyPair::create().expect("Failed to generate key pair.");
+	let message = H256::random();
+	let signature = ec::sign(keypair.secret(), &message).expect("Failed to sign message.");
+
+	let start = Instant::now();
+	for _ in 0..iterations {
+		ec::sign(keypair.secret(), &message).expect("Failed to sign message.");
+	}
+	println!("secp256k1 libsecp: {:.0} signatures/s", per_second(iterations, start));
+
+	let start = Instant::now();
+	for _ in 0..iterations {
+		ec::verify(keypair.public(), &signature, &message).expect("Failed to verify signature.");
+	}
+	println!("secp256k1 libsecp: {:.0} verifications/s", per_second(iterations, start));
+
+	let start = Instant::now();
+	for _ in 0..iterations {
+		ec::recover(&signature, &message).expect("Failed to recover public key.");
+	}
+	println!("secp256k1 libsecp: {:.0} recoveries/s", per_second(iterations, start));
+}
@@ -27,8 +27,16 @@ Usage:
   parity account import <path>... [options]
   parity wallet import <path> --password FILE [options]
   parity import [ <file> ] [options]
+  parity export activity [ <file> ] --address ADDRESS [options]
+  parity export state <path> [options]
   parity export [ <file> ] [options]
   parity signer new-token [options]
+  parity snapshot verify <path> [options]
+  parity state verify <path> [options]
+  parity trace reindex [options]
+  parity benchmark crypto [options]
+  parity doctor [options]
+  parity testnet [options]
   parity [options]
   parity ui [options]
 
@@ -51,16 +59,30 @@ Operating Options:
                            testnet [default: homestead].
   -d --db-path PATH        Specify the database & configuration directory path
                            [default: $HOME/.parity].
-  --keys-path PATH         Specify the path for JSON key files to be found
-                           [default: $HOME/.parity/keys].
-  --identity NAME          Specify your node's name.
+  --keys-path PATH         Specify the path for JSON key files to be found.
+                           Keys of each chain are kept in a subdirectory
+                           named after it, e.g. mainnet or morden. Keys left
+                           directly in PATH by older versions are moved to
+                           mainnet [default: $HOME/.parity/keys].
+  --keys-shared            Keep the keys of all chains directly in
+                           --keys-path rather than in a directory per chain.
+  --identity NAME          Specify your node's name. It is included in the
+                           client version announced to peers and returned by
+                           web3_clientVersion.
 
 Account Options:
   --unlock ACCOUNTS        Unlock ACCOUNTS for the duration of the execution.
                            ACCOUNTS is a comma-delimited list of addresses.
-                           Implies --no-signer.
+                           Each address may be suffixed with :once to unlock
+                           it for a single transaction or :SECS to unlock it
+                           for SECS seconds only. Implies --no-signer.
   --password FILE          Provide a file containing a password for unlocking
                            an account.
+  --unlock-attempts NUM    Delay further attempts to unlock an account or sign
+                           with its password after NUM failed ones. 0 disables
+                           the delay [default: 5].
+  --unlock-delay SECS      Delay after NUM failed attempts, doubled with every
+                           further failure up to an hour [default: 2].
   --keys-iterations NUM    Specify the number of iterations to use when
                            deriving key from the password (bigger is more
                            secure) [default: 10240].
@@ -73,9 +95,33 @@ Account Options:
                            [default: 8180].
   --signer-path PATH       Specify directory where Signer UIs tokens should
                            be stored. [default: $HOME/.parity/signer]
+  --signer-tls-cert FILE   Serve Trusted Signer over HTTPS and WSS only, using
+                           the PEM-encoded certificate chain in FILE. Requires
+                           --signer-tls-key.
+  --signer-tls-key FILE    PEM-encoded private key of the certificate given
+                           with --signer-tls-cert.
+  --ws-apis APIS           Specify the APIs available through the Trusted
+                           Signer WebSocket endpoint. APIS is a comma-delimited
+                           list of API names, as for --jsonrpc-apis.
+                           Subscriptions (eth_subscribe) are served with eth,
+                           here and over IPC, but not over HTTP.
+                           [default: web3,eth,net,ethcore,personal,traces,rpc,signer].
+  --remote-signer URL      Keep no keys on the node and forward all signing to
+                           the external signing service at URL instead. URL is
+                           either ipc://PATH for a service listening on the
+                           Unix socket at PATH, or an http URL. Requests are
+                           sent over plain HTTP, so an http URL must be on a
+                           loopback address.
+  --remote-signer-token FILE
+                           Provide a file containing the token used to
+                           authenticate with the remote signer over HTTP.
 
 Networking Options:
   --no-network             Disable p2p networking.
+  --offline                Disable all outbound HTTP requests: Ether price
+                           lookups, ethstats reporting and work notifications.
+                           Can't be used with --remote-signer. Intended for
+                           air-gapped deployments.
   --port PORT              Override the port on which the node should listen
                            [default: 30303].
   --peers NUM              Try to maintain that many peers [default: 25].
@@ -93,6 +139,15 @@ Networking Options:
                            These nodes will always have a reserved slot on top
                            of the normal maximum peers.
   --reserved-only          Connect only to reserved nodes.
+  --min-peer-protocol-version VER
+                           Refuse peers using an eth protocol version lower
+                           than VER, e.g. 63 [default: 0].
+  --warp                   When starting with an empty database, restore the
+                           state and chain from a snapshot downloaded from
+                           peers before switching to normal block sync.
+  --ethstats SERVER        Report block, peer and latency statistics to an
+                           ethstats server. SERVER should be given as
+                           name:secret@host:port.
 
 API and Console Options:
   --no-jsonrpc             Disable the JSON-RPC API server.
@@ -102,22 +157,94 @@ API and Console Options:
                            server, IP should be an interface's IP address, or
                            all (all interfaces) or local [default: local].
   --jsonrpc-cors URL       Specify CORS header for JSON-RPC API responses.
+                           URL is a comma-delimited list of allowed origins.
+                           Special options: "all" (or "*") allows any origin,
+                           "null" allows local files, "none" allows none.
   --jsonrpc-apis APIS      Specify the APIs available through the JSONRPC
                            interface. APIS is a comma-delimited list of API
                            name. Possible name are web3, eth, net, personal,
-                           ethcore, ethcore_set, traces.
+                           ethcore, ethcore_set, traces, rpc, admin.
                            [default: web3,eth,net,ethcore,personal,traces].
   --jsonrpc-hosts HOSTS    List of allowed Host header values. This option will
                            validate the Host header sent by the browser, it
                            is additional security against some attack
                            vectors. Special options: "all", "none",
                            [default: none].
+  --jsonrpc-keys           Require an API key with every JSON-RPC request over
+                           HTTP, given in the X-Api-Key header or as a bearer
+                           token. Each key grants access to some of the
+                           JSONRPC APIs and may be limited to a number of
+                           calls a day. Keys are managed with
+                           ethcore_addApiKey and ethcore_removeApiKey. The
+                           Trusted Signer WebSocket then also accepts a key
+                           in place of an authorization code.
+  --jsonrpc-secret FILE    Require the shared secret in FILE with every JSON-RPC
+                           request over HTTP, given as a bearer token or as the
+                           password of HTTP Basic Authentication (with any
+                           username). The secret grants access to all the
+                           JSONRPC APIs. Use it when exposing the interface
+                           beyond localhost.
+  --jsonrpc-rate-limit CALLS  Limit the number of JSON-RPC calls over HTTP a
+                           single IP address may make per second. Calls over
+                           the limit fail with error -32005.
+  --jsonrpc-method-limits LIMITS  Limit the number of calls per second to
+                           single methods, e.g. expensive ones. LIMITS is a
+                           comma-delimited list of METHOD=CALLS, e.g.
+                           eth_getLogs=2,trace_filter=1.
+  --jsonrpc-compress-above BYTES  Compress JSON-RPC responses over HTTP larger
+                           than BYTES with gzip or deflate, if the client
+                           accepts it.
+  --jsonrpc-tls-cert FILE  Serve JSON-RPC over HTTPS only, using the
+                           PEM-encoded certificate chain in FILE. Requires
+                           --jsonrpc-tls-key.
+  --jsonrpc-tls-key FILE   PEM-encoded private key of the certificate given
+                           with --jsonrpc-tls-cert.
+  --rpc-gas-cap GAS        Gas supplied to eth_call, eth_estimateGas,
+                           trace_call and ethcore_pinnedCall when the request
+                           doesn't specify it. Also the upper bound of gas
+                           estimation [default: 50000000].
+  --rpc-audit-log PATH     Record every JSON-RPC call over HTTP and the Trusted
+                           Signer port (time, transport, origin, method and
+                           truncated params, with passwords redacted) in the
+                           file at PATH. Calls over IPC are recorded too,
+                           except on Windows.
+  --rpc-audit-log-size MB  Rotate the audit log once it grows over MB megabytes.
+                           The last 5 rotated logs are kept [default: 100].
 
   --no-ipc                 Disable JSON-RPC over IPC service.
   --ipc-path PATH          Specify custom path for JSON-RPC over IPC service
-                           [default: $HOME/.parity/jsonrpc.ipc].
+                           [default: $HOME/.parity/jsonrpc.ipc]. PATH may
+                           contain placeholders: %c - chain, %p - network
+                           port, %r - JSON-RPC port, %i - identity, %h - home
+                           directory, %% - literal %. The same placeholders
+                           are accepted in --log-file and <pid-file>.
+  --ipc-pipe NAME          On Windows, serve JSON-RPC over IPC on the named pipe
+                           \\.\pipe\NAME instead of a socket at --ipc-path. NAME
+                           accepts the placeholders of --ipc-path
+                           [default: parity.jsonrpc].
+  --ipc-mode MODE          Set the permissions of the IPC socket to the octal
+                           MODE, e.g. 660 to allow access to the group of the
+                           socket. Ignored on Windows.
+  --ipc-owner UID          Set the owner of the IPC socket to the numeric user
+                           id UID. Ignored on Windows.
+  --ipc-group GID          Set the group of the IPC socket to the numeric group
+                           id GID. Ignored on Windows. With any of these
+                           options the socket is created accessible by its
+                           owner only, unless --ipc-mode says otherwise.
   --ipc-apis APIS          Specify custom API set available via JSON-RPC over
-                           IPC [default: web3,eth,net,ethcore,personal,traces,rpc].
+                           IPC. Subscriptions (eth_subscribe) are served with
+                           eth, except on Windows
+                           [default: web3,eth,net,ethcore,personal,traces,rpc].
+  --trace-stream PATH      Publish the traces of blocks entering the canonical
+                           chain, and the hashes of blocks leaving it, on a
+                           nanomsg PUB socket at ipc://PATH. PATH accepts the
+                           placeholders of --ipc-path. Requires --tracing on.
+  --metrics-port PORT      Serve import, queue, memory, peer and sync metrics
+                           in the Prometheus text format on
+                           http://IP:PORT/metrics.
+  --metrics-interface IP   Specify the hostname portion of the metrics server,
+                           IP should be an interface's IP address, or all (all
+                           interfaces) or local [default: local].
 
   --no-dapps               Disable the Dapps server (e.g. status page).
   --dapps-port PORT        Specify the port portion of the Dapps server
@@ -170,6 +297,8 @@ Sealing/Mining Options:
                            amount in USD, a web service or 'auto' to use each
                            web service in turn and fallback on the last known
                            good value [default: auto].
+  --offline-price USD      USD value of a single ETH used in place of 'auto'
+                           when running with --offline [default: 10].
   --price-update-period T  T will be allowed to pass between each gas price
                            update. T may be daily, hourly, a number of seconds,
                            or a time string of the form "2 days", "30 minutes"
@@ -182,6 +311,16 @@ Sealing/Mining Options:
                            more than 32 characters.
   --tx-queue-size LIMIT    Maximum amount of transactions in the queue (waiting
                            to be included in next block) [default: 1024].
+  --tx-rebroadcast-interval SECS
+                           Re-broadcast own transactions which haven't been
+                           seen in a block or announced by peers after SECS
+                           seconds [default: 60].
+  --tx-rebroadcast-backoff FACTOR
+                           Multiply the re-broadcast interval by FACTOR after
+                           each attempt [default: 2].
+  --tx-rebroadcast-attempts N
+                           Give up re-broadcasting a transaction after N
+                           attempts. 0 disables re-broadcasting [default: 5].
   --remove-solved          Move solved blocks from the work package queue
                            instead of cloning them. This gives a slightly
                            faster import speed, but means that extra solutions
@@ -195,12 +334,19 @@ Footprint Options:
                            with tracing enabled. BOOL may be one of auto, on,
                            off. auto uses last used value of this option (off
                            if it does not exist) [default: auto].
+  --trace-retention BLOCKS Keep traces of the latest BLOCKS blocks only. Older
+                           traces are removed from the database as the chain
+                           advances.
   --pruning METHOD         Configure pruning of the state/storage trie. METHOD
                            may be one of auto, archive, fast:
                            archive - keep all state trie data. No pruning.
                            fast - maintain journal overlay. Fast but 50MB used.
                            auto - use the method most recently synced or
                            default to fast if none synced [default: auto].
+  --state-replay BLOCKS    When the state of a block asked for by eth_call and
+                           similar has been pruned, rebuild it by executing up
+                           to BLOCKS blocks from the nearest block whose state
+                           is kept. 0 to disable [default: 0].
   --cache-pref-size BYTES  Specify the preferred size of the blockchain cache in
                            bytes [default: 16384].
   --cache-max-size BYTES   Specify the maximum size of the blockchain cache in
@@ -217,6 +363,17 @@ Database Options:
                            ssd - suitable for SSDs and fast HDDs;
                            hdd - suitable for slow HDDs [default: ssd].
   --fat-db                 Fat database.
+  --snapshot-every N       Take a snapshot of the chain and state in the
+                           background every N blocks. Snapshots are stored in
+                           the snapshots subdirectory of the database
+                           [default: 0]. Zero disables periodic snapshots.
+  --snapshot-history K     Keep K most recent periodic snapshots and remove
+                           the older ones [default: 2].
+  --snapshot-chunk-size BYTES
+                           Preferred size of snapshot chunks before
+                           compression [default: 16777216].
+  --snapshot-codec CODEC   Compress snapshot chunks with CODEC. CODEC may be
+                           one of: snappy, zlib, none [default: snappy].
 
 Import/Export Options:
   --from BLOCK             Export from block BLOCK, which may be an index or
@@ -224,10 +381,55 @@ Import/Export Options:
   --to BLOCK               Export to (including) block BLOCK, which may be an
                            index, hash or 'latest' [default: latest].
   --format FORMAT          For import/export in given format. FORMAT must be
-                           one of 'hex' and 'binary'.
+                           one of 'hex' and 'binary'. For activity export
+                           FORMAT must be one of 'csv' and 'json'.
+  --address ADDRESS        Export all value transfers, including internal
+                           ones, sent from or to ADDRESS. Requires a chain
+                           fully synced with --tracing on. When verifying
+                           an exported state, verify just the account of
+                           ADDRESS.
+  --at BLOCK               Export the state at block BLOCK, which may be an
+                           index, hash or 'latest' [default: latest].
+  --schedule TASKS         Run maintenance tasks at given times. TASKS is a
+                           ;-separated list of TASK=MINUTE HOUR, times being
+                           in UTC and given like in crontab, e.g.
+                           "snapshot=30 4; rotate-log=0 */6". TASK may be
+                           snapshot - take a snapshot of the latest block;
+                           rotate-log - move the log file aside and start a
+                           new one.
+  --import-dir PATH        Watch directory PATH and import any block files
+                           placed in it, in hex or binary format. Processed
+                           files are moved to the imported or failed
+                           subdirectories. Files starting with a dot are
+                           ignored until renamed.
+  --import-log FILE        Append a CSV row with the number, transaction
+                           count, gas used, import time in milliseconds and
+                           size of every imported block to FILE. Useful for
+                           comparing pruning and cache settings.
+
+Test Network Options:
+  --testnet-nodes N        Number of nodes started by the testnet command. The
+                           nodes use consecutive ports from --port and
+                           --jsonrpc-port on and live in the testnet
+                           subdirectory of the database path [default: 3].
 
 Virtual Machine Options:
   --jitvm                  Enable the JIT VM.
+  --profile-evm            Gather number of executions and gas used of every
+                           instruction of each contract, written to
+                           evm_profile.json in the database path every minute
+                           and served by ethcore_evmProfile. Slows down block
+                           import. Not supported by the JIT VM.
+
+Crypto Options:
+  --keccak-backend IMPL    Specify the Keccak implementation used for hashing.
+                           IMPL may be one of:
+                           auto - fastest implementation supported by the CPU;
+                           portable - portable implementation;
+                           avx2 - implementation for x86_64 CPUs supporting
+                           AVX2 and BMI2 [default: auto].
+  --bench-iterations NUM   Number of iterations performed by each step of
+                           benchmark crypto [default: 100000].
 
 Legacy Options:
   --geth                   Run in Geth-compatibility mode. Sets the IPC path
@@ -235,8 +437,8 @@ Legacy Options:
                            and --ipcpath options. Alters RPCs to reflect Geth
                            bugs.
   --testnet                Geth-compatible testnet mode. Equivalent to --chain
-                           testnet --keys-path $HOME/parity/testnet-keys.
-                           Overrides the --keys-path option.
+                           testnet. Together with --keys-shared, uses
+                           $HOME/.parity/testnet_keys as --keys-path.
   --datadir PATH           Equivalent to --db-path PATH.
   --networkid INDEX        Equivalent to --network-id INDEX.
   --maxpeers COUNT         Equivalent to --peers COUNT.
@@ -265,8 +467,18 @@ Miscellaneous Options:
   -l --logging LOGGING     Specify the logging level. Must conform to the same
                            format as RUST_LOG.
   --log-file FILENAME      Specify a filename into which logging should be
-                           directed.
+                           directed. May contain the same placeholders as
+                           --ipc-path.
+  --log-allow TARGETS      Log only the given comma-separated targets, each
+                           with an optional level, e.g. sync=debug,import.
+                           Targets set with --logging are still logged.
+  --log-deny TARGETS       Never log the given comma-separated targets.
   --no-color               Don't use terminal color codes in output.
+  --log-format FORMAT      Format of the logs. FORMAT may be either text or
+                           json, which logs a JSON object with the time, level
+                           and target per line. Status and import lines carry
+                           their values as fields of the object
+                           [default: text].
   -v --version             Show information about version.
   -h --help                Show this screen.
 "#;
@@ -279,10 +491,20 @@ pub struct Args {
 	pub cmd_new: bool,
 	pub cmd_list: bool,
 	pub cmd_export: bool,
+	pub cmd_activity: bool,
 	pub cmd_import: bool,
 	pub cmd_signer: bool,
 	pub cmd_new_token: bool,
+	pub cmd_snapshot: bool,
+	pub cmd_verify: bool,
+	pub cmd_state: bool,
+	pub cmd_trace: bool,
+	pub cmd_reindex: bool,
 	pub cmd_ui: bool,
+	pub cmd_benchmark: bool,
+	pub cmd_crypto: bool,
+	pub cmd_doctor: bool,
+	pub cmd_testnet: bool,
 	pub arg_pid_file: String,
 	pub arg_file: Option<String>,
 	pub arg_path: Vec<String>,
@@ -294,6 +516,8 @@ pub struct Args {
 	pub flag_identity: String,
 	pub flag_unlock: Option<String>,
 	pub flag_password: Vec<String>,
+	pub flag_unlock_attempts: usize,
+	pub flag_unlock_delay: u64,
 	pub flag_cache: Option<usize>,
 	pub flag_keys_path: String,
 	pub flag_keys_iterations: u32,
@@ -302,6 +526,8 @@ pub struct Args {
 	pub flag_network_id: Option<String>,
 	pub flag_pruning: String,
 	pub flag_tracing: String,
+	pub flag_trace_retention: Option<u64>,
+	pub flag_profile_evm: bool,
 	pub flag_port: u16,
 	pub flag_peers: usize,
 	pub flag_no_discovery: bool,
@@ -309,6 +535,9 @@ pub struct Args {
 	pub flag_node_key: Option<String>,
 	pub flag_reserved_peers: Option<String>,
 	pub flag_reserved_only: bool,
+	pub flag_min_peer_protocol_version: u32,
+	pub flag_warp: bool,
+	pub flag_ethstats: Option<String>,
 	pub flag_cache_pref_size: usize,
 	pub flag_cache_max_size: usize,
 	pub flag_queue_max_size: usize,
@@ -318,9 +547,26 @@ pub struct Args {
 	pub flag_jsonrpc_cors: Option<String>,
 	pub flag_jsonrpc_hosts: String,
 	pub flag_jsonrpc_apis: String,
+	pub flag_jsonrpc_keys: bool,
+	pub flag_jsonrpc_secret: Option<String>,
+	pub flag_jsonrpc_rate_limit: Option<u32>,
+	pub flag_jsonrpc_method_limits: Option<String>,
+	pub flag_jsonrpc_compress_above: Option<usize>,
+	pub flag_jsonrpc_tls_cert: Option<String>,
+	pub flag_jsonrpc_tls_key: Option<String>,
+	pub flag_rpc_gas_cap: String,
+	pub flag_rpc_audit_log: Option<String>,
+	pub flag_rpc_audit_log_size: u64,
 	pub flag_no_ipc: bool,
 	pub flag_ipc_path: String,
+	pub flag_ipc_pipe: String,
+	pub flag_ipc_mode: Option<String>,
+	pub flag_ipc_owner: Option<u32>,
+	pub flag_ipc_group: Option<u32>,
 	pub flag_ipc_apis: String,
+	pub flag_trace_stream: Option<String>,
+	pub flag_metrics_port: Option<u16>,
+	pub flag_metrics_interface: String,
 	pub flag_no_dapps: bool,
 	pub flag_dapps_port: u16,
 	pub flag_dapps_interface: String,
@@ -331,6 +577,11 @@ pub struct Args {
 	pub flag_no_signer: bool,
 	pub flag_signer_port: u16,
 	pub flag_signer_path: String,
+	pub flag_signer_tls_cert: Option<String>,
+	pub flag_signer_tls_key: Option<String>,
+	pub flag_ws_apis: String,
+	pub flag_remote_signer: Option<String>,
+	pub flag_remote_signer_token: Option<String>,
 	pub flag_force_sealing: bool,
 	pub flag_reseal_on_txs: String,
 	pub flag_reseal_min_period: u64,
@@ -346,16 +597,34 @@ pub struct Args {
 	pub flag_gas_cap: String,
 	pub flag_extra_data: Option<String>,
 	pub flag_tx_queue_size: usize,
+	pub flag_tx_rebroadcast_interval: u64,
+	pub flag_tx_rebroadcast_backoff: u32,
+	pub flag_tx_rebroadcast_attempts: usize,
 	pub flag_notify_work: Option<String>,
 	pub flag_logging: Option<String>,
 	pub flag_version: bool,
 	pub flag_from: String,
 	pub flag_to: String,
 	pub flag_format: Option<String>,
+	pub flag_address: Option<String>,
+	pub flag_at: String,
+	pub flag_schedule: Option<String>,
+	pub flag_import_dir: Option<String>,
+	pub flag_import_log: Option<String>,
+	pub flag_state_replay: u64,
+	pub flag_testnet_nodes: usize,
 	pub flag_jitvm: bool,
+	pub flag_keccak_backend: String,
+	pub flag_bench_iterations: usize,
 	pub flag_log_file: Option<String>,
+	pub flag_log_allow: Option<String>,
+	pub flag_log_deny: Option<String>,
 	pub flag_no_color: bool,
+	pub flag_keys_shared: bool,
+	pub flag_log_format: String,
 	pub flag_no_network: bool,
+	pub flag_offline: bool,
+	pub flag_offline_price: String,
 	// legacy...
 	pub flag_geth: bool,
 	pub flag_nodekey: Option<String>,
@@ -383,6 +652,10 @@ pub struct Args {
 	pub flag_db_cache_size: Option<usize>,
 	pub flag_db_compaction: String,
 	pub flag_fat_db: bool,
+	pub flag_snapshot_every: u64,
+	pub flag_snapshot_history: usize,
+	pub flag_snapshot_chunk_size: usize,
+	pub flag_snapshot_codec: String,
 }
 
 pub fn print_version() {
@@ -15,26 +15,34 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::env;
+use std::collections::HashMap;
 use std::fs::File;
 use std::time::Duration;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
 use std::net::{SocketAddr, IpAddr};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use cli::{USAGE, Args};
 use docopt::Docopt;
 
 use die::*;
 use util::*;
 use util::log::Colour::*;
-use ethcore::account_provider::AccountProvider;
+use ethcore::account_provider::{AccountProvider, UnlockDuration, UnlockPolicy};
+use ethcore::remote_signer::{RemoteSigner, RemoteSignerOptions};
 use util::network_settings::NetworkSettings;
-use ethcore::client::{append_path, get_db_path, Mode, ClientConfig, DatabaseCompactionProfile, Switch, VMType};
-use ethcore::miner::{MinerOptions, PendingSet, GasPricer, GasPriceCalibratorOptions};
+use ethcore::client::{append_path, get_db_path, Mode, ClientConfig, DatabaseCompactionProfile, SnapshotConfig, Switch, VMType};
+use ethcore::miner::{MinerOptions, PendingSet, GasPricer, GasPriceCalibratorOptions, RebroadcastPolicy};
 use ethcore::ethereum;
+use ethcore::snapshot::ChunkOptions;
 use ethcore::spec::Spec;
 use ethsync::SyncConfig;
 use rpc::IpcConfiguration;
-use ethcore_logger::Settings as LogSettings;
+use rpc_keys::Tls;
+use ethcore_rpc::RateLimits;
+use rpc_apis::{self, ApiSet, ApiError};
+use ethcore_logger::{Settings as LogSettings, parse_targets as parse_log_targets};
+use informant::Format as InformantFormat;
+use scheduler::{self, Task, Schedule};
 
 pub struct Configuration {
 	pub args: Args
@@ -87,7 +95,17 @@ impl Configuration {
 		)
 	}
 
+	fn parse_apis(apis: &str, argument: &str) -> ApiSet {
+		apis.parse().unwrap_or_else(|e| match e {
+			ApiError::UnknownApi(api) => die!("{}: Unknown API specified for {}. Possible names are: {}.", api, argument, rpc_apis::API_NAMES.join(", ")),
+		})
+	}
+
 	fn work_notify(&self) -> Vec<String> {
+		if self.args.flag_offline && self.args.flag_notify_work.is_some() {
+			warn!("Running with --offline, work notifications are disabled.");
+			return Vec::new();
+		}
 		self.args.flag_notify_work.as_ref().map_or_else(Vec::new, |s| s.split(',').map(|s| s.to_owned()).collect())
 	}
 
@@ -115,6 +133,11 @@ impl Configuration {
 			reseal_min_period: Duration::from_millis(self.args.flag_reseal_min_period),
 			work_queue_size: self.args.flag_work_queue_size,
 			enable_resubmission: !self.args.flag_remove_solved,
+			tx_rebroadcast: RebroadcastPolicy {
+				interval: Duration::from_secs(self.args.flag_tx_rebroadcast_interval),
+				backoff: self.args.flag_tx_rebroadcast_backoff,
+				max_attempts: self.args.flag_tx_rebroadcast_attempts,
+			},
 		}
 	}
 
@@ -140,6 +163,13 @@ impl Configuration {
 		})
 	}
 
+	pub fn rpc_gas_cap(&self) -> U256 {
+		let d = &self.args.flag_rpc_gas_cap;
+		U256::from_dec_str(d).unwrap_or_else(|_| {
+			die!("{}: Invalid gas cap given for --rpc-gas-cap. Must be a decimal unsigned 256-bit number.", d)
+		})
+	}
+
 	fn to_duration(s: &str) -> Duration {
 		let bad = |_| {
 			die!("{}: Invalid duration given. See parity --help for more information.", s)
@@ -170,7 +200,11 @@ impl Configuration {
 				let usd_per_tx: f32 = FromStr::from_str(&self.args.flag_usd_per_tx).unwrap_or_else(|_| {
 					die!("{}: Invalid basic transaction price given in USD. Must be a decimal number.", self.args.flag_usd_per_tx)
 				});
-				match self.args.flag_usd_per_eth.as_str() {
+				let usd_per_eth = match self.args.flag_offline {
+					true if self.args.flag_usd_per_eth == "auto" => self.args.flag_offline_price.as_str(),
+					_ => self.args.flag_usd_per_eth.as_str(),
+				};
+				match usd_per_eth {
 					"auto" => {
 						GasPricer::new_calibrated(GasPriceCalibratorOptions {
 							usd_per_tx: usd_per_tx,
@@ -276,6 +310,7 @@ impl Configuration {
 		net_path.push("network");
 		ret.config_path = Some(net_path.to_str().unwrap().to_owned());
 		ret.reserved_nodes = self.init_reserved_nodes();
+		ret.client_version = self.client_version();
 
 		if self.args.flag_reserved_only {
 			ret.non_reserved_mode = ::util::network::NonReservedPeerMode::Deny;
@@ -339,6 +374,10 @@ impl Configuration {
 		};
 		// forced trace db cache size if provided
 		client_config.tracing.db_cache_size = self.args.flag_db_cache_size.and_then(|cs| Some(cs / 4));
+		client_config.tracing.retention = match self.args.flag_trace_retention {
+			Some(0) => die!("Trace retention must be at least one block."),
+			retention => retention,
+		};
 
 		client_config.pruning = self.pruning_algorithm(spec);
 
@@ -364,24 +403,95 @@ impl Configuration {
 			client_config.vm_type = VMType::jit().unwrap_or_else(|| die!("Parity is built without the JIT EVM."))
 		}
 
+		client_config.evm_profiling = self.args.flag_profile_evm;
+		client_config.state_replay_budget = self.args.flag_state_replay;
+
 		trace!(target: "parity", "Using pruning strategy of {}", client_config.pruning);
 		client_config.name = self.args.flag_identity.clone();
 		client_config.queue.max_mem_use = self.args.flag_queue_max_size;
+		if self.args.flag_snapshot_chunk_size == 0 {
+			die!("Invalid snapshot chunk size given (--snapshot-chunk-size argument), expected a positive number of bytes.");
+		}
+		client_config.snapshot = SnapshotConfig {
+			period: self.args.flag_snapshot_every,
+			history: self.args.flag_snapshot_history,
+			chunks: ChunkOptions {
+				chunk_size: self.args.flag_snapshot_chunk_size,
+				codec: self.args.flag_snapshot_codec.parse().unwrap_or_else(|e| die!("{} (--snapshot-codec argument), expected snappy, zlib or none.", e)),
+			},
+		};
 		client_config
 	}
 
+	pub fn keccak_backend(&self) -> sha3::Backend {
+		match self.args.flag_keccak_backend.as_str() {
+			"auto" => sha3::Backend::best(),
+			x => x.parse().unwrap_or_else(|e| die!("{} (--keccak-backend argument), expected auto, portable or avx2.", e)),
+		}
+	}
+
 	pub fn sync_config(&self, spec: &Spec) -> SyncConfig {
 		let mut sync_config = SyncConfig::default();
 		sync_config.network_id = self.args.flag_network_id.as_ref().or(self.args.flag_networkid.as_ref()).map_or(spec.network_id(), |id| {
 			U256::from_str(id).unwrap_or_else(|_| die!("{}: Invalid index given with --network-id/--networkid", id))
 		});
+		sync_config.warp_sync = self.args.flag_warp;
+		sync_config.min_peer_protocol_version = self.args.flag_min_peer_protocol_version;
 		sync_config
 	}
 
-	pub fn account_service(&self) -> AccountProvider {
-		use ethcore::ethstore::{import_accounts, EthStore};
-		use ethcore::ethstore::dir::{GethDirectory, DirectoryType, DiskDirectory};
+	pub fn ethstats(&self) -> Option<::ethstats::Configuration> {
+		if self.args.flag_offline && self.args.flag_ethstats.is_some() {
+			warn!("Running with --offline, ethstats reporting is disabled.");
+			return None;
+		}
+		self.args.flag_ethstats.as_ref().map(|s| s.parse().unwrap_or_else(|e| die!("{}", e)))
+	}
+
+	pub fn trace_stream_addr(&self) -> Option<String> {
+		self.args.flag_trace_stream.as_ref().map(|path| format!("ipc://{}", self.replace_placeholders(path)))
+	}
+
+	pub fn metrics_addr(&self) -> Option<SocketAddr> {
+		self.args.flag_metrics_port.map(|port| {
+			let interface = match self.args.flag_metrics_interface.as_str() {
+				"all" => "0.0.0.0",
+				"local" => "127.0.0.1",
+				x => x,
+			};
+			let ip = IpAddr::from_str(interface).unwrap_or_else(|_| die!("{}: Invalid metrics interface given.", interface));
+			SocketAddr::new(ip, port)
+		})
+	}
+
+	pub fn evm_profile_path(&self) -> Option<PathBuf> {
+		match self.args.flag_profile_evm {
+			true => Some(Path::new(&self.path()).join("evm_profile.json")),
+			false => None,
+		}
+	}
+
+	pub fn api_keys_path(&self) -> PathBuf {
+		Path::new(&self.path()).join("rpc_keys.json")
+	}
+
+	pub fn schedule(&self) -> Option<Vec<(Task, Schedule)>> {
+		self.args.flag_schedule.as_ref().map(|spec| scheduler::parse(spec).unwrap_or_else(|e| die!("{} (--schedule argument)", e)))
+	}
+
+	pub fn rpc_audit_log_path(&self) -> Option<PathBuf> {
+		self.args.flag_rpc_audit_log.as_ref().map(|f| PathBuf::from(Configuration::replace_home(f)))
+	}
 
+	pub fn import_log_path(&self) -> Option<PathBuf> {
+		self.args.flag_import_log.as_ref().map(|f| PathBuf::from(Configuration::replace_home(f)))
+	}
+
+	pub fn import_dir(&self) -> Option<PathBuf> {
+		self.args.flag_import_dir.as_ref().map(|d| PathBuf::from(Configuration::replace_home(d)))
+	}
+
+	pub fn account_service(&self) -> AccountProvider {
 		// Secret Store
 		let passwords = self.args.flag_password.iter().flat_map(|filename| {
 			BufReader::new(&File::open(filename).unwrap_or_else(|_| die!("{} Unable to read password file. Ensure it exists and permissions are correct.", filename)))
@@ -391,6 +501,47 @@ impl Configuration {
 				.into_iter()
 		}).collect::<Vec<_>>();
 
+		let account_service = match self.args.flag_remote_signer {
+			Some(_) if self.args.flag_offline => die!("The remote signer can't be used with --offline. Remove --remote-signer or --offline."),
+			Some(ref url) => AccountProvider::new(Box::new(self.remote_signer(url))),
+			None => self.local_account_service(),
+		}.with_unlock_policy(self.unlock_policy());
+
+		// key derivation is slow, so accounts are unlocked in parallel
+		let failed = account_service.unlock_accounts(self.unlocks(), &passwords, ::num_cpus::get());
+		if let Some(a) = failed.first() {
+			die!("No password given to unlock account {}. Pass the password using `--password`.", a);
+		}
+		account_service
+	}
+
+	fn unlock_policy(&self) -> UnlockPolicy {
+		UnlockPolicy {
+			max_attempts: self.args.flag_unlock_attempts,
+			delay: Duration::from_secs(self.args.flag_unlock_delay),
+			..Default::default()
+		}
+	}
+
+	fn remote_signer(&self, url: &str) -> RemoteSigner {
+		let token = self.args.flag_remote_signer_token.as_ref().map_or_else(String::new, |filename| {
+			let mut token = String::new();
+			File::open(filename).and_then(|mut file| file.read_to_string(&mut token))
+				.unwrap_or_else(|_| die!("{} Unable to read remote signer token file. Ensure it exists and permissions are correct.", filename));
+			token.trim().to_owned()
+		});
+
+		RemoteSigner::new(RemoteSignerOptions {
+			url: url.to_owned(),
+			token: token,
+			timeout: Duration::from_secs(10),
+		}).unwrap_or_else(|e| die!("{}", e))
+	}
+
+	fn local_account_service(&self) -> AccountProvider {
+		use ethcore::ethstore::{import_accounts, EthStore};
+		use ethcore::ethstore::dir::{GethDirectory, DirectoryType, DiskDirectory};
+
 		if !self.args.flag_no_import_keys {
 			let dir_type = if self.args.flag_testnet {
 				DirectoryType::Testnet
@@ -406,38 +557,118 @@ impl Configuration {
 
 		let dir = Box::new(DiskDirectory::create(self.keys_path()).unwrap());
 		let iterations = self.keys_iterations();
-		let account_service = AccountProvider::new(Box::new(EthStore::open_with_iterations(dir, iterations).unwrap()));
+		AccountProvider::new(Box::new(EthStore::open_with_iterations(dir, iterations).unwrap()))
+	}
 
-		if let Some(ref unlocks) = self.args.flag_unlock {
-			for d in unlocks.split(',') {
-				let a = Address::from_str(clean_0x(d)).unwrap_or_else(|_| {
-					die!("{}: Invalid address for --unlock. Must be 40 hex characters, without the 0x at the beginning.", d)
-				});
-				if passwords.iter().find(|p| account_service.unlock_account_permanently(a, (*p).clone()).is_ok()).is_none() {
-					die!("No password given to unlock account {}. Pass the password using `--password`.", a);
-				}
-			}
-		}
-		account_service
+	fn unlocks(&self) -> Vec<(Address, UnlockDuration)> {
+		self.args.flag_unlock.as_ref().map_or_else(Vec::new, |unlocks| unlocks.split(',').map(|d| {
+			let mut parts = d.splitn(2, ':');
+			let address = parts.next().expect("splitn always returns at least one element; qed");
+			let a = Address::from_str(clean_0x(address)).unwrap_or_else(|_| {
+				die!("{}: Invalid address for --unlock. Must be 40 hex characters, without the 0x at the beginning.", address)
+			});
+			let duration = match parts.next() {
+				None => UnlockDuration::Permanent,
+				Some("once") => UnlockDuration::Once,
+				Some(secs) => UnlockDuration::Timed(Duration::from_secs(secs.parse().unwrap_or_else(|_| {
+					die!("{}: Invalid unlock duration for --unlock. Must be 'once' or a number of seconds.", secs)
+				}))),
+			};
+			(a, duration)
+		}).collect())
 	}
 
-	pub fn rpc_apis(&self) -> String {
-		self.args.flag_rpcapi.clone().unwrap_or(self.args.flag_jsonrpc_apis.clone())
+	pub fn rpc_apis(&self) -> ApiSet {
+		let apis = self.args.flag_rpcapi.clone().unwrap_or(self.args.flag_jsonrpc_apis.clone());
+		Self::parse_apis(&apis, "--jsonrpc-apis")
+	}
+
+	pub fn ipc_apis(&self) -> ApiSet {
+		let apis = self.args.flag_ipcapi.clone().unwrap_or(self.args.flag_ipc_apis.clone());
+		Self::parse_apis(&apis, "--ipc-apis")
+	}
+
+	pub fn ws_apis(&self) -> ApiSet {
+		Self::parse_apis(&self.args.flag_ws_apis, "--ws-apis")
 	}
 
 	pub fn rpc_cors(&self) -> Option<Vec<String>> {
 		let cors = self.args.flag_jsonrpc_cors.clone().or(self.args.flag_rpccorsdomain.clone());
-		cors.map(|c| c.split(',').map(|s| s.to_owned()).collect())
+		cors.map(|c| match c.trim() {
+			"none" => Vec::new(),
+			"all" => vec!["*".to_owned()],
+			c => Self::split_list(c),
+		})
 	}
 
 	pub fn rpc_hosts(&self) -> Option<Vec<String>> {
-		match self.args.flag_jsonrpc_hosts.as_ref() {
-			"none" => return Some(Vec::new()),
-			"all" => return None,
-			_ => {}
+		match self.args.flag_jsonrpc_hosts.trim() {
+			"none" => Some(Vec::new()),
+			"all" => None,
+			hosts => Some(Self::split_list(hosts)),
+		}
+	}
+
+	pub fn rpc_secret(&self) -> Option<String> {
+		self.args.flag_jsonrpc_secret.as_ref().map(|path| {
+			let path = self.replace_placeholders(path);
+			let mut secret = String::new();
+			File::open(&path).and_then(|mut file| file.read_to_string(&mut secret))
+				.unwrap_or_else(|_| die!("{} Unable to read JSON-RPC secret file. Ensure it exists and permissions are correct.", path));
+			match secret.trim() {
+				"" => die!("{}: The JSON-RPC secret file is empty.", path),
+				secret => secret.to_owned(),
+			}
+		})
+	}
+
+	pub fn rpc_tls(&self) -> Option<Tls> {
+		self.tls(&self.args.flag_jsonrpc_tls_cert, &self.args.flag_jsonrpc_tls_key)
+			.unwrap_or_else(|| die!("Both --jsonrpc-tls-cert and --jsonrpc-tls-key must be given to serve JSON-RPC over HTTPS."))
+	}
+
+	pub fn signer_tls(&self) -> Option<Tls> {
+		self.tls(&self.args.flag_signer_tls_cert, &self.args.flag_signer_tls_key)
+			.unwrap_or_else(|| die!("Both --signer-tls-cert and --signer-tls-key must be given to serve Trusted Signer over WSS."))
+	}
+
+	// `None` if only one of the certificate and the key is given.
+	fn tls(&self, certificate: &Option<String>, key: &Option<String>) -> Option<Option<Tls>> {
+		match (certificate, key) {
+			(&Some(ref certificate), &Some(ref key)) => Some(Some(Tls {
+				certificate: self.replace_placeholders(certificate),
+				key: self.replace_placeholders(key),
+			})),
+			(&None, &None) => Some(None),
+			_ => None,
 		}
-		let hosts = self.args.flag_jsonrpc_hosts.split(',').map(|h| h.into()).collect();
-		Some(hosts)
+	}
+
+	pub fn rpc_rate_limits(&self) -> Option<RateLimits> {
+		if self.args.flag_jsonrpc_rate_limit.is_none() && self.args.flag_jsonrpc_method_limits.is_none() {
+			return None;
+		}
+
+		let mut methods = HashMap::new();
+		if let Some(ref limits) = self.args.flag_jsonrpc_method_limits {
+			for limit in Self::split_list(limits) {
+				let mut parts = limit.splitn(2, '=');
+				let method = parts.next().unwrap_or("").trim().to_owned();
+				match parts.next().and_then(|calls| calls.trim().parse::<u32>().ok()) {
+					Some(calls) if !method.is_empty() => { methods.insert(method, calls); },
+					_ => die!("{}: Invalid method limit given for --jsonrpc-method-limits. Expected METHOD=CALLS.", limit),
+				}
+			}
+		}
+
+		Some(RateLimits {
+			calls_per_sec: self.args.flag_jsonrpc_rate_limit,
+			methods: methods,
+		})
+	}
+
+	fn split_list(list: &str) -> Vec<String> {
+		list.split(',').map(str::trim).filter(|s| !s.is_empty()).map(|s| s.to_owned()).collect()
 	}
 
 	fn geth_ipc_path(&self) -> String {
@@ -459,7 +690,25 @@ impl Configuration {
 		IpcConfiguration {
 			enabled: !(self.args.flag_ipcdisable || self.args.flag_ipc_off || self.args.flag_no_ipc),
 			socket_addr: self.ipc_path(),
-			apis: self.args.flag_ipcapi.clone().unwrap_or(self.args.flag_ipc_apis.clone()),
+			apis: self.ipc_apis(),
+			mode: self.ipc_mode(),
+			uid: self.args.flag_ipc_owner,
+			gid: self.args.flag_ipc_group,
+		}
+	}
+
+	fn ipc_mode(&self) -> Option<u32> {
+		self.args.flag_ipc_mode.as_ref().map(|mode| match u32::from_str_radix(mode, 8) {
+			Ok(bits) if bits <= 0o777 => bits,
+			_ => die!("{}: Invalid mode given for --ipc-mode. Must be octal permission bits, e.g. 660.", mode),
+		})
+	}
+
+	/// Client version string announced to peers, including the node identity if given.
+	pub fn client_version(&self) -> String {
+		match self.args.flag_identity.is_empty() {
+			true => ::util::misc::version(),
+			false => ::util::misc::version_with_identity(&self.args.flag_identity),
 		}
 	}
 
@@ -473,6 +722,8 @@ impl Configuration {
 			rpc_enabled: !self.args.flag_jsonrpc_off && !self.args.flag_no_jsonrpc,
 			rpc_interface: self.args.flag_rpcaddr.clone().unwrap_or(self.args.flag_jsonrpc_interface.clone()),
 			rpc_port: self.args.flag_rpcport.unwrap_or(self.args.flag_jsonrpc_port),
+			db_path: self.path(),
+			keys_path: self.keys_path(),
 		}
 	}
 
@@ -481,13 +732,18 @@ impl Configuration {
 			self.args.flag_datadir.as_ref().unwrap_or(&self.args.flag_db_path));
 		::std::fs::create_dir_all(&db_path).unwrap_or_else(|e| die_with_io_error("main", e));
 
-		let keys_path = Configuration::replace_home(
-			if self.args.flag_testnet {
-				"$HOME/.parity/testnet_keys"
-			} else {
-				&self.args.flag_keys_path
-			}
-		);
+		let keys_path = if self.args.flag_keys_shared {
+			Configuration::replace_home(
+				if self.args.flag_testnet {
+					"$HOME/.parity/testnet_keys"
+				} else {
+					&self.args.flag_keys_path
+				}
+			)
+		} else {
+			let keys_root = Configuration::replace_home(&self.args.flag_keys_path);
+			Path::new(&keys_root).join(self.keys_chain_dir()).to_string_lossy().into_owned()
+		};
 		::std::fs::create_dir_all(&keys_path).unwrap_or_else(|e| die_with_io_error("main", e));
 		let dapps_path = Configuration::replace_home(&self.args.flag_dapps_path);
 		::std::fs::create_dir_all(&dapps_path).unwrap_or_else(|e| die_with_io_error("main", e));
@@ -512,6 +768,31 @@ impl Configuration {
 		self.directories().keys
 	}
 
+	/// Name of the directory keeping the keys of the chain, unless keys are shared between chains.
+	fn keys_chain_dir(&self) -> String {
+		let chain = self.chain();
+		match chain.as_str() {
+			"frontier" | "homestead" | "mainnet" | "homestead-dogmatic" => "mainnet".to_owned(),
+			"morden" | "testnet" => "morden".to_owned(),
+			"olympic" => "olympic".to_owned(),
+			f => Path::new(f).file_stem().and_then(|s| s.to_str()).unwrap_or(f).to_owned(),
+		}
+	}
+
+	/// Directories holding keys of the chain being run from before keys were kept per chain.
+	/// The keys directory held mainnet keys and `testnet_keys` those of Morden; other chains
+	/// had no keys of their own.
+	pub fn legacy_keys_paths(&self) -> Vec<String> {
+		if self.args.flag_keys_shared {
+			return Vec::new();
+		}
+		match self.keys_chain_dir().as_str() {
+			"mainnet" => vec![Configuration::replace_home(&self.args.flag_keys_path)],
+			"morden" => vec![Configuration::replace_home("$HOME/.parity/testnet_keys")],
+			_ => Vec::new(),
+		}
+	}
+
 	pub fn path(&self) -> String {
 		self.directories().db
 	}
@@ -520,18 +801,82 @@ impl Configuration {
 		arg.replace("$HOME", env::home_dir().unwrap().to_str().unwrap())
 	}
 
+	/// Expands placeholders allowing multiple instances to share a single path template:
+	/// `%c` - chain name, `%p` - network port, `%r` - JSON-RPC port, `%i` - identity,
+	/// `%h` - home directory and `%%` - literal `%`.
+	fn replace_placeholders(&self, arg: &str) -> String {
+		let chain = self.chain();
+		let chain = Path::new(&chain).file_stem().and_then(|s| s.to_str()).unwrap_or(&chain).to_owned();
+		let mut result = String::with_capacity(arg.len());
+		let mut chars = arg.chars();
+		while let Some(c) = chars.next() {
+			if c != '%' {
+				result.push(c);
+				continue;
+			}
+			match chars.next() {
+				Some('c') => result.push_str(&chain),
+				Some('p') => result.push_str(&self.net_port().to_string()),
+				Some('r') => result.push_str(&self.args.flag_rpcport.unwrap_or(self.args.flag_jsonrpc_port).to_string()),
+				Some('i') => result.push_str(&self.args.flag_identity),
+				Some('h') => result.push_str(env::home_dir().unwrap().to_str().unwrap()),
+				Some('%') => result.push('%'),
+				Some(x) => die!("{}: Unknown placeholder '%{}'. Must be one of %c, %p, %r, %i, %h or %%.", arg, x),
+				None => die!("{}: Path must not end with a single '%'.", arg),
+			}
+		}
+		Configuration::replace_home(&result)
+	}
+
 	fn ipc_path(&self) -> String {
 		if self.args.flag_geth {
 			self.geth_ipc_path()
 		} else if cfg!(windows) {
-			r"\\.\pipe\parity.jsonrpc".to_owned()
+			format!(r"\\.\pipe\{}", self.replace_placeholders(&self.args.flag_ipc_pipe))
 		} else {
-			Configuration::replace_home(&self.args.flag_ipcpath.clone().unwrap_or(self.args.flag_ipc_path.clone()))
+			self.replace_placeholders(&self.args.flag_ipcpath.clone().unwrap_or(self.args.flag_ipc_path.clone()))
 		}
 	}
 
+	pub fn log_file(&self) -> Option<String> {
+		self.args.flag_log_file.as_ref().map(|f| self.replace_placeholders(f))
+	}
+
+	pub fn pid_file(&self) -> String {
+		self.replace_placeholders(&self.args.arg_pid_file)
+	}
+
+	/// Makes sure that none of the files created by this instance end up at the same path.
+	pub fn validate_paths(&self) -> Result<(), String> {
+		let mut paths = Vec::new();
+		if self.ipc_settings().enabled {
+			paths.push(("IPC socket", self.ipc_path()));
+		}
+		if let Some(file) = self.log_file() {
+			paths.push(("log file", file));
+		}
+		if self.args.cmd_daemon {
+			paths.push(("pid file", self.pid_file()));
+		}
+
+		for (i, &(name, ref path)) in paths.iter().enumerate() {
+			if let Some(&(other, _)) = paths[..i].iter().find(|&&(_, ref p)| p == path) {
+				return Err(format!("{}: The {} and the {} resolve to the same path.", path, other, name));
+			}
+		}
+		Ok(())
+	}
+
 	pub fn have_color(&self) -> bool {
-		!self.args.flag_no_color && !cfg!(windows)
+		self.log_settings().use_color()
+	}
+
+	pub fn informant_format(&self) -> InformantFormat {
+		match self.args.flag_log_format.as_str() {
+			"text" => InformantFormat::Text { color: self.have_color() },
+			"json" => InformantFormat::Json,
+			other => die!("{}: Invalid log format given. Expected text or json.", other),
+		}
 	}
 
 	pub fn signer_port(&self) -> Option<u16> {
@@ -578,11 +923,20 @@ impl Configuration {
 		if self.args.flag_no_color || cfg!(windows) {
 			settings = settings.no_color();
 		}
+		if self.args.flag_log_format == "json" {
+			settings = settings.json();
+		}
 		if let Some(ref init) = self.args.flag_logging {
 			settings = settings.init(init.to_owned())
 		}
-		if let Some(ref file) = self.args.flag_log_file {
-			settings = settings.file(file.to_owned())
+		if let Some(file) = self.log_file() {
+			settings = settings.file(file)
+		}
+		if let Some(ref allow) = self.args.flag_log_allow {
+			settings = settings.allow(parse_log_targets(allow).unwrap_or_else(|e| die!("{}", e)));
+		}
+		if let Some(ref deny) = self.args.flag_log_deny {
+			settings = settings.deny(deny.split(',').map(|t| t.trim().to_owned()).filter(|t| !t.is_empty()).collect());
 		}
 		settings
 	}
@@ -591,9 +945,24 @@ impl Configuration {
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use std::env;
+	use std::path::PathBuf;
+	use std::time::Duration;
+	use ethcore::account_provider::UnlockDuration;
+	use ethcore::miner::GasPricer;
+	use rpc_apis::{Api, ApiSet};
+	use std::collections::HashMap;
+	use ethcore_rpc::RateLimits;
 	use cli::USAGE;
 	use docopt::Docopt;
+	use util::{Address, U256};
+	use rpc_keys::Tls;
+	use std::fs::File;
+	use std::io::Write;
+	use devtools::RandomTempPath;
 	use util::network_settings::NetworkSettings;
+	use informant::Format as InformantFormat;
+	use scheduler::Task;
 
 	fn parse(args: &[&str]) -> Configuration {
 		Configuration {
@@ -601,6 +970,150 @@ mod tests {
 		}
 	}
 
+	#[test]
+	#[cfg(not(windows))]
+	fn should_replace_path_placeholders() {
+		// given
+		let home = env::home_dir().unwrap().to_str().unwrap().to_owned();
+
+		// when
+		let conf = parse(&["parity", "--chain", "morden", "--port", "30304", "--identity", "node1",
+			"--ipc-path", "%h/%c-%p-%r-%i.ipc", "--log-file", "$HOME/100%%.log"]);
+
+		// then
+		assert_eq!(conf.ipc_path(), format!("{}/morden-30304-8545-node1.ipc", home));
+		assert_eq!(conf.log_file(), Some(format!("{}/100%.log", home)));
+	}
+
+	#[test]
+	#[cfg(not(windows))]
+	fn should_detect_path_collisions() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity", "--ipc-path", "/tmp/%p", "--log-file", "/tmp/%p.log"]);
+		let conf1 = parse(&["parity", "--ipc-path", "/tmp/%p", "--log-file", "/tmp/30303"]);
+		let conf2 = parse(&["parity", "--no-ipc", "--ipc-path", "/tmp/%p", "--log-file", "/tmp/30303"]);
+
+		// then
+		assert_eq!(conf0.validate_paths(), Ok(()));
+		assert!(conf1.validate_paths().is_err());
+		assert_eq!(conf2.validate_paths(), Ok(()));
+	}
+
+	#[test]
+	fn should_disable_outbound_requests_when_offline() {
+		// given
+
+		// when
+		let conf = parse(&["parity", "--offline", "--ethstats", "node:secret@localhost:3000", "--notify-work", "http://localhost:3001"]);
+
+		// then
+		assert_eq!(conf.ethstats(), None);
+		assert_eq!(conf.work_notify(), Vec::<String>::new());
+	}
+
+	#[test]
+	fn should_use_fixed_gas_price_when_offline() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity", "--offline"]);
+		let conf1 = parse(&["parity", "--offline", "--offline-price", "20"]);
+		let conf2 = parse(&["parity", "--offline", "--usd-per-eth", "50"]);
+		let conf3 = parse(&["parity"]);
+
+		// then
+		let fixed = |p: GasPricer| match p {
+			GasPricer::Fixed(price) => Some(price),
+			GasPricer::Calibrated(_) => None,
+		};
+		let price0 = fixed(conf0.gas_pricer()).unwrap();
+		let price1 = fixed(conf1.gas_pricer()).unwrap();
+		let price2 = fixed(conf2.gas_pricer()).unwrap();
+		assert!(price0 > price1);
+		assert!(price1 > price2);
+		assert_eq!(fixed(conf3.gas_pricer()), None);
+	}
+
+	#[test]
+	fn should_separate_keys_per_chain() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity", "--keys-path", "/keys"]);
+		let conf1 = parse(&["parity", "--keys-path", "/keys", "--testnet"]);
+		let conf2 = parse(&["parity", "--keys-path", "/keys", "--chain", "/specs/private.json"]);
+		let conf3 = parse(&["parity", "--keys-path", "/keys", "--keys-shared"]);
+
+		// then
+		assert_eq!(conf0.keys_chain_dir(), "mainnet".to_owned());
+		assert_eq!(conf0.legacy_keys_paths(), vec!["/keys".to_owned()]);
+		assert_eq!(conf1.keys_chain_dir(), "morden".to_owned());
+		assert_eq!(conf1.legacy_keys_paths(), vec![Configuration::replace_home("$HOME/.parity/testnet_keys")]);
+		assert_eq!(conf2.keys_chain_dir(), "private".to_owned());
+		assert!(conf2.legacy_keys_paths().is_empty());
+		assert!(conf3.legacy_keys_paths().is_empty());
+	}
+
+	#[test]
+	fn should_not_move_mainnet_keys_to_other_chains() {
+		// given
+		let mainnet_keys = Configuration::replace_home("$HOME/.parity/keys");
+
+		// when
+		let conf0 = parse(&["parity", "--testnet"]);
+		let conf1 = parse(&["parity", "--chain", "/specs/custom.json"]);
+		let conf2 = parse(&["parity"]);
+
+		// then
+		assert!(!conf0.legacy_keys_paths().contains(&mainnet_keys));
+		assert!(!conf1.legacy_keys_paths().contains(&mainnet_keys));
+		assert_eq!(conf2.legacy_keys_paths(), vec![mainnet_keys]);
+	}
+
+	#[test]
+	fn should_parse_log_format() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity", "--no-color"]);
+		let conf1 = parse(&["parity", "--log-format", "json"]);
+
+		// then
+		assert_eq!(conf0.informant_format(), InformantFormat::Text { color: false });
+		assert_eq!(conf1.informant_format(), InformantFormat::Json);
+		assert!(!conf1.have_color());
+	}
+
+	#[test]
+	fn should_parse_import_log_path() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--import-log", "/tmp/import.csv"]);
+
+		// then
+		assert_eq!(conf0.import_log_path(), None);
+		assert_eq!(conf1.import_log_path(), Some(PathBuf::from("/tmp/import.csv")));
+	}
+
+	#[test]
+	fn should_parse_schedule() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--schedule", "snapshot=30 4; rotate-log=0 */6"]);
+
+		// then
+		assert_eq!(conf0.schedule(), None);
+		let tasks = conf1.schedule().unwrap();
+		assert_eq!(tasks.iter().map(|&(task, _)| task).collect::<Vec<_>>(), vec![Task::Snapshot, Task::RotateLog]);
+		assert!(tasks[0].1.matches(4, 30));
+	}
+
 	#[test]
 	fn should_parse_network_settings() {
 		// given
@@ -617,9 +1130,57 @@ mod tests {
 			rpc_enabled: true,
 			rpc_interface: "local".to_owned(),
 			rpc_port: 8545,
+			db_path: conf.path(),
+			keys_path: conf.keys_path(),
 		});
 	}
 
+	#[test]
+	fn should_include_identity_in_client_version() {
+		let conf = parse(&["parity", "--identity", "my node/1"]);
+		assert!(conf.client_version().starts_with("Parity/mynode1/v"));
+
+		let conf = parse(&["parity"]);
+		assert!(conf.client_version().starts_with("Parity/v"));
+	}
+
+	#[test]
+	fn should_parse_apis_per_transport() {
+		// given
+		let conf = parse(&["parity",
+						   "--jsonrpc-apis", "eth,net,web3",
+						   "--ipc-apis", "personal, ethcore_set",
+						   "--ws-apis", "signer"]);
+
+		// then
+		assert_eq!(conf.rpc_apis(), ApiSet::List(vec![Api::Eth, Api::Net, Api::Web3]));
+		assert_eq!(conf.ipc_settings().apis, ApiSet::List(vec![Api::Personal, Api::EthcoreSet]));
+		assert_eq!(conf.ws_apis(), ApiSet::List(vec![Api::Signer]));
+	}
+
+	#[test]
+	fn should_parse_ipc_permissions() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--ipc-mode", "660", "--ipc-owner", "1000", "--ipc-group", "100"]);
+
+		// then
+		let (ipc0, ipc1) = (conf0.ipc_settings(), conf1.ipc_settings());
+		assert_eq!((ipc0.mode, ipc0.uid, ipc0.gid), (None, None, None));
+		assert_eq!((ipc1.mode, ipc1.uid, ipc1.gid), (Some(0o660), Some(1000), Some(100)));
+	}
+
+	#[test]
+	fn should_expose_signer_api_over_ws_by_default() {
+		// given
+		let conf = parse(&["parity"]);
+
+		// then
+		assert_eq!(conf.ws_apis().names(), vec!["web3", "eth", "net", "ethcore", "personal", "traces", "rpc", "signer"]);
+	}
+
 	#[test]
 	fn should_parse_rpc_settings_with_geth_compatiblity() {
 		// given
@@ -629,7 +1190,7 @@ mod tests {
 			assert_eq!(net.rpc_interface, "all".to_owned());
 			assert_eq!(net.rpc_port, 8000);
 			assert_eq!(conf.rpc_cors(), Some(vec!["*".to_owned()]));
-			assert_eq!(conf.rpc_apis(), "web3,eth".to_owned());
+			assert_eq!(conf.rpc_apis(), ApiSet::List(vec![Api::Web3, Api::Eth]));
 		}
 
 		// when
@@ -668,6 +1229,102 @@ mod tests {
 		assert_eq!(conf3.rpc_hosts(), Some(vec!["ethcore.io".into(), "something.io".into()]));
 	}
 
+	#[test]
+	fn should_parse_rpc_rate_limits() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--jsonrpc-rate-limit", "20"]);
+		let conf2 = parse(&["parity", "--jsonrpc-method-limits", "eth_getLogs=2, trace_filter = 1"]);
+
+		// then
+		assert_eq!(conf0.rpc_rate_limits(), None);
+		assert_eq!(conf1.rpc_rate_limits(), Some(RateLimits { calls_per_sec: Some(20), methods: HashMap::new() }));
+		let mut methods = HashMap::new();
+		methods.insert("eth_getLogs".to_owned(), 2);
+		methods.insert("trace_filter".to_owned(), 1);
+		assert_eq!(conf2.rpc_rate_limits(), Some(RateLimits { calls_per_sec: None, methods: methods }));
+	}
+
+	#[test]
+	fn should_parse_rpc_gas_cap() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--rpc-gas-cap", "1000000"]);
+
+		// then
+		assert_eq!(conf0.rpc_gas_cap(), U256::from(50_000_000));
+		assert_eq!(conf1.rpc_gas_cap(), U256::from(1_000_000));
+	}
+
+	#[test]
+	fn should_read_rpc_secret_from_file() {
+		// given
+		let temp = RandomTempPath::new();
+		File::create(temp.as_path()).unwrap().write_all(b"  s3cret\n").unwrap();
+		let path = temp.as_str().to_owned();
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--jsonrpc-secret", &path]);
+
+		// then
+		assert_eq!(conf0.rpc_secret(), None);
+		assert_eq!(conf1.rpc_secret(), Some("s3cret".into()));
+	}
+
+	#[test]
+	fn should_parse_rpc_tls() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--jsonrpc-tls-cert", "/etc/parity/cert.pem", "--jsonrpc-tls-key", "/etc/parity/key.pem"]);
+
+		// then
+		assert_eq!(conf0.rpc_tls(), None);
+		assert_eq!(conf1.rpc_tls(), Some(Tls {
+			certificate: "/etc/parity/cert.pem".into(),
+			key: "/etc/parity/key.pem".into(),
+		}));
+		assert_eq!(conf1.signer_tls(), None);
+	}
+
+	#[test]
+	fn should_parse_signer_tls() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity", "--signer-tls-cert", "/etc/parity/cert.pem", "--signer-tls-key", "/etc/parity/key.pem"]);
+
+		// then
+		assert_eq!(conf0.signer_tls(), Some(Tls {
+			certificate: "/etc/parity/cert.pem".into(),
+			key: "/etc/parity/key.pem".into(),
+		}));
+		assert_eq!(conf0.rpc_tls(), None);
+	}
+
+	#[test]
+	fn should_parse_rpc_cors() {
+		// given
+
+		// when
+		let conf0 = parse(&["parity"]);
+		let conf1 = parse(&["parity", "--jsonrpc-cors", "none"]);
+		let conf2 = parse(&["parity", "--jsonrpc-cors", "all"]);
+		let conf3 = parse(&["parity", "--jsonrpc-cors", "http://ethcore.io, null,"]);
+
+		// then
+		assert_eq!(conf0.rpc_cors(), None);
+		assert_eq!(conf1.rpc_cors(), Some(Vec::new()));
+		assert_eq!(conf2.rpc_cors(), Some(vec!["*".to_owned()]));
+		assert_eq!(conf3.rpc_cors(), Some(vec!["http://ethcore.io".to_owned(), "null".to_owned()]));
+	}
+
 	#[test]
 	fn should_disable_signer_in_geth_compat() {
 		// given
@@ -681,6 +1338,21 @@ mod tests {
 		assert_eq!(conf1.signer_enabled(), true);
 	}
 
+	#[test]
+	fn should_parse_unlock_durations() {
+		// given
+
+		// when
+		let conf = parse(&["parity", "--unlock", "0x0000000000000000000000000000000000000001,0000000000000000000000000000000000000002:once,0x0000000000000000000000000000000000000003:300"]);
+
+		// then
+		assert_eq!(conf.unlocks(), vec![
+			(Address::from(1), UnlockDuration::Permanent),
+			(Address::from(2), UnlockDuration::Once),
+			(Address::from(3), UnlockDuration::Timed(Duration::from_secs(300))),
+		]);
+	}
+
 	#[test]
 	fn should_disable_signer_when_account_is_unlocked() {
 		// given
@@ -0,0 +1,263 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Startup self-test (`parity doctor`).
+
+use std::fs;
+use std::io;
+use std::net::{TcpListener, TcpStream, UdpSocket, SocketAddr, ToSocketAddrs};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use util::kvdb::Database;
+use util::Colour;
+use ethcore::client::{get_db_path, append_path};
+use ethcore::ethstore::dir::{DiskDirectory, KeyDirectory};
+use ethcore::spec::Spec;
+use configuration::Configuration;
+
+/// Minimal number of file descriptors which may be opened by the client.
+const MIN_FD_LIMIT: u64 = 1024;
+/// Minimal free disk space in the database directory.
+const MIN_DISK_SPACE: u64 = 4 * 1024 * 1024 * 1024;
+/// Maximal allowed difference between local clock and NTP time.
+const MAX_CLOCK_DRIFT: u64 = 10;
+/// NTP server used to measure clock drift.
+const NTP_SERVER: &'static str = "pool.ntp.org:123";
+/// Seconds between 1900 (NTP epoch) and 1970 (UNIX epoch).
+const NTP_UNIX_OFFSET: u64 = 2_208_988_800;
+/// Time allowed for each network check.
+const NETWORK_TIMEOUT: u64 = 5;
+
+/// Result of a single check: description of what was found or why it failed.
+pub type CheckResult = Result<String, String>;
+
+/// Runs all checks and prints the report. Returns `true` if all of them passed.
+pub fn execute(conf: &Configuration, spec: &Spec) -> bool {
+	let client_config = conf.client_config(spec);
+	let db_path = get_db_path(Path::new(&conf.path()), client_config.pruning, spec.genesis_header().hash());
+
+	let checks: Vec<(&'static str, CheckResult)> = vec![
+		("File descriptor limit", fd_limit()),
+		("Disk space", disk_space(&conf.path())),
+		("Clock drift", clock_drift()),
+		("Ports", ports(conf)),
+		("Database", database(&db_path)),
+		("Keystore", keystore(&conf.keys_path())),
+		("Bootnodes", bootnodes(&conf.init_nodes(spec))),
+	];
+
+	let mut passed = true;
+	for (name, result) in checks {
+		match result {
+			Ok(info) => println!("[{}] {}: {}", Colour::Green.bold().paint("PASS"), name, info),
+			Err(info) => {
+				passed = false;
+				println!("[{}] {}: {}", Colour::Red.bold().paint("FAIL"), name, info);
+			},
+		}
+	}
+	passed
+}
+
+#[cfg(not(windows))]
+fn fd_limit() -> CheckResult {
+	use libc;
+
+	let mut rlim = libc::rlimit { rlim_cur: 0, rlim_max: 0 };
+	if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut rlim) } != 0 {
+		return Err(format!("Unable to read the limit: {}", io::Error::last_os_error()));
+	}
+
+	// soft limit is raised to the hard one on startup
+	let limit = rlim.rlim_max as u64;
+	match limit >= MIN_FD_LIMIT {
+		true => Ok(format!("{} (hard), {} (soft)", limit, rlim.rlim_cur)),
+		false => Err(format!("{} is below the recommended minimum of {}. Raise it with `ulimit -n` or in /etc/security/limits.conf.", limit, MIN_FD_LIMIT)),
+	}
+}
+
+#[cfg(windows)]
+fn fd_limit() -> CheckResult {
+	Ok("Not applicable on this platform".into())
+}
+
+#[cfg(not(windows))]
+fn disk_space(path: &str) -> CheckResult {
+	use std::ffi::CString;
+	use std::mem;
+	use libc;
+
+	let c_path = try!(CString::new(path).map_err(|_| format!("{}: Invalid path", path)));
+	let mut stat: libc::statvfs = unsafe { mem::zeroed() };
+	if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+		return Err(format!("{}: Unable to read free space: {}", path, io::Error::last_os_error()));
+	}
+
+	let available = stat.f_bavail as u64 * stat.f_frsize as u64;
+	let gib = |bytes: u64| format!("{:.1} GiB", bytes as f64 / (1024 * 1024 * 1024) as f64);
+	match available >= MIN_DISK_SPACE {
+		true => Ok(format!("{} available in {}", gib(available), path)),
+		false => Err(format!("Only {} available in {}, at least {} recommended", gib(available), path, gib(MIN_DISK_SPACE))),
+	}
+}
+
+#[cfg(windows)]
+fn disk_space(_path: &str) -> CheckResult {
+	Ok("Not checked on this platform".into())
+}
+
+/// Queries NTP server for the current time (SNTP, RFC 4330) and compares it with the local clock.
+fn clock_drift() -> CheckResult {
+	let query = || -> io::Result<u64> {
+		let socket = try!(UdpSocket::bind("0.0.0.0:0"));
+		try!(socket.set_read_timeout(Some(Duration::from_secs(NETWORK_TIMEOUT))));
+		let mut packet = [0u8; 48];
+		// leap indicator 0, version 3, client mode
+		packet[0] = 0x1b;
+		try!(socket.send_to(&packet, NTP_SERVER));
+		let (len, _) = try!(socket.recv_from(&mut packet));
+		if len < 48 {
+			return Err(io::Error::new(io::ErrorKind::InvalidData, "Response too short"));
+		}
+		let seconds = packet[40..44].iter().fold(0u64, |acc, b| (acc << 8) | *b as u64);
+		Ok(seconds.saturating_sub(NTP_UNIX_OFFSET))
+	};
+
+	let ntp = try!(query().map_err(|e| format!("Unable to query {}: {}", NTP_SERVER, e)));
+	let local = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+	let drift = if ntp > local { ntp - local } else { local - ntp };
+	match drift <= MAX_CLOCK_DRIFT {
+		true => Ok(format!("{}s", drift)),
+		false => Err(format!("Local clock is {}s off. Peers reject messages with skewed timestamps, please synchronise your clock.", drift)),
+	}
+}
+
+fn ports(conf: &Configuration) -> CheckResult {
+	let mut tcp = Vec::new();
+	let (listen, _) = conf.net_addresses();
+	if let Some(listen) = listen {
+		tcp.push(("network", listen));
+		if let Err(e) = UdpSocket::bind(listen) {
+			return Err(format!("Discovery port {} (UDP) is not available: {}", listen.port(), e));
+		}
+	}
+	let settings = conf.network_settings();
+	if settings.rpc_enabled {
+		tcp.push(("JSON-RPC", try!(socket_addr(&conf.rpc_interface(), settings.rpc_port))));
+	}
+	if conf.dapps_enabled() {
+		tcp.push(("Dapps", try!(socket_addr(&conf.dapps_interface(), conf.args.flag_dapps_port))));
+	}
+	if let Some(port) = conf.signer_port() {
+		tcp.push(("Signer", try!(socket_addr("127.0.0.1", port))));
+	}
+
+	for &(name, addr) in &tcp {
+		if let Err(e) = TcpListener::bind(addr) {
+			return Err(format!("{} port {} is not available: {}", name, addr, e));
+		}
+	}
+	Ok(tcp.iter().map(|&(name, addr)| format!("{} {}", name, addr.port())).collect::<Vec<_>>().join(", "))
+}
+
+fn socket_addr(interface: &str, port: u16) -> Result<SocketAddr, String> {
+	try!((interface, port).to_socket_addrs().map_err(|e| format!("{}: Invalid interface: {}", interface, e)))
+		.next()
+		.ok_or_else(|| format!("{}: Invalid interface", interface))
+}
+
+fn database(db_path: &Path) -> CheckResult {
+	let mut opened = Vec::new();
+	for name in &["blocks", "extras", "state", "tracedb"] {
+		let path = append_path(db_path, name);
+		if fs::metadata(&path).is_err() {
+			continue;
+		}
+		// fails if the database is corrupted or locked by a running instance
+		try!(Database::open_default(&path).map_err(|e| format!("{}: Unable to open: {}", path, e)));
+		opened.push(*name);
+	}
+	match opened.is_empty() {
+		true => Ok(format!("Not created yet in {}", db_path.display())),
+		false => Ok(format!("Opened {} in {}", opened.join(", "), db_path.display())),
+	}
+}
+
+fn keystore(keys_path: &str) -> CheckResult {
+	let dir = DiskDirectory::at(keys_path);
+	match dir.load() {
+		Ok(accounts) => Ok(format!("{} account(s) in {}", accounts.len(), keys_path)),
+		Err(e) => Err(format!("{}: Unable to read keys: {:?}", keys_path, e)),
+	}
+}
+
+/// Extracts address from an enode URL (`enode://<id>@<ip>:<port>[?discport=<port>]`).
+fn enode_address(enode: &str) -> Option<&str> {
+	enode.split('@').nth(1).and_then(|addr| addr.split('?').next())
+}
+
+fn bootnodes(nodes: &[String]) -> CheckResult {
+	if nodes.is_empty() {
+		return Ok("No bootnodes configured".into());
+	}
+
+	let (tx, rx) = mpsc::channel();
+	for node in nodes {
+		let tx = tx.clone();
+		let address = enode_address(node).map(str::to_owned);
+		thread::spawn(move || {
+			let reachable = address.map_or(false, |address| TcpStream::connect(address.as_str()).is_ok());
+			let _ = tx.send(reachable);
+		});
+	}
+	drop(tx);
+
+	// connecting threads may hang for a long time, so don't wait for them longer than necessary
+	let deadline = Instant::now() + Duration::from_secs(NETWORK_TIMEOUT);
+	let mut reachable = 0;
+	let mut answered = 0;
+	while answered < nodes.len() && Instant::now() < deadline {
+		match rx.try_recv() {
+			Ok(ok) => {
+				answered += 1;
+				if ok {
+					reachable += 1;
+				}
+			},
+			Err(mpsc::TryRecvError::Empty) => thread::sleep(Duration::from_millis(50)),
+			Err(mpsc::TryRecvError::Disconnected) => break,
+		}
+	}
+
+	match reachable {
+		0 => Err(format!("None of {} bootnodes is reachable. Check your firewall and internet connection.", nodes.len())),
+		n => Ok(format!("{} of {} reachable", n, nodes.len())),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::enode_address;
+
+	#[test]
+	fn should_extract_enode_address() {
+		assert_eq!(enode_address("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@52.16.188.185:30303"), Some("52.16.188.185:30303"));
+		assert_eq!(enode_address("enode://a979fb5754@10.0.0.1:30303?discport=30301"), Some("10.0.0.1:30303"));
+		assert_eq!(enode_address("10.0.0.1:30303"), None);
+	}
+}
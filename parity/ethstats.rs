@@ -0,0 +1,342 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reporting of node and chain statistics to an ethstats server.
+
+use std::env;
+use std::thread;
+use std::str::FromStr;
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
+use rustc_serialize::json::{Json, ToJson};
+use ws;
+use util::{Mutex, U256, Uint, H256, version};
+use ethcore::client::{BlockChainClient, BlockID, ChainNotify, Client};
+use ethcore::miner::{Miner, MinerService, ExternalMiner, ExternalMinerService};
+use ethcore::views::BlockView;
+use ethsync::SyncProvider;
+
+/// How often node statistics are reported.
+const REPORT_INTERVAL_SEC: u64 = 15;
+/// How long to wait before reconnecting after the connection was lost.
+const RECONNECT_DELAY_SEC: u64 = 10;
+/// Version of the ethstats reporting protocol we speak.
+const PROTOCOL_VERSION: &'static str = "0.1.1";
+
+/// Ethstats server to report to.
+#[derive(Debug, PartialEq)]
+pub struct Configuration {
+	/// Name of the node shown on the dashboard.
+	pub name: String,
+	/// Secret required by the server.
+	pub secret: String,
+	/// Server host.
+	pub host: String,
+	/// Server port.
+	pub port: u16,
+}
+
+impl FromStr for Configuration {
+	type Err = String;
+
+	/// Parses `name:secret@host:port`.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let invalid = || format!("Invalid ethstats server: {}. Expected name:secret@host:port.", s);
+
+		let (credentials, server) = match s.rfind('@') {
+			Some(i) => (&s[..i], &s[i + 1..]),
+			None => return Err(invalid()),
+		};
+		let (name, secret) = match credentials.find(':') {
+			Some(i) => (&credentials[..i], &credentials[i + 1..]),
+			None => return Err(invalid()),
+		};
+		let (host, port) = match server.rfind(':') {
+			Some(i) => (&server[..i], &server[i + 1..]),
+			None => return Err(invalid()),
+		};
+		let port = try!(port.parse().map_err(|_| invalid()));
+		if name.is_empty() || host.is_empty() {
+			return Err(invalid());
+		}
+
+		Ok(Configuration {
+			name: name.to_owned(),
+			secret: secret.to_owned(),
+			host: host.to_owned(),
+			port: port,
+		})
+	}
+}
+
+impl Configuration {
+	fn url(&self) -> String {
+		format!("ws://{}:{}/api", self.host, self.port)
+	}
+}
+
+/// Sources of the reported statistics.
+pub struct Dependencies {
+	pub client: Arc<Client>,
+	pub sync: Arc<SyncProvider>,
+	pub miner: Arc<Miner>,
+	pub external_miner: Arc<ExternalMiner>,
+	pub network_port: u16,
+}
+
+/// Ethstats reporter. Reports new blocks as they are imported and the node statistics periodically.
+pub struct Ethstats {
+	config: Configuration,
+	deps: Dependencies,
+	out: Mutex<Option<ws::Sender>>,
+	ping_sent: Mutex<Option<Instant>>,
+}
+
+/// Starts reporting to the server. Reporting stops once the returned reporter is dropped.
+pub fn start(config: Configuration, deps: Dependencies) -> Arc<Ethstats> {
+	let ethstats = Arc::new(Ethstats {
+		config: config,
+		deps: deps,
+		out: Mutex::new(None),
+		ping_sent: Mutex::new(None),
+	});
+
+	let weak = Arc::downgrade(&ethstats);
+	thread::Builder::new().name("ethstats".into()).spawn(move || loop {
+		let url = match weak.upgrade() {
+			Some(ethstats) => ethstats.config.url(),
+			None => break,
+		};
+		let session = weak.clone();
+		if let Err(e) = ws::connect(url, move |out| Session { out: out, ethstats: session.clone() }) {
+			warn!(target: "ethstats", "Error connecting to ethstats server: {}", e);
+		}
+		thread::sleep(Duration::from_secs(RECONNECT_DELAY_SEC));
+	}).expect("Error creating ethstats thread");
+
+	let weak = Arc::downgrade(&ethstats);
+	thread::Builder::new().name("ethstats-report".into()).spawn(move || loop {
+		thread::sleep(Duration::from_secs(REPORT_INTERVAL_SEC));
+		match weak.upgrade() {
+			Some(ethstats) => ethstats.report_periodic(),
+			None => break,
+		}
+	}).expect("Error creating ethstats thread");
+
+	ethstats
+}
+
+fn object(fields: Vec<(&str, Json)>) -> Json {
+	Json::Object(fields.into_iter().map(|(k, v)| (k.to_owned(), v)).collect())
+}
+
+fn hex(hash: &H256) -> Json {
+	format!("0x{:?}", hash).to_json()
+}
+
+/// Builds a message emitting `event` on behalf of the node `id`.
+fn message(id: &str, event: &str, mut fields: Vec<(&str, Json)>) -> String {
+	fields.push(("id", id.to_json()));
+	object(vec![("emit", Json::Array(vec![event.to_json(), object(fields)]))]).to_string()
+}
+
+/// Returns the name of the event emitted by a server message.
+fn event_name(message: &str) -> Option<String> {
+	Json::from_str(message).ok()
+		.and_then(|json| json.find("emit").and_then(|emit| emit.as_array()).and_then(|emit| emit.get(0)).and_then(|e| e.as_string()).map(|e| e.to_owned()))
+}
+
+impl Ethstats {
+	fn emit(&self, event: &str, fields: Vec<(&str, Json)>) {
+		if let Some(ref out) = *self.out.lock() {
+			if let Err(e) = out.send(message(&self.config.name, event, fields)) {
+				debug!(target: "ethstats", "Error sending {} report: {}", event, e);
+			}
+		}
+	}
+
+	fn report_hello(&self) {
+		let status = self.deps.sync.status();
+		let info = object(vec![
+			("name", self.config.name.to_json()),
+			("node", version().to_json()),
+			("port", (self.deps.network_port as u64).to_json()),
+			("net", format!("{}", status.network_id).to_json()),
+			("protocol", format!("eth/{}", status.protocol_version).to_json()),
+			("api", "No".to_json()),
+			("os", env::consts::OS.to_json()),
+			("os_v", env::consts::ARCH.to_json()),
+			("client", PROTOCOL_VERSION.to_json()),
+			("canUpdateHistory", false.to_json()),
+		]);
+		self.emit("hello", vec![("secret", self.config.secret.to_json()), ("info", info)]);
+	}
+
+	fn report_block(&self) {
+		let client = &self.deps.client;
+		let best = client.chain_info().best_block_hash;
+		let bytes = match client.block(BlockID::Hash(best.clone())) {
+			Some(bytes) => bytes,
+			None => return,
+		};
+		let view = BlockView::new(&bytes);
+		let header = view.header_view();
+		let total_difficulty = client.block_total_difficulty(BlockID::Hash(best)).unwrap_or_else(U256::zero);
+
+		let block = object(vec![
+			("number", header.number().to_json()),
+			("hash", hex(&header.hash())),
+			("parentHash", hex(&header.parent_hash())),
+			("timestamp", header.timestamp().to_json()),
+			("miner", format!("0x{:?}", header.author()).to_json()),
+			("gasUsed", header.gas_used().low_u64().to_json()),
+			("gasLimit", header.gas_limit().low_u64().to_json()),
+			("difficulty", format!("{}", header.difficulty()).to_json()),
+			("totalDifficulty", format!("{}", total_difficulty).to_json()),
+			("transactions", Json::Array(view.transaction_hashes().iter().map(|h| object(vec![("hash", hex(h))])).collect())),
+			("transactionsRoot", hex(&header.transactions_root())),
+			("stateRoot", hex(&header.state_root())),
+			("uncles", Json::Array(view.uncle_hashes().iter().map(hex).collect())),
+		]);
+		self.emit("block", vec![("block", block)]);
+	}
+
+	fn report_pending(&self) {
+		let pending = self.deps.miner.status().transactions_in_pending_queue as u64;
+		self.emit("pending", vec![("stats", object(vec![("pending", pending.to_json())]))]);
+	}
+
+	fn report_stats(&self) {
+		let status = self.deps.sync.status();
+		let stats = object(vec![
+			("active", true.to_json()),
+			("syncing", status.is_major_syncing().to_json()),
+			("mining", self.deps.external_miner.is_mining().to_json()),
+			("hashrate", self.deps.external_miner.hashrate().low_u64().to_json()),
+			("peers", (status.num_peers as u64).to_json()),
+			("gasPrice", format!("{}", self.deps.miner.sensible_gas_price()).to_json()),
+			("uptime", 100u64.to_json()),
+		]);
+		self.emit("stats", vec![("stats", stats)]);
+	}
+
+	fn ping(&self) {
+		let now = ::time::get_time();
+		*self.ping_sent.lock() = Some(Instant::now());
+		let client_time = now.sec as u64 * 1000 + now.nsec as u64 / 1_000_000;
+		self.emit("node-ping", vec![("clientTime", format!("{}", client_time).to_json())]);
+	}
+
+	fn report_latency(&self) {
+		let sent = match self.ping_sent.lock().take() {
+			Some(sent) => sent,
+			None => return,
+		};
+		// The ping travelled to the server and back.
+		let elapsed = sent.elapsed();
+		let latency = (elapsed.as_secs() * 1000 + elapsed.subsec_nanos() as u64 / 1_000_000) / 2;
+		self.emit("latency", vec![("latency", latency.to_json())]);
+	}
+
+	fn report_periodic(&self) {
+		self.report_stats();
+		self.report_pending();
+		self.ping();
+	}
+}
+
+impl ChainNotify for Ethstats {
+	fn new_blocks(&self, _imported: Vec<H256>, _invalid: Vec<H256>, enacted: Vec<H256>, _retracted: Vec<H256>, _sealed: Vec<H256>, _duration: u64) {
+		if !enacted.is_empty() {
+			self.report_block();
+			self.report_pending();
+		}
+	}
+}
+
+impl Drop for Ethstats {
+	fn drop(&mut self) {
+		if let Some(out) = self.out.lock().take() {
+			let _ = out.close(ws::CloseCode::Normal);
+		}
+	}
+}
+
+/// Connection to the ethstats server.
+struct Session {
+	out: ws::Sender,
+	ethstats: Weak<Ethstats>,
+}
+
+impl ws::Handler for Session {
+	fn on_open(&mut self, _shake: ws::Handshake) -> ws::Result<()> {
+		match self.ethstats.upgrade() {
+			Some(ethstats) => {
+				info!(target: "ethstats", "Connected to ethstats server at {}", ethstats.config.url());
+				*ethstats.out.lock() = Some(self.out.clone());
+				ethstats.report_hello();
+				ethstats.report_block();
+				ethstats.report_periodic();
+				Ok(())
+			},
+			None => self.out.close(ws::CloseCode::Normal),
+		}
+	}
+
+	fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
+		let text = try!(msg.as_text());
+		if let Some(ethstats) = self.ethstats.upgrade() {
+			if let Some("node-pong") = event_name(text).as_ref().map(|e| e.as_str()) {
+				ethstats.report_latency();
+			}
+		}
+		Ok(())
+	}
+
+	fn on_close(&mut self, code: ws::CloseCode, reason: &str) {
+		debug!(target: "ethstats", "Connection to ethstats server closed: {:?} {}", code, reason);
+		if let Some(ethstats) = self.ethstats.upgrade() {
+			*ethstats.out.lock() = None;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_serialize::json::ToJson;
+	use super::{Configuration, message, event_name};
+
+	#[test]
+	fn should_parse_configuration() {
+		assert_eq!("node:pass@stats.example.com:3000".parse(), Ok(Configuration {
+			name: "node".into(),
+			secret: "pass".into(),
+			host: "stats.example.com".into(),
+			port: 3000,
+		}));
+		assert!("node@stats.example.com:3000".parse::<Configuration>().is_err());
+		assert!("node:pass@stats.example.com".parse::<Configuration>().is_err());
+		assert!(":pass@stats.example.com:3000".parse::<Configuration>().is_err());
+	}
+
+	#[test]
+	fn should_build_messages() {
+		let msg = message("node", "pending", vec![("stats", 5u64.to_json())]);
+		assert_eq!(msg, r#"{"emit":["pending",{"id":"node","stats":5}]}"#);
+		assert_eq!(event_name(&msg), Some("pending".into()));
+		assert_eq!(event_name("{}"), None);
+	}
+}
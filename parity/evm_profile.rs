@@ -0,0 +1,126 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Periodic dump of the EVM profile gathered with `--profile-evm` to a JSON file.
+
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
+use serde_json;
+use serde_json::builder::ObjectBuilder;
+use serde_json::Value;
+use ethcore::client::{BlockChainClient, Client, ContractProfile};
+
+const DUMP_INTERVAL_SEC: u64 = 60;
+
+/// Writes the profile to a file every minute and once more when dropped.
+pub struct ProfileDump {
+	path: PathBuf,
+	client: Arc<Client>,
+}
+
+/// Starts dumping the profile of `client` to the file at `path`.
+pub fn start(path: PathBuf, client: Arc<Client>) -> Result<Arc<ProfileDump>, String> {
+	info!(target: "evm", "Writing EVM profile to {}", path.display());
+
+	let dump = Arc::new(ProfileDump {
+		path: path,
+		client: client,
+	});
+
+	let weak: Weak<ProfileDump> = Arc::downgrade(&dump);
+	try!(thread::Builder::new().name("evm-profile".into()).spawn(move || loop {
+		thread::sleep(Duration::from_secs(DUMP_INTERVAL_SEC));
+		match weak.upgrade() {
+			Some(dump) => dump.write(),
+			None => break,
+		}
+	}).map_err(|e| format!("Error creating EVM profile thread: {}", e)));
+
+	Ok(dump)
+}
+
+impl ProfileDump {
+	/// Writes the current profile, replacing the previous one.
+	pub fn write(&self) {
+		let profile = match self.client.evm_profile() {
+			Some(profile) => profile,
+			None => return,
+		};
+		if let Err(e) = write_json(&self.path, &to_json(profile)) {
+			warn!(target: "evm", "Cannot write EVM profile to {}: {}", self.path.display(), e);
+		}
+	}
+}
+
+impl Drop for ProfileDump {
+	fn drop(&mut self) {
+		self.write();
+	}
+}
+
+fn to_json(profile: Vec<ContractProfile>) -> Value {
+	let contracts: Vec<Value> = profile.into_iter().map(|contract| {
+		let instructions: Vec<Value> = contract.instructions.into_iter().map(|instruction| {
+			ObjectBuilder::new()
+				.insert("name", instruction.name)
+				.insert("count", instruction.count)
+				.insert("gasUsed", format!("{}", instruction.gas))
+				.build()
+		}).collect();
+		ObjectBuilder::new()
+			.insert("address", format!("0x{:?}", contract.address))
+			.insert("gasUsed", format!("{}", contract.gas))
+			.insert("instructions", instructions)
+			.build()
+	}).collect();
+	Value::Array(contracts)
+}
+
+// write through a temporary file so readers never see a partial profile.
+fn write_json(path: &Path, json: &Value) -> io::Result<()> {
+	let tmp = path.with_extension("json.tmp");
+	{
+		let mut file = try!(File::create(&tmp));
+		let text = try!(serde_json::to_string_pretty(json).map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{}", e))));
+		try!(file.write_all(text.as_bytes()));
+	}
+	fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+	use util::{Address, U256};
+	use ethcore::client::{ContractProfile, InstructionProfile};
+	use serde_json;
+	use super::to_json;
+
+	#[test]
+	fn profile_to_json() {
+		let profile = vec![ContractProfile {
+			address: Address::from(5),
+			gas: U256::from(20003),
+			instructions: vec![InstructionProfile { name: "SSTORE".into(), count: 1, gas: U256::from(20003) }],
+		}];
+		assert_eq!(
+			serde_json::to_string(&to_json(profile)).unwrap(),
+			r#"[{"address":"0x0000000000000000000000000000000000000005","gasUsed":"20003","instructions":[{"count":1,"gasUsed":"20003","name":"SSTORE"}]}]"#
+		);
+	}
+}
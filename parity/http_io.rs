@@ -0,0 +1,96 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Blocking HTTP/1.1 plumbing shared by the servers serving a connection per thread.
+
+use std::cell::Cell;
+use std::io::{self, BufRead, Read, Write};
+use std::net::TcpStream;
+use std::rc::Rc;
+use std::time::Instant;
+
+/// A socket failing reads past the deadline, however slowly the data trickles in.
+pub struct Deadline {
+	stream: TcpStream,
+	until: Rc<Cell<Instant>>,
+}
+
+impl Deadline {
+	/// Wraps the socket. The deadline may be moved through `until`.
+	pub fn new(stream: TcpStream, until: Rc<Cell<Instant>>) -> Self {
+		Deadline {
+			stream: stream,
+			until: until,
+		}
+	}
+}
+
+impl Read for Deadline {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		let now = Instant::now();
+		if now >= self.until.get() {
+			return Err(io::Error::new(io::ErrorKind::TimedOut, "Request not received in time"));
+		}
+		try!(self.stream.set_read_timeout(Some(self.until.get() - now)));
+		self.stream.read(buf)
+	}
+}
+
+impl Write for Deadline {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		self.stream.write(buf)
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		self.stream.flush()
+	}
+}
+
+/// Reads a line into `line`, at most `left` bytes, which are deducted from it.
+/// Returns false if the line doesn't end within the limit.
+pub fn read_line<R: BufRead>(reader: &mut R, line: &mut String, left: &mut usize) -> io::Result<bool> {
+	line.clear();
+	let read = try!(reader.take(*left as u64).read_line(line));
+	*left -= read;
+	Ok(line.ends_with('\n') || *left > 0)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::cell::Cell;
+	use std::io::{ErrorKind, Read, Write};
+	use std::net::{TcpListener, TcpStream};
+	use std::rc::Rc;
+	use std::time::{Duration, Instant};
+	use super::Deadline;
+
+	#[test]
+	fn fails_reads_past_deadline() {
+		let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+		let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+		let (stream, _) = listener.accept().unwrap();
+		let until = Rc::new(Cell::new(Instant::now() + Duration::from_secs(5)));
+		let mut stream = Deadline::new(stream, until.clone());
+		let mut buf = [0u8; 4];
+
+		client.write_all(b"GET ").unwrap();
+		assert_eq!(stream.read(&mut buf).unwrap(), 4);
+
+		until.set(Instant::now());
+		client.write_all(b"/metrics").unwrap();
+		assert_eq!(stream.read(&mut buf).unwrap_err().kind(), ErrorKind::TimedOut);
+	}
+}
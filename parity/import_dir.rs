@@ -0,0 +1,197 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Import of blocks dropped into a watched directory.
+//!
+//! Every file placed in the directory is read as a sequence of blocks, either binary RLP
+//! or hex with a block per line (the formats of `parity export`), and imported.
+//! Files are then moved to the `imported` subdirectory, or to `failed` if any of their
+//! blocks couldn't be imported. Files starting with a dot are ignored, so a writer can
+//! create a file under a hidden name and rename it once complete.
+
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::from_utf8;
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
+use rustc_serialize::hex::FromHex;
+use util::{Bytes, PayloadInfo};
+use ethcore::client::{BlockChainClient, BlockImportError, Client};
+use ethcore::error::ImportError;
+
+const POLL_INTERVAL_SEC: u64 = 5;
+const IMPORTED_DIR: &'static str = "imported";
+const FAILED_DIR: &'static str = "failed";
+
+/// Watches a directory for block files. Watching stops once dropped.
+pub struct ImportDir {
+	path: PathBuf,
+	client: Arc<Client>,
+}
+
+/// Starts watching the directory at `path`, creating it if needed.
+pub fn start(path: PathBuf, client: Arc<Client>) -> Result<Arc<ImportDir>, String> {
+	for dir in &[path.clone(), path.join(IMPORTED_DIR), path.join(FAILED_DIR)] {
+		try!(fs::create_dir_all(dir).map_err(|e| format!("Cannot create directory {}: {}", dir.display(), e)));
+	}
+	info!(target: "import", "Watching {} for blocks to import", path.display());
+
+	let import_dir = Arc::new(ImportDir {
+		path: path,
+		client: client,
+	});
+
+	let weak: Weak<ImportDir> = Arc::downgrade(&import_dir);
+	try!(thread::Builder::new().name("import-dir".into()).spawn(move || loop {
+		match weak.upgrade() {
+			Some(import_dir) => import_dir.poll(),
+			None => break,
+		}
+		thread::sleep(Duration::from_secs(POLL_INTERVAL_SEC));
+	}).map_err(|e| format!("Error creating import thread: {}", e)));
+
+	Ok(import_dir)
+}
+
+impl ImportDir {
+	/// Imports all files currently in the directory, in the order of their names.
+	pub fn poll(&self) {
+		let mut files = match pending_files(&self.path) {
+			Ok(files) => files,
+			Err(e) => {
+				warn!(target: "import", "Cannot read directory {}: {}", self.path.display(), e);
+				return;
+			}
+		};
+		files.sort();
+
+		for file in files {
+			let result = read_blocks(&file).and_then(|blocks| self.import(blocks));
+			let dir = match result {
+				Ok(count) => {
+					info!(target: "import", "Imported {} blocks from {}", count, file.display());
+					IMPORTED_DIR
+				},
+				Err(e) => {
+					warn!(target: "import", "Cannot import blocks from {}: {}", file.display(), e);
+					FAILED_DIR
+				}
+			};
+			if let Err(e) = archive(&file, &self.path.join(dir)) {
+				warn!(target: "import", "Cannot move {} to {}: {}", file.display(), dir, e);
+			}
+		}
+	}
+
+	fn import(&self, blocks: Vec<Bytes>) -> Result<usize, String> {
+		let count = blocks.len();
+		for block in blocks {
+			while self.client.queue_info().is_full() { thread::sleep(Duration::from_secs(1)); }
+			match self.client.import_block(block) {
+				Ok(_) => {},
+				Err(BlockImportError::Import(ImportError::AlreadyInChain)) => { trace!(target: "import", "Skipping block already in chain."); },
+				Err(BlockImportError::Import(ImportError::AlreadyQueued)) => { trace!(target: "import", "Skipping block already queued."); },
+				Err(e) => return Err(format!("{:?}", e)),
+			}
+		}
+		Ok(count)
+	}
+}
+
+// regular, non-hidden files in the directory.
+fn pending_files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+	let mut files = Vec::new();
+	for entry in try!(fs::read_dir(dir)) {
+		let entry = try!(entry);
+		if !try!(entry.file_type()).is_file() {
+			continue;
+		}
+		let hidden = entry.file_name().to_str().map_or(true, |name| name.starts_with('.'));
+		if !hidden {
+			files.push(entry.path());
+		}
+	}
+	Ok(files)
+}
+
+fn read_blocks(file: &Path) -> Result<Vec<Bytes>, String> {
+	let mut bytes = Vec::new();
+	try!(File::open(file).and_then(|mut f| f.read_to_end(&mut bytes)).map_err(|e| format!("{}", e)));
+	split_blocks(&bytes)
+}
+
+/// Splits the contents of a block file into blocks. Binary RLP is told from hex
+/// by the first byte, like `parity import` does.
+pub fn split_blocks(bytes: &[u8]) -> Result<Vec<Bytes>, String> {
+	let mut blocks = Vec::new();
+	match bytes.first() {
+		None => {},
+		Some(&0xf9) => {
+			let mut offset = 0;
+			while offset < bytes.len() {
+				let size = try!(PayloadInfo::from(&bytes[offset..]).map_err(|e| format!("Invalid RLP: {:?}", e))).total();
+				if offset + size > bytes.len() {
+					return Err("Truncated block RLP".into());
+				}
+				blocks.push(bytes[offset..offset + size].to_vec());
+				offset += size;
+			}
+		},
+		Some(_) => {
+			let text = try!(from_utf8(bytes).map_err(|_| "Invalid hex".to_owned()));
+			for line in text.lines().map(|l| l.trim()).filter(|l| !l.is_empty()) {
+				blocks.push(try!(line.from_hex().map_err(|_| "Invalid hex".to_owned())));
+			}
+		}
+	}
+	Ok(blocks)
+}
+
+fn archive(file: &Path, dir: &Path) -> io::Result<()> {
+	match file.file_name() {
+		Some(name) => fs::rename(file, dir.join(name)),
+		None => Ok(()),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::split_blocks;
+
+	#[test]
+	fn splits_binary_blocks() {
+		let mut first = vec![0xf9, 0x00, 0x03];
+		first.extend_from_slice(&[0x01, 0x02, 0x03]);
+		let mut second = vec![0xf9, 0x00, 0x01];
+		second.push(0x04);
+
+		let mut bytes = first.clone();
+		bytes.extend_from_slice(&second);
+		assert_eq!(split_blocks(&bytes), Ok(vec![first, second]));
+
+		assert!(split_blocks(&bytes[..bytes.len() - 1]).is_err());
+	}
+
+	#[test]
+	fn splits_hex_blocks() {
+		let text = b"c20102\n\nc103\r\n";
+		assert_eq!(split_blocks(text), Ok(vec![vec![0xc2, 0x01, 0x02], vec![0xc1, 0x03]]));
+		assert!(split_blocks(b"zz").is_err());
+		assert_eq!(split_blocks(b""), Ok(vec![]));
+	}
+}
@@ -0,0 +1,94 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Logging of block import performance to a CSV file.
+//!
+//! Every imported block appends a row with its number, transaction count,
+//! gas used, import time and size. Blocks are imported in batches, so the import time is the
+//! time of the whole batch divided evenly among its blocks.
+
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Weak};
+use util::{H256, Mutex, U256};
+use ethcore::client::{BlockChainClient, BlockID, ChainNotify, Client};
+use ethcore::views::BlockView;
+
+const HEADER: &'static str = "block,transactions,gas,import_ms,size\n";
+
+/// Appends import statistics of new blocks to a file.
+pub struct ImportLog {
+	client: Weak<Client>,
+	file: Mutex<File>,
+}
+
+/// Starts logging to the file at `path`. Rows are appended to an existing file.
+pub fn start(path: &Path, client: &Arc<Client>) -> Result<Arc<ImportLog>, String> {
+	let mut file = try!(OpenOptions::new().create(true).append(true).open(path)
+		.map_err(|e| format!("Cannot open import log {}: {}", path.display(), e)));
+	let empty = try!(file.metadata().map_err(|e| format!("{}: {}", path.display(), e))).len() == 0;
+	if empty {
+		try!(file.write_all(HEADER.as_bytes()).map_err(|e| format!("Cannot write import log {}: {}", path.display(), e)));
+	}
+	info!("Logging block import performance to {}", path.display());
+
+	Ok(Arc::new(ImportLog {
+		client: Arc::downgrade(client),
+		file: Mutex::new(file),
+	}))
+}
+
+impl ChainNotify for ImportLog {
+	fn new_blocks(&self, imported: Vec<H256>, _invalid: Vec<H256>, _enacted: Vec<H256>, _retracted: Vec<H256>, _sealed: Vec<H256>, duration: u64) {
+		let client = match self.client.upgrade() {
+			Some(client) => client,
+			None => return,
+		};
+		if imported.is_empty() {
+			return;
+		}
+		let import_ms = duration as f64 / 1_000_000f64 / imported.len() as f64;
+
+		let mut rows = String::new();
+		for hash in imported {
+			if let Some(block) = client.block(BlockID::Hash(hash)) {
+				let view = BlockView::new(&block);
+				let header = view.header();
+				rows.push_str(&row(header.number(), view.transactions_count(), &header.gas_used, import_ms, block.len()));
+			}
+		}
+		if let Err(e) = self.file.lock().write_all(rows.as_bytes()) {
+			warn!("Cannot write import log: {}", e);
+		}
+	}
+}
+
+// a single CSV row.
+fn row(number: u64, transactions: usize, gas: &U256, import_ms: f64, size: usize) -> String {
+	format!("{},{},{},{:.3},{}\n", number, transactions, gas, import_ms, size)
+}
+
+#[cfg(test)]
+mod tests {
+	use util::U256;
+	use super::row;
+
+	#[test]
+	fn formats_rows() {
+		assert_eq!(row(1000, 3, &U256::from(63000), 1.23456, 1500), "1000,3,63000,1.235,1500\n");
+	}
+}
@@ -15,31 +15,57 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 extern crate ansi_term;
-use self::ansi_term::Colour::{White, Yellow, Green, Cyan, Blue};
+use self::ansi_term::Colour::{White, Yellow, Green, Cyan, Blue, Red};
 use self::ansi_term::Style;
 
 use std::sync::{Arc};
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use std::time::{Instant, Duration};
 use std::ops::{Deref, DerefMut};
-use isatty::{stdout_isatty};
-use ethsync::{SyncProvider, ManageNetwork};
+use ethsync::{SyncProvider, ManageNetwork, SyncState};
 use util::{Uint, RwLock, Mutex, H256, Colour};
 use ethcore::client::*;
-use ethcore::views::BlockView;
-use number_prefix::{binary_prefix, Standalone, Prefixed};
+use ethcore::views::{BlockView, HeaderView};
+use ethcore::header::BlockNumber;
+use ethcore::miner::{Miner, MinerService, ExternalMiner, ExternalMinerService};
+use number_prefix::{binary_prefix, decimal_prefix, Standalone, Prefixed};
+use serde_json::builder::ObjectBuilder;
+
+/// Time over which the import rate is measured for estimating time left to sync.
+const SYNC_WINDOW_SEC: u64 = 120;
+
+/// Minimal time between samples of the size of the databases on disk.
+const DISK_USAGE_INTERVAL_SEC: u64 = 60;
+
+/// Format of the informant's output.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+	/// Human-readable text, optionally colored.
+	Text {
+		color: bool,
+	},
+	/// A JSON object per line.
+	Json,
+}
 
 pub struct Informant {
 	chain_info: RwLock<Option<BlockChainInfo>>,
 	cache_info: RwLock<Option<BlockChainCacheSize>>,
 	report: RwLock<Option<ClientReport>>,
 	last_tick: RwLock<Instant>,
-	with_color: bool,
+	format: Format,
 	client: Arc<Client>,
 	sync: Option<Arc<SyncProvider>>,
 	net: Option<Arc<ManageNetwork>>,
+	miner: Option<Arc<Miner>>,
+	external_miner: Option<Arc<ExternalMiner>>,
+	sealed: AtomicUsize,
+	reorgs: AtomicUsize,
 	last_import: Mutex<Instant>,
 	skipped: AtomicUsize,
+	sync_progress: Mutex<SyncProgress>,
+	disk_usage: Mutex<Option<(Instant, DiskUsage)>>,
 }
 
 trait MillisecondDuration {
@@ -53,19 +79,32 @@ impl MillisecondDuration for Duration {
 }
 
 impl Informant {
-	/// Make a new instance potentially `with_color` output.
-	pub fn new(client: Arc<Client>, sync: Option<Arc<SyncProvider>>, net: Option<Arc<ManageNetwork>>, with_color: bool) -> Self {
+	/// Make a new instance writing output in given format. Mining is reported if `miner` is given.
+	pub fn new(
+		client: Arc<Client>,
+		sync: Option<Arc<SyncProvider>>,
+		net: Option<Arc<ManageNetwork>>,
+		miner: Option<Arc<Miner>>,
+		external_miner: Option<Arc<ExternalMiner>>,
+		format: Format
+	) -> Self {
 		Informant {
 			chain_info: RwLock::new(None),
 			cache_info: RwLock::new(None),
 			report: RwLock::new(None),
 			last_tick: RwLock::new(Instant::now()),
-			with_color: with_color,
+			format: format,
 			client: client,
 			sync: sync,
 			net: net,
+			miner: miner,
+			external_miner: external_miner,
+			sealed: AtomicUsize::new(0),
+			reorgs: AtomicUsize::new(0),
 			last_import: Mutex::new(Instant::now()),
 			skipped: AtomicUsize::new(0),
+			sync_progress: Mutex::new(SyncProgress::new()),
+			disk_usage: Mutex::new(None),
 		}
 	}
 
@@ -76,6 +115,84 @@ impl Informant {
 		}
 	}
 
+	fn format_hashrate(h: u64) -> String {
+		match decimal_prefix(h as f64) {
+			Standalone(h)       => format!("{} H/s", h),
+			Prefixed(prefix, n) => format!("{:.2} {}H/s", n, prefix),
+		}
+	}
+
+	// hashrate submitted by external miners, blocks sealed and pending transactions, while sealing.
+	fn mining_status(&self) -> Option<(u64, usize, usize)> {
+		let miner = match self.miner {
+			Some(ref miner) => miner,
+			None => return None,
+		};
+		let hashrate = self.external_miner.as_ref().map_or(0, |m| m.hashrate().low_u64());
+		let mining = miner.is_sealing() || self.external_miner.as_ref().map_or(false, |m| m.is_mining());
+		match mining {
+			true => Some((hashrate, self.sealed.load(AtomicOrdering::Relaxed), miner.status().transactions_in_pending_queue)),
+			false => None,
+		}
+	}
+
+	// size of the databases on disk, sampled at most every `DISK_USAGE_INTERVAL_SEC`.
+	fn disk_usage(&self) -> DiskUsage {
+		let mut last = self.disk_usage.lock();
+		let stale = last.as_ref().map_or(true, |&(ref at, _)| at.elapsed() >= Duration::from_secs(DISK_USAGE_INTERVAL_SEC));
+		if stale {
+			*last = Some((Instant::now(), self.client.disk_usage()));
+		}
+		last.as_ref().map_or_else(DiskUsage::default, |&(_, ref usage)| usage.clone())
+	}
+
+	// paints the text if the output is colored.
+	fn paint(&self, c: Style, t: String) -> String {
+		match self.format {
+			Format::Text { color: true } => format!("{}", c.paint(t)),
+			_ => t,
+		}
+	}
+
+	// log a reorganisation replacing `retracted` canonical blocks.
+	fn report_reorg(&self, retracted: &[H256]) {
+		let total = self.reorgs.fetch_add(1, AtomicOrdering::Relaxed) + 1;
+		// the common ancestor is the parent of the oldest retracted block.
+		let ancestor = retracted.iter()
+			.filter_map(|hash| self.client.block_header(BlockID::Hash(hash.clone())))
+			.map(|header| {
+				let view = HeaderView::new(&header);
+				(view.number() - 1, view.parent_hash())
+			})
+			.min_by_key(|&(number, _)| number);
+		let (ancestor_number, ancestor_hash) = match ancestor {
+			Some(ancestor) => ancestor,
+			None => return,
+		};
+		let best = self.client.chain_info();
+
+		if self.format == Format::Json {
+			warn!(target: "import", "{}", ObjectBuilder::new()
+				.insert("event", "reorg")
+				.insert("depth", retracted.len())
+				.insert("ancestor", ancestor_number)
+				.insert("ancestorHash", format!("0x{:?}", ancestor_hash))
+				.insert("best", best.best_block_number)
+				.insert("bestHash", format!("0x{:?}", best.best_block_hash))
+				.insert("total", total)
+				.build()
+			);
+		} else {
+			warn!(target: "import", "Chain reorganisation: {} block(s) retracted back to #{} {}, new best block #{} {} ({} reorganisation(s) so far)",
+				self.paint(Red.bold(), format!("{}", retracted.len())),
+				ancestor_number,
+				ancestor_hash,
+				self.paint(White.bold(), format!("{}", best.best_block_number)),
+				self.paint(White.bold(), format!("{}", best.best_block_hash)),
+				total
+			);
+		}
+	}
 
 	#[cfg_attr(feature="dev", allow(match_bool))]
 	pub fn tick(&self) {
@@ -92,19 +209,105 @@ impl Informant {
 
 		let importing = queue_info.unverified_queue_size + queue_info.verified_queue_size > 3
 			|| self.sync.as_ref().map_or(false, |s| s.status().is_major_syncing());
-		if !importing && elapsed < Duration::from_secs(30) {
+		let snapshot_progress = self.client.snapshot_progress();
+		if !importing && snapshot_progress.is_none() && elapsed < Duration::from_secs(30) {
 			return;
 		}
 
 		*self.last_tick.write() = Instant::now();
 
+		let disk_usage = self.disk_usage();
+		let mining_status = self.mining_status();
+
+		// percentage of the chain synced and estimated time left.
+		let sync_eta = match sync_status {
+			Some(ref sync_info) if sync_info.is_major_syncing() => {
+				let current = sync_info.last_imported_block_number.unwrap_or(chain_info.best_block_number);
+				let mut progress = self.sync_progress.lock();
+				progress.note(Instant::now(), current);
+				sync_info.highest_block_number.map(|highest| (sync_percentage(current, highest), progress.eta(highest)))
+			},
+			_ => {
+				self.sync_progress.lock().clear();
+				None
+			},
+		};
+
 		let mut write_report = self.report.write();
 		let report = self.client.report();
 
-		let paint = |c: Style, t: String| match self.with_color && stdout_isatty() {
-			true => format!("{}", c.paint(t)),
-			false => t,
-		};
+		if self.format == Format::Json {
+			let last_report = match write_report.deref() { &Some(ref last_report) => last_report.clone(), _ => ClientReport::default() };
+			let status = ObjectBuilder::new()
+				.insert("event", "status")
+				.insert("block", chain_info.best_block_number)
+				.insert("hash", format!("0x{:?}", chain_info.best_block_hash))
+				.insert("importing", importing)
+				.insert("blocksPerSec", ((report.blocks_imported - last_report.blocks_imported) * 1000) as u64 / elapsed.as_milliseconds())
+				.insert("txPerSec", ((report.transactions_applied - last_report.transactions_applied) * 1000) as u64 / elapsed.as_milliseconds())
+				.insert("mgasPerSec", ((report.gas_processed - last_report.gas_processed) / From::from(elapsed.as_milliseconds() * 1000)).low_u64())
+				.insert("unverifiedQueue", queue_info.unverified_queue_size)
+				.insert("verifiedQueue", queue_info.verified_queue_size)
+				.insert("stateDbMem", report.state_db_mem)
+				.insert("chainCacheMem", cache_info.total())
+				.insert("queueMem", queue_info.mem_used)
+				.insert_object("disk", |disk| disk
+					.insert("state", disk_usage.state)
+					.insert("blocks", disk_usage.blocks)
+					.insert("traces", disk_usage.traces)
+				);
+			let status = match (&sync_status, &network_config) {
+				(&Some(ref sync_info), &Some(ref net_config)) => status
+					.insert("syncBlock", sync_info.last_imported_block_number.unwrap_or(chain_info.best_block_number))
+					.insert("activePeers", sync_info.num_active_peers)
+					.insert("peers", sync_info.num_peers)
+					.insert("idealPeers", net_config.ideal_peers)
+					.insert("syncMem", sync_info.mem_used),
+				_ => status,
+			};
+			let status = match sync_eta {
+				Some((percentage, Some(eta))) => status
+					.insert("syncPercent", percentage)
+					.insert("syncEtaSec", eta.as_secs()),
+				Some((percentage, None)) => status.insert("syncPercent", percentage),
+				None => status,
+			};
+			let status = match snapshot_progress {
+				Some((ref number, ref progress)) => status.insert_object("snapshot", |snapshot| snapshot
+					.insert("block", *number)
+					.insert("accounts", progress.accounts())
+					.insert("blocks", progress.blocks())
+					.insert("size", progress.size())
+				),
+				None => status,
+			};
+			info!(target: "import", "{}", status.build());
+			if let Some((hashrate, sealed, pending)) = mining_status {
+				info!(target: "miner", "{}", ObjectBuilder::new()
+					.insert("event", "mining")
+					.insert("hashrate", hashrate)
+					.insert("sealed", sealed)
+					.insert("pending", pending)
+					.build()
+				);
+			}
+
+			*self.chain_info.write().deref_mut() = Some(chain_info);
+			*self.cache_info.write().deref_mut() = Some(cache_info);
+			*write_report.deref_mut() = Some(report);
+			return;
+		}
+
+		let paint = |c: Style, t: String| self.paint(c, t);
+
+		if let Some((number, progress)) = snapshot_progress {
+			info!(target: "snapshot", "Snapshot {}   {} accounts {} blocks {} written",
+				paint(White.bold(), format!("#{}", number)),
+				paint(Yellow.bold(), format!("{}", progress.accounts())),
+				paint(Yellow.bold(), format!("{}", progress.blocks())),
+				paint(Blue.bold(), Informant::format_bytes(progress.size()))
+			);
+		}
 
 		info!(target: "import", "{}   {}   {}",
 			match importing {
@@ -126,9 +329,20 @@ impl Informant {
 			},
 			match (&sync_status, &network_config) {
 				(&Some(ref sync_info), &Some(ref net_config)) => format!("{}{}/{}/{} peers",
-					match importing {
-						true => format!("{}   ", paint(Green.bold(), format!("{:>8}", format!("#{}", sync_info.last_imported_block_number.unwrap_or(chain_info.best_block_number))))),
-						false => String::new(),
+					match (importing, sync_info.state) {
+						(_, SyncState::SnapshotData) => format!("Snapshot {}   ", paint(Green.bold(), format!("{}/{}", sync_info.snapshot_chunks_done, sync_info.num_snapshot_chunks))),
+						(_, SyncState::SnapshotWaiting) => "Restoring snapshot   ".to_owned(),
+						(true, _) => format!("{}{}   ",
+							paint(Green.bold(), format!("{:>8}", format!("#{}", sync_info.last_imported_block_number.unwrap_or(chain_info.best_block_number)))),
+							match sync_eta {
+								Some((percentage, eta)) => format!(" {}{}",
+									paint(Green.bold(), format!("{:5.1}%", percentage)),
+									eta.map_or(String::new(), |eta| format!(" ETA {}", paint(Green.bold(), format_eta(eta))))
+								),
+								None => String::new(),
+							}
+						),
+						(false, _) => String::new(),
 					},
 					paint(Cyan.bold(), format!("{:2}", sync_info.num_active_peers)),
 					paint(Cyan.bold(), format!("{:2}", sync_info.num_peers)),
@@ -136,25 +350,95 @@ impl Informant {
 				),
 				_ => String::new(),
 			},
-			format!("{} db {} chain {} queue{}",
+			format!("{} db {} chain {} queue{}   {} state {} blocks {} traces on disk",
 				paint(Blue.bold(), format!("{:>8}", Informant::format_bytes(report.state_db_mem))),
 				paint(Blue.bold(), format!("{:>8}", Informant::format_bytes(cache_info.total()))),
 				paint(Blue.bold(), format!("{:>8}", Informant::format_bytes(queue_info.mem_used))),
 				match sync_status {
 					Some(ref sync_info) => format!(" {} sync", paint(Blue.bold(), format!("{:>8}", Informant::format_bytes(sync_info.mem_used)))),
 					_ => String::new(),
-				}
+				},
+				paint(Blue.bold(), format!("{:>8}", Informant::format_bytes(disk_usage.state as usize))),
+				paint(Blue.bold(), format!("{:>8}", Informant::format_bytes(disk_usage.blocks as usize))),
+				paint(Blue.bold(), format!("{:>8}", Informant::format_bytes(disk_usage.traces as usize)))
 			)
 		);
 
+		if let Some((hashrate, sealed, pending)) = mining_status {
+			info!(target: "miner", "Mining   {}   {} blocks sealed   {} pending txs",
+				paint(Yellow.bold(), Informant::format_hashrate(hashrate)),
+				paint(White.bold(), format!("{}", sealed)),
+				paint(Green.bold(), format!("{}", pending))
+			);
+		}
+
 		*self.chain_info.write().deref_mut() = Some(chain_info);
 		*self.cache_info.write().deref_mut() = Some(cache_info);
 		*write_report.deref_mut() = Some(report);
 	}
 }
 
+/// Imported block numbers over the last `SYNC_WINDOW_SEC` seconds.
+struct SyncProgress {
+	samples: VecDeque<(Instant, BlockNumber)>,
+}
+
+impl SyncProgress {
+	fn new() -> Self {
+		SyncProgress {
+			samples: VecDeque::new(),
+		}
+	}
+
+	/// Note the best block imported at given time.
+	fn note(&mut self, at: Instant, block: BlockNumber) {
+		self.samples.push_back((at, block));
+		while self.samples.len() > 2 && at.duration_since(self.samples[0].0) > Duration::from_secs(SYNC_WINDOW_SEC) {
+			self.samples.pop_front();
+		}
+	}
+
+	fn clear(&mut self) {
+		self.samples.clear();
+	}
+
+	/// Time left to import blocks up to `highest` at the rate they were imported within the window.
+	fn eta(&self, highest: BlockNumber) -> Option<Duration> {
+		let (&(first_at, first), &(last_at, last)) = match (self.samples.front(), self.samples.back()) {
+			(Some(first), Some(last)) => (first, last),
+			_ => return None,
+		};
+		let elapsed = last_at.duration_since(first_at).as_milliseconds();
+		if last <= first || elapsed == 0 {
+			return None;
+		}
+		let left = highest.saturating_sub(last);
+		Some(Duration::from_millis(left * elapsed / (last - first)))
+	}
+}
+
+fn sync_percentage(current: BlockNumber, highest: BlockNumber) -> f64 {
+	match highest {
+		0 => 100.0,
+		_ => (current.min(highest) * 1000 / highest) as f64 / 10.0,
+	}
+}
+
+fn format_eta(eta: Duration) -> String {
+	let secs = eta.as_secs();
+	match (secs / 3600, secs / 60 % 60, secs % 60) {
+		(0, 0, s) => format!("{}s", s),
+		(0, m, s) => format!("{}m {:02}s", m, s),
+		(h, m, _) => format!("{}h {:02}m", h, m),
+	}
+}
+
 impl ChainNotify for Informant {
-	fn new_blocks(&self, _imported: Vec<H256>, _invalid: Vec<H256>, enacted: Vec<H256>, _retracted: Vec<H256>, _sealed: Vec<H256>, duration: u64) {
+	fn new_blocks(&self, _imported: Vec<H256>, _invalid: Vec<H256>, enacted: Vec<H256>, retracted: Vec<H256>, sealed: Vec<H256>, duration: u64) {
+		self.sealed.fetch_add(sealed.len(), AtomicOrdering::Relaxed);
+		if !retracted.is_empty() {
+			self.report_reorg(&retracted);
+		}
 		let mut last_import = self.last_import.lock();
 		if Instant::now() > *last_import + Duration::from_secs(1) {
 			let queue_info = self.client.queue_info();
@@ -167,15 +451,29 @@ impl ChainNotify for Informant {
 					let tx_count = view.transactions_count();
 					let size = block.len();
 					let skipped = self.skipped.load(AtomicOrdering::Relaxed);
-					info!(target: "import", "Imported {} {} ({} txs, {} Mgas, {} ms, {} KiB){}",
-					Colour::White.bold().paint(format!("#{}", header.number())),
-					Colour::White.bold().paint(format!("{}", header.hash())),
-					Colour::Yellow.bold().paint(format!("{}", tx_count)),
-					Colour::Yellow.bold().paint(format!("{:.2}", header.gas_used.low_u64() as f32 / 1000000f32)),
-					Colour::Purple.bold().paint(format!("{:.2}", duration as f32 / 1000000f32)),
-					Colour::Blue.bold().paint(format!("{:.2}", size as f32 / 1024f32)),
-					if skipped > 0 { format!(" + another {} block(s)", Colour::Red.bold().paint(format!("{}", skipped))) } else { String::new() }
-					);
+					if self.format == Format::Json {
+						info!(target: "import", "{}", ObjectBuilder::new()
+							.insert("event", "import")
+							.insert("block", header.number())
+							.insert("hash", format!("0x{:?}", header.hash()))
+							.insert("transactions", tx_count)
+							.insert("gasUsed", header.gas_used.low_u64())
+							.insert("durationNs", duration)
+							.insert("size", size)
+							.insert("skipped", skipped)
+							.build()
+						);
+					} else {
+						info!(target: "import", "Imported {} {} ({} txs, {} Mgas, {} ms, {} KiB){}",
+						Colour::White.bold().paint(format!("#{}", header.number())),
+						Colour::White.bold().paint(format!("{}", header.hash())),
+						Colour::Yellow.bold().paint(format!("{}", tx_count)),
+						Colour::Yellow.bold().paint(format!("{:.2}", header.gas_used.low_u64() as f32 / 1000000f32)),
+						Colour::Purple.bold().paint(format!("{:.2}", duration as f32 / 1000000f32)),
+						Colour::Blue.bold().paint(format!("{:.2}", size as f32 / 1024f32)),
+						if skipped > 0 { format!(" + another {} block(s)", Colour::Red.bold().paint(format!("{}", skipped))) } else { String::new() }
+						);
+					}
 					*last_import = Instant::now();
 				}
 			}
@@ -186,3 +484,44 @@ impl ChainNotify for Informant {
 	}
 }
 
+
+#[cfg(test)]
+mod tests {
+	use std::time::{Instant, Duration};
+	use super::{Informant, SyncProgress, sync_percentage, format_eta};
+
+	#[test]
+	fn estimates_time_left_to_sync() {
+		let start = Instant::now();
+		let mut progress = SyncProgress::new();
+		progress.note(start, 1000);
+		assert_eq!(progress.eta(2000), None);
+
+		progress.note(start + Duration::from_secs(10), 1100);
+		assert_eq!(progress.eta(2000), Some(Duration::from_secs(90)));
+
+		// samples older than the window are dropped.
+		progress.note(start + Duration::from_secs(200), 1300);
+		progress.note(start + Duration::from_secs(210), 1500);
+		assert_eq!(progress.eta(2000), Some(Duration::from_secs(25)));
+
+		progress.clear();
+		assert_eq!(progress.eta(2000), None);
+	}
+
+	#[test]
+	fn formats_sync_progress() {
+		assert_eq!(sync_percentage(1500, 2000), 75.0);
+		assert_eq!(sync_percentage(2500, 2000), 100.0);
+		assert_eq!(sync_percentage(0, 0), 100.0);
+		assert_eq!(format_eta(Duration::from_secs(42)), "42s".to_owned());
+		assert_eq!(format_eta(Duration::from_secs(312)), "5m 12s".to_owned());
+		assert_eq!(format_eta(Duration::from_secs(3900)), "1h 05m".to_owned());
+	}
+
+	#[test]
+	fn formats_hashrate() {
+		assert_eq!(Informant::format_hashrate(999), "999 H/s".to_owned());
+		assert_eq!(Informant::format_hashrate(1500000), "1.50 MH/s".to_owned());
+	}
+}
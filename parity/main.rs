@@ -25,6 +25,7 @@
 extern crate docopt;
 extern crate num_cpus;
 extern crate rustc_serialize;
+extern crate serde_json;
 extern crate ethcore_util as util;
 extern crate ethcore;
 extern crate ethsync;
@@ -35,6 +36,8 @@ extern crate ctrlc;
 extern crate fdlimit;
 #[cfg(not(windows))]
 extern crate daemonize;
+#[cfg(not(windows))]
+extern crate libc;
 extern crate time;
 extern crate number_prefix;
 extern crate rpassword;
@@ -47,18 +50,24 @@ extern crate json_ipc_server as jsonipc;
 
 extern crate ethcore_ipc_hypervisor as hypervisor;
 extern crate ethcore_rpc;
+extern crate jsonrpc_core;
 
 extern crate ethcore_signer;
 extern crate ansi_term;
 #[macro_use]
 extern crate lazy_static;
 extern crate regex;
+extern crate flate2;
+extern crate openssl;
 extern crate ethcore_logger;
-extern crate isatty;
+extern crate ws;
 
 #[cfg(feature = "dapps")]
 extern crate ethcore_dapps;
 
+#[cfg(test)]
+extern crate ethcore_devtools as devtools;
+
 
 #[macro_use]
 mod die;
@@ -74,6 +83,20 @@ mod signer;
 mod rpc_apis;
 mod url;
 mod modules;
+mod activity;
+mod state_export;
+mod testnet;
+mod benchmark;
+mod doctor;
+mod ethstats;
+mod trace_stream;
+mod import_dir;
+mod import_log;
+mod metrics;
+mod evm_profile;
+mod scheduler;
+mod rpc_keys;
+mod http_io;
 
 use std::io::{Write, Read, BufReader, BufRead};
 use std::ops::Deref;
@@ -85,9 +108,9 @@ use std::thread::sleep;
 use std::time::Duration;
 use rustc_serialize::hex::FromHex;
 use ctrlc::CtrlC;
-use util::{H256, ToPretty, PayloadInfo, Bytes, Colour, version, journaldb, RotatingLogger};
+use util::{H256, Address, clean_0x, ToPretty, PayloadInfo, Bytes, Colour, version, journaldb, RotatingLogger};
 use util::panics::{MayPanic, ForwardPanic, PanicHandler};
-use ethcore::client::{BlockID, BlockChainClient, ClientConfig, get_db_path, BlockImportError, Mode};
+use ethcore::client::{BlockID, BlockChainClient, Client, ClientConfig, get_db_path, BlockImportError, Mode};
 use ethcore::error::{ImportError};
 use ethcore::service::ClientService;
 use ethcore::spec::Spec;
@@ -103,6 +126,7 @@ use ethcore::client::ChainNotify;
 use die::*;
 use cli::print_version;
 use rpc::RpcServer;
+use ethcore_rpc::{ExitHandle, ExitRequest, ApiKeys, AuditLog};
 use signer::{SignerServer, new_token};
 use dapps::WebappServer;
 use io_handler::ClientIoHandler;
@@ -119,12 +143,44 @@ fn execute(conf: Configuration) {
 		return;
 	}
 
+	::util::sha3::set_backend(conf.keccak_backend()).unwrap_or_else(|e| die!("{}", e));
+
+	if conf.args.cmd_benchmark {
+		benchmark::crypto(conf.args.flag_bench_iterations);
+		return;
+	}
+
 	if conf.args.cmd_signer {
 		execute_signer(conf);
 		return;
 	}
 
+	if conf.args.cmd_snapshot && conf.args.cmd_verify {
+		execute_snapshot_verify(conf);
+		return;
+	}
+
+	if conf.args.cmd_state && conf.args.cmd_verify {
+		execute_state_verify(conf);
+		return;
+	}
+
+	conf.validate_paths().unwrap_or_else(|e| die!("{}", e));
+
+	if conf.args.cmd_testnet {
+		execute_testnet(conf);
+		return;
+	}
+
 	let spec = conf.spec();
+
+	if conf.args.cmd_doctor {
+		if !doctor::execute(&conf, &spec) {
+			::std::process::exit(1);
+		}
+		return;
+	}
+
 	let client_config = conf.client_config(&spec);
 
 	execute_upgrades(&conf, &spec, &client_config);
@@ -150,24 +206,62 @@ fn execute(conf: Configuration) {
 		return;
 	}
 
+	if conf.args.cmd_export && conf.args.cmd_activity {
+		execute_export_activity(conf, spec, panic_handler);
+		return;
+	}
+
+	if conf.args.cmd_export && conf.args.cmd_state {
+		execute_export_state(conf, spec, panic_handler);
+		return;
+	}
+
+	if conf.args.cmd_trace && conf.args.cmd_reindex {
+		execute_trace_reindex(conf, spec, panic_handler);
+		return;
+	}
+
 	if conf.args.cmd_export {
-		execute_export(conf, panic_handler);
+		execute_export(conf, spec, panic_handler);
 		return;
 	}
 
 	if conf.args.cmd_import {
-		execute_import(conf, panic_handler);
+		execute_import(conf, spec, panic_handler);
 		return;
 	}
 
-	execute_client(conf, spec, client_config, panic_handler, logger);
+	if let Some(ExitRequest::Restart) = execute_client(conf, spec, client_config, panic_handler, logger) {
+		restart();
+	}
+}
+
+#[cfg(not(windows))]
+fn restart() {
+	use std::os::unix::process::CommandExt;
+	let err = restart_command().exec();
+	die!("Unable to restart: {}", err);
+}
+
+#[cfg(windows)]
+fn restart() {
+	restart_command().spawn().unwrap_or_else(|e| die!("Unable to restart: {}", e));
+}
+
+// The same executable with the same arguments.
+fn restart_command() -> ::std::process::Command {
+	let exe = ::std::env::current_exe().unwrap_or_else(|e| die!("Unable to restart: {}", e));
+	info!("Restarting {}", exe.display());
+	let mut command = ::std::process::Command::new(exe);
+	command.args(&::std::env::args_os().skip(1).collect::<Vec<_>>());
+	command
 }
 
 #[cfg(not(windows))]
 fn daemonize(conf: &Configuration) {
 	use daemonize::Daemonize;
 	Daemonize::new()
-			.pid_file(conf.args.arg_pid_file.clone())
+			.pid_file(conf.pid_file())
 			.chown_pid_file(true)
 			.start()
 			.unwrap_or_else(|e| die!("Couldn't daemonize; {}", e));
@@ -195,8 +289,22 @@ fn execute_upgrades(conf: &Configuration, spec: &Spec, client_config: &ClientCon
 	}
 }
 
-fn execute_client(conf: Configuration, spec: Spec, client_config: ClientConfig, panic_handler: Arc<PanicHandler>, logger: Arc<RotatingLogger>) {
-	let mut hypervisor = modules::hypervisor();
+fn execute_keys_upgrade(conf: &Configuration) {
+	let keys_path = conf.keys_path();
+	for legacy_path in conf.legacy_keys_paths() {
+		match ::upgrade::upgrade_key_location(Path::new(&legacy_path), Path::new(&keys_path)) {
+			Ok(0) => {},
+			Ok(moved) => info!("Moved {} key(s) from {} to {}", moved, legacy_path, keys_path),
+			Err(e) => die!("Error moving keys from {} to {}: {}", legacy_path, keys_path, e),
+		}
+	}
+}
+
+fn execute_client(conf: Configuration, spec: Spec, client_config: ClientConfig, panic_handler: Arc<PanicHandler>, logger: Arc<RotatingLogger>) -> Option<ExitRequest> {
+	// Move keys kept before keys were separated per chain
+	execute_keys_upgrade(&conf);
+
+	let mut hypervisor = modules::hypervisor(&conf.directories().db);
 
 	info!("Starting {}", Colour::White.bold().paint(format!("{}", version())));
 	info!("Using state DB journalling strategy {}", Colour::White.bold().paint(match client_config.pruning {
@@ -206,6 +314,8 @@ fn execute_client(conf: Configuration, spec: Spec, client_config: ClientConfig,
 		journaldb::Algorithm::RefCounted => "basic",
 	}));
 
+	info!("Using keccak backend {}", Colour::White.bold().paint(format!("{}", ::util::sha3::backend())));
+
 	// Display warning about using experimental journaldb types
 	match client_config.pruning {
 		journaldb::Algorithm::EarlyMerge | journaldb::Algorithm::RefCounted => {
@@ -227,7 +337,7 @@ fn execute_client(conf: Configuration, spec: Spec, client_config: ClientConfig,
 	let account_service = Arc::new(conf.account_service());
 
 	// Miner
-	let miner = Miner::new(conf.miner_options(), conf.gas_pricer(), conf.spec(), Some(account_service.clone()));
+	let miner = Miner::new(conf.miner_options(), conf.gas_pricer(), &spec, Some(account_service.clone()));
 	miner.set_author(conf.author().unwrap_or_default());
 	miner.set_gas_floor_target(conf.gas_floor_target());
 	miner.set_gas_ceil_target(conf.gas_ceil_target());
@@ -237,7 +347,7 @@ fn execute_client(conf: Configuration, spec: Spec, client_config: ClientConfig,
 	// Build client
 	let  service = ClientService::start(
 		client_config,
-		spec,
+		&spec,
 		Path::new(&conf.path()),
 		miner.clone(),
 	).unwrap_or_else(|e| die_with_error("Client", e));
@@ -254,12 +364,23 @@ fn execute_client(conf: Configuration, spec: Spec, client_config: ClientConfig,
 			.unwrap_or_else(|e| die_with_error("Sync", e));
 
 	service.add_notify(chain_notify.clone());
+	service.set_sync_oracle(Arc::new(modules::SyncOracle(sync_provider.clone())));
 
 	// if network is active by default
 	if match conf.mode() { Mode::Dark(..) => false, _ => !conf.args.flag_no_network } {
 		chain_notify.start();
 	}
 
+	let exit = Arc::new(ExitHandle::new());
+
+	// Feed pub-sub subscriptions
+	let pubsub = Arc::new(rpc_apis::EthPubSubClient::new(&client));
+	service.add_notify(pubsub.clone());
+	{
+		let pubsub = pubsub.clone();
+		miner.add_transactions_listener(Box::new(move |hashes: &[H256]| pubsub.notify_transactions(hashes)));
+	}
+
 	let deps_for_rpc_apis = Arc::new(rpc_apis::Dependencies {
 		signer_port: conf.signer_port(),
 		signer_queue: Arc::new(rpc_apis::ConfirmationsQueue::default()),
@@ -272,7 +393,14 @@ fn execute_client(conf: Configuration, spec: Spec, client_config: ClientConfig,
 		logger: logger.clone(),
 		settings: network_settings.clone(),
 		allow_pending_receipt_query: !conf.args.flag_geth,
+		gas_cap: conf.rpc_gas_cap(),
 		net_service: manage_network.clone(),
+		exit: exit.clone(),
+		api_keys: Arc::new(ApiKeys::load(conf.api_keys_path(), rpc_apis::API_NAMES.iter().map(|api| api.to_string()).collect())
+			.unwrap_or_else(|e| die!("{}", e))),
+		audit_log: conf.rpc_audit_log_path().map(|path| Arc::new(AuditLog::open(path.clone(), conf.args.flag_rpc_audit_log_size * 1024 * 1024)
+			.unwrap_or_else(|e| die!("{}: Cannot open audit log: {}", path.display(), e)))),
+		pubsub: pubsub,
 	});
 
 	let dependencies = rpc::Dependencies {
@@ -281,14 +409,21 @@ fn execute_client(conf: Configuration, spec: Spec, client_config: ClientConfig,
 	};
 
 	// Setup http rpc
-	let rpc_server = rpc::new_http(rpc::HttpConfiguration {
+	let http_conf = rpc::HttpConfiguration {
 		enabled: network_settings.rpc_enabled,
 		interface: conf.rpc_interface(),
 		port: network_settings.rpc_port,
 		apis: conf.rpc_apis(),
 		cors: conf.rpc_cors(),
 		hosts: conf.rpc_hosts(),
-	}, &dependencies);
+		keys: conf.args.flag_jsonrpc_keys,
+		secret: conf.rpc_secret(),
+		limits: conf.rpc_rate_limits(),
+		compress_above: conf.args.flag_jsonrpc_compress_above,
+		tls: conf.rpc_tls(),
+	};
+	let _keyed_rpc_server = rpc::new_keyed_http(&http_conf, &dependencies);
+	let rpc_server = rpc::new_http(http_conf, &dependencies);
 
 	// setup ipc rpc
 	let _ipc_server = rpc::new_ipc(conf.ipc_settings(), &dependencies);
@@ -312,13 +447,61 @@ fn execute_client(conf: Configuration, spec: Spec, client_config: ClientConfig,
 		enabled: conf.signer_enabled(),
 		port: conf.args.flag_signer_port,
 		signer_path: conf.directories().signer,
+		apis: conf.ws_apis(),
+		tls: conf.signer_tls(),
+		api_keys: conf.args.flag_jsonrpc_keys,
 	}, signer::Dependencies {
 		panic_handler: panic_handler.clone(),
 		apis: deps_for_rpc_apis.clone(),
 	});
 
-	let informant = Arc::new(Informant::new(service.client(), Some(sync_provider.clone()), Some(manage_network.clone()), conf.have_color()));
+	let informant = Arc::new(Informant::new(service.client(), Some(sync_provider.clone()), Some(manage_network.clone()), Some(miner.clone()), Some(external_miner.clone()), conf.informant_format()));
 	service.add_notify(informant.clone());
+
+	let _ethstats = conf.ethstats().map(|config| {
+		let ethstats = ethstats::start(config, ethstats::Dependencies {
+			client: client.clone(),
+			sync: sync_provider.clone(),
+			miner: miner.clone(),
+			external_miner: external_miner.clone(),
+			network_port: network_settings.network_port,
+		});
+		service.add_notify(ethstats.clone());
+		ethstats
+	});
+
+	let _trace_stream = conf.trace_stream_addr().map(|addr| {
+		let trace_stream = trace_stream::start(&addr, &client).unwrap_or_else(|e| die!("{}", e));
+		service.add_notify(trace_stream.clone());
+		trace_stream
+	});
+
+	let _import_log = conf.import_log_path().map(|path| {
+		let import_log = import_log::start(&path, &client).unwrap_or_else(|e| die!("{}", e));
+		service.add_notify(import_log.clone());
+		import_log
+	});
+
+	let _metrics = conf.metrics_addr().map(|addr| {
+		metrics::start(addr, metrics::Dependencies {
+			client: client.clone(),
+			sync: sync_provider.clone(),
+			net: manage_network.clone(),
+		}).unwrap_or_else(|e| die!("{}", e))
+	});
+
+	let _evm_profile = conf.evm_profile_path().map(|path| {
+		evm_profile::start(path, client.clone()).unwrap_or_else(|e| die!("{}", e))
+	});
+
+	let _scheduler = conf.schedule().map(|tasks| {
+		scheduler::start(tasks, client.clone()).unwrap_or_else(|e| die!("{}", e))
+	});
+
+	let _import_dir = conf.import_dir().map(|path| {
+		import_dir::start(path, client.clone()).unwrap_or_else(|e| die!("{}", e))
+	});
+
 	// Register IO handler
 	let io_handler = Arc::new(ClientIoHandler {
 		client: service.client(),
@@ -337,7 +520,8 @@ fn execute_client(conf: Configuration, spec: Spec, client_config: ClientConfig,
 	}
 
 	// Handle exit
-	wait_for_exit(panic_handler, rpc_server, dapps_server, signer_server);
+	wait_for_exit(panic_handler, exit.clone(), rpc_server, dapps_server, signer_server);
+	exit.request()
 }
 
 fn flush_stdout() {
@@ -349,34 +533,20 @@ enum DataFormat {
 	Binary,
 }
 
-fn execute_export(conf: Configuration, panic_handler: Arc<PanicHandler>) {
-	let spec = conf.spec();
+fn execute_export(conf: Configuration, spec: Spec, panic_handler: Arc<PanicHandler>) {
 	let client_config = conf.client_config(&spec);
 
 	// Build client
 	let service = ClientService::start(
-		client_config, spec, Path::new(&conf.path()), Arc::new(Miner::with_spec(conf.spec()))
+		client_config, &spec, Path::new(&conf.path()), Arc::new(Miner::with_spec(&spec))
 	).unwrap_or_else(|e| die_with_error("Client", e));
 
 	panic_handler.forward_from(&service);
 	let client = service.client();
 
 	// we have a client!
-	let parse_block_id = |s: &str, arg: &str| -> u64 {
-		if s == "latest" {
-			client.chain_info().best_block_number
-		} else if let Ok(n) = s.parse::<u64>() {
-			n
-		} else if let Ok(h) = H256::from_str(s) {
-			client.block_number(BlockID::Hash(h)).unwrap_or_else(|| {
-				die!("Unknown block hash passed to {} parameter: {:?}", arg, s);
-			})
-		} else {
-			die!("Invalid {} parameter given: {:?}", arg, s);
-		}
-	};
-	let from = parse_block_id(&conf.args.flag_from, "--from");
-	let to = parse_block_id(&conf.args.flag_to, "--to");
+	let from = parse_block_id(&*client, &conf.args.flag_from, "--from");
+	let to = parse_block_id(&*client, &conf.args.flag_to, "--to");
 	let format = match conf.args.flag_format {
 		Some(x) => match x.deref() {
 			"binary" | "bin" => DataFormat::Binary,
@@ -402,13 +572,111 @@ fn execute_export(conf: Configuration, panic_handler: Arc<PanicHandler>) {
 	}
 }
 
-fn execute_import(conf: Configuration, panic_handler: Arc<PanicHandler>) {
-	let spec = conf.spec();
+fn execute_export_activity(conf: Configuration, spec: Spec, panic_handler: Arc<PanicHandler>) {
+	use activity::ActivityFormat;
+
+	let address = match conf.args.flag_address {
+		Some(ref a) => Address::from_str(clean_0x(a)).unwrap_or_else(|_| die!("{}: Invalid address for --address.", a)),
+		None => die!("No address given. Pass the address using `--address`."),
+	};
+	let format = match conf.args.flag_format {
+		Some(ref x) => match x.deref() {
+			"csv" => ActivityFormat::Csv,
+			"json" => ActivityFormat::Json,
+			x => die!("Invalid --format parameter given: {:?}", x),
+		},
+		None => ActivityFormat::Csv,
+	};
+
 	let client_config = conf.client_config(&spec);
 
 	// Build client
 	let service = ClientService::start(
-		client_config, spec, Path::new(&conf.path()), Arc::new(Miner::with_spec(conf.spec()))
+		client_config, &spec, Path::new(&conf.path()), Arc::new(Miner::with_spec(&spec))
+	).unwrap_or_else(|e| die_with_error("Client", e));
+
+	panic_handler.forward_from(&service);
+	let client = service.client();
+
+	let from = parse_block_id(&*client, &conf.args.flag_from, "--from");
+	let to = parse_block_id(&*client, &conf.args.flag_to, "--to");
+
+	let transfers = activity::collect(&*client, &address, from, to).unwrap_or_else(|| {
+		die!("Unable to query traces. Ensure the chain was synced with --tracing on.")
+	});
+
+	let mut out: Box<Write> = if let Some(f) = conf.args.arg_file {
+		Box::new(File::create(&f).unwrap_or_else(|_| die!("Cannot write to file given: {}", f)))
+	} else {
+		Box::new(::std::io::stdout())
+	};
+
+	activity::write(&mut out, &transfers, format).expect("Couldn't write to stream.");
+}
+
+fn execute_export_state(conf: Configuration, spec: Spec, panic_handler: Arc<PanicHandler>) {
+	let client_config = conf.client_config(&spec);
+
+	// Build client
+	let service = ClientService::start(
+		client_config, &spec, Path::new(&conf.path()), Arc::new(Miner::with_spec(&spec))
+	).unwrap_or_else(|e| die_with_error("Client", e));
+
+	panic_handler.forward_from(&service);
+	let client = service.client();
+
+	let at = parse_block_id(&*client, &conf.args.flag_at, "--at");
+	let path = Path::new(conf.args.arg_path.first().expect("<path> is required by the export state command; qed"));
+	match state_export::export(&*client, BlockID::Number(at), path) {
+		Ok(accounts) => info!("Exported {} accounts at block #{} into {}", accounts, at, path.display()),
+		Err(e) => die!("State export failed: {}", e),
+	}
+}
+
+fn execute_trace_reindex(conf: Configuration, spec: Spec, panic_handler: Arc<PanicHandler>) {
+	let mut client_config = conf.client_config(&spec);
+	// re-indexing replays blocks on the state of their parents, and a database which isn't
+	// archive would be marked for re-indexing that can never finish.
+	match client_config.pruning {
+		journaldb::Algorithm::Archive => {},
+		_ => die!("Re-indexing traces requires a database synced with --pruning archive."),
+	}
+	client_config.tracing.reindex = true;
+
+	// Build client
+	let service = ClientService::start(
+		client_config, &spec, Path::new(&conf.path()), Arc::new(Miner::with_spec(&spec))
+	).unwrap_or_else(|e| die_with_error("Client", e));
+
+	panic_handler.forward_from(&service);
+	let client = service.client();
+
+	match client.reindex_traces(|number, best| info!("Re-indexed traces up to block #{} of #{}", number, best)) {
+		Ok(()) => info!("Traces of all blocks are indexed."),
+		Err(e) => die!("Trace re-indexing failed: {}", e),
+	}
+}
+
+fn parse_block_id(client: &Client, s: &str, arg: &str) -> u64 {
+	if s == "latest" {
+		client.chain_info().best_block_number
+	} else if let Ok(n) = s.parse::<u64>() {
+		n
+	} else if let Ok(h) = H256::from_str(s) {
+		client.block_number(BlockID::Hash(h)).unwrap_or_else(|| {
+			die!("Unknown block hash passed to {} parameter: {:?}", arg, s);
+		})
+	} else {
+		die!("Invalid {} parameter given: {:?}", arg, s);
+	}
+}
+
+fn execute_import(conf: Configuration, spec: Spec, panic_handler: Arc<PanicHandler>) {
+	let client_config = conf.client_config(&spec);
+
+	// Build client
+	let service = ClientService::start(
+		client_config, &spec, Path::new(&conf.path()), Arc::new(Miner::with_spec(&spec))
 	).unwrap_or_else(|e| die_with_error("Client", e));
 
 	panic_handler.forward_from(&service);
@@ -448,7 +716,7 @@ fn execute_import(conf: Configuration, panic_handler: Arc<PanicHandler>) {
 		}
 	};
 
-	let informant = Informant::new(client.clone(), None, None, conf.have_color());
+	let informant = Informant::new(client.clone(), None, None, None, None, conf.informant_format());
 
 	let do_import = |bytes| {
 		while client.queue_info().is_full() { sleep(Duration::from_secs(1)); }
@@ -499,7 +767,90 @@ fn execute_signer(conf: Configuration) {
 	let code = new_token(path).unwrap_or_else(|e| {
 		die!("Error generating token: {:?}", e)
 	});
-	println!("This key code will authorise your System Signer UI: {}", if conf.args.flag_no_color { code } else { format!("{}", Colour::White.bold().paint(code)) });
+	println!("This key code will authorise your System Signer UI: {}", if !conf.have_color() { code } else { format!("{}", Colour::White.bold().paint(code)) });
+}
+
+fn execute_snapshot_verify(conf: Configuration) {
+	use ethcore::snapshot::{self, ManifestData};
+	use util::FixedHash;
+	use util::kvdb::DatabaseConfig;
+
+	let path = Path::new(conf.args.arg_path.first().expect("<path> is required by the snapshot verify command; qed"));
+	let mut manifest_rlp = Vec::new();
+	File::open(path.join("MANIFEST")).and_then(|mut file| file.read_to_end(&mut manifest_rlp))
+		.unwrap_or_else(|e| die!("Unable to read snapshot manifest in {}: {}", path.display(), e));
+	let manifest = ManifestData::from_rlp(&manifest_rlp).unwrap_or_else(|e| die!("Invalid snapshot manifest: {:?}", e));
+
+	println!("Snapshot at block #{} ({})", manifest.block_number, manifest.block_hash);
+	println!("  {} state chunks, state root {}", manifest.state_hashes.len(), manifest.state_root);
+	println!("  {} block chunks", manifest.block_hashes.len());
+
+	// the state is rebuilt into a scratch database which is thrown away afterwards.
+	let scratch = ::std::env::temp_dir().join(format!("parity-snapshot-verify-{}", H256::random().hex()));
+	let result = {
+		let state_db = journaldb::new(&scratch.to_string_lossy(), journaldb::Algorithm::Archive, DatabaseConfig::default());
+		snapshot::verify(&manifest, path, state_db)
+	};
+	let _ = ::std::fs::remove_dir_all(&scratch);
+
+	match result {
+		Ok(()) => println!("Snapshot verification passed."),
+		Err(e) => {
+			println!("Snapshot verification failed: {}", e);
+			::std::process::exit(1);
+		},
+	}
+}
+
+fn execute_state_verify(conf: Configuration) {
+	let address = conf.args.flag_address.as_ref().map(|a| {
+		Address::from_str(clean_0x(a)).unwrap_or_else(|_| die!("{}: Invalid address for --address.", a))
+	});
+	let path = Path::new(conf.args.arg_path.first().expect("<path> is required by the state verify command; qed"));
+
+	match state_export::verify(path, address.as_ref()) {
+		Ok(verified) => {
+			println!("State at block #{} ({}), state root {}", verified.root.block_number, verified.root.block_hash, verified.root.state_root);
+			println!("  {} accounts verified, total balance {} wei", verified.accounts, verified.total_balance);
+			println!("State verification passed.");
+		},
+		Err(e) => {
+			println!("State verification failed: {}", e);
+			::std::process::exit(1);
+		},
+	}
+}
+
+fn execute_testnet(conf: Configuration) {
+	use testnet::TestnetOptions;
+
+	let options = TestnetOptions {
+		nodes: conf.args.flag_testnet_nodes,
+		dir: Path::new(&conf.path()).join("testnet"),
+		port: conf.args.flag_port,
+		rpc_port: conf.network_settings().rpc_port,
+	};
+	if options.nodes == 0 {
+		die!("--testnet-nodes must be at least 1.");
+	}
+
+	let nodes = testnet::nodes(&options).unwrap_or_else(|e| die!("{}", e));
+	let spec = testnet::prepare(&options, &nodes).unwrap_or_else(|e| die!("{}", e));
+	let mut children = testnet::start(&spec, &nodes).unwrap_or_else(|e| die!("{}", e));
+
+	println!("Started {} nodes, chain spec in {}", nodes.len(), spec.display());
+	for (i, node) in nodes.iter().enumerate() {
+		println!("Node {}{}:", i, if i == 0 { " (sealing)" } else { "" });
+		println!("  JSON-RPC: {}", node.rpc_endpoint());
+		println!("  enode:    {}", node.enode());
+		println!("  account:  0x{} (secret {})", node.account.address(), node.account.secret());
+		println!("  password: {}", node.password);
+		println!("  logs:     {}", node.dir.join("parity.log").display());
+	}
+
+	for child in &mut children {
+		let _ = child.wait();
+	}
 }
 
 fn execute_account_cli(conf: Configuration) {
@@ -575,6 +926,7 @@ fn execute_wallet_cli(conf: Configuration) {
 
 fn wait_for_exit(
 	panic_handler: Arc<PanicHandler>,
+	exit_handle: Arc<ExitHandle>,
 	_rpc_server: Option<RpcServer>,
 	_dapps_server: Option<WebappServer>,
 	_signer_server: Option<SignerServer>
@@ -589,6 +941,10 @@ fn wait_for_exit(
 	let e = exit.clone();
 	panic_handler.on_panic(move |_reason| { e.notify_all(); });
 
+	// Handle shutdown and restart requested over RPC
+	let e = exit.clone();
+	exit_handle.set_handler(move || { e.notify_all(); });
+
 	// Wait for signal
 	let mutex = Mutex::new(());
 	exit.wait(&mut mutex.lock());
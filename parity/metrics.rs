@@ -0,0 +1,200 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Node metrics served over HTTP in the Prometheus text exposition format.
+//!
+//! `GET /metrics` returns the figures the informant reports: import counters, queue sizes,
+//! memory used by caches, peer counts and the sync status. Rates are left to the scraper.
+//!
+//! Each scrape is served on its own thread and must arrive within a deadline, with a bounded
+//! request head; connections over the limit are closed right away.
+
+use std::cell::Cell;
+use std::fmt::Write as FmtWrite;
+use std::io::{self, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::rc::Rc;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use util::Uint;
+use ethcore::client::{BlockChainClient, Client};
+use ethsync::{SyncProvider, ManageNetwork};
+use http_io::{Deadline, read_line};
+
+/// Time a scraper has to send its whole request.
+const REQUEST_TIMEOUT_SEC: u64 = 5;
+/// Largest accepted size of the request line and headers together.
+const MAX_HEAD_SIZE: usize = 8 * 1024;
+/// Maximal number of scrapes served at once. Further connections are closed right away.
+const MAX_CONNECTIONS: usize = 8;
+/// How often the listener checks whether the server was dropped, while no connections arrive.
+const ACCEPT_POLL_MS: u64 = 100;
+
+/// Sources of the served metrics.
+pub struct Dependencies {
+	pub client: Arc<Client>,
+	pub sync: Arc<SyncProvider>,
+	pub net: Arc<ManageNetwork>,
+}
+
+/// Metrics server. Stops serving once dropped.
+pub struct Metrics {
+	deps: Dependencies,
+}
+
+/// Starts serving metrics on given address.
+pub fn start(addr: SocketAddr, deps: Dependencies) -> Result<Arc<Metrics>, String> {
+	let listener = try!(TcpListener::bind(&addr).map_err(|e| format!("Cannot bind metrics server to {}: {}", addr, e)));
+	// polled, so that the thread notices the server was dropped without waiting for a connection.
+	try!(listener.set_nonblocking(true).map_err(|e| format!("Cannot set up metrics server on {}: {}", addr, e)));
+	info!(target: "metrics", "Serving metrics on http://{}/metrics", addr);
+
+	let metrics = Arc::new(Metrics { deps: deps });
+	let weak: Weak<Metrics> = Arc::downgrade(&metrics);
+	let connections = Arc::new(AtomicUsize::new(0));
+	try!(thread::Builder::new().name("metrics".into()).spawn(move || {
+		loop {
+			let stream = match listener.accept() {
+				Ok((stream, _)) => stream,
+				Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+					if weak.upgrade().is_none() {
+						break;
+					}
+					thread::sleep(Duration::from_millis(ACCEPT_POLL_MS));
+					continue;
+				},
+				Err(e) => {
+					debug!(target: "metrics", "Error accepting connection: {}", e);
+					thread::sleep(Duration::from_millis(ACCEPT_POLL_MS));
+					continue;
+				},
+			};
+			let metrics = match weak.upgrade() {
+				Some(metrics) => metrics,
+				None => break,
+			};
+			if connections.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+				debug!(target: "metrics", "Closing metrics connection, {} scrapes already served", MAX_CONNECTIONS);
+				continue;
+			}
+
+			let guard = Connection::open(connections.clone());
+			let spawned = thread::Builder::new().name("metrics-conn".into()).spawn(move || {
+				let _guard = guard;
+				if let Err(e) = stream.set_nonblocking(false).and_then(|_| metrics.serve(stream)) {
+					debug!(target: "metrics", "Error serving metrics: {}", e);
+				}
+			});
+			if let Err(e) = spawned {
+				warn!(target: "metrics", "Error creating metrics connection thread: {}", e);
+			}
+		}
+	}).map_err(|e| format!("Error creating metrics thread: {}", e)));
+
+	Ok(metrics)
+}
+
+// an open connection, counted until dropped.
+struct Connection(Arc<AtomicUsize>);
+
+impl Connection {
+	fn open(count: Arc<AtomicUsize>) -> Connection {
+		count.fetch_add(1, Ordering::SeqCst);
+		Connection(count)
+	}
+}
+
+impl Drop for Connection {
+	fn drop(&mut self) {
+		self.0.fetch_sub(1, Ordering::SeqCst);
+	}
+}
+
+impl Metrics {
+	fn serve(&self, stream: TcpStream) -> io::Result<()> {
+		try!(stream.set_write_timeout(Some(Duration::from_secs(REQUEST_TIMEOUT_SEC))));
+		let until = Rc::new(Cell::new(Instant::now() + Duration::from_secs(REQUEST_TIMEOUT_SEC)));
+		let mut reader = BufReader::new(Deadline::new(stream, until));
+
+		let mut line = String::new();
+		let mut head_left = MAX_HEAD_SIZE;
+		let mut complete = try!(read_line(&mut reader, &mut line, &mut head_left));
+		let request_line = line.clone();
+		// skip the headers.
+		while complete && !line.trim().is_empty() {
+			complete = try!(read_line(&mut reader, &mut line, &mut head_left));
+		}
+
+		let mut parts = request_line.split_whitespace();
+		let response = match (complete, parts.next(), parts.next()) {
+			(false, _, _) => "HTTP/1.1 431 Request Header Fields Too Large\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned(),
+			(true, Some("GET"), Some("/metrics")) => {
+				let body = self.render();
+				format!("HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body)
+			},
+			_ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_owned(),
+		};
+		reader.get_mut().write_all(response.as_bytes())
+	}
+
+	/// Current metrics in the text exposition format.
+	pub fn render(&self) -> String {
+		let client = &self.deps.client;
+		let chain_info = client.chain_info();
+		let queue_info = client.queue_info();
+		let cache_info = client.blockchain_cache_info();
+		let report = client.report();
+		let sync = self.deps.sync.status();
+		let net = self.deps.net.network_config();
+
+		let mut out = String::new();
+		metric(&mut out, "parity_best_block", "gauge", "Number of the best block.", chain_info.best_block_number);
+		metric(&mut out, "parity_blocks_imported_total", "counter", "Blocks imported since start.", report.blocks_imported as u64);
+		metric(&mut out, "parity_transactions_applied_total", "counter", "Transactions applied since start.", report.transactions_applied as u64);
+		metric(&mut out, "parity_gas_processed_total", "counter", "Gas processed since start.", report.gas_processed.low_u64());
+		metric(&mut out, "parity_queue_unverified_blocks", "gauge", "Blocks waiting for verification.", queue_info.unverified_queue_size as u64);
+		metric(&mut out, "parity_queue_verified_blocks", "gauge", "Verified blocks waiting for import.", queue_info.verified_queue_size as u64);
+		metric(&mut out, "parity_queue_memory_bytes", "gauge", "Memory used by the block queue.", queue_info.mem_used as u64);
+		metric(&mut out, "parity_state_db_memory_bytes", "gauge", "Memory used by the state database.", report.state_db_mem as u64);
+		metric(&mut out, "parity_chain_cache_memory_bytes", "gauge", "Memory used by the blockchain caches.", cache_info.total() as u64);
+		metric(&mut out, "parity_sync_memory_bytes", "gauge", "Memory used by the sync.", sync.mem_used as u64);
+		metric(&mut out, "parity_sync_major_syncing", "gauge", "1 while catching up with the chain, 0 otherwise.", sync.is_major_syncing() as u64);
+		metric(&mut out, "parity_sync_highest_block", "gauge", "Highest block number known to the sync.", sync.highest_block_number.unwrap_or(chain_info.best_block_number));
+		metric(&mut out, "parity_peers_active", "gauge", "Peers currently syncing with us.", sync.num_active_peers as u64);
+		metric(&mut out, "parity_peers_connected", "gauge", "Connected peers.", sync.num_peers as u64);
+		metric(&mut out, "parity_peers_ideal", "gauge", "Number of peers the node tries to keep.", net.ideal_peers as u64);
+		out
+	}
+}
+
+// append a metric with its help and type lines.
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, value: u64) {
+	let _ = write!(out, "# HELP {} {}\n# TYPE {} {}\n{} {}\n", name, help, name, kind, name, value);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::metric;
+
+	#[test]
+	fn formats_metric() {
+		let mut out = String::new();
+		metric(&mut out, "parity_best_block", "gauge", "Number of the best block.", 42);
+		assert_eq!(out, "# HELP parity_best_block Number of the best block.\n# TYPE parity_best_block gauge\nparity_best_block 42\n");
+	}
+}
@@ -26,9 +26,11 @@ use ethcore::migrations;
 /// Database is assumed to be at default version, when no version file is found.
 const DEFAULT_VERSION: u32 = 5;
 /// Current version of database models.
-const CURRENT_VERSION: u32 = 7;
+const CURRENT_VERSION: u32 = 8;
 /// Defines how many items are migrated to the new version of database at once.
 const BATCH_SIZE: usize = 1024;
+/// Last version changing the state database.
+const STATE_VERSION: u32 = 7;
 /// Version file name.
 const VERSION_FILE_NAME: &'static str = "db_version";
 
@@ -156,6 +158,7 @@ fn blocks_database_migrations() -> Result<MigrationManager, Error> {
 fn extras_database_migrations() -> Result<MigrationManager, Error> {
 	let mut manager = MigrationManager::new(default_migration_settings());
 	try!(manager.add_migration(migrations::extras::ToV6).map_err(|_| Error::MigrationImpossible));
+	try!(manager.add_migration(migrations::extras::ToV8).map_err(|_| Error::MigrationImpossible));
 	Ok(manager)
 }
 
@@ -217,7 +220,10 @@ pub fn migrate(path: &Path, pruning: Algorithm) -> Result<(), Error> {
 		println!("Migrating database from version {} to {}", version, CURRENT_VERSION);
 		try!(migrate_database(version, blocks_database_path(path), try!(blocks_database_migrations())));
 		try!(migrate_database(version, extras_database_path(path), try!(extras_database_migrations())));
-		try!(migrate_database(version, state_database_path(path), try!(state_database_migrations(pruning))));
+		// only archive and fast pruning can be migrated, so don't ask for a state migration unless one is due.
+		if version < STATE_VERSION {
+			try!(migrate_database(version, state_database_path(path), try!(state_database_migrations(pruning))));
+		}
 		println!("Migration finished");
 	} else if version > CURRENT_VERSION {
 		return Err(Error::FutureDBVersion);
@@ -227,3 +233,35 @@ pub fn migrate(path: &Path, pruning: Algorithm) -> Result<(), Error> {
 	update_version(path)
 }
 
+
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use std::io::{Read, Write};
+	use std::path::Path;
+	use devtools::RandomTempPath;
+	use util::journaldb::Algorithm;
+	use util::kvdb::Database;
+	use super::{migrate, version_file_path, blocks_database_path, extras_database_path, state_database_path};
+
+	fn v7_database(path: &Path) {
+		for db in &[blocks_database_path(path), extras_database_path(path), state_database_path(path)] {
+			Database::open_default(db.to_str().unwrap()).unwrap();
+		}
+		File::create(version_file_path(path)).unwrap().write_all(b"7").unwrap();
+	}
+
+	#[test]
+	fn should_migrate_v7_without_state_migration() {
+		for pruning in &[Algorithm::EarlyMerge, Algorithm::RefCounted] {
+			let temp = RandomTempPath::create_dir();
+			v7_database(temp.as_path());
+
+			migrate(temp.as_path(), *pruning).unwrap();
+
+			let mut version = String::new();
+			File::open(version_file_path(temp.as_path())).unwrap().read_to_string(&mut version).unwrap();
+			assert_eq!(version, "8");
+		}
+	}
+}
@@ -26,6 +26,15 @@ use self::ipc_deps::*;
 
 use ethcore_logger::Settings as LogSettings;
 
+/// Lets the client service know when a major sync is in progress.
+pub struct SyncOracle(pub Arc<::ethsync::SyncProvider>);
+
+impl ethcore::service::SyncOracle for SyncOracle {
+	fn is_major_syncing(&self) -> bool {
+		self.0.status().is_major_syncing()
+	}
+}
+
 #[cfg(not(feature="ipc"))]
 mod no_ipc_deps {
 	pub use ethsync::{EthSync, SyncProvider, ManageNetwork};
@@ -50,21 +59,22 @@ mod ipc_deps {
 	pub use nanoipc::{GuardedSocket, NanoSocket, init_client};
 	pub use ipc::IpcSocket;
 	pub use ipc::binary::serialize;
+	pub use ethcore_logger::format_targets as format_log_targets;
 }
 
 
 #[cfg(feature="ipc")]
-pub fn hypervisor() -> Option<Hypervisor> {
-	Some(Hypervisor::new())
+pub fn hypervisor(data_dir: &str) -> Option<Hypervisor> {
+	Some(Hypervisor::new(data_dir))
 }
 
 #[cfg(not(feature="ipc"))]
-pub fn hypervisor() -> Option<Hypervisor> {
+pub fn hypervisor(_data_dir: &str) -> Option<Hypervisor> {
 	None
 }
 
 #[cfg(feature="ipc")]
-fn sync_arguments(sync_cfg: SyncConfig, net_cfg: NetworkConfiguration, log_settings: &LogSettings) -> BootArgs {
+fn sync_arguments(sync_cfg: SyncConfig, net_cfg: NetworkConfiguration, log_settings: &LogSettings, hypervisor_socket: Option<&str>) -> BootArgs {
 	let service_config = ServiceConfiguration {
 		sync: sync_cfg,
 		net: net_cfg,
@@ -76,7 +86,12 @@ fn sync_arguments(sync_cfg: SyncConfig, net_cfg: NetworkConfiguration, log_setti
 	// client service url and logging settings are passed in command line
 	let mut cli_args = Vec::new();
 	cli_args.push("ipc:///tmp/parity-chain.ipc".to_owned());
+	if let Some(socket) = hypervisor_socket {
+		cli_args.push("--hypervisor-socket".to_owned());
+		cli_args.push(socket.to_owned());
+	}
 	if !log_settings.color { cli_args.push("--no-color".to_owned()); }
+	if log_settings.json { cli_args.push("--log-json".to_owned()); }
 	if let Some(ref init) = log_settings.init {
 		cli_args.push("-l".to_owned());
 		cli_args.push(init.to_owned());
@@ -85,6 +100,14 @@ fn sync_arguments(sync_cfg: SyncConfig, net_cfg: NetworkConfiguration, log_setti
 		cli_args.push("--log-file".to_owned());
 		cli_args.push(file.to_owned());
 	}
+	if !log_settings.allow.is_empty() {
+		cli_args.push("--log-allow".to_owned());
+		cli_args.push(format_log_targets(&log_settings.allow));
+	}
+	if !log_settings.deny.is_empty() {
+		cli_args.push("--log-deny".to_owned());
+		cli_args.push(log_settings.deny.join(","));
+	}
 
 	BootArgs::new().stdin(service_payload).cli(cli_args)
 }
@@ -101,7 +124,8 @@ pub fn sync
 	-> Result<SyncModules, ethcore::error::Error>
 {
 	let mut hypervisor = hypervisor_ref.take().expect("There should be hypervisor for ipc configuration");
-	hypervisor = hypervisor.module(SYNC_MODULE_ID, "sync", sync_arguments(sync_cfg, net_cfg, log_settings));
+	let sync_args = sync_arguments(sync_cfg, net_cfg, log_settings, hypervisor.mio_socket());
+	hypervisor = hypervisor.module(SYNC_MODULE_ID, "sync", sync_args);
 
 	hypervisor.start();
 	hypervisor.wait_for_startup();
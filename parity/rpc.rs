@@ -15,31 +15,57 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 
+use std::io;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::path::Path;
 use std::net::SocketAddr;
 use util::panics::PanicHandler;
+use util::path::set_owner;
 use die::*;
+#[cfg(windows)]
 use jsonipc;
-use rpc_apis;
+use rpc_apis::{self, ApiSet};
+use rpc_keys::{self, KeyedRpc};
 use std::fmt;
 
 pub use ethcore_rpc::Server as RpcServer;
-use ethcore_rpc::{RpcServerError, RpcServer as Server};
+use ethcore_rpc::{RpcServerError, RpcServer as Server, RateLimits};
 
 pub struct HttpConfiguration {
 	pub enabled: bool,
 	pub interface: String,
 	pub port: u16,
-	pub apis: String,
+	pub apis: ApiSet,
 	pub cors: Option<Vec<String>>,
 	pub hosts: Option<Vec<String>>,
+	pub keys: bool,
+	pub secret: Option<String>,
+	pub limits: Option<RateLimits>,
+	/// Compress responses larger than this many bytes.
+	pub compress_above: Option<usize>,
+	/// Serve HTTPS with this certificate and key.
+	pub tls: Option<rpc_keys::Tls>,
+}
+
+impl HttpConfiguration {
+	/// Whether requests need checks (or recording) the plain JSON-RPC server can't do.
+	fn is_guarded(&self, deps: &Dependencies) -> bool {
+		self.keys || self.secret.is_some() || self.limits.is_some() || self.compress_above.is_some() || self.tls.is_some() || deps.apis.audit_log.is_some()
+	}
 }
 
 pub struct IpcConfiguration {
 	pub enabled: bool,
+	/// Path of the socket, or of the named pipe on Windows.
 	pub socket_addr: String,
-	pub apis: String,
+	pub apis: ApiSet,
+	/// Permission bits of the socket. Ignored on Windows.
+	pub mode: Option<u32>,
+	/// Owner of the socket. Ignored on Windows.
+	pub uid: Option<u32>,
+	/// Group of the socket. Ignored on Windows.
+	pub gid: Option<u32>,
 }
 
 impl fmt::Display for IpcConfiguration {
@@ -59,21 +85,47 @@ pub struct Dependencies {
 }
 
 pub fn new_http(conf: HttpConfiguration, deps: &Dependencies) -> Option<RpcServer> {
-	if !conf.enabled {
+	if !conf.enabled || conf.is_guarded(deps) {
 		return None;
 	}
 
-	let apis = conf.apis.split(',').collect();
-	let url = format!("{}:{}", conf.interface, conf.port);
-	let addr = SocketAddr::from_str(&url).unwrap_or_else(|_| die!("{}: Invalid JSONRPC listen host/port given.", url));
+	let addr = http_addr(&conf);
+
+	Some(setup_http_rpc_server(deps, &addr, conf.cors, conf.hosts, conf.apis))
+}
+
+/// Starts the HTTP JSON-RPC server checking API keys, a shared secret or rate limits,
+/// recording an audit log, compressing responses or serving HTTPS, if enabled and any is required.
+pub fn new_keyed_http(conf: &HttpConfiguration, deps: &Dependencies) -> Option<Arc<KeyedRpc>> {
+	if !conf.enabled || !conf.is_guarded(deps) {
+		return None;
+	}
+
+	let config = rpc_keys::Config {
+		addr: http_addr(conf),
+		apis: conf.apis.names(),
+		auth: rpc_keys::Auth {
+			keys: conf.keys,
+			secret: conf.secret.clone(),
+		},
+		limits: conf.limits.clone(),
+		cors: conf.cors.clone(),
+		hosts: conf.hosts.clone(),
+		compress_above: conf.compress_above,
+		tls: conf.tls.clone(),
+	};
+	let server = rpc_keys::start(config, deps.apis.clone());
+	Some(server.unwrap_or_else(|e| die!("{}", e)))
+}
 
-	Some(setup_http_rpc_server(deps, &addr, conf.cors, conf.hosts, apis))
+fn http_addr(conf: &HttpConfiguration) -> SocketAddr {
+	let url = format!("{}:{}", conf.interface, conf.port);
+	SocketAddr::from_str(&url).unwrap_or_else(|_| die!("{}: Invalid JSONRPC listen host/port given.", url))
 }
 
-fn setup_rpc_server(apis: Vec<&str>, deps: &Dependencies) -> Server {
-	let apis = rpc_apis::from_str(apis);
+fn setup_rpc_server(apis: ApiSet, deps: &Dependencies) -> Server {
 	let server = Server::new();
-	rpc_apis::setup_rpc(server, deps.apis.clone(), rpc_apis::ApiSet::List(apis))
+	rpc_apis::setup_rpc(server, deps.apis.clone(), apis)
 }
 
 pub fn setup_http_rpc_server(
@@ -81,7 +133,7 @@ pub fn setup_http_rpc_server(
 	url: &SocketAddr,
 	cors_domains: Option<Vec<String>>,
 	allowed_hosts: Option<Vec<String>>,
-	apis: Vec<&str>,
+	apis: ApiSet,
 ) -> RpcServer {
 	let server = setup_rpc_server(apis, dependencies);
 	let ph = dependencies.panic_handler.clone();
@@ -93,13 +145,76 @@ pub fn setup_http_rpc_server(
 	}
 }
 
-pub fn new_ipc(conf: IpcConfiguration, deps: &Dependencies) -> Option<jsonipc::Server> {
+#[cfg(not(windows))]
+pub use ethcore_rpc::IpcServer;
+#[cfg(windows)]
+pub use jsonipc::Server as IpcServer;
+
+pub fn new_ipc(conf: IpcConfiguration, deps: &Dependencies) -> Option<IpcServer> {
 	if !conf.enabled { return None; }
-	let apis = conf.apis.split(',').collect();
-	Some(setup_ipc_rpc_server(deps, &conf.socket_addr, apis))
+	let restricted = conf.mode.is_some() || conf.uid.is_some() || conf.gid.is_some();
+	// bind owner-only so that nobody can connect before the permissions are set.
+	let umask = if restricted { Some(set_umask(0o177)) } else { None };
+	let server = setup_ipc_rpc_server(deps, &conf.socket_addr, conf.apis.clone());
+	if let Some(umask) = umask {
+		set_umask(umask);
+	}
+	set_ipc_permissions(&conf).unwrap_or_else(|e| die!("{}: Cannot set permissions of the IPC socket: {}", conf.socket_addr, e));
+	Some(server)
+}
+
+#[cfg(not(windows))]
+fn set_ipc_permissions(conf: &IpcConfiguration) -> io::Result<()> {
+	use std::fs;
+	use std::os::unix::fs::PermissionsExt;
+
+	let path = Path::new(&conf.socket_addr);
+	if conf.uid.is_some() || conf.gid.is_some() {
+		try!(set_owner(path, conf.uid, conf.gid));
+	}
+	match conf.mode {
+		Some(mode) => fs::set_permissions(path, fs::Permissions::from_mode(mode)),
+		None => Ok(()),
+	}
+}
+
+#[cfg(windows)]
+fn set_ipc_permissions(_conf: &IpcConfiguration) -> io::Result<()> {
+	Ok(())
+}
+
+// sets the process umask, returning the previous one.
+#[cfg(not(windows))]
+fn set_umask(mask: u32) -> u32 {
+	use libc;
+
+	unsafe { libc::umask(mask as libc::mode_t) as u32 }
+}
+
+#[cfg(windows)]
+fn set_umask(mask: u32) -> u32 {
+	mask
+}
+
+#[cfg(not(windows))]
+pub fn setup_ipc_rpc_server(dependencies: &Dependencies, addr: &str, apis: ApiSet) -> IpcServer {
+	use ethcore_rpc::v1::EthPubSub;
+
+	// subscriptions serve the same data as the eth API
+	let pubsub = match apis.contains(&rpc_apis::Api::Eth) {
+		true => Some(dependencies.apis.pubsub.clone() as Arc<EthPubSub>),
+		false => None,
+	};
+	let server = setup_rpc_server(apis, dependencies);
+	match server.start_unix_ipc(addr, pubsub, dependencies.apis.audit_log.clone()) {
+		Err(io_error) => die_with_io_error("RPC", io_error),
+		Ok(server) => server
+	}
 }
 
-pub fn setup_ipc_rpc_server(dependencies: &Dependencies, addr: &str, apis: Vec<&str>) -> jsonipc::Server {
+// calls over IPC aren't recorded in the audit log, and subscriptions aren't served, on Windows.
+#[cfg(windows)]
+pub fn setup_ipc_rpc_server(dependencies: &Dependencies, addr: &str, apis: ApiSet) -> IpcServer {
 	let server = setup_rpc_server(apis, dependencies);
 	match server.start_ipc(addr) {
 		Err(jsonipc::Error::Io(io_error)) => die_with_io_error("RPC", io_error),
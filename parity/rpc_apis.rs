@@ -15,20 +15,23 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::collections::BTreeMap;
+use std::fmt;
 use std::str::FromStr;
 use std::sync::Arc;
 
 use ethsync::{ManageNetwork, SyncProvider};
 use ethcore::miner::{Miner, ExternalMiner};
 use ethcore::client::Client;
-use util::RotatingLogger;
+use util::{RotatingLogger, U256};
 use ethcore::account_provider::AccountProvider;
 use util::network_settings::NetworkSettings;
 
-pub use ethcore_rpc::ConfirmationsQueue;
+pub use ethcore_rpc::{ConfirmationsQueue, ExitHandle, ApiKeys, AuditLog};
+pub use ethcore_rpc::v1::EthPubSubClient;
 
 use ethcore_rpc::Extendable;
 
+#[derive(Debug, PartialEq, Clone)]
 pub enum Api {
 	Web3,
 	Net,
@@ -39,14 +42,19 @@ pub enum Api {
 	EthcoreSet,
 	Traces,
 	Rpc,
+	Admin,
 }
 
+/// Names of all the APIs.
+pub const API_NAMES: &'static [&'static str] = &["web3", "net", "eth", "personal", "signer", "ethcore", "ethcore_set", "traces", "rpc", "admin"];
+
+#[derive(Debug, PartialEq)]
 pub enum ApiError {
 	UnknownApi(String)
 }
 
+#[derive(Debug, PartialEq, Clone)]
 pub enum ApiSet {
-	SafeContext,
 	UnsafeContext,
 	List(Vec<Api>),
 }
@@ -67,6 +75,7 @@ impl FromStr for Api {
 			"ethcore_set" => Ok(EthcoreSet),
 			"traces" => Ok(Traces),
 			"rpc" => Ok(Rpc),
+			"admin" => Ok(Admin),
 			e => Err(ApiError::UnknownApi(e.into())),
 		}
 	}
@@ -84,35 +93,72 @@ pub struct Dependencies {
 	pub logger: Arc<RotatingLogger>,
 	pub settings: Arc<NetworkSettings>,
 	pub allow_pending_receipt_query: bool,
+	pub gas_cap: U256,
 	pub net_service: Arc<ManageNetwork>,
+	pub exit: Arc<ExitHandle>,
+	pub api_keys: Arc<ApiKeys>,
+	pub audit_log: Option<Arc<AuditLog>>,
+	pub pubsub: Arc<EthPubSubClient<Client>>,
+}
+
+impl Api {
+	/// Name of the API, as accepted by `from_str`.
+	pub fn name(&self) -> &'static str {
+		match *self {
+			Api::Web3 => "web3",
+			Api::Net => "net",
+			Api::Eth => "eth",
+			Api::Personal => "personal",
+			Api::Signer => "signer",
+			Api::Ethcore => "ethcore",
+			Api::EthcoreSet => "ethcore_set",
+			Api::Traces => "traces",
+			Api::Rpc => "rpc",
+			Api::Admin => "admin",
+		}
+	}
+}
+
+impl FromStr for ApiSet {
+	type Err = ApiError;
+
+	/// Parses a comma-delimited list of API names.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		s.split(',')
+			.map(str::trim)
+			.filter(|api| !api.is_empty())
+			.map(Api::from_str)
+			.collect::<Result<Vec<Api>, ApiError>>()
+			.map(ApiSet::List)
+	}
+}
+
+impl ApiSet {
+	/// Names of all the APIs in this set.
+	pub fn names(&self) -> Vec<String> {
+		list_apis(self.clone()).iter().map(|api| api.name().to_owned()).collect()
+	}
+}
+
+impl fmt::Display for ApiSet {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}", self.names().join(","))
+	}
 }
 
 fn to_modules(apis: &[Api]) -> BTreeMap<String, String> {
 	let mut modules = BTreeMap::new();
 	for api in apis {
-		let (name, version) = match *api {
-			Api::Web3 => ("web3", "1.0"),
-			Api::Net => ("net", "1.0"),
-			Api::Eth => ("eth", "1.0"),
-			Api::Personal => ("personal", "1.0"),
-			Api::Signer => ("signer", "1.0"),
-			Api::Ethcore => ("ethcore", "1.0"),
-			Api::EthcoreSet => ("ethcore_set", "1.0"),
-			Api::Traces => ("traces", "1.0"),
-			Api::Rpc => ("rpc", "1.0"),
-		};
-		modules.insert(name.into(), version.into());
+		modules.insert(api.name().into(), "1.0".into());
 	}
 	modules
 }
 
-pub fn from_str(apis: Vec<&str>) -> Vec<Api> {
-	apis.into_iter()
-		.map(Api::from_str)
-		.collect::<Result<Vec<Api>, ApiError>>()
-		.unwrap_or_else(|e| match e {
-			ApiError::UnknownApi(s) => die!("Unknown RPC API specified: {}", s),
-		})
+impl ApiSet {
+	/// Whether given API is in the set.
+	pub fn contains(&self, api: &Api) -> bool {
+		list_apis(self.clone()).contains(api)
+	}
 }
 
 fn list_apis(apis: ApiSet) -> Vec<Api> {
@@ -121,9 +167,6 @@ fn list_apis(apis: ApiSet) -> Vec<Api> {
 		ApiSet::UnsafeContext => {
 			vec![Api::Web3, Api::Net, Api::Eth, Api::Personal, Api::Ethcore, Api::Traces, Api::Rpc]
 		},
-		_ => {
-			vec![Api::Web3, Api::Net, Api::Eth, Api::Personal, Api::Signer, Api::Ethcore, Api::Traces, Api::Rpc]
-		},
 	}
 }
 
@@ -134,13 +177,13 @@ pub fn setup_rpc<T: Extendable>(server: T, deps: Arc<Dependencies>, apis: ApiSet
 	for api in &apis {
 		match *api {
 			Api::Web3 => {
-				server.add_delegate(Web3Client::new().to_delegate());
+				server.add_delegate(Web3Client::new(&deps.settings.name).to_delegate());
 			},
 			Api::Net => {
 				server.add_delegate(NetClient::new(&deps.sync).to_delegate());
 			},
 			Api::Eth => {
-				server.add_delegate(EthClient::new(&deps.client, &deps.sync, &deps.secret_store, &deps.miner, &deps.external_miner, deps.allow_pending_receipt_query).to_delegate());
+				server.add_delegate(EthClient::new(&deps.client, &deps.sync, &deps.secret_store, &deps.miner, &deps.external_miner, deps.allow_pending_receipt_query, deps.gas_cap).to_delegate());
 				server.add_delegate(EthFilterClient::new(&deps.client, &deps.miner).to_delegate());
 
 				if deps.signer_port.is_some() {
@@ -157,18 +200,21 @@ pub fn setup_rpc<T: Extendable>(server: T, deps: Arc<Dependencies>, apis: ApiSet
 			},
 			Api::Ethcore => {
 				let queue = deps.signer_port.map(|_| deps.signer_queue.clone());
-				server.add_delegate(EthcoreClient::new(&deps.client, &deps.miner, deps.logger.clone(), deps.settings.clone(), queue).to_delegate())
+				server.add_delegate(EthcoreClient::new(&deps.client, &deps.miner, &deps.sync, deps.logger.clone(), deps.settings.clone(), queue, deps.gas_cap).to_delegate())
 			},
 			Api::EthcoreSet => {
-				server.add_delegate(EthcoreSetClient::new(&deps.client, &deps.miner, &deps.net_service).to_delegate())
+				server.add_delegate(EthcoreSetClient::new(&deps.client, &deps.miner, &deps.net_service, &deps.secret_store, &deps.exit, &deps.api_keys).to_delegate())
 			},
 			Api::Traces => {
-				server.add_delegate(TracesClient::new(&deps.client, &deps.miner).to_delegate())
+				server.add_delegate(TracesClient::new(&deps.client, &deps.miner, deps.gas_cap).to_delegate())
 			},
 			Api::Rpc => {
 				let modules = to_modules(&apis);
 				server.add_delegate(RpcClient::new(modules).to_delegate());
-			}
+			},
+			Api::Admin => {
+				server.add_delegate(AdminClient::new(&deps.sync, &deps.net_service).to_delegate());
+			},
 		}
 	}
 	server
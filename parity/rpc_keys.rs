@@ -0,0 +1,850 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! JSON-RPC over HTTP requiring an API key or a shared secret with every request.
+//!
+//! The key is taken from the `X-Api-Key` header or an `Authorization: Bearer` header.
+//! Each key grants access to a subset of the configured JSON-RPC APIs and may be limited
+//! to a number of calls a day; every call of a batch counts against the quota.
+//!
+//! The shared secret is given as a bearer token or as the password of HTTP Basic
+//! authentication and grants access to all the configured APIs.
+//! If neither is required, all the configured APIs are available to anyone.
+//!
+//! Calls over the rate limits of the client's IP address fail with a JSON-RPC error.
+//! If an audit log is configured, every call is recorded in it along with the client's IP address.
+//! Large responses are compressed with gzip or deflate if enabled and accepted by the client.
+//! Given a certificate and a private key, the server speaks HTTPS only.
+//!
+//! Connections which haven't sent an authorized request yet are limited separately (also
+//! per IP address) from authorized ones, and each request must arrive within a deadline,
+//! so that unauthorized clients can't lock out authorized ones.
+//! Request bodies need a `Content-Length`; chunked requests are refused.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpListener, TcpStream};
+use std::rc::Rc;
+use std::str::{FromStr, from_utf8};
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+use rustc_serialize::base64::FromBase64;
+use flate2::Compression;
+use flate2::write::{GzEncoder, ZlibEncoder};
+use openssl::ssl::{SslContext, SslMethod, SslStream, SSL_OP_NO_SSLV2, SSL_OP_NO_SSLV3};
+use openssl::x509::X509FileType;
+use serde_json::{self, Value};
+use util::Mutex;
+use http_io::{Deadline, read_line};
+use ethcore_rpc::{KeyError, RpcServer, RateLimiter, RateLimits, LimitError};
+use rpc_apis::{self, Api, ApiSet};
+
+/// Time a client has to send a whole request, including waiting for it on a kept-alive connection.
+const REQUEST_TIMEOUT_SEC: u64 = 30;
+/// Largest accepted request body.
+const MAX_BODY_SIZE: usize = 5 * 1024 * 1024;
+/// Largest accepted size of the request line and headers together.
+const MAX_HEAD_SIZE: usize = 16 * 1024;
+/// Maximal number of connections yet to send an authorized request. Further ones are closed right away.
+const MAX_PENDING_CONNECTIONS: usize = 64;
+/// Maximal number of connections yet to send an authorized request from a single IP address.
+const MAX_PENDING_PER_IP: usize = 8;
+/// Maximal number of connections kept open after an authorized request.
+const MAX_CONNECTIONS: usize = 256;
+/// How often the listener checks whether the server was dropped, while no connections arrive.
+const ACCEPT_POLL_MS: u64 = 100;
+/// JSON-RPC error code of calls over the rate limits.
+const LIMIT_EXCEEDED_CODE: i64 = -32005;
+
+/// Accepted credentials.
+pub struct Auth {
+	/// Accept API keys managed with `ethcore_addApiKey`.
+	pub keys: bool,
+	/// Accept the shared secret.
+	pub secret: Option<String>,
+}
+
+/// Certificate and private key to serve HTTPS or WSS with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tls {
+	/// Path to the PEM-encoded certificate chain.
+	pub certificate: String,
+	/// Path to the PEM-encoded private key.
+	pub key: String,
+}
+
+impl Tls {
+	/// Loads the certificate and key into a context accepting TLS only.
+	pub fn context(&self) -> Result<SslContext, String> {
+		let mut context = try!(SslContext::new(SslMethod::Sslv23).map_err(|e| format!("Cannot create TLS context: {}", e)));
+		context.set_options(SSL_OP_NO_SSLV2 | SSL_OP_NO_SSLV3);
+		try!(context.set_certificate_chain_file(&self.certificate, X509FileType::PEM)
+			.map_err(|e| format!("{}: Cannot load TLS certificate: {}", self.certificate, e)));
+		try!(context.set_private_key_file(&self.key, X509FileType::PEM)
+			.map_err(|e| format!("{}: Cannot load TLS private key: {}", self.key, e)));
+		try!(context.check_private_key().map_err(|e| format!("{}: TLS private key doesn't match the certificate: {}", self.key, e)));
+		Ok(context)
+	}
+}
+
+impl Auth {
+	fn is_required(&self) -> bool {
+		self.keys || self.secret.is_some()
+	}
+}
+
+/// Settings of the key-checking server.
+pub struct Config {
+	/// Address to listen on.
+	pub addr: SocketAddr,
+	/// APIs available over HTTP. Keys can't grant access to anything else.
+	pub apis: Vec<String>,
+	/// Accepted credentials.
+	pub auth: Auth,
+	/// Limits of calls per client IP address.
+	pub limits: Option<RateLimits>,
+	/// Origins allowed to make cross-origin requests.
+	pub cors: Option<Vec<String>>,
+	/// Allowed values of the Host header; the listening address is always allowed. `None` allows any.
+	pub hosts: Option<Vec<String>>,
+	/// Responses larger than this are compressed, if the client accepts it.
+	pub compress_above: Option<usize>,
+	/// Serve over HTTPS only, with given certificate.
+	pub tls: Option<Tls>,
+}
+
+/// Key-checking JSON-RPC server. Stops serving once dropped.
+pub struct KeyedRpc {
+	deps: Arc<rpc_apis::Dependencies>,
+	// APIs exposed over HTTP; keys can't grant access to anything else.
+	apis: Vec<String>,
+	auth: Auth,
+	limiter: Option<RateLimiter>,
+	cors: Option<Vec<String>>,
+	// allowed values of the Host header, `None` allows any.
+	hosts: Option<Vec<String>>,
+	// responses larger than this are compressed, if the client accepts it.
+	compress_above: Option<usize>,
+	// connections are accepted over TLS if set.
+	tls: Option<SslContext>,
+	// handlers by the set of APIs they expose.
+	handlers: Mutex<HashMap<Vec<String>, Arc<RpcServer>>>,
+}
+
+// the parts of a request the server cares about.
+#[derive(Debug, Default, PartialEq)]
+struct Request {
+	method: String,
+	key: Option<String>,
+	// password of HTTP Basic authentication.
+	password: Option<String>,
+	origin: Option<String>,
+	host: Option<String>,
+	accept_encoding: Option<String>,
+	// whether the client wants the connection kept open for further requests.
+	keep_alive: bool,
+	body: Vec<u8>,
+}
+
+// content codings the server can apply to responses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Encoding {
+	Gzip,
+	Deflate,
+}
+
+impl Encoding {
+	fn name(&self) -> &'static str {
+		match *self {
+			Encoding::Gzip => "gzip",
+			Encoding::Deflate => "deflate",
+		}
+	}
+
+	fn encode(&self, data: &[u8]) -> io::Result<Vec<u8>> {
+		match *self {
+			Encoding::Gzip => {
+				let mut encoder = GzEncoder::new(Vec::new(), Compression::Default);
+				try!(encoder.write_all(data));
+				encoder.finish()
+			},
+			Encoding::Deflate => {
+				let mut encoder = ZlibEncoder::new(Vec::new(), Compression::Default);
+				try!(encoder.write_all(data));
+				encoder.finish()
+			},
+		}
+	}
+}
+
+/// Starts serving JSON-RPC with given settings.
+pub fn start(config: Config, deps: Arc<rpc_apis::Dependencies>) -> Result<Arc<KeyedRpc>, String> {
+	let addr = config.addr;
+	let tls = match config.tls {
+		Some(tls) => Some(try!(tls.context())),
+		None => None,
+	};
+	let listener = try!(TcpListener::bind(&addr).map_err(|e| format!("Cannot bind JSON-RPC server to {}: {}", addr, e)));
+	// polled, so that the thread notices the server was dropped without waiting for a connection.
+	try!(listener.set_nonblocking(true).map_err(|e| format!("Cannot set up JSON-RPC server on {}: {}", addr, e)));
+	info!(target: "rpc", "Serving JSON-RPC with authorization on {}://{}", if tls.is_some() { "https" } else { "http" }, addr);
+
+	let server = Arc::new(KeyedRpc {
+		deps: deps,
+		apis: config.apis,
+		auth: config.auth,
+		limiter: config.limits.map(RateLimiter::new),
+		cors: config.cors,
+		hosts: config.hosts.map(|hosts| with_local_hosts(hosts, &addr)),
+		compress_above: config.compress_above,
+		tls: tls,
+		handlers: Mutex::new(HashMap::new()),
+	});
+	let weak: Weak<KeyedRpc> = Arc::downgrade(&server);
+	let connections = Arc::new(Mutex::new(Connections::default()));
+	try!(thread::Builder::new().name("jsonrpc-keys".into()).spawn(move || {
+		loop {
+			let (stream, peer) = match listener.accept() {
+				Ok(accepted) => accepted,
+				Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+					if weak.upgrade().is_none() {
+						break;
+					}
+					thread::sleep(Duration::from_millis(ACCEPT_POLL_MS));
+					continue;
+				},
+				Err(e) => {
+					debug!(target: "rpc", "Error accepting connection: {}", e);
+					thread::sleep(Duration::from_millis(ACCEPT_POLL_MS));
+					continue;
+				},
+			};
+			let server = match weak.upgrade() {
+				Some(server) => server,
+				None => break,
+			};
+			let connection = match Connection::open(connections.clone(), peer.ip()) {
+				Some(connection) => connection,
+				None => {
+					debug!(target: "rpc", "Closing JSON-RPC connection of {}, too many connections waiting for a request", peer.ip());
+					continue;
+				},
+			};
+
+			let spawned = thread::Builder::new().name("jsonrpc-keys-conn".into()).spawn(move || {
+				if let Err(e) = stream.set_nonblocking(false).and_then(|_| server.serve(stream, connection)) {
+					debug!(target: "rpc", "Error serving JSON-RPC request: {}", e);
+				}
+			});
+			if let Err(e) = spawned {
+				warn!(target: "rpc", "Error creating JSON-RPC connection thread: {}", e);
+			}
+		}
+	}).map_err(|e| format!("Error creating JSON-RPC thread: {}", e)));
+
+	Ok(server)
+}
+
+// open connections, limited separately by whether they sent an authorized request yet.
+#[derive(Default)]
+struct Connections {
+	pending: usize,
+	pending_by_ip: HashMap<IpAddr, usize>,
+	authorized: usize,
+}
+
+impl Connections {
+	fn release_pending(&mut self, ip: IpAddr) {
+		self.pending -= 1;
+		let last = match self.pending_by_ip.get_mut(&ip) {
+			Some(count) => {
+				*count -= 1;
+				*count == 0
+			},
+			None => false,
+		};
+		if last {
+			self.pending_by_ip.remove(&ip);
+		}
+	}
+}
+
+// an open connection, counted until dropped.
+struct Connection {
+	connections: Arc<Mutex<Connections>>,
+	ip: IpAddr,
+	authorized: bool,
+}
+
+impl Connection {
+	// `None` if too many connections are waiting for a request, overall or from the IP address.
+	fn open(connections: Arc<Mutex<Connections>>, ip: IpAddr) -> Option<Connection> {
+		{
+			let mut open = connections.lock();
+			let from_ip = open.pending_by_ip.get(&ip).cloned().unwrap_or(0);
+			if open.pending >= MAX_PENDING_CONNECTIONS || from_ip >= MAX_PENDING_PER_IP {
+				return None;
+			}
+			open.pending += 1;
+			open.pending_by_ip.insert(ip, from_ip + 1);
+		}
+		Some(Connection {
+			connections: connections,
+			ip: ip,
+			authorized: false,
+		})
+	}
+
+	// counts the connection as authorized. Returns false if it can't be kept open.
+	fn authorize(&mut self) -> bool {
+		if self.authorized {
+			return true;
+		}
+		let mut open = self.connections.lock();
+		if open.authorized >= MAX_CONNECTIONS {
+			return false;
+		}
+		open.release_pending(self.ip);
+		open.authorized += 1;
+		self.authorized = true;
+		true
+	}
+}
+
+impl Drop for Connection {
+	fn drop(&mut self) {
+		let mut open = self.connections.lock();
+		match self.authorized {
+			true => open.authorized -= 1,
+			false => open.release_pending(self.ip),
+		}
+	}
+}
+
+fn request_deadline() -> Instant {
+	Instant::now() + Duration::from_secs(REQUEST_TIMEOUT_SEC)
+}
+
+impl KeyedRpc {
+	fn serve(&self, stream: TcpStream, connection: Connection) -> io::Result<()> {
+		try!(stream.set_write_timeout(Some(Duration::from_secs(REQUEST_TIMEOUT_SEC))));
+		let ip = connection.ip;
+		let until = Rc::new(Cell::new(request_deadline()));
+		let stream = Deadline::new(stream, until.clone());
+		match self.tls {
+			Some(ref context) => {
+				let stream = try!(SslStream::accept(context, stream).map_err(|e| io::Error::new(io::ErrorKind::Other, e)));
+				self.serve_connection(ip, stream, &until, connection)
+			},
+			None => self.serve_connection(ip, stream, &until, connection),
+		}
+	}
+
+	// serves requests until the client or the server closes the connection.
+	fn serve_connection<S: Read + Write>(&self, ip: IpAddr, stream: S, until: &Cell<Instant>, mut connection: Connection) -> io::Result<()> {
+		let mut reader = BufReader::new(stream);
+		loop {
+			if try!(reader.fill_buf()).is_empty() {
+				return Ok(());
+			}
+			if !try!(self.serve_request(ip, &mut reader, &mut connection)) {
+				return Ok(());
+			}
+			until.set(request_deadline());
+		}
+	}
+
+	// serves a single request. Returns whether to keep the connection open.
+	fn serve_request<S: Read + Write>(&self, ip: IpAddr, reader: &mut BufReader<S>, connection: &mut Connection) -> io::Result<bool> {
+		let request = match try!(read_request(reader)) {
+			Ok(request) => request,
+			Err(status) => return respond(reader.get_mut(), status, None, "").map(|_| false),
+		};
+		let stream = reader.get_mut();
+		if !is_host_allowed(&self.hosts, &request.host) {
+			return respond(stream, "403 Forbidden", None, "Provided Host header is not whitelisted.").map(|_| false);
+		}
+		let origin = self.allowed_origin(&request.origin);
+
+		if request.method == "OPTIONS" {
+			return respond(stream, "200 OK", origin, "").map(|_| false);
+		}
+		if request.method != "POST" {
+			return respond(stream, "405 Method Not Allowed", origin, "Only POST is supported.").map(|_| false);
+		}
+
+		if let Some(ref audit_log) = self.deps.audit_log {
+			audit_log.log("http", &format!("{}", ip), &String::from_utf8_lossy(&request.body));
+		}
+
+		if let Some(ref limiter) = self.limiter {
+			let calls = calls(&request.body);
+			let methods = calls.iter().map(|&(ref method, _)| method.clone()).collect::<Vec<_>>();
+			if let Err(e) = limiter.check(ip, &methods) {
+				debug!(target: "rpc", "Refusing request of {}: {}", ip, e);
+				return respond(stream, "200 OK", origin, &limit_exceeded(&request.body, &calls, &e)).map(|_| false);
+			}
+		}
+
+		let apis = if !self.auth.is_required() || self.has_secret(&request) {
+			self.apis.clone()
+		} else {
+			let key = match request.key {
+				Some(ref key) if self.auth.keys => key,
+				_ => return respond(stream, "401 Unauthorized", origin, self.missing_credentials()).map(|_| false),
+			};
+			match self.deps.api_keys.authorize(key, count_calls(&request.body)) {
+				Ok(apis) => apis,
+				Err(KeyError::UnknownKey) => return respond(stream, "401 Unauthorized", origin, "Unknown API key.").map(|_| false),
+				Err(KeyError::QuotaExceeded(quota)) =>
+					return respond(stream, "429 Too Many Requests", origin, &format!("Daily quota of {} calls exceeded.", quota)).map(|_| false),
+			}
+		};
+		let keep_alive = request.keep_alive && connection.authorize();
+
+		let body = String::from_utf8_lossy(&request.body).into_owned();
+		let response = self.handler(apis).handle_request(&body).unwrap_or_else(String::new);
+		let encoding = match self.compress_above {
+			Some(threshold) if response.len() > threshold => accepted_encoding(&request.accept_encoding),
+			_ => None,
+		};
+		try!(match encoding {
+			Some(encoding) => {
+				let compressed = try!(encoding.encode(response.as_bytes()));
+				write_response(stream, "200 OK", origin, Some(encoding), &compressed, keep_alive)
+			},
+			None => write_response(stream, "200 OK", origin, None, response.as_bytes(), keep_alive),
+		});
+		Ok(keep_alive)
+	}
+
+	fn has_secret(&self, request: &Request) -> bool {
+		let secret = match self.auth.secret {
+			Some(ref secret) => secret,
+			None => return false,
+		};
+		request.key.iter().chain(request.password.iter()).any(|given| constant_time_eq(given.as_bytes(), secret.as_bytes()))
+	}
+
+	fn missing_credentials(&self) -> &'static str {
+		match (self.auth.keys, self.auth.secret.is_some()) {
+			(true, true) => "API key or secret required.",
+			(true, false) => "API key required.",
+			_ => "Secret required.",
+		}
+	}
+
+	// handler exposing the APIs the key grants access to, limited to the ones available over HTTP.
+	fn handler(&self, apis: Vec<String>) -> Arc<RpcServer> {
+		let mut apis: Vec<String> = apis.into_iter().filter(|api| self.apis.contains(api)).collect();
+		apis.sort();
+		apis.dedup();
+
+		let mut handlers = self.handlers.lock();
+		if let Some(handler) = handlers.get(&apis) {
+			return handler.clone();
+		}
+		let list = apis.iter().filter_map(|api| Api::from_str(api).ok()).collect();
+		let handler = Arc::new(rpc_apis::setup_rpc(RpcServer::new(), self.deps.clone(), ApiSet::List(list)));
+		handlers.insert(apis, handler.clone());
+		handler
+	}
+
+	fn allowed_origin<'a>(&self, origin: &'a Option<String>) -> Option<&'a str> {
+		match (self.cors.as_ref(), origin.as_ref()) {
+			(Some(cors), Some(origin)) if cors.iter().any(|c| c == "*" || c == origin) => Some(origin),
+			_ => None,
+		}
+	}
+}
+
+// reads the request line, headers and body. Returns the status to respond with if
+// the head or the body is too large or the body's length isn't given.
+fn read_request<R: BufRead>(reader: &mut R) -> io::Result<Result<Request, &'static str>> {
+	const HEAD_TOO_LARGE: &'static str = "431 Request Header Fields Too Large";
+	const BAD_REQUEST: &'static str = "400 Bad Request";
+
+	let mut line = String::new();
+	let mut head_left = MAX_HEAD_SIZE;
+	if !try!(read_line(reader, &mut line, &mut head_left)) {
+		return Ok(Err(HEAD_TOO_LARGE));
+	}
+	let (method, version) = {
+		let mut parts = line.split_whitespace();
+		(parts.next().unwrap_or("").to_owned(), parts.nth(1).unwrap_or("").to_owned())
+	};
+	let mut request = Request {
+		method: method,
+		// persistent by default since HTTP/1.1 only.
+		keep_alive: version != "HTTP/1.0",
+		..Default::default()
+	};
+
+	let mut content_length = None;
+	let mut transfer_encoding = false;
+	loop {
+		if !try!(read_line(reader, &mut line, &mut head_left)) {
+			return Ok(Err(HEAD_TOO_LARGE));
+		}
+		if line.trim().is_empty() {
+			break;
+		}
+		let mut parts = line.splitn(2, ':');
+		let name = parts.next().unwrap_or("").trim().to_lowercase();
+		let value = parts.next().unwrap_or("").trim();
+		match name.as_str() {
+			"content-length" => match value.parse::<usize>() {
+				Ok(length) if content_length.map_or(true, |l| l == length) => content_length = Some(length),
+				_ => return Ok(Err(BAD_REQUEST)),
+			},
+			"transfer-encoding" => transfer_encoding = true,
+			"connection" => match value.to_lowercase().as_str() {
+				"close" => request.keep_alive = false,
+				"keep-alive" => request.keep_alive = true,
+				_ => {},
+			},
+			"x-api-key" => request.key = Some(value.to_owned()),
+			"authorization" if value.starts_with("Bearer ") && request.key.is_none() =>
+				request.key = Some(value["Bearer ".len()..].trim().to_owned()),
+			"authorization" if value.starts_with("Basic ") =>
+				request.password = basic_password(value["Basic ".len()..].trim()),
+			"origin" => request.origin = Some(value.to_owned()),
+			"host" => request.host = Some(value.to_owned()),
+			"accept-encoding" => request.accept_encoding = Some(value.to_owned()),
+			_ => {},
+		}
+	}
+
+	let content_length = match content_length {
+		_ if transfer_encoding => return Ok(Err("411 Length Required")),
+		Some(length) => length,
+		None if request.method == "POST" => return Ok(Err("411 Length Required")),
+		None => 0,
+	};
+	if content_length > MAX_BODY_SIZE {
+		return Ok(Err("413 Payload Too Large"));
+	}
+	request.body.resize(content_length, 0);
+	try!(reader.read_exact(&mut request.body));
+	Ok(Ok(request))
+}
+
+// password part of base64-encoded `user:password` credentials.
+fn basic_password(credentials: &str) -> Option<String> {
+	let decoded = match credentials.from_base64().ok().and_then(|c| String::from_utf8(c).ok()) {
+		Some(decoded) => decoded,
+		None => return None,
+	};
+	decoded.splitn(2, ':').nth(1).map(|password| password.to_owned())
+}
+
+// compares secrets in time independent of the position of the first difference.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+	if a.len() != b.len() {
+		return false;
+	}
+	a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// adds the addresses the server is reachable at locally.
+fn with_local_hosts(mut hosts: Vec<String>, addr: &SocketAddr) -> Vec<String> {
+	hosts.push(format!("localhost:{}", addr.port()));
+	hosts.push(format!("127.0.0.1:{}", addr.port()));
+	hosts.push(format!("{}", addr));
+	hosts
+}
+
+// checks the Host header; entries without a port match any port.
+fn is_host_allowed(hosts: &Option<Vec<String>>, host: &Option<String>) -> bool {
+	let (hosts, host) = match (hosts.as_ref(), host.as_ref()) {
+		(None, _) => return true,
+		(Some(_), None) => return false,
+		(Some(hosts), Some(host)) => (hosts, host.to_lowercase()),
+	};
+	let hostname = host.rsplitn(2, ':').last().unwrap_or("");
+	hosts.iter().any(|allowed| {
+		let allowed = allowed.to_lowercase();
+		allowed == host || (!allowed.contains(':') && allowed == hostname)
+	})
+}
+
+// method and id of every call in a request.
+fn calls(body: &[u8]) -> Vec<(String, Value)> {
+	fn call(value: &Value) -> (String, Value) {
+		let method = value.find("method").and_then(Value::as_str).unwrap_or("").to_owned();
+		(method, value.find("id").cloned().unwrap_or(Value::Null))
+	}
+
+	match from_utf8(body).ok().and_then(|body| serde_json::from_str::<Value>(body).ok()) {
+		Some(Value::Array(calls)) => calls.iter().map(call).collect(),
+		Some(value) => vec![call(&value)],
+		None => vec![(String::new(), Value::Null)],
+	}
+}
+
+// number of calls in a request; every call of a batch counts.
+fn count_calls(body: &[u8]) -> u64 {
+	calls(body).len() as u64
+}
+
+// error response to every call of a request.
+fn limit_exceeded(body: &[u8], calls: &[(String, Value)], error: &LimitError) -> String {
+	let message = serde_json::to_string(&format!("{}", error)).expect("String serialization is infallible; qed");
+	let responses = calls.iter().map(|&(_, ref id)| format!(
+		r#"{{"jsonrpc":"2.0","error":{{"code":{},"message":{}}},"id":{}}}"#,
+		LIMIT_EXCEEDED_CODE,
+		message,
+		serde_json::to_string(id).expect("Value serialization is infallible; qed")
+	)).collect::<Vec<_>>();
+
+	let is_batch = body.iter().find(|b| !(**b as char).is_whitespace()) == Some(&b'[');
+	match is_batch {
+		true => format!("[{}]", responses.join(",")),
+		false => responses.join(","),
+	}
+}
+
+// preferred of the encodings accepted by the client, if any. Prefers gzip.
+fn accepted_encoding(accept_encoding: &Option<String>) -> Option<Encoding> {
+	let accept_encoding = match *accept_encoding {
+		Some(ref accept_encoding) => accept_encoding.to_lowercase(),
+		None => return None,
+	};
+	let accepted = accept_encoding.split(',')
+		.filter_map(|coding| {
+			let mut parts = coding.split(';').map(str::trim);
+			let name = parts.next().unwrap_or("");
+			// codings with `q=0` are not acceptable.
+			let quality = parts.filter_map(|p| match p.starts_with("q=") {
+				true => p[2..].parse::<f32>().ok(),
+				false => None,
+			}).next().unwrap_or(1.0);
+			match quality > 0.0 {
+				true => Some(name.to_owned()),
+				false => None,
+			}
+		})
+		.collect::<Vec<_>>();
+
+	[Encoding::Gzip, Encoding::Deflate].iter()
+		.find(|encoding| accepted.iter().any(|name| name == encoding.name() || name == "*"))
+		.cloned()
+}
+
+// responds and closes the connection.
+fn respond<W: Write>(stream: &mut W, status: &str, origin: Option<&str>, body: &str) -> io::Result<()> {
+	write_response(stream, status, origin, None, body.as_bytes(), false)
+}
+
+fn write_response<W: Write>(stream: &mut W, status: &str, origin: Option<&str>, encoding: Option<Encoding>, body: &[u8], keep_alive: bool) -> io::Result<()> {
+	let content_type = match status {
+		"200 OK" => "application/json",
+		_ => "text/plain",
+	};
+	let cors = match origin {
+		Some(origin) => format!("Access-Control-Allow-Origin: {}\r\nAccess-Control-Allow-Headers: Content-Type, X-Api-Key, Authorization\r\nAccess-Control-Allow-Methods: POST, OPTIONS\r\n", origin),
+		None => String::new(),
+	};
+	let encoding = match encoding {
+		Some(encoding) => format!("Content-Encoding: {}\r\nVary: Accept-Encoding\r\n", encoding.name()),
+		None => String::new(),
+	};
+	let connection = match keep_alive {
+		true => "keep-alive",
+		false => "close",
+	};
+	let head = format!("HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\n{}{}Connection: {}\r\n\r\n", status, content_type, body.len(), cors, encoding, connection);
+	try!(stream.write_all(head.as_bytes()));
+	stream.write_all(body)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::io::Read;
+	use std::net::{IpAddr, SocketAddr};
+	use std::str::FromStr;
+	use std::sync::Arc;
+	use flate2::read::ZlibDecoder;
+	use util::Mutex;
+	use ethcore_rpc::LimitError;
+	use super::{read_request, count_calls, is_host_allowed, with_local_hosts, constant_time_eq, calls, limit_exceeded,
+		accepted_encoding, Encoding, Connection, Connections, MAX_HEAD_SIZE, MAX_PENDING_PER_IP};
+
+	#[test]
+	fn reads_key_from_headers() {
+		let raw = b"POST / HTTP/1.1\r\nX-Api-Key: abc\r\nContent-Length: 2\r\n\r\n{}";
+		let request = read_request(&mut &raw[..]).unwrap().unwrap();
+		assert_eq!(request.method, "POST");
+		assert_eq!(request.key, Some("abc".to_owned()));
+		assert_eq!(request.body, b"{}".to_vec());
+
+		let raw = b"POST / HTTP/1.1\r\nauthorization: Bearer def\r\nContent-Length: 0\r\n\r\n";
+		assert_eq!(read_request(&mut &raw[..]).unwrap().unwrap().key, Some("def".to_owned()));
+
+		let raw = b"POST / HTTP/1.1\r\nContent-Length: 99999999\r\n\r\n";
+		assert_eq!(read_request(&mut &raw[..]).unwrap(), Err("413 Payload Too Large"));
+	}
+
+	#[test]
+	fn requires_body_length() {
+		let raw = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n2\r\n{}\r\n0\r\n\r\n";
+		assert_eq!(read_request(&mut &raw[..]).unwrap(), Err("411 Length Required"));
+
+		let raw = b"POST / HTTP/1.1\r\n\r\n{}";
+		assert_eq!(read_request(&mut &raw[..]).unwrap(), Err("411 Length Required"));
+
+		let raw = b"POST / HTTP/1.1\r\nContent-Length: two\r\n\r\n{}";
+		assert_eq!(read_request(&mut &raw[..]).unwrap(), Err("400 Bad Request"));
+
+		let raw = b"POST / HTTP/1.1\r\nContent-Length: 2\r\nContent-Length: 3\r\n\r\n{}";
+		assert_eq!(read_request(&mut &raw[..]).unwrap(), Err("400 Bad Request"));
+	}
+
+	#[test]
+	fn reads_consecutive_requests() {
+		let raw = b"POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}POST / HTTP/1.1\r\nConnection: close\r\nContent-Length: 3\r\n\r\n[1]";
+		let mut reader = &raw[..];
+
+		let first = read_request(&mut reader).unwrap().unwrap();
+		assert!(first.keep_alive);
+		assert_eq!(first.body, b"{}".to_vec());
+		let second = read_request(&mut reader).unwrap().unwrap();
+		assert!(!second.keep_alive);
+		assert_eq!(second.body, b"[1]".to_vec());
+
+		let raw = b"POST / HTTP/1.0\r\nContent-Length: 0\r\n\r\n";
+		assert!(!read_request(&mut &raw[..]).unwrap().unwrap().keep_alive);
+	}
+
+	#[test]
+	fn limits_pending_connections_per_ip() {
+		let connections = Arc::new(Mutex::new(Connections::default()));
+		let ip = IpAddr::from_str("10.0.0.1").unwrap();
+		let other = IpAddr::from_str("10.0.0.2").unwrap();
+
+		let mut open = (0..MAX_PENDING_PER_IP).map(|_| Connection::open(connections.clone(), ip).unwrap()).collect::<Vec<_>>();
+		assert!(Connection::open(connections.clone(), ip).is_none());
+		assert!(Connection::open(connections.clone(), other).is_some());
+
+		// authorized connections don't count against the limit.
+		assert!(open[0].authorize());
+		assert!(Connection::open(connections.clone(), ip).is_some());
+
+		open.clear();
+		assert_eq!(connections.lock().pending, 0);
+		assert_eq!(connections.lock().authorized, 0);
+		assert!(connections.lock().pending_by_ip.is_empty());
+	}
+
+	#[test]
+	fn bounds_request_head() {
+		let mut raw = b"POST / HTTP/1.1\r\nX-Padding: ".to_vec();
+		raw.extend(vec![b'a'; MAX_HEAD_SIZE]);
+		raw.extend(b"\r\nContent-Length: 0\r\n\r\n".iter().cloned());
+		assert_eq!(read_request(&mut &raw[..]).unwrap(), Err("431 Request Header Fields Too Large"));
+	}
+
+	#[test]
+	fn reads_basic_auth_password() {
+		// "user:s3cr:et"
+		let raw = b"POST / HTTP/1.1\r\nAuthorization: Basic dXNlcjpzM2NyOmV0\r\nContent-Length: 0\r\n\r\n";
+		let request = read_request(&mut &raw[..]).unwrap().unwrap();
+		assert_eq!(request.password, Some("s3cr:et".to_owned()));
+		assert_eq!(request.key, None);
+
+		let raw = b"POST / HTTP/1.1\r\nAuthorization: Basic !!!\r\nContent-Length: 0\r\n\r\n";
+		assert_eq!(read_request(&mut &raw[..]).unwrap().unwrap().password, None);
+	}
+
+	#[test]
+	fn negotiates_encoding() {
+		let accept = |a: &str| Some(a.to_owned());
+
+		assert_eq!(accepted_encoding(&None), None);
+		assert_eq!(accepted_encoding(&accept("identity")), None);
+		assert_eq!(accepted_encoding(&accept("deflate, gzip")), Some(Encoding::Gzip));
+		assert_eq!(accepted_encoding(&accept("GZIP;q=0, deflate;q=0.5")), Some(Encoding::Deflate));
+		assert_eq!(accepted_encoding(&accept("*")), Some(Encoding::Gzip));
+
+		let raw = b"POST / HTTP/1.1\r\nAccept-Encoding: gzip, deflate\r\nContent-Length: 0\r\n\r\n";
+		assert_eq!(read_request(&mut &raw[..]).unwrap().unwrap().accept_encoding, accept("gzip, deflate"));
+	}
+
+	#[test]
+	fn compresses_responses() {
+		let response = (0..100).map(|_| r#"{"jsonrpc":"2.0","result":"0x00","id":1}"#).collect::<Vec<_>>().join(",");
+
+		let gzipped = Encoding::Gzip.encode(response.as_bytes()).unwrap();
+		assert_eq!(&gzipped[..2], &[0x1f, 0x8b]);
+		assert!(gzipped.len() < response.len());
+
+		let deflated = Encoding::Deflate.encode(response.as_bytes()).unwrap();
+		let mut inflated = String::new();
+		ZlibDecoder::new(&deflated[..]).read_to_string(&mut inflated).unwrap();
+		assert_eq!(inflated, response);
+	}
+
+	#[test]
+	fn compares_secrets() {
+		assert!(constant_time_eq(b"secret", b"secret"));
+		assert!(!constant_time_eq(b"secret", b"secreT"));
+		assert!(!constant_time_eq(b"secret", b"secret2"));
+	}
+
+	#[test]
+	fn validates_host_header() {
+		let addr = SocketAddr::from_str("127.0.0.1:8545").unwrap();
+		let hosts = Some(with_local_hosts(vec!["ethcore.io".to_owned()], &addr));
+		let host = |h: &str| Some(h.to_owned());
+
+		assert!(is_host_allowed(&None, &None));
+		assert!(is_host_allowed(&None, &host("evil.com")));
+		assert!(is_host_allowed(&hosts, &host("localhost:8545")));
+		assert!(is_host_allowed(&hosts, &host("127.0.0.1:8545")));
+		assert!(is_host_allowed(&hosts, &host("ethcore.io")));
+		assert!(is_host_allowed(&hosts, &host("Ethcore.io:8080")));
+		assert!(!is_host_allowed(&hosts, &host("localhost:8080")));
+		assert!(!is_host_allowed(&hosts, &host("evil.com")));
+		assert!(!is_host_allowed(&hosts, &None));
+	}
+
+	#[test]
+	fn counts_batched_calls() {
+		assert_eq!(count_calls(br#"{"jsonrpc":"2.0","method":"eth_blockNumber","id":1}"#), 1);
+		assert_eq!(count_calls(br#"[{"method":"a"},{"method":"b"},{"method":"c"}]"#), 3);
+		assert_eq!(count_calls(b"garbage"), 1);
+	}
+
+	#[test]
+	fn responds_to_calls_over_limits() {
+		let error = LimitError::Method("eth_getLogs".to_owned(), 2);
+
+		let body = br#"{"jsonrpc":"2.0","method":"eth_getLogs","params":[],"id":7}"#;
+		assert_eq!(
+			limit_exceeded(body, &calls(body), &error),
+			r#"{"jsonrpc":"2.0","error":{"code":-32005,"message":"Rate limit of 2 calls per second to eth_getLogs exceeded."},"id":7}"#
+		);
+
+		let body = br#" [{"method":"eth_getLogs","id":"a"},{"method":"eth_blockNumber"}]"#;
+		assert_eq!(calls(body).iter().map(|c| c.0.as_str()).collect::<Vec<_>>(), vec!["eth_getLogs", "eth_blockNumber"]);
+		assert_eq!(
+			limit_exceeded(body, &calls(body), &error),
+			r#"[{"jsonrpc":"2.0","error":{"code":-32005,"message":"Rate limit of 2 calls per second to eth_getLogs exceeded."},"id":"a"},{"jsonrpc":"2.0","error":{"code":-32005,"message":"Rate limit of 2 calls per second to eth_getLogs exceeded."},"id":null}]"#
+		);
+	}
+}
@@ -0,0 +1,213 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Scheduler of recurring maintenance tasks.
+//!
+//! Tasks are scheduled with `--schedule`, a `;`-separated list of `TASK=MINUTE HOUR` entries.
+//! Like in crontab, `MINUTE` and `HOUR` are a number, `*` for any, `*/N` for every N-th
+//! or a comma-separated list of those, in UTC. E.g. `snapshot=30 4; rotate-log=0 */6`
+//! takes a snapshot at 04:30 and rotates the log file every six hours.
+
+use std::str::FromStr;
+use std::sync::{Arc, Weak};
+use std::thread;
+use std::time::Duration;
+use time;
+use ethcore::client::{BlockChainClient, BlockID, Client};
+use ethcore_logger::rotate_log_file;
+
+/// A maintenance task.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Task {
+	/// Take a snapshot of the latest block.
+	Snapshot,
+	/// Move the log file aside and continue in a new one.
+	RotateLog,
+}
+
+impl FromStr for Task {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, String> {
+		match s {
+			"snapshot" => Ok(Task::Snapshot),
+			"rotate-log" => Ok(Task::RotateLog),
+			other => Err(format!("{}: Unknown task. Expected snapshot or rotate-log.", other)),
+		}
+	}
+}
+
+// values of a single field of a schedule.
+#[derive(Debug, PartialEq, Clone)]
+enum Field {
+	Any,
+	Every(u32),
+	At(u32),
+}
+
+impl Field {
+	fn parse(s: &str, max: u32) -> Result<Vec<Field>, String> {
+		s.split(',').map(|part| {
+			let field = match part {
+				"*" => Field::Any,
+				_ if part.starts_with("*/") => Field::Every(try!(part[2..].parse().map_err(|_| format!("{}: Invalid step.", part)))),
+				_ => Field::At(try!(part.parse().map_err(|_| format!("{}: Invalid value.", part)))),
+			};
+			match field {
+				Field::Every(0) => Err(format!("{}: Step must be positive.", part)),
+				Field::At(value) | Field::Every(value) if value > max => Err(format!("{}: Value must be at most {}.", part, max)),
+				field => Ok(field),
+			}
+		}).collect()
+	}
+
+	fn matches(&self, value: u32) -> bool {
+		match *self {
+			Field::Any => true,
+			Field::Every(step) => value % step == 0,
+			Field::At(at) => value == at,
+		}
+	}
+}
+
+/// Times at which a task runs, to the minute.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Schedule {
+	minute: Vec<Field>,
+	hour: Vec<Field>,
+}
+
+impl Schedule {
+	/// Whether the task runs at the given hour and minute.
+	pub fn matches(&self, hour: u32, minute: u32) -> bool {
+		self.hour.iter().any(|f| f.matches(hour)) && self.minute.iter().any(|f| f.matches(minute))
+	}
+}
+
+impl FromStr for Schedule {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, String> {
+		let fields: Vec<_> = s.split_whitespace().collect();
+		if fields.len() != 2 {
+			return Err(format!("{}: Invalid schedule. Expected MINUTE HOUR.", s));
+		}
+		Ok(Schedule {
+			minute: try!(Field::parse(fields[0], 59)),
+			hour: try!(Field::parse(fields[1], 23)),
+		})
+	}
+}
+
+/// Parses a `;`-separated list of `TASK=MINUTE HOUR` entries.
+pub fn parse(spec: &str) -> Result<Vec<(Task, Schedule)>, String> {
+	spec.split(';')
+		.map(|entry| entry.trim())
+		.filter(|entry| !entry.is_empty())
+		.map(|entry| {
+			let mut parts = entry.splitn(2, '=');
+			let task = parts.next().expect("splitn always yields at least one item; qed");
+			match parts.next() {
+				Some(schedule) => Ok((try!(task.trim().parse::<Task>()), try!(schedule.parse::<Schedule>()))),
+				None => Err(format!("{}: Invalid schedule entry. Expected TASK=MINUTE HOUR.", entry)),
+			}
+		})
+		.collect()
+}
+
+/// Runs scheduled tasks. Stops once dropped.
+pub struct Scheduler {
+	tasks: Vec<(Task, Schedule)>,
+	client: Arc<Client>,
+}
+
+/// Starts running `tasks` of `client`.
+pub fn start(tasks: Vec<(Task, Schedule)>, client: Arc<Client>) -> Result<Arc<Scheduler>, String> {
+	let scheduler = Arc::new(Scheduler {
+		tasks: tasks,
+		client: client,
+	});
+
+	let weak: Weak<Scheduler> = Arc::downgrade(&scheduler);
+	try!(thread::Builder::new().name("scheduler".into()).spawn(move || loop {
+		// wake up at the start of every minute.
+		let now = time::now_utc();
+		thread::sleep(Duration::from_secs(60 - now.tm_sec as u64 % 60));
+		let now = time::now_utc();
+		match weak.upgrade() {
+			Some(scheduler) => scheduler.run_due(now.tm_hour as u32, now.tm_min as u32),
+			None => break,
+		}
+	}).map_err(|e| format!("Error creating scheduler thread: {}", e)));
+
+	Ok(scheduler)
+}
+
+impl Scheduler {
+	/// Runs the tasks scheduled at the given hour and minute.
+	pub fn run_due(&self, hour: u32, minute: u32) {
+		for &(task, ref schedule) in &self.tasks {
+			if schedule.matches(hour, minute) {
+				self.run(task);
+			}
+		}
+	}
+
+	fn run(&self, task: Task) {
+		match task {
+			Task::Snapshot => match self.client.request_snapshot(BlockID::Latest) {
+				Some(number) => info!(target: "scheduler", "Taking scheduled snapshot at block #{}", number),
+				None => warn!(target: "scheduler", "Skipping scheduled snapshot: one is being taken already."),
+			},
+			Task::RotateLog => match rotate_log_file() {
+				Ok(Some(path)) => info!(target: "scheduler", "Rotated log file, previous one moved to {}", path),
+				Ok(None) => {},
+				Err(e) => warn!(target: "scheduler", "Cannot rotate log file: {}", e),
+			},
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{parse, Task, Schedule};
+
+	#[test]
+	fn parses_schedule() {
+		let tasks = parse("snapshot=30 4; rotate-log=0,15 */6;").unwrap();
+		assert_eq!(tasks.len(), 2);
+		assert_eq!(tasks[0].0, Task::Snapshot);
+		assert!(tasks[0].1.matches(4, 30));
+		assert!(!tasks[0].1.matches(5, 30));
+		assert_eq!(tasks[1].0, Task::RotateLog);
+		assert!(tasks[1].1.matches(12, 15));
+		assert!(tasks[1].1.matches(0, 0));
+		assert!(!tasks[1].1.matches(13, 0));
+
+		let any: Schedule = "* *".parse().unwrap();
+		assert!(any.matches(23, 59));
+	}
+
+	#[test]
+	fn rejects_invalid_schedule() {
+		assert!(parse("compact=0 0").is_err());
+		assert!(parse("snapshot").is_err());
+		assert!(parse("snapshot=0").is_err());
+		assert!(parse("snapshot=60 0").is_err());
+		assert!(parse("snapshot=0 */0").is_err());
+		assert!(parse("snapshot=x 0").is_err());
+	}
+}
@@ -15,12 +15,17 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use std::io;
+use std::collections::HashMap;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::path::PathBuf;
 use ansi_term::Colour;
+use jsonrpc_core::IoHandler;
+use util::Mutex;
 use util::panics::{ForwardPanic, PanicHandler};
 use util::path::restrict_permissions_owner;
 use rpc_apis;
+use rpc_keys::Tls;
 use ethcore_signer as signer;
 use die::*;
 
@@ -32,6 +37,10 @@ pub struct Configuration {
 	pub enabled: bool,
 	pub port: u16,
 	pub signer_path: String,
+	pub apis: rpc_apis::ApiSet,
+	pub tls: Option<Tls>,
+	/// Accept API keys in place of authorization codes.
+	pub api_keys: bool,
 }
 
 pub struct Dependencies {
@@ -73,7 +82,27 @@ fn do_start(conf: Configuration, deps: Dependencies) -> SignerServer {
 			deps.apis.signer_queue.clone(),
 			codes_path(conf.signer_path),
 		);
-		let server = rpc_apis::setup_rpc(server, deps.apis, rpc_apis::ApiSet::SafeContext);
+		// subscriptions serve the same data as the eth API
+		let server = match conf.apis.contains(&rpc_apis::Api::Eth) {
+			true => server.with_pubsub(deps.apis.pubsub.clone()),
+			false => server,
+		};
+		let server = match deps.apis.audit_log {
+			Some(ref audit_log) => server.with_audit_log(audit_log.clone()),
+			None => server,
+		};
+		let server = match conf.tls {
+			Some(ref tls) => server.with_tls(tls.context().unwrap_or_else(|e| die!("Trusted Signer: {}", e))),
+			None => server,
+		};
+		let server = match conf.api_keys {
+			true => server.with_api_keys(signer::KeyedApis {
+				keys: deps.apis.api_keys.clone(),
+				handler: keyed_handler(deps.apis.clone(), conf.apis.clone()),
+			}),
+			false => server,
+		};
+		let server = rpc_apis::setup_rpc(server, deps.apis, conf.apis);
 		server.start(addr)
 	};
 
@@ -87,4 +116,24 @@ fn do_start(conf: Configuration, deps: Dependencies) -> SignerServer {
 	}
 }
 
-
+// creates handlers exposing the APIs a key grants access to, limited to the ones available over WebSockets.
+fn keyed_handler(deps: Arc<rpc_apis::Dependencies>, available: rpc_apis::ApiSet) -> Box<Fn(Vec<String>) -> Arc<IoHandler> + Send + Sync> {
+	let handlers = Mutex::new(HashMap::new());
+	Box::new(move |apis: Vec<String>| {
+		let mut apis: Vec<rpc_apis::Api> = apis.iter()
+			.filter_map(|api| rpc_apis::Api::from_str(api).ok())
+			.filter(|api| available.contains(api))
+			.collect();
+		apis.sort_by(|a, b| a.name().cmp(b.name()));
+		apis.dedup();
+
+		let mut handlers = handlers.lock();
+		let key = apis.iter().map(|api| api.name().to_owned()).collect::<Vec<_>>();
+		if let Some(handler) = handlers.get(&key) {
+			return handler.clone();
+		}
+		let handler = Arc::new(rpc_apis::setup_rpc(IoHandler::new(), deps.clone(), rpc_apis::ApiSet::List(apis)));
+		handlers.insert(key, handler.clone());
+		handler
+	})
+}
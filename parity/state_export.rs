@@ -0,0 +1,353 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Export of the state at a block into flat files which can be audited offline.
+//!
+//! Every account is written into its own file, named by the Keccak hash of its address,
+//! together with the state trie nodes proving it against the state root of the block.
+//! The block is described in a file called `root.json`. Verifying an export needs
+//! nothing but the files, so anyone trusting the state root can check balances without
+//! trusting the node which produced them.
+
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::str::FromStr;
+use rustc_serialize::hex::{FromHex, ToHex};
+use rustc_serialize::json::Json;
+use util::{Address, H256, U256, Bytes, Uint, Hashable, clean_0x};
+use util::rlp::{RlpStream, Stream, UntrustedRlp, View};
+use util::trie::{RangeProof, verify_range};
+use ethcore::client::{BlockChainClient, BlockID, Client};
+use ethcore::views::HeaderView;
+
+const ROOT_FILE: &'static str = "root.json";
+
+// Number of accounts proven at once.
+const BATCH_SIZE: usize = 1000;
+
+/// Block the state was exported at.
+#[derive(Debug, PartialEq)]
+pub struct ExportRoot {
+	/// Number of the block.
+	pub block_number: u64,
+	/// Hash of the block.
+	pub block_hash: H256,
+	/// State root of the block.
+	pub state_root: H256,
+}
+
+impl ExportRoot {
+	fn to_json(&self) -> String {
+		format!("{{\"blockNumber\":{},\"blockHash\":\"0x{}\",\"stateRoot\":\"0x{}\"}}\n",
+			self.block_number, self.block_hash.hex(), self.state_root.hex())
+	}
+
+	fn from_json(json: &Json) -> Result<Self, String> {
+		Ok(ExportRoot {
+			block_number: try!(json.find("blockNumber").and_then(Json::as_u64).ok_or("Missing blockNumber")),
+			block_hash: try!(hash_field(json, "blockHash")),
+			state_root: try!(hash_field(json, "stateRoot")),
+		})
+	}
+}
+
+/// Single exported account with its inclusion proof.
+#[derive(Debug, PartialEq)]
+pub struct AccountEntry {
+	/// Keccak hash of the address of the account.
+	pub key: H256,
+	/// Nonce of the account.
+	pub nonce: U256,
+	/// Balance of the account in wei.
+	pub balance: U256,
+	/// Root of the account's storage trie.
+	pub storage_root: H256,
+	/// Hash of the account's code.
+	pub code_hash: H256,
+	/// State trie nodes proving the account.
+	pub proof: Vec<Bytes>,
+}
+
+impl AccountEntry {
+	/// Converts a proof of a single account into an entry. Returns `None` if the proof
+	/// doesn't contain exactly one valid account.
+	pub fn from_proof(proof: RangeProof) -> Option<Self> {
+		if proof.items.len() != 1 || proof.items[0].0.len() != 32 {
+			return None;
+		}
+
+		let (key, account) = proof.items.into_iter().next().expect("exactly one item; qed");
+		let rlp = UntrustedRlp::new(&account);
+		let fields = (rlp.val_at(0), rlp.val_at(1), rlp.val_at(2), rlp.val_at(3));
+		match fields {
+			(Ok(nonce), Ok(balance), Ok(storage_root), Ok(code_hash)) => Some(AccountEntry {
+				key: H256::from_slice(&key),
+				nonce: nonce,
+				balance: balance,
+				storage_root: storage_root,
+				code_hash: code_hash,
+				proof: proof.nodes,
+			}),
+			_ => None,
+		}
+	}
+
+	/// RLP of the account, as stored in the state trie.
+	pub fn account_rlp(&self) -> Bytes {
+		let mut stream = RlpStream::new_list(4);
+		stream.append(&self.nonce).append(&self.balance).append(&self.storage_root).append(&self.code_hash);
+		stream.out()
+	}
+
+	/// Checks the proof of the account against given state root.
+	pub fn verify(&self, state_root: &H256) -> bool {
+		verify_range(state_root, &self.key, &RangeProof {
+			items: vec![(self.key.to_vec(), self.account_rlp())],
+			nodes: self.proof.clone(),
+		}).is_ok()
+	}
+
+	fn file_name(key: &H256) -> String {
+		format!("{}.json", key.hex())
+	}
+
+	fn to_json(&self) -> String {
+		format!("{{\"key\":\"0x{}\",\"nonce\":\"{}\",\"balance\":\"{}\",\"storageRoot\":\"0x{}\",\"codeHash\":\"0x{}\",\"proof\":[{}]}}\n",
+			self.key.hex(), self.nonce, self.balance, self.storage_root.hex(), self.code_hash.hex(),
+			self.proof.iter().map(|node| format!("\"0x{}\"", node.to_hex())).collect::<Vec<_>>().join(","))
+	}
+
+	fn from_json(json: &Json) -> Result<Self, String> {
+		let proof = try!(json.find("proof").and_then(Json::as_array).ok_or("Missing proof"));
+		let proof = try!(proof.iter()
+			.map(|node| node.as_string().and_then(|n| clean_0x(n).from_hex().ok()).ok_or("Invalid proof node"))
+			.collect::<Result<Vec<_>, _>>());
+
+		Ok(AccountEntry {
+			key: try!(hash_field(json, "key")),
+			nonce: try!(number_field(json, "nonce")),
+			balance: try!(number_field(json, "balance")),
+			storage_root: try!(hash_field(json, "storageRoot")),
+			code_hash: try!(hash_field(json, "codeHash")),
+			proof: proof,
+		})
+	}
+}
+
+fn hash_field(json: &Json, name: &str) -> Result<H256, String> {
+	json.find(name)
+		.and_then(Json::as_string)
+		.and_then(|s| H256::from_str(clean_0x(s)).ok())
+		.ok_or_else(|| format!("Missing or invalid {}", name))
+}
+
+fn number_field(json: &Json, name: &str) -> Result<U256, String> {
+	json.find(name)
+		.and_then(Json::as_string)
+		.and_then(|s| U256::from_dec_str(s).ok())
+		.ok_or_else(|| format!("Missing or invalid {}", name))
+}
+
+fn read_json(path: &Path) -> Result<Json, String> {
+	let mut contents = String::new();
+	try!(File::open(path).and_then(|mut file| file.read_to_string(&mut contents))
+		.map_err(|e| format!("Unable to read {}: {}", path.display(), e)));
+	Json::from_str(&contents).map_err(|e| format!("Invalid JSON in {}: {}", path.display(), e))
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+	File::create(path).and_then(|mut file| file.write_all(contents.as_bytes()))
+		.map_err(|e| format!("Unable to write {}: {}", path.display(), e))
+}
+
+/// Exports all accounts of the state at given block into directory `dir`.
+/// Returns the number of accounts exported.
+pub fn export(client: &Client, id: BlockID, dir: &Path) -> Result<usize, String> {
+	let header = try!(client.block_header(id.clone()).ok_or("Unknown block"));
+	let header = HeaderView::new(&header);
+	let root = ExportRoot {
+		block_number: header.number(),
+		block_hash: header.hash(),
+		state_root: header.state_root(),
+	};
+	// make sure the same state is proven even if the chain moves on while exporting.
+	let id = BlockID::Hash(root.block_hash.clone());
+
+	try!(fs::create_dir_all(dir).map_err(|e| format!("Unable to create {}: {}", dir.display(), e)));
+
+	let mut from: Bytes = Vec::new();
+	let mut exported = 0;
+	loop {
+		let proofs = try!(client.prove_accounts(&from, BATCH_SIZE, id.clone())
+			.ok_or("State of the block is not available. Try again with --pruning archive."));
+		let done = proofs.len() < BATCH_SIZE;
+
+		for proof in proofs {
+			let entry = try!(AccountEntry::from_proof(proof).ok_or("Invalid account in the state"));
+			try!(write_file(&dir.join(AccountEntry::file_name(&entry.key)), &entry.to_json()));
+			// the next batch starts right after the last key.
+			from = entry.key.to_vec();
+			from.push(0);
+			exported += 1;
+		}
+
+		if done {
+			break;
+		}
+		info!("Exported {} accounts", exported);
+	}
+
+	try!(write_file(&dir.join(ROOT_FILE), &root.to_json()));
+	Ok(exported)
+}
+
+/// Result of verifying an export.
+#[derive(Debug, PartialEq)]
+pub struct Verified {
+	/// Block the state was exported at.
+	pub root: ExportRoot,
+	/// Number of accounts verified.
+	pub accounts: usize,
+	/// Sum of the balances of the verified accounts.
+	pub total_balance: U256,
+}
+
+fn verify_file(path: &Path, state_root: &H256) -> Result<AccountEntry, String> {
+	let entry = try!(AccountEntry::from_json(&try!(read_json(path))).map_err(|e| format!("{}: {}", path.display(), e)));
+	let expected = AccountEntry::file_name(&entry.key);
+	if path.file_name().and_then(|name| name.to_str()) != Some(&expected[..]) {
+		return Err(format!("{}: file name doesn't match the account", path.display()));
+	}
+	match entry.verify(state_root) {
+		true => Ok(entry),
+		false => Err(format!("{}: invalid proof", path.display())),
+	}
+}
+
+/// Verifies the export in directory `dir` against its state root: every account when no
+/// `address` is given, just the account of `address` otherwise.
+pub fn verify(dir: &Path, address: Option<&Address>) -> Result<Verified, String> {
+	let root = try!(ExportRoot::from_json(&try!(read_json(&dir.join(ROOT_FILE)))));
+
+	let paths = match address {
+		Some(address) => vec![dir.join(AccountEntry::file_name(&address.sha3()))],
+		None => {
+			let entries = try!(fs::read_dir(dir).map_err(|e| format!("Unable to read {}: {}", dir.display(), e)));
+			entries.filter_map(|entry| entry.ok().map(|e| e.path()))
+				.filter(|path| path.file_name().and_then(|name| name.to_str()) != Some(ROOT_FILE))
+				.collect()
+		},
+	};
+
+	let mut total_balance = U256::zero();
+	for path in &paths {
+		let entry = try!(verify_file(path, &root.state_root));
+		total_balance = total_balance + entry.balance;
+	}
+
+	Ok(Verified {
+		root: root,
+		accounts: paths.len(),
+		total_balance: total_balance,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use rustc_serialize::json::Json;
+	use util::{H256, U256, Address, Hashable};
+	use util::memorydb::MemoryDB;
+	use util::rlp::{RlpStream, Stream};
+	use util::trie::{TrieDBMut, TrieMut, prove_range};
+	use devtools::RandomTempPath;
+	use super::{AccountEntry, ExportRoot, ROOT_FILE, write_file, verify};
+
+	fn account(balance: u64) -> Vec<u8> {
+		let mut stream = RlpStream::new_list(4);
+		stream.append(&U256::from(1)).append(&U256::from(balance)).append(&H256::from(2)).append(&H256::from(3));
+		stream.out()
+	}
+
+	fn state() -> (MemoryDB, H256) {
+		let mut db = MemoryDB::new();
+		let mut root = H256::new();
+		{
+			let mut t = TrieDBMut::new(&mut db, &mut root);
+			for i in 0..50 {
+				t.insert(&Address::from(i).sha3(), &account(i * 1000));
+			}
+		}
+		(db, root)
+	}
+
+	fn entry(db: &MemoryDB, root: &H256, address: &Address) -> AccountEntry {
+		AccountEntry::from_proof(prove_range(db, root, &address.sha3(), 1).unwrap()).unwrap()
+	}
+
+	#[test]
+	fn account_entry_roundtrip() {
+		let (db, root) = state();
+		let entry = entry(&db, &root, &Address::from(7));
+		assert_eq!(entry.balance, U256::from(7000));
+		assert!(entry.verify(&root));
+
+		let json = Json::from_str(&entry.to_json()).unwrap();
+		assert_eq!(AccountEntry::from_json(&json).unwrap(), entry);
+	}
+
+	#[test]
+	fn rejects_altered_accounts() {
+		let (db, root) = state();
+		let mut entry = entry(&db, &root, &Address::from(7));
+		entry.balance = U256::from(1_000_000);
+		assert!(!entry.verify(&root));
+	}
+
+	#[test]
+	fn verifies_exported_files() {
+		let (db, root) = state();
+		let temp = RandomTempPath::new();
+		let dir = temp.as_path();
+		::std::fs::create_dir_all(dir).unwrap();
+
+		let export_root = ExportRoot {
+			block_number: 10,
+			block_hash: H256::from(11),
+			state_root: root.clone(),
+		};
+		write_file(&dir.join(ROOT_FILE), &export_root.to_json()).unwrap();
+		for i in 1..4 {
+			let entry = entry(&db, &root, &Address::from(i));
+			write_file(&dir.join(AccountEntry::file_name(&entry.key)), &entry.to_json()).unwrap();
+		}
+
+		let verified = verify(dir, None).unwrap();
+		assert_eq!(verified.root, export_root);
+		assert_eq!(verified.accounts, 3);
+		assert_eq!(verified.total_balance, U256::from(6000));
+
+		let verified = verify(dir, Some(&Address::from(2))).unwrap();
+		assert_eq!(verified.total_balance, U256::from(2000));
+		assert!(verify(dir, Some(&Address::from(5))).is_err());
+
+		// an account claiming more than it has.
+		let mut forged = entry(&db, &root, &Address::from(3));
+		forged.balance = U256::from(1_000_000);
+		write_file(&dir.join(AccountEntry::file_name(&forged.key)), &forged.to_json()).unwrap();
+		assert!(verify(dir, None).is_err());
+	}
+}
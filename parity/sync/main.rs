@@ -18,6 +18,8 @@
 
 extern crate ethcore_ipc_nano as nanoipc;
 extern crate ethcore_ipc_hypervisor as hypervisor;
+#[cfg(not(windows))]
+extern crate ethcore_ipc_mio as mioipc;
 extern crate ethcore_ipc as ipc;
 extern crate ctrlc;
 #[macro_use] extern crate log;
@@ -29,7 +31,7 @@ extern crate ethcore_util as util;
 extern crate ethcore_logger;
 
 use std::sync::Arc;
-use hypervisor::{HypervisorServiceClient, SYNC_MODULE_ID, HYPERVISOR_IPC_URL};
+use hypervisor::{HypervisorServiceClient, SYNC_MODULE_ID};
 use ctrlc::CtrlC;
 use std::sync::atomic::{AtomicBool, Ordering};
 use docopt::Docopt;
@@ -39,7 +41,7 @@ use std::thread;
 use nanoipc::IpcInterface;
 
 use ethcore_logger::Settings as LogSettings;
-use ethcore_logger::setup_log;
+use ethcore_logger::{setup_log, parse_targets};
 
 const USAGE: &'static str = "
 Ethcore sync service
@@ -47,19 +49,30 @@ Usage:
   sync <client-url> [options]
 
  Options:
+  --hypervisor-socket PATH
+                           Check in with the hypervisor over its pipelined
+                           listener at the Unix socket PATH.
   -l --logging LOGGING     Specify the logging level. Must conform to the same
                            format as RUST_LOG.
   --log-file FILENAME      Specify a filename into which logging should be
                            directed.
+  --log-allow TARGETS      Log only the given comma-separated targets, each
+                           with an optional level.
+  --log-deny TARGETS       Never log the given comma-separated targets.
   --no-color               Don't use terminal color codes in output.
+  --log-json               Log a JSON object per line.
 ";
 
 #[derive(Debug, RustcDecodable)]
 struct Args {
 	arg_client_url: String,
+	flag_hypervisor_socket: Option<String>,
 	flag_logging: Option<String>,
 	flag_log_file: Option<String>,
+	flag_log_allow: Option<String>,
+	flag_log_deny: Option<String>,
 	flag_no_color: bool,
+	flag_log_json: bool,
 }
 
 impl Args {
@@ -68,12 +81,21 @@ impl Args {
 		if self.flag_no_color || cfg!(windows) {
 			settings = settings.no_color();
 		}
+		if self.flag_log_json {
+			settings = settings.json();
+		}
 		if let Some(ref init) = self.flag_logging {
 			settings = settings.init(init.to_owned())
 		}
 		if let Some(ref file) = self.flag_log_file {
 			settings = settings.file(file.to_owned())
 		}
+		if let Some(ref allow) = self.flag_log_allow {
+			settings = settings.allow(parse_targets(allow).unwrap_or_else(|e| panic!("{}", e)));
+		}
+		if let Some(ref deny) = self.flag_log_deny {
+			settings = settings.deny(deny.split(',').map(|t| t.to_owned()).collect());
+		}
 		settings
 	}
 }
@@ -90,6 +112,31 @@ fn run_service<T: ?Sized + Send + Sync + 'static>(addr: &str, stop_guard: Arc<At
 	});
 }
 
+/// Checks in with the hypervisor over its pipelined listener, if given
+#[cfg(not(windows))]
+fn report_ready(hypervisor_socket: Option<&str>) {
+	let path = match hypervisor_socket {
+		Some(path) => path,
+		None => return report_ready_nano(),
+	};
+	let transport = mioipc::Transport::connect(path).unwrap();
+	let hypervisor_client = mioipc::init_client::<HypervisorServiceClient<_>>(&transport);
+	hypervisor_client.handshake().unwrap();
+	hypervisor_client.module_ready(SYNC_MODULE_ID);
+}
+
+/// Checks in with the hypervisor
+#[cfg(windows)]
+fn report_ready(_hypervisor_socket: Option<&str>) {
+	report_ready_nano()
+}
+
+fn report_ready_nano() {
+	let hypervisor_client = nanoipc::init_client::<HypervisorServiceClient<_>>(hypervisor::HYPERVISOR_IPC_URL).unwrap();
+	hypervisor_client.handshake().unwrap();
+	hypervisor_client.module_ready(SYNC_MODULE_ID);
+}
+
 fn main() {
 	use std::io::{self, Read};
 
@@ -114,9 +161,7 @@ fn main() {
 	run_service("ipc:///tmp/parity-manage-net.ipc", stop.clone(), sync.clone() as Arc<ManageNetwork>);
 	run_service("ipc:///tmp/parity-sync-notify.ipc", stop.clone(), sync.clone() as Arc<ChainNotify>);
 
-	let hypervisor_client = nanoipc::init_client::<HypervisorServiceClient<_>>(HYPERVISOR_IPC_URL).unwrap();
-	hypervisor_client.handshake().unwrap();
-	hypervisor_client.module_ready(SYNC_MODULE_ID);
+	report_ready(args.flag_hypervisor_socket.as_ref().map(|path| path.as_str()));
 
 	let terminate_stop = stop.clone();
 	CtrlC::set_handler(move || {
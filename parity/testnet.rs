@@ -0,0 +1,269 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Local test network of several nodes.
+//!
+//! Every node gets its own directory, ports, node key and account. The nodes run a
+//! generated proof-of-authority chain sealed by the first node, know each other as
+//! reserved peers and have their accounts funded in the genesis block.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use util::{H256, FixedHash};
+use ethcore::ethstore::{EthStore, SecretStore};
+use ethcore::ethstore::dir::DiskDirectory;
+use ethcore::ethstore::ethkey::{Generator, KeyPair, Random, Address};
+
+// Genesis balance of every node's account: 1,000,000 ether.
+const ACCOUNT_BALANCE: &'static str = "1000000000000000000000000";
+
+/// Test network configuration.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TestnetOptions {
+	/// Number of nodes.
+	pub nodes: usize,
+	/// Directory holding the chain spec and the nodes' directories.
+	pub dir: PathBuf,
+	/// Network port of the first node. Other nodes use the following ports.
+	pub port: u16,
+	/// JSON-RPC port of the first node. Other nodes use the following ports.
+	pub rpc_port: u16,
+}
+
+/// Single node of the test network.
+pub struct TestnetNode {
+	/// Directory of the node.
+	pub dir: PathBuf,
+	/// Network port.
+	pub port: u16,
+	/// JSON-RPC port.
+	pub rpc_port: u16,
+	/// Network identity.
+	pub node_key: KeyPair,
+	/// Prefunded account, which is also the block author.
+	pub account: KeyPair,
+	/// Password of the account.
+	pub password: String,
+}
+
+impl TestnetNode {
+	/// Enode URL of the node.
+	pub fn enode(&self) -> String {
+		format!("enode://{}@127.0.0.1:{}", self.node_key.public(), self.port)
+	}
+
+	/// JSON-RPC endpoint of the node.
+	pub fn rpc_endpoint(&self) -> String {
+		format!("http://127.0.0.1:{}", self.rpc_port)
+	}
+
+	fn path(&self, name: &str) -> String {
+		self.dir.join(name).to_string_lossy().into_owned()
+	}
+
+	/// Command line arguments running the node. Only the sealer authors blocks.
+	pub fn args(&self, spec: &Path, sealer: bool) -> Vec<String> {
+		let mut args = vec![
+			"--chain".to_owned(), spec.to_string_lossy().into_owned(),
+			"--db-path".to_owned(), self.path("db"),
+			"--keys-path".to_owned(), self.path("keys"),
+			"--log-file".to_owned(), self.path("parity.log"),
+			"--port".to_owned(), format!("{}", self.port),
+			"--jsonrpc-port".to_owned(), format!("{}", self.rpc_port),
+			"--node-key".to_owned(), format!("{}", self.node_key.secret()),
+			"--reserved-peers".to_owned(), self.path("reserved_peers"),
+			"--no-discovery".to_owned(),
+			"--no-ipc".to_owned(),
+			"--no-dapps".to_owned(),
+			"--no-signer".to_owned(),
+			"--author".to_owned(), format!("0x{}", self.account.address()),
+			"--unlock".to_owned(), format!("0x{}", self.account.address()),
+			"--password".to_owned(), self.path("password"),
+			"--gasprice".to_owned(), "0".to_owned(),
+		];
+		if sealer {
+			args.push("--force-sealing".to_owned());
+		}
+		args
+	}
+}
+
+/// Chain spec of the test network: `authority` seals the blocks and `accounts` are funded.
+pub fn spec_json(authority: &Address, accounts: &[Address]) -> String {
+	let builtins = [
+		("0000000000000000000000000000000000000001", r#"{ "name": "ecrecover", "pricing": { "linear": { "base": 3000, "word": 0 } } }"#),
+		("0000000000000000000000000000000000000002", r#"{ "name": "sha256", "pricing": { "linear": { "base": 60, "word": 12 } } }"#),
+		("0000000000000000000000000000000000000003", r#"{ "name": "ripemd160", "pricing": { "linear": { "base": 600, "word": 120 } } }"#),
+		("0000000000000000000000000000000000000004", r#"{ "name": "identity", "pricing": { "linear": { "base": 15, "word": 3 } } }"#),
+	];
+	let mut entries: Vec<String> = builtins.iter()
+		.map(|&(address, builtin)| format!("\t\t\"{}\": {{ \"balance\": \"1\", \"builtin\": {} }}", address, builtin))
+		.collect();
+	entries.extend(accounts.iter().map(|a| format!("\t\t\"{}\": {{ \"balance\": \"{}\" }}", a, ACCOUNT_BALANCE)));
+
+	format!(r#"{{
+	"name": "LocalTestnet",
+	"engine": {{
+		"BasicAuthority": {{
+			"params": {{
+				"gasLimitBoundDivisor": "0x0400",
+				"durationLimit": "0x0d",
+				"authorities": ["0x{}"]
+			}}
+		}}
+	}},
+	"params": {{
+		"accountStartNonce": "0x0",
+		"maximumExtraDataSize": "0x20",
+		"minGasLimit": "0x1388",
+		"networkID": "0x4c54"
+	}},
+	"genesis": {{
+		"seal": {{
+			"generic": {{
+				"fields": 1,
+				"rlp": "0x11bbe8db4e347b4e8c937c1c8370e4b5ed33adb3db69cbdb7a38e1e50b1b82fa"
+			}}
+		}},
+		"difficulty": "0x20000",
+		"author": "0x0000000000000000000000000000000000000000",
+		"timestamp": "0x00",
+		"parentHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+		"extraData": "0x",
+		"gasLimit": "0x2fefd8"
+	}},
+	"accounts": {{
+{}
+	}}
+}}
+"#, authority, entries.join(",\n"))
+}
+
+fn write_file(path: &Path, contents: &str) -> Result<(), String> {
+	File::create(path).and_then(|mut file| file.write_all(contents.as_bytes()))
+		.map_err(|e| format!("Unable to write {}: {}", path.display(), e))
+}
+
+// `i`-th port after `first`, if there is one.
+fn nth_port(first: u16, i: usize) -> Result<u16, String> {
+	match first as usize + i {
+		port if port <= ::std::u16::MAX as usize => Ok(port as u16),
+		_ => Err(format!("Not enough ports above {} for node {}. Use lower ports.", first, i)),
+	}
+}
+
+/// Generates keys, passwords and ports of the nodes.
+pub fn nodes(options: &TestnetOptions) -> Result<Vec<TestnetNode>, String> {
+	(0..options.nodes).map(|i| {
+		let node_key = try!(Random.generate().map_err(|e| format!("Unable to generate node key: {}", e)));
+		let account = try!(Random.generate().map_err(|e| format!("Unable to generate account: {}", e)));
+		Ok(TestnetNode {
+			dir: options.dir.join(format!("node{}", i)),
+			port: try!(nth_port(options.port, i)),
+			rpc_port: try!(nth_port(options.rpc_port, i)),
+			node_key: node_key,
+			account: account,
+			password: H256::random().hex(),
+		})
+	}).collect()
+}
+
+/// Writes the chain spec and the directory of every node: its account with the password file
+/// unlocking it and the list of the other nodes as reserved peers. Returns the path of the spec.
+pub fn prepare(options: &TestnetOptions, nodes: &[TestnetNode]) -> Result<PathBuf, String> {
+	let authority = try!(nodes.first().ok_or("A test network needs at least one node")).account.address();
+	let accounts: Vec<_> = nodes.iter().map(|n| n.account.address()).collect();
+	try!(fs::create_dir_all(&options.dir).map_err(|e| format!("Unable to create {}: {}", options.dir.display(), e)));
+	let spec = options.dir.join("spec.json");
+	try!(write_file(&spec, &spec_json(&authority, &accounts)));
+
+	for node in nodes {
+		let keys = node.dir.join("keys");
+		try!(fs::create_dir_all(&keys).map_err(|e| format!("Unable to create {}: {}", keys.display(), e)));
+		let store = try!(DiskDirectory::create(&keys)
+			.and_then(|dir| EthStore::open(Box::new(dir)))
+			.map_err(|e| format!("Unable to open key store: {}", e)));
+		try!(store.insert_account(node.account.secret().clone(), &node.password).map_err(|e| format!("Unable to store account: {}", e)));
+		try!(write_file(&node.dir.join("password"), &node.password));
+
+		let peers: Vec<_> = nodes.iter().filter(|n| n.port != node.port).map(TestnetNode::enode).collect();
+		try!(write_file(&node.dir.join("reserved_peers"), &peers.join("\n")));
+	}
+	Ok(spec)
+}
+
+/// Starts the nodes as child processes of the current executable. Their output goes to the
+/// output of the current process.
+pub fn start(spec: &Path, nodes: &[TestnetNode]) -> Result<Vec<Child>, String> {
+	let exe = try!(::std::env::current_exe().map_err(|e| format!("Unable to find parity executable: {}", e)));
+	nodes.iter().enumerate().map(|(i, node)| {
+		Command::new(&exe)
+			.args(&node.args(spec, i == 0))
+			.stdin(Stdio::null())
+			.spawn()
+			.map_err(|e| format!("Unable to start node {}: {}", i, e))
+	}).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::{Path, PathBuf};
+	use ethcore::spec::Spec;
+	use ethcore::ethstore::ethkey::Address;
+	use super::{TestnetOptions, nodes, spec_json};
+
+	fn options() -> TestnetOptions {
+		TestnetOptions {
+			nodes: 3,
+			dir: PathBuf::from("/tmp/testnet"),
+			port: 30400,
+			rpc_port: 8600,
+		}
+	}
+
+	#[test]
+	fn generates_loadable_spec() {
+		let accounts = vec![Address::from([1u8; 20]), Address::from([2u8; 20])];
+		let spec = Spec::load(spec_json(&accounts[0], &accounts).as_bytes());
+		assert_eq!(spec.engine.name(), "BasicAuthority");
+	}
+
+	#[test]
+	fn assigns_distinct_ports_and_keys() {
+		let nodes = nodes(&options()).unwrap();
+		assert_eq!(nodes.len(), 3);
+		assert_eq!(nodes[2].port, 30402);
+		assert_eq!(nodes[2].rpc_endpoint(), "http://127.0.0.1:8602");
+		assert!(nodes[0].node_key.public() != nodes[1].node_key.public());
+		assert!(nodes[1].enode().ends_with("@127.0.0.1:30401"));
+
+		let spec = Path::new("/tmp/testnet/spec.json");
+		assert!(nodes[0].args(spec, true).contains(&"--force-sealing".to_owned()));
+		assert!(!nodes[1].args(spec, false).contains(&"--force-sealing".to_owned()));
+		assert!(nodes[1].args(spec, false).contains(&"/tmp/testnet/node1/db".to_owned()));
+	}
+
+	#[test]
+	fn rejects_ports_out_of_range() {
+		let mut options = options();
+		options.port = 65534;
+		assert!(nodes(&options).is_err());
+		options.nodes = 2;
+		assert_eq!(nodes(&options).unwrap()[1].port, 65535);
+	}
+}
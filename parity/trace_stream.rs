@@ -0,0 +1,101 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Publishing of traces to IPC subscribers.
+//!
+//! Whenever the canonical chain changes, every subscriber receives a JSON message for each
+//! retracted block, newest first, followed by one for each enacted block, oldest first, carrying
+//! its traces in the format of `trace_block`. An indexer can thus follow reorganisations
+//! without polling.
+//!
+//! Messages are numbered in sequence. Subscribers which can't keep up miss messages, and
+//! can tell so from a gap in the numbers.
+
+use std::sync::{Arc, Weak};
+use serde_json;
+use util::{H256, Mutex};
+use nanoipc::Publisher;
+use ethcore::client::{BlockChainClient, BlockID, ChainNotify, Client};
+use ethcore_rpc::v1::types::TraceNotification;
+
+/// Publishes traces of blocks entering and leaving the canonical chain.
+pub struct TraceStream {
+	client: Weak<Client>,
+	channel: Mutex<Channel>,
+}
+
+// publishing socket and the sequence number of the next message.
+struct Channel {
+	publisher: Publisher,
+	next: u64,
+}
+
+/// Starts publishing traces on the socket at `addr`.
+pub fn start(addr: &str, client: &Arc<Client>) -> Result<Arc<TraceStream>, String> {
+	if !client.tracing_enabled() {
+		return Err("Streaming traces requires tracing. Run with --tracing on.".into());
+	}
+	let publisher = try!(Publisher::bind(addr).map_err(|e| format!("Cannot publish traces at {}: {:?}", addr, e)));
+	info!("Publishing traces at {}", addr);
+
+	Ok(Arc::new(TraceStream {
+		client: Arc::downgrade(client),
+		channel: Mutex::new(Channel {
+			publisher: publisher,
+			next: 0,
+		}),
+	}))
+}
+
+impl TraceStream {
+	fn publish(&self, notification: TraceNotification) {
+		let mut channel = self.channel.lock();
+		match serde_json::to_vec(&notification.with_sequence(channel.next)) {
+			Ok(message) => {
+				channel.publisher.publish(&message);
+				channel.next += 1;
+			},
+			Err(e) => warn!("Cannot serialize trace notification: {:?}", e),
+		}
+	}
+}
+
+impl ChainNotify for TraceStream {
+	fn new_blocks(&self, _imported: Vec<H256>, _invalid: Vec<H256>, enacted: Vec<H256>, retracted: Vec<H256>, _sealed: Vec<H256>, _duration: u64) {
+		let client = match self.client.upgrade() {
+			Some(client) => client,
+			None => return,
+		};
+		let numbered = |hashes: Vec<H256>| {
+			hashes.into_iter()
+				.filter_map(|hash| client.block_number(BlockID::Hash(hash.clone())).map(|number| (number, hash)))
+				.collect::<Vec<_>>()
+		};
+
+		let mut retracted = numbered(retracted);
+		retracted.sort_by(|a, b| b.0.cmp(&a.0));
+		for (number, hash) in retracted {
+			self.publish(TraceNotification::retracted(hash, number));
+		}
+
+		let mut enacted = numbered(enacted);
+		enacted.sort_by(|a, b| a.0.cmp(&b.0));
+		for (number, hash) in enacted {
+			let traces = client.block_traces(BlockID::Hash(hash.clone())).unwrap_or_else(Vec::new);
+			self.publish(TraceNotification::enacted(hash, number, traces));
+		}
+	}
+}
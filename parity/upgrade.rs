@@ -18,10 +18,10 @@
 
 use semver::Version;
 use std::collections::*;
-use std::fs::{File, create_dir_all};
+use std::fs::{self, File, create_dir_all};
 use std::env;
-use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
 
 #[cfg_attr(feature="dev", allow(enum_variant_names))]
 #[derive(Debug)]
@@ -126,3 +126,55 @@ pub fn upgrade(db_path: Option<&str>) -> Result<usize, Error> {
 		upgrade_from_version(ver)
 	})
 }
+
+/// Moves the key files kept directly in `from` to `to`. Files already present in `to` are left
+/// where they are. Returns the number of keys moved.
+pub fn upgrade_key_location(from: &Path, to: &Path) -> io::Result<usize> {
+	if !from.is_dir() {
+		return Ok(0);
+	}
+
+	let mut moved = 0;
+	for entry in try!(fs::read_dir(from)) {
+		let entry = try!(entry);
+		if !try!(entry.file_type()).is_file() {
+			continue;
+		}
+		let name = entry.file_name();
+		if name.to_str().map_or(true, |name| name.starts_with('.')) {
+			continue;
+		}
+		let target = to.join(&name);
+		if target.exists() {
+			continue;
+		}
+		try!(create_dir_all(to));
+		try!(fs::rename(entry.path(), target));
+		moved += 1;
+	}
+	Ok(moved)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::{self, File};
+	use devtools::RandomTempPath;
+	use super::upgrade_key_location;
+
+	#[test]
+	fn moves_loose_keys() {
+		let temp = RandomTempPath::create_dir();
+		let root = temp.as_path().to_owned();
+		let chain = root.join("mainnet");
+		File::create(root.join("key1")).unwrap();
+		File::create(root.join(".hidden")).unwrap();
+		fs::create_dir_all(root.join("morden")).unwrap();
+
+		assert_eq!(upgrade_key_location(&root, &chain).unwrap(), 1);
+		assert!(chain.join("key1").exists());
+		assert!(!root.join("key1").exists());
+		assert!(root.join(".hidden").exists());
+		assert_eq!(upgrade_key_location(&root, &chain).unwrap(), 0);
+		assert_eq!(upgrade_key_location(&root.join("missing"), &chain).unwrap(), 0);
+	}
+}
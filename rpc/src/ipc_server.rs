@@ -0,0 +1,312 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! JSON-RPC over a Unix domain socket, recording every call in the audit log.
+//!
+//! Requests are JSON objects or arrays (batches) sent back to back, optionally separated
+//! by whitespace. Each response, and each subscription notification, is written followed
+//! by a newline.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::Duration;
+use jsonrpc_core::IoHandler;
+use util::Mutex;
+use v1::{AuditLog, EthPubSub};
+use pubsub::PubSubSession;
+
+/// Largest accepted request.
+const MAX_REQUEST_SIZE: usize = 5 * 1024 * 1024;
+/// How often the listener checks whether the server was stopped, while no connections arrive.
+const ACCEPT_POLL_MS: u64 = 100;
+/// How long a write to a client may block. Notifications are written from the thread
+/// which publishes them, so a client which doesn't read can't hold it up for longer.
+const WRITE_TIMEOUT_SEC: u64 = 1;
+
+/// IPC server. Stops listening and removes the socket once dropped.
+pub struct IpcServer {
+	path: PathBuf,
+	stop: Arc<AtomicBool>,
+	handle: Option<thread::JoinHandle<()>>,
+}
+
+impl IpcServer {
+	/// Starts serving requests on the socket at `path`, recording them in `audit_log` if given.
+	/// Subscriptions are served by `pubsub`, if given. Fails if another server is listening there already.
+	pub fn start(path: &str, handler: Arc<IoHandler>, pubsub: Option<Arc<EthPubSub>>, audit_log: Option<Arc<AuditLog>>) -> io::Result<IpcServer> {
+		let listener = try!(bind(Path::new(path)));
+		// polled, so that the thread notices the server was stopped without waiting for a connection.
+		try!(listener.set_nonblocking(true));
+		let stop = Arc::new(AtomicBool::new(false));
+		let stopped = stop.clone();
+		let handle = try!(thread::Builder::new().name("jsonrpc-ipc".into()).spawn(move || {
+			while !stopped.load(Ordering::SeqCst) {
+				let stream = match listener.accept() {
+					Ok((stream, _)) => stream,
+					Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+						thread::sleep(Duration::from_millis(ACCEPT_POLL_MS));
+						continue;
+					},
+					Err(e) => {
+						debug!(target: "rpc", "Error accepting IPC connection: {}", e);
+						thread::sleep(Duration::from_millis(ACCEPT_POLL_MS));
+						continue;
+					},
+				};
+				let handler = handler.clone();
+				let pubsub = pubsub.clone();
+				let audit_log = audit_log.clone();
+				let spawned = thread::Builder::new().name("jsonrpc-ipc-conn".into()).spawn(move || {
+					let served = stream.set_nonblocking(false)
+						.and_then(|_| stream.set_write_timeout(Some(Duration::from_secs(WRITE_TIMEOUT_SEC))))
+						.and_then(|_| stream.try_clone())
+						.and_then(|writer| Connection::new(writer, handler, pubsub, audit_log).serve(stream));
+					if let Err(e) = served {
+						debug!(target: "rpc", "Error serving IPC connection: {}", e);
+					}
+				});
+				if let Err(e) = spawned {
+					warn!(target: "rpc", "Error creating IPC connection thread: {}", e);
+				}
+			}
+		}));
+
+		Ok(IpcServer {
+			path: PathBuf::from(path),
+			stop: stop,
+			handle: Some(handle),
+		})
+	}
+}
+
+impl Drop for IpcServer {
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::SeqCst);
+		if let Some(handle) = self.handle.take() {
+			let _ = handle.join();
+		}
+		let _ = fs::remove_file(&self.path);
+	}
+}
+
+/// Binds the socket at `path`, replacing a socket left behind by a server which is gone.
+/// Fails with `AddrInUse` if a server still listens there.
+pub fn bind(path: &Path) -> io::Result<UnixListener> {
+	match UnixListener::bind(path) {
+		Err(ref e) if e.kind() == io::ErrorKind::AddrInUse => {
+			if UnixStream::connect(path).is_ok() {
+				return Err(io::Error::new(io::ErrorKind::AddrInUse, format!("{} is in use by a running server", path.display())));
+			}
+			try!(fs::remove_file(path));
+			UnixListener::bind(path)
+		},
+		result => result,
+	}
+}
+
+struct Connection {
+	session: PubSubSession,
+	writer: Arc<Mutex<UnixStream>>,
+	audit_log: Option<Arc<AuditLog>>,
+}
+
+// writes a message followed by a newline; responses and notifications share the stream.
+fn write_message(writer: &Mutex<UnixStream>, message: &str) -> io::Result<()> {
+	writer.lock().write_all(format!("{}\n", message).as_bytes())
+}
+
+impl Connection {
+	fn new(writer: UnixStream, handler: Arc<IoHandler>, pubsub: Option<Arc<EthPubSub>>, audit_log: Option<Arc<AuditLog>>) -> Self {
+		let writer = Arc::new(Mutex::new(writer));
+		let sink_writer = writer.clone();
+		Connection {
+			session: PubSubSession::new(handler, pubsub, Arc::new(move |message: String| write_message(&sink_writer, &message).is_ok())),
+			writer: writer,
+			audit_log: audit_log,
+		}
+	}
+
+	// serves requests until the client closes the connection or sends something which isn't a request.
+	// the session, and so its subscriptions, end with the connection.
+	fn serve(self, mut reader: UnixStream) -> io::Result<()> {
+		let mut requests = Requests::default();
+		let mut buf = [0u8; 4096];
+		loop {
+			let read = try!(reader.read(&mut buf));
+			if read == 0 {
+				return Ok(());
+			}
+			for byte in &buf[..read] {
+				match requests.push(*byte) {
+					Ok(Some(request)) => try!(self.handle(&request)),
+					Ok(None) => {},
+					Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+				}
+			}
+		}
+	}
+
+	fn handle(&self, request: &str) -> io::Result<()> {
+		if let Some(ref audit_log) = self.audit_log {
+			audit_log.log("ipc", "local", request);
+		}
+		match self.session.handle_request(request) {
+			Some(response) => write_message(&self.writer, &response),
+			None => Ok(()),
+		}
+	}
+}
+
+// splits the received bytes into requests: top-level JSON objects or arrays.
+#[derive(Default)]
+struct Requests {
+	current: Vec<u8>,
+	depth: usize,
+	in_string: bool,
+	escaped: bool,
+}
+
+impl Requests {
+	// takes the next byte, returning the request it completes, if any.
+	fn push(&mut self, byte: u8) -> Result<Option<String>, &'static str> {
+		if self.depth == 0 {
+			match byte {
+				b'{' | b'[' => {},
+				b' ' | b'\t' | b'\r' | b'\n' => return Ok(None),
+				_ => return Err("Requests must be JSON objects or arrays"),
+			}
+		}
+		if self.current.len() >= MAX_REQUEST_SIZE {
+			return Err("Request too large");
+		}
+		self.current.push(byte);
+
+		if self.in_string {
+			match (self.escaped, byte) {
+				(true, _) => self.escaped = false,
+				(false, b'\\') => self.escaped = true,
+				(false, b'"') => self.in_string = false,
+				_ => {},
+			}
+			return Ok(None);
+		}
+		match byte {
+			b'"' => self.in_string = true,
+			b'{' | b'[' => self.depth += 1,
+			// the request starts with an opening bracket, so depth is positive here.
+			b'}' | b']' => self.depth -= 1,
+			_ => {},
+		}
+		if self.depth > 0 {
+			return Ok(None);
+		}
+		String::from_utf8(mem::replace(&mut self.current, Vec::new()))
+			.map(Some)
+			.map_err(|_| "Requests must be UTF-8")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use std::io::{BufRead, BufReader, Read, Write};
+	use std::os::unix::net::UnixStream;
+	use std::sync::Arc;
+	use jsonrpc_core::IoHandler;
+	use devtools::RandomTempPath;
+	use util::hash::H256;
+	use ethcore::client::TestBlockChainClient;
+	use v1::{AuditLog, EthPubSubClient, Web3, Web3Client};
+	use super::{IpcServer, Requests, bind};
+
+	fn split(input: &str) -> Result<Vec<String>, &'static str> {
+		let mut requests = Requests::default();
+		let mut out = Vec::new();
+		for byte in input.bytes() {
+			if let Some(request) = try!(requests.push(byte)) {
+				out.push(request);
+			}
+		}
+		Ok(out)
+	}
+
+	#[test]
+	fn splits_requests() {
+		assert_eq!(split(r#"{"a":"}\"{"} [1,[2]]
+{}"#), Ok(vec![r#"{"a":"}\"{"}"#.to_owned(), "[1,[2]]".to_owned(), "{}".to_owned()]));
+		assert_eq!(split(r#"{"a":1}x"#), Err("Requests must be JSON objects or arrays"));
+	}
+
+	#[test]
+	fn serves_and_audits_calls() {
+		let temp = RandomTempPath::create_dir();
+		let socket = temp.as_path().join("jsonrpc.ipc");
+		let log_path = temp.as_path().join("audit.log");
+		let audit_log = Arc::new(AuditLog::open(log_path.clone(), 1024 * 1024).unwrap());
+		let io = IoHandler::new();
+		io.add_delegate(Web3Client::new("").to_delegate());
+		let _server = IpcServer::start(socket.to_str().unwrap(), Arc::new(io), None, Some(audit_log)).unwrap();
+
+		let mut client = UnixStream::connect(&socket).unwrap();
+		client.write_all(br#"{"jsonrpc":"2.0","method":"web3_sha3","params":["0x00"],"id":1}"#).unwrap();
+		let mut response = String::new();
+		BufReader::new(client).read_line(&mut response).unwrap();
+		assert_eq!(response, "{\"jsonrpc\":\"2.0\",\"result\":\"0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a\",\"id\":1}\n");
+
+		let mut log = String::new();
+		File::open(&log_path).unwrap().read_to_string(&mut log).unwrap();
+		assert!(log.ends_with(" ipc local web3_sha3 [\"0x00\"]\n"));
+	}
+
+	#[test]
+	fn pushes_subscription_notifications() {
+		let temp = RandomTempPath::create_dir();
+		let socket = temp.as_path().join("jsonrpc.ipc");
+		let chain = Arc::new(TestBlockChainClient::new());
+		let pubsub = Arc::new(EthPubSubClient::new(&chain));
+		let _server = IpcServer::start(socket.to_str().unwrap(), Arc::new(IoHandler::new()), Some(pubsub.clone()), None).unwrap();
+
+		let mut client = UnixStream::connect(&socket).unwrap();
+		client.write_all(br#"{"jsonrpc":"2.0","method":"eth_subscribe","params":["newPendingTransactions"],"id":1}"#).unwrap();
+		let mut reader = BufReader::new(client);
+		let mut response = String::new();
+		reader.read_line(&mut response).unwrap();
+		assert_eq!(response, "{\"jsonrpc\":\"2.0\",\"result\":\"0x00\",\"id\":1}\n");
+
+		pubsub.notify_transactions(&[H256::default()]);
+		let mut notification = String::new();
+		reader.read_line(&mut notification).unwrap();
+		assert_eq!(notification, r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"subscription":"0x00","result":"0x0000000000000000000000000000000000000000000000000000000000000000"}}"#.to_owned() + "\n");
+	}
+
+	#[test]
+	fn refuses_socket_in_use() {
+		let temp = RandomTempPath::create_dir();
+		let socket = temp.as_path().join("jsonrpc.ipc");
+
+		let listener = bind(&socket).unwrap();
+		assert!(bind(&socket).is_err());
+		drop(listener);
+		// the socket file is left behind, but nobody listens.
+		assert!(bind(&socket).is_ok());
+	}
+}
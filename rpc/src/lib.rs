@@ -31,6 +31,7 @@ extern crate ethcore_util as util;
 extern crate ethcore;
 extern crate ethsync;
 extern crate transient_hashmap;
+extern crate time;
 extern crate json_ipc_server as ipc;
 extern crate ethcore_ipc;
 
@@ -46,7 +47,13 @@ use self::jsonrpc_core::{IoHandler, IoDelegate};
 
 pub use jsonrpc_http_server::{ServerBuilder, Server, RpcServerError};
 pub mod v1;
-pub use v1::{SigningQueue, ConfirmationsQueue};
+mod pubsub;
+#[cfg(not(windows))]
+mod ipc_server;
+pub use v1::{SigningQueue, ConfirmationsQueue, ExitHandle, ExitRequest, ApiKeys, KeyError, RateLimiter, RateLimits, LimitError, AuditLog};
+pub use pubsub::PubSubSession;
+#[cfg(not(windows))]
+pub use ipc_server::IpcServer;
 
 /// An object that can be extended with `IoDelegates`
 pub trait Extendable {
@@ -54,6 +61,12 @@ pub trait Extendable {
 	fn add_delegate<D: Send + Sync + 'static>(&self, delegate: IoDelegate<D>);
 }
 
+impl Extendable for IoHandler {
+	fn add_delegate<D: Send + Sync + 'static>(&self, delegate: IoDelegate<D>) {
+		IoHandler::add_delegate(self, delegate);
+	}
+}
+
 /// Http server.
 pub struct RpcServer {
 	handler: Arc<jsonrpc_core::io::IoHandler>,
@@ -102,6 +115,18 @@ impl RpcServer {
 			.start_http(addr)
 	}
 
+	/// Handle a single JSON-RPC request (or batch) outside of the built-in servers.
+	pub fn handle_request(&self, request: &str) -> Option<String> {
+		self.handler.handle_request(request)
+	}
+
+	/// Start ipc server on a Unix domain socket, recording every call in `audit_log` if given.
+	/// Subscriptions are served by `pubsub`, if given.
+	#[cfg(not(windows))]
+	pub fn start_unix_ipc(&self, path: &str, pubsub: Option<Arc<v1::EthPubSub>>, audit_log: Option<Arc<AuditLog>>) -> ::std::io::Result<IpcServer> {
+		IpcServer::start(path, self.handler.clone(), pubsub, audit_log)
+	}
+
 	/// Start ipc server asynchronously and returns result with `Server` handle on success or an error.
 	pub fn start_ipc(&self, addr: &str) -> Result<ipc::Server, ipc::Error> {
 		let server = try!(ipc::Server::new(addr, &self.handler));
@@ -0,0 +1,103 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Pub-sub support for transports able to push messages to clients.
+//!
+//! The signer WebSocket server and the IPC server (except on Windows) create a session
+//! per connection. The HTTP server answers request by request and can't push messages,
+//! so `eth_subscribe` isn't available there.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, ATOMIC_USIZE_INIT, Ordering};
+use serde_json;
+use jsonrpc_core::{IoHandler, Params, Value, Error};
+use v1::{EthPubSub, Sink, SessionId};
+
+static NEXT_SESSION_ID: AtomicUsize = ATOMIC_USIZE_INIT;
+
+/// A single client connection of a pub-sub capable transport.
+///
+/// Serves `eth_subscribe` and `eth_unsubscribe` with the connection's sink and
+/// forwards all other requests to the regular `IoHandler`.
+/// Subscriptions of the session are dropped together with it.
+pub struct PubSubSession {
+	id: SessionId,
+	sink: Sink,
+	handler: Arc<IoHandler>,
+	pubsub: Option<Arc<EthPubSub>>,
+}
+
+impl PubSubSession {
+	/// Creates new session delivering notifications to given sink.
+	pub fn new(handler: Arc<IoHandler>, pubsub: Option<Arc<EthPubSub>>, sink: Sink) -> Self {
+		PubSubSession {
+			id: NEXT_SESSION_ID.fetch_add(1, Ordering::SeqCst),
+			sink: sink,
+			handler: handler,
+			pubsub: pubsub,
+		}
+	}
+
+	/// Handles a single JSON-RPC request (or batch) received on this session.
+	pub fn handle_request(&self, request: &str) -> Option<String> {
+		let pubsub = match self.pubsub {
+			Some(ref pubsub) => pubsub,
+			None => return self.handler.handle_request(request),
+		};
+
+		// Subscriptions are only served as single calls, batches go to the handler.
+		let call = match serde_json::from_str::<Value>(request) {
+			Ok(Value::Object(call)) => call,
+			_ => return self.handler.handle_request(request),
+		};
+		let subscribe = match call.get("method") {
+			Some(&Value::String(ref method)) if method == "eth_subscribe" => true,
+			Some(&Value::String(ref method)) if method == "eth_unsubscribe" => false,
+			_ => return self.handler.handle_request(request),
+		};
+
+		let result = match call.get("params") {
+			None => Ok(Params::None),
+			Some(&Value::Array(ref params)) => Ok(Params::Array(params.clone())),
+			Some(&Value::Object(ref params)) => Ok(Params::Map(params.clone())),
+			Some(_) => Err(Error::invalid_params()),
+		}.and_then(|params| match subscribe {
+			true => pubsub.subscribe(params, self.id, self.sink.clone()),
+			false => pubsub.unsubscribe(params, self.id),
+		});
+
+		// Notifications don't get a response.
+		let id = match call.get("id") {
+			Some(id) => serde_json::to_string(id).expect("Value serialization is infallible; qed"),
+			None => return None,
+		};
+
+		let output = match result {
+			Ok(result) => serde_json::to_string(&result).map(|result| format!(r#""result":{}"#, result)),
+			Err(error) => serde_json::to_string(&error).map(|error| format!(r#""error":{}"#, error)),
+		}.expect("Value and Error serialization is infallible; qed");
+
+		Some(format!(r#"{{"jsonrpc":"2.0",{},"id":{}}}"#, output, id))
+	}
+}
+
+impl Drop for PubSubSession {
+	fn drop(&mut self) {
+		if let Some(ref pubsub) = self.pubsub {
+			pubsub.close_session(self.id);
+		}
+	}
+}
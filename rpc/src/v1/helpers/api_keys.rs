@@ -0,0 +1,327 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! API keys granting access to a subset of the RPC namespaces, with daily request quotas.
+
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde_json::{self, Value};
+use util::{Mutex, FixedHash, H128};
+
+/// Number of counted requests after which the usage is written to disk.
+const SAVE_EVERY: usize = 100;
+
+/// Reason of refusing a request.
+#[derive(Debug, PartialEq)]
+pub enum KeyError {
+	/// The key is not known.
+	UnknownKey,
+	/// The daily quota of the key is used up.
+	QuotaExceeded(u64),
+}
+
+/// Namespaces a key grants access to and its usage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApiKey {
+	/// Namespaces the key grants access to.
+	pub apis: Vec<String>,
+	/// Maximal number of calls per day (UTC), unlimited if `None`.
+	pub daily_quota: Option<u64>,
+	/// Number of calls made on `day`.
+	pub used: u64,
+	/// Day of the last call, in days since the unix epoch.
+	pub day: u64,
+}
+
+struct State {
+	keys: BTreeMap<String, ApiKey>,
+	// requests counted since the keys were last saved.
+	unsaved: usize,
+}
+
+/// API keys, persisted to a file if it's given.
+pub struct ApiKeys {
+	path: Option<PathBuf>,
+	known_apis: Vec<String>,
+	state: Mutex<State>,
+}
+
+fn today() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() / 86400).unwrap_or(0)
+}
+
+impl ApiKeys {
+	/// Creates an empty set of keys, kept only in memory. Keys may grant access to `known_apis` only.
+	pub fn new(known_apis: Vec<String>) -> Self {
+		ApiKeys::with_keys(None, known_apis, BTreeMap::new())
+	}
+
+	/// Loads keys from the file at `path`, if it exists. Changes are written back to it.
+	pub fn load(path: PathBuf, known_apis: Vec<String>) -> Result<Self, String> {
+		let mut keys = BTreeMap::new();
+		if path.exists() {
+			let mut content = String::new();
+			try!(File::open(&path).and_then(|mut f| f.read_to_string(&mut content)).map_err(|e| format!("{}: {}", path.display(), e)));
+			let json: Value = try!(serde_json::from_str(&content).map_err(|e| format!("{}: Invalid API keys file: {}", path.display(), e)));
+			keys = try!(from_json(&json).ok_or_else(|| format!("{}: Invalid API keys file.", path.display())));
+		}
+		Ok(ApiKeys::with_keys(Some(path), known_apis, keys))
+	}
+
+	fn with_keys(path: Option<PathBuf>, known_apis: Vec<String>, keys: BTreeMap<String, ApiKey>) -> Self {
+		ApiKeys {
+			path: path,
+			known_apis: known_apis,
+			state: Mutex::new(State {
+				keys: keys,
+				unsaved: 0,
+			}),
+		}
+	}
+
+	/// Creates a new key granting access to `apis`, limited to `daily_quota` calls a day if given.
+	pub fn add(&self, apis: Vec<String>, daily_quota: Option<u64>) -> Result<String, String> {
+		if let Some(api) = apis.iter().find(|api| !self.known_apis.contains(api)) {
+			return Err(format!("Unknown API: {}", api));
+		}
+		let key = H128::random().hex();
+		self.state.lock().keys.insert(key.clone(), ApiKey {
+			apis: apis,
+			daily_quota: daily_quota,
+			used: 0,
+			day: today(),
+		});
+		self.save_logged();
+		Ok(key)
+	}
+
+	/// Removes a key. Returns `false` if it wasn't known.
+	pub fn remove(&self, key: &str) -> bool {
+		let removed = self.state.lock().keys.remove(key).is_some();
+		if removed {
+			self.save_logged();
+		}
+		removed
+	}
+
+	/// All keys with their namespaces and usage today.
+	pub fn list(&self) -> Vec<(String, ApiKey)> {
+		let today = today();
+		self.state.lock().keys.iter().map(|(key, api_key)| {
+			let mut api_key = api_key.clone();
+			if api_key.day != today {
+				api_key.day = today;
+				api_key.used = 0;
+			}
+			(key.clone(), api_key)
+		}).collect()
+	}
+
+	/// Counts `calls` made with `key` against its quota. Returns the namespaces the key grants access to.
+	pub fn authorize(&self, key: &str, calls: u64) -> Result<Vec<String>, KeyError> {
+		self.authorize_on(key, calls, today())
+	}
+
+	/// Counts every call of a JSON-RPC request (or batch) made with `key` against its quota.
+	/// Returns the namespaces the key grants access to.
+	pub fn authorize_request(&self, key: &str, request: &str) -> Result<Vec<String>, KeyError> {
+		let calls = match serde_json::from_str::<Value>(request) {
+			Ok(Value::Array(calls)) => calls.len() as u64,
+			_ => 1,
+		};
+		self.authorize(key, calls)
+	}
+
+	fn authorize_on(&self, key: &str, calls: u64, day: u64) -> Result<Vec<String>, KeyError> {
+		let apis = {
+			let mut state = self.state.lock();
+			let apis = {
+				let api_key = try!(state.keys.get_mut(key).ok_or(KeyError::UnknownKey));
+				if api_key.day != day {
+					api_key.day = day;
+					api_key.used = 0;
+				}
+				if let Some(quota) = api_key.daily_quota {
+					if api_key.used + calls > quota {
+						return Err(KeyError::QuotaExceeded(quota));
+					}
+				}
+				api_key.used += calls;
+				api_key.apis.clone()
+			};
+			state.unsaved += 1;
+			if state.unsaved < SAVE_EVERY {
+				return Ok(apis);
+			}
+			apis
+		};
+		self.save_logged();
+		Ok(apis)
+	}
+
+	/// Writes the keys to the file they were loaded from. The file is replaced at once,
+	/// so it's never left half-written, and is readable by the owner only.
+	pub fn save(&self) -> io::Result<()> {
+		let path = match self.path {
+			Some(ref path) => path,
+			None => return Ok(()),
+		};
+		// held while writing, so that an older state can't replace a newer one.
+		let mut state = self.state.lock();
+		state.unsaved = 0;
+		let content = try!(serde_json::to_string_pretty(&to_json(&state.keys)).map_err(|e| io::Error::new(io::ErrorKind::Other, format!("{}", e))));
+
+		let mut temp = path.clone().into_os_string();
+		temp.push(".tmp");
+		let temp = PathBuf::from(temp);
+		{
+			let mut file = try!(create_private(&temp));
+			try!(file.write_all(content.as_bytes()));
+			try!(file.sync_all());
+		}
+		fs::rename(&temp, path)
+	}
+
+	fn save_logged(&self) {
+		if let Err(e) = self.save() {
+			warn!(target: "rpc", "Cannot save API keys: {}", e);
+		}
+	}
+}
+
+impl Drop for ApiKeys {
+	fn drop(&mut self) {
+		self.save_logged();
+	}
+}
+
+// creates the file anew, accessible by the owner only.
+#[cfg(unix)]
+fn create_private(path: &Path) -> io::Result<File> {
+	use std::os::unix::fs::OpenOptionsExt;
+
+	// permissions are only applied to new files.
+	let _ = fs::remove_file(path);
+	fs::OpenOptions::new().write(true).create(true).truncate(true).mode(0o600).open(path)
+}
+
+#[cfg(not(unix))]
+fn create_private(path: &Path) -> io::Result<File> {
+	File::create(path)
+}
+
+fn to_json(keys: &BTreeMap<String, ApiKey>) -> Value {
+	Value::Object(keys.iter().map(|(key, api_key)| {
+		let mut object = BTreeMap::new();
+		object.insert("apis".to_owned(), Value::Array(api_key.apis.iter().cloned().map(Value::String).collect()));
+		object.insert("dailyQuota".to_owned(), api_key.daily_quota.map_or(Value::Null, Value::U64));
+		object.insert("used".to_owned(), Value::U64(api_key.used));
+		object.insert("day".to_owned(), Value::U64(api_key.day));
+		(key.clone(), Value::Object(object))
+	}).collect())
+}
+
+fn from_json(json: &Value) -> Option<BTreeMap<String, ApiKey>> {
+	let object = match json.as_object() {
+		Some(object) => object,
+		None => return None,
+	};
+	let mut keys = BTreeMap::new();
+	for (key, value) in object {
+		let apis = match value.find("apis").and_then(Value::as_array) {
+			Some(apis) => apis.iter().filter_map(|api| api.as_string().map(str::to_owned)).collect(),
+			None => return None,
+		};
+		keys.insert(key.clone(), ApiKey {
+			apis: apis,
+			daily_quota: value.find("dailyQuota").and_then(Value::as_u64),
+			used: value.find("used").and_then(Value::as_u64).unwrap_or(0),
+			day: value.find("day").and_then(Value::as_u64).unwrap_or(0),
+		});
+	}
+	Some(keys)
+}
+
+#[cfg(test)]
+mod tests {
+	use std::path::Path;
+	use devtools::RandomTempPath;
+	use super::{ApiKeys, KeyError};
+
+	fn known_apis() -> Vec<String> {
+		vec!["eth".into(), "net".into(), "ethcore".into()]
+	}
+
+	#[test]
+	fn enforces_daily_quota() {
+		let keys = ApiKeys::new(known_apis());
+		let key = keys.add(vec!["eth".into()], Some(3)).unwrap();
+
+		assert_eq!(keys.authorize_on(&key, 2, 100), Ok(vec!["eth".to_owned()]));
+		assert_eq!(keys.authorize_on(&key, 2, 100), Err(KeyError::QuotaExceeded(3)));
+		assert_eq!(keys.authorize_on(&key, 1, 100), Ok(vec!["eth".to_owned()]));
+		// quota is renewed the next day.
+		assert_eq!(keys.authorize_on(&key, 3, 101), Ok(vec!["eth".to_owned()]));
+		assert_eq!(keys.authorize_on("unknown", 1, 101), Err(KeyError::UnknownKey));
+
+		assert!(keys.add(vec!["personal".into()], None).is_err());
+		assert!(keys.remove(&key));
+		assert_eq!(keys.authorize_on(&key, 1, 101), Err(KeyError::UnknownKey));
+	}
+
+	#[test]
+	fn counts_batched_calls() {
+		let keys = ApiKeys::new(known_apis());
+		let key = keys.add(vec!["eth".into()], Some(3)).unwrap();
+
+		assert!(keys.authorize_request(&key, r#"[{"method":"a"},{"method":"b"}]"#).is_ok());
+		assert!(keys.authorize_request(&key, r#"{"method":"a"}"#).is_ok());
+		assert_eq!(keys.authorize_request(&key, r#"{"method":"a"}"#), Err(KeyError::QuotaExceeded(3)));
+	}
+
+	#[test]
+	fn persists_keys() {
+		let path = RandomTempPath::new();
+		let key = {
+			let keys = ApiKeys::load(path.as_path().to_owned(), known_apis()).unwrap();
+			let key = keys.add(vec!["eth".into(), "net".into()], None).unwrap();
+			keys.authorize(&key, 5).unwrap();
+			key
+		};
+
+		let keys = ApiKeys::load(path.as_path().to_owned(), known_apis()).unwrap();
+		let listed = keys.list();
+		assert_eq!(listed.len(), 1);
+		assert_eq!(listed[0].0, key);
+		assert_eq!(listed[0].1.apis, vec!["eth".to_owned(), "net".to_owned()]);
+		assert_eq!(listed[0].1.daily_quota, None);
+		assert_eq!(listed[0].1.used, 5);
+		assert_private(path.as_path());
+	}
+
+	#[cfg(unix)]
+	fn assert_private(path: &Path) {
+		use std::fs;
+		use std::os::unix::fs::PermissionsExt;
+		assert_eq!(fs::metadata(path).unwrap().permissions().mode() & 0o777, 0o600);
+	}
+
+	#[cfg(not(unix))]
+	fn assert_private(_path: &Path) {}
+}
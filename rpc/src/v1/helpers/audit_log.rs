@@ -0,0 +1,266 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Audit log of RPC calls.
+//!
+//! Every call is recorded on a line with the time, transport, origin, method and params.
+//! Params are truncated and secrets (e.g. account passwords) are redacted.
+//! The log is rotated once it grows over the configured size.
+
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use serde_json::{self, Value};
+use time;
+use util::Mutex;
+
+/// Number of rotated logs kept, as `<path>.1` (the most recent) to `<path>.N`.
+const ROTATED_LOGS: usize = 5;
+/// Params longer than this many characters are truncated.
+const MAX_PARAMS_LEN: usize = 256;
+/// Logged in place of secrets.
+const REDACTED: &'static str = "<redacted>";
+
+/// Positions of the params holding secrets, by method.
+const SECRET_PARAMS: &'static [(&'static str, usize)] = &[
+	("personal_newAccount", 0),
+	("personal_unlockAccount", 1),
+	("personal_signAndSendTransaction", 1),
+	("personal_sendTransaction", 1),
+	("personal_sign", 2),
+	("personal_confirmTransaction", 2),
+	("ethcore_removeApiKey", 0),
+];
+
+struct LogFile {
+	file: File,
+	size: u64,
+}
+
+/// Appends RPC calls to a size-rotated log file.
+pub struct AuditLog {
+	path: PathBuf,
+	max_size: u64,
+	file: Mutex<LogFile>,
+}
+
+impl AuditLog {
+	/// Opens (or creates) the log at given path, rotating it once it grows over `max_size` bytes.
+	pub fn open(path: PathBuf, max_size: u64) -> io::Result<Self> {
+		let file = try!(open_file(&path));
+		Ok(AuditLog {
+			path: path,
+			max_size: max_size,
+			file: Mutex::new(file),
+		})
+	}
+
+	/// Records every call of a JSON-RPC request (or batch).
+	/// `transport` is e.g. `http`, `origin` identifies the client, e.g. by its address.
+	pub fn log(&self, transport: &str, origin: &str, request: &str) {
+		let timestamp = time::now_utc().rfc3339().to_string();
+		let lines = calls(request).into_iter()
+			.map(|(method, params)| format!("{} {} {} {} {}\n", timestamp, transport, origin, method, params))
+			.collect::<String>();
+
+		if let Err(e) = self.write(lines.as_bytes()) {
+			warn!(target: "rpc", "Error writing audit log {}: {}", self.path.display(), e);
+		}
+	}
+
+	fn write(&self, data: &[u8]) -> io::Result<()> {
+		let mut file = self.file.lock();
+		if file.size > 0 && file.size + data.len() as u64 > self.max_size {
+			try!(self.rotate());
+			*file = try!(open_file(&self.path));
+		}
+		try!(file.file.write_all(data));
+		file.size += data.len() as u64;
+		Ok(())
+	}
+
+	// shifts the rotated logs by one, dropping the oldest, and moves the current log to `<path>.1`.
+	fn rotate(&self) -> io::Result<()> {
+		let oldest = self.rotated(ROTATED_LOGS);
+		if oldest.exists() {
+			try!(fs::remove_file(&oldest));
+		}
+		for index in (1..ROTATED_LOGS).rev() {
+			let log = self.rotated(index);
+			if log.exists() {
+				try!(fs::rename(&log, self.rotated(index + 1)));
+			}
+		}
+		fs::rename(&self.path, self.rotated(1))
+	}
+
+	fn rotated(&self, index: usize) -> PathBuf {
+		let mut path = OsString::from(self.path.as_os_str());
+		path.push(format!(".{}", index));
+		PathBuf::from(path)
+	}
+}
+
+fn open_file(path: &Path) -> io::Result<LogFile> {
+	let file = try!(open_append(path));
+	let size = try!(file.metadata()).len();
+	Ok(LogFile {
+		file: file,
+		size: size,
+	})
+}
+
+// opens the file for appending, creating it accessible by the owner only.
+#[cfg(unix)]
+fn open_append(path: &Path) -> io::Result<File> {
+	use std::os::unix::fs::OpenOptionsExt;
+
+	OpenOptions::new().create(true).append(true).mode(0o600).open(path)
+}
+
+#[cfg(not(unix))]
+fn open_append(path: &Path) -> io::Result<File> {
+	OpenOptions::new().create(true).append(true).open(path)
+}
+
+// method and printable params of every call in a request.
+fn calls(request: &str) -> Vec<(String, String)> {
+	fn call(value: &Value) -> (String, String) {
+		let method = value.find("method").and_then(Value::as_str).unwrap_or("").to_owned();
+		let params = match value.find("params") {
+			Some(params) => redact(&method, params.clone()),
+			None => Value::Array(vec![]),
+		};
+		let params = serde_json::to_string(&params).expect("Value serialization is infallible; qed");
+		(method, truncate(params))
+	}
+
+	match serde_json::from_str::<Value>(request) {
+		Ok(Value::Array(calls)) => calls.iter().map(call).collect(),
+		Ok(value) => vec![call(&value)],
+		// the text may hold secrets which can't be told apart
+		Err(_) => vec![("<invalid>".to_owned(), REDACTED.to_owned())],
+	}
+}
+
+fn redact(method: &str, params: Value) -> Value {
+	let positions = SECRET_PARAMS.iter()
+		.filter(|&&(m, _)| m == method)
+		.map(|&(_, position)| position)
+		.collect::<Vec<_>>();
+	if positions.is_empty() {
+		return params;
+	}
+
+	match params {
+		Value::Array(params) => Value::Array(params.into_iter().enumerate()
+			.map(|(position, param)| match positions.contains(&position) {
+				true => Value::String(REDACTED.to_owned()),
+				false => param,
+			})
+			.collect()),
+		// can't tell which of named params are secret
+		_ => Value::String(REDACTED.to_owned()),
+	}
+}
+
+fn truncate(params: String) -> String {
+	match params.char_indices().nth(MAX_PARAMS_LEN) {
+		Some((end, _)) => format!("{}...", &params[..end]),
+		None => params,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::fs::File;
+	use std::io::Read;
+	use std::path::Path;
+	use devtools::RandomTempPath;
+	use super::{AuditLog, calls};
+
+	fn read(path: &Path) -> String {
+		let mut content = String::new();
+		File::open(path).unwrap().read_to_string(&mut content).unwrap();
+		content
+	}
+
+	#[test]
+	fn should_redact_secrets() {
+		let request = r#"[
+			{"jsonrpc":"2.0","method":"personal_unlockAccount","params":["0x0000000000000000000000000000000000000001","password",null],"id":1},
+			{"jsonrpc":"2.0","method":"personal_newAccount","params":{"pass":"password"},"id":2},
+			{"jsonrpc":"2.0","method":"eth_blockNumber","id":3},
+			{"jsonrpc":"2.0","method":"ethcore_removeApiKey","params":["0123456789abcdef0123456789abcdef"],"id":4}
+		]"#;
+
+		assert_eq!(calls(request), vec![
+			("personal_unlockAccount".to_owned(), r#"["0x0000000000000000000000000000000000000001","<redacted>",null]"#.to_owned()),
+			("personal_newAccount".to_owned(), r#""<redacted>""#.to_owned()),
+			("eth_blockNumber".to_owned(), "[]".to_owned()),
+			("ethcore_removeApiKey".to_owned(), r#"["<redacted>"]"#.to_owned()),
+		]);
+	}
+
+	#[test]
+	fn should_truncate_params() {
+		let data = (0..300).map(|_| "a").collect::<String>();
+		let request = format!(r#"{{"jsonrpc":"2.0","method":"eth_sendRawTransaction","params":["0x{}"],"id":1}}"#, data);
+
+		let (method, params) = calls(&request).pop().unwrap();
+		assert_eq!(method, "eth_sendRawTransaction");
+		assert_eq!(params.len(), 256 + 3);
+		assert!(params.starts_with(r#"["0xaaa"#) && params.ends_with("aa..."));
+	}
+
+	#[test]
+	fn should_not_log_invalid_requests() {
+		let request = r#"{"jsonrpc":"2.0","method":"personal_unlockAccount","params":["0x01","password",null]"#;
+
+		assert_eq!(calls(request), vec![("<invalid>".to_owned(), "<redacted>".to_owned())]);
+	}
+
+	#[test]
+	fn should_rotate_by_size() {
+		let temp = RandomTempPath::create_dir();
+		let path = temp.as_path().join("audit.log");
+		let request = r#"{"jsonrpc":"2.0","method":"eth_blockNumber","params":[],"id":1}"#;
+
+		let log = AuditLog::open(path.clone(), 100).unwrap();
+		log.log("http", "127.0.0.1", request);
+		let first = read(&path);
+		assert!(first.ends_with(" http 127.0.0.1 eth_blockNumber []\n"));
+		assert!(first.len() < 100);
+
+		log.log("ws", "localhost:8180", request);
+		assert_eq!(read(&log.rotated(1)), first);
+		assert!(read(&path).ends_with(" ws localhost:8180 eth_blockNumber []\n"));
+	}
+
+	#[test]
+	#[cfg(unix)]
+	fn should_create_private_log() {
+		use std::fs;
+		use std::os::unix::fs::PermissionsExt;
+
+		let temp = RandomTempPath::create_dir();
+		let path = temp.as_path().join("audit.log");
+		AuditLog::open(path.clone(), 100).unwrap();
+
+		assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+	}
+}
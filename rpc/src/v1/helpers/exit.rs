@@ -0,0 +1,102 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::thread;
+use std::time::Duration;
+use std::sync::Arc;
+use util::Mutex;
+
+/// The way the node was asked to stop.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExitRequest {
+	/// Stop the node.
+	Shutdown,
+	/// Stop the node and start it again with the same arguments.
+	Restart,
+}
+
+/// Schedules a graceful shutdown or restart of the node.
+///
+/// The node registers a handler which wakes up its main thread; the main thread
+/// then goes through the regular shutdown path and checks `request` to see whether
+/// it should start again.
+#[derive(Default)]
+pub struct ExitHandle {
+	request: Mutex<Option<ExitRequest>>,
+	handler: Mutex<Option<Box<Fn() + Send>>>,
+}
+
+impl ExitHandle {
+	/// Creates new handle without any handler registered.
+	pub fn new() -> Self {
+		ExitHandle::default()
+	}
+
+	/// Sets the function called when the scheduled exit is due.
+	pub fn set_handler<F>(&self, f: F) where F: Fn() + Send + 'static {
+		*self.handler.lock() = Some(Box::new(f));
+	}
+
+	/// Returns the exit request which is due, if any.
+	pub fn request(&self) -> Option<ExitRequest> {
+		*self.request.lock()
+	}
+
+	/// Triggers the exit immediately. The first request wins.
+	pub fn exit(&self, request: ExitRequest) {
+		{
+			let mut current = self.request.lock();
+			if current.is_some() {
+				return;
+			}
+			*current = Some(request);
+		}
+		info!("{:?} requested", request);
+		if let Some(ref handler) = *self.handler.lock() {
+			handler();
+		}
+	}
+
+	/// Triggers the exit after given delay.
+	pub fn schedule(handle: &Arc<ExitHandle>, request: ExitRequest, delay: Duration) {
+		let handle = handle.clone();
+		thread::spawn(move || {
+			thread::sleep(delay);
+			handle.exit(request);
+		});
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::sync::Arc;
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use super::{ExitHandle, ExitRequest};
+
+	#[test]
+	fn should_call_handler_once() {
+		let calls = Arc::new(AtomicUsize::new(0));
+		let handle = ExitHandle::new();
+		let c = calls.clone();
+		handle.set_handler(move || { c.fetch_add(1, Ordering::SeqCst); });
+
+		handle.exit(ExitRequest::Restart);
+		handle.exit(ExitRequest::Shutdown);
+
+		assert_eq!(handle.request(), Some(ExitRequest::Restart));
+		assert_eq!(calls.load(Ordering::SeqCst), 1);
+	}
+}
@@ -14,12 +14,22 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
+mod api_keys;
+mod audit_log;
+mod exit;
 mod poll_manager;
 mod poll_filter;
+mod rate_limiter;
 mod requests;
 mod signing_queue;
+mod subscribers;
 
+pub use self::api_keys::{ApiKeys, ApiKey, KeyError};
+pub use self::audit_log::AuditLog;
+pub use self::exit::{ExitHandle, ExitRequest};
 pub use self::poll_manager::PollManager;
 pub use self::poll_filter::PollFilter;
+pub use self::rate_limiter::{RateLimiter, RateLimits, LimitError};
 pub use self::requests::{TransactionRequest, TransactionConfirmation, CallRequest};
 pub use self::signing_queue::{ConfirmationsQueue, ConfirmationPromise, ConfirmationResult, SigningQueue, QueueEvent};
+pub use self::subscribers::{Subscribers, Sink, SessionId, SubscriptionId};
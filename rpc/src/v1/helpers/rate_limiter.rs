@@ -0,0 +1,229 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Per-client limits of calls per second, with optional stricter limits for single methods.
+
+use std::fmt;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use util::Mutex;
+
+/// Clients idle for longer than this are forgotten.
+const FORGET_AFTER_SEC: u64 = 60;
+/// Number of checks after which idle clients are forgotten.
+const PRUNE_EVERY: usize = 1024;
+
+/// Limits of calls per second a single client may make.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimits {
+	/// Limit of all calls, unlimited if `None`.
+	pub calls_per_sec: Option<u32>,
+	/// Limits of calls to single methods.
+	pub methods: HashMap<String, u32>,
+}
+
+/// Reason of refusing a request.
+#[derive(Debug, PartialEq)]
+pub enum LimitError {
+	/// The client exceeded the limit of all calls.
+	Calls(u32),
+	/// The client exceeded the limit of calls to a method.
+	Method(String, u32),
+}
+
+impl fmt::Display for LimitError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			LimitError::Calls(limit) => write!(f, "Rate limit of {} calls per second exceeded.", limit),
+			LimitError::Method(ref method, limit) => write!(f, "Rate limit of {} calls per second to {} exceeded.", limit, method),
+		}
+	}
+}
+
+// Token bucket refilled with `rate` tokens a second and holding at most `rate` tokens.
+struct Bucket {
+	tokens: f64,
+	updated: Instant,
+}
+
+impl Bucket {
+	fn new(rate: u32, now: Instant) -> Self {
+		Bucket {
+			tokens: rate as f64,
+			updated: now,
+		}
+	}
+
+	fn refill(&mut self, rate: u32, now: Instant) {
+		let elapsed = now.duration_since(self.updated);
+		let elapsed = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000f64;
+		self.tokens = (self.tokens + elapsed * rate as f64).min(rate as f64);
+		self.updated = now;
+	}
+}
+
+struct Client {
+	calls: Option<Bucket>,
+	methods: HashMap<String, Bucket>,
+	last_seen: Instant,
+}
+
+struct State {
+	clients: HashMap<IpAddr, Client>,
+	checks: usize,
+}
+
+/// Tracks the calls of every client.
+///
+/// All calls of a batch are counted, so a batch larger than a limit is always refused.
+pub struct RateLimiter {
+	limits: RateLimits,
+	state: Mutex<State>,
+}
+
+impl RateLimiter {
+	/// Creates a limiter enforcing given limits.
+	pub fn new(limits: RateLimits) -> Self {
+		RateLimiter {
+			limits: limits,
+			state: Mutex::new(State {
+				clients: HashMap::new(),
+				checks: 0,
+			}),
+		}
+	}
+
+	/// Counts a request of `client` making calls to given methods.
+	/// The request is not counted if it exceeds any of the limits.
+	pub fn check(&self, client: IpAddr, methods: &[String]) -> Result<(), LimitError> {
+		self.check_at(client, methods, Instant::now())
+	}
+
+	fn check_at(&self, client: IpAddr, methods: &[String], now: Instant) -> Result<(), LimitError> {
+		let mut state = self.state.lock();
+		state.checks += 1;
+		if state.checks % PRUNE_EVERY == 0 {
+			let idle = state.clients.iter()
+				.filter(|&(_, client)| now.duration_since(client.last_seen) > Duration::from_secs(FORGET_AFTER_SEC))
+				.map(|(ip, _)| *ip)
+				.collect::<Vec<_>>();
+			for ip in idle {
+				state.clients.remove(&ip);
+			}
+		}
+
+		let client = state.clients.entry(client).or_insert_with(|| Client {
+			calls: None,
+			methods: HashMap::new(),
+			last_seen: now,
+		});
+		client.last_seen = now;
+
+		let mut counts: HashMap<&str, u32> = HashMap::new();
+		for method in methods.iter().filter(|method| self.limits.methods.contains_key(*method)) {
+			*counts.entry(method.as_str()).or_insert(0) += 1;
+		}
+
+		// check all the limits before counting the calls against any of them.
+		if let Some(rate) = self.limits.calls_per_sec {
+			if client.calls.is_none() {
+				client.calls = Some(Bucket::new(rate, now));
+			}
+			let bucket = client.calls.as_mut().expect("Inserted above; qed");
+			bucket.refill(rate, now);
+			if bucket.tokens < methods.len() as f64 {
+				return Err(LimitError::Calls(rate));
+			}
+		}
+		for (method, count) in &counts {
+			let rate = self.limits.methods[*method];
+			let bucket = client.methods.entry((*method).to_owned()).or_insert_with(|| Bucket::new(rate, now));
+			bucket.refill(rate, now);
+			if bucket.tokens < *count as f64 {
+				return Err(LimitError::Method((*method).to_owned(), rate));
+			}
+		}
+
+		if let Some(ref mut bucket) = client.calls {
+			bucket.tokens -= methods.len() as f64;
+		}
+		for (method, count) in counts {
+			if let Some(bucket) = client.methods.get_mut(method) {
+				bucket.tokens -= count as f64;
+			}
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::HashMap;
+	use std::net::IpAddr;
+	use std::str::FromStr;
+	use std::time::{Duration, Instant};
+	use super::{RateLimiter, RateLimits, LimitError};
+
+	fn calls(methods: &[&str]) -> Vec<String> {
+		methods.iter().map(|m| m.to_string()).collect()
+	}
+
+	#[test]
+	fn should_limit_calls_per_client() {
+		// given
+		let limiter = RateLimiter::new(RateLimits {
+			calls_per_sec: Some(2),
+			methods: HashMap::new(),
+		});
+		let (client, other) = (IpAddr::from_str("10.0.0.1").unwrap(), IpAddr::from_str("10.0.0.2").unwrap());
+		let now = Instant::now();
+
+		// when
+		assert_eq!(limiter.check_at(client, &calls(&["eth_call", "eth_call"]), now), Ok(()));
+		assert_eq!(limiter.check_at(client, &calls(&["eth_call"]), now), Err(LimitError::Calls(2)));
+		assert_eq!(limiter.check_at(other, &calls(&["eth_call"]), now), Ok(()));
+
+		// then
+		let later = now + Duration::from_millis(500);
+		assert_eq!(limiter.check_at(client, &calls(&["eth_call"]), later), Ok(()));
+		assert_eq!(limiter.check_at(client, &calls(&["eth_call"]), later), Err(LimitError::Calls(2)));
+	}
+
+	#[test]
+	fn should_limit_single_methods() {
+		// given
+		let mut methods = HashMap::new();
+		methods.insert("eth_getLogs".to_owned(), 1);
+		let limiter = RateLimiter::new(RateLimits {
+			calls_per_sec: Some(10),
+			methods: methods,
+		});
+		let client = IpAddr::from_str("10.0.0.1").unwrap();
+		let now = Instant::now();
+
+		// when
+		assert_eq!(limiter.check_at(client, &calls(&["eth_getLogs", "eth_blockNumber"]), now), Ok(()));
+		assert_eq!(limiter.check_at(client, &calls(&["eth_blockNumber", "eth_getLogs"]), now), Err(LimitError::Method("eth_getLogs".to_owned(), 1)));
+
+		// then
+		// refused request was not counted against the limit of all calls.
+		for _ in 0..8 {
+			assert_eq!(limiter.check_at(client, &calls(&["eth_blockNumber"]), now), Ok(()));
+		}
+		assert_eq!(limiter.check_at(client, &calls(&["eth_blockNumber"]), now), Err(LimitError::Calls(10)));
+	}
+}
@@ -45,7 +45,7 @@ pub struct TransactionConfirmation {
 }
 
 /// Call request
-#[derive(Debug, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct CallRequest {
 	/// From
 	pub from: Option<Address>,
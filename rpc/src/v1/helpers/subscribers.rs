@@ -0,0 +1,145 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Indexes all active pub-sub subscriptions.
+
+use std::sync::Arc;
+use std::collections::HashMap;
+
+pub type SubscriptionId = usize;
+
+/// Identifies a single connection of a pub-sub capable transport.
+pub type SessionId = usize;
+
+/// Delivers serialized notifications to a connected client.
+///
+/// Returns `false` if the message could not be delivered (e.g. the client has gone away).
+pub type Sink = Arc<Fn(String) -> bool + Send + Sync>;
+
+/// A single subscription.
+pub struct Subscriber<T> {
+	/// Session which created the subscription.
+	pub session: SessionId,
+	/// Where to send notifications.
+	pub sink: Sink,
+	/// Subscription parameters.
+	pub params: T,
+}
+
+/// Indexes subscriptions of all sessions.
+pub struct Subscribers<T> {
+	subscriptions: HashMap<SubscriptionId, Subscriber<T>>,
+	next_available_id: SubscriptionId,
+}
+
+impl<T> Subscribers<T> {
+	/// Creates new empty index.
+	pub fn new() -> Self {
+		Subscribers {
+			subscriptions: HashMap::new(),
+			next_available_id: 0,
+		}
+	}
+
+	/// Returns `true` if there are no active subscriptions.
+	pub fn is_empty(&self) -> bool {
+		self.subscriptions.is_empty()
+	}
+
+	/// Registers new subscription and returns its id.
+	pub fn insert(&mut self, session: SessionId, sink: Sink, params: T) -> SubscriptionId {
+		let id = self.next_available_id;
+		self.subscriptions.insert(id, Subscriber {
+			session: session,
+			sink: sink,
+			params: params,
+		});
+
+		self.next_available_id += 1;
+		id
+	}
+
+	/// Removes subscription if it belongs to given session.
+	/// Returns `false` if there was no such subscription.
+	pub fn remove(&mut self, session: SessionId, id: &SubscriptionId) -> bool {
+		match self.subscriptions.get(id) {
+			Some(subscriber) if subscriber.session == session => {},
+			_ => return false,
+		}
+		self.subscriptions.remove(id);
+		true
+	}
+
+	/// Removes all subscriptions of given session.
+	pub fn remove_session(&mut self, session: SessionId) {
+		let ids = self.subscriptions.iter()
+			.filter(|&(_, subscriber)| subscriber.session == session)
+			.map(|(id, _)| *id)
+			.collect::<Vec<_>>();
+		for id in ids {
+			self.subscriptions.remove(&id);
+		}
+	}
+
+	/// Iterates over all subscriptions.
+	pub fn iter(&self) -> ::std::collections::hash_map::Iter<SubscriptionId, Subscriber<T>> {
+		self.subscriptions.iter()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::sync::Arc;
+	use super::{Subscribers, Sink};
+
+	fn sink() -> Sink {
+		Arc::new(|_| true)
+	}
+
+	#[test]
+	fn should_only_remove_own_subscriptions() {
+		// given
+		let mut subscribers = Subscribers::new();
+		let first = subscribers.insert(0, sink(), ());
+		let second = subscribers.insert(1, sink(), ());
+		assert!(first != second);
+
+		// when
+		assert!(!subscribers.remove(0, &second));
+		assert!(subscribers.remove(0, &first));
+		assert!(!subscribers.remove(0, &first));
+
+		// then
+		assert_eq!(subscribers.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![second]);
+	}
+
+	#[test]
+	fn should_remove_whole_session() {
+		// given
+		let mut subscribers = Subscribers::new();
+		subscribers.insert(0, sink(), ());
+		subscribers.insert(0, sink(), ());
+		let other = subscribers.insert(1, sink(), ());
+
+		// when
+		subscribers.remove_session(0);
+
+		// then
+		assert_eq!(subscribers.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![other]);
+		subscribers.remove_session(1);
+		assert!(subscribers.is_empty());
+	}
+}
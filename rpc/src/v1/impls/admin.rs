@@ -0,0 +1,84 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Admin rpc implementation.
+use std::sync::{Arc, Weak};
+use jsonrpc_core::*;
+use ethsync::{SyncProvider, ManageNetwork};
+use v1::traits::Admin;
+use v1::types::{PeerInfo, LocalNodeInfo};
+
+/// Admin rpc implementation.
+pub struct AdminClient<S: ?Sized> where S: SyncProvider {
+	sync: Weak<S>,
+	net: Weak<ManageNetwork>,
+}
+
+impl<S: ?Sized> AdminClient<S> where S: SyncProvider {
+	/// Creates new AdminClient.
+	pub fn new(sync: &Arc<S>, net: &Arc<ManageNetwork>) -> Self {
+		AdminClient {
+			sync: Arc::downgrade(sync),
+			net: Arc::downgrade(net),
+		}
+	}
+}
+
+impl<S: ?Sized> Admin for AdminClient<S> where S: SyncProvider + 'static {
+	fn add_peer(&self, params: Params) -> Result<Value, Error> {
+		from_params::<(String,)>(params).and_then(|(peer,)| {
+			match take_weak!(self.net).add_reserved_peer(peer) {
+				Ok(()) => to_value(&true),
+				Err(_) => Err(Error::invalid_params()),
+			}
+		})
+	}
+
+	fn remove_peer(&self, params: Params) -> Result<Value, Error> {
+		from_params::<(String,)>(params).and_then(|(peer,)| {
+			match take_weak!(self.net).remove_reserved_peer(peer) {
+				Ok(()) => to_value(&true),
+				Err(_) => Err(Error::invalid_params()),
+			}
+		})
+	}
+
+	fn peers(&self, params: Params) -> Result<Value, Error> {
+		match params {
+			Params::None => {
+				let peers = take_weak!(self.net).connected_peers().into_iter().map(PeerInfo::from).collect::<Vec<_>>();
+				to_value(&peers)
+			},
+			_ => Err(Error::invalid_params()),
+		}
+	}
+
+	fn node_info(&self, params: Params) -> Result<Value, Error> {
+		match params {
+			Params::None => {
+				let sync = take_weak!(self.sync);
+				let config = take_weak!(self.net).network_config();
+				to_value(&LocalNodeInfo {
+					name: config.client_version,
+					enode: sync.enode(),
+					listen_address: config.listen_address,
+					protocols: sync.protocols(),
+				})
+			},
+			_ => Err(Error::invalid_params()),
+		}
+	}
+}
@@ -32,19 +32,19 @@ use util::sha3::*;
 use util::rlp::{encode, decode, UntrustedRlp, View};
 use util::{FromHex, Mutex};
 use ethcore::account_provider::AccountProvider;
-use ethcore::client::{MiningBlockChainClient, BlockID, TransactionID, UncleID};
+use ethcore::client::{BlockChainClient, MiningBlockChainClient, BlockID, TransactionID, UncleID, Executed, StateOverride as EthStateOverride};
 use ethcore::header::Header as BlockHeader;
 use ethcore::block::IsBlock;
 use ethcore::views::*;
 use ethcore::ethereum::Ethash;
-use ethcore::transaction::{Transaction as EthTransaction, SignedTransaction, Action};
+use ethcore::transaction::SignedTransaction;
 use ethcore::log_entry::LogEntry;
 use ethcore::filter::Filter as EthcoreFilter;
 use self::ethash::SeedHashCompute;
 use v1::traits::Eth;
-use v1::types::{Block, BlockTransactions, BlockNumber, Bytes, SyncStatus, SyncInfo, Transaction, CallRequest, Index, Filter, Log, Receipt, H64 as RpcH64, H256 as RpcH256, H160 as RpcH160, U256 as RpcU256};
+use v1::types::{Block, BlockTransactions, BlockNumber, Bytes, SyncStatus, SyncInfo, Transaction, CallRequest, StateOverride, Index, Filter, Log, Receipt, H64 as RpcH64, H256 as RpcH256, H160 as RpcH160, U256 as RpcU256};
 use v1::helpers::CallRequest as CRequest;
-use v1::impls::{default_gas_price, dispatch_transaction, error_codes};
+use v1::impls::{default_gas_price, dispatch_transaction, error_codes, gas_estimation_error, params_len, sign_call};
 use serde;
 
 /// Eth rpc implementation.
@@ -61,6 +61,7 @@ pub struct EthClient<C, S: ?Sized, M, EM> where
 	external_miner: Arc<EM>,
 	seed_compute: Mutex<SeedHashCompute>,
 	allow_pending_receipt_query: bool,
+	gas_cap: U256,
 }
 
 impl<C, S: ?Sized, M, EM> EthClient<C, S, M, EM> where
@@ -70,7 +71,9 @@ impl<C, S: ?Sized, M, EM> EthClient<C, S, M, EM> where
 	EM: ExternalMinerService {
 
 	/// Creates new EthClient.
-	pub fn new(client: &Arc<C>, sync: &Arc<S>, accounts: &Arc<AccountProvider>, miner: &Arc<M>, em: &Arc<EM>, allow_pending_receipt_query: bool)
+	///
+	/// `gas_cap` is the gas supplied to calls which don't specify it and the upper bound of gas estimation.
+	pub fn new(client: &Arc<C>, sync: &Arc<S>, accounts: &Arc<AccountProvider>, miner: &Arc<M>, em: &Arc<EM>, allow_pending_receipt_query: bool, gas_cap: U256)
 		-> EthClient<C, S, M, EM> {
 		EthClient {
 			client: Arc::downgrade(client),
@@ -80,43 +83,14 @@ impl<C, S: ?Sized, M, EM> EthClient<C, S, M, EM> where
 			external_miner: em.clone(),
 			seed_compute: Mutex::new(SeedHashCompute::new()),
 			allow_pending_receipt_query: allow_pending_receipt_query,
+			gas_cap: gas_cap,
 		}
 	}
 
 	fn block(&self, id: BlockID, include_txs: bool) -> Result<Value, Error> {
-		let client = take_weak!(self.client);
-		match (client.block(id.clone()), client.block_total_difficulty(id)) {
-			(Some(bytes), Some(total_difficulty)) => {
-				let block_view = BlockView::new(&bytes);
-				let view = block_view.header_view();
-				let block = Block {
-					hash: Some(view.sha3().into()),
-					size: Some(bytes.len()),
-					parent_hash: view.parent_hash().into(),
-					uncles_hash: view.uncles_hash().into(),
-					author: view.author().into(),
-					miner: view.author().into(),
-					state_root: view.state_root().into(),
-					transactions_root: view.transactions_root().into(),
-					receipts_root: view.receipts_root().into(),
-					number: Some(view.number().into()),
-					gas_used: view.gas_used().into(),
-					gas_limit: view.gas_limit().into(),
-					logs_bloom: view.log_bloom().into(),
-					timestamp: view.timestamp().into(),
-					difficulty: view.difficulty().into(),
-					total_difficulty: total_difficulty.into(),
-					seal_fields: view.seal().into_iter().map(|f| decode(&f)).map(Bytes::new).collect(),
-					uncles: block_view.uncle_hashes().into_iter().map(Into::into).collect(),
-					transactions: match include_txs {
-						true => BlockTransactions::Full(block_view.localized_transactions().into_iter().map(Into::into).collect()),
-						false => BlockTransactions::Hashes(block_view.transaction_hashes().into_iter().map(Into::into).collect()),
-					},
-					extra_data: Bytes::new(view.extra_data())
-				};
-				to_value(&block)
-			},
-			_ => Ok(Value::Null)
+		match block(&*take_weak!(self.client), id, include_txs) {
+			Some(block) => to_value(&block),
+			None => Ok(Value::Null)
 		}
 	}
 
@@ -165,15 +139,82 @@ impl<C, S: ?Sized, M, EM> EthClient<C, S, M, EM> where
 
 	fn sign_call(&self, request: CRequest) -> Result<SignedTransaction, Error> {
 		let (client, miner) = (take_weak!(self.client), take_weak!(self.miner));
-		let from = request.from.unwrap_or(Address::zero());
-		Ok(EthTransaction {
-			nonce: request.nonce.unwrap_or_else(|| client.latest_nonce(&from)),
-			action: request.to.map_or(Action::Create, Action::Call),
-			gas: request.gas.unwrap_or(U256::from(50_000_000)),
-			gas_price: request.gas_price.unwrap_or_else(|| default_gas_price(&*client, &*miner)),
-			value: request.value.unwrap_or_else(U256::zero),
-			data: request.data.map_or_else(Vec::new, |d| d.to_vec())
-		}.fake_sign(from))
+		Ok(sign_call(&*client, &*miner, request, BlockID::Latest, self.gas_cap))
+	}
+
+	fn call_with_gas(&self, request: &CRequest, gas: U256, block_number: &BlockNumber) -> Result<Option<Executed>, Error> {
+		let mut request = request.clone();
+		request.gas = Some(gas);
+		let signed = try!(self.sign_call(request));
+		Ok(match *block_number {
+			BlockNumber::Pending => take_weak!(self.miner).call(take_weak!(self.client).deref(), &signed, Default::default(), &Default::default()).ok(),
+			_ => take_weak!(self.client).call(&signed, BlockID::Latest, Default::default(), &Default::default()).ok(),
+		})
+	}
+
+	/// Binary-searches the lowest gas (bounded by the requested gas and the gas cap)
+	/// for which the call executes without an exception.
+	fn estimate_gas_used(&self, request: CRequest, block_number: BlockNumber) -> Result<U256, Error> {
+		let upper = match request.gas {
+			Some(gas) if gas < self.gas_cap => gas,
+			_ => self.gas_cap,
+		};
+
+		let used = match try!(self.call_with_gas(&request, upper, &block_number)) {
+			Some(ref executed) if executed.exception => return Err(gas_estimation_error(upper)),
+			Some(executed) => executed.gas_used + executed.refunded,
+			None => return Ok(U256::zero()),
+		};
+		if used >= upper {
+			return Ok(used);
+		}
+
+		// Anything below the gas consumed before refunds can't succeed.
+		let (mut lo, mut hi) = (if used.is_zero() { used } else { used - U256::one() }, upper);
+		while hi - lo > U256::one() {
+			let mid = (lo + hi) / U256::from(2);
+			match try!(self.call_with_gas(&request, mid, &block_number)) {
+				Some(ref executed) if !executed.exception => hi = mid,
+				_ => lo = mid,
+			}
+		}
+		Ok(hi)
+	}
+}
+
+/// Builds the rpc representation of a block known to the client.
+pub fn block<C>(client: &C, id: BlockID, include_txs: bool) -> Option<Block> where C: BlockChainClient {
+	match (client.block(id.clone()), client.block_total_difficulty(id)) {
+		(Some(bytes), Some(total_difficulty)) => {
+			let block_view = BlockView::new(&bytes);
+			let view = block_view.header_view();
+			Some(Block {
+				hash: Some(view.sha3().into()),
+				size: Some(bytes.len()),
+				parent_hash: view.parent_hash().into(),
+				uncles_hash: view.uncles_hash().into(),
+				author: view.author().into(),
+				miner: view.author().into(),
+				state_root: view.state_root().into(),
+				transactions_root: view.transactions_root().into(),
+				receipts_root: view.receipts_root().into(),
+				number: Some(view.number().into()),
+				gas_used: view.gas_used().into(),
+				gas_limit: view.gas_limit().into(),
+				logs_bloom: view.log_bloom().into(),
+				timestamp: view.timestamp().into(),
+				difficulty: view.difficulty().into(),
+				total_difficulty: total_difficulty.into(),
+				seal_fields: view.seal().into_iter().map(|f| decode(&f)).map(Bytes::new).collect(),
+				uncles: block_view.uncle_hashes().into_iter().map(Into::into).collect(),
+				transactions: match include_txs {
+					true => BlockTransactions::Full(block_view.localized_transactions().into_iter().map(Into::into).collect()),
+					false => BlockTransactions::Hashes(block_view.transaction_hashes().into_iter().map(Into::into).collect()),
+				},
+				extra_data: Bytes::new(view.extra_data())
+			})
+		},
+		_ => None
 	}
 }
 
@@ -198,13 +239,6 @@ pub fn pending_logs<M>(miner: &M, filter: &EthcoreFilter) -> Vec<Log> where M: M
 
 const MAX_QUEUE_SIZE_TO_MINE_ON: usize = 4;	// because uncles go back 6.
 
-fn params_len(params: &Params) -> usize {
-	match params {
-		&Params::Array(ref vec) => vec.len(),
-		_ => 0,
-	}
-}
-
 fn from_params_default_second<F>(params: Params) -> Result<(F, BlockNumber, ), Error> where F: serde::de::Deserialize {
 	match params_len(&params) {
 		1 => from_params::<(F, )>(params).map(|(f,)| (f, BlockNumber::Latest)),
@@ -283,7 +317,9 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 				let status = take_weak!(self.sync).status();
 				let res = match status.state {
 					SyncState::Idle => SyncStatus::None,
-					SyncState::Waiting | SyncState::Blocks | SyncState::NewBlocks | SyncState::ChainHead => {
+					SyncState::Waiting | SyncState::Blocks | SyncState::NewBlocks | SyncState::ChainHead
+						| SyncState::SnapshotManifest | SyncState::SnapshotHeader | SyncState::SnapshotData
+						| SyncState::SnapshotWaiting => {
 						let current_block = U256::from(take_weak!(self.client).chain_info().best_block_number);
 						let highest_block = U256::from(status.highest_block_number.unwrap_or(status.start_block_number));
 
@@ -471,6 +507,25 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 			})
 	}
 
+	fn transaction_by_sender_and_nonce(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(RpcH160, RpcU256)>(params)
+			.and_then(|(sender, nonce)| {
+				let sender: Address = sender.into();
+				let nonce: U256 = nonce.into();
+				if let Some(tx) = take_weak!(self.client).transaction_by_sender_and_nonce(&sender, &nonce) {
+					return to_value(&Transaction::from(tx));
+				}
+
+				let pending = take_weak!(self.miner).all_transactions().into_iter()
+					.find(|tx| tx.nonce == nonce && tx.sender().ok().as_ref() == Some(&sender));
+				match pending {
+					Some(tx) => to_value(&Transaction::from(tx)),
+					None => Ok(Value::Null),
+				}
+			})
+	}
+
 	fn transaction_by_block_hash_and_index(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		from_params::<(RpcH256, Index)>(params)
@@ -622,14 +677,20 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 	fn call(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		trace!(target: "jsonrpc", "call: {:?}", params);
-		from_params_default_second(params)
-			.and_then(|(request, block_number,)| {
+		let params = match params_len(&params) {
+			3 => from_params::<(CallRequest, BlockNumber, StateOverride)>(params),
+			_ => from_params_default_second(params).map(|(request, block_number,)| (request, block_number, StateOverride::new())),
+		};
+		params
+			.and_then(|(request, block_number, state_override)| {
 				let request = CallRequest::into(request);
 				let signed = try!(self.sign_call(request));
+				let state_override: EthStateOverride = state_override.into_iter()
+					.map(|(address, account)| (address.into(), account.into()))
+					.collect();
 				let r = match block_number {
-					BlockNumber::Pending => take_weak!(self.miner).call(take_weak!(self.client).deref(), &signed, Default::default()),
-					BlockNumber::Latest => take_weak!(self.client).call(&signed, Default::default()),
-					_ => panic!("{:?}", block_number),
+					BlockNumber::Pending => take_weak!(self.miner).call(take_weak!(self.client).deref(), &signed, Default::default(), &state_override).ok(),
+					block_number => take_weak!(self.client).call(&signed, block_number.into(), Default::default(), &state_override).ok(),
 				};
 				to_value(&r.map(|e| Bytes(e.output)).unwrap_or(Bytes::new(vec![])))
 			})
@@ -639,14 +700,13 @@ impl<C, S: ?Sized, M, EM> Eth for EthClient<C, S, M, EM> where
 		try!(self.active());
 		from_params_default_second(params)
 			.and_then(|(request, block_number,)| {
-				let request = CallRequest::into(request);
-				let signed = try!(self.sign_call(request));
-				let r = match block_number {
-					BlockNumber::Pending => take_weak!(self.miner).call(take_weak!(self.client).deref(), &signed, Default::default()),
-					BlockNumber::Latest => take_weak!(self.client).call(&signed, Default::default()),
+				match block_number {
+					BlockNumber::Pending | BlockNumber::Latest => {},
 					_ => return Err(Error::invalid_params()),
-				};
-				to_value(&RpcU256::from(r.map(|res| res.gas_used + res.refunded).unwrap_or(From::from(0))))
+				}
+				let request = CallRequest::into(request);
+				let gas = try!(self.estimate_gas_used(request, block_number));
+				to_value(&RpcU256::from(gas))
 			})
 	}
 
@@ -0,0 +1,194 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Eth PUB-SUB rpc implementation.
+
+use std::sync::{Arc, Weak};
+use jsonrpc_core::*;
+use serde_json;
+use ethcore::client::{BlockChainClient, BlockID, ChainNotify};
+use ethcore::filter::Filter as EthcoreFilter;
+use util::{H256, Mutex};
+use v1::traits::EthPubSub;
+use v1::types::{Filter, Index, Log, SubscriptionKind, H256 as RpcH256, U256 as RpcU256};
+use v1::helpers::{Subscribers, Sink, SessionId, SubscriptionId};
+use v1::impls::eth::block;
+use v1::impls::params_len;
+
+#[derive(Clone)]
+enum Subscription {
+	NewHeads,
+	Logs(EthcoreFilter),
+	NewPendingTransactions,
+}
+
+/// Eth pub-sub rpc implementation.
+///
+/// Register it with `ChainNotify` and the miner's transactions listener to feed the subscriptions.
+pub struct EthPubSubClient<C> where C: BlockChainClient {
+	client: Weak<C>,
+	subscribers: Mutex<Subscribers<Subscription>>,
+}
+
+impl<C> EthPubSubClient<C> where C: BlockChainClient {
+	/// Creates new Eth pub-sub client.
+	pub fn new(client: &Arc<C>) -> Self {
+		EthPubSubClient {
+			client: Arc::downgrade(client),
+			subscribers: Mutex::new(Subscribers::new()),
+		}
+	}
+
+	/// Notifies subscribers about transactions which entered the pending set.
+	pub fn notify_transactions(&self, hashes: &[H256]) {
+		let subscribers = self.subscribers();
+		let notifications = subscribers.into_iter()
+			.filter_map(|(id, session, sink, subscription)| match subscription {
+				Subscription::NewPendingTransactions => {
+					let values = hashes.iter()
+						.filter_map(|hash| to_value(&RpcH256::from(hash.clone())).ok())
+						.collect::<Vec<_>>();
+					Some((id, session, sink, values))
+				},
+				_ => None,
+			})
+			.collect::<Vec<_>>();
+
+		self.send(notifications);
+	}
+
+	/// Takes a snapshot of all subscriptions so that notifications are prepared
+	/// and delivered without holding the lock.
+	fn subscribers(&self) -> Vec<(SubscriptionId, SessionId, Sink, Subscription)> {
+		self.subscribers.lock().iter()
+			.map(|(id, subscriber)| (*id, subscriber.session, subscriber.sink.clone(), subscriber.params.clone()))
+			.collect()
+	}
+
+	fn send(&self, notifications: Vec<(SubscriptionId, SessionId, Sink, Vec<Value>)>) {
+		let mut gone = Vec::new();
+		for (id, session, sink, values) in notifications {
+			for value in values {
+				if !sink(notification(id, value)) {
+					gone.push((session, id));
+					break;
+				}
+			}
+		}
+
+		if !gone.is_empty() {
+			let mut subscribers = self.subscribers.lock();
+			for (session, id) in gone {
+				trace!(target: "rpc", "Dropping subscription {} of disconnected session {}", id, session);
+				subscribers.remove(session, &id);
+			}
+		}
+	}
+}
+
+/// Serializes a notification for given subscription.
+fn notification(id: SubscriptionId, result: Value) -> String {
+	format!(
+		r#"{{"jsonrpc":"2.0","method":"eth_subscription","params":{{"subscription":{},"result":{}}}}}"#,
+		serde_json::to_string(&RpcU256::from(id)).expect("U256 serialization is infallible; qed"),
+		serde_json::to_string(&result).expect("Value serialization is infallible; qed")
+	)
+}
+
+impl<C> ChainNotify for EthPubSubClient<C> where C: BlockChainClient + 'static {
+	fn new_blocks(
+		&self,
+		_imported: Vec<H256>,
+		_invalid: Vec<H256>,
+		enacted: Vec<H256>,
+		retracted: Vec<H256>,
+		_sealed: Vec<H256>,
+		_duration: u64)
+	{
+		let subscribers = self.subscribers();
+		if subscribers.is_empty() || (enacted.is_empty() && retracted.is_empty()) {
+			return;
+		}
+
+		let client = match self.client.upgrade() {
+			Some(client) => client,
+			None => return,
+		};
+
+		let wants_heads = subscribers.iter().any(|&(_, _, _, ref subscription)| match *subscription {
+			Subscription::NewHeads => true,
+			_ => false,
+		});
+		let heads = if wants_heads {
+			enacted.iter()
+				.filter_map(|hash| block(&*client, BlockID::Hash(hash.clone()), false))
+				.filter_map(|block| to_value(&block).ok())
+				.collect::<Vec<_>>()
+		} else {
+			Vec::new()
+		};
+
+		let notifications = subscribers.into_iter()
+			.filter_map(|(id, session, sink, subscription)| match subscription {
+				Subscription::NewHeads => Some((id, session, sink, heads.clone())),
+				Subscription::Logs(filter) => {
+					// logs of retracted blocks are reported as removed before the logs of the new branch.
+					let removed = retracted.iter()
+						.flat_map(|hash| client.block_logs(hash, &filter).into_iter())
+						.map(Log::removed);
+					let mined = enacted.iter()
+						.flat_map(|hash| client.block_logs(hash, &filter).into_iter())
+						.map(Log::from);
+					let values = removed.chain(mined)
+						.filter_map(|log| to_value(&log).ok())
+						.collect::<Vec<_>>();
+					Some((id, session, sink, values))
+				},
+				Subscription::NewPendingTransactions => None,
+			})
+			.collect::<Vec<_>>();
+
+		self.send(notifications);
+	}
+}
+
+impl<C> EthPubSub for EthPubSubClient<C> where C: BlockChainClient + 'static {
+	fn subscribe(&self, params: Params, session: SessionId, sink: Sink) -> Result<Value, Error> {
+		let subscription = match params_len(&params) {
+			1 => try!(from_params::<(SubscriptionKind,)>(params).map(|(kind,)| match kind {
+				SubscriptionKind::NewHeads => Subscription::NewHeads,
+				SubscriptionKind::Logs => Subscription::Logs(Filter::default().into()),
+				SubscriptionKind::NewPendingTransactions => Subscription::NewPendingTransactions,
+			})),
+			_ => match try!(from_params::<(SubscriptionKind, Filter)>(params)) {
+				(SubscriptionKind::Logs, filter) => Subscription::Logs(filter.into()),
+				_ => return Err(Error::invalid_params()),
+			},
+		};
+
+		let id = self.subscribers.lock().insert(session, sink, subscription);
+		to_value(&RpcU256::from(id))
+	}
+
+	fn unsubscribe(&self, params: Params, session: SessionId) -> Result<Value, Error> {
+		from_params::<(Index,)>(params)
+			.and_then(|(index,)| to_value(&self.subscribers.lock().remove(session, &index.value())))
+	}
+
+	fn close_session(&self, session: SessionId) {
+		self.subscribers.lock().remove_session(session);
+	}
+}
@@ -15,44 +15,128 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 //! Ethcore-specific rpc implementation.
-use util::{RotatingLogger};
+use util::{RotatingLogger, Mutex, Address, FixedHash, Uint, H256 as EthH256, U256 as EthU256};
 use util::network_settings::NetworkSettings;
 use util::misc::version_data;
 use std::sync::{Arc, Weak};
 use std::ops::Deref;
 use std::collections::{BTreeMap};
-use ethcore::client::{MiningBlockChainClient};
+use transient_hashmap::TransientHashMap;
+use ethcore::client::{MiningBlockChainClient, BlockID, TraceFilter, AccountSummary as EthAccountSummary};
+use ethcore::trace::GasProfile;
+use ethcore::filter::Filter as EthcoreFilter;
+use ethcore::error::CallError;
+use ethcore::block::Block;
+use ethcore::views::HeaderView;
+use util::rlp::{UntrustedRlp, View};
 use jsonrpc_core::*;
 use ethcore::miner::MinerService;
+use ethsync::SyncProvider;
 use v1::traits::Ethcore;
-use v1::types::{Bytes, U256};
-use v1::helpers::{SigningQueue, ConfirmationsQueue};
-use v1::impls::error_codes;
+use v1::types::{Bytes, BlockChunk, U256, H160, H256, BlockNumber, Index, Log, DatabaseStats, BlockTemplate, ContractGas, ContractProfile, StorageRangeProof, MinerStats, NodeInfo, NodeStatus, QueueStatus, PeerStatus, SnapshotStatus, PeerVersion, PinnedBlock, CallRequest, Filter, AccountSummary};
+use v1::helpers::{SigningQueue, ConfirmationsQueue, PollManager};
+use v1::impls::{error_codes, params_len, sign_call};
+
+/// Maximal number of storage items proven by a single `ethcore_getStorageRangeProof` call.
+const MAX_STORAGE_RANGE: u64 = 1024;
+/// Maximal number of storage positions or accounts queried by a single `ethcore_getStorageAtMany` or `ethcore_getAccounts` call.
+const MAX_BATCH_SIZE: usize = 1024;
+/// Maximal number of blocks whose traces are aggregated by a single `ethcore_gasProfile` call.
+const MAX_PROFILE_BLOCKS: u64 = 1000;
+/// Maximal number of blocks in a chunk of `ethcore_streamChunk`.
+const MAX_CHUNK_BLOCKS: usize = 128;
+/// Size of blocks in a chunk of `ethcore_streamChunk` after which no more blocks are added.
+const MAX_CHUNK_SIZE: usize = 1024 * 1024;
+/// Number of seconds a pin is kept after its last use.
+const PIN_LIFETIME: u64 = 60;
+
+/// State of a stream opened with `ethcore_streamBlocks`.
+struct BlockStream {
+	/// Number of the first block not sent yet.
+	next: u64,
+	/// Number of the last block of the stream.
+	last: u64,
+	/// Last chunk sent, kept until acknowledged.
+	sent: Option<BlockChunk>,
+}
 
 /// Ethcore implementation.
-pub struct EthcoreClient<C, M> where
+pub struct EthcoreClient<C, M, S: ?Sized> where
 	C: MiningBlockChainClient,
-	M: MinerService {
+	M: MinerService,
+	S: SyncProvider {
 
 	client: Weak<C>,
 	miner: Weak<M>,
+	sync: Weak<S>,
 	logger: Arc<RotatingLogger>,
 	settings: Arc<NetworkSettings>,
 	confirmations_queue: Option<Arc<ConfirmationsQueue>>,
+	gas_cap: EthU256,
+	// number and hash of pinned blocks by random handle.
+	pins: Mutex<TransientHashMap<EthH256, (u64, EthH256)>>,
+	streams: Mutex<PollManager<BlockStream>>,
 }
 
-impl<C, M> EthcoreClient<C, M> where C: MiningBlockChainClient, M: MinerService {
+impl<C, M, S: ?Sized> EthcoreClient<C, M, S> where C: MiningBlockChainClient, M: MinerService, S: SyncProvider {
 	/// Creates new `EthcoreClient`.
-	pub fn new(client: &Arc<C>, miner: &Arc<M>, logger: Arc<RotatingLogger>, settings: Arc<NetworkSettings>, queue: Option<Arc<ConfirmationsQueue>>) -> Self {
+	pub fn new(client: &Arc<C>, miner: &Arc<M>, sync: &Arc<S>, logger: Arc<RotatingLogger>, settings: Arc<NetworkSettings>, queue: Option<Arc<ConfirmationsQueue>>, gas_cap: EthU256) -> Self {
 		EthcoreClient {
 			client: Arc::downgrade(client),
 			miner: Arc::downgrade(miner),
+			sync: Arc::downgrade(sync),
 			logger: logger,
 			settings: settings,
 			confirmations_queue: queue,
+			gas_cap: gas_cap,
+			pins: Mutex::new(TransientHashMap::new_with_timer(PIN_LIFETIME, Default::default())),
+			streams: Mutex::new(PollManager::new()),
+		}
+	}
+
+	/// Number and hash of a pinned block.
+	fn pinned(&self, id: H256) -> Result<(u64, EthH256), Error> {
+		let mut pins = self.pins.lock();
+		pins.prune();
+		pins.get(&id.into()).cloned().ok_or_else(|| Error {
+			code: ErrorCode::ServerError(error_codes::UNKNOWN_PIN),
+			message: "Unknown or expired pin.".into(),
+			data: None,
+		})
+	}
+
+	fn unknown_stream() -> Error {
+		Error {
+			code: ErrorCode::ServerError(error_codes::UNKNOWN_STREAM),
+			message: "Unknown or expired stream.".into(),
+			data: None,
 		}
 	}
 
+	/// Reads the chunk of blocks following the ones already sent.
+	fn next_chunk(&self, stream: &BlockStream) -> Result<BlockChunk, Error> {
+		let client = take_weak!(self.client);
+		let seq = stream.sent.as_ref().map_or(1, |chunk| Into::<EthU256>::into(chunk.seq).low_u64() + 1);
+		let mut blocks = Vec::new();
+		let mut size = 0;
+		let mut number = stream.next;
+		while number <= stream.last && blocks.len() < MAX_CHUNK_BLOCKS && size < MAX_CHUNK_SIZE {
+			let block = try!(client.block(BlockID::Number(number)).ok_or_else(|| Error {
+				code: ErrorCode::ServerError(error_codes::UNKNOWN_ERROR),
+				message: format!("Block #{} is not available.", number),
+				data: None,
+			}));
+			size += block.len();
+			blocks.push(Bytes::new(block));
+			number += 1;
+		}
+		Ok(BlockChunk {
+			seq: seq.into(),
+			blocks: blocks,
+			done: number > stream.last,
+		})
+	}
+
 	fn active(&self) -> Result<(), Error> {
 		// TODO: only call every 30s at most.
 		take_weak!(self.client).keep_alive();
@@ -60,7 +144,10 @@ impl<C, M> EthcoreClient<C, M> where C: MiningBlockChainClient, M: MinerService
 	}
 }
 
-impl<C, M> Ethcore for EthcoreClient<C, M> where M: MinerService + 'static, C: MiningBlockChainClient + 'static {
+impl<C, M, S: ?Sized> Ethcore for EthcoreClient<C, M, S> where
+	M: MinerService + 'static,
+	C: MiningBlockChainClient + 'static,
+	S: SyncProvider + 'static {
 
 	fn transactions_limit(&self, _: Params) -> Result<Value, Error> {
 		try!(self.active());
@@ -160,4 +247,417 @@ impl<C, M> Ethcore for EthcoreClient<C, M> where M: MinerService + 'static, C: M
 			Some(ref queue) => to_value(&queue.len()),
 		}
 	}
+
+	fn db_stats(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		match params {
+			Params::None => to_value(&take_weak!(self.client).database_stats()
+				.into_iter()
+				.map(DatabaseStats::from)
+				.collect::<Vec<_>>()),
+			_ => Err(Error::invalid_params()),
+		}
+	}
+
+	fn block_template(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		match params {
+			Params::None => {
+				let client = take_weak!(self.client);
+				let miner = take_weak!(self.miner);
+				if miner.author().is_zero() {
+					warn!(target: "miner", "Cannot give block template - no author is configured. Use --author to configure!");
+					return Err(Error {
+						code: ErrorCode::ServerError(error_codes::NO_AUTHOR_CODE),
+						message: "Author not configured. Run parity with --author to configure.".into(),
+						data: None
+					});
+				}
+				miner.map_sealing_work(client.deref(), |b| to_value(&BlockTemplate::from(b)))
+					.unwrap_or(Err(Error::internal_error()))	// no pending block.
+			},
+			_ => Err(Error::invalid_params()),
+		}
+	}
+
+	fn submit_block(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(Bytes, )>(params).and_then(|(raw, )| {
+			let raw = raw.to_vec();
+			let block: Block = try!(UntrustedRlp::new(&raw).as_val().map_err(|_| Error::invalid_params()));
+			let hash = block.header.bare_hash();
+			trace!(target: "miner", "submit_block: Decoded: bare_hash={}, seal fields={}", hash, block.header.seal().len());
+			let miner = take_weak!(self.miner);
+			let client = take_weak!(self.client);
+			let r = miner.submit_seal(client.deref(), hash, block.header.seal().to_vec());
+			to_value(&r.is_ok())
+		})
+	}
+
+	fn storage_range_proof(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		let with_block = match params {
+			Params::Array(ref vec) => vec.len() > 3,
+			_ => false,
+		};
+		let (address, from, limit, block) = try!(match with_block {
+			true => from_params::<(H160, H256, u64, BlockNumber)>(params),
+			false => from_params::<(H160, H256, u64)>(params).map(|(address, from, limit)| (address, from, limit, BlockNumber::Latest)),
+		});
+		if limit > MAX_STORAGE_RANGE {
+			return Err(Error::invalid_params());
+		}
+
+		let proof = take_weak!(self.client).prove_storage_range(&address.into(), &from.into(), limit as usize, block.into());
+		to_value(&proof.map(StorageRangeProof::from))
+	}
+
+	fn miner_stats_range(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(BlockNumber, BlockNumber)>(params).and_then(|(from, to)| {
+			match take_weak!(self.client).miner_stats(from.into(), to.into()) {
+				Some(stats) => to_value(&stats.into_iter().map(MinerStats::from).collect::<Vec<_>>()),
+				None => Err(Error::invalid_params()),
+			}
+		})
+	}
+
+	fn gas_profile(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(BlockNumber, BlockNumber)>(params).and_then(|(from, to)| {
+			let client = take_weak!(self.client);
+			let (from, to): (BlockID, BlockID) = (from.into(), to.into());
+			let number = |id: BlockID| client.block_header(id).map(|header| HeaderView::new(&header).number());
+			if let (Some(first), Some(last)) = (number(from.clone()), number(to.clone())) {
+				if last >= first && last - first >= MAX_PROFILE_BLOCKS {
+					return Err(Error::invalid_params());
+				}
+			}
+
+			let filter = TraceFilter {
+				range: from..to,
+				from_address: Vec::new(),
+				to_address: Vec::new(),
+				include_retracted: false,
+			};
+			match client.filter_traces(filter) {
+				Some(traces) => {
+					let mut profile = GasProfile::new();
+					for trace in &traces {
+						profile.accrue(&trace.action, &trace.result);
+					}
+					to_value(&profile.contracts().into_iter().map(ContractGas::from).collect::<Vec<_>>())
+				},
+				None => Err(Error::invalid_params()),
+			}
+		})
+	}
+
+	fn evm_profile(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		match params {
+			Params::None => match take_weak!(self.client).evm_profile() {
+				Some(profile) => to_value(&profile.into_iter().map(ContractProfile::from).collect::<Vec<_>>()),
+				None => Err(Error {
+					code: ErrorCode::ServerError(error_codes::UNSUPPORTED_REQUEST_CODE),
+					message: "EVM profiling is disabled. Run parity with --profile-evm to enable.".into(),
+					data: None,
+				}),
+			},
+			_ => Err(Error::invalid_params()),
+		}
+	}
+
+	fn snapshot_status(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		match params {
+			Params::None => {
+				let status = take_weak!(self.client).snapshot_status();
+				let sync = take_weak!(self.sync).status();
+				to_value(&SnapshotStatus::new(status, &sync))
+			},
+			_ => Err(Error::invalid_params()),
+		}
+	}
+
+	fn log(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(BlockNumber, Index, Index)>(params).and_then(|(block, transaction_index, log_index)| {
+			let log = take_weak!(self.client).log(block.into(), transaction_index.value(), log_index.value());
+			to_value(&log.map(Log::from))
+		})
+	}
+
+	fn peer_versions(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		match params {
+			Params::None => {
+				let versions = take_weak!(self.sync).peer_versions();
+				to_value(&versions.into_iter().map(PeerVersion::from).collect::<Vec<_>>())
+			},
+			_ => Err(Error::invalid_params()),
+		}
+	}
+
+	fn pin_block(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		match params {
+			Params::None => {
+				let info = take_weak!(self.client).chain_info();
+				let id = EthH256::random();
+				{
+					let mut pins = self.pins.lock();
+					pins.prune();
+					pins.insert(id.clone(), (info.best_block_number, info.best_block_hash.clone()));
+				}
+				to_value(&PinnedBlock {
+					id: id.into(),
+					number: info.best_block_number.into(),
+					hash: info.best_block_hash.into(),
+				})
+			},
+			_ => Err(Error::invalid_params()),
+		}
+	}
+
+	fn unpin_block(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(H256,)>(params)
+			.and_then(|(id,)| {
+				let mut pins = self.pins.lock();
+				pins.prune();
+				to_value(&pins.remove(&id.into()).is_some())
+			})
+	}
+
+	fn pinned_balance(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(H256, H160)>(params)
+			.and_then(|(id, address)| {
+				let (_, hash) = try!(self.pinned(id));
+				let client = take_weak!(self.client);
+				if client.block_header(BlockID::Hash(hash.clone())).is_none() {
+					return Err(unknown_block());
+				}
+				match client.balance(&address.into(), BlockID::Hash(hash)) {
+					Some(balance) => to_value(&U256::from(balance)),
+					None => Err(state_pruned()),
+				}
+			})
+	}
+
+	fn pinned_call(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(H256, CallRequest)>(params)
+			.and_then(|(id, request)| {
+				let (_, hash) = try!(self.pinned(id));
+				let (client, miner) = (take_weak!(self.client), take_weak!(self.miner));
+				let signed = sign_call(&*client, &*miner, request.into(), BlockID::Hash(hash.clone()), self.gas_cap);
+				match client.call(&signed, BlockID::Hash(hash), Default::default(), &Default::default()) {
+					Ok(executed) => to_value(&Bytes::new(executed.output)),
+					Err(CallError::UnknownBlock) => Err(unknown_block()),
+					Err(CallError::StatePruned) => Err(state_pruned()),
+					Err(CallError::Execution(e)) => Err(Error {
+						code: ErrorCode::ServerError(error_codes::TRANSACTION_ERROR),
+						message: format!("{}", e),
+						data: None,
+					}),
+				}
+			})
+	}
+
+	fn pinned_logs(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(H256, Filter)>(params)
+			.and_then(|(id, filter)| {
+				let (number, hash) = try!(self.pinned(id));
+				let mut filter: EthcoreFilter = filter.into();
+				// nothing past the pinned block is visible.
+				let anchor = |id: BlockID| match id {
+					BlockID::Latest | BlockID::Pending => BlockID::Hash(hash.clone()),
+					BlockID::Number(n) if n > number => BlockID::Hash(hash.clone()),
+					id => id,
+				};
+				filter.from_block = anchor(filter.from_block);
+				filter.to_block = anchor(filter.to_block);
+				let logs = take_weak!(self.client).logs(filter)
+					.into_iter()
+					.map(From::from)
+					.collect::<Vec<Log>>();
+				to_value(&logs)
+			})
+	}
+
+	fn storage_at_many(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		let (address, positions, block) = try!(match params_len(&params) {
+			2 => from_params::<(H160, Vec<U256>)>(params).map(|(address, positions)| (address, positions, BlockNumber::Latest)),
+			_ => from_params::<(H160, Vec<U256>, BlockNumber)>(params),
+		});
+		if positions.len() > MAX_BATCH_SIZE {
+			return Err(Error::invalid_params());
+		}
+
+		let address: Address = address.into();
+		let positions = positions.into_iter().map(|p| EthH256::from(Into::<EthU256>::into(p))).collect::<Vec<_>>();
+		let client = take_weak!(self.client);
+		let values: Vec<EthH256> = match block {
+			BlockNumber::Pending => {
+				let miner = take_weak!(self.miner);
+				positions.iter().map(|p| miner.storage_at(&*client, &address, p)).collect()
+			},
+			block => try!(client.storage_at_many(&address, positions, block.into()).ok_or_else(state_pruned)),
+		};
+		to_value(&values.into_iter().map(U256::from).collect::<Vec<_>>())
+	}
+
+	fn accounts(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		let (addresses, block) = try!(match params_len(&params) {
+			1 => from_params::<(Vec<H160>,)>(params).map(|(addresses,)| (addresses, BlockNumber::Latest)),
+			_ => from_params::<(Vec<H160>, BlockNumber)>(params),
+		});
+		if addresses.len() > MAX_BATCH_SIZE {
+			return Err(Error::invalid_params());
+		}
+
+		let addresses = addresses.into_iter().map(Into::into).collect::<Vec<Address>>();
+		let client = take_weak!(self.client);
+		let accounts: Vec<EthAccountSummary> = match block {
+			BlockNumber::Pending => {
+				let miner = take_weak!(self.miner);
+				addresses.iter().map(|a| EthAccountSummary {
+					balance: miner.balance(&*client, a),
+					nonce: miner.nonce(&*client, a),
+					code: miner.code(&*client, a).unwrap_or_else(Vec::new),
+				}).collect()
+			},
+			block => try!(client.accounts(addresses, block.into()).ok_or_else(state_pruned)),
+		};
+		to_value(&accounts.into_iter().map(AccountSummary::from).collect::<Vec<_>>())
+	}
+
+	fn stream_blocks(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(BlockNumber, BlockNumber)>(params).and_then(|(from, to)| {
+			let client = take_weak!(self.client);
+			let number = |id: BlockNumber| client.block_header(id.into()).map(|header| HeaderView::new(&header).number());
+			match (number(from), number(to)) {
+				(Some(from), Some(to)) if from <= to => {
+					let id = self.streams.lock().create_poll(BlockStream {
+						next: from,
+						last: to,
+						sent: None,
+					});
+					to_value(&U256::from(id))
+				},
+				_ => Err(Error::invalid_params()),
+			}
+		})
+	}
+
+	fn stream_chunk(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(Index, Index)>(params).and_then(|(index, ack)| {
+			let mut streams = self.streams.lock();
+			let stream = try!(streams.poll_mut(&index.value()).ok_or_else(Self::unknown_stream));
+			let sent = stream.sent.as_ref().map_or(0, |chunk| Into::<EthU256>::into(chunk.seq).low_u64() as usize);
+			if ack.value() + 1 == sent {
+				// the last chunk was lost, send it again.
+				return to_value(&stream.sent);
+			}
+			if ack.value() != sent {
+				return Err(Error::invalid_params());
+			}
+
+			let chunk = try!(self.next_chunk(stream));
+			stream.next += chunk.blocks.len() as u64;
+			stream.sent = Some(chunk.clone());
+			to_value(&chunk)
+		})
+	}
+
+	fn close_stream(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(Index,)>(params)
+			.and_then(|(index,)| {
+				let mut streams = self.streams.lock();
+				let known = streams.poll(&index.value()).is_some();
+				streams.remove_poll(&index.value());
+				to_value(&known)
+			})
+	}
+
+	fn node_info(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		match params {
+			Params::None => {
+				let sync = take_weak!(self.sync);
+				let genesis_hash = take_weak!(self.client).block_hash(BlockID::Earliest).unwrap_or_else(EthH256::zero);
+				to_value(&NodeInfo {
+					name: self.settings.name.clone(),
+					enode: sync.enode(),
+					chain: self.settings.chain.clone(),
+					genesis_hash: genesis_hash.into(),
+					protocols: sync.protocols(),
+					network_port: self.settings.network_port,
+					rpc_port: match self.settings.rpc_enabled {
+						true => Some(self.settings.rpc_port),
+						false => None,
+					},
+					data_path: self.settings.db_path.clone(),
+					keys_path: self.settings.keys_path.clone(),
+				})
+			},
+			_ => Err(Error::invalid_params()),
+		}
+	}
+
+	fn node_status(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		match params {
+			Params::None => {
+				let client = take_weak!(self.client);
+				let chain_info = client.chain_info();
+				let queue_info = client.queue_info();
+				let sync = take_weak!(self.sync).status();
+				to_value(&NodeStatus {
+					best_block_number: chain_info.best_block_number.into(),
+					best_block_hash: chain_info.best_block_hash.into(),
+					total_difficulty: chain_info.total_difficulty.into(),
+					queue: QueueStatus {
+						unverified: queue_info.unverified_queue_size as u64,
+						verifying: queue_info.verifying_queue_size as u64,
+						verified: queue_info.verified_queue_size as u64,
+						mem_bytes: queue_info.mem_used as u64,
+					},
+					cache_bytes: client.cache_size() as u64,
+					major_syncing: sync.is_major_syncing(),
+					highest_block: sync.highest_block_number.map(Into::into),
+					peers: PeerStatus {
+						active: sync.num_active_peers as u64,
+						connected: sync.num_peers as u64,
+						ideal: self.settings.max_peers as u64,
+					},
+				})
+			},
+			_ => Err(Error::invalid_params()),
+		}
+	}
+}
+
+fn unknown_block() -> Error {
+	Error {
+		code: ErrorCode::ServerError(error_codes::UNKNOWN_BLOCK),
+		message: "Unknown block.".into(),
+		data: None,
+	}
+}
+
+fn state_pruned() -> Error {
+	Error {
+		code: ErrorCode::ServerError(error_codes::STATE_PRUNED),
+		message: "State of the block is not available, it may have been pruned.".into(),
+		data: None,
+	}
 }
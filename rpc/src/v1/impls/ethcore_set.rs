@@ -16,12 +16,18 @@
 
 /// Ethcore-specific rpc interface for operations altering the settings.
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 use jsonrpc_core::*;
-use ethcore::miner::MinerService;
-use ethcore::client::MiningBlockChainClient;
+use util::{U256 as EthU256, Uint};
+use ethcore::miner::{MinerService, RebroadcastPolicy};
+use ethcore::client::{MiningBlockChainClient, DatabaseColumn};
+use ethcore::account_provider::AccountProvider;
+use ethcore::transaction::Transaction as EthTransaction;
 use ethsync::ManageNetwork;
 use v1::traits::EthcoreSet;
-use v1::types::{Bytes, H160, U256};
+use v1::types::{Bytes, H160, H256, U256, BlockNumber, Transaction, ApiKey};
+use v1::helpers::{ExitHandle, ExitRequest, ApiKeys};
+use v1::impls::{dispatch_transaction, signing_error};
 
 /// Ethcore-specific rpc interface for operations altering the settings.
 pub struct EthcoreSetClient<C, M> where
@@ -31,20 +37,33 @@ pub struct EthcoreSetClient<C, M> where
 	client: Weak<C>,
 	miner: Weak<M>,
 	net: Weak<ManageNetwork>,
+	accounts: Weak<AccountProvider>,
+	exit: Weak<ExitHandle>,
+	api_keys: Weak<ApiKeys>,
 }
 
 impl<C, M> EthcoreSetClient<C, M> where
 	C: MiningBlockChainClient,
 	M: MinerService {
 	/// Creates new `EthcoreSetClient`.
-	pub fn new(client: &Arc<C>, miner: &Arc<M>, net: &Arc<ManageNetwork>) -> Self {
+	pub fn new(client: &Arc<C>, miner: &Arc<M>, net: &Arc<ManageNetwork>, accounts: &Arc<AccountProvider>, exit: &Arc<ExitHandle>, api_keys: &Arc<ApiKeys>) -> Self {
 		EthcoreSetClient {
 			client: Arc::downgrade(client),
 			miner: Arc::downgrade(miner),
 			net: Arc::downgrade(net),
+			accounts: Arc::downgrade(accounts),
+			exit: Arc::downgrade(exit),
+			api_keys: Arc::downgrade(api_keys),
 		}
 	}
 
+	fn schedule_exit(&self, params: Params, request: ExitRequest) -> Result<Value, Error> {
+		from_params::<(u64,)>(params).and_then(|(delay,)| {
+			ExitHandle::schedule(&take_weak!(self.exit), request, Duration::from_secs(delay));
+			to_value(&true)
+		})
+	}
+
 	fn active(&self) -> Result<(), Error> {
 		// TODO: only call every 30s at most.
 		take_weak!(self.client).keep_alive();
@@ -112,6 +131,31 @@ impl<C, M> EthcoreSet for EthcoreSetClient<C, M> where
 		})
 	}
 
+	fn set_transaction_rebroadcast(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(H256, u64, u32, usize)>(params).and_then(|(hash, interval, backoff, max_attempts)| {
+			let policy = RebroadcastPolicy {
+				interval: Duration::from_secs(interval),
+				backoff: backoff,
+				max_attempts: max_attempts,
+			};
+			match take_weak!(self.miner).set_transaction_rebroadcast(&hash.into(), policy) {
+				true => to_value(&true),
+				false => Err(Error::invalid_params()),
+			}
+		})
+	}
+
+	fn take_snapshot(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(BlockNumber,)>(params).and_then(|(block,)| {
+			match take_weak!(self.client).request_snapshot(block.into()) {
+				Some(number) => to_value(&number),
+				None => Err(Error::invalid_params()),
+			}
+		})
+	}
+
 	fn add_reserved_peer(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
 		from_params::<(String,)>(params).and_then(|(peer,)| {
@@ -153,4 +197,81 @@ impl<C, M> EthcoreSet for EthcoreSetClient<C, M> where
 		take_weak!(self.net).stop_network();
 		Ok(Value::Bool(true))
 	}
+
+	fn db_get(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(String, Bytes)>(params).and_then(|(column, key)| {
+			let column = try!(column.parse::<DatabaseColumn>().map_err(|_| Error::invalid_params()));
+			let value = take_weak!(self.client).database_get(column, key.to_vec());
+			to_value(&value.map(Bytes::new))
+		})
+	}
+
+	fn remove_transaction(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(H256,)>(params).and_then(|(hash,)| {
+			let client = take_weak!(self.client);
+			let removed = take_weak!(self.miner).remove_pending_transaction(&*client, &hash.into());
+			to_value(&removed.map(Transaction::from))
+		})
+	}
+
+	fn reprioritize_transaction(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(H256, U256)>(params).and_then(|(hash, gas_price)| {
+			let client = take_weak!(self.client);
+			let miner = take_weak!(self.miner);
+			let accounts = take_weak!(self.accounts);
+
+			let queued = try!(miner.transaction(&hash.into()).ok_or_else(Error::invalid_params));
+			let sender = try!(queued.sender().map_err(|_| Error::invalid_params()));
+			let replacement = EthTransaction {
+				gas_price: gas_price.into(),
+				..(*queued).clone()
+			};
+			let signature = try!(accounts.sign(sender, replacement.hash()).map_err(signing_error));
+			dispatch_transaction(&*client, &*miner, replacement.with_signature(signature))
+		})
+	}
+
+	fn shutdown(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		self.schedule_exit(params, ExitRequest::Shutdown)
+	}
+
+	fn restart(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		self.schedule_exit(params, ExitRequest::Restart)
+	}
+
+	fn add_api_key(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(Vec<String>, Option<U256>)>(params).and_then(|(apis, quota)| {
+			let quota = quota.map(|quota| {
+				let quota: EthU256 = quota.into();
+				quota.low_u64()
+			});
+			take_weak!(self.api_keys).add(apis, quota)
+				.map_err(|_| Error::invalid_params())
+				.and_then(|key| to_value(&key))
+		})
+	}
+
+	fn remove_api_key(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(String,)>(params).and_then(|(key,)| {
+			to_value(&take_weak!(self.api_keys).remove(&key))
+		})
+	}
+
+	fn api_keys(&self, _params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		let keys = take_weak!(self.api_keys).list().into_iter().map(|(key, api_key)| ApiKey {
+			key: key,
+			apis: api_key.apis,
+			daily_quota: api_key.daily_quota.map(Into::into),
+			used_today: api_key.used.into(),
+		}).collect::<Vec<_>>();
+		to_value(&keys)
+	}
 }
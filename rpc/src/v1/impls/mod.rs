@@ -32,6 +32,7 @@ macro_rules! rpc_unimplemented {
 mod web3;
 mod eth;
 mod eth_filter;
+mod eth_pubsub;
 mod eth_signing;
 mod net;
 mod personal;
@@ -40,10 +41,12 @@ mod ethcore;
 mod ethcore_set;
 mod traces;
 mod rpc;
+mod admin;
 
 pub use self::web3::Web3Client;
 pub use self::eth::EthClient;
 pub use self::eth_filter::EthFilterClient;
+pub use self::eth_pubsub::EthPubSubClient;
 pub use self::eth_signing::{EthSigningUnsafeClient, EthSigningQueueClient};
 pub use self::net::NetClient;
 pub use self::personal::PersonalClient;
@@ -52,18 +55,19 @@ pub use self::ethcore::EthcoreClient;
 pub use self::ethcore_set::EthcoreSetClient;
 pub use self::traces::TracesClient;
 pub use self::rpc::RpcClient;
+pub use self::admin::AdminClient;
 
-use v1::helpers::TransactionRequest;
+use v1::helpers::{TransactionRequest, CallRequest};
 use v1::types::H256 as NH256;
 use ethcore::error::Error as EthcoreError;
 use ethcore::miner::MinerService;
-use ethcore::client::MiningBlockChainClient;
+use ethcore::client::{BlockChainClient, MiningBlockChainClient, BlockID};
 use ethcore::transaction::{Action, SignedTransaction, Transaction};
 use ethcore::account_provider::{AccountProvider, Error as AccountError};
 use util::numbers::*;
 use util::rlp::encode;
 use util::bytes::ToPretty;
-use jsonrpc_core::{Error, ErrorCode, Value, to_value};
+use jsonrpc_core::{Error, ErrorCode, Params, Value, to_value};
 
 mod error_codes {
 	// NOTE [ToDr] Codes from [-32099, -32000]
@@ -73,9 +77,15 @@ mod error_codes {
 	pub const UNKNOWN_ERROR: i64 = -32009;
 	pub const TRANSACTION_ERROR: i64 = -32010;
 	pub const TRANSACTION_REJECTED: i64 = -32011;
+	pub const EXECUTION_ERROR: i64 = -32015;
 	pub const ACCOUNT_LOCKED: i64 = -32020;
 	pub const PASSWORD_INVALID: i64 = -32021;
+	pub const PASSWORD_THROTTLED: i64 = -32022;
 	pub const SIGNER_DISABLED: i64 = -32030;
+	pub const STATE_PRUNED: i64 = -32040;
+	pub const UNKNOWN_PIN: i64 = -32041;
+	pub const UNKNOWN_STREAM: i64 = -32042;
+	pub const UNKNOWN_BLOCK: i64 = -32043;
 }
 
 fn dispatch_transaction<C, M>(client: &C, miner: &M, signed_transaction: SignedTransaction) -> Result<Value, Error>
@@ -133,13 +143,35 @@ fn sign_and_dispatch<C, M>(client: &C, miner: &M, request: TransactionRequest, a
 	dispatch_transaction(&*client, &*miner, signed_transaction)
 }
 
-fn default_gas_price<C, M>(client: &C, miner: &M) -> U256 where C: MiningBlockChainClient, M: MinerService {
+fn default_gas_price<C, M>(client: &C, miner: &M) -> U256 where C: BlockChainClient, M: MinerService {
 	client
 		.gas_price_statistics(100, 8)
 		.map(|x| x[4])
 		.unwrap_or_else(|_| miner.sensible_gas_price())
 }
 
+/// Fake-signs a call request so it can be executed on the state of `block`.
+/// Gas defaults to `gas_cap` and the nonce to the sender's nonce at `block`.
+fn sign_call<C, M>(client: &C, miner: &M, request: CallRequest, block: BlockID, gas_cap: U256) -> SignedTransaction
+	where C: BlockChainClient, M: MinerService {
+	let from = request.from.unwrap_or(Address::zero());
+	Transaction {
+		nonce: request.nonce.unwrap_or_else(|| client.nonce(&from, block).unwrap_or_else(U256::zero)),
+		action: request.to.map_or(Action::Create, Action::Call),
+		gas: request.gas.unwrap_or(gas_cap),
+		gas_price: request.gas_price.unwrap_or_else(|| default_gas_price(client, miner)),
+		value: request.value.unwrap_or_else(U256::zero),
+		data: request.data.map_or_else(Vec::new, |d| d.to_vec())
+	}.fake_sign(from)
+}
+
+fn params_len(params: &Params) -> usize {
+	match params {
+		&Params::Array(ref vec) => vec.len(),
+		_ => 0,
+	}
+}
+
 fn signing_error(error: AccountError) -> Error {
 	Error {
 		code: ErrorCode::ServerError(error_codes::ACCOUNT_LOCKED),
@@ -149,6 +181,14 @@ fn signing_error(error: AccountError) -> Error {
 }
 
 fn password_error(error: AccountError) -> Error {
+	if let AccountError::Throttled(_) = error {
+		return Error {
+			code: ErrorCode::ServerError(error_codes::PASSWORD_THROTTLED),
+			message: format!("{}", error),
+			data: None,
+		};
+	}
+
 	Error {
 		code: ErrorCode::ServerError(error_codes::PASSWORD_INVALID),
 		message: "Account password is invalid or account does not exist.".into(),
@@ -165,6 +205,14 @@ pub fn transaction_rejected_error() -> Error {
 	}
 }
 
+fn gas_estimation_error(gas: U256) -> Error {
+	Error {
+		code: ErrorCode::ServerError(error_codes::EXECUTION_ERROR),
+		message: format!("Gas required exceeds allowance ({}) or the call always fails.", gas),
+		data: None,
+	}
+}
+
 fn transaction_error(error: EthcoreError) -> Error {
 	use ethcore::error::TransactionError::*;
 
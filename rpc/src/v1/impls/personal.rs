@@ -16,13 +16,15 @@
 
 //! Account management (personal) rpc implementation
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 use jsonrpc_core::*;
 use v1::traits::Personal;
-use v1::types::{H160 as RpcH160, TransactionRequest};
-use v1::impls::unlock_sign_and_dispatch;
+use v1::types::{Bytes, H160 as RpcH160, H520 as RpcH520, TransactionRequest};
+use v1::impls::{unlock_sign_and_dispatch, password_error, params_len};
 use v1::helpers::{TransactionRequest as TRequest};
-use ethcore::account_provider::AccountProvider;
-use util::Address;
+use ethcore::account_provider::{AccountProvider, Error as AccountError};
+use util::{Address, H256};
+use util::sha3::Hashable;
 use ethcore::client::MiningBlockChainClient;
 use ethcore::miner::MinerService;
 
@@ -52,6 +54,13 @@ impl<C, M> PersonalClient<C, M> where C: MiningBlockChainClient, M: MinerService
 	}
 }
 
+/// Hash of a message as signed by `personal_sign`, so that it can't be mistaken for a transaction.
+fn message_hash(data: &[u8]) -> H256 {
+	let mut message = format!("\x19Ethereum Signed Message:\n{}", data.len()).into_bytes();
+	message.extend_from_slice(data);
+	message.sha3()
+}
+
 impl<C: 'static, M: 'static> Personal for PersonalClient<C, M> where C: MiningBlockChainClient, M: MinerService {
 
 	fn signer_enabled(&self, _: Params) -> Result<Value, Error> {
@@ -82,12 +91,21 @@ impl<C: 'static, M: 'static> Personal for PersonalClient<C, M> where C: MiningBl
 
 	fn unlock_account(&self, params: Params) -> Result<Value, Error> {
 		try!(self.active());
-		from_params::<(RpcH160, String, u64)>(params).and_then(
-			|(account, account_pass, _)|{
+		let params = match params_len(&params) {
+			2 => from_params::<(RpcH160, String)>(params).map(|(account, pass)| (account, pass, None)),
+			_ => from_params::<(RpcH160, String, Option<u64>)>(params),
+		};
+		params.and_then(
+			|(account, account_pass, duration)|{
 				let account: Address = account.into();
 				let store = take_weak!(self.accounts);
-				match store.unlock_account_temporarily(account, account_pass) {
+				let unlocked = match duration {
+					None | Some(0) => store.unlock_account_temporarily(account, account_pass),
+					Some(secs) => store.unlock_account_timed(account, account_pass, Duration::from_secs(secs)),
+				};
+				match unlocked {
 					Ok(_) => Ok(Value::Bool(true)),
+					Err(e @ AccountError::Throttled(_)) => Err(password_error(e)),
 					Err(_) => Ok(Value::Bool(false)),
 				}
 			})
@@ -104,4 +122,16 @@ impl<C: 'static, M: 'static> Personal for PersonalClient<C, M> where C: MiningBl
 				unlock_sign_and_dispatch(&*take_weak!(self.client), &*take_weak!(self.miner), request, &*accounts, sender, password)
 			})
 	}
+
+	fn sign(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(Bytes, RpcH160, String)>(params)
+			.and_then(|(data, account, password)| {
+				let account: Address = account.into();
+				let hash = message_hash(&data.to_vec());
+				take_weak!(self.accounts).sign_with_password(account, password, hash)
+					.map_err(password_error)
+					.and_then(|signature| to_value(&RpcH520::from(signature)))
+			})
+	}
 }
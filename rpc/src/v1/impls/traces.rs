@@ -20,43 +20,56 @@ use std::sync::{Weak, Arc};
 use jsonrpc_core::*;
 use std::collections::BTreeMap;
 //use util::H256;
-use ethcore::client::{BlockChainClient, CallAnalytics, TransactionID, TraceId};
+use util::U256;
+use ethcore::client::{BlockChainClient, BlockID, CallAnalytics, TransactionID, TraceId, Executed};
+use ethcore::error::ReplayError;
 use ethcore::miner::MinerService;
-use ethcore::transaction::{Transaction as EthTransaction, SignedTransaction, Action};
+use ethcore::views::BlockView;
 use v1::traits::Traces;
-use v1::helpers::CallRequest as CRequest;
 use v1::types::{TraceFilter, LocalizedTrace, Trace, BlockNumber, Index, CallRequest, Bytes, StateDiff, VMTrace, H256};
+use v1::impls::{error_codes, sign_call};
+
+fn to_call_analytics(flags: Vec<String>) -> CallAnalytics {
+	CallAnalytics {
+		transaction_tracing: flags.contains(&("trace".to_owned())),
+		vm_tracing: flags.contains(&("vmTrace".to_owned())),
+		state_diffing: flags.contains(&("stateDiff".to_owned())),
+	}
+}
+
+fn executed_to_value(executed: Executed) -> Value {
+	// TODO maybe add other stuff to this?
+	let mut ret = map!["output".to_owned() => to_value(&Bytes(executed.output)).unwrap()];
+	if let Some(trace) = executed.trace {
+		ret.insert("trace".to_owned(), to_value(&Trace::from(trace)).unwrap());
+	}
+	if let Some(vm_trace) = executed.vm_trace {
+		ret.insert("vmTrace".to_owned(), to_value(&VMTrace::from(vm_trace)).unwrap());
+	}
+	if let Some(state_diff) = executed.state_diff {
+		ret.insert("stateDiff".to_owned(), to_value(&StateDiff::from(state_diff)).unwrap());
+	}
+	Value::Object(ret)
+}
 
 /// Traces api implementation.
 pub struct TracesClient<C, M> where C: BlockChainClient, M: MinerService {
 	client: Weak<C>,
 	miner: Weak<M>,
+	gas_cap: U256,
 }
 
 impl<C, M> TracesClient<C, M> where C: BlockChainClient, M: MinerService {
 	/// Creates new Traces client.
-	pub fn new(client: &Arc<C>, miner: &Arc<M>) -> Self {
+	/// `gas_cap` is the gas supplied to calls which don't specify it.
+	pub fn new(client: &Arc<C>, miner: &Arc<M>, gas_cap: U256) -> Self {
 		TracesClient {
 			client: Arc::downgrade(client),
 			miner: Arc::downgrade(miner),
+			gas_cap: gas_cap,
 		}
 	}
 
-	// TODO: share with eth.rs
-	fn sign_call(&self, request: CRequest) -> Result<SignedTransaction, Error> {
-		let client = take_weak!(self.client);
-		let miner = take_weak!(self.miner);
-		let from = request.from.unwrap_or(0.into());
-		Ok(EthTransaction {
-			nonce: request.nonce.unwrap_or_else(|| client.latest_nonce(&from)),
-			action: request.to.map_or(Action::Create, Action::Call),
-			gas: request.gas.unwrap_or(50_000_000.into()),
-			gas_price: request.gas_price.unwrap_or_else(|| miner.sensible_gas_price()),
-			value: request.value.unwrap_or(0.into()),
-			data: request.data.map_or_else(Vec::new, |d| d.to_vec())
-		}.fake_sign(from))
-	}
-
 	fn active(&self) -> Result<(), Error> {
 		// TODO: only call every 30s at most.
 		take_weak!(self.client).keep_alive();
@@ -119,29 +132,61 @@ impl<C, M> Traces for TracesClient<C, M> where C: BlockChainClient + 'static, M:
 		from_params(params)
 			.and_then(|(request, flags)| {
 				let request = CallRequest::into(request);
-				let flags: Vec<String> = flags;
-				let analytics = CallAnalytics {
-					transaction_tracing: flags.contains(&("trace".to_owned())),
-					vm_tracing: flags.contains(&("vmTrace".to_owned())),
-					state_diffing: flags.contains(&("stateDiff".to_owned())),
-				};
-				let signed = try!(self.sign_call(request));
-				let r = take_weak!(self.client).call(&signed, analytics);
+				let client = take_weak!(self.client);
+				let miner = take_weak!(self.miner);
+				let signed = sign_call(&*client, &*miner, request, BlockID::Latest, self.gas_cap);
+				let r = client.call(&signed, BlockID::Latest, to_call_analytics(flags), &Default::default());
 				if let Ok(executed) = r {
-					// TODO maybe add other stuff to this?
-					let mut ret = map!["output".to_owned() => to_value(&Bytes(executed.output)).unwrap()];
-					if let Some(trace) = executed.trace {
-						ret.insert("trace".to_owned(), to_value(&Trace::from(trace)).unwrap());
-					}
-					if let Some(vm_trace) = executed.vm_trace {
-						ret.insert("vmTrace".to_owned(), to_value(&VMTrace::from(vm_trace)).unwrap());
-					}
-					if let Some(state_diff) = executed.state_diff {
-						ret.insert("stateDiff".to_owned(), to_value(&StateDiff::from(state_diff)).unwrap());
-					}
-					return Ok(Value::Object(ret))
+					return Ok(executed_to_value(executed))
 				}
 				Ok(Value::Null)
 			})
 	}
+
+	fn replay_transaction(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(H256, Vec<String>)>(params)
+			.and_then(|(transaction_hash, flags)| {
+				match take_weak!(self.client).replay(TransactionID::Hash(transaction_hash.into()), to_call_analytics(flags)) {
+					Ok(executed) => Ok(executed_to_value(executed)),
+					Err(e) => replay_error(e),
+				}
+			})
+	}
+
+	fn replay_block_transactions(&self, params: Params) -> Result<Value, Error> {
+		try!(self.active());
+		from_params::<(BlockNumber, Vec<String>)>(params)
+			.and_then(|(block_number, flags)| {
+				let client = take_weak!(self.client);
+				let id: BlockID = block_number.into();
+				let hashes = client.block(id.clone()).map_or_else(Vec::new, |block| BlockView::new(&block).transaction_hashes());
+				match client.replay_block(id, to_call_analytics(flags)) {
+					Ok(executed) => Ok(Value::Array(executed.into_iter().zip(hashes).map(|(executed, hash)| {
+						let mut value = executed_to_value(executed);
+						if let Value::Object(ref mut map) = value {
+							map.insert("transactionHash".to_owned(), to_value(&H256::from(hash)).unwrap());
+						}
+						value
+					}).collect())),
+					Err(e) => replay_error(e),
+				}
+			})
+	}
+}
+
+fn replay_error(e: ReplayError) -> Result<Value, Error> {
+	match e {
+		ReplayError::TransactionNotFound | ReplayError::BlockNotFound => Ok(Value::Null),
+		ReplayError::StatePruned => Err(Error {
+			code: ErrorCode::ServerError(error_codes::STATE_PRUNED),
+			message: "State of the transaction's block is not available, it may have been pruned.".into(),
+			data: None,
+		}),
+		ReplayError::Execution(e) => Err(Error {
+			code: ErrorCode::ServerError(error_codes::TRANSACTION_ERROR),
+			message: format!("{}", e),
+			data: None,
+		}),
+	}
 }
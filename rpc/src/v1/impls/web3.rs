@@ -16,23 +16,29 @@
 
 //! Web3 rpc implementation.
 use jsonrpc_core::*;
-use util::version;
+use util::misc::version_with_identity;
 use v1::traits::Web3;
 use v1::types::{H256, Bytes};
 use util::sha3::Hashable;
 
 /// Web3 rpc implementation.
-pub struct Web3Client;
+pub struct Web3Client {
+	client_version: String,
+}
 
 impl Web3Client {
-	/// Creates new Web3Client.
-	pub fn new() -> Self { Web3Client }
+	/// Creates new Web3Client reporting given node identity.
+	pub fn new(identity: &str) -> Self {
+		Web3Client {
+			client_version: version_with_identity(identity),
+		}
+	}
 }
 
 impl Web3 for Web3Client {
 	fn client_version(&self, params: Params) -> Result<Value, Error> {
 		match params {
-			Params::None => Ok(Value::String(version().to_owned().replace("Parity/", "Parity//"))),
+			Params::None => Ok(Value::String(self.client_version.clone())),
 			_ => Err(Error::invalid_params())
 		}
 	}
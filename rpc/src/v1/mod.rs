@@ -25,6 +25,6 @@ pub mod traits;
 pub mod tests;
 pub mod types;
 
-pub use self::traits::{Web3, Eth, EthFilter, EthSigning, Personal, PersonalSigner, Net, Ethcore, EthcoreSet, Traces, Rpc};
+pub use self::traits::{Web3, Eth, EthFilter, EthPubSub, EthSigning, Personal, PersonalSigner, Net, Ethcore, EthcoreSet, Traces, Rpc, Admin};
 pub use self::impls::*;
-pub use self::helpers::{SigningQueue, ConfirmationsQueue};
+pub use self::helpers::{SigningQueue, ConfirmationsQueue, ExitHandle, ExitRequest, ApiKeys, KeyError, RateLimiter, RateLimits, LimitError, AuditLog, Sink, SessionId};
@@ -25,7 +25,7 @@ use ethcore::spec::{Genesis, Spec};
 use ethcore::block::Block;
 use ethcore::views::BlockView;
 use ethcore::ethereum;
-use ethcore::miner::{MinerOptions, GasPricer, MinerService, ExternalMiner, Miner, PendingSet};
+use ethcore::miner::{MinerOptions, GasPricer, MinerService, ExternalMiner, Miner, PendingSet, RebroadcastPolicy};
 use ethcore::account_provider::AccountProvider;
 use devtools::RandomTempPath;
 use util::Hashable;
@@ -50,7 +50,7 @@ fn sync_provider() -> Arc<TestSyncProvider> {
 	}))
 }
 
-fn miner_service(spec: Spec, accounts: Arc<AccountProvider>) -> Arc<Miner> {
+fn miner_service(spec: &Spec, accounts: Arc<AccountProvider>) -> Arc<Miner> {
 	Miner::new(
 		MinerOptions {
 			new_work_notify: vec![],
@@ -63,6 +63,7 @@ fn miner_service(spec: Spec, accounts: Arc<AccountProvider>) -> Arc<Miner> {
 			reseal_min_period: Duration::from_secs(0),
 			work_queue_size: 50,
 			enable_resubmission: true,
+			tx_rebroadcast: RebroadcastPolicy::default(),
 		},
 		GasPricer::new_fixed(20_000_000_000u64.into()),
 		spec,
@@ -110,8 +111,9 @@ impl EthTester {
 
 		let dir = RandomTempPath::new();
 		let account_provider = account_provider();
-		let miner_service = miner_service(spec_provider(), account_provider.clone());
-		let client = Client::new(ClientConfig::default(), spec_provider(), dir.as_path(), miner_service.clone(), IoChannel::disconnected()).unwrap();
+		let spec = spec_provider();
+		let miner_service = miner_service(&spec, account_provider.clone());
+		let client = Client::new(ClientConfig::default(), &spec, dir.as_path(), miner_service.clone(), IoChannel::disconnected()).unwrap();
 		let sync_provider = sync_provider();
 		let external_miner = Arc::new(ExternalMiner::default());
 
@@ -121,7 +123,8 @@ impl EthTester {
 			&account_provider,
 			&miner_service,
 			&external_miner,
-			true
+			true,
+			U256::from(50_000_000)
 		);
 		let eth_sign = EthSigningUnsafeClient::new(
 			&client,
@@ -189,6 +192,42 @@ fn eth_block_number() {
 	assert_eq!(tester.handler.handle_request(req_number).unwrap(), res_number);
 }
 
+#[test]
+fn eth_call_state_override() {
+	let chain = extract_chain!("BlockchainTests/bcRPC_API_Test");
+	let tester = EthTester::from_chain(&chain);
+
+	// no code at the address
+	let req_call = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_call",
+		"params": [{"to": "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"}, "latest"],
+		"id": 1
+	}"#;
+	let res_call = r#"{"jsonrpc":"2.0","result":"0x","id":1}"#.to_owned();
+	assert_eq!(tester.handler.handle_request(req_call).unwrap(), res_call);
+
+	// code returning the first storage slot, and the slot itself
+	let req_override = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_call",
+		"params": [{"to": "0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa"}, "latest", {
+			"0xaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa": {
+				"code": "0x60005460005260206000f3",
+				"stateDiff": {
+					"0x0000000000000000000000000000000000000000000000000000000000000000": "0x0000000000000000000000000000000000000000000000000000000000000045"
+				}
+			}
+		}],
+		"id": 2
+	}"#;
+	let res_override = r#"{"jsonrpc":"2.0","result":"0x0000000000000000000000000000000000000000000000000000000000000045","id":2}"#.to_owned();
+	assert_eq!(tester.handler.handle_request(req_override).unwrap(), res_override);
+
+	// the override doesn't outlive the call
+	assert_eq!(tester.handler.handle_request(req_call).unwrap(), res_call);
+}
+
 // a frontier-like test with an expanded gas limit and balance on known account.
 const TRANSACTION_COUNT_SPEC: &'static [u8] = br#"{
 	"name": "Frontier (Test)",
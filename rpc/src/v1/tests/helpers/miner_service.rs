@@ -19,11 +19,11 @@
 use util::{Address, H256, Bytes, U256, FixedHash, Uint};
 use util::standard::*;
 use ethcore::error::{Error, ExecutionError};
-use ethcore::client::{MiningBlockChainClient, Executed, CallAnalytics};
+use ethcore::client::{MiningBlockChainClient, Executed, CallAnalytics, StateOverride};
 use ethcore::block::{ClosedBlock, IsBlock};
 use ethcore::transaction::SignedTransaction;
 use ethcore::receipt::Receipt;
-use ethcore::miner::{MinerService, MinerStatus, TransactionImportResult};
+use ethcore::miner::{MinerService, MinerStatus, TransactionImportResult, RebroadcastPolicy};
 
 /// Test miner service.
 pub struct TestMinerService {
@@ -37,6 +37,8 @@ pub struct TestMinerService {
 	pub pending_receipts: Mutex<BTreeMap<H256, Receipt>>,
 	/// Last nonces.
 	pub last_nonces: RwLock<HashMap<Address, U256>>,
+	/// Re-broadcast policies set for pending transactions.
+	pub rebroadcast_policies: Mutex<HashMap<H256, RebroadcastPolicy>>,
 
 	min_gas_price: RwLock<U256>,
 	gas_range_target: RwLock<(U256, U256)>,
@@ -54,6 +56,7 @@ impl Default for TestMinerService {
 			pending_transactions: Mutex::new(HashMap::new()),
 			pending_receipts: Mutex::new(BTreeMap::new()),
 			last_nonces: RwLock::new(HashMap::new()),
+			rebroadcast_policies: Mutex::new(HashMap::new()),
 			min_gas_price: RwLock::new(U256::from(20_000_000)),
 			gas_range_target: RwLock::new((U256::from(12345), U256::from(54321))),
 			author: RwLock::new(Address::zero()),
@@ -166,6 +169,10 @@ impl MinerService for TestMinerService {
 		vec![]
 	}
 
+	fn remove_pending_transaction(&self, _chain: &MiningBlockChainClient, hash: &H256) -> Option<SignedTransaction> {
+		self.pending_transactions.lock().remove(hash)
+	}
+
 	/// Removes all transactions from the queue and restart mining operation.
 	fn clear_and_reset(&self, _chain: &MiningBlockChainClient) {
 		unimplemented!();
@@ -193,6 +200,18 @@ impl MinerService for TestMinerService {
 		self.pending_transactions.lock().values().cloned().collect()
 	}
 
+	fn transactions_to_rebroadcast(&self) -> Vec<SignedTransaction> {
+		vec![]
+	}
+
+	fn set_transaction_rebroadcast(&self, hash: &H256, policy: RebroadcastPolicy) -> bool {
+		if !self.pending_transactions.lock().contains_key(hash) {
+			return false;
+		}
+		self.rebroadcast_policies.lock().insert(hash.clone(), policy);
+		true
+	}
+
 	fn pending_transactions(&self) -> Vec<SignedTransaction> {
 		self.pending_transactions.lock().values().cloned().collect()
 	}
@@ -208,14 +227,15 @@ impl MinerService for TestMinerService {
 	/// Submit `seal` as a valid solution for the header of `pow_hash`.
 	/// Will check the seal, but not actually insert the block into the chain.
 	fn submit_seal(&self, _chain: &MiningBlockChainClient, _pow_hash: H256, _seal: Vec<Bytes>) -> Result<(), Error> {
-		unimplemented!();
+		// no sealing work is ever prepared
+		Err(Error::PowHashInvalid)
 	}
 
 	fn balance(&self, _chain: &MiningBlockChainClient, address: &Address) -> U256 {
 		self.latest_closed_block.lock().as_ref().map_or_else(U256::zero, |b| b.block().fields().state.balance(address).clone())
 	}
 
-	fn call(&self, _chain: &MiningBlockChainClient, _t: &SignedTransaction, _analytics: CallAnalytics) -> Result<Executed, ExecutionError> {
+	fn call(&self, _chain: &MiningBlockChainClient, _t: &SignedTransaction, _analytics: CallAnalytics, _state_override: &StateOverride) -> Result<Executed, ExecutionError> {
 		unimplemented!();
 	}
 
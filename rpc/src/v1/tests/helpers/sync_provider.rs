@@ -17,7 +17,7 @@
 //! Test implementation of SyncProvider.
 
 use util::{RwLock, U256};
-use ethsync::{SyncProvider, SyncStatus, SyncState};
+use ethsync::{SyncProvider, SyncStatus, SyncState, PeerVersionCount};
 
 /// TestSyncProvider config.
 pub struct Config {
@@ -31,6 +31,8 @@ pub struct Config {
 pub struct TestSyncProvider {
 	/// Sync status.
 	pub status: RwLock<SyncStatus>,
+	/// Peers by protocol and client version.
+	pub peer_versions: RwLock<Vec<PeerVersionCount>>,
 }
 
 impl TestSyncProvider {
@@ -49,7 +51,10 @@ impl TestSyncProvider {
 				num_peers: config.num_peers,
 				num_active_peers: 0,
 				mem_used: 0,
+				num_snapshot_chunks: 0,
+				snapshot_chunks_done: 0,
 			}),
+			peer_versions: RwLock::new(Vec::new()),
 		}
 	}
 }
@@ -58,5 +63,17 @@ impl SyncProvider for TestSyncProvider {
 	fn status(&self) -> SyncStatus {
 		self.status.read().clone()
 	}
+
+	fn peer_versions(&self) -> Vec<PeerVersionCount> {
+		self.peer_versions.read().clone()
+	}
+
+	fn enode(&self) -> Option<String> {
+		Some("enode://1234@127.0.0.1:30303".to_owned())
+	}
+
+	fn protocols(&self) -> Vec<String> {
+		vec!["eth/63".to_owned()]
+	}
 }
 
@@ -0,0 +1,74 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use jsonrpc_core::IoHandler;
+use ethsync::ManageNetwork;
+use util::{self, U256};
+use v1::{Admin, AdminClient};
+use v1::tests::helpers::{Config, TestSyncProvider};
+use super::manage_network::TestManageNetwork;
+
+fn io() -> IoHandler {
+	let sync = Arc::new(TestSyncProvider::new(Config {
+		network_id: U256::from(3),
+		num_peers: 1,
+	}));
+	let net: Arc<ManageNetwork> = Arc::new(TestManageNetwork);
+	let io = IoHandler::new();
+	io.add_delegate(AdminClient::new(&sync, &net).to_delegate());
+	io
+}
+
+#[test]
+fn rpc_admin_add_peer() {
+	let io = io();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "admin_addPeer", "params": ["enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_admin_remove_peer() {
+	let io = io();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "admin_removePeer", "params": ["enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_admin_peers() {
+	let io = io();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "admin_peers", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[{"id":"1234","clientVersion":"Parity/v1.3.0","protocolVersion":4,"capabilities":["eth/62","eth/63"],"remoteAddress":"127.0.0.1:30303","pingMs":25,"originated":true}],"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_admin_node_info() {
+	let io = io();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "admin_nodeInfo", "params": [], "id": 1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":{{"name":"{}","enode":"enode://1234@127.0.0.1:30303","listenAddress":"127.0.0.1:0","protocols":["eth/63"]}},"id":1}}"#, util::version());
+
+	assert_eq!(io.handle_request(request), Some(response));
+}
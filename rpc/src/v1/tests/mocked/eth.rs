@@ -69,7 +69,7 @@ impl Default for EthTester {
 		let miner = miner_service();
 		let hashrates = Arc::new(RwLock::new(HashMap::new()));
 		let external_miner = Arc::new(ExternalMiner::new(hashrates.clone()));
-		let eth = EthClient::new(&client, &sync, &ap, &miner, &external_miner, true).to_delegate();
+		let eth = EthClient::new(&client, &sync, &ap, &miner, &external_miner, true, U256::from(50_000_000)).to_delegate();
 		let sign = EthSigningUnsafeClient::new(&client, &ap, &miner).to_delegate();
 		let io = IoHandler::new();
 		io.add_delegate(eth);
@@ -377,6 +377,36 @@ fn rpc_eth_pending_transaction_by_hash() {
 	assert_eq!(tester.io.handle_request(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_eth_pending_transaction_by_sender_and_nonce() {
+	use util::*;
+	use ethcore::transaction::*;
+
+	let tester = EthTester::default();
+	{
+		let tx: SignedTransaction = decode(&FromHex::from_hex("f85f800182520894095e7baea6a6c7c4c2dfeb977efac326af552d870a801ba048b55bfa915ac795c431978d8a6a992b628d557da5ff759b307d495a36649353a0efffd310ac743f371de3b9f7f9cb56c0b28ad43601b4ab949f53faa07bd2c804").unwrap());
+		tester.miner.pending_transactions.lock().insert(H256::zero(), tx);
+	}
+
+	let response = r#"{"jsonrpc":"2.0","result":{"blockHash":null,"blockNumber":null,"creates":null,"from":"0x0f65fe9276bc9a24ae7083ae28e2660ef72df99e","gas":"0x5208","gasPrice":"0x01","hash":"0x41df922fd0d4766fcc02e161f8295ec28522f329ae487f14d811e4b64c8d6e31","input":"0x","nonce":"0x00","to":"0x095e7baea6a6c7c4c2dfeb977efac326af552d87","transactionIndex":null,"value":"0x0a"},"id":1}"#;
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_getTransactionBySenderAndNonce",
+		"params": ["0x0f65fe9276bc9a24ae7083ae28e2660ef72df99e", "0x0"],
+		"id": 1
+	}"#;
+	assert_eq!(tester.io.handle_request(request), Some(response.to_owned()));
+
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_getTransactionBySenderAndNonce",
+		"params": ["0x0f65fe9276bc9a24ae7083ae28e2660ef72df99e", "0x1"],
+		"id": 1
+	}"#;
+	assert_eq!(tester.io.handle_request(request), Some(response.to_owned()));
+}
+
 
 #[test]
 fn rpc_eth_uncle_count_by_block_hash() {
@@ -434,6 +464,7 @@ fn rpc_eth_call() {
 		trace: None,
 		vm_trace: None,
 		state_diff: None,
+		exception: false,
 	});
 
 	let request = r#"{
@@ -469,6 +500,7 @@ fn rpc_eth_call_default_block() {
 		trace: None,
 		vm_trace: None,
 		state_diff: None,
+		exception: false,
 	});
 
 	let request = r#"{
@@ -503,6 +535,7 @@ fn rpc_eth_estimate_gas() {
 		trace: None,
 		vm_trace: None,
 		state_diff: None,
+		exception: false,
 	});
 
 	let request = r#"{
@@ -538,6 +571,7 @@ fn rpc_eth_estimate_gas_default_block() {
 		trace: None,
 		vm_trace: None,
 		state_diff: None,
+		exception: false,
 	});
 
 	let request = r#"{
@@ -558,6 +592,38 @@ fn rpc_eth_estimate_gas_default_block() {
 	assert_eq!(tester.io.handle_request(request), Some(response.to_owned()));
 }
 
+#[test]
+fn rpc_eth_estimate_gas_failing_call() {
+	let tester = EthTester::default();
+	tester.client.set_execution_result(Executed {
+		gas: U256::from(0x76c0),
+		gas_used: U256::from(0x76c0),
+		refunded: U256::zero(),
+		cumulative_gas_used: U256::zero(),
+		logs: vec![],
+		contracts_created: vec![],
+		output: vec![],
+		trace: None,
+		vm_trace: None,
+		state_diff: None,
+		exception: true,
+	});
+
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "eth_estimateGas",
+		"params": [{
+			"from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"gas": "0x76c0"
+		}],
+		"id": 1
+	}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32015,"message":"Gas required exceeds allowance (30400) or the call always fails.","data":null},"id":1}"#;
+
+	assert_eq!(tester.io.handle_request(request), Some(response.to_owned()));
+}
+
 #[test]
 fn rpc_eth_send_transaction() {
 	let tester = EthTester::default();
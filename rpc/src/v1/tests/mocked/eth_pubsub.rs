@@ -0,0 +1,138 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use jsonrpc_core::IoHandler;
+use util::hash::H256;
+use util::Mutex;
+use ethcore::client::{TestBlockChainClient, EachBlockWith, BlockChainClient, BlockID, ChainNotify};
+use v1::{EthPubSubClient, Web3, Web3Client};
+use PubSubSession;
+
+struct Tester {
+	client: Arc<TestBlockChainClient>,
+	pubsub: Arc<EthPubSubClient<TestBlockChainClient>>,
+	session: PubSubSession,
+	messages: Arc<Mutex<Vec<String>>>,
+}
+
+fn tester() -> Tester {
+	let client = Arc::new(TestBlockChainClient::new());
+	let pubsub = Arc::new(EthPubSubClient::new(&client));
+	let io = IoHandler::new();
+	io.add_delegate(Web3Client::new("").to_delegate());
+
+	let messages = Arc::new(Mutex::new(Vec::new()));
+	let m = messages.clone();
+	let session = PubSubSession::new(Arc::new(io), Some(pubsub.clone()), Arc::new(move |message: String| {
+		m.lock().push(message);
+		true
+	}));
+
+	Tester {
+		client: client,
+		pubsub: pubsub,
+		session: session,
+		messages: messages,
+	}
+}
+
+#[test]
+fn rpc_eth_subscribe_pending_transactions() {
+	let tester = tester();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["newPendingTransactions"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x00","id":1}"#;
+	assert_eq!(tester.session.handle_request(request), Some(response.to_owned()));
+
+	tester.pubsub.notify_transactions(&[H256::default()]);
+	let notification = r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"subscription":"0x00","result":"0x0000000000000000000000000000000000000000000000000000000000000000"}}"#;
+	assert_eq!(*tester.messages.lock(), vec![notification.to_owned()]);
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_unsubscribe", "params": ["0x00"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(tester.session.handle_request(request), Some(response.to_owned()));
+
+	tester.pubsub.notify_transactions(&[H256::default()]);
+	assert_eq!(tester.messages.lock().len(), 1);
+}
+
+#[test]
+fn rpc_eth_subscribe_new_heads() {
+	let tester = tester();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["newHeads"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x00","id":1}"#;
+	assert_eq!(tester.session.handle_request(request), Some(response.to_owned()));
+
+	tester.client.add_blocks(1, EachBlockWith::Nothing);
+	let hash = tester.client.block_hash(BlockID::Latest).unwrap();
+	tester.pubsub.new_blocks(vec![hash.clone()], vec![], vec![hash.clone()], vec![], vec![], 0);
+
+	let messages = tester.messages.lock();
+	assert_eq!(messages.len(), 1);
+	assert!(messages[0].starts_with(r#"{"jsonrpc":"2.0","method":"eth_subscription","params":{"subscription":"0x00","result":{"#));
+	assert!(messages[0].contains(&format!(r#""hash":"0x{}""#, hash.hex())));
+}
+
+#[test]
+fn rpc_eth_subscribe_rejects_invalid_params() {
+	let tester = tester();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["syncing"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":null},"id":1}"#;
+	assert_eq!(tester.session.handle_request(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["newHeads", {}], "id": 1}"#;
+	assert_eq!(tester.session.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_eth_unsubscribe_other_session() {
+	let tester = tester();
+	let other = PubSubSession::new(Arc::new(IoHandler::new()), Some(tester.pubsub.clone()), Arc::new(|_| true));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["newPendingTransactions"], "id": 1}"#;
+	assert!(tester.session.handle_request(request).is_some());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_unsubscribe", "params": ["0x00"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":false,"id":1}"#;
+	assert_eq!(other.handle_request(request), Some(response.to_owned()));
+
+	tester.pubsub.notify_transactions(&[H256::default()]);
+	assert_eq!(tester.messages.lock().len(), 1);
+}
+
+#[test]
+fn rpc_pubsub_session_forwards_other_requests() {
+	let tester = tester();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "web3_sha3", "params": ["0x00"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0xbc36789e7a1e281436464229828f817d6612f7b477d66591ff96a9e064bcc98a","id":1}"#;
+	assert_eq!(tester.session.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_pubsub_session_drops_subscriptions_when_closed() {
+	let Tester { pubsub, session, messages, .. } = tester();
+
+	let request = r#"{"jsonrpc": "2.0", "method": "eth_subscribe", "params": ["newPendingTransactions"], "id": 1}"#;
+	assert!(session.handle_request(request).is_some());
+
+	drop(session);
+	pubsub.notify_transactions(&[H256::default()]);
+	assert!(messages.lock().is_empty());
+}
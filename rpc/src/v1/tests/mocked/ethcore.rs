@@ -17,10 +17,11 @@
 use std::sync::Arc;
 use jsonrpc_core::IoHandler;
 use v1::{Ethcore, EthcoreClient};
-use v1::tests::helpers::TestMinerService;
+use v1::tests::helpers::{TestMinerService, TestSyncProvider, Config};
 use v1::helpers::ConfirmationsQueue;
 use ethcore::client::{TestBlockChainClient};
 use util::log::RotatingLogger;
+use util::numbers::U256;
 use util::network_settings::NetworkSettings;
 
 fn miner_service() -> Arc<TestMinerService> {
@@ -31,6 +32,13 @@ fn client_service() -> Arc<TestBlockChainClient> {
 	Arc::new(TestBlockChainClient::default())
 }
 
+fn sync_provider() -> Arc<TestSyncProvider> {
+	Arc::new(TestSyncProvider::new(Config {
+		network_id: U256::from(3),
+		num_peers: 120,
+	}))
+}
+
 fn logger() -> Arc<RotatingLogger> {
 	Arc::new(RotatingLogger::new("rpc=trace".to_owned()))
 }
@@ -44,11 +52,13 @@ fn settings() -> Arc<NetworkSettings> {
 		rpc_enabled: true,
 		rpc_interface: "all".to_owned(),
 		rpc_port: 8545,
+		db_path: "/db".to_owned(),
+		keys_path: "/keys".to_owned(),
 	})
 }
 
-fn ethcore_client(client: &Arc<TestBlockChainClient>, miner: &Arc<TestMinerService>) -> EthcoreClient<TestBlockChainClient, TestMinerService> {
-	EthcoreClient::new(client, miner, logger(), settings(), None)
+fn ethcore_client(client: &Arc<TestBlockChainClient>, miner: &Arc<TestMinerService>) -> EthcoreClient<TestBlockChainClient, TestMinerService, TestSyncProvider> {
+	EthcoreClient::new(client, miner, &sync_provider(), logger(), settings(), None, U256::from(50_000_000))
 }
 
 #[test]
@@ -113,7 +123,7 @@ fn rpc_ethcore_dev_logs() {
 	let logger = logger();
 	logger.append("a".to_owned());
 	logger.append("b".to_owned());
-	let ethcore = EthcoreClient::new(&client, &miner, logger.clone(), settings(), None).to_delegate();
+	let ethcore = EthcoreClient::new(&client, &miner, &sync_provider(), logger.clone(), settings(), None, U256::from(50_000_000)).to_delegate();
 	let io = IoHandler::new();
 	io.add_delegate(ethcore);
 
@@ -220,7 +230,7 @@ fn rpc_ethcore_unsigned_transactions_count() {
 	let client = client_service();
 	let io = IoHandler::new();
 	let queue = Arc::new(ConfirmationsQueue::default());
-	let ethcore = EthcoreClient::new(&client, &miner, logger(), settings(), Some(queue)).to_delegate();
+	let ethcore = EthcoreClient::new(&client, &miner, &sync_provider(), logger(), settings(), Some(queue), U256::from(50_000_000)).to_delegate();
 	io.add_delegate(ethcore);
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_unsignedTransactionsCount", "params":[], "id": 1}"#;
@@ -241,3 +251,469 @@ fn rpc_ethcore_unsigned_transactions_count_when_signer_disabled() {
 
 	assert_eq!(io.handle_request(request), Some(response.to_owned()));
 }
+
+#[test]
+fn rpc_ethcore_db_stats() {
+	let miner = miner_service();
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_dbStats", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[],"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_get_block_template_without_author() {
+	let miner = miner_service();
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_getBlockTemplate", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32002,"message":"Author not configured. Run parity with --author to configure.","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_get_block_template_without_pending_block() {
+	use ethcore::miner::MinerService;
+	use util::Address;
+
+	let miner = miner_service();
+	miner.set_author(Address::from(1));
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_getBlockTemplate", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32603,"message":"Internal error","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_submit_block_invalid_rlp() {
+	let miner = miner_service();
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_submitBlock", "params": ["0x0102"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_submit_block_unknown_template() {
+	use ethcore::block::Block;
+	use ethcore::header::{Header, Seal};
+	use util::ToPretty;
+
+	let miner = miner_service();
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let block = Block { header: Header::new(), transactions: vec![], uncles: vec![] };
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "ethcore_submitBlock", "params": ["0x{}"], "id": 1}}"#, block.rlp_bytes(Seal::With).to_hex());
+	let response = r#"{"jsonrpc":"2.0","result":false,"id":1}"#;
+
+	assert_eq!(io.handle_request(&request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_get_storage_range_proof_unknown_account() {
+	let miner = miner_service();
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_getStorageRangeProof", "params": ["0x0000000000000000000000000000000000000001", "0x0000000000000000000000000000000000000000000000000000000000000000", 10, "latest"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_get_storage_range_proof_too_large() {
+	let miner = miner_service();
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_getStorageRangeProof", "params": ["0x0000000000000000000000000000000000000001", "0x0000000000000000000000000000000000000000000000000000000000000000", 100000], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_miner_stats_range() {
+	use ethcore::client::EachBlockWith;
+
+	let miner = miner_service();
+	let client = client_service();
+	client.add_blocks(3, EachBlockWith::Uncle);
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_minerStatsRange", "params": ["0x1", "latest"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[{"author":"0x0000000000000000000000000000000000000000","blocks":3,"uncles":3,"fees":"0x00","reward":"0x00"}],"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_miner_stats_range_unknown_blocks() {
+	let miner = miner_service();
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_minerStatsRange", "params": ["0x0", "0x10"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_gas_profile() {
+	use util::{Address, H256};
+	use ethcore::trace::LocalizedTrace;
+	use ethcore::trace::trace::{Action, Call, CallResult, Res};
+
+	let miner = miner_service();
+	let client = client_service();
+	let trace = |to: u64, gas_used: u64| LocalizedTrace {
+		action: Action::Call(Call {
+			from: Address::from(1),
+			to: Address::from(to),
+			value: U256::from(0),
+			gas: U256::from(100000),
+			input: vec![],
+		}),
+		result: Res::Call(CallResult {
+			gas_used: U256::from(gas_used),
+			output: vec![],
+		}),
+		subtraces: 0,
+		trace_address: vec![],
+		transaction_number: Some(0),
+		transaction_hash: Some(H256::from(3)),
+		block_number: 0,
+		block_hash: H256::from(4),
+	};
+	*client.traces.write() = Some(vec![trace(2, 0x100), trace(5, 0x1000), trace(2, 0x200)]);
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_gasProfile", "params": ["earliest", "latest"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[{"address":"0x0000000000000000000000000000000000000005","calls":1,"failed":0,"gasUsed":"0x1000"},{"address":"0x0000000000000000000000000000000000000002","calls":2,"failed":0,"gasUsed":"0x0300"}],"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_gas_profile_rejects_long_ranges() {
+	use ethcore::client::EachBlockWith;
+
+	let miner = miner_service();
+	let client = client_service();
+	client.add_blocks(1000, EachBlockWith::Nothing);
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_gasProfile", "params": ["earliest", "latest"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_evm_profile() {
+	use util::Address;
+	use ethcore::client::{ContractProfile, InstructionProfile};
+
+	let miner = miner_service();
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_evmProfile", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32000,"message":"EVM profiling is disabled. Run parity with --profile-evm to enable.","data":null},"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+
+	*client.evm_profile.write() = Some(vec![ContractProfile {
+		address: Address::from(5),
+		gas: U256::from(0x20),
+		instructions: vec![
+			InstructionProfile { name: "SSTORE".into(), count: 1, gas: U256::from(0x14) },
+			InstructionProfile { name: "PUSH1".into(), count: 4, gas: U256::from(0x0c) },
+		],
+	}]);
+	let response = r#"{"jsonrpc":"2.0","result":[{"address":"0x0000000000000000000000000000000000000005","gasUsed":"0x20","instructions":[{"name":"SSTORE","count":1,"gasUsed":"0x14"},{"name":"PUSH1","count":4,"gasUsed":"0x0c"}]}],"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_snapshot_status() {
+	use ethcore::client::SnapshotStatus;
+	use ethsync::SyncState;
+
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	*client.snapshot_status.write() = SnapshotStatus {
+		creating: Some(3000),
+		accounts: 120,
+		blocks: 30,
+		size: 4096,
+		last_snapshot: Some(2000),
+	};
+	{
+		let mut status = sync.status.write();
+		status.state = SyncState::SnapshotData;
+		status.num_snapshot_chunks = 10;
+		status.snapshot_chunks_done = 5;
+	}
+	let io = IoHandler::new();
+	io.add_delegate(EthcoreClient::new(&client, &miner, &sync, logger(), settings(), None, U256::from(50_000_000)).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_snapshotStatus", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"creating":{"block":3000,"accounts":120,"blocks":30,"size":4096},"restoring":{"chunksDone":5,"chunksTotal":10},"lastSnapshot":2000},"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_get_log() {
+	let miner = miner_service();
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_getLog", "params": ["latest", "0x0", "0x1"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_getLog", "params": ["latest", "0x0"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":null},"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_peer_versions() {
+	use ethsync::PeerVersionCount;
+
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	*sync.peer_versions.write() = vec![PeerVersionCount {
+		protocol_version: 63,
+		client_version: "Parity/v1.3.0".to_owned(),
+		count: 2,
+	}];
+	let io = IoHandler::new();
+	io.add_delegate(EthcoreClient::new(&client, &miner, &sync, logger(), settings(), None, U256::from(50_000_000)).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_peerVersions", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[{"protocolVersion":63,"clientVersion":"Parity/v1.3.0","count":2}],"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_node_info() {
+	use ethcore::client::{BlockChainClient, BlockID};
+
+	let miner = miner_service();
+	let client = client_service();
+	let sync = sync_provider();
+	let io = IoHandler::new();
+	io.add_delegate(EthcoreClient::new(&client, &miner, &sync, logger(), settings(), None, U256::from(50_000_000)).to_delegate());
+
+	let genesis = client.block_hash(BlockID::Earliest).unwrap();
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_nodeInfo", "params": [], "id": 1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":{{"name":"mynode","enode":"enode://1234@127.0.0.1:30303","chain":"testchain","genesisHash":"0x{}","protocols":["eth/63"],"networkPort":30303,"rpcPort":8545,"dataPath":"/db","keysPath":"/keys"}},"id":1}}"#, genesis.hex());
+
+	assert_eq!(io.handle_request(request), Some(response));
+}
+
+#[test]
+fn rpc_ethcore_node_status() {
+	use ethcore::client::BlockChainClient;
+
+	let miner = miner_service();
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let hash = client.chain_info().best_block_hash;
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_nodeStatus", "params": [], "id": 1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":{{"bestBlockNumber":"0x00","bestBlockHash":"0x{}","totalDifficulty":"0x00","queue":{{"unverified":0,"verifying":0,"verified":0,"memBytes":0}},"cacheBytes":0,"majorSyncing":false,"highestBlock":null,"peers":{{"active":0,"connected":120,"ideal":25}}}},"id":1}}"#, hash.hex());
+
+	assert_eq!(io.handle_request(request), Some(response));
+}
+
+// pins the latest block, returning the handle.
+fn pin_block(io: &IoHandler) -> String {
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_pinBlock", "params": [], "id": 1}"#;
+	let response = io.handle_request(request).unwrap();
+	let start = response.find(r#""id":""#).unwrap() + 6;
+	response[start..start + 66].to_owned()
+}
+
+#[test]
+fn rpc_ethcore_pin_block() {
+	use ethcore::client::BlockChainClient;
+
+	let miner = miner_service();
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let hash = client.chain_info().best_block_hash;
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_pinBlock", "params": [], "id": 1}"#;
+	let response = format!(r#""number":"0x00","hash":"0x{}"}},"id":1}}"#, hash.hex());
+	assert!(io.handle_request(request).unwrap().ends_with(&response));
+
+	// handles are random.
+	let id = pin_block(&io);
+	assert!(id != pin_block(&io));
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "ethcore_unpinBlock", "params": ["{}"], "id": 1}}"#, id);
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request(&request), Some(response.to_owned()));
+
+	let response = r#"{"jsonrpc":"2.0","result":false,"id":1}"#;
+	assert_eq!(io.handle_request(&request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_pinned_call() {
+	use ethcore::client::Executed;
+
+	let miner = miner_service();
+	let client = client_service();
+	client.set_execution_result(Executed {
+		gas: U256::zero(),
+		gas_used: U256::from(0xff30),
+		refunded: U256::from(0x5),
+		cumulative_gas_used: U256::zero(),
+		logs: vec![],
+		contracts_created: vec![],
+		output: vec![0x12, 0x34, 0xff],
+		trace: None,
+		vm_trace: None,
+		state_diff: None,
+		exception: false,
+	});
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let id = pin_block(&io);
+
+	let request = format!(r#"{{
+		"jsonrpc": "2.0",
+		"method": "ethcore_pinnedCall",
+		"params": ["{}", {{
+			"from": "0xb60e8dd61c5d32be8058bb8eb970870f07233155",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"data": "0xd46e8dd67c5d32be"
+		}}],
+		"id": 1
+	}}"#, id);
+	let response = r#"{"jsonrpc":"2.0","result":"0x1234ff","id":1}"#;
+	assert_eq!(io.handle_request(&request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_unknown_pin() {
+	let miner = miner_service();
+	let client = client_service();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	// pins of other sessions can't be guessed.
+	pin_block(&io);
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_pinnedBalance", "params": ["0x0000000000000000000000000000000000000000000000000000000000000000", "0x0000000000000000000000000000000000000001"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32041,"message":"Unknown or expired pin.","data":null},"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_get_storage_at_many() {
+	use util::hash::{Address, H256};
+
+	let miner = miner_service();
+	let client = client_service();
+	client.set_storage(Address::from(1), H256::from(4), H256::from(7));
+	client.set_storage(Address::from(1), H256::from(5), H256::from(8));
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_getStorageAtMany", "params": ["0x0000000000000000000000000000000000000001", ["0x4", "0x5", "0x6"], "latest"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":["0x07","0x08","0x00"],"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_getStorageAtMany", "params": ["0x0000000000000000000000000000000000000001", ["0x4"], "0x1"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32040,"message":"State of the block is not available, it may have been pruned.","data":null},"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_get_accounts() {
+	use util::hash::Address;
+
+	let miner = miner_service();
+	let client = client_service();
+	client.set_balance(Address::from(1), U256::from(0x1000));
+	client.set_nonce(Address::from(1), U256::from(3));
+	client.set_code(Address::from(2), vec![0x60, 0x00]);
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_getAccounts", "params": [["0x0000000000000000000000000000000000000001", "0x0000000000000000000000000000000000000002"]], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":[{"balance":"0x1000","code":"0x","nonce":"0x03"},{"balance":"0x00","code":"0x6000","nonce":"0x00"}],"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_stream_blocks() {
+	use rustc_serialize::hex::ToHex;
+	use ethcore::client::{BlockChainClient, BlockID, EachBlockWith};
+
+	let miner = miner_service();
+	let client = client_service();
+	client.add_blocks(2, EachBlockWith::Nothing);
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_client(&client, &miner).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_streamBlocks", "params": ["0x1", "latest"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"0x00","id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+
+	let blocks = (1..3).map(|n| format!("\"0x{}\"", client.block(BlockID::Number(n)).unwrap().to_hex())).collect::<Vec<_>>();
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_streamChunk", "params": ["0x0", "0x0"], "id": 1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":{{"seq":"0x01","blocks":[{}],"done":true}},"id":1}}"#, blocks.join(","));
+	assert_eq!(io.handle_request(request), Some(response.clone()));
+	// unacknowledged chunk is sent again.
+	assert_eq!(io.handle_request(request), Some(response));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_streamChunk", "params": ["0x0", "0x5"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":null},"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_closeStream", "params": ["0x0"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_streamChunk", "params": ["0x0", "0x1"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32042,"message":"Unknown or expired stream.","data":null},"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
@@ -17,11 +17,14 @@
 use std::sync::Arc;
 use std::str::FromStr;
 use jsonrpc_core::IoHandler;
-use v1::{EthcoreSet, EthcoreSetClient};
+use v1::{EthcoreSet, EthcoreSetClient, ExitHandle, ApiKeys};
 use ethcore::miner::MinerService;
 use ethcore::client::TestBlockChainClient;
+use ethcore::account_provider::AccountProvider;
+use ethcore::transaction::{Transaction, Action};
 use v1::tests::helpers::TestMinerService;
 use util::numbers::*;
+use util::crypto::Secret;
 use rustc_serialize::hex::FromHex;
 use super::manage_network::TestManageNetwork;
 use ethsync::ManageNetwork;
@@ -38,8 +41,20 @@ fn network_service() -> Arc<TestManageNetwork> {
 	Arc::new(TestManageNetwork)
 }
 
-fn ethcore_set_client(client: &Arc<TestBlockChainClient>, miner: &Arc<TestMinerService>, net: &Arc<TestManageNetwork>) -> EthcoreSetClient<TestBlockChainClient, TestMinerService> {
-	EthcoreSetClient::new(client, miner, &(net.clone() as Arc<ManageNetwork>))
+fn accounts_provider() -> Arc<AccountProvider> {
+	Arc::new(AccountProvider::transient_provider())
+}
+
+fn exit_handle() -> Arc<ExitHandle> {
+	Arc::new(ExitHandle::new())
+}
+
+fn api_keys() -> Arc<ApiKeys> {
+	Arc::new(ApiKeys::new(vec!["eth".into(), "net".into()]))
+}
+
+fn ethcore_set_client(client: &Arc<TestBlockChainClient>, miner: &Arc<TestMinerService>, net: &Arc<TestManageNetwork>, accounts: &Arc<AccountProvider>) -> EthcoreSetClient<TestBlockChainClient, TestMinerService> {
+	EthcoreSetClient::new(client, miner, &(net.clone() as Arc<ManageNetwork>), accounts, &exit_handle(), &api_keys())
 }
 
 #[test]
@@ -47,8 +62,9 @@ fn rpc_ethcore_set_min_gas_price() {
 	let miner = miner_service();
 	let client = client_service();
 	let network = network_service();
+	let accounts = accounts_provider();
 	let io = IoHandler::new();
-	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_setMinGasPrice", "params":["0xcd1722f3947def4cf144679da39c4c32bdc35681"], "id": 1}"#;
 	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
@@ -62,8 +78,9 @@ fn rpc_ethcore_set_gas_floor_target() {
 	let miner = miner_service();
 	let client = client_service();
 	let network = network_service();
+	let accounts = accounts_provider();
 	let io = IoHandler::new();
-	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_setGasFloorTarget", "params":["0xcd1722f3947def4cf144679da39c4c32bdc35681"], "id": 1}"#;
 	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
@@ -77,8 +94,9 @@ fn rpc_ethcore_set_extra_data() {
 	let miner = miner_service();
 	let client = client_service();
 	let network = network_service();
+	let accounts = accounts_provider();
 	let io = IoHandler::new();
-	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_setExtraData", "params":["0xcd1722f3947def4cf144679da39c4c32bdc35681"], "id": 1}"#;
 	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
@@ -92,8 +110,9 @@ fn rpc_ethcore_set_author() {
 	let miner = miner_service();
 	let client = client_service();
 	let network = network_service();
+	let accounts = accounts_provider();
 	let io = IoHandler::new();
-	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_setAuthor", "params":["0xcd1722f3947def4cf144679da39c4c32bdc35681"], "id": 1}"#;
 	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
@@ -107,8 +126,9 @@ fn rpc_ethcore_set_transactions_limit() {
 	let miner = miner_service();
 	let client = client_service();
 	let network = network_service();
+	let accounts = accounts_provider();
 	let io = IoHandler::new();
-	io.add_delegate(ethcore_set_client(&client, &miner, &network).to_delegate());
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
 
 	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_setTransactionsLimit", "params":[10240240], "id": 1}"#;
 	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
@@ -116,3 +136,219 @@ fn rpc_ethcore_set_transactions_limit() {
 	assert_eq!(io.handle_request(request), Some(response.to_owned()));
 	assert_eq!(miner.transactions_limit(), 10_240_240);
 }
+
+#[test]
+fn rpc_ethcore_db_get() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let accounts = accounts_provider();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_dbGet", "params":["extras", "0x62657374"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_dbGet", "params":["unknown", "0x62657374"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":null},"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_remove_transaction() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let accounts = accounts_provider();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
+
+	let transaction = Transaction {
+		nonce: U256::zero(),
+		gas_price: U256::from(1),
+		gas: U256::from(21000),
+		action: Action::Call(Address::from(1)),
+		value: U256::from(10),
+		data: vec![],
+	}.sign(&Secret::from(1));
+	let hash = transaction.hash();
+	miner.pending_transactions.lock().insert(hash, transaction);
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "ethcore_removeTransaction", "params":["0x{}"], "id": 1}}"#, hash.hex());
+	let response = io.handle_request(&request).unwrap();
+	assert!(response.contains(&format!(r#""hash":"0x{}""#, hash.hex())));
+	assert!(miner.pending_transactions.lock().is_empty());
+
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+	assert_eq!(io.handle_request(&request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_reprioritize_transaction() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let accounts = accounts_provider();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
+
+	let secret = Secret::from(1);
+	let address = accounts.insert_account(secret.clone(), "").unwrap();
+	let transaction = Transaction {
+		nonce: U256::zero(),
+		gas_price: U256::from(1),
+		gas: U256::from(21000),
+		action: Action::Call(Address::from(1)),
+		value: U256::from(10),
+		data: vec![],
+	}.sign(&secret);
+	let hash = transaction.hash();
+	miner.pending_transactions.lock().insert(hash, transaction.clone());
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "ethcore_reprioritizeTransaction", "params":["0x{}", "0x2"], "id": 1}}"#, hash.hex());
+
+	// locked account
+	let response = io.handle_request(&request).unwrap();
+	assert!(response.contains(r#""code":-32020"#));
+
+	accounts.unlock_account_permanently(address, "".into()).unwrap();
+	let response = io.handle_request(&request).unwrap();
+	let imported = miner.imported_transactions.lock()[0].clone();
+	assert_eq!(response, format!(r#"{{"jsonrpc":"2.0","result":"0x{}","id":1}}"#, imported.hash().hex()));
+	assert_eq!(imported.nonce, transaction.nonce);
+	assert_eq!(imported.gas_price, U256::from(2));
+	assert_eq!(imported.sender().unwrap(), address);
+}
+
+#[test]
+fn rpc_ethcore_set_transaction_rebroadcast() {
+	use std::time::Duration;
+	use ethcore::miner::RebroadcastPolicy;
+
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let accounts = accounts_provider();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
+
+	let transaction = Transaction {
+		nonce: U256::zero(),
+		gas_price: U256::from(1),
+		gas: U256::from(21000),
+		action: Action::Call(Address::from(1)),
+		value: U256::from(10),
+		data: vec![],
+	}.sign(&Secret::from(1));
+	let hash = transaction.hash();
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "ethcore_setTransactionRebroadcast", "params":["0x{}", 30, 3, 10], "id": 1}}"#, hash.hex());
+
+	// not queued
+	let response = io.handle_request(&request).unwrap();
+	assert!(response.contains(r#""code":-32602"#));
+
+	miner.pending_transactions.lock().insert(hash, transaction);
+	assert_eq!(io.handle_request(&request), Some(r#"{"jsonrpc":"2.0","result":true,"id":1}"#.to_owned()));
+	assert_eq!(miner.rebroadcast_policies.lock()[&hash], RebroadcastPolicy {
+		interval: Duration::from_secs(30),
+		backoff: 3,
+		max_attempts: 10,
+	});
+}
+
+#[test]
+fn rpc_ethcore_restart() {
+	use std::{thread, time};
+	use v1::ExitRequest;
+
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let accounts = accounts_provider();
+	let exit = exit_handle();
+	let io = IoHandler::new();
+	io.add_delegate(EthcoreSetClient::new(&client, &miner, &(network.clone() as Arc<ManageNetwork>), &accounts, &exit, &api_keys()).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_restart", "params":[0], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+	for _ in 0..100 {
+		if exit.request().is_some() {
+			break;
+		}
+		thread::sleep(time::Duration::from_millis(10));
+	}
+	assert_eq!(exit.request(), Some(ExitRequest::Restart));
+}
+
+#[test]
+fn rpc_ethcore_shutdown_requires_delay() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let accounts = accounts_provider();
+	let exit = exit_handle();
+	let io = IoHandler::new();
+	io.add_delegate(EthcoreSetClient::new(&client, &miner, &(network.clone() as Arc<ManageNetwork>), &accounts, &exit, &api_keys()).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_shutdown", "params":[], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+	assert_eq!(exit.request(), None);
+}
+
+#[test]
+fn rpc_ethcore_take_snapshot() {
+	use ethcore::client::EachBlockWith;
+
+	let miner = miner_service();
+	let client = client_service();
+	client.add_blocks(3, EachBlockWith::Nothing);
+	let network = network_service();
+	let accounts = accounts_provider();
+	let io = IoHandler::new();
+	io.add_delegate(ethcore_set_client(&client, &miner, &network, &accounts).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_takeSnapshot", "params":["latest"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":3,"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+	assert_eq!(*client.requested_snapshot.read(), Some(3));
+
+	// busy taking another one.
+	client.snapshot_status.write().creating = Some(3);
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":null},"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_ethcore_api_keys() {
+	let miner = miner_service();
+	let client = client_service();
+	let network = network_service();
+	let accounts = accounts_provider();
+	let keys = api_keys();
+	let io = IoHandler::new();
+	io.add_delegate(EthcoreSetClient::new(&client, &miner, &(network.clone() as Arc<ManageNetwork>), &accounts, &exit_handle(), &keys).to_delegate());
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_addApiKey", "params":[["eth"], "0x64"], "id": 1}"#;
+	io.handle_request(request).unwrap();
+	let key = keys.list()[0].0.clone();
+	assert_eq!(keys.authorize(&key, 1), Ok(vec!["eth".to_owned()]));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_apiKeys", "params":[], "id": 1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":[{{"key":"{}","apis":["eth"],"dailyQuota":"0x64","usedToday":"0x01"}}],"id":1}}"#, key);
+	assert_eq!(io.handle_request(request), Some(response));
+
+	let request = r#"{"jsonrpc": "2.0", "method": "ethcore_addApiKey", "params":[["personal"], null], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":null},"id":1}"#;
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+
+	let request = format!(r#"{{"jsonrpc": "2.0", "method": "ethcore_removeApiKey", "params":["{}"], "id": 1}}"#, key);
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+	assert_eq!(io.handle_request(&request), Some(response.to_owned()));
+	assert!(keys.list().is_empty());
+}
@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
-use ethsync::{ManageNetwork, NetworkConfiguration};
+use ethsync::{ManageNetwork, NetworkConfiguration, PeerInfo};
 use util;
 
 pub struct TestManageNetwork;
@@ -28,4 +28,15 @@ impl ManageNetwork for TestManageNetwork {
 	fn start_network(&self) {}
 	fn stop_network(&self) {}
 	fn network_config(&self) -> NetworkConfiguration { NetworkConfiguration::from(util::NetworkConfiguration::new_local()) }
+	fn connected_peers(&self) -> Vec<PeerInfo> {
+		vec![PeerInfo {
+			id: Some("1234".to_owned()),
+			client_version: "Parity/v1.3.0".to_owned(),
+			protocol_version: 4,
+			capabilities: vec!["eth/62".to_owned(), "eth/63".to_owned()],
+			remote_address: "127.0.0.1:30303".to_owned(),
+			ping_ms: Some(25),
+			originated: true,
+		}]
+	}
 }
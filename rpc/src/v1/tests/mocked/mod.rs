@@ -17,7 +17,9 @@
 //! RPC mocked tests. Most of these test that the RPC server is serializing and forwarding
 //! method calls properly.
 
+mod admin;
 mod eth;
+mod eth_pubsub;
 mod eth_signing;
 mod net;
 mod web3;
@@ -27,3 +29,4 @@ mod ethcore;
 mod ethcore_set;
 mod rpc;
 mod manage_network;
+mod traces;
@@ -18,6 +18,7 @@ use std::sync::Arc;
 use std::str::FromStr;
 use jsonrpc_core::IoHandler;
 use util::numbers::*;
+use util::sha3::Hashable;
 use ethcore::account_provider::AccountProvider;
 use v1::{PersonalClient, Personal};
 use v1::tests::helpers::TestMinerService;
@@ -192,3 +193,74 @@ fn sign_and_send_transaction() {
 
 	assert_eq!(tester.io.handle_request(request.as_ref()), Some(response));
 }
+
+#[test]
+fn send_transaction_is_alias_of_sign_and_send_transaction() {
+	let tester = setup(None);
+	let address = tester.accounts.new_account("password123").unwrap();
+	let request = r#"{
+		"jsonrpc": "2.0",
+		"method": "personal_sendTransaction",
+		"params": [{
+			"from": ""#.to_owned() + format!("0x{:?}", address).as_ref() + r#"",
+			"to": "0xd46e8dd67c5d32be8058bb8eb970870f07244567",
+			"gas": "0x76c0",
+			"gasPrice": "0x9184e72a000",
+			"value": "0x9184e72a"
+		}, "password321"],
+		"id": 1
+	}"#;
+
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32021,"message":"Account password is invalid or account does not exist.","data":"SStore(InvalidPassword)"},"id":1}"#;
+
+	assert_eq!(tester.io.handle_request(request.as_ref()), Some(response.into()));
+}
+
+#[test]
+fn sign_with_password() {
+	let tester = setup(None);
+	let address = tester.accounts.new_account("password123").unwrap();
+	let request = r#"{"jsonrpc": "2.0", "method": "personal_sign", "params": ["0x61626364", ""#.to_owned() + format!("0x{:?}", address).as_ref() + r#"", "password123"], "id": 1}"#;
+
+	let hash = "\x19Ethereum Signed Message:\n4abcd".sha3();
+	let signature = tester.accounts.sign_with_password(address, "password123".into(), hash).unwrap();
+	let response = r#"{"jsonrpc":"2.0","result":""#.to_owned() + format!("0x{:?}", signature).as_ref() + r#"","id":1}"#;
+
+	assert_eq!(tester.io.handle_request(request.as_ref()), Some(response));
+	// the account is not left unlocked
+	assert!(tester.accounts.sign(address, hash).is_err());
+}
+
+#[test]
+fn sign_with_invalid_password() {
+	let tester = setup(None);
+	let address = tester.accounts.new_account("password123").unwrap();
+	let request = r#"{"jsonrpc": "2.0", "method": "personal_sign", "params": ["0x61626364", ""#.to_owned() + format!("0x{:?}", address).as_ref() + r#"", "password321"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32021,"message":"Account password is invalid or account does not exist.","data":"SStore(InvalidPassword)"},"id":1}"#;
+
+	assert_eq!(tester.io.handle_request(request.as_ref()), Some(response.into()));
+}
+
+#[test]
+fn unlock_account_for_single_use_without_duration() {
+	let tester = setup(None);
+	let address = tester.accounts.new_account("password123").unwrap();
+	let request = r#"{"jsonrpc": "2.0", "method": "personal_unlockAccount", "params": [""#.to_owned() + format!("0x{:?}", address).as_ref() + r#"", "password123"], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+	assert_eq!(tester.io.handle_request(request.as_ref()), Some(response.into()));
+	assert!(tester.accounts.sign(address, H256::default()).is_ok());
+	assert!(tester.accounts.sign(address, H256::default()).is_err());
+}
+
+#[test]
+fn unlock_account_for_duration() {
+	let tester = setup(None);
+	let address = tester.accounts.new_account("password123").unwrap();
+	let request = r#"{"jsonrpc": "2.0", "method": "personal_unlockAccount", "params": [""#.to_owned() + format!("0x{:?}", address).as_ref() + r#"", "password123", 300], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":true,"id":1}"#;
+
+	assert_eq!(tester.io.handle_request(request.as_ref()), Some(response.into()));
+	assert!(tester.accounts.sign(address, H256::default()).is_ok());
+	assert!(tester.accounts.sign(address, H256::default()).is_ok());
+}
@@ -0,0 +1,165 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+use jsonrpc_core::IoHandler;
+use util::{Address, H256, U256};
+use ethcore::client::{TestBlockChainClient, Executed};
+use ethcore::trace::{LocalizedTrace, VMTrace, VMOperation, VMExecutedOperation};
+use ethcore::trace::trace::{Action, Call, CallResult, Res};
+use v1::{Traces, TracesClient};
+use v1::tests::helpers::TestMinerService;
+
+fn io() -> IoHandler {
+	io_with_client(Arc::new(TestBlockChainClient::new()))
+}
+
+fn io_with_client(client: Arc<TestBlockChainClient>) -> IoHandler {
+	*client.traces.write() = Some(vec![LocalizedTrace {
+		action: Action::Call(Call {
+			from: Address::from(1),
+			to: Address::from(2),
+			value: U256::from(3),
+			gas: U256::from(4),
+			input: vec![0x12],
+		}),
+		result: Res::Call(CallResult {
+			gas_used: U256::from(5),
+			output: vec![0x34],
+		}),
+		subtraces: 0,
+		trace_address: vec![0],
+		transaction_number: Some(1),
+		transaction_hash: Some(H256::from(6)),
+		block_number: 7,
+		block_hash: H256::from(8),
+	}]);
+	let miner = Arc::new(TestMinerService::default());
+	let traces = TracesClient::new(&client, &miner, U256::from(50_000_000));
+	let io = IoHandler::new();
+	io.add_delegate(traces.to_delegate());
+	io
+}
+
+const TRACES: &'static str = r#"[{"action":{"call":{"from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","value":"0x03","gas":"0x04","input":"0x12"}},"result":{"call":{"gasUsed":"0x05","output":"0x34"}},"traceAddress":["0x00"],"subtraces":"0x00","transactionPosition":"0x01","transactionHash":"0x0000000000000000000000000000000000000000000000000000000000000006","blockNumber":"0x07","blockHash":"0x0000000000000000000000000000000000000000000000000000000000000008"}]"#;
+
+#[test]
+fn rpc_trace_block() {
+	let io = io();
+
+	let request = r#"{"jsonrpc":"2.0","method":"trace_block","params":["0x07"],"id":1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":{},"id":1}}"#, TRACES);
+
+	assert_eq!(io.handle_request(request), Some(response));
+}
+
+#[test]
+fn rpc_trace_transaction() {
+	let io = io();
+
+	let request = r#"{"jsonrpc":"2.0","method":"trace_transaction","params":["0x0000000000000000000000000000000000000000000000000000000000000006"],"id":1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":{},"id":1}}"#, TRACES);
+
+	assert_eq!(io.handle_request(request), Some(response));
+}
+
+#[test]
+fn rpc_trace_transaction_invalid_params() {
+	let io = io();
+
+	let request = r#"{"jsonrpc":"2.0","method":"trace_transaction","params":[],"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","error":{"code":-32602,"message":"Invalid params","data":null},"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_trace_replay_transaction() {
+	let client = Arc::new(TestBlockChainClient::new());
+	client.set_execution_result(Executed {
+		gas: U256::zero(),
+		gas_used: U256::from(3),
+		refunded: U256::zero(),
+		cumulative_gas_used: U256::from(3),
+		logs: vec![],
+		contracts_created: vec![],
+		output: vec![0x12],
+		trace: None,
+		vm_trace: Some(VMTrace {
+			parent_step: 0,
+			code: vec![0x60, 0x01],
+			operations: vec![VMOperation {
+				pc: 0,
+				instruction: 0x60,
+				gas_cost: U256::from(3),
+				executed: Some(VMExecutedOperation {
+					gas_used: U256::from(3),
+					stack_push: vec![U256::from(1)],
+					mem_diff: None,
+					store_diff: None,
+				}),
+			}],
+			subs: vec![],
+		}),
+		state_diff: None,
+		exception: false,
+	});
+	let io = io_with_client(client);
+
+	let request = r#"{"jsonrpc":"2.0","method":"trace_replayTransaction","params":["0x0000000000000000000000000000000000000000000000000000000000000006", ["vmTrace"]],"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":{"output":"0x12","vmTrace":{"code":[96,1],"ops":[{"pc":0,"op":96,"cost":3,"ex":{"used":3,"push":["0x01"],"mem":null,"store":null},"sub":null}]}},"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_trace_replay_unknown_transaction() {
+	let io = io();
+
+	let request = r#"{"jsonrpc":"2.0","method":"trace_replayTransaction","params":["0x0000000000000000000000000000000000000000000000000000000000000006", ["vmTrace"]],"id":1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":null,"id":1}"#;
+
+	assert_eq!(io.handle_request(request), Some(response.to_owned()));
+}
+
+#[test]
+fn rpc_trace_replay_block_transactions() {
+	use ethcore::client::{BlockChainClient, BlockID, EachBlockWith};
+	use ethcore::views::BlockView;
+
+	let client = Arc::new(TestBlockChainClient::new());
+	client.add_blocks(1, EachBlockWith::Transaction);
+	client.set_execution_result(Executed {
+		gas: U256::zero(),
+		gas_used: U256::from(3),
+		refunded: U256::zero(),
+		cumulative_gas_used: U256::from(3),
+		logs: vec![],
+		contracts_created: vec![],
+		output: vec![0x12],
+		trace: None,
+		vm_trace: None,
+		state_diff: None,
+		exception: false,
+	});
+	let hash = BlockView::new(&client.block(BlockID::Number(1)).unwrap()).transaction_hashes()[0].clone();
+	let io = io_with_client(client);
+
+	let request = r#"{"jsonrpc":"2.0","method":"trace_replayBlockTransactions","params":["0x1", ["stateDiff"]],"id":1}"#;
+	let response = format!(r#"{{"jsonrpc":"2.0","result":[{{"output":"0x12","transactionHash":"0x{}"}}],"id":1}}"#, hash.hex());
+
+	assert_eq!(io.handle_request(request), Some(response));
+}
@@ -20,7 +20,7 @@ use v1::{Web3, Web3Client};
 
 #[test]
 fn rpc_web3_version() {
-	let web3 = Web3Client::new().to_delegate();
+	let web3 = Web3Client::new("").to_delegate();
 	let io = IoHandler::new();
 	io.add_delegate(web3);
 
@@ -34,7 +34,7 @@ fn rpc_web3_version() {
 
 #[test]
 fn rpc_web3_sha3() {
-	let web3 = Web3Client::new().to_delegate();
+	let web3 = Web3Client::new("").to_delegate();
 	let io = IoHandler::new();
 	io.add_delegate(web3);
 
@@ -46,7 +46,7 @@ fn rpc_web3_sha3() {
 
 #[test]
 fn rpc_web3_sha3_wiki() {
-	let web3 = Web3Client::new().to_delegate();
+	let web3 = Web3Client::new("").to_delegate();
 	let io = IoHandler::new();
 	io.add_delegate(web3);
 
@@ -55,3 +55,17 @@ fn rpc_web3_sha3_wiki() {
 
 	assert_eq!(io.handle_request(request), Some(response.to_owned()));
 }
+
+#[test]
+fn rpc_web3_version_with_identity() {
+	let web3 = Web3Client::new("my node").to_delegate();
+	let io = IoHandler::new();
+	io.add_delegate(web3);
+
+	let v = version().to_owned().replace("Parity/", "Parity/mynode/");
+
+	let request = r#"{"jsonrpc": "2.0", "method": "web3_clientVersion", "params": [], "id": 1}"#;
+	let response = r#"{"jsonrpc":"2.0","result":"VER","id":1}"#.to_owned().replace("VER", v.as_ref());
+
+	assert_eq!(io.handle_request(request), Some(response));
+}
@@ -0,0 +1,44 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Admin rpc interface for managing peers at runtime.
+use std::sync::Arc;
+use jsonrpc_core::*;
+
+/// Admin rpc interface for managing peers at runtime.
+pub trait Admin: Sized + Send + Sync + 'static {
+	/// Adds a reserved peer given its enode URL and connects to it.
+	fn add_peer(&self, _: Params) -> Result<Value, Error>;
+
+	/// Removes the reservation of a peer given its enode URL.
+	fn remove_peer(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns details of all connected peers.
+	fn peers(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns enode URL and listening address of the local node.
+	fn node_info(&self, _: Params) -> Result<Value, Error>;
+
+	/// Should be used to convert object to io delegate.
+	fn to_delegate(self) -> IoDelegate<Self> {
+		let mut delegate = IoDelegate::new(Arc::new(self));
+		delegate.add_method("admin_addPeer", Admin::add_peer);
+		delegate.add_method("admin_removePeer", Admin::remove_peer);
+		delegate.add_method("admin_peers", Admin::peers);
+		delegate.add_method("admin_nodeInfo", Admin::node_info);
+		delegate
+	}
+}
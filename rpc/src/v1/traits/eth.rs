@@ -17,6 +17,7 @@
 //! Eth rpc interface.
 use std::sync::Arc;
 use jsonrpc_core::*;
+use v1::helpers::{SessionId, Sink};
 
 /// Eth rpc interface.
 pub trait Eth: Sized + Send + Sync + 'static {
@@ -77,7 +78,7 @@ pub trait Eth: Sized + Send + Sync + 'static {
 	/// Sends signed transaction.
 	fn send_raw_transaction(&self, _: Params) -> Result<Value, Error>;
 
-	/// Call contract.
+	/// Call contract, optionally overriding balances, code and storage of some accounts for the duration of the call.
 	fn call(&self, _: Params) -> Result<Value, Error>;
 
 	/// Estimate gas needed for execution of given contract.
@@ -86,6 +87,9 @@ pub trait Eth: Sized + Send + Sync + 'static {
 	/// Get transaction by its hash.
 	fn transaction_by_hash(&self, _: Params) -> Result<Value, Error>;
 
+	/// Get transaction sent by given address with given nonce, either mined or pending.
+	fn transaction_by_sender_and_nonce(&self, _: Params) -> Result<Value, Error>;
+
 	/// Returns transaction at given block hash and index.
 	fn transaction_by_block_hash_and_index(&self, _: Params) -> Result<Value, Error>;
 
@@ -150,6 +154,7 @@ pub trait Eth: Sized + Send + Sync + 'static {
 		delegate.add_method("eth_getBlockByHash", Eth::block_by_hash);
 		delegate.add_method("eth_getBlockByNumber", Eth::block_by_number);
 		delegate.add_method("eth_getTransactionByHash", Eth::transaction_by_hash);
+		delegate.add_method("eth_getTransactionBySenderAndNonce", Eth::transaction_by_sender_and_nonce);
 		delegate.add_method("eth_getTransactionByBlockHashAndIndex", Eth::transaction_by_block_hash_and_index);
 		delegate.add_method("eth_getTransactionByBlockNumberAndIndex", Eth::transaction_by_block_number_and_index);
 		delegate.add_method("eth_getTransactionReceipt", Eth::transaction_receipt);
@@ -201,6 +206,22 @@ pub trait EthFilter: Sized + Send + Sync + 'static {
 	}
 }
 
+/// Eth pub-sub rpc api.
+///
+/// Notifications are pushed to the session's sink, so this interface is not
+/// an `IoDelegate` and is only served by transports able to send unsolicited
+/// messages (see `PubSubSession`).
+pub trait EthPubSub: Send + Sync + 'static {
+	/// Subscribes given session to a notification stream; returns subscription id.
+	fn subscribe(&self, _: Params, session: SessionId, sink: Sink) -> Result<Value, Error>;
+
+	/// Cancels a subscription created by the same session.
+	fn unsubscribe(&self, _: Params, session: SessionId) -> Result<Value, Error>;
+
+	/// Drops all subscriptions of a closed session.
+	fn close_session(&self, session: SessionId);
+}
+
 /// Signing methods implementation relying on unlocked accounts.
 pub trait EthSigning: Sized + Send + Sync + 'static {
 	/// Signs the data with given address signature.
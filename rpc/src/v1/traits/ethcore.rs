@@ -67,6 +67,77 @@ pub trait Ethcore: Sized + Send + Sync + 'static {
 	/// Returns error when signer is disabled
 	fn unsigned_transactions_count(&self, _: Params) -> Result<Value, Error>;
 
+	/// Returns estimated number of keys, live data size, table files size and compaction levels of each database.
+	fn db_stats(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns the pending block assembled by the miner, ready to be sealed by an external process.
+	fn block_template(&self, _: Params) -> Result<Value, Error>;
+
+	/// Imports a block built from a template returned by `ethcore_getBlockTemplate` with the seal filled in.
+	fn submit_block(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns a proof of a range of a contract's storage at given block.
+	fn storage_range_proof(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns number of blocks, included uncles, fees and rewards of each author of a range of blocks.
+	fn miner_stats_range(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns number of calls and cumulative gas used of each contract called in a range of blocks,
+	/// the one using the most gas first. Requires tracing. At most 1000 blocks are profiled at once.
+	fn gas_profile(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns number of executions and gas used by instructions of each contract since the node started,
+	/// the contract using the most gas first. Requires `--profile-evm`.
+	fn evm_profile(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns progress of snapshot creation and restoration and the latest complete snapshot.
+	fn snapshot_status(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns a single log given block, index of the transaction within the block and index of the log within the transaction.
+	fn log(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns the number of connected peers by protocol and client version.
+	fn peer_versions(&self, _: Params) -> Result<Value, Error>;
+
+	/// Pins the latest block, so that `ethcore_pinned*` calls given the returned handle
+	/// all see its state. Handles are random, so a pin is private to the session which
+	/// created it. Pins expire when unused for a minute.
+	fn pin_block(&self, _: Params) -> Result<Value, Error>;
+
+	/// Releases a pin.
+	fn unpin_block(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns balance of an account at a pinned block.
+	fn pinned_balance(&self, _: Params) -> Result<Value, Error>;
+
+	/// Makes a call on the state of a pinned block.
+	fn pinned_call(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns logs matching given filter up to a pinned block.
+	fn pinned_logs(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns values of many storage slots of an account at given block.
+	fn storage_at_many(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns balances, nonces and code of many accounts at given block.
+	fn accounts(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns enode URL, chain, protocols, ports and data directories of the node.
+	fn node_info(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns the best block, block queue, cache, sync and peer status the informant reports.
+	fn node_status(&self, _: Params) -> Result<Value, Error>;
+
+	/// Opens a stream of the blocks in given range, to be read with `ethcore_streamChunk`.
+	fn stream_blocks(&self, _: Params) -> Result<Value, Error>;
+
+	/// Returns the chunk following the acknowledged one, or the acknowledged one again
+	/// if the one following it was not received.
+	fn stream_chunk(&self, _: Params) -> Result<Value, Error>;
+
+	/// Closes a stream of blocks.
+	fn close_stream(&self, _: Params) -> Result<Value, Error>;
+
 	/// Should be used to convert object to io delegate.
 	fn to_delegate(self) -> IoDelegate<Self> {
 		let mut delegate = IoDelegate::new(Arc::new(self));
@@ -86,6 +157,28 @@ pub trait Ethcore: Sized + Send + Sync + 'static {
 		delegate.add_method("ethcore_defaultExtraData", Ethcore::default_extra_data);
 		delegate.add_method("ethcore_gasPriceStatistics", Ethcore::gas_price_statistics);
 		delegate.add_method("ethcore_unsignedTransactionsCount", Ethcore::unsigned_transactions_count);
+		delegate.add_method("ethcore_dbStats", Ethcore::db_stats);
+		delegate.add_method("ethcore_getBlockTemplate", Ethcore::block_template);
+		delegate.add_method("ethcore_submitBlock", Ethcore::submit_block);
+		delegate.add_method("ethcore_getStorageRangeProof", Ethcore::storage_range_proof);
+		delegate.add_method("ethcore_minerStatsRange", Ethcore::miner_stats_range);
+		delegate.add_method("ethcore_gasProfile", Ethcore::gas_profile);
+		delegate.add_method("ethcore_evmProfile", Ethcore::evm_profile);
+		delegate.add_method("ethcore_snapshotStatus", Ethcore::snapshot_status);
+		delegate.add_method("ethcore_getLog", Ethcore::log);
+		delegate.add_method("ethcore_peerVersions", Ethcore::peer_versions);
+		delegate.add_method("ethcore_pinBlock", Ethcore::pin_block);
+		delegate.add_method("ethcore_unpinBlock", Ethcore::unpin_block);
+		delegate.add_method("ethcore_pinnedBalance", Ethcore::pinned_balance);
+		delegate.add_method("ethcore_pinnedCall", Ethcore::pinned_call);
+		delegate.add_method("ethcore_pinnedLogs", Ethcore::pinned_logs);
+		delegate.add_method("ethcore_getStorageAtMany", Ethcore::storage_at_many);
+		delegate.add_method("ethcore_getAccounts", Ethcore::accounts);
+		delegate.add_method("ethcore_nodeInfo", Ethcore::node_info);
+		delegate.add_method("ethcore_nodeStatus", Ethcore::node_status);
+		delegate.add_method("ethcore_streamBlocks", Ethcore::stream_blocks);
+		delegate.add_method("ethcore_streamChunk", Ethcore::stream_chunk);
+		delegate.add_method("ethcore_closeStream", Ethcore::close_stream);
 
 		delegate
 	}
@@ -43,6 +43,10 @@ pub trait EthcoreSet: Sized + Send + Sync + 'static {
 	/// Sets the maximum amount of gas a single transaction may consume.
 	fn set_tx_gas_limit(&self, _: Params) -> Result<Value, Error>;
 
+	/// Sets the re-broadcast policy of a queued local transaction:
+	/// interval in seconds, backoff factor and maximal number of attempts.
+	fn set_transaction_rebroadcast(&self, _: Params) -> Result<Value, Error>;
+
 	/// Add a reserved peer.
 	fn add_reserved_peer(&self, _: Params) -> Result<Value, Error>;
 
@@ -61,6 +65,37 @@ pub trait EthcoreSet: Sized + Send + Sync + 'static {
 	/// Stop the network.
 	fn stop_network(&self, _: Params) -> Result<Value, Error>;
 
+	/// Returns raw value stored in one of the databases.
+	/// Exposed only with this API, since it gives access to the raw database.
+	fn db_get(&self, _: Params) -> Result<Value, Error>;
+
+	/// Removes transaction from the queue. Returns removed transaction or null if it was not found.
+	fn remove_transaction(&self, _: Params) -> Result<Value, Error>;
+
+	/// Replaces queued transaction with a copy paying new gas price, signed by the (unlocked) sender account.
+	/// Returns hash of the replacement.
+	fn reprioritize_transaction(&self, _: Params) -> Result<Value, Error>;
+
+	/// Starts taking a snapshot at given block in background. Returns number of the block,
+	/// or an error if the block is unknown or a snapshot is being taken already.
+	fn take_snapshot(&self, _: Params) -> Result<Value, Error>;
+
+	/// Gracefully shuts the node down after given number of seconds.
+	fn shutdown(&self, _: Params) -> Result<Value, Error>;
+
+	/// Gracefully shuts the node down after given number of seconds and starts it again with the same arguments.
+	fn restart(&self, _: Params) -> Result<Value, Error>;
+
+	/// Creates an API key granting access to given namespaces, optionally limited to a number of calls a day.
+	/// Returns the key.
+	fn add_api_key(&self, _: Params) -> Result<Value, Error>;
+
+	/// Removes an API key. Returns `false` if the key is not known.
+	fn remove_api_key(&self, _: Params) -> Result<Value, Error>;
+
+	/// Lists API keys along with their namespaces and usage today.
+	fn api_keys(&self, _: Params) -> Result<Value, Error>;
+
 	/// Should be used to convert object to io delegate.
 	fn to_delegate(self) -> IoDelegate<Self> {
 		let mut delegate = IoDelegate::new(Arc::new(self));
@@ -75,6 +110,16 @@ pub trait EthcoreSet: Sized + Send + Sync + 'static {
 		delegate.add_method("ethcore_removeReservedPeer", EthcoreSet::remove_reserved_peer);
 		delegate.add_method("ethcore_dropNonReservedPeers", EthcoreSet::drop_non_reserved_peers);
 		delegate.add_method("ethcore_acceptNonReservedPeers", EthcoreSet::accept_non_reserved_peers);
+		delegate.add_method("ethcore_dbGet", EthcoreSet::db_get);
+		delegate.add_method("ethcore_removeTransaction", EthcoreSet::remove_transaction);
+		delegate.add_method("ethcore_reprioritizeTransaction", EthcoreSet::reprioritize_transaction);
+		delegate.add_method("ethcore_setTransactionRebroadcast", EthcoreSet::set_transaction_rebroadcast);
+		delegate.add_method("ethcore_takeSnapshot", EthcoreSet::take_snapshot);
+		delegate.add_method("ethcore_shutdown", EthcoreSet::shutdown);
+		delegate.add_method("ethcore_restart", EthcoreSet::restart);
+		delegate.add_method("ethcore_addApiKey", EthcoreSet::add_api_key);
+		delegate.add_method("ethcore_removeApiKey", EthcoreSet::remove_api_key);
+		delegate.add_method("ethcore_apiKeys", EthcoreSet::api_keys);
 
 		delegate
 	}
@@ -24,14 +24,16 @@ pub mod ethcore;
 pub mod ethcore_set;
 pub mod traces;
 pub mod rpc;
+pub mod admin;
 
 pub use self::web3::Web3;
-pub use self::eth::{Eth, EthFilter, EthSigning};
+pub use self::eth::{Eth, EthFilter, EthPubSub, EthSigning};
 pub use self::net::Net;
 pub use self::personal::{Personal, PersonalSigner};
 pub use self::ethcore::Ethcore;
 pub use self::ethcore_set::EthcoreSet;
 pub use self::traces::Traces;
 pub use self::rpc::Rpc;
+pub use self::admin::Admin;
 
 
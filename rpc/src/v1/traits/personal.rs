@@ -27,12 +27,16 @@ pub trait Personal: Sized + Send + Sync + 'static {
 	/// Creates new account (it becomes new current unlocked account)
 	fn new_account(&self, _: Params) -> Result<Value, Error>;
 
-	/// Unlocks specified account for use (can only be one unlocked account at one moment)
+	/// Unlocks specified account for a single use, or for the given number of seconds.
 	fn unlock_account(&self, _: Params) -> Result<Value, Error>;
 
 	/// Sends transaction and signs it in single call. The account is not unlocked in such case.
 	fn sign_and_send_transaction(&self, _: Params) -> Result<Value, Error>;
 
+	/// Signs `keccak256("\x19Ethereum Signed Message:\n" + len(data) + data)` with given account
+	/// and password. The account is not unlocked in such case.
+	fn sign(&self, _: Params) -> Result<Value, Error>;
+
 	/// Returns `true` if Trusted Signer is enabled, `false` otherwise.
 	fn signer_enabled(&self, _: Params) -> Result<Value, Error>;
 
@@ -44,6 +48,8 @@ pub trait Personal: Sized + Send + Sync + 'static {
 		delegate.add_method("personal_newAccount", Personal::new_account);
 		delegate.add_method("personal_unlockAccount", Personal::unlock_account);
 		delegate.add_method("personal_signAndSendTransaction", Personal::sign_and_send_transaction);
+		delegate.add_method("personal_sendTransaction", Personal::sign_and_send_transaction);
+		delegate.add_method("personal_sign", Personal::sign);
 		delegate
 	}
 }
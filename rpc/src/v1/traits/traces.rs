@@ -35,6 +35,13 @@ pub trait Traces: Sized + Send + Sync + 'static {
 	/// Executes the given call and returns a number of possible traces for it.
 	fn call(&self, _: Params) -> Result<Value, Error>;
 
+	/// Re-executes the transaction with given hash and returns a number of possible traces for it,
+	/// including opcode-level VM traces which are not stored.
+	fn replay_transaction(&self, _: Params) -> Result<Value, Error>;
+
+	/// Executes all transactions of a block again, returning the output, traces and state diff of each.
+	fn replay_block_transactions(&self, _: Params) -> Result<Value, Error>;
+
 	/// Should be used to convert object to io delegate.
 	fn to_delegate(self) -> IoDelegate<Self> {
 		let mut delegate = IoDelegate::new(Arc::new(self));
@@ -43,6 +50,8 @@ pub trait Traces: Sized + Send + Sync + 'static {
 		delegate.add_method("trace_transaction", Traces::transaction_traces);
 		delegate.add_method("trace_block", Traces::block_traces);
 		delegate.add_method("trace_call", Traces::call);
+		delegate.add_method("trace_replayTransaction", Traces::replay_transaction);
+		delegate.add_method("trace_replayBlockTransactions", Traces::replay_block_transactions);
 
 		delegate
 	}
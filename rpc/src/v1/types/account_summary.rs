@@ -0,0 +1,57 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::client::AccountSummary as EthAccountSummary;
+use v1::types::{Bytes, U256};
+
+/// Balance, nonce and code of an account
+#[derive(Debug, Serialize, PartialEq)]
+pub struct AccountSummary {
+	/// Balance
+	pub balance: U256,
+	/// Nonce
+	pub nonce: U256,
+	/// Code
+	pub code: Bytes,
+}
+
+impl From<EthAccountSummary> for AccountSummary {
+	fn from(s: EthAccountSummary) -> Self {
+		AccountSummary {
+			balance: s.balance.into(),
+			nonce: s.nonce.into(),
+			code: s.code.into(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::{Bytes, U256};
+	use super::AccountSummary;
+
+	#[test]
+	fn test_serialize_account_summary() {
+		let s = AccountSummary {
+			balance: U256::from(0x1000),
+			nonce: U256::from(1),
+			code: Bytes::new(vec![0x60, 0x00]),
+		};
+		let serialized = serde_json::to_string(&s).unwrap();
+		assert_eq!(serialized, r#"{"balance":"0x1000","nonce":"0x01","code":"0x6000"}"#);
+	}
+}
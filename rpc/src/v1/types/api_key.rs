@@ -0,0 +1,50 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use v1::types::U256;
+
+/// API key with the namespaces it grants access to and its usage.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ApiKey {
+	/// The key
+	pub key: String,
+	/// Namespaces the key grants access to
+	pub apis: Vec<String>,
+	/// Maximal number of calls per day
+	#[serde(rename="dailyQuota")]
+	pub daily_quota: Option<U256>,
+	/// Number of calls made today
+	#[serde(rename="usedToday")]
+	pub used_today: U256,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::ApiKey;
+
+	#[test]
+	fn api_key_serialization() {
+		let key = ApiKey {
+			key: "abc".into(),
+			apis: vec!["eth".into()],
+			daily_quota: Some(1000.into()),
+			used_today: 10.into(),
+		};
+		let serialized = serde_json::to_string(&key).unwrap();
+		assert_eq!(serialized, r#"{"key":"abc","apis":["eth"],"dailyQuota":"0x03e8","usedToday":"0x0a"}"#);
+	}
+}
@@ -0,0 +1,46 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use v1::types::{Bytes, U256};
+
+/// Consecutive blocks sent over a stream opened with `ethcore_streamBlocks`.
+#[derive(Debug, Serialize, PartialEq, Clone)]
+pub struct BlockChunk {
+	/// Sequence number of the chunk, starting at 1
+	pub seq: U256,
+	/// RLP of the blocks, in ascending order
+	pub blocks: Vec<Bytes>,
+	/// Whether the chunk ends the stream
+	pub done: bool,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::Bytes;
+	use super::BlockChunk;
+
+	#[test]
+	fn block_chunk_serialization() {
+		let chunk = BlockChunk {
+			seq: 1.into(),
+			blocks: vec![Bytes::new(vec![0xc0])],
+			done: true,
+		};
+		let serialized = serde_json::to_string(&chunk).unwrap();
+		assert_eq!(serialized, r#"{"seq":"0x01","blocks":["0xc0"],"done":true}"#);
+	}
+}
@@ -0,0 +1,81 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::block::{ClosedBlock, IsBlock};
+use ethcore::header::Seal;
+use v1::types::{Bytes, H160, H256, U256};
+
+/// Fully assembled pending block waiting to be sealed.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct BlockTemplate {
+	/// Hash of the block without the seal
+	pub hash: H256,
+	/// Hash of the parent block
+	#[serde(rename="parentHash")]
+	pub parent_hash: H256,
+	/// Block number
+	pub number: U256,
+	/// Block author
+	pub author: H160,
+	/// Block timestamp
+	pub timestamp: U256,
+	/// Block difficulty
+	pub difficulty: U256,
+	/// Block gas limit
+	#[serde(rename="gasLimit")]
+	pub gas_limit: U256,
+	/// RLP of the whole block without the seal fields
+	pub rlp: Bytes,
+}
+
+impl<'a> From<&'a ClosedBlock> for BlockTemplate {
+	fn from(b: &'a ClosedBlock) -> Self {
+		let header = b.header();
+		BlockTemplate {
+			hash: b.hash().into(),
+			parent_hash: header.parent_hash().clone().into(),
+			number: header.number().into(),
+			author: header.author().clone().into(),
+			timestamp: header.timestamp().into(),
+			difficulty: header.difficulty().clone().into(),
+			gas_limit: header.gas_limit().clone().into(),
+			rlp: Bytes::new(b.base().rlp_bytes(Seal::Without)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::{Bytes, H160, H256, U256};
+	use super::BlockTemplate;
+
+	#[test]
+	fn test_serialize_block_template() {
+		let t = BlockTemplate {
+			hash: H256::default(),
+			parent_hash: H256::default(),
+			number: U256::from(1),
+			author: H160::default(),
+			timestamp: U256::from(2),
+			difficulty: U256::from(3),
+			gas_limit: U256::from(4),
+			rlp: Bytes::new(vec![0xc0]),
+		};
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000000","parentHash":"0x0000000000000000000000000000000000000000000000000000000000000000","number":"0x01","author":"0x0000000000000000000000000000000000000000","timestamp":"0x02","difficulty":"0x03","gasLimit":"0x04","rlp":"0xc0"}"#);
+	}
+}
@@ -0,0 +1,71 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::client::DatabaseStats as EthDatabaseStats;
+
+/// Database statistics
+#[derive(Debug, Serialize, PartialEq)]
+pub struct DatabaseStats {
+	/// Database name
+	pub name: String,
+	/// Estimated number of keys
+	pub keys: u64,
+	/// Estimated size of live data in bytes
+	#[serde(rename="liveDataBytes")]
+	pub live_data_bytes: u64,
+	/// Total size of table files in bytes
+	#[serde(rename="sstFilesBytes")]
+	pub sst_files_bytes: u64,
+	/// Number of table files at each compaction level, starting at level 0
+	#[serde(rename="filesPerLevel")]
+	pub files_per_level: Vec<u64>,
+	/// Whether a compaction is pending
+	#[serde(rename="compactionPending")]
+	pub compaction_pending: bool,
+}
+
+impl From<EthDatabaseStats> for DatabaseStats {
+	fn from(s: EthDatabaseStats) -> Self {
+		DatabaseStats {
+			name: s.column.name().to_owned(),
+			keys: s.keys,
+			live_data_bytes: s.live_data_bytes,
+			sst_files_bytes: s.sst_files_bytes,
+			files_per_level: s.files_per_level,
+			compaction_pending: s.compaction_pending,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::DatabaseStats;
+
+	#[test]
+	fn test_serialize_database_stats() {
+		let s = DatabaseStats {
+			name: "blocks".to_owned(),
+			keys: 2,
+			live_data_bytes: 1024,
+			sst_files_bytes: 2048,
+			files_per_level: vec![2, 1],
+			compaction_pending: false,
+		};
+		let serialized = serde_json::to_string(&s).unwrap();
+		assert_eq!(serialized, r#"{"name":"blocks","keys":2,"liveDataBytes":1024,"sstFilesBytes":2048,"filesPerLevel":[2,1],"compactionPending":false}"#);
+	}
+}
@@ -0,0 +1,62 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::client::{ContractProfile as EthContractProfile, InstructionProfile as EthInstructionProfile};
+use v1::types::{H160, U256};
+
+/// Number of executions and gas used by a single instruction
+#[derive(Debug, Serialize, PartialEq)]
+pub struct InstructionProfile {
+	/// Name of the instruction
+	pub name: String,
+	/// Number of executions
+	pub count: u64,
+	/// Gas used, including the gas used by the callee for calls and creations
+	#[serde(rename="gasUsed")]
+	pub gas_used: U256,
+}
+
+impl From<EthInstructionProfile> for InstructionProfile {
+	fn from(i: EthInstructionProfile) -> Self {
+		InstructionProfile {
+			name: i.name,
+			count: i.count,
+			gas_used: i.gas.into(),
+		}
+	}
+}
+
+/// Instructions executed in the code of a single contract
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ContractProfile {
+	/// Address of the contract
+	pub address: H160,
+	/// Gas used by all the instructions
+	#[serde(rename="gasUsed")]
+	pub gas_used: U256,
+	/// Executed instructions, the one using the most gas first
+	pub instructions: Vec<InstructionProfile>,
+}
+
+impl From<EthContractProfile> for ContractProfile {
+	fn from(c: EthContractProfile) -> Self {
+		ContractProfile {
+			address: c.address.into(),
+			gas_used: c.gas.into(),
+			instructions: c.instructions.into_iter().map(Into::into).collect(),
+		}
+	}
+}
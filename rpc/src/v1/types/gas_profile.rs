@@ -0,0 +1,63 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::trace::ContractGas as EthContractGas;
+use util::Address;
+use v1::types::{H160, U256};
+
+/// Calls made to a single contract and the gas they used
+#[derive(Debug, Serialize, PartialEq)]
+pub struct ContractGas {
+	/// Address of the contract
+	pub address: H160,
+	/// Number of calls, including the one creating the contract
+	pub calls: usize,
+	/// Number of failed calls
+	pub failed: usize,
+	/// Cumulative gas used by the calls, including nested calls
+	#[serde(rename="gasUsed")]
+	pub gas_used: U256,
+}
+
+impl From<(Address, EthContractGas)> for ContractGas {
+	fn from((address, gas): (Address, EthContractGas)) -> Self {
+		ContractGas {
+			address: address.into(),
+			calls: gas.calls,
+			failed: gas.failed,
+			gas_used: gas.gas_used.into(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::{H160, U256};
+	use super::ContractGas;
+
+	#[test]
+	fn test_serialize_contract_gas() {
+		let c = ContractGas {
+			address: H160::default(),
+			calls: 3,
+			failed: 1,
+			gas_used: U256::from(0x5208),
+		};
+		let serialized = serde_json::to_string(&c).unwrap();
+		assert_eq!(serialized, r#"{"address":"0x0000000000000000000000000000000000000000","calls":3,"failed":1,"gasUsed":"0x5208"}"#);
+	}
+}
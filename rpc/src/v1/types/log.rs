@@ -62,6 +62,16 @@ impl From<LocalizedLogEntry> for Log {
 	}
 }
 
+impl Log {
+	/// Creates a log of a block retracted by a chain reorganisation.
+	pub fn removed(e: LocalizedLogEntry) -> Log {
+		Log {
+			log_type: "removed".to_owned(),
+			..Log::from(e)
+		}
+	}
+}
+
 impl From<LogEntry> for Log {
 	fn from(e: LogEntry) -> Log {
 		Log {
@@ -0,0 +1,65 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::client::MinerStats as EthMinerStats;
+use v1::types::{H160, U256};
+
+/// Summary of the blocks authored by a single address
+#[derive(Debug, Serialize, PartialEq)]
+pub struct MinerStats {
+	/// Author of the blocks
+	pub author: H160,
+	/// Number of blocks authored
+	pub blocks: u64,
+	/// Number of uncles included in those blocks
+	pub uncles: u64,
+	/// Transaction fees paid in those blocks
+	pub fees: U256,
+	/// Block and uncle inclusion rewards, excluding fees
+	pub reward: U256,
+}
+
+impl From<EthMinerStats> for MinerStats {
+	fn from(s: EthMinerStats) -> Self {
+		MinerStats {
+			author: s.author.into(),
+			blocks: s.blocks,
+			uncles: s.uncles,
+			fees: s.fees.into(),
+			reward: s.reward.into(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::{H160, U256};
+	use super::MinerStats;
+
+	#[test]
+	fn test_serialize_miner_stats() {
+		let s = MinerStats {
+			author: H160::default(),
+			blocks: 3,
+			uncles: 1,
+			fees: U256::from(0x1000),
+			reward: U256::from(0x4563918244f40000u64),
+		};
+		let serialized = serde_json::to_string(&s).unwrap();
+		assert_eq!(serialized, r#"{"author":"0x0000000000000000000000000000000000000000","blocks":3,"uncles":1,"fees":"0x1000","reward":"0x4563918244f40000"}"#);
+	}
+}
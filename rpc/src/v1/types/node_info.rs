@@ -0,0 +1,83 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use v1::types::H256;
+
+/// Information about the node
+#[derive(Debug, Serialize, PartialEq)]
+pub struct NodeInfo {
+	/// Node name
+	pub name: String,
+	/// Enode URL with the public address, null until it's known
+	pub enode: Option<String>,
+	/// Name of the chain
+	pub chain: String,
+	/// Hash of the genesis block
+	#[serde(rename="genesisHash")]
+	pub genesis_hash: H256,
+	/// Supported protocols with versions, e.g. `eth/63`
+	pub protocols: Vec<String>,
+	/// Port listening for peers
+	#[serde(rename="networkPort")]
+	pub network_port: u16,
+	/// Port of JSON-RPC server, null if it's disabled
+	#[serde(rename="rpcPort")]
+	pub rpc_port: Option<u16>,
+	/// Database directory
+	#[serde(rename="dataPath")]
+	pub data_path: String,
+	/// Key store directory
+	#[serde(rename="keysPath")]
+	pub keys_path: String,
+}
+
+/// Network identity of the local node
+#[derive(Debug, Serialize, PartialEq)]
+pub struct LocalNodeInfo {
+	/// Client version advertised to peers
+	pub name: String,
+	/// Enode URL with the public address, null until it's known
+	pub enode: Option<String>,
+	/// Address listening for peers, null if bound to all interfaces
+	#[serde(rename="listenAddress")]
+	pub listen_address: Option<String>,
+	/// Supported protocols with versions, e.g. `eth/63`
+	pub protocols: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::H256;
+	use super::NodeInfo;
+
+	#[test]
+	fn test_serialize_node_info() {
+		let info = NodeInfo {
+			name: "node".into(),
+			enode: None,
+			chain: "morden".into(),
+			genesis_hash: H256::default(),
+			protocols: vec!["eth/63".into()],
+			network_port: 30303,
+			rpc_port: Some(8545),
+			data_path: "/db".into(),
+			keys_path: "/keys".into(),
+		};
+		let serialized = serde_json::to_string(&info).unwrap();
+		assert_eq!(serialized, r#"{"name":"node","enode":null,"chain":"morden","genesisHash":"0x0000000000000000000000000000000000000000000000000000000000000000","protocols":["eth/63"],"networkPort":30303,"rpcPort":8545,"dataPath":"/db","keysPath":"/keys"}"#);
+	}
+}
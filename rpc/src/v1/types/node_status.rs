@@ -0,0 +1,101 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use v1::types::{H256, U256};
+
+/// Status of the node, as reported by the informant
+#[derive(Debug, Serialize, PartialEq)]
+pub struct NodeStatus {
+	/// Number of the best block
+	#[serde(rename="bestBlockNumber")]
+	pub best_block_number: U256,
+	/// Hash of the best block
+	#[serde(rename="bestBlockHash")]
+	pub best_block_hash: H256,
+	/// Total difficulty of the best block
+	#[serde(rename="totalDifficulty")]
+	pub total_difficulty: U256,
+	/// Block queue
+	pub queue: QueueStatus,
+	/// Memory used by the blockchain caches in bytes
+	#[serde(rename="cacheBytes")]
+	pub cache_bytes: u64,
+	/// Whether the node is catching up with the chain
+	#[serde(rename="majorSyncing")]
+	pub major_syncing: bool,
+	/// Highest block known to the sync, null if unknown
+	#[serde(rename="highestBlock")]
+	pub highest_block: Option<U256>,
+	/// Peers
+	pub peers: PeerStatus,
+}
+
+/// Block queue status
+#[derive(Debug, Serialize, PartialEq)]
+pub struct QueueStatus {
+	/// Blocks waiting for verification
+	pub unverified: u64,
+	/// Blocks being verified
+	pub verifying: u64,
+	/// Verified blocks waiting for import
+	pub verified: u64,
+	/// Memory used by the queue in bytes
+	#[serde(rename="memBytes")]
+	pub mem_bytes: u64,
+}
+
+/// Peer counts
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PeerStatus {
+	/// Peers currently syncing with the node
+	pub active: u64,
+	/// Connected peers
+	pub connected: u64,
+	/// Number of peers the node tries to keep
+	pub ideal: u64,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::H256;
+	use super::{NodeStatus, QueueStatus, PeerStatus};
+
+	#[test]
+	fn test_serialize_node_status() {
+		let status = NodeStatus {
+			best_block_number: 10.into(),
+			best_block_hash: H256::default(),
+			total_difficulty: 256.into(),
+			queue: QueueStatus {
+				unverified: 1,
+				verifying: 2,
+				verified: 3,
+				mem_bytes: 1024,
+			},
+			cache_bytes: 2048,
+			major_syncing: false,
+			highest_block: None,
+			peers: PeerStatus {
+				active: 1,
+				connected: 5,
+				ideal: 25,
+			},
+		};
+		let serialized = serde_json::to_string(&status).unwrap();
+		assert_eq!(serialized, r#"{"bestBlockNumber":"0x0a","bestBlockHash":"0x0000000000000000000000000000000000000000000000000000000000000000","totalDifficulty":"0x0100","queue":{"unverified":1,"verifying":2,"verified":3,"memBytes":1024},"cacheBytes":2048,"majorSyncing":false,"highestBlock":null,"peers":{"active":1,"connected":5,"ideal":25}}"#);
+	}
+}
@@ -0,0 +1,45 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use v1::types::{H256, U256};
+
+/// Block anchoring subsequent `ethcore_pinned*` calls.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PinnedBlock {
+	/// Handle of the pin. It's random, so only the session it was handed to can use the pin.
+	pub id: H256,
+	/// Number of the pinned block
+	pub number: U256,
+	/// Hash of the pinned block
+	pub hash: H256,
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::PinnedBlock;
+
+	#[test]
+	fn pinned_block_serialization() {
+		let pin = PinnedBlock {
+			id: 1.into(),
+			number: 10.into(),
+			hash: 5.into(),
+		};
+		let serialized = serde_json::to_string(&pin).unwrap();
+		assert_eq!(serialized, r#"{"id":"0x0000000000000000000000000000000000000000000000000000000000000001","number":"0x0a","hash":"0x0000000000000000000000000000000000000000000000000000000000000005"}"#);
+	}
+}
@@ -0,0 +1,69 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use serde::{Deserialize, Deserializer, Error};
+use serde::de::Visitor;
+
+/// Kind of a pub-sub subscription.
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub enum SubscriptionKind {
+	/// Headers of new canonical blocks.
+	NewHeads,
+	/// Logs of new canonical blocks matching a filter.
+	Logs,
+	/// Hashes of transactions entering the pending set.
+	NewPendingTransactions,
+}
+
+impl Deserialize for SubscriptionKind {
+	fn deserialize<D>(deserializer: &mut D) -> Result<SubscriptionKind, D::Error>
+	where D: Deserializer {
+		deserializer.deserialize(SubscriptionKindVisitor)
+	}
+}
+
+struct SubscriptionKindVisitor;
+
+impl Visitor for SubscriptionKindVisitor {
+	type Value = SubscriptionKind;
+
+	fn visit_str<E>(&mut self, value: &str) -> Result<Self::Value, E> where E: Error {
+		match value {
+			"newHeads" => Ok(SubscriptionKind::NewHeads),
+			"logs" => Ok(SubscriptionKind::Logs),
+			"newPendingTransactions" => Ok(SubscriptionKind::NewPendingTransactions),
+			_ => Err(Error::custom("invalid subscription kind")),
+		}
+	}
+
+	fn visit_string<E>(&mut self, value: String) -> Result<Self::Value, E> where E: Error {
+		self.visit_str(value.as_ref())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::SubscriptionKind;
+
+	#[test]
+	fn subscription_kind_deserialization() {
+		let s = r#"["newHeads", "logs", "newPendingTransactions"]"#;
+		let deserialized: Vec<SubscriptionKind> = serde_json::from_str(s).unwrap();
+		assert_eq!(deserialized, vec![SubscriptionKind::NewHeads, SubscriptionKind::Logs, SubscriptionKind::NewPendingTransactions]);
+		assert!(serde_json::from_str::<SubscriptionKind>(r#""syncing""#).is_err());
+	}
+}
@@ -0,0 +1,112 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::client::SnapshotStatus as EthSnapshotStatus;
+use ethsync::{SyncStatus as EthSyncStatus, SyncState};
+
+/// Progress of a snapshot being taken
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SnapshotCreation {
+	/// Number of the block the snapshot is taken at
+	pub block: u64,
+	/// Accounts written so far
+	pub accounts: u64,
+	/// Blocks written so far
+	pub blocks: u64,
+	/// Compressed size of the chunks written so far
+	pub size: u64,
+}
+
+/// Progress of a snapshot being restored
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SnapshotRestoration {
+	/// Chunks restored so far
+	#[serde(rename="chunksDone")]
+	pub chunks_done: usize,
+	/// Total number of chunks
+	#[serde(rename="chunksTotal")]
+	pub chunks_total: usize,
+}
+
+/// Snapshot status
+#[derive(Debug, Serialize, PartialEq)]
+pub struct SnapshotStatus {
+	/// Snapshot being taken, if any
+	pub creating: Option<SnapshotCreation>,
+	/// Snapshot being restored, if any
+	pub restoring: Option<SnapshotRestoration>,
+	/// Block number of the latest complete snapshot, if any
+	#[serde(rename="lastSnapshot")]
+	pub last_snapshot: Option<u64>,
+}
+
+impl SnapshotStatus {
+	/// Combine the state of snapshot creation with the state of sync, which drives restoration.
+	pub fn new(status: EthSnapshotStatus, sync: &EthSyncStatus) -> Self {
+		let restoring = match sync.state {
+			SyncState::SnapshotManifest | SyncState::SnapshotHeader | SyncState::SnapshotData
+				| SyncState::SnapshotWaiting => Some(SnapshotRestoration {
+				chunks_done: sync.snapshot_chunks_done,
+				chunks_total: sync.num_snapshot_chunks,
+			}),
+			_ => None,
+		};
+
+		SnapshotStatus {
+			creating: status.creating.map(|block| SnapshotCreation {
+				block: block,
+				accounts: status.accounts,
+				blocks: status.blocks,
+				size: status.size,
+			}),
+			restoring: restoring,
+			last_snapshot: status.last_snapshot,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use super::{SnapshotStatus, SnapshotCreation, SnapshotRestoration};
+
+	#[test]
+	fn test_serialize_snapshot_status() {
+		let s = SnapshotStatus {
+			creating: Some(SnapshotCreation {
+				block: 3000,
+				accounts: 120,
+				blocks: 30,
+				size: 4096,
+			}),
+			restoring: None,
+			last_snapshot: Some(2000),
+		};
+		let serialized = serde_json::to_string(&s).unwrap();
+		assert_eq!(serialized, r#"{"creating":{"block":3000,"accounts":120,"blocks":30,"size":4096},"restoring":null,"lastSnapshot":2000}"#);
+
+		let s = SnapshotStatus {
+			creating: None,
+			restoring: Some(SnapshotRestoration {
+				chunks_done: 5,
+				chunks_total: 10,
+			}),
+			last_snapshot: None,
+		};
+		let serialized = serde_json::to_string(&s).unwrap();
+		assert_eq!(serialized, r#"{"creating":null,"restoring":{"chunksDone":5,"chunksTotal":10},"lastSnapshot":null}"#);
+	}
+}
@@ -0,0 +1,80 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use ethcore::client::AccountOverride as EthAccountOverride;
+use v1::types::{Bytes, H160, H256, U256};
+
+/// Changes applied to a single account for the duration of a call
+#[derive(Debug, Default, PartialEq, Deserialize)]
+pub struct AccountOverride {
+	/// Balance
+	pub balance: Option<U256>,
+	/// Code
+	pub code: Option<Bytes>,
+	/// Storage entries to set, other entries are left intact
+	#[serde(rename="stateDiff")]
+	pub state_diff: Option<BTreeMap<H256, H256>>,
+}
+
+/// Account changes applied to a copy of the state for the duration of a call
+pub type StateOverride = BTreeMap<H160, AccountOverride>;
+
+impl Into<EthAccountOverride> for AccountOverride {
+	fn into(self) -> EthAccountOverride {
+		EthAccountOverride {
+			balance: self.balance.map(Into::into),
+			code: self.code.map(Into::into),
+			storage: self.state_diff.unwrap_or_else(BTreeMap::new).into_iter()
+				.map(|(key, value)| (key.into(), value.into()))
+				.collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::collections::BTreeMap;
+	use serde_json;
+	use v1::types::{U256, H160, H256};
+	use super::{AccountOverride, StateOverride};
+
+	#[test]
+	fn state_override_deserialize() {
+		let s = r#"{
+			"0x0000000000000000000000000000000000000001": {
+				"balance": "0x10",
+				"code": "0x6000",
+				"stateDiff": {
+					"0x0000000000000000000000000000000000000000000000000000000000000002": "0x0000000000000000000000000000000000000000000000000000000000000003"
+				}
+			},
+			"0x0000000000000000000000000000000000000002": {}
+		}"#;
+		let deserialized: StateOverride = serde_json::from_str(s).unwrap();
+
+		let mut state_diff = BTreeMap::new();
+		state_diff.insert(H256::from(2), H256::from(3));
+		let mut expected = BTreeMap::new();
+		expected.insert(H160::from(1), AccountOverride {
+			balance: Some(U256::from(0x10)),
+			code: Some(vec![0x60, 0x00].into()),
+			state_diff: Some(state_diff),
+		});
+		expected.insert(H160::from(2), AccountOverride::default());
+		assert_eq!(deserialized, expected);
+	}
+}
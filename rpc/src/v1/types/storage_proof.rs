@@ -0,0 +1,67 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use ethcore::client::StorageRangeProof as EthStorageRangeProof;
+use v1::types::{Bytes, H256};
+
+/// Proof of a range of contract storage
+#[derive(Debug, Serialize, PartialEq)]
+pub struct StorageRangeProof {
+	/// RLP of the account
+	pub account: Bytes,
+	/// State trie nodes proving the account
+	#[serde(rename="accountProof")]
+	pub account_proof: Vec<Bytes>,
+	/// Keccak hashes of the storage positions in the range
+	pub keys: Vec<H256>,
+	/// Storage values at the positions
+	pub values: Vec<H256>,
+	/// Storage trie nodes proving the values
+	#[serde(rename="storageProof")]
+	pub storage_proof: Vec<Bytes>,
+}
+
+impl From<EthStorageRangeProof> for StorageRangeProof {
+	fn from(p: EthStorageRangeProof) -> Self {
+		StorageRangeProof {
+			account: Bytes::new(p.account),
+			account_proof: p.account_proof.into_iter().map(Bytes::new).collect(),
+			keys: p.keys.into_iter().map(Into::into).collect(),
+			values: p.values.into_iter().map(Into::into).collect(),
+			storage_proof: p.storage_proof.into_iter().map(Bytes::new).collect(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use serde_json;
+	use v1::types::{Bytes, H256};
+	use super::StorageRangeProof;
+
+	#[test]
+	fn test_serialize_storage_range_proof() {
+		let p = StorageRangeProof {
+			account: Bytes::new(vec![0xc0]),
+			account_proof: vec![Bytes::new(vec![0x80])],
+			keys: vec![H256::default()],
+			values: vec![H256::default()],
+			storage_proof: vec![],
+		};
+		let serialized = serde_json::to_string(&p).unwrap();
+		assert_eq!(serialized, r#"{"account":"0xc0","accountProof":["0x80"],"keys":["0x0000000000000000000000000000000000000000000000000000000000000000"],"values":["0x0000000000000000000000000000000000000000000000000000000000000000"],"storageProof":[]}"#);
+	}
+}
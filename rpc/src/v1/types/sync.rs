@@ -15,6 +15,7 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use serde::{Serialize, Serializer};
+use ethsync::{PeerVersionCount, PeerInfo as EthPeerInfo};
 use v1::types::U256;
 
 /// Sync info
@@ -50,10 +51,70 @@ impl Serialize for SyncStatus {
 	}
 }
 
+/// Number of peers running a given protocol and client version
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PeerVersion {
+	/// eth protocol version
+	#[serde(rename="protocolVersion")]
+	pub protocol_version: u32,
+	/// Client version string
+	#[serde(rename="clientVersion")]
+	pub client_version: String,
+	/// Number of peers
+	pub count: usize,
+}
+
+impl From<PeerVersionCount> for PeerVersion {
+	fn from(c: PeerVersionCount) -> Self {
+		PeerVersion {
+			protocol_version: c.protocol_version,
+			client_version: c.client_version,
+			count: c.count,
+		}
+	}
+}
+
+/// Connected peer
+#[derive(Debug, Serialize, PartialEq)]
+pub struct PeerInfo {
+	/// Public node id, null if the handshake is not finished
+	pub id: Option<String>,
+	/// Client version string
+	#[serde(rename="clientVersion")]
+	pub client_version: String,
+	/// RLPx protocol version
+	#[serde(rename="protocolVersion")]
+	pub protocol_version: u32,
+	/// Negotiated capabilities, e.g. `eth/63`
+	pub capabilities: Vec<String>,
+	/// Remote socket address
+	#[serde(rename="remoteAddress")]
+	pub remote_address: String,
+	/// Ping delay in milliseconds, null if not measured yet
+	#[serde(rename="pingMs")]
+	pub ping_ms: Option<u64>,
+	/// True if the connection was originated by this node
+	pub originated: bool,
+}
+
+impl From<EthPeerInfo> for PeerInfo {
+	fn from(p: EthPeerInfo) -> Self {
+		PeerInfo {
+			id: p.id,
+			client_version: p.client_version,
+			protocol_version: p.protocol_version,
+			capabilities: p.capabilities,
+			remote_address: p.remote_address,
+			ping_ms: p.ping_ms,
+			originated: p.originated,
+		}
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use serde_json;
-	use super::{SyncInfo, SyncStatus};
+	use super::{SyncInfo, SyncStatus, PeerInfo};
 
 	#[test]
 	fn test_serialize_sync_info() {
@@ -72,4 +133,19 @@ mod tests {
 		let serialized = serde_json::to_string(&t).unwrap();
 		assert_eq!(serialized, r#"{"startingBlock":"0x00","currentBlock":"0x00","highestBlock":"0x00"}"#);
 	}
+
+	#[test]
+	fn test_serialize_peer_info() {
+		let t = PeerInfo {
+			id: Some("1234".into()),
+			client_version: "Parity/v1.3.0".into(),
+			protocol_version: 4,
+			capabilities: vec!["eth/63".into()],
+			remote_address: "127.0.0.1:30303".into(),
+			ping_ms: None,
+			originated: true,
+		};
+		let serialized = serde_json::to_string(&t).unwrap();
+		assert_eq!(serialized, r#"{"id":"1234","clientVersion":"Parity/v1.3.0","protocolVersion":4,"capabilities":["eth/63"],"remoteAddress":"127.0.0.1:30303","pingMs":null,"originated":true}"#);
+	}
 }
@@ -21,7 +21,7 @@ use ethcore::trace::{Trace as EthTrace, LocalizedTrace as EthLocalizedTrace};
 use ethcore::trace as et;
 use ethcore::state_diff;
 use ethcore::account_diff;
-use util::Uint;
+use util::{Uint, H256 as EthH256};
 use v1::types::{Bytes, H160, H256, U256};
 
 #[derive(Debug, Serialize)]
@@ -92,6 +92,8 @@ impl From<et::VMExecutedOperation> for VMExecutedOperation {
 pub struct VMOperation {
 	/// The program counter.
 	pub pc: usize,
+	/// The instruction executed.
+	pub op: u8,
 	/// The gas cost for this instruction.
 	pub cost: u64,
 	/// Information concerning the execution of the operation.
@@ -104,6 +106,7 @@ impl From<(et::VMOperation, Option<et::VMTrace>)> for VMOperation {
 	fn from(c: (et::VMOperation, Option<et::VMTrace>)) -> Self {
 		VMOperation {
 			pc: c.0.pc,
+			op: c.0.instruction,
 			cost: c.0.gas_cost.low_u64(),
 			ex: c.0.executed.map(Into::into),
 			sub: c.1.map(Into::into),
@@ -282,6 +285,48 @@ impl From<trace::Suicide> for Suicide {
 	}
 }
 
+/// Reward type.
+#[derive(Debug, Serialize)]
+pub enum RewardType {
+	/// Block
+	#[serde(rename="block")]
+	Block,
+	/// Uncle
+	#[serde(rename="uncle")]
+	Uncle,
+}
+
+impl From<trace::RewardType> for RewardType {
+	fn from(r: trace::RewardType) -> Self {
+		match r {
+			trace::RewardType::Block => RewardType::Block,
+			trace::RewardType::Uncle => RewardType::Uncle,
+		}
+	}
+}
+
+/// Reward
+#[derive(Debug, Serialize)]
+pub struct Reward {
+	/// Author.
+	pub author: H160,
+	/// Value.
+	pub value: U256,
+	/// Reward type.
+	#[serde(rename="rewardType")]
+	pub reward_type: RewardType,
+}
+
+impl From<trace::Reward> for Reward {
+	fn from(r: trace::Reward) -> Self {
+		Reward {
+			author: r.author.into(),
+			value: r.value.into(),
+			reward_type: r.reward_type.into(),
+		}
+	}
+}
+
 /// Action
 #[derive(Debug, Serialize)]
 pub enum Action {
@@ -294,6 +339,9 @@ pub enum Action {
 	/// Suicide
 	#[serde(rename="suicide")]
 	Suicide(Suicide),
+	/// Reward
+	#[serde(rename="reward")]
+	Reward(Reward),
 }
 
 impl From<trace::Action> for Action {
@@ -302,6 +350,7 @@ impl From<trace::Action> for Action {
 			trace::Action::Call(call) => Action::Call(call.into()),
 			trace::Action::Create(create) => Action::Create(create.into()),
 			trace::Action::Suicide(suicide) => Action::Suicide(suicide.into()),
+			trace::Action::Reward(reward) => Action::Reward(reward.into()),
 		}
 	}
 }
@@ -356,12 +405,12 @@ pub enum Res {
 	/// Create
 	#[serde(rename="create")]
 	Create(CreateResult),
-	/// Call failure
+	/// Call failure, with the reason
 	#[serde(rename="failedCall")]
-	FailedCall,
-	/// Creation failure
+	FailedCall(String),
+	/// Creation failure, with the reason
 	#[serde(rename="failedCreate")]
-	FailedCreate,
+	FailedCreate(String),
 	/// None
 	#[serde(rename="none")]
 	None,
@@ -372,8 +421,8 @@ impl From<trace::Res> for Res {
 		match t {
 			trace::Res::Call(call) => Res::Call(CallResult::from(call)),
 			trace::Res::Create(create) => Res::Create(CreateResult::from(create)),
-			trace::Res::FailedCall => Res::FailedCall,
-			trace::Res::FailedCreate => Res::FailedCreate,
+			trace::Res::FailedCall(error) => Res::FailedCall(format!("{}", error)),
+			trace::Res::FailedCreate(error) => Res::FailedCreate(format!("{}", error)),
 			trace::Res::None => Res::None,
 		}
 	}
@@ -391,12 +440,12 @@ pub struct LocalizedTrace {
 	trace_address: Vec<U256>,
 	/// Subtraces
 	subtraces: U256,
-	/// Transaction position
+	/// Transaction position, null for block rewards
 	#[serde(rename="transactionPosition")]
-	transaction_position: U256,
-	/// Transaction hash
+	transaction_position: Option<U256>,
+	/// Transaction hash, null for block rewards
 	#[serde(rename="transactionHash")]
-	transaction_hash: H256,
+	transaction_hash: Option<H256>,
 	/// Block Number
 	#[serde(rename="blockNumber")]
 	block_number: U256,
@@ -412,8 +461,8 @@ impl From<EthLocalizedTrace> for LocalizedTrace {
 			result: t.result.into(),
 			trace_address: t.trace_address.into_iter().map(Into::into).collect(),
 			subtraces: t.subtraces.into(),
-			transaction_position: t.transaction_number.into(),
-			transaction_hash: t.transaction_hash.into(),
+			transaction_position: t.transaction_number.map(Into::into),
+			transaction_hash: t.transaction_hash.map(Into::into),
 			block_number: t.block_number.into(),
 			block_hash: t.block_hash.into(),
 		}
@@ -444,10 +493,59 @@ impl From<EthTrace> for Trace {
 	}
 }
 
+/// Change of the canonical chain sent to trace subscribers.
+#[derive(Debug, Serialize)]
+pub struct TraceNotification {
+	/// Number of the message, one more than that of the previous one. A gap means that
+	/// messages were dropped.
+	sequence: U256,
+	/// Block Hash
+	#[serde(rename="blockHash")]
+	block_hash: H256,
+	/// Block Number
+	#[serde(rename="blockNumber")]
+	block_number: U256,
+	/// Whether the block left the canonical chain. Its traces were sent when it was enacted.
+	retracted: bool,
+	/// Traces of an enacted block, empty for retracted ones.
+	traces: Vec<LocalizedTrace>,
+}
+
+impl TraceNotification {
+	/// Notification of a block which became canonical.
+	pub fn enacted(block_hash: EthH256, block_number: u64, traces: Vec<EthLocalizedTrace>) -> Self {
+		TraceNotification {
+			sequence: U256::default(),
+			block_hash: block_hash.into(),
+			block_number: block_number.into(),
+			retracted: false,
+			traces: traces.into_iter().map(Into::into).collect(),
+		}
+	}
+
+	/// Notification of a block which is not canonical anymore.
+	pub fn retracted(block_hash: EthH256, block_number: u64) -> Self {
+		TraceNotification {
+			sequence: U256::default(),
+			block_hash: block_hash.into(),
+			block_number: block_number.into(),
+			retracted: true,
+			traces: Vec::new(),
+		}
+	}
+
+	/// Sets the sequence number of the message.
+	pub fn with_sequence(mut self, sequence: u64) -> Self {
+		self.sequence = sequence.into();
+		self
+	}
+}
+
 #[cfg(test)]
 mod tests {
 	use serde_json;
 	use std::collections::BTreeMap;
+	use util::H256 as EthH256;
 	use v1::types::{Bytes, U256, H256, H160};
 	use super::*;
 
@@ -467,8 +565,8 @@ mod tests {
 			}),
 			trace_address: vec![U256::from(10)],
 			subtraces: U256::from(1),
-			transaction_position: U256::from(11),
-			transaction_hash: H256::from(12),
+			transaction_position: Some(U256::from(11)),
+			transaction_hash: Some(H256::from(12)),
 			block_number: U256::from(13),
 			block_hash: H256::from(14),
 		};
@@ -476,6 +574,13 @@ mod tests {
 		assert_eq!(serialized, r#"{"action":{"call":{"from":"0x0000000000000000000000000000000000000004","to":"0x0000000000000000000000000000000000000005","value":"0x06","gas":"0x07","input":"0x1234"}},"result":{"call":{"gasUsed":"0x08","output":"0x5678"}},"traceAddress":["0x0a"],"subtraces":"0x01","transactionPosition":"0x0b","transactionHash":"0x000000000000000000000000000000000000000000000000000000000000000c","blockNumber":"0x0d","blockHash":"0x000000000000000000000000000000000000000000000000000000000000000e"}"#);
 	}
 
+	#[test]
+	fn test_trace_notification_serialize() {
+		let n = TraceNotification::retracted(EthH256::from(14u64), 13).with_sequence(5);
+		let serialized = serde_json::to_string(&n).unwrap();
+		assert_eq!(serialized, r#"{"sequence":"0x05","blockHash":"0x000000000000000000000000000000000000000000000000000000000000000e","blockNumber":"0x0d","retracted":true,"traces":[]}"#);
+	}
+
 	#[test]
 	fn test_vmtrace_serialize() {
 		let t = VMTrace {
@@ -483,12 +588,14 @@ mod tests {
 			ops: vec![
 				VMOperation {
 					pc: 0,
+					op: 0x60,
 					cost: 10,
 					ex: None,
 					sub: None,
 				},
 				VMOperation {
 					pc: 1,
+					op: 0xf1,
 					cost: 11,
 					ex: Some(VMExecutedOperation {
 						used: 10,
@@ -501,6 +608,7 @@ mod tests {
 						ops: vec![
 							VMOperation {
 								pc: 0,
+								op: 0x60,
 								cost: 0,
 								ex: Some(VMExecutedOperation {
 									used: 10,
@@ -516,7 +624,7 @@ mod tests {
 			]
 		};
 		let serialized = serde_json::to_string(&t).unwrap();
-		assert_eq!(serialized, r#"{"code":[0,1,2,3],"ops":[{"pc":0,"cost":10,"ex":null,"sub":null},{"pc":1,"cost":11,"ex":{"used":10,"push":["0x45"],"mem":null,"store":null},"sub":{"code":[0],"ops":[{"pc":0,"cost":0,"ex":{"used":10,"push":["0x2a"],"mem":{"off":42,"data":[1,2,3]},"store":{"key":"0x45","val":"0x2a"}},"sub":null}]}}]}"#);
+		assert_eq!(serialized, r#"{"code":[0,1,2,3],"ops":[{"pc":0,"op":96,"cost":10,"ex":null,"sub":null},{"pc":1,"op":241,"cost":11,"ex":{"used":10,"push":["0x45"],"mem":null,"store":null},"sub":{"code":[0],"ops":[{"pc":0,"op":96,"cost":0,"ex":{"used":10,"push":["0x2a"],"mem":{"off":42,"data":[1,2,3]},"store":{"key":"0x45","val":"0x2a"}},"sub":null}]}}]}"#);
 	}
 
 	#[test]
@@ -554,10 +662,14 @@ mod tests {
 			value: U256::from(6),
 			gas: U256::from(7),
 			init: vec![0x56, 0x78].into(),
+		}), Action::Reward(Reward {
+			author: H160::from(8),
+			value: U256::from(9),
+			reward_type: RewardType::Uncle,
 		})];
 
 		let serialized = serde_json::to_string(&actions).unwrap();
-		assert_eq!(serialized, r#"[{"call":{"from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","value":"0x03","gas":"0x04","input":"0x1234"}},{"create":{"from":"0x0000000000000000000000000000000000000005","value":"0x06","gas":"0x07","init":"0x5678"}}]"#);
+		assert_eq!(serialized, r#"[{"call":{"from":"0x0000000000000000000000000000000000000001","to":"0x0000000000000000000000000000000000000002","value":"0x03","gas":"0x04","input":"0x1234"}},{"create":{"from":"0x0000000000000000000000000000000000000005","value":"0x06","gas":"0x07","init":"0x5678"}},{"reward":{"author":"0x0000000000000000000000000000000000000008","value":"0x09","rewardType":"uncle"}}]"#);
 	}
 
 	#[test]
@@ -572,11 +684,11 @@ mod tests {
 				code: vec![0x45, 0x56].into(),
 				address: H160::from(3),
 			}),
-			Res::FailedCall,
-			Res::FailedCreate,
+			Res::FailedCall("Out of gas".to_owned()),
+			Res::FailedCreate("Bad jump destination".to_owned()),
 		];
 
 		let serialized = serde_json::to_string(&results).unwrap();
-		assert_eq!(serialized, r#"[{"call":{"gasUsed":"0x01","output":"0x1234"}},{"create":{"gasUsed":"0x02","code":"0x4556","address":"0x0000000000000000000000000000000000000003"}},{"failedCall":[]},{"failedCreate":[]}]"#);
+		assert_eq!(serialized, r#"[{"call":{"gasUsed":"0x01","output":"0x1234"}},{"create":{"gasUsed":"0x02","code":"0x4556","address":"0x0000000000000000000000000000000000000003"}},{"failedCall":"Out of gas"},{"failedCreate":"Bad jump destination"}]"#);
 	}
 }
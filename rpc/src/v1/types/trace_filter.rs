@@ -35,6 +35,9 @@ pub struct TraceFilter {
 	/// To address
 	#[serde(rename="toAddress")]
 	pub to_address: Option<Vec<H160>>,
+	/// Include traces of retracted blocks
+	#[serde(rename="includeRetracted")]
+	pub include_retracted: Option<bool>,
 }
 
 impl Into<client::TraceFilter> for TraceFilter {
@@ -45,6 +48,7 @@ impl Into<client::TraceFilter> for TraceFilter {
 			range: start..end,
 			from_address: self.from_address.map_or_else(Vec::new, |x| x.into_iter().map(Into::into).collect()),
 			to_address: self.to_address.map_or_else(Vec::new, |x| x.into_iter().map(Into::into).collect()),
+			include_retracted: self.include_retracted.unwrap_or(false),
 		}
 	}
 }
@@ -63,7 +67,8 @@ mod tests {
 			from_block: None,
 			to_block: None,
 			from_address: None,
-			to_address: None
+			to_address: None,
+			include_retracted: None,
 		});
 	}
 
@@ -73,7 +78,8 @@ mod tests {
 			"fromBlock": "latest",
 			"toBlock": "latest",
 			"fromAddress": ["0x0000000000000000000000000000000000000003"],
-			"toAddress": ["0x0000000000000000000000000000000000000005"]
+			"toAddress": ["0x0000000000000000000000000000000000000005"],
+			"includeRetracted": true
 		}"#;
 		let deserialized: TraceFilter = serde_json::from_str(s).unwrap();
 		assert_eq!(deserialized, TraceFilter {
@@ -81,6 +87,7 @@ mod tests {
 			to_block: Some(BlockNumber::Latest),
 			from_address: Some(vec![Address::from(3).into()]),
 			to_address: Some(vec![Address::from(5).into()]),
+			include_retracted: Some(true),
 		});
 	}
 }
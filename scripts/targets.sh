@@ -5,6 +5,7 @@ export TARGETS="
 	-p ethash \
 	-p ethcore \
 	-p ethcore-dapps \
+	-p ethcore-ipc-mio \
 	-p ethcore-rpc \
 	-p ethcore-signer \
 	-p ethcore-util \
@@ -51,6 +51,7 @@ extern crate ethcore_util as util;
 extern crate ethcore_rpc as rpc;
 extern crate jsonrpc_core;
 extern crate ws;
+extern crate openssl;
 #[cfg(feature = "ui")]
 extern crate parity_dapps_signer as signer;
 
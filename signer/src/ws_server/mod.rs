@@ -26,7 +26,9 @@ use std::sync::Arc;
 use std::net::SocketAddr;
 use util::panics::{PanicHandler, OnPanicListener, MayPanic};
 use jsonrpc_core::{IoHandler, IoDelegate};
-use rpc::{Extendable, ConfirmationsQueue};
+use openssl::ssl::SslContext;
+use rpc::{Extendable, ConfirmationsQueue, AuditLog, ApiKeys};
+use rpc::v1::EthPubSub;
 
 mod session;
 
@@ -48,10 +50,22 @@ impl From<ws::Error> for ServerError {
 	}
 }
 
+/// API keys accepted in place of a Signer authorization code.
+pub struct KeyedApis {
+	/// Known keys.
+	pub keys: Arc<ApiKeys>,
+	/// Creates the handler serving given APIs.
+	pub handler: Box<Fn(Vec<String>) -> Arc<IoHandler> + Send + Sync>,
+}
+
 /// Builder for `WebSockets` server
 pub struct ServerBuilder {
 	queue: Arc<ConfirmationsQueue>,
 	handler: Arc<IoHandler>,
+	pubsub: Option<Arc<EthPubSub>>,
+	audit_log: Option<Arc<AuditLog>>,
+	tls: Option<Arc<SslContext>>,
+	keys: Option<Arc<KeyedApis>>,
 	authcodes_path: PathBuf,
 }
 
@@ -67,14 +81,44 @@ impl ServerBuilder {
 		ServerBuilder {
 			queue: queue,
 			handler: Arc::new(IoHandler::new()),
+			pubsub: None,
+			audit_log: None,
+			tls: None,
+			keys: None,
 			authcodes_path: authcodes_path,
 		}
 	}
 
+	/// Serve `eth_subscribe` and `eth_unsubscribe` using given pub-sub implementation.
+	pub fn with_pubsub(mut self, pubsub: Arc<EthPubSub>) -> Self {
+		self.pubsub = Some(pubsub);
+		self
+	}
+
+	/// Record every call in given audit log.
+	pub fn with_audit_log(mut self, audit_log: Arc<AuditLog>) -> Self {
+		self.audit_log = Some(audit_log);
+		self
+	}
+
+	/// Serve over TLS only (`wss://`), using given context.
+	pub fn with_tls(mut self, context: SslContext) -> Self {
+		self.tls = Some(Arc::new(context));
+		self
+	}
+
+	/// Accept given API keys (in the `X-Api-Key` header or as a bearer token) instead of an authorization code.
+	/// Sessions opened with a key are served the APIs the key grants access to.
+	pub fn with_api_keys(mut self, keys: KeyedApis) -> Self {
+		self.keys = Some(Arc::new(keys));
+		self
+	}
+
 	/// Starts a new `WebSocket` server in separate thread.
 	/// Returns a `Server` handle which closes the server when droped.
 	pub fn start(self, addr: SocketAddr) -> Result<Server, ServerError> {
-		Server::start(addr, self.handler, self.queue, self.authcodes_path)
+		let factory = session::Factory::new(self.handler, self.pubsub, self.audit_log, self.tls.clone(), self.keys, format!("{}", addr), self.authcodes_path);
+		Server::start(addr, factory, self.tls.is_some(), self.queue)
 	}
 }
 
@@ -89,19 +133,19 @@ pub struct Server {
 impl Server {
 	/// Starts a new `WebSocket` server in separate thread.
 	/// Returns a `Server` handle which closes the server when droped.
-	fn start(addr: SocketAddr, handler: Arc<IoHandler>, queue: Arc<ConfirmationsQueue>, authcodes_path: PathBuf) -> Result<Server, ServerError> {
+	fn start(addr: SocketAddr, factory: session::Factory, encrypt: bool, queue: Arc<ConfirmationsQueue>) -> Result<Server, ServerError> {
 		let config = {
 			let mut config = ws::Settings::default();
 			// It's also used for handling min-sysui requests (browser can make many of them in paralel)
 			config.method_strict = true;
 			// Was shutting down server when suspending on linux:
 			config.shutdown_on_interrupt = false;
+			config.encrypt_server = encrypt;
 			config
 		};
 
 		// Create WebSocket
-		let origin = format!("{}", addr);
-		let ws = try!(ws::Builder::new().with_settings(config).build(session::Factory::new(handler, origin, authcodes_path)));
+		let ws = try!(ws::Builder::new().with_settings(config).build(factory));
 
 		let panic_handler = PanicHandler::new_in_arc();
 		let ph = panic_handler.clone();
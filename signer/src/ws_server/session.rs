@@ -22,7 +22,11 @@ use std::path::{PathBuf, Path};
 use std::sync::Arc;
 use std::str::FromStr;
 use jsonrpc_core::IoHandler;
-use util::H256;
+use rpc::{PubSubSession, AuditLog, KeyError};
+use rpc::v1::{EthPubSub, Sink};
+use ws_server::KeyedApis;
+use util::{H256, Mutex};
+use openssl::ssl::{Ssl, SslContext};
 
 #[cfg(feature = "ui")]
 mod signer {
@@ -44,6 +48,9 @@ mod signer {
 	}
 }
 
+/// JSON-RPC error code of calls over the daily quota of the session's key.
+const QUOTA_EXCEEDED_CODE: i64 = -32005;
+
 fn origin_is_allowed(self_origin: &str, header: Option<&[u8]>) -> bool {
 	match header {
 		None => false,
@@ -53,6 +60,7 @@ fn origin_is_allowed(self_origin: &str, header: Option<&[u8]>) -> bool {
 				Some(ref origin) if origin.starts_with("chrome-extension://") => true,
 				Some(ref origin) if origin.starts_with(self_origin) => true,
 				Some(ref origin) if origin.starts_with(&format!("http://{}", self_origin)) => true,
+				Some(ref origin) if origin.starts_with(&format!("https://{}", self_origin)) => true,
 				_ => false
 			}
 		}
@@ -81,6 +89,15 @@ fn auth_is_valid(codes: &Path, protocols: ws::Result<Vec<&str>>) -> bool {
 	}
 }
 
+// key given in the `X-Api-Key` header or as a bearer token.
+fn api_key(req: &ws::Request) -> Option<String> {
+	let header = |name: &str, alt: &str| req.header(name).or_else(|| req.header(alt)).map(|v| String::from_utf8_lossy(v).trim().to_owned());
+	header("x-api-key", "X-Api-Key").or_else(|| header("authorization", "Authorization").and_then(|value| match value.starts_with("Bearer ") {
+		true => Some(value["Bearer ".len()..].trim().to_owned()),
+		false => None,
+	}))
+}
+
 fn add_headers(mut response: ws::Response, mime: &str) -> ws::Response {
 	let content_len = format!("{}", response.len());
 	{
@@ -98,7 +115,39 @@ pub struct Session {
 	out: ws::Sender,
 	self_origin: String,
 	authcodes_path: PathBuf,
-	handler: Arc<IoHandler>,
+	session: PubSubSession,
+	sink: Sink,
+	pubsub: Option<Arc<EthPubSub>>,
+	audit_log: Option<Arc<AuditLog>>,
+	tls: Option<Arc<SslContext>>,
+	keys: Option<Arc<KeyedApis>>,
+	// key the session was opened with, if any; calls are counted against its quota.
+	key: Option<String>,
+	// origin of the connection, as recorded in the audit log.
+	origin: String,
+}
+
+impl Session {
+	// serves the session with the APIs given key grants access to.
+	fn authorize_key(&mut self, req: &ws::Request) -> Result<(), &'static str> {
+		let keys = match self.keys {
+			Some(ref keys) => keys.clone(),
+			None => return Err("You are not authorized."),
+		};
+		let key = match api_key(req) {
+			Some(key) => key,
+			None => return Err("You are not authorized."),
+		};
+		let apis = try!(keys.keys.authorize(&key, 0).map_err(|_| "Unknown API key."));
+		// subscriptions serve the same data as the eth API
+		let pubsub = match apis.iter().any(|api| api == "eth") {
+			true => self.pubsub.clone(),
+			false => None,
+		};
+		self.session = PubSubSession::new((keys.handler)(apis), pubsub, self.sink.clone());
+		self.key = Some(key);
+		Ok(())
+	}
 }
 
 impl ws::Handler for Session {
@@ -109,15 +158,21 @@ impl ws::Handler for Session {
 		// Check request origin and host header.
 		if !origin_is_allowed(&self.self_origin, origin) && !(origin.is_none() && origin_is_allowed(&self.self_origin, host)) {
 			warn!(target: "signer", "Blocked connection to Signer API from untrusted origin.");
-			return Ok(ws::Response::forbidden(format!("You are not allowed to access system ui. Use: http://{}", self.self_origin)));
+			let scheme = if self.tls.is_some() { "https" } else { "http" };
+			return Ok(ws::Response::forbidden(format!("You are not allowed to access system ui. Use: {}://{}", scheme, self.self_origin)));
 		}
 
+		self.origin = origin.or(host).map_or_else(|| "unknown".to_owned(), |o| String::from_utf8_lossy(o).into_owned());
+
 		// Detect if it's a websocket request.
 		if req.header("sec-websocket-key").is_some() {
 			// Check authorization
 			if !auth_is_valid(&self.authcodes_path, req.protocols()) {
-				info!(target: "signer", "Unauthorized connection to Signer API blocked.");
-				return Ok(ws::Response::forbidden("You are not authorized.".into()));
+				if let Err(message) = self.authorize_key(req) {
+					info!(target: "signer", "Unauthorized connection to Signer API blocked.");
+					return Ok(ws::Response::forbidden(message.into()));
+				}
+				return ws::Response::from_request(req);
 			}
 
 			let protocols = req.protocols().expect("Existence checked by authorization.");
@@ -141,23 +196,51 @@ impl ws::Handler for Session {
 
 	fn on_message(&mut self, msg: ws::Message) -> ws::Result<()> {
 		let req = try!(msg.as_text());
-		match self.handler.handle_request(req) {
+		if let Some(ref audit_log) = self.audit_log {
+			audit_log.log("ws", &self.origin, req);
+		}
+		if let Some(ref key) = self.key {
+			let keys = self.keys.as_ref().expect("Sessions are opened with a key only if keys are accepted; qed");
+			match keys.keys.authorize_request(key, req) {
+				Ok(_) => {},
+				Err(KeyError::UnknownKey) => return self.out.close(ws::CloseCode::Policy),
+				Err(KeyError::QuotaExceeded(quota)) => return self.out.send(format!(
+					r#"{{"jsonrpc":"2.0","error":{{"code":{},"message":"Daily quota of {} calls exceeded."}},"id":null}}"#,
+					QUOTA_EXCEEDED_CODE,
+					quota
+				)),
+			}
+		}
+		match self.session.handle_request(req) {
 			Some(res) => self.out.send(res),
 			None => Ok(()),
 		}
 	}
+
+	fn build_ssl(&mut self) -> ws::Result<Ssl> {
+		let context = self.tls.as_ref().expect("Connections are encrypted only when the server has a TLS context; qed");
+		Ssl::new(context).map_err(|e| ws::Error::new(ws::ErrorKind::Internal, format!("Cannot start TLS session: {}", e)))
+	}
 }
 
 pub struct Factory {
 	handler: Arc<IoHandler>,
+	pubsub: Option<Arc<EthPubSub>>,
+	audit_log: Option<Arc<AuditLog>>,
+	tls: Option<Arc<SslContext>>,
+	keys: Option<Arc<KeyedApis>>,
 	self_origin: String,
 	authcodes_path: PathBuf,
 }
 
 impl Factory {
-	pub fn new(handler: Arc<IoHandler>, self_origin: String, authcodes_path: PathBuf) -> Self {
+	pub fn new(handler: Arc<IoHandler>, pubsub: Option<Arc<EthPubSub>>, audit_log: Option<Arc<AuditLog>>, tls: Option<Arc<SslContext>>, keys: Option<Arc<KeyedApis>>, self_origin: String, authcodes_path: PathBuf) -> Self {
 		Factory {
 			handler: handler,
+			pubsub: pubsub,
+			audit_log: audit_log,
+			tls: tls,
+			keys: keys,
 			self_origin: self_origin,
 			authcodes_path: authcodes_path,
 		}
@@ -168,11 +251,20 @@ impl ws::Factory for Factory {
 	type Handler = Session;
 
 	fn connection_made(&mut self, sender: ws::Sender) -> Self::Handler {
+		let out = Mutex::new(sender.clone());
+		let sink: Sink = Arc::new(move |message: String| out.lock().send(message).is_ok());
 		Session {
 			out: sender,
-			handler: self.handler.clone(),
+			session: PubSubSession::new(self.handler.clone(), self.pubsub.clone(), sink.clone()),
+			sink: sink,
+			pubsub: self.pubsub.clone(),
 			self_origin: self.self_origin.clone(),
 			authcodes_path: self.authcodes_path.clone(),
+			audit_log: self.audit_log.clone(),
+			tls: self.tls.clone(),
+			keys: self.keys.clone(),
+			key: None,
+			origin: String::new(),
 		}
 	}
 }
@@ -17,27 +17,37 @@
 use std::ops::*;
 use std::sync::Arc;
 use util::network::{NetworkProtocolHandler, NetworkService, NetworkContext, PeerId,
-	NetworkConfiguration as BasicNetworkConfiguration, NonReservedPeerMode};
-use util::{TimerToken, U256, H256, UtilError, Secret, Populatable};
+	NetworkConfiguration as BasicNetworkConfiguration, NonReservedPeerMode, ConnectedPeer};
+use util::{TimerToken, U256, H256, UtilError, Secret};
 use ethcore::client::{BlockChainClient, ChainNotify};
+use ethcore::header::BlockNumber;
 use io::NetSyncIo;
-use chain::{ChainSync, SyncStatus};
+use chain::{ChainSync, ETH_PACKET_COUNT, SNAPSHOT_SYNC_PACKET_COUNT};
 use std::net::{SocketAddr, AddrParseError};
 use ipc::{BinaryConvertable, BinaryConvertError, IpcConfig};
-use std::mem;
 use std::collections::VecDeque;
 use parking_lot::RwLock;
 
 /// Ethereum sync protocol
 pub const ETH_PROTOCOL: &'static str = "eth";
+/// Versions of the ethereum protocol we support.
+const ETH_PROTOCOL_VERSIONS: &'static [u8] = &[62u8, 63u8];
+/// Parity sync protocol. Carries all `eth/63` packets plus the snapshot packets used for warp sync.
+pub const WARP_SYNC_PROTOCOL: &'static str = "par";
+/// Versions of the parity sync protocol we support.
+const WARP_SYNC_PROTOCOL_VERSIONS: &'static [u8] = &[1u8];
 
 /// Sync configuration
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Binary)]
 pub struct SyncConfig {
 	/// Max blocks to download ahead
 	pub max_download_ahead_blocks: usize,
 	/// Network ID
 	pub network_id: U256,
+	/// Restore from a snapshot served by peers when starting with an empty chain
+	pub warp_sync: bool,
+	/// Refuse peers with a lower eth protocol version
+	pub min_peer_protocol_version: u32,
 }
 
 impl Default for SyncConfig {
@@ -45,17 +55,130 @@ impl Default for SyncConfig {
 		SyncConfig {
 			max_download_ahead_blocks: 20000,
 			network_id: U256::from(1),
+			warp_sync: false,
+			min_peer_protocol_version: 0,
 		}
 	}
 }
 
-binary_fixed_size!(SyncConfig);
-binary_fixed_size!(SyncStatus);
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Binary)]
+/// Sync state
+pub enum SyncState {
+	/// Downloading subchain heads
+	ChainHead,
+	/// Initial chain sync complete. Waiting for new packets
+	Idle,
+	/// Block downloading paused. Waiting for block queue to process blocks and free some space
+	Waiting,
+	/// Downloading blocks
+	Blocks,
+	/// Downloading blocks learned from `NewHashes` packet
+	NewBlocks,
+	/// Downloading snapshot manifest
+	SnapshotManifest,
+	/// Downloading the header of the snapshot block to verify the manifest
+	SnapshotHeader,
+	/// Downloading snapshot chunks
+	SnapshotData,
+	/// Waiting for the client to restore the downloaded snapshot
+	SnapshotWaiting,
+}
+
+/// Syncing status and statistics
+#[derive(Clone, Binary)]
+pub struct SyncStatus {
+	/// State
+	pub state: SyncState,
+	/// Syncing protocol version. That's the maximum protocol version we connect to.
+	pub protocol_version: u8,
+	/// The underlying p2p network version.
+	pub network_id: U256,
+	/// `BlockChain` height for the moment the sync started.
+	pub start_block_number: BlockNumber,
+	/// Last fully downloaded and imported block number (if any).
+	pub last_imported_block_number: Option<BlockNumber>,
+	/// Highest block number in the download queue (if any).
+	pub highest_block_number: Option<BlockNumber>,
+	/// Total number of blocks for the sync process.
+	pub blocks_total: BlockNumber,
+	/// Number of blocks downloaded so far.
+	pub blocks_received: BlockNumber,
+	/// Total number of connected peers
+	pub num_peers: usize,
+	/// Total number of active peers
+	pub num_active_peers: usize,
+	/// Heap memory used in bytes
+	pub mem_used: usize,
+	/// Total number of chunks in the snapshot being downloaded
+	pub num_snapshot_chunks: usize,
+	/// Number of snapshot chunks downloaded so far
+	pub snapshot_chunks_done: usize,
+}
+
+impl SyncStatus {
+	/// Indicates if initial sync is still in progress.
+	pub fn is_major_syncing(&self) -> bool {
+		self.state != SyncState::Idle && self.state != SyncState::NewBlocks
+	}
+}
+
+/// Number of connected peers running a given protocol version and client.
+#[derive(Binary, Debug, Clone, PartialEq)]
+pub struct PeerVersionCount {
+	/// eth protocol version
+	pub protocol_version: u32,
+	/// Client version string
+	pub client_version: String,
+	/// Number of peers
+	pub count: usize,
+}
+
+/// Connected peer details.
+#[derive(Binary, Debug, Clone, PartialEq)]
+pub struct PeerInfo {
+	/// Public node id
+	pub id: Option<String>,
+	/// Client version string
+	pub client_version: String,
+	/// RLPx protocol version
+	pub protocol_version: u32,
+	/// Negotiated capabilities, e.g. `eth/63`
+	pub capabilities: Vec<String>,
+	/// Remote socket address
+	pub remote_address: String,
+	/// Ping delay in milliseconds
+	pub ping_ms: Option<u64>,
+	/// True if the connection was originated by us
+	pub originated: bool,
+}
+
+impl From<ConnectedPeer> for PeerInfo {
+	fn from(peer: ConnectedPeer) -> Self {
+		PeerInfo {
+			id: peer.id.map(|id| id.hex()),
+			client_version: peer.client_version,
+			protocol_version: peer.protocol_version,
+			capabilities: peer.capabilities,
+			remote_address: peer.remote_address,
+			ping_ms: peer.ping_ms,
+			originated: peer.originated,
+		}
+	}
+}
 
 /// Current sync status
 pub trait SyncProvider: Send + Sync {
 	/// Get sync status
 	fn status(&self) -> SyncStatus;
+
+	/// Get the number of peers by protocol and client version
+	fn peer_versions(&self) -> Vec<PeerVersionCount>;
+
+	/// Get the enode URL of this node with its public address, if known
+	fn enode(&self) -> Option<String>;
+
+	/// Get the supported protocols with their versions, e.g. `eth/63`
+	fn protocols(&self) -> Vec<String>;
 }
 
 /// Ethereum network protocol handler
@@ -87,6 +210,21 @@ impl SyncProvider for EthSync {
 	fn status(&self) -> SyncStatus {
 		self.handler.sync.write().status()
 	}
+
+	/// Get the number of peers by protocol and client version
+	fn peer_versions(&self) -> Vec<PeerVersionCount> {
+		self.handler.sync.read().peer_versions()
+	}
+
+	fn enode(&self) -> Option<String> {
+		self.network.external_url()
+	}
+
+	fn protocols(&self) -> Vec<String> {
+		ETH_PROTOCOL_VERSIONS.iter().map(|v| format!("{}/{}", ETH_PROTOCOL, v))
+			.chain(WARP_SYNC_PROTOCOL_VERSIONS.iter().map(|v| format!("{}/{}", WARP_SYNC_PROTOCOL, v)))
+			.collect()
+	}
 }
 
 struct SyncProtocolHandler {
@@ -96,9 +234,19 @@ struct SyncProtocolHandler {
 	sync: RwLock<ChainSync>,
 }
 
+impl SyncProtocolHandler {
+	/// Check if the peer's connection events should be handled in the given context.
+	fn is_sync_context(&self, io: &NetworkContext, peer: PeerId) -> bool {
+		let warp_peer = io.protocol_version(WARP_SYNC_PROTOCOL, peer).is_some();
+		warp_peer == (io.subprotocol_name() == WARP_SYNC_PROTOCOL)
+	}
+}
+
 impl NetworkProtocolHandler for SyncProtocolHandler {
 	fn initialize(&self, io: &NetworkContext) {
-		io.register_timer(0, 1000).expect("Error registering sync timer");
+		if io.subprotocol_name() != WARP_SYNC_PROTOCOL {
+			io.register_timer(0, 1000).expect("Error registering sync timer");
+		}
 	}
 
 	fn read(&self, io: &NetworkContext, peer: &PeerId, packet_id: u8, data: &[u8]) {
@@ -106,11 +254,16 @@ impl NetworkProtocolHandler for SyncProtocolHandler {
 	}
 
 	fn connected(&self, io: &NetworkContext, peer: &PeerId) {
-		self.sync.write().on_peer_connected(&mut NetSyncIo::new(io, self.chain.deref()), *peer);
+		// Peers supporting both protocols are only handled in the `par` context.
+		if self.is_sync_context(io, *peer) {
+			self.sync.write().on_peer_connected(&mut NetSyncIo::new(io, self.chain.deref()), *peer);
+		}
 	}
 
 	fn disconnected(&self, io: &NetworkContext, peer: &PeerId) {
-		self.sync.write().on_peer_aborting(&mut NetSyncIo::new(io, self.chain.deref()), *peer);
+		if self.is_sync_context(io, *peer) {
+			self.sync.write().on_peer_aborting(&mut NetSyncIo::new(io, self.chain.deref()), *peer);
+		}
 	}
 
 	fn timeout(&self, io: &NetworkContext, _timer: TimerToken) {
@@ -142,8 +295,10 @@ impl ChainNotify for EthSync {
 
 	fn start(&self) {
 		self.network.start().unwrap_or_else(|e| warn!("Error starting network: {:?}", e));
-		self.network.register_protocol(self.handler.clone(), ETH_PROTOCOL, &[62u8, 63u8])
+		self.network.register_protocol(self.handler.clone(), ETH_PROTOCOL, ETH_PACKET_COUNT, ETH_PROTOCOL_VERSIONS)
 			.unwrap_or_else(|e| warn!("Error registering ethereum protocol: {:?}", e));
+		self.network.register_protocol(self.handler.clone(), WARP_SYNC_PROTOCOL, SNAPSHOT_SYNC_PACKET_COUNT, WARP_SYNC_PROTOCOL_VERSIONS)
+			.unwrap_or_else(|e| warn!("Error registering warp sync protocol: {:?}", e));
 	}
 
 	fn stop(&self) {
@@ -170,6 +325,8 @@ pub trait ManageNetwork : Send + Sync {
 	fn stop_network(&self);
 	/// Query the current configuration of the network
 	fn network_config(&self) -> NetworkConfiguration;
+	/// Get details of all connected peers
+	fn connected_peers(&self) -> Vec<PeerInfo>;
 }
 
 
@@ -207,6 +364,10 @@ impl ManageNetwork for EthSync {
 	fn network_config(&self) -> NetworkConfiguration {
 		NetworkConfiguration::from(self.network.config().clone())
 	}
+
+	fn connected_peers(&self) -> Vec<PeerInfo> {
+		self.network.connected_peers().into_iter().map(PeerInfo::from).collect()
+	}
 }
 
 #[derive(Binary, Debug, Clone)]
@@ -234,6 +395,8 @@ pub struct NetworkConfiguration {
 	pub reserved_nodes: Vec<String>,
 	/// The non-reserved peer mode.
 	pub allow_non_reserved: bool,
+	/// Client version string sent in the handshake.
+	pub client_version: String,
 }
 
 impl NetworkConfiguration {
@@ -252,6 +415,7 @@ impl NetworkConfiguration {
 			ideal_peers: self.ideal_peers,
 			reserved_nodes: self.reserved_nodes,
 			non_reserved_mode: if self.allow_non_reserved { NonReservedPeerMode::Accept } else { NonReservedPeerMode::Deny },
+			client_version: self.client_version,
 		})
 	}
 }
@@ -270,6 +434,7 @@ impl From<BasicNetworkConfiguration> for NetworkConfiguration {
 			ideal_peers: other.ideal_peers,
 			reserved_nodes: other.reserved_nodes,
 			allow_non_reserved: match other.non_reserved_mode { NonReservedPeerMode::Accept => true, _ => false } ,
+			client_version: other.client_version,
 		}
 	}
 }
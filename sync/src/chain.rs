@@ -86,8 +86,17 @@
 ///
 /// All other messages are ignored.
 ///
+/// Warp sync.
+/// If enabled and the chain contains just the genesis block, peers supporting the `par` protocol are asked for the snapshot
+/// advertised in their `Status` packet. Sync waits a few seconds for such peers to show up before falling back to block sync.
+/// The manifest is requested from the peer with the most recent snapshot and checked against the header of the snapshot
+/// block, requested from another peer when possible. Chunks are then requested from all peers advertising the same manifest.
+/// Once all chunks are received the client restores the state and the chain from them on its IO thread, and sync continues
+/// in `ChainHead` state from the restored head.
+///
 
 use util::*;
+use std::collections::BTreeMap;
 use std::mem::{replace};
 use ethcore::views::{HeaderView, BlockView};
 use ethcore::header::{BlockNumber, Header as BlockHeader};
@@ -96,8 +105,11 @@ use ethcore::error::*;
 use ethcore::block::Block;
 use io::SyncIo;
 use time;
-use super::SyncConfig;
+use super::{SyncConfig, PeerVersionCount};
+use api::{WARP_SYNC_PROTOCOL, SyncStatus, SyncState};
 use blocks::BlockCollection;
+use snapshot::Snapshot;
+use ethcore::snapshot::ManifestData;
 use rand::{thread_rng, Rng};
 
 known_heap_size!(0, PeerInfo);
@@ -105,6 +117,10 @@ known_heap_size!(0, PeerInfo);
 type PacketDecodeError = DecoderError;
 
 const PROTOCOL_VERSION: u8 = 63u8;
+/// Number of packet IDs used by the `eth` protocol.
+pub const ETH_PACKET_COUNT: u8 = 0x11;
+/// Number of packet IDs used by the `par` protocol.
+pub const SNAPSHOT_SYNC_PACKET_COUNT: u8 = 0x15;
 const MAX_BODIES_TO_SEND: usize = 256;
 const MAX_HEADERS_TO_SEND: usize = 512;
 const MAX_NODE_DATA_TO_SEND: usize = 1024;
@@ -112,6 +128,7 @@ const MAX_RECEIPTS_TO_SEND: usize = 1024;
 const MAX_RECEIPTS_HEADERS_TO_SEND: usize = 256;
 const MAX_HEADERS_TO_REQUEST: usize = 128;
 const MAX_BODIES_TO_REQUEST: usize = 64;
+const MAX_NODE_DATA_TO_REQUEST: usize = 384;
 const MIN_PEERS_PROPAGATION: usize = 4;
 const MAX_PEERS_PROPAGATION: usize = 128;
 const MAX_PEER_LAG_PROPAGATION: BlockNumber = 20;
@@ -135,57 +152,17 @@ const NODE_DATA_PACKET: u8 = 0x0e;
 const GET_RECEIPTS_PACKET: u8 = 0x0f;
 const RECEIPTS_PACKET: u8 = 0x10;
 
+const GET_SNAPSHOT_MANIFEST_PACKET: u8 = 0x11;
+const SNAPSHOT_MANIFEST_PACKET: u8 = 0x12;
+const GET_SNAPSHOT_DATA_PACKET: u8 = 0x13;
+const SNAPSHOT_DATA_PACKET: u8 = 0x14;
+
 const HEADERS_TIMEOUT_SEC: f64 = 15f64;
 const BODIES_TIMEOUT_SEC: f64 = 5f64;
-
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
-/// Sync state
-pub enum SyncState {
-	/// Downloading subchain heads
-	ChainHead,
-	/// Initial chain sync complete. Waiting for new packets
-	Idle,
-	/// Block downloading paused. Waiting for block queue to process blocks and free some space
-	Waiting,
-	/// Downloading blocks
-	Blocks,
-	/// Downloading blocks learned from `NewHashes` packet
-	NewBlocks,
-}
-
-/// Syncing status and statistics
-#[derive(Clone)]
-pub struct SyncStatus {
-	/// State
-	pub state: SyncState,
-	/// Syncing protocol version. That's the maximum protocol version we connect to.
-	pub protocol_version: u8,
-	/// The underlying p2p network version.
-	pub network_id: U256,
-	/// `BlockChain` height for the moment the sync started.
-	pub start_block_number: BlockNumber,
-	/// Last fully downloaded and imported block number (if any).
-	pub last_imported_block_number: Option<BlockNumber>,
-	/// Highest block number in the download queue (if any).
-	pub highest_block_number: Option<BlockNumber>,
-	/// Total number of blocks for the sync process.
-	pub blocks_total: BlockNumber,
-	/// Number of blocks downloaded so far.
-	pub blocks_received: BlockNumber,
-	/// Total number of connected peers
-	pub num_peers: usize,
-	/// Total number of active peers
-	pub num_active_peers: usize,
-	/// Heap memory used in bytes
-	pub mem_used: usize,
-}
-
-impl SyncStatus {
-	/// Indicates if initial sync is still in progress.
-	pub fn is_major_syncing(&self) -> bool {
-		self.state != SyncState::Idle && self.state != SyncState::NewBlocks
-	}
-}
+const SNAPSHOT_MANIFEST_TIMEOUT_SEC: f64 = 5f64;
+const SNAPSHOT_DATA_TIMEOUT_SEC: f64 = 60f64;
+const NODE_DATA_TIMEOUT_SEC: f64 = 10f64;
+const WAIT_PEERS_TIMEOUT_SEC: f64 = 5f64;
 
 #[derive(PartialEq, Eq, Debug, Clone)]
 /// Peer data type requested
@@ -194,6 +171,10 @@ enum PeerAsking {
 	BlockHeaders,
 	BlockBodies,
 	Heads,
+	SnapshotManifest,
+	SnapshotHeader,
+	SnapshotData,
+	NodeData,
 }
 
 #[derive(Clone)]
@@ -221,6 +202,22 @@ struct PeerInfo {
 	ask_time: f64,
 	/// Pending request is expird and result should be ignored
 	expired: bool,
+	/// Peer client version
+	client_version: String,
+	/// Peer snapshot manifest hash, if any
+	snapshot_hash: Option<H256>,
+	/// Block number of the peer snapshot, if any
+	snapshot_number: Option<BlockNumber>,
+	/// Hash of the snapshot chunk being requested
+	asking_snapshot_data: Option<H256>,
+	/// Hashes of the missing state nodes being requested
+	asking_node_data: Vec<H256>,
+}
+
+impl PeerInfo {
+	fn has_snapshot(&self, hash: &Option<H256>) -> bool {
+		hash.is_some() && &self.snapshot_hash == hash
+	}
 }
 
 /// Blockchain sync handler.
@@ -254,6 +251,16 @@ pub struct ChainSync {
 	round_parents: VecDeque<(H256, H256)>,
 	/// Network ID
 	network_id: U256,
+	/// Restore from a snapshot before syncing blocks
+	enable_warp_sync: bool,
+	/// Snapshot being downloaded
+	snapshot: Snapshot,
+	/// Time the first peer connected. Used to stop waiting for peers with snapshots.
+	sync_start_time: Option<f64>,
+	/// Missing state nodes being requested from any peer
+	node_data_requested: HashSet<H256>,
+	/// Minimum eth protocol version of peers
+	min_peer_protocol_version: u32,
 }
 
 type RlpResponseResult = Result<Option<(PacketId, RlpStream)>, PacketDecodeError>;
@@ -262,6 +269,7 @@ impl ChainSync {
 	/// Create a new instance of syncing strategy.
 	pub fn new(config: SyncConfig, chain: &BlockChainClient) -> ChainSync {
 		let chain = chain.chain_info();
+		let warp_sync = config.warp_sync && chain.best_block_number == 0;
 		let mut sync = ChainSync {
 			state: SyncState::ChainHead,
 			starting_block: chain.best_block_number,
@@ -277,6 +285,11 @@ impl ChainSync {
 			round_parents: VecDeque::new(),
 			_max_download_ahead_blocks: max(MAX_HEADERS_TO_REQUEST, config.max_download_ahead_blocks),
 			network_id: config.network_id,
+			enable_warp_sync: warp_sync,
+			snapshot: Snapshot::new(),
+			sync_start_time: None,
+			node_data_requested: HashSet::new(),
+			min_peer_protocol_version: config.min_peer_protocol_version,
 		};
 		sync.reset();
 		sync
@@ -299,7 +312,24 @@ impl ChainSync {
 				self.blocks.heap_size()
 				+ self.peers.heap_size_of_children()
 				+ self.round_parents.heap_size_of_children(),
+			num_snapshot_chunks: self.snapshot.total_chunks(),
+			snapshot_chunks_done: self.snapshot.done_chunks(),
+		}
+	}
+
+	/// Returns the number of connected peers by protocol and client version
+	pub fn peer_versions(&self) -> Vec<PeerVersionCount> {
+		let mut counts = BTreeMap::new();
+		for peer in self.peers.values() {
+			*counts.entry((peer.protocol_version, peer.client_version.clone())).or_insert(0) += 1;
 		}
+		counts.into_iter()
+			.map(|((protocol_version, client_version), count)| PeerVersionCount {
+				protocol_version: protocol_version,
+				client_version: client_version,
+				count: count,
+			})
+			.collect()
 	}
 
 	/// Abort all sync activity
@@ -358,7 +388,7 @@ impl ChainSync {
 
 	/// Called by peer to report status
 	fn on_peer_status(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
-		let peer = PeerInfo {
+		let mut peer = PeerInfo {
 			protocol_version: try!(r.val_at(0)),
 			network_id: try!(r.val_at(1)),
 			difficulty: Some(try!(r.val_at(2))),
@@ -370,9 +400,22 @@ impl ChainSync {
 			asking_hash: None,
 			ask_time: 0f64,
 			expired: false,
+			client_version: io.peer_info(peer_id),
+			snapshot_hash: None,
+			snapshot_number: None,
+			asking_snapshot_data: None,
+			asking_node_data: Vec::new(),
 		};
+		if io.protocol_version(WARP_SYNC_PROTOCOL, peer_id) != 0 {
+			let snapshot_hash: H256 = try!(r.val_at(5));
+			let snapshot_number: BlockNumber = try!(r.val_at(6));
+			if !snapshot_hash.is_zero() {
+				peer.snapshot_hash = Some(snapshot_hash);
+				peer.snapshot_number = Some(snapshot_number);
+			}
+		}
 
-		trace!(target: "sync", "New peer {} (protocol: {}, network: {:?}, difficulty: {:?}, latest:{}, genesis:{})", peer_id, peer.protocol_version, peer.network_id, peer.difficulty, peer.latest_hash, peer.genesis);
+		trace!(target: "sync", "New peer {} (protocol: {}, network: {:?}, difficulty: {:?}, latest:{}, genesis:{}, snapshot:{:?})", peer_id, peer.protocol_version, peer.network_id, peer.difficulty, peer.latest_hash, peer.genesis, peer.snapshot_number);
 		if io.is_expired() {
 			trace!(target: "sync", "Status packet from expired session {}:{}", peer_id, io.peer_info(peer_id));
 			return Ok(());
@@ -393,6 +436,11 @@ impl ChainSync {
 			trace!(target: "sync", "Peer {} network id mismatch (ours: {}, theirs: {})", peer_id, self.network_id, peer.network_id);
 			return Ok(());
 		}
+		if peer.protocol_version < self.min_peer_protocol_version {
+			io.disable_peer(peer_id);
+			trace!(target: "sync", "Peer {} protocol version too old (minimum: {}, theirs: {})", peer_id, self.min_peer_protocol_version, peer.protocol_version);
+			return Ok(());
+		}
 
 		self.peers.insert(peer_id.clone(), peer);
 		self.active_peers.insert(peer_id.clone());
@@ -404,6 +452,9 @@ impl ChainSync {
 	#[cfg_attr(feature="dev", allow(cyclomatic_complexity))]
 	/// Called by peer once it has new block headers during sync
 	fn on_peer_block_headers(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if self.peers.get(&peer_id).map_or(false, |p| p.asking == PeerAsking::SnapshotHeader) {
+			return self.on_snapshot_header(io, peer_id, r);
+		}
 		self.clear_peer_download(peer_id);
 		let expected_hash = self.peers.get(&peer_id).and_then(|p| p.asking_hash);
 		let expected_asking = if self.state == SyncState::ChainHead { PeerAsking::Heads } else { PeerAsking::BlockHeaders };
@@ -610,7 +661,7 @@ impl ChainSync {
 
 	/// Handles `NewHashes` packet. Initiates headers download for any unknown hashes.
 	fn on_peer_new_hashes(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
-		if self.state != SyncState::Idle {
+		if self.state != SyncState::Idle || self.enable_warp_sync {
 			trace!(target: "sync", "Ignoring new hashes since we're already downloading.");
 			let max = r.iter().take(MAX_NEW_HASHES).map(|item| item.val_at::<BlockNumber>(1).unwrap_or(0)).fold(0u64, max);
 			if max > self.highest_block.unwrap_or(0) {
@@ -669,6 +720,158 @@ impl ChainSync {
 		Ok(())
 	}
 
+	/// Called when peer sends us a snapshot manifest
+	fn on_snapshot_manifest(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if !self.reset_peer_asking(peer_id, PeerAsking::SnapshotManifest) || self.state != SyncState::SnapshotManifest {
+			trace!(target: "sync", "{}: Ignored unexpected manifest", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+		trace!(target: "sync", "{} -> SnapshotManifest", peer_id);
+
+		let manifest_rlp = match r.item_count() {
+			0 => None,
+			_ => Some(try!(r.at(0)).as_raw().to_vec()),
+		};
+		let expected_hash = self.peers.get(&peer_id).and_then(|p| p.snapshot_hash.clone());
+		let manifest = manifest_rlp.and_then(|raw| match ManifestData::from_rlp(&raw) {
+			Ok(manifest) => if Some(raw.sha3()) == expected_hash { Some((manifest, raw)) } else { None },
+			Err(_) => None,
+		});
+		match manifest {
+			Some((manifest, raw)) => {
+				trace!(target: "sync", "Verifying snapshot manifest for block #{} ({})", manifest.block_number, manifest.block_hash);
+				self.snapshot.reset_to(&manifest, raw);
+				self.state = SyncState::SnapshotHeader;
+				// Prefer a peer other than the one which served the manifest to vouch for the block.
+				let verifier = self.peers.iter()
+					.filter(|&(id, p)| *id != peer_id && self.active_peers.contains(id) && p.asking == PeerAsking::Nothing)
+					.map(|(id, _)| *id)
+					.next()
+					.unwrap_or(peer_id);
+				self.request_snapshot_header(io, verifier, &manifest.block_hash);
+			},
+			None => {
+				trace!(target: "sync", "{}: Snapshot manifest missing or invalid", peer_id);
+				self.forget_peer_snapshot(peer_id);
+				self.state = SyncState::Idle;
+			},
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called when peer sends us the header of the snapshot block
+	fn on_snapshot_header(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		if !self.reset_peer_asking(peer_id, PeerAsking::SnapshotHeader) || self.state != SyncState::SnapshotHeader {
+			trace!(target: "sync", "{}: Ignored unexpected snapshot header", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+		trace!(target: "sync", "{} -> BlockHeaders (snapshot block, {} entries)", peer_id, r.item_count());
+
+		let (number, hash, state_root) = self.snapshot.block().expect("snapshot header is only requested once the manifest is known; qed");
+		let verified = match r.item_count() {
+			0 => false,
+			_ => {
+				let header: BlockHeader = try!(r.val_at(0));
+				header.hash() == hash && header.number() == number && *header.state_root() == state_root
+					&& io.chain().verify_header_seal(try!(r.at(0)).as_raw().to_vec())
+			},
+		};
+		if verified {
+			debug!(target: "sync", "Downloading snapshot at block #{} ({} chunks)", number, self.snapshot.total_chunks());
+			self.snapshot.set_verified();
+			self.state = SyncState::SnapshotData;
+		} else {
+			debug!(target: "sync", "{}: Could not verify snapshot manifest against block #{} ({}), ignoring the snapshot", peer_id, number, hash);
+			let snapshot_hash = self.snapshot.snapshot_hash();
+			for peer in self.peers.values_mut().filter(|p| p.has_snapshot(&snapshot_hash)) {
+				peer.snapshot_hash = None;
+				peer.snapshot_number = None;
+			}
+			self.snapshot.clear();
+			self.state = SyncState::Idle;
+			if !self.peers.values().any(|p| p.snapshot_hash.is_some()) {
+				debug!(target: "sync", "No other peers with snapshots, syncing blocks");
+				self.enable_warp_sync = false;
+			}
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Called when peer sends us a snapshot chunk
+	fn on_snapshot_data(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.clear_peer_download(peer_id);
+		if !self.reset_peer_asking(peer_id, PeerAsking::SnapshotData) || self.state != SyncState::SnapshotData {
+			trace!(target: "sync", "{}: Ignored unexpected snapshot data", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+
+		if r.item_count() == 0 {
+			trace!(target: "sync", "{}: Snapshot chunk is no longer available", peer_id);
+			self.forget_peer_snapshot(peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+
+		let chunk: Bytes = try!(r.val_at(0));
+		match self.snapshot.validate_chunk(&chunk) {
+			Ok(hash) => {
+				trace!(target: "sync", "{} -> SnapshotData ({}), {}/{} chunks", peer_id, hash, self.snapshot.done_chunks(), self.snapshot.total_chunks());
+				io.chain().feed_snapshot_chunk(&hash, chunk);
+			},
+			Err(()) => {
+				debug!(target: "sync", "{}: Unexpected snapshot chunk", peer_id);
+				self.forget_peer_snapshot(peer_id);
+				io.disable_peer(peer_id);
+			}
+		}
+
+		if self.snapshot.is_complete() {
+			self.complete_snapshot_sync(io);
+		} else {
+			self.continue_sync(io);
+		}
+		Ok(())
+	}
+
+	/// Called when peer sends us missing state nodes
+	fn on_peer_node_data(&mut self, io: &mut SyncIo, peer_id: PeerId, r: &UntrustedRlp) -> Result<(), PacketDecodeError> {
+		self.clear_peer_download(peer_id);
+		if !self.reset_peer_asking(peer_id, PeerAsking::NodeData) {
+			trace!(target: "sync", "{}: Ignored unexpected node data", peer_id);
+			self.continue_sync(io);
+			return Ok(());
+		}
+
+		let item_count = r.item_count();
+		let mut healed = 0;
+		for i in 0..item_count {
+			let node: Bytes = try!(r.val_at(i));
+			if io.chain().feed_state_node(node) {
+				healed += 1;
+			}
+		}
+		trace!(target: "sync", "{} -> NodeData: {} of {} nodes healed", peer_id, healed, item_count);
+
+		if self.active_peers.contains(&peer_id) {
+			self.request_state_nodes(io, peer_id);
+		}
+		self.continue_sync(io);
+		Ok(())
+	}
+
+	/// Stop downloading snapshot data from a peer.
+	fn forget_peer_snapshot(&mut self, peer_id: PeerId) {
+		if let Some(peer) = self.peers.get_mut(&peer_id) {
+			peer.snapshot_hash = None;
+			peer.snapshot_number = None;
+		}
+	}
+
 	/// Called by peer when it is disconnecting
 	pub fn on_peer_aborting(&mut self, io: &mut SyncIo, peer: PeerId) {
 		trace!(target: "sync", "== Disconnecting {}: {}", peer, io.peer_info(peer));
@@ -684,7 +887,7 @@ impl ChainSync {
 	/// Called when a new peer is connected
 	pub fn on_peer_connected(&mut self, io: &mut SyncIo, peer: PeerId) {
 		trace!(target: "sync", "== Connected {}: {}", peer, io.peer_info(peer));
-		if let Err(e) = self.send_status(io) {
+		if let Err(e) = self.send_status(io, peer) {
 			debug!(target:"sync", "Error sending status request: {:?}", e);
 			io.disable_peer(peer);
 		}
@@ -700,7 +903,8 @@ impl ChainSync {
 				self.sync_peer(io, p, false);
 			}
 		}
-		if self.state != SyncState::Waiting && !self.peers.values().any(|p| p.asking != PeerAsking::Nothing && !p.expired) {
+		if self.state != SyncState::Waiting && self.state != SyncState::SnapshotWaiting
+			&& !self.peers.values().any(|p| p.asking != PeerAsking::Nothing && !p.expired) {
 			self.complete_sync();
 		}
 	}
@@ -736,6 +940,17 @@ impl ChainSync {
 			(peer.latest_hash.clone(), peer.difficulty.clone())
 		};
 		let chain_info = io.chain().chain_info();
+		if self.enable_warp_sync {
+			if chain_info.best_block_number == 0 {
+				self.sync_snapshot_peer(io, peer_id);
+			} else {
+				trace!(target: "sync", "Chain is not empty, disabling warp sync");
+				self.enable_warp_sync = false;
+			}
+			if self.enable_warp_sync {
+				return;
+			}
+		}
 		let td = chain_info.pending_total_difficulty;
 		let syncing_difficulty = max(self.syncing_difficulty, td);
 
@@ -761,11 +976,106 @@ impl ChainSync {
 						self.request_blocks(io, peer_id, false);
 					}
 				}
-				SyncState::Waiting => ()
+				SyncState::Waiting | SyncState::SnapshotManifest | SyncState::SnapshotHeader
+					| SyncState::SnapshotData | SyncState::SnapshotWaiting => ()
+			}
+		}
+	}
+
+	/// Find something to do for a peer while warp syncing. Disables warp sync if no peer
+	/// has offered a snapshot for long enough.
+	fn sync_snapshot_peer(&mut self, io: &mut SyncIo, peer_id: PeerId) {
+		match self.state {
+			SyncState::SnapshotManifest | SyncState::SnapshotHeader | SyncState::SnapshotWaiting => (),
+			SyncState::SnapshotData => {
+				let snapshot_hash = self.snapshot.snapshot_hash();
+				if self.peers.get(&peer_id).map_or(false, |p| p.has_snapshot(&snapshot_hash)) {
+					if let Some(chunk) = self.snapshot.needed_chunk() {
+						self.request_snapshot_data(io, peer_id, &chunk);
+					}
+				}
+			},
+			_ => {
+				let now = time::precise_time_s();
+				let start = match self.sync_start_time {
+					Some(start) => start,
+					None => {
+						self.sync_start_time = Some(now);
+						now
+					}
+				};
+				let current_snapshot = self.snapshot.snapshot_hash();
+				let best_peer = self.peers.iter()
+					.filter(|&(id, p)| self.active_peers.contains(id) && p.snapshot_number.is_some() && p.asking == PeerAsking::Nothing)
+					.max_by_key(|&(_, p)| p.snapshot_number)
+					.map(|(id, p)| (*id, p.snapshot_hash.clone()));
+				match best_peer {
+					Some((_, ref hash)) if current_snapshot.is_some() && *hash == current_snapshot => {
+						if self.snapshot.is_complete() {
+							trace!(target: "sync", "Snapshot is being restored");
+							self.state = SyncState::SnapshotWaiting;
+						} else if self.snapshot.is_verified() {
+							trace!(target: "sync", "Resuming snapshot download");
+							self.state = SyncState::SnapshotData;
+							self.sync_snapshot_peer(io, peer_id);
+						} else {
+							trace!(target: "sync", "Resuming snapshot manifest verification");
+							let (_, hash, _) = self.snapshot.block().expect("current_snapshot is some; the manifest is known; qed");
+							self.state = SyncState::SnapshotHeader;
+							self.request_snapshot_header(io, peer_id, &hash);
+						}
+					},
+					Some((best_id, _)) => {
+						trace!(target: "sync", "Starting snapshot sync with {}", best_id);
+						self.state = SyncState::SnapshotManifest;
+						self.request_snapshot_manifest(io, best_id);
+					},
+					None if now - start > WAIT_PEERS_TIMEOUT_SEC => {
+						debug!(target: "sync", "No peers with snapshots, syncing blocks");
+						self.enable_warp_sync = false;
+					},
+					None => trace!(target: "sync", "Waiting for peers with snapshots"),
+				}
 			}
 		}
 	}
 
+	/// Ask the client to restore the downloaded snapshot. Sync waits for the restoration in `maintain_sync`.
+	fn complete_snapshot_sync(&mut self, io: &mut SyncIo) {
+		let manifest = self.snapshot.manifest().cloned().expect("snapshot is only complete once the manifest is known; qed");
+		if io.chain().restore_snapshot(manifest) {
+			debug!(target: "sync", "Snapshot downloaded, waiting for restoration");
+			self.state = SyncState::SnapshotWaiting;
+		} else {
+			warn!(target: "sync", "Error queueing snapshot restoration, syncing blocks from genesis");
+			self.finish_warp_sync(io);
+		}
+	}
+
+	/// Check if the client is done restoring the snapshot and continue with block sync.
+	fn check_snapshot_restored(&mut self, io: &mut SyncIo) {
+		if io.chain().is_restoring_snapshot() {
+			return;
+		}
+		if io.chain().chain_info().best_block_number == 0 {
+			warn!(target: "sync", "Error restoring snapshot, syncing blocks from genesis");
+		} else {
+			debug!(target: "sync", "Snapshot restored");
+		}
+		self.finish_warp_sync(io);
+	}
+
+	/// Disable warp sync and continue with block sync from the current best block.
+	fn finish_warp_sync(&mut self, io: &mut SyncIo) {
+		self.snapshot.clear();
+		self.enable_warp_sync = false;
+		let chain = io.chain().chain_info();
+		self.starting_block = chain.best_block_number;
+		self.last_imported_block = chain.best_block_number;
+		self.last_imported_hash = chain.best_block_hash;
+		self.restart(io);
+	}
+
 	fn start_sync_round(&mut self, io: &mut SyncIo) {
 		self.state = SyncState::ChainHead;
 		trace!(target: "sync", "Starting round (last imported count = {:?}, block = {:?}", self.imported_this_round, self.last_imported_block);
@@ -834,6 +1144,16 @@ impl ChainSync {
 					self.blocks.clear_body_download(b);
 				}
 			},
+			PeerAsking::SnapshotData => {
+				if let Some(hash) = peer.asking_snapshot_data.take() {
+					self.snapshot.clear_chunk_download(&hash);
+				}
+			},
+			PeerAsking::NodeData => {
+				for h in peer.asking_node_data.drain(..) {
+					self.node_data_requested.remove(&h);
+				}
+			},
 			_ => (),
 		}
 		peer.asking_blocks.clear();
@@ -932,6 +1252,66 @@ impl ChainSync {
 		self.send_request(sync, peer_id, PeerAsking::BlockBodies, GET_BLOCK_BODIES_PACKET, rlp.out());
 	}
 
+	/// Request snapshot manifest from a peer.
+	fn request_snapshot_manifest(&mut self, sync: &mut SyncIo, peer_id: PeerId) {
+		trace!(target: "sync", "{} <- GetSnapshotManifest", peer_id);
+		let rlp = RlpStream::new_list(0);
+		self.send_request(sync, peer_id, PeerAsking::SnapshotManifest, GET_SNAPSHOT_MANIFEST_PACKET, rlp.out());
+	}
+
+	/// Request the header of the snapshot block from a peer.
+	fn request_snapshot_header(&mut self, sync: &mut SyncIo, peer_id: PeerId, hash: &H256) {
+		self.request_headers_by_hash(sync, peer_id, hash, 1, 0, false, PeerAsking::SnapshotHeader);
+	}
+
+	/// Request snapshot chunk from a peer.
+	fn request_snapshot_data(&mut self, sync: &mut SyncIo, peer_id: PeerId, chunk: &H256) {
+		trace!(target: "sync", "{} <- GetSnapshotData {:?}", peer_id, chunk);
+		let mut rlp = RlpStream::new_list(1);
+		rlp.append(chunk);
+		self.send_request(sync, peer_id, PeerAsking::SnapshotData, GET_SNAPSHOT_DATA_PACKET, rlp.out());
+		self.peers.get_mut(&peer_id)
+			.expect("peer_id may originate either from on_packet, where it is already validated or from enumerating self.peers. qed")
+			.asking_snapshot_data = Some(chunk.clone());
+	}
+
+	/// Request state nodes missing from the local database from a peer.
+	/// Returns `false` if there was nothing left to request.
+	fn request_state_nodes(&mut self, sync: &mut SyncIo, peer_id: PeerId) -> bool {
+		let hashes: Vec<H256> = sync.chain().missing_state_nodes(self.node_data_requested.len() + MAX_NODE_DATA_TO_REQUEST).into_iter()
+			.filter(|h| !self.node_data_requested.contains(h))
+			.take(MAX_NODE_DATA_TO_REQUEST)
+			.collect();
+		if hashes.is_empty() {
+			return false;
+		}
+
+		trace!(target: "sync", "{} <- GetNodeData: {} entries", peer_id, hashes.len());
+		let mut rlp = RlpStream::new_list(hashes.len());
+		for h in &hashes {
+			rlp.append(h);
+			self.node_data_requested.insert(h.clone());
+		}
+		self.send_request(sync, peer_id, PeerAsking::NodeData, GET_NODE_DATA_PACKET, rlp.out());
+		self.peers.get_mut(&peer_id)
+			.expect("peer_id may originate either from on_packet, where it is already validated or from enumerating self.peers. qed")
+			.asking_node_data = hashes;
+		true
+	}
+
+	/// Ask idle peers for state nodes missing from the local database.
+	fn heal_state(&mut self, io: &mut SyncIo) {
+		let idle_peers: Vec<PeerId> = self.peers.iter()
+			.filter(|&(id, p)| self.active_peers.contains(id) && p.asking == PeerAsking::Nothing)
+			.map(|(id, _)| *id)
+			.collect();
+		for peer_id in idle_peers {
+			if !self.request_state_nodes(io, peer_id) {
+				break;
+			}
+		}
+	}
+
 	/// Reset peer status after request is complete.
 	fn reset_peer_asking(&mut self, peer_id: PeerId, asking: PeerAsking) -> bool {
 		let peer = self.peers.get_mut(&peer_id).unwrap();
@@ -989,14 +1369,23 @@ impl ChainSync {
 	}
 
 	/// Send Status message
-	fn send_status(&mut self, io: &mut SyncIo) -> Result<(), UtilError> {
-		let mut packet = RlpStream::new_list(5);
+	fn send_status(&mut self, io: &mut SyncIo, peer: PeerId) -> Result<(), UtilError> {
+		// `par` status is the `eth` status followed by the snapshot manifest hash and block number.
+		let warp_protocol = io.protocol_version(WARP_SYNC_PROTOCOL, peer) != 0;
+		let mut packet = RlpStream::new_list(if warp_protocol { 7 } else { 5 });
 		let chain = io.chain().chain_info();
 		packet.append(&(PROTOCOL_VERSION as u32));
 		packet.append(&self.network_id);
 		packet.append(&chain.total_difficulty);
 		packet.append(&chain.best_block_hash);
 		packet.append(&chain.genesis_hash);
+		if warp_protocol {
+			let manifest = io.chain().snapshot_manifest();
+			let block_number = manifest.as_ref().and_then(|m| ManifestData::from_rlp(m).ok()).map_or(0, |m| m.block_number);
+			let manifest_hash = manifest.map_or_else(H256::new, |m| m.sha3());
+			packet.append(&manifest_hash);
+			packet.append(&block_number);
+		}
 		io.respond(STATUS_PACKET, packet.out())
 	}
 
@@ -1133,6 +1522,46 @@ impl ChainSync {
 		Ok(Some((RECEIPTS_PACKET, rlp_result)))
 	}
 
+	/// Respond to GetSnapshotManifest request
+	fn return_snapshot_manifest(io: &SyncIo, r: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
+		trace!(target: "sync", "{} -> GetSnapshotManifest", peer_id);
+		if r.item_count() != 0 {
+			debug!(target: "sync", "Invalid GetSnapshotManifest request, ignoring.");
+			return Ok(None);
+		}
+		let rlp = match io.chain().snapshot_manifest() {
+			Some(manifest) => {
+				trace!(target: "sync", "{} <- SnapshotManifest", peer_id);
+				let mut rlp = RlpStream::new_list(1);
+				rlp.append_raw(&manifest, 1);
+				rlp
+			},
+			None => {
+				trace!(target: "sync", "{}: No snapshot manifest to return", peer_id);
+				RlpStream::new_list(0)
+			},
+		};
+		Ok(Some((SNAPSHOT_MANIFEST_PACKET, rlp)))
+	}
+
+	/// Respond to GetSnapshotData request
+	fn return_snapshot_data(io: &SyncIo, r: &UntrustedRlp, peer_id: PeerId) -> RlpResponseResult {
+		let hash: H256 = try!(r.val_at(0));
+		trace!(target: "sync", "{} -> GetSnapshotData {:?}", peer_id, hash);
+		let rlp = match io.chain().snapshot_chunk(&hash) {
+			Some(data) => {
+				let mut rlp = RlpStream::new_list(1);
+				rlp.append(&data);
+				rlp
+			},
+			None => {
+				trace!(target: "sync", "{}: No snapshot chunk {:?} to return", peer_id, hash);
+				RlpStream::new_list(0)
+			},
+		};
+		Ok(Some((SNAPSHOT_DATA_PACKET, rlp)))
+	}
+
 	fn return_rlp<FRlp, FError>(io: &mut SyncIo, rlp: &UntrustedRlp, peer: PeerId, rlp_func: FRlp, error_func: FError) -> Result<(), PacketDecodeError>
 		where FRlp : Fn(&SyncIo, &UntrustedRlp, PeerId) -> RlpResponseResult,
 			FError : FnOnce(UtilError) -> String
@@ -1169,6 +1598,14 @@ impl ChainSync {
 				ChainSync::return_node_data,
 				|e| format!("Error sending nodes: {:?}", e)),
 
+			GET_SNAPSHOT_MANIFEST_PACKET => ChainSync::return_rlp(io, &rlp, peer,
+				ChainSync::return_snapshot_manifest,
+				|e| format!("Error sending snapshot manifest: {:?}", e)),
+
+			GET_SNAPSHOT_DATA_PACKET => ChainSync::return_rlp(io, &rlp, peer,
+				ChainSync::return_snapshot_data,
+				|e| format!("Error sending snapshot data: {:?}", e)),
+
 			_ => {
 				sync.write().on_packet(io, peer, packet_id, data);
 				Ok(())
@@ -1192,6 +1629,9 @@ impl ChainSync {
 			BLOCK_BODIES_PACKET => self.on_peer_block_bodies(io, peer, &rlp),
 			NEW_BLOCK_PACKET => self.on_peer_new_block(io, peer, &rlp),
 			NEW_BLOCK_HASHES_PACKET => self.on_peer_new_hashes(io, peer, &rlp),
+			SNAPSHOT_MANIFEST_PACKET => self.on_snapshot_manifest(io, peer, &rlp),
+			SNAPSHOT_DATA_PACKET => self.on_snapshot_data(io, peer, &rlp),
+			NODE_DATA_PACKET => self.on_peer_node_data(io, peer, &rlp),
 			_ => {
 				debug!(target: "sync", "Unknown packet {}", packet_id);
 				Ok(())
@@ -1209,6 +1649,10 @@ impl ChainSync {
 			let timeout = match peer.asking {
 				PeerAsking::BlockHeaders | PeerAsking::Heads => (tick - peer.ask_time) > HEADERS_TIMEOUT_SEC,
 				PeerAsking::BlockBodies => (tick - peer.ask_time) > BODIES_TIMEOUT_SEC,
+				PeerAsking::SnapshotManifest => (tick - peer.ask_time) > SNAPSHOT_MANIFEST_TIMEOUT_SEC,
+				PeerAsking::SnapshotHeader => (tick - peer.ask_time) > HEADERS_TIMEOUT_SEC,
+				PeerAsking::SnapshotData => (tick - peer.ask_time) > SNAPSHOT_DATA_TIMEOUT_SEC,
+				PeerAsking::NodeData => (tick - peer.ask_time) > NODE_DATA_TIMEOUT_SEC,
 				PeerAsking::Nothing => false,
 			};
 			if timeout {
@@ -1397,6 +1841,32 @@ impl ChainSync {
 		sent
 	}
 
+	/// sends local transactions which haven't made it to the network yet to all peers
+	fn rebroadcast_transactions(&mut self, io: &mut SyncIo) -> usize {
+		// don't use up the attempts while there's nobody to send to.
+		if self.peers.is_empty() {
+			return 0;
+		}
+
+		let transactions = io.chain().transactions_to_rebroadcast();
+		if transactions.is_empty() {
+			return 0;
+		}
+
+		let mut packet = RlpStream::new_list(transactions.len());
+		for tx in &transactions {
+			packet.append(tx);
+		}
+		let rlp = packet.out();
+
+		let peers: Vec<PeerId> = self.peers.keys().cloned().collect();
+		for peer_id in &peers {
+			self.send_packet(io, *peer_id, TRANSACTIONS_PACKET, rlp.clone());
+		}
+		debug!(target: "sync", "Re-broadcast {} transactions to {} peers.", transactions.len(), peers.len());
+		peers.len()
+	}
+
 	fn propagate_latest_blocks(&mut self, io: &mut SyncIo, sealed: &[H256]) {
 		let chain_info = io.chain().chain_info();
 		if (((chain_info.best_block_number as i64) - (self.last_sent_block_number as i64)).abs() as BlockNumber) < MAX_PEER_LAG_PROPAGATION {
@@ -1413,6 +1883,19 @@ impl ChainSync {
 	/// Maintain other peers. Send out any new blocks and transactions
 	pub fn maintain_sync(&mut self, io: &mut SyncIo) {
 		self.check_resume(io);
+		if self.state == SyncState::SnapshotWaiting {
+			self.check_snapshot_restored(io);
+		}
+		if self.state == SyncState::Idle {
+			self.rebroadcast_transactions(io);
+		}
+		if self.enable_warp_sync && self.state == SyncState::Idle {
+			// re-check if any peer has a snapshot or if it's time to give up waiting.
+			self.continue_sync(io);
+		}
+		if !self.enable_warp_sync && self.state != SyncState::Waiting {
+			self.heal_state(io);
+		}
 	}
 
 	/// called when block is imported to chain, updates transactions queue and propagates the blocks
@@ -1627,6 +2110,11 @@ mod tests {
 				asking_hash: None,
 				ask_time: 0f64,
 				expired: false,
+				client_version: String::new(),
+				snapshot_hash: None,
+				snapshot_number: None,
+				asking_snapshot_data: None,
+				asking_node_data: Vec::new(),
 			});
 		sync
 	}
@@ -1699,6 +2187,44 @@ mod tests {
 		assert_eq!(0x07, io.queue[0].packet_id);
 	}
 
+	#[test]
+	fn rebroadcasts_local_transactions() {
+		use std::time::Duration;
+		use ethcore::miner::RebroadcastPolicy;
+		use ethcore::transaction::{Transaction, Action};
+
+		let mut client = TestBlockChainClient::new();
+		client.add_blocks(1, EachBlockWith::Nothing);
+		let keypair = KeyPair::create().unwrap();
+		client.set_balance(keypair.address(), U256::from(1_000_000_000));
+		let tx = Transaction {
+			action: Action::Create,
+			value: U256::zero(),
+			data: vec![],
+			gas: U256::from(100_000),
+			gas_price: U256::one(),
+			nonce: U256::zero(),
+		}.sign(keypair.secret());
+		let hash = tx.hash();
+		client.miner.import_own_transaction(&client, tx).unwrap();
+		assert!(client.miner.set_transaction_rebroadcast(&hash, RebroadcastPolicy {
+			interval: Duration::from_secs(0),
+			backoff: 1,
+			max_attempts: 1,
+		}));
+
+		let mut queue = VecDeque::new();
+		let mut sync = dummy_sync_with_peer(client.block_hash_delta_minus(1), &client);
+		let mut io = TestIo::new(&mut client, &mut queue, None);
+		assert_eq!(1, sync.rebroadcast_transactions(&mut io));
+		// attempts exhausted.
+		assert_eq!(0, sync.rebroadcast_transactions(&mut io));
+
+		assert_eq!(1, io.queue.len());
+		// TRANSACTIONS_PACKET
+		assert_eq!(0x02, io.queue[0].packet_id);
+	}
+
 	#[test]
 	fn sends_sealed_block() {
 		let mut client = TestBlockChainClient::new();
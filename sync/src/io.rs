@@ -15,8 +15,10 @@
 // along with Parity.  If not, see <http://www.gnu.org/licenses/>.
 
 use util::{NetworkContext, PeerId, PacketId,};
+use util::network::ProtocolId;
 use util::error::UtilError;
 use ethcore::client::BlockChainClient;
+use api::{ETH_PROTOCOL, WARP_SYNC_PROTOCOL};
 
 /// IO interface for the syning handler.
 /// Provides peer connection management and an interface to the blockchain client.
@@ -42,6 +44,8 @@ pub trait SyncIo {
 	}
 	/// Check if the session is expired
 	fn is_expired(&self) -> bool;
+	/// Returns the version of the given protocol negotiated with the peer, or 0 if the peer does not support it
+	fn protocol_version(&self, protocol: ProtocolId, peer_id: PeerId) -> u8;
 }
 
 /// Wraps `NetworkContext` and the blockchain client
@@ -74,7 +78,12 @@ impl<'s, 'h> SyncIo for NetSyncIo<'s, 'h> {
 	}
 
 	fn send(&mut self, peer_id: PeerId, packet_id: PacketId, data: Vec<u8>) -> Result<(), UtilError>{
-		self.network.send(peer_id, packet_id, data)
+		// `par` carries all `eth` packets, so peers supporting it are only talked to over `par`
+		if self.network.protocol_version(WARP_SYNC_PROTOCOL, peer_id).is_some() {
+			self.network.send_protocol(WARP_SYNC_PROTOCOL, peer_id, packet_id, data)
+		} else {
+			self.network.send_protocol(ETH_PROTOCOL, peer_id, packet_id, data)
+		}
 	}
 
 	fn chain(&self) -> &BlockChainClient {
@@ -88,6 +97,10 @@ impl<'s, 'h> SyncIo for NetSyncIo<'s, 'h> {
 	fn is_expired(&self) -> bool {
 		self.network.is_expired()
 	}
+
+	fn protocol_version(&self, protocol: ProtocolId, peer_id: PeerId) -> u8 {
+		self.network.protocol_version(protocol, peer_id).unwrap_or(0)
+	}
 }
 
 
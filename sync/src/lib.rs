@@ -25,6 +25,8 @@
 //! Blockchain sync module
 //! Implements ethereum protocol version 63 as specified here:
 //! https://github.com/ethereum/wiki/wiki/Ethereum-Wire-Protocol
+//! Version 64 extends it with packets for downloading state snapshots and is
+//! only negotiated between Parity peers.
 //!
 //! Usage example:
 //!
@@ -42,15 +44,16 @@
 //!
 //! fn main() {
 //! 	let dir = env::temp_dir();
+//! 	let spec = ethereum::new_frontier();
 //! 	let miner = Miner::new(
 //! 		Default::default(),
 //! 		GasPricer::new_fixed(20_000_000_000u64.into()),
-//! 		ethereum::new_frontier(),
+//! 		&spec,
 //! 		None
 //! 	);
 //! 	let client = Client::new(
 //!			ClientConfig::default(),
-//!			ethereum::new_frontier(),
+//!			&spec,
 //!			&dir,
 //!			miner,
 //!			IoChannel::disconnected()
@@ -78,6 +81,7 @@ extern crate parking_lot;
 mod chain;
 mod blocks;
 mod io;
+mod snapshot;
 
 #[cfg(test)]
 mod tests;
@@ -88,6 +92,5 @@ mod api {
 }
 
 pub use api::{EthSync, SyncProvider, SyncClient, NetworkManagerClient, ManageNetwork, SyncConfig,
-	NetworkConfiguration, ServiceConfiguration};
-pub use chain::{SyncStatus, SyncState};
+	NetworkConfiguration, ServiceConfiguration, PeerVersionCount, PeerInfo, SyncStatus, SyncState};
 
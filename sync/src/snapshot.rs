@@ -0,0 +1,192 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+use util::*;
+use ethcore::header::BlockNumber;
+use ethcore::snapshot::ManifestData;
+
+/// Snapshot being downloaded. Keeps track of which chunks are still needed,
+/// which are being downloaded and which have already been received.
+#[derive(Default)]
+pub struct Snapshot {
+	/// Raw manifest of the snapshot.
+	manifest: Option<Bytes>,
+	/// Hash of the raw manifest.
+	snapshot_hash: Option<H256>,
+	/// Number, hash and state root of the snapshot block.
+	block: Option<(BlockNumber, H256, H256)>,
+	/// Whether the manifest has been checked against the header of the snapshot block.
+	verified: bool,
+	/// All chunks listed in the manifest, state chunks first.
+	chunks: Vec<H256>,
+	/// Chunks being downloaded.
+	downloading_chunks: HashSet<H256>,
+	/// Chunks received and stored by the client.
+	completed_chunks: HashSet<H256>,
+}
+
+impl Snapshot {
+	/// Create a new instance.
+	pub fn new() -> Snapshot {
+		Snapshot::default()
+	}
+
+	/// Clear everything.
+	pub fn clear(&mut self) {
+		self.manifest = None;
+		self.snapshot_hash = None;
+		self.block = None;
+		self.verified = false;
+		self.chunks.clear();
+		self.downloading_chunks.clear();
+		self.completed_chunks.clear();
+	}
+
+	/// Start downloading the snapshot described by the given raw manifest.
+	pub fn reset_to(&mut self, manifest: &ManifestData, manifest_rlp: Bytes) {
+		self.clear();
+		self.snapshot_hash = Some(manifest_rlp.sha3());
+		self.manifest = Some(manifest_rlp);
+		self.block = Some((manifest.block_number, manifest.block_hash.clone(), manifest.state_root.clone()));
+		self.chunks = manifest.state_hashes.iter().chain(manifest.block_hashes.iter()).cloned().collect();
+	}
+
+	/// Mark the manifest as matching the header of the snapshot block.
+	pub fn set_verified(&mut self) {
+		self.verified = true;
+	}
+
+	/// Check if the manifest has been verified. Chunks are only downloaded for verified manifests.
+	pub fn is_verified(&self) -> bool {
+		self.verified
+	}
+
+	/// Number, hash and state root of the snapshot block, as claimed by the manifest.
+	pub fn block(&self) -> Option<(BlockNumber, H256, H256)> {
+		self.block.clone()
+	}
+
+	/// Find a chunk to download and mark it as being downloaded.
+	pub fn needed_chunk(&mut self) -> Option<H256> {
+		if !self.verified {
+			return None;
+		}
+		let chunk = self.chunks.iter()
+			.find(|&h| !self.downloading_chunks.contains(h) && !self.completed_chunks.contains(h))
+			.cloned();
+		if let Some(ref hash) = chunk {
+			self.downloading_chunks.insert(hash.clone());
+		}
+		chunk
+	}
+
+	/// Unmark a chunk as being downloaded.
+	pub fn clear_chunk_download(&mut self, hash: &H256) {
+		self.downloading_chunks.remove(hash);
+	}
+
+	/// Check that the chunk data is a part of the snapshot and mark it as completed.
+	/// Returns the chunk hash.
+	pub fn validate_chunk(&mut self, chunk: &[u8]) -> Result<H256, ()> {
+		let hash = chunk.sha3();
+		if self.completed_chunks.contains(&hash) || !self.chunks.contains(&hash) {
+			return Err(());
+		}
+		self.downloading_chunks.remove(&hash);
+		self.completed_chunks.insert(hash.clone());
+		Ok(hash)
+	}
+
+	/// Raw manifest of the snapshot being downloaded.
+	pub fn manifest(&self) -> Option<&Bytes> {
+		self.manifest.as_ref()
+	}
+
+	/// Hash of the snapshot manifest being downloaded.
+	pub fn snapshot_hash(&self) -> Option<H256> {
+		self.snapshot_hash.clone()
+	}
+
+	/// Total number of chunks in the snapshot.
+	pub fn total_chunks(&self) -> usize {
+		self.chunks.len()
+	}
+
+	/// Number of chunks received so far.
+	pub fn done_chunks(&self) -> usize {
+		self.completed_chunks.len()
+	}
+
+	/// Check if all chunks have been received.
+	pub fn is_complete(&self) -> bool {
+		self.manifest.is_some() && self.completed_chunks.len() == self.chunks.len()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Snapshot;
+	use ethcore::snapshot::{Codec, ManifestData};
+	use util::*;
+
+	fn test_manifest() -> (ManifestData, Bytes, Vec<Bytes>) {
+		let state_chunks: Vec<Bytes> = (0..3).map(|i| vec![i; 32]).collect();
+		let block_chunks: Vec<Bytes> = (3..5).map(|i| vec![i; 32]).collect();
+		let manifest = ManifestData {
+			version: 2,
+			codec: Codec::Snappy,
+			state_hashes: state_chunks.iter().map(|c| c.sha3()).collect(),
+			block_hashes: block_chunks.iter().map(|c| c.sha3()).collect(),
+			state_root: H256::new(),
+			block_number: 100,
+			block_hash: H256::new(),
+		};
+		let raw = manifest.into_rlp();
+		(ManifestData::from_rlp(&raw).unwrap(), raw, state_chunks.into_iter().chain(block_chunks.into_iter()).collect())
+	}
+
+	#[test]
+	fn tracks_chunks() {
+		let mut snapshot = Snapshot::new();
+		let (manifest, raw, chunks) = test_manifest();
+		snapshot.reset_to(&manifest, raw.clone());
+		assert_eq!(snapshot.snapshot_hash(), Some(raw.sha3()));
+		assert_eq!(snapshot.block(), Some((100, H256::new(), H256::new())));
+		assert_eq!(snapshot.total_chunks(), 5);
+		assert!(snapshot.needed_chunk().is_none());
+		snapshot.set_verified();
+
+		let requested: Vec<_> = (0..5).filter_map(|_| snapshot.needed_chunk()).collect();
+		assert_eq!(requested.len(), 5);
+		assert!(snapshot.needed_chunk().is_none());
+
+		snapshot.clear_chunk_download(&requested[0]);
+		assert_eq!(snapshot.needed_chunk(), Some(requested[0].clone()));
+
+		assert!(snapshot.validate_chunk(b"not a part of the snapshot").is_err());
+		for chunk in &chunks {
+			assert_eq!(snapshot.validate_chunk(chunk), Ok(chunk.sha3()));
+		}
+		assert!(snapshot.validate_chunk(&chunks[0]).is_err());
+		assert_eq!(snapshot.done_chunks(), 5);
+		assert!(snapshot.is_complete());
+
+		snapshot.clear();
+		assert!(!snapshot.is_complete());
+		assert!(snapshot.manifest().is_none());
+		assert!(!snapshot.is_verified());
+	}
+}
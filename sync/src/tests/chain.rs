@@ -16,8 +16,10 @@
 
 use util::*;
 use ethcore::client::{BlockChainClient, BlockID, EachBlockWith};
-use chain::{SyncState};
+use ethcore::snapshot::{Codec, ManifestData};
+use SyncState;
 use super::helpers::*;
+use ::{SyncConfig, PeerVersionCount};
 
 #[test]
 fn two_peers() {
@@ -202,3 +204,92 @@ fn high_td_attach() {
 
 	assert_eq!(net.peer(0).chain.chain_info().best_block_number, 5);
 }
+
+/// Make peer 1 serve a snapshot of its block #100 claiming the given state root.
+fn serve_snapshot(net: &mut TestNet, state_root: H256) -> Bytes {
+	net.peer_mut(1).chain.add_blocks(100, EachBlockWith::Uncle);
+	let chunks: Vec<Bytes> = (0..3).map(|i| vec![i; 64]).collect();
+	let manifest = ManifestData {
+		version: 2,
+		codec: Codec::Snappy,
+		state_hashes: vec![chunks[0].sha3(), chunks[1].sha3()],
+		block_hashes: vec![chunks[2].sha3()],
+		state_root: state_root,
+		block_number: 100,
+		block_hash: net.peer(1).chain.block_hash(BlockID::Number(100)).unwrap(),
+	}.into_rlp();
+	*net.peer(1).chain.snapshot_manifest.write() = Some(manifest.clone());
+	for chunk in &chunks {
+		net.peer(1).chain.snapshot_chunks.write().insert(chunk.sha3(), chunk.clone());
+	}
+	manifest
+}
+
+#[test]
+fn warp_sync() {
+	::env_logger::init().ok();
+	let mut config = SyncConfig::default();
+	config.warp_sync = true;
+	let mut net = TestNet::new_with_config(2, config);
+	let manifest = serve_snapshot(&mut net, SHA3_NULL_RLP);
+
+	net.sync();
+	assert_eq!(*net.peer(0).chain.restored_manifest.read(), Some(manifest));
+	assert_eq!(net.peer(0).chain.snapshot_chunks.read().len(), 3);
+	// test client doesn't import restored blocks, so they are downloaded afterwards
+	assert!(net.peer(0).chain.block(BlockID::Number(100)).is_some());
+	assert_eq!(net.peer(0).sync.read().status().state, SyncState::Idle);
+}
+
+#[test]
+fn warp_sync_ignores_snapshot_not_matching_block() {
+	::env_logger::init().ok();
+	let mut config = SyncConfig::default();
+	config.warp_sync = true;
+	let mut net = TestNet::new_with_config(2, config);
+	// block #100 of the test client has an empty state
+	serve_snapshot(&mut net, H256::from(1));
+
+	net.sync();
+	assert!(net.peer(0).chain.restored_manifest.read().is_none());
+	assert!(net.peer(0).chain.snapshot_chunks.read().is_empty());
+	assert!(net.peer(0).chain.block(BlockID::Number(100)).is_some());
+	assert_eq!(net.peer(0).sync.read().status().state, SyncState::Idle);
+}
+
+#[test]
+fn heals_state() {
+	::env_logger::init().ok();
+	let mut net = TestNet::new(2);
+	net.peer_mut(0).chain.add_blocks(10, EachBlockWith::Nothing);
+	net.peer_mut(1).chain.add_blocks(10, EachBlockWith::Nothing);
+
+	let nodes: Vec<Bytes> = (0..3).map(|i| vec![i; 32]).collect();
+	*net.peer(0).chain.missing_state_nodes.write() = nodes.iter().map(|n| n.sha3()).collect();
+	*net.peer(1).chain.state_nodes.write() = nodes.clone();
+
+	net.sync_steps(10);
+	assert!(net.peer(0).chain.missing_state_nodes.read().is_empty());
+	assert_eq!(*net.peer(0).chain.state_nodes.read(), nodes);
+}
+
+#[test]
+fn counts_peer_versions() {
+	let mut net = TestNet::new(2);
+	net.sync_steps(2);
+	assert_eq!(net.peer(0).sync.read().peer_versions(), vec![PeerVersionCount {
+		protocol_version: 63,
+		client_version: "1".to_owned(),
+		count: 1,
+	}]);
+}
+
+#[test]
+fn refuses_old_peers() {
+	let mut config = SyncConfig::default();
+	config.min_peer_protocol_version = 65;
+	let mut net = TestNet::new_with_config(2, config);
+	net.sync_steps(2);
+	assert!(net.peer(0).sync.read().peer_versions().is_empty());
+	assert!(net.peer(1).sync.read().peer_versions().is_empty());
+}
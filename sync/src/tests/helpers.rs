@@ -18,6 +18,7 @@ use util::*;
 use ethcore::client::{TestBlockChainClient, BlockChainClient};
 use io::SyncIo;
 use chain::ChainSync;
+use api::WARP_SYNC_PROTOCOL;
 use ::SyncConfig;
 
 pub struct TestIo<'p> {
@@ -68,6 +69,10 @@ impl<'p> SyncIo for TestIo<'p> {
 	fn chain(&self) -> &BlockChainClient {
 		self.chain
 	}
+
+	fn protocol_version(&self, protocol: ProtocolId, _peer_id: PeerId) -> u8 {
+		if protocol == WARP_SYNC_PROTOCOL { 1 } else { 63 }
+	}
 }
 
 pub struct TestPacket {
@@ -89,13 +94,17 @@ pub struct TestNet {
 
 impl TestNet {
 	pub fn new(n: usize) -> TestNet {
+		TestNet::new_with_config(n, SyncConfig::default())
+	}
+
+	pub fn new_with_config(n: usize, config: SyncConfig) -> TestNet {
 		let mut net = TestNet {
 			peers: Vec::new(),
 			started: false,
 		};
 		for _ in 0..n {
 			let chain = TestBlockChainClient::new();
-			let sync = ChainSync::new(SyncConfig::default(), &chain);
+			let sync = ChainSync::new(config.clone(), &chain);
 			net.peers.push(TestPeer {
 				sync: RwLock::new(sync),
 				chain: chain,
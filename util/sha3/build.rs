@@ -20,7 +20,20 @@
 // invoking the C compiler.
 extern crate gcc;
 
+use std::env;
+
 fn main() {
     gcc::compile_library("libtinykeccak.a", &["src/tinykeccak.c"]);
+
+    // The accelerated variant is only used after a runtime CPU check,
+    // so it's safe to build it for every x86_64 target.
+    if env::var("TARGET").map(|t| t.starts_with("x86_64")).unwrap_or(false) {
+        gcc::Config::new()
+            .file("src/tinykeccak_avx2.c")
+            .flag("-O3")
+            .flag("-mavx2")
+            .flag("-mbmi2")
+            .compile("libtinykeccak_avx2.a");
+    }
 }
 
@@ -18,3 +18,10 @@ extern {
 	pub fn sha3_256(out: *mut u8, outlen: usize, input: *const u8, inputlen: usize) -> i32;
 	pub fn sha3_512(out: *mut u8, outlen: usize, input: *const u8, inputlen: usize) -> i32;
 }
+
+#[cfg(target_arch = "x86_64")]
+extern {
+	pub fn sha3_256_avx2(out: *mut u8, outlen: usize, input: *const u8, inputlen: usize) -> i32;
+	pub fn sha3_512_avx2(out: *mut u8, outlen: usize, input: *const u8, inputlen: usize) -> i32;
+	pub fn sha3_avx2_supported() -> i32;
+}
@@ -0,0 +1,20 @@
+/* Keccak compiled for CPUs supporting AVX2 and BMI2.
+ *
+ * The implementation is shared with tinykeccak.c, exported symbols are
+ * renamed so both variants can be linked into the same binary. Callers must
+ * check `sha3_avx2_supported` before using any of the functions below.
+ */
+
+#define shake128 shake128_avx2
+#define shake256 shake256_avx2
+#define sha3_224 sha3_224_avx2
+#define sha3_256 sha3_256_avx2
+#define sha3_384 sha3_384_avx2
+#define sha3_512 sha3_512_avx2
+
+#include "tinykeccak.c"
+
+int sha3_avx2_supported(void) {
+	__builtin_cpu_init();
+	return __builtin_cpu_supports("avx2") && __builtin_cpu_supports("bmi2");
+}
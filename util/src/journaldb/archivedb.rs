@@ -200,6 +200,25 @@ impl JournalDB for ArchiveDB {
 
 	fn latest_era(&self) -> Option<u64> { self.latest_era }
 
+	fn backing(&self) -> &Arc<Database> {
+		&self.backing
+	}
+
+	fn inject(&mut self, batch: &DBTransaction) -> Result<u32, UtilError> {
+		let mut ops = 0u32;
+		for (key, (value, rc)) in self.overlay.drain() {
+			if rc > 0 {
+				try!(batch.put(&key, &value));
+				ops += 1;
+			}
+			if rc < 0 {
+				try!(batch.delete(&key));
+				ops += 1;
+			}
+		}
+		Ok(ops)
+	}
+
 	fn state(&self, id: &H256) -> Option<Bytes> {
 		self.backing.get_by_prefix(&id[0..DB_PREFIX_LEN]).map(|b| b.to_vec())
 	}
@@ -330,6 +330,25 @@ impl JournalDB for EarlyMergeDB {
 
 	fn latest_era(&self) -> Option<u64> { self.latest_era }
 
+	fn backing(&self) -> &Arc<Database> {
+		&self.backing
+	}
+
+	fn inject(&mut self, batch: &DBTransaction) -> Result<u32, UtilError> {
+		let mut ops = 0u32;
+		for (key, (value, rc)) in self.overlay.drain() {
+			if rc > 0 {
+				try!(batch.put(&key, &value));
+				ops += 1;
+			}
+			if rc < 0 {
+				try!(batch.delete(&key));
+				ops += 1;
+			}
+		}
+		Ok(ops)
+	}
+
 	fn mem_used(&self) -> usize {
 		self.overlay.mem_used() + match self.refs {
 			Some(ref c) => c.read().heap_size_of_children(),
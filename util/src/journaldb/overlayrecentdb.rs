@@ -217,6 +217,25 @@ impl JournalDB for OverlayRecentDB {
 
 	fn latest_era(&self) -> Option<u64> { self.journal_overlay.read().latest_era }
 
+	fn backing(&self) -> &Arc<Database> {
+		&self.backing
+	}
+
+	fn inject(&mut self, batch: &DBTransaction) -> Result<u32, UtilError> {
+		let mut ops = 0u32;
+		for (key, (value, rc)) in self.transaction_overlay.drain() {
+			if rc > 0 {
+				try!(batch.put(&key, &value));
+				ops += 1;
+			}
+			if rc < 0 {
+				try!(batch.delete(&key));
+				ops += 1;
+			}
+		}
+		Ok(ops)
+	}
+
 	fn state(&self, key: &H256) -> Option<Bytes> {
 		let v = self.journal_overlay.read().backing_overlay.get(&OverlayRecentDB::to_short_key(key)).map(|v| v.to_vec());
 		v.or_else(|| self.backing.get_by_prefix(&key[0..DB_PREFIX_LEN]).map(|b| b.to_vec()))
@@ -374,7 +393,7 @@ mod tests {
 	use hashdb::*;
 	use log::init_log;
 	use journaldb::JournalDB;
-	use kvdb::DatabaseConfig;
+	use kvdb::{DatabaseConfig, DBTransaction};
 
 	#[test]
 	fn insert_same_in_fork() {
@@ -902,4 +921,24 @@ mod tests {
 		assert!(jdb.contains(&foo));
 		assert!(jdb.contains(&bar));
 	}
+
+	#[test]
+	fn inject() {
+		let mut dir = ::std::env::temp_dir();
+		dir.push(H32::random().hex());
+
+		let foo = {
+			let mut jdb = OverlayRecentDB::new(dir.to_str().unwrap(), DatabaseConfig::default());
+			let foo = jdb.insert(b"foo");
+			let batch = DBTransaction::new();
+			assert_eq!(jdb.inject(&batch).unwrap(), 1);
+			jdb.backing().write(batch).unwrap();
+			assert!(jdb.contains(&foo));
+			foo
+		};
+
+		// injected data doesn't depend on any era being canonicalised.
+		let jdb = OverlayRecentDB::new(dir.to_str().unwrap(), DatabaseConfig::default());
+		assert!(jdb.contains(&foo));
+	}
 }
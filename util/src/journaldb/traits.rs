@@ -18,6 +18,7 @@
 
 use common::*;
 use hashdb::*;
+use kvdb::{Database, DBTransaction};
 
 /// A `HashDB` which can manage a short-term journal potentially containing many forks of mutually
 /// exclusive actions.
@@ -38,9 +39,18 @@ pub trait JournalDB : HashDB + Send + Sync {
 	/// old era to the backing database, reverting any non-canonical historical commit's inserts.
 	fn commit(&mut self, now: u64, id: &H256, end: Option<(u64, H256)>) -> Result<u32, UtilError>;
 
+	/// Add all insert and remove operations since the last commit to `batch`, to be written
+	/// straight to the backing database without journalling. Used for data which belongs to
+	/// the database regardless of any era, e.g. state nodes fetched to heal it.
+	/// Returns the number of operations added.
+	fn inject(&mut self, batch: &DBTransaction) -> Result<u32, UtilError>;
+
 	/// State data query
 	fn state(&self, _id: &H256) -> Option<Bytes>;
 
 	/// Whether this database is pruned.
 	fn is_pruned(&self) -> bool { true }
+
+	/// Get backing database.
+	fn backing(&self) -> &Arc<Database>;
 }
@@ -219,6 +219,11 @@ impl Database {
 		self.db.iterator(IteratorMode::Start).next().is_none()
 	}
 
+	/// Get integer value of a RocksDB property, e.g. `rocksdb.estimate-num-keys`.
+	pub fn property_int(&self, name: &str) -> Option<u64> {
+		self.db.property_value(name).and_then(|value| value.trim().parse().ok())
+	}
+
 	/// Check if there is anything in the database.
 	pub fn iter(&self) -> DatabaseIterator {
 		DatabaseIterator { iter: self.db.iterator(IteratorMode::Start) }
@@ -179,9 +179,10 @@ impl Manager {
 	}
 
 	/// Adds new migration rules.
+	/// Migrations must be added in order of version; versions which leave the database unchanged may be skipped.
 	pub fn add_migration<T>(&mut self, migration: T) -> Result<(), Error> where T: Migration {
 		let version_match = match self.migrations.last() {
-			Some(last) => last.version() + 1 == migration.version(),
+			Some(last) => last.version() < migration.version(),
 			None => true,
 		};
 
@@ -237,7 +238,7 @@ impl Manager {
 
 	fn migrations_from(&mut self, version: u32) -> Option<&mut [Box<Migration>]> {
 		// index of the first required migration
-		let position = self.migrations.iter().position(|m| m.version() == version + 1);
+		let position = self.migrations.iter().position(|m| m.version() > version);
 		position.map(move |p| &mut self.migrations[p..])
 	}
 }
@@ -149,3 +149,34 @@ fn is_migration_needed() {
 	assert!(manager.is_needed(1));
 	assert!(!manager.is_needed(2));
 }
+
+struct Migration3;
+
+impl SimpleMigration for Migration3 {
+	fn version(&self) -> u32 {
+		4
+	}
+
+	fn simple_migrate(&mut self, key: Vec<u8>, value: Vec<u8>) -> Option<(Vec<u8>, Vec<u8>)> {
+		let mut value = value;
+		value.push(0x33);
+		Some((key, value))
+	}
+}
+
+#[test]
+fn migrations_may_skip_versions() {
+	let dir = RandomTempPath::create_dir();
+	let db_path = db_path(dir.as_path());
+	let mut manager = Manager::new(Config::default());
+	make_db(&db_path, map![vec![] => vec![], vec![1] => vec![1]]);
+	let expected = map![vec![] => vec![0x33], vec![1] => vec![1, 0x33]];
+
+	manager.add_migration(Migration0).unwrap();
+	manager.add_migration(Migration3).unwrap();
+	assert!(manager.add_migration(Migration1).is_err());
+	assert!(manager.is_needed(2));
+	let end_path = manager.execute(&db_path, 2).unwrap();
+
+	verify_migration(&end_path, expected);
+}
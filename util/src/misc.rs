@@ -52,6 +52,26 @@ pub fn version() -> String {
 	format!("Parity/v{}-unstable{}{}{}{}/{}-{}{}{}/rustc{}", env!("CARGO_PKG_VERSION"), sha3_dash, sha3, date_dash, commit_date, Target::arch(), Target::os(), env_dash, env, rustc_version())
 }
 
+/// Maximal length of the node identity included in the version string.
+const MAX_IDENTITY_LEN: usize = 32;
+
+/// Make a user-supplied node name safe to include in the version string.
+/// Only ASCII alphanumerics, `-`, `_` and `.` are kept, up to 32 characters.
+pub fn sanitize_identity(identity: &str) -> String {
+	identity.chars()
+		.filter(|c| match *c {
+			'a'...'z' | 'A'...'Z' | '0'...'9' | '-' | '_' | '.' => true,
+			_ => false,
+		})
+		.take(MAX_IDENTITY_LEN)
+		.collect()
+}
+
+/// Get the version string including the node identity, e.g. `Parity/node1/v1.3.0-unstable/...`.
+pub fn version_with_identity(identity: &str) -> String {
+	version().replace("Parity/", &format!("Parity/{}/", sanitize_identity(identity)))
+}
+
 /// Get the standard version data for this software.
 pub fn version_data() -> Bytes {
 	let mut s = RlpStream::new_list(4);
@@ -64,4 +84,18 @@ pub fn version_data() -> Bytes {
 	s.append(&rustc_version());
 	s.append(&&Target::os()[0..2]);
 	s.out()
-}
\ No newline at end of file
+}
+
+#[test]
+fn should_sanitize_identity() {
+	assert_eq!(sanitize_identity("my node #1 (eu-west)"), "mynode1eu-west");
+	assert_eq!(sanitize_identity("pool.example_01"), "pool.example_01");
+	assert_eq!(sanitize_identity("ñode/é"), "ode");
+	assert_eq!(sanitize_identity(&::std::iter::repeat('a').take(40).collect::<String>()).len(), 32);
+}
+
+#[test]
+fn should_include_identity_in_version() {
+	assert!(version_with_identity("node1").starts_with("Parity/node1/v"));
+	assert!(version_with_identity("").starts_with("Parity//v"));
+}
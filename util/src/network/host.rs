@@ -32,7 +32,7 @@ use misc::*;
 use crypto::*;
 use sha3::Hashable;
 use rlp::*;
-use network::session::{Session, SessionData};
+use network::session::{Session, SessionData, ConnectedPeer};
 use error::*;
 use io::*;
 use network::{NetworkProtocolHandler, NonReservedPeerMode, PROTOCOL_VERSION};
@@ -76,6 +76,8 @@ pub struct NetworkConfiguration {
 	pub reserved_nodes: Vec<String>,
 	/// The non-reserved peer mode.
 	pub non_reserved_mode: NonReservedPeerMode,
+	/// Client version string sent in the handshake.
+	pub client_version: String,
 }
 
 impl Default for NetworkConfiguration {
@@ -99,6 +101,7 @@ impl NetworkConfiguration {
 			ideal_peers: 25,
 			reserved_nodes: Vec::new(),
 			non_reserved_mode: NonReservedPeerMode::Accept,
+			client_version: version(),
 		}
 	}
 
@@ -144,6 +147,8 @@ pub enum NetworkIoMessage {
 		handler: Arc<NetworkProtocolHandler + Sync>,
 		/// Protocol Id.
 		protocol: ProtocolId,
+		/// Number of packet IDs used by the protocol.
+		packet_count: u8,
 		/// Supported protocol versions.
 		versions: Vec<u8>,
 	},
@@ -222,9 +227,14 @@ impl<'s> NetworkContext<'s> {
 
 	/// Send a packet over the network to another peer.
 	pub fn send(&self, peer: PeerId, packet_id: PacketId, data: Vec<u8>) -> Result<(), UtilError> {
+		self.send_protocol(self.protocol, peer, packet_id, data)
+	}
+
+	/// Send a packet of another protocol handled by the same handler to a peer.
+	pub fn send_protocol(&self, protocol: ProtocolId, peer: PeerId, packet_id: PacketId, data: Vec<u8>) -> Result<(), UtilError> {
 		let session = self.resolve_session(peer);
 		if let Some(session) = session {
-			try!(session.lock().send_packet(self.io, self.protocol, packet_id as u8, &data));
+			try!(session.lock().send_packet(self.io, protocol, packet_id as u8, &data));
 		} else  {
 			trace!(target: "network", "Send: Peer no longer exist")
 		}
@@ -278,6 +288,17 @@ impl<'s> NetworkContext<'s> {
 		}
 		"unknown".to_owned()
 	}
+
+	/// Returns the version of the given protocol negotiated with the peer, if the peer supports it
+	pub fn protocol_version(&self, protocol: ProtocolId, peer: PeerId) -> Option<u8> {
+		let session = self.resolve_session(peer);
+		session.and_then(|s| s.lock().capability_version(protocol))
+	}
+
+	/// Returns the protocol this context was created for
+	pub fn subprotocol_name(&self) -> ProtocolId {
+		self.protocol
+	}
 }
 
 /// Shared host information
@@ -375,13 +396,15 @@ impl Host {
 		let boot_nodes = config.boot_nodes.clone();
 		let reserved_nodes = config.reserved_nodes.clone();
 
+		let client_version = config.client_version.clone();
+
 		let mut host = Host {
 			info: RwLock::new(HostInfo {
 				keys: keys,
 				config: config,
 				nonce: H256::random(),
 				protocol_version: PROTOCOL_VERSION,
-				client_version: version(),
+				client_version: client_version,
 				capabilities: Vec::new(),
 				public_endpoint: None,
 				local_endpoint: local_endpoint,
@@ -476,8 +499,12 @@ impl Host {
 		Ok(())
 	}
 
-	pub fn client_version() -> String {
-		version()
+	pub fn connected_peers(&self) -> Vec<ConnectedPeer> {
+		self.sessions.read().iter()
+			.map(|e| e.lock())
+			.filter(|s| s.is_ready() && !s.expired())
+			.map(|s| s.connected_peer())
+			.collect()
 	}
 
 	pub fn external_url(&self) -> Option<String> {
@@ -790,6 +817,9 @@ impl Host {
 								}
 							}
 						}
+						if let Some(id) = s.id() {
+							self.nodes.write().note_success(id);
+						}
 						for (p, _) in self.handlers.read().iter() {
 							if s.have_capability(p)  {
 								ready_data.push(p);
@@ -967,7 +997,9 @@ impl IoHandler<NetworkIoMessage> for Host {
 			},
 			NODE_TABLE => {
 				trace!(target: "network", "Refreshing node table");
-				self.nodes.write().clear_useless();
+				let mut nodes = self.nodes.write();
+				nodes.clear_useless();
+				nodes.save();
 			},
 			_ => match self.timers.read().get(&token).cloned() {
 				Some(timer) => match self.handlers.read().get(timer.protocol).cloned() {
@@ -990,6 +1022,7 @@ impl IoHandler<NetworkIoMessage> for Host {
 			NetworkIoMessage::AddHandler {
 				ref handler,
 				ref protocol,
+				ref packet_count,
 				ref versions
 			} => {
 				let h = handler.clone();
@@ -998,7 +1031,7 @@ impl IoHandler<NetworkIoMessage> for Host {
 				self.handlers.write().insert(protocol, h);
 				let mut info = self.info.write();
 				for v in versions {
-					info.capabilities.push(CapabilityInfo { protocol: protocol, version: *v, packet_count:0 });
+					info.capabilities.push(CapabilityInfo { protocol: protocol, version: *v, packet_count: *packet_count });
 				}
 			},
 			NetworkIoMessage::AddTimer {
@@ -48,7 +48,7 @@
 //!
 //! fn main () {
 //! 	let mut service = NetworkService::new(NetworkConfiguration::new_local()).expect("Error creating network service");
-//! 	service.register_protocol(Arc::new(MyHandler), "myproto", &[1u8]);
+//! 	service.register_protocol(Arc::new(MyHandler), "myproto", 1, &[1u8]);
 //! 	service.start().expect("Error starting service");
 //!
 //! 	// Wait for quit condition
@@ -72,11 +72,13 @@ mod tests;
 
 pub use network::host::PeerId;
 pub use network::host::PacketId;
+pub use network::host::ProtocolId;
 pub use network::host::NetworkContext;
 pub use network::service::NetworkService;
 pub use network::host::NetworkIoMessage;
 pub use network::error::NetworkError;
 pub use network::host::NetworkConfiguration;
+pub use network::session::ConnectedPeer;
 pub use network::stats::NetworkStats;
 
 use io::TimerToken;
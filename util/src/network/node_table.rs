@@ -20,6 +20,7 @@ use std::net::{SocketAddr, ToSocketAddrs, SocketAddrV4, SocketAddrV6, Ipv4Addr,
 use std::hash::{Hash, Hasher};
 use std::str::{FromStr};
 use std::collections::{HashMap, HashSet};
+use std::cmp::Ordering;
 use std::fmt::{Display, Formatter};
 use std::path::{PathBuf};
 use std::fmt;
@@ -135,6 +136,8 @@ pub struct Node {
 	pub peer_type: PeerType,
 	pub failures: u32,
 	pub last_attempted: Option<Tm>,
+	/// Seconds since the epoch when a session with the node was last established.
+	pub last_contact: Option<u64>,
 }
 
 impl Node {
@@ -145,6 +148,7 @@ impl Node {
 			peer_type: PeerType::Optional,
 			failures: 0,
 			last_attempted: None,
+			last_contact: None,
 		}
 	}
 }
@@ -176,6 +180,7 @@ impl FromStr for Node {
 			peer_type: PeerType::Optional,
 			last_attempted: None,
 			failures: 0,
+			last_contact: None,
 		})
 	}
 }
@@ -211,16 +216,21 @@ impl NodeTable {
 
 	/// Add a node to table
 	pub fn add_node(&mut self, mut node: Node) {
-		// preserve failure counter
-		let failures = self.nodes.get(&node.id).map_or(0, |n| n.failures);
-		node.failures = failures;
+		// preserve failure counter and last contact
+		if let Some(known) = self.nodes.get(&node.id) {
+			node.failures = known.failures;
+			node.last_contact = known.last_contact;
+		}
 		self.nodes.insert(node.id.clone(), node);
 	}
 
-	/// Returns node ids sorted by number of failures
+	/// Returns node ids sorted by number of failures, most recently contacted first among equals
 	pub fn nodes(&self) -> Vec<NodeId> {
 		let mut refs: Vec<&Node> = self.nodes.values().filter(|n| !self.useless_nodes.contains(&n.id)).collect();
-		refs.sort_by(|a, b| a.failures.cmp(&b.failures));
+		refs.sort_by(|a, b| match a.failures.cmp(&b.failures) {
+			Ordering::Equal => b.last_contact.cmp(&a.last_contact),
+			ordering => ordering,
+		});
 		refs.iter().map(|n| n.id.clone()).collect()
 	}
 
@@ -255,6 +265,14 @@ impl NodeTable {
 		}
 	}
 
+	/// Note an established session with a node, resetting its failure counter
+	pub fn note_success(&mut self, id: &NodeId) {
+		if let Some(node) = self.nodes.get_mut(id) {
+			node.failures = 0;
+			node.last_contact = Some(::time::get_time().sec as u64);
+		}
+	}
+
 	/// Mark as useless, no furter attempts to connect until next call to `clear_useless`.
 	pub fn mark_as_useless(&mut self, id: &NodeId) {
 		self.useless_nodes.insert(id.clone());
@@ -265,7 +283,8 @@ impl NodeTable {
 		self.useless_nodes.clear();
 	}
 
-	fn save(&self) {
+	/// Write the table to disk
+	pub fn save(&self) {
 		if let Some(ref path) = self.path {
 			let mut path_buf = PathBuf::from(path);
 			if let Err(e) = fs::create_dir_all(path_buf.as_path()) {
@@ -279,7 +298,8 @@ impl NodeTable {
 			let node_ids = self.nodes();
 			for i in 0 .. node_ids.len() {
 				let node = self.nodes.get(&node_ids[i]).unwrap();
-				json.push_str(&format!("\t{{ \"url\": \"{}\", \"failures\": {} }}{}\n", node, node.failures, if i == node_ids.len() - 1 {""} else {","}))
+				let last_contact = node.last_contact.map_or(String::new(), |t| format!(", \"lastContact\": {}", t));
+				json.push_str(&format!("\t{{ \"url\": \"{}\", \"failures\": {}{} }}{}\n", node, node.failures, last_contact, if i == node_ids.len() - 1 {""} else {","}))
 			}
 			json.push_str("]\n");
 			json.push_str("}");
@@ -330,6 +350,7 @@ impl NodeTable {
 							if let Some(failures) = n.get("failures").and_then(|f| f.as_u64()) {
 								node.failures = failures as u32;
 							}
+							node.last_contact = n.get("lastContact").and_then(|t| t.as_u64());
 							nodes.insert(node.id.clone(), node);
 						}
 					}
@@ -431,4 +452,31 @@ mod tests {
 			assert_eq!(r[1][..], id2[..]);
 		}
 	}
+
+	#[test]
+	fn table_prefers_contacted_nodes() {
+		let temp_path = RandomTempPath::create_dir();
+		let node1 = Node::from_str("enode://a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let node2 = Node::from_str("enode://b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c@22.99.55.44:7770").unwrap();
+		let id1 = H512::from_str("a979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		let id2 = H512::from_str("b979fb575495b8d6db44f750317d0f4622bf4c2aa3365d6af7c284339968eef29b69ad0dce72a4d8db5ebb4968de0e3bec910127f134779fbcb0cb6d3331163c").unwrap();
+		{
+			let mut table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			table.add_node(node1);
+			table.add_node(node2);
+			table.note_failure(&id2);
+			table.note_success(&id2);
+			let r = table.nodes();
+			assert_eq!(r[0][..], id2[..]);
+			assert_eq!(r[1][..], id1[..]);
+			table.save();
+		}
+
+		{
+			let table = NodeTable::new(Some(temp_path.as_path().to_str().unwrap().to_owned()));
+			let r = table.nodes();
+			assert_eq!(r[0][..], id2[..]);
+			assert_eq!(r[1][..], id1[..]);
+		}
+	}
 }
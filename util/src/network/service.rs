@@ -19,6 +19,7 @@ use panics::*;
 use network::{NetworkProtocolHandler, NetworkConfiguration};
 use network::error::NetworkError;
 use network::host::{Host, NetworkContext, NetworkIoMessage, ProtocolId};
+use network::session::ConnectedPeer;
 use network::stats::NetworkStats;
 use io::*;
 use parking_lot::RwLock;
@@ -62,7 +63,7 @@ impl NetworkService {
 		panic_handler.forward_from(&io_service);
 
 		let stats = Arc::new(NetworkStats::new());
-		let host_info = Host::client_version();
+		let host_info = config.client_version.clone();
 		Ok(NetworkService {
 			io_service: io_service,
 			host_info: host_info,
@@ -74,11 +75,12 @@ impl NetworkService {
 		})
 	}
 
-	/// Regiter a new protocol handler with the event loop.
-	pub fn register_protocol(&self, handler: Arc<NetworkProtocolHandler + Send + Sync>, protocol: ProtocolId, versions: &[u8]) -> Result<(), NetworkError> {
+	/// Regiter a new protocol handler with the event loop. `packet_count` is the number of packet IDs the protocol uses.
+	pub fn register_protocol(&self, handler: Arc<NetworkProtocolHandler + Send + Sync>, protocol: ProtocolId, packet_count: u8, versions: &[u8]) -> Result<(), NetworkError> {
 		try!(self.io_service.send_message(NetworkIoMessage::AddHandler {
 			handler: handler,
 			protocol: protocol,
+			packet_count: packet_count,
 			versions: versions.to_vec(),
 		}));
 		Ok(())
@@ -116,6 +118,12 @@ impl NetworkService {
 		host.as_ref().map(|h| h.local_url())
 	}
 
+	/// Returns summaries of all connected peers.
+	pub fn connected_peers(&self) -> Vec<ConnectedPeer> {
+		let host = self.host.read();
+		host.as_ref().map_or_else(Vec::new, |h| h.connected_peers())
+	}
+
 	/// Start network IO
 	pub fn start(&self) -> Result<(), UtilError> {
 		let mut host = self.host.write();
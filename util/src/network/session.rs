@@ -88,6 +88,25 @@ pub struct SessionInfo {
 	pub originated: bool,
 }
 
+/// Summary of a connected peer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectedPeer {
+	/// Peer public key
+	pub id: Option<NodeId>,
+	/// Peer client ID
+	pub client_version: String,
+	/// Peer RLPx protocol version
+	pub protocol_version: u32,
+	/// Negotiated protocol capabilities, e.g. `eth/63`
+	pub capabilities: Vec<String>,
+	/// Remote socket address
+	pub remote_address: String,
+	/// Peer ping delay in milliseconds
+	pub ping_ms: Option<u64>,
+	/// True if this session was originated by us.
+	pub originated: bool,
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct PeerCapabilityInfo {
 	pub protocol: String,
@@ -200,6 +219,19 @@ impl Session {
 		self.connection().remote_addr()
 	}
 
+	/// Get a summary of the remote peer
+	pub fn connected_peer(&self) -> ConnectedPeer {
+		ConnectedPeer {
+			id: self.info.id.clone(),
+			client_version: self.info.client_version.clone(),
+			protocol_version: self.info.protocol_version,
+			capabilities: self.info.capabilities.iter().map(|c| format!("{}/{}", c.protocol, c.version)).collect(),
+			remote_address: self.connection().remote_addr_str(),
+			ping_ms: self.info.ping_ms,
+			originated: self.info.originated,
+		}
+	}
+
 	/// Readable IO handler. Returns packet data if available.
 	pub fn readable<Message>(&mut self, io: &IoContext<Message>, host: &HostInfo) -> Result<SessionData, UtilError>  where Message: Send + Sync + Clone {
 		if self.expired() {
@@ -244,6 +276,11 @@ impl Session {
 		self.info.capabilities.iter().any(|c| c.protocol == protocol)
 	}
 
+	/// Returns negotiated version of the given capability, if supported by peer
+	pub fn capability_version(&self, protocol: &str) -> Option<u8> {
+		self.info.capabilities.iter().find(|c| c.protocol == protocol).map(|c| c.version)
+	}
+
 	/// Register the session socket with the event loop
 	pub fn register_socket<Host:Handler<Timeout = Token>>(&self, reg: Token, event_loop: &mut EventLoop<Host>) -> Result<(), UtilError> {
 		if self.expired() {
@@ -348,19 +385,16 @@ impl Session {
 			PACKET_GET_PEERS => Ok(SessionData::None), //TODO;
 			PACKET_PEERS => Ok(SessionData::None),
 			PACKET_USER ... PACKET_LAST => {
-				let mut i = 0usize;
-				while packet_id < self.info.capabilities[i].id_offset {
-					i += 1;
-					if i == self.info.capabilities.len() {
+				// map to protocol
+				let capability = self.info.capabilities.iter()
+					.find(|c| packet_id >= c.id_offset && packet_id - c.id_offset < c.packet_count);
+				match capability {
+					Some(c) => Ok(SessionData::Packet { data: packet.data, protocol: c.protocol, packet_id: packet_id - c.id_offset }),
+					None => {
 						debug!(target: "network", "Unknown packet: {:?}", packet_id);
-						return Ok(SessionData::Continue)
+						Ok(SessionData::Continue)
 					}
 				}
-
-				// map to protocol
-				let protocol = self.info.capabilities[i].protocol;
-				let pid = packet_id - self.info.capabilities[i].id_offset;
-				Ok(SessionData::Packet { data: packet.data, protocol: protocol, packet_id: pid } )
 			},
 			_ => {
 				debug!(target: "network", "Unknown packet: {:?}", packet_id);
@@ -413,6 +447,8 @@ impl Session {
 			}
 		}
 
+		// Packet IDs are assigned to capabilities in alphabetical order
+		caps.sort_by(|a, b| a.protocol.cmp(b.protocol));
 		i = 0;
 		let mut offset: u8 = PACKET_USER;
 		while i < caps.len() {
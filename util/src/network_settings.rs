@@ -32,5 +32,9 @@ pub struct NetworkSettings {
 	pub rpc_interface: String,
 	/// Port for JSON-RPC server
 	pub rpc_port: u16,
+	/// Database directory
+	pub db_path: String,
+	/// Key store directory
+	pub keys_path: String,
 }
 
@@ -95,3 +95,23 @@ pub fn restrict_permissions_owner(_file_path: &Path) -> Result<(), i32>  {
 	Ok(())
 }
 
+/// Changes the owner and group of given path. `None` leaves the respective id unchanged.
+#[cfg(not(windows))]
+pub fn set_owner(file_path: &Path, uid: Option<u32>, gid: Option<u32>) -> ::std::io::Result<()> {
+	let cstr = try!(::std::ffi::CString::new(file_path.to_string_lossy().into_owned())
+		.map_err(|e| ::std::io::Error::new(::std::io::ErrorKind::InvalidInput, e)));
+	// -1 keeps the id unchanged.
+	let uid = uid.map_or(!0, |uid| uid as ::libc::uid_t);
+	let gid = gid.map_or(!0, |gid| gid as ::libc::gid_t);
+	match unsafe { ::libc::chown(cstr.as_ptr(), uid, gid) } {
+		0 => Ok(()),
+		_ => Err(::std::io::Error::last_os_error()),
+	}
+}
+
+/// Changes the owner and group of given path. Not supported on Windows.
+#[cfg(windows)]
+pub fn set_owner(_file_path: &Path, _uid: Option<u32>, _gid: Option<u32>) -> ::std::io::Result<()> {
+	Ok(())
+}
+
@@ -17,7 +17,10 @@
 //! Wrapper around tiny-keccak crate.
 extern crate sha3 as sha3_ext;
 
+use std::fmt;
 use std::mem::uninitialized;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering, ATOMIC_USIZE_INIT};
 use bytes::{BytesConvertable, Populatable};
 use hash::{H256, FixedHash};
 use self::sha3_ext::*;
@@ -26,6 +29,117 @@ use self::sha3_ext::*;
 pub const SHA3_EMPTY: H256 = H256( [0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0, 0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70] );
 
 
+/// Keccak implementation used for hashing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+	/// Portable implementation, works everywhere.
+	Portable,
+	/// Implementation compiled for x86_64 CPUs with AVX2 and BMI2 extensions.
+	Avx2,
+}
+
+static BACKEND: AtomicUsize = ATOMIC_USIZE_INIT;
+
+impl Backend {
+	/// Returns all backends known to this build.
+	pub fn all() -> Vec<Backend> {
+		vec![Backend::Portable, Backend::Avx2]
+	}
+
+	/// Returns true if the backend can be used on this machine.
+	pub fn is_supported(&self) -> bool {
+		match *self {
+			Backend::Portable => true,
+			Backend::Avx2 => avx2_supported(),
+		}
+	}
+
+	/// Returns the fastest backend supported by this machine.
+	pub fn best() -> Backend {
+		if Backend::Avx2.is_supported() {
+			Backend::Avx2
+		} else {
+			Backend::Portable
+		}
+	}
+
+	/// Calculates SHA3 of `input` with this backend and places result into `dest`.
+	/// Fails if the backend is not supported by this machine.
+	pub fn sha3_into(&self, input: &[u8], dest: &mut [u8]) -> Result<(), String> {
+		if !self.is_supported() {
+			return Err(format!("Keccak backend {} is not supported by this CPU", self));
+		}
+		self.sha3_into_unchecked(input, dest);
+		Ok(())
+	}
+
+	// the caller must make sure the backend is supported.
+	fn sha3_into_unchecked(&self, input: &[u8], dest: &mut [u8]) {
+		match *self {
+			#[cfg(target_arch = "x86_64")]
+			Backend::Avx2 => unsafe {
+				sha3_256_avx2(dest.as_mut_ptr(), dest.len(), input.as_ptr(), input.len());
+			},
+			_ => unsafe {
+				sha3_256(dest.as_mut_ptr(), dest.len(), input.as_ptr(), input.len());
+			},
+		}
+	}
+}
+
+impl FromStr for Backend {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"portable" => Ok(Backend::Portable),
+			"avx2" => Ok(Backend::Avx2),
+			_ => Err(format!("Unknown keccak backend: {}", s)),
+		}
+	}
+}
+
+impl fmt::Display for Backend {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match *self {
+			Backend::Portable => write!(f, "portable"),
+			Backend::Avx2 => write!(f, "avx2"),
+		}
+	}
+}
+
+#[cfg(target_arch = "x86_64")]
+fn avx2_supported() -> bool {
+	unsafe { sha3_avx2_supported() != 0 }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn avx2_supported() -> bool {
+	false
+}
+
+/// Selects the backend used by `Hashable` from now on.
+/// Fails if the backend is not supported by this machine.
+pub fn set_backend(backend: Backend) -> Result<(), String> {
+	if !backend.is_supported() {
+		return Err(format!("Keccak backend {} is not supported by this CPU", backend));
+	}
+	let value = match backend {
+		Backend::Portable => 0,
+		Backend::Avx2 => 1,
+	};
+	BACKEND.store(value, Ordering::Relaxed);
+	Ok(())
+}
+
+/// Returns the backend currently used by `Hashable`.
+pub fn backend() -> Backend {
+	match BACKEND.load(Ordering::Relaxed) {
+		1 => Backend::Avx2,
+		_ => Backend::Portable,
+	}
+}
+
 /// Types implementing this trait are sha3able.
 ///
 /// ```
@@ -57,10 +171,8 @@ impl<T> Hashable for T where T: BytesConvertable {
 		}
 	}
 	fn sha3_into(&self, dest: &mut [u8]) {
-		unsafe {
-			let input: &[u8] = self.as_slice();
-			sha3_256(dest.as_mut_ptr(), dest.len(), input.as_ptr(), input.len());
-		}
+		// `set_backend` only accepts supported backends.
+		backend().sha3_into_unchecked(self.as_slice(), dest);
 	}
 }
 
@@ -72,4 +184,21 @@ fn sha3_empty() {
 fn sha3_as() {
 	assert_eq!([0x41u8; 32].sha3(), From::from("59cad5948673622c1d64e2322488bf01619f7ff45789741b15a9f782ce9290a8"));
 }
-
+#[test]
+fn sha3_backends_agree() {
+	let input = [0x41u8; 300];
+	let mut expected = H256::new();
+	Backend::Portable.sha3_into(&input, expected.as_slice_mut()).unwrap();
+	for backend in Backend::all() {
+		let mut out = H256::new();
+		match backend.sha3_into(&input, out.as_slice_mut()) {
+			Ok(()) => assert_eq!(out, expected),
+			Err(_) => assert!(!backend.is_supported()),
+		}
+	}
+}
+#[test]
+fn sha3_best_backend_is_supported() {
+	assert!(Backend::best().is_supported());
+	assert!(set_backend(Backend::best()).is_ok());
+}
@@ -36,6 +36,8 @@ pub mod triedbmut;
 pub mod sectriedb;
 /// Export the sectriedbmut module.
 pub mod sectriedbmut;
+/// Export the proof module.
+pub mod proof;
 
 mod fatdb;
 mod fatdbmut;
@@ -48,17 +50,29 @@ pub use self::sectriedbmut::SecTrieDBMut;
 pub use self::sectriedb::SecTrieDB;
 pub use self::fatdb::{FatDB, FatDBIterator};
 pub use self::fatdbmut::FatDBMut;
+pub use self::proof::{RangeProof, prove_range, verify_range};
 
 /// Trie Errors
 #[derive(Debug)]
 pub enum TrieError {
 	/// Attempted to create a trie with a state root not in the DB.
 	InvalidStateRoot,
+	/// Trie node referenced by the trie is not in the DB.
+	IncompleteDatabase(H256),
+	/// Trie node couldn't be decoded.
+	InvalidNode,
+	/// Proven items don't match the trie.
+	InvalidProof,
 }
 
 impl fmt::Display for TrieError {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-		write!(f, "Trie Error: Invalid state root.")
+		match *self {
+			TrieError::InvalidStateRoot => write!(f, "Trie Error: Invalid state root."),
+			TrieError::IncompleteDatabase(ref hash) => write!(f, "Trie Error: Missing trie node {}.", hash),
+			TrieError::InvalidNode => write!(f, "Trie Error: Invalid trie node."),
+			TrieError::InvalidProof => write!(f, "Trie Error: Proof doesn't match the trie."),
+		}
 	}
 }
 
@@ -0,0 +1,274 @@
+// Copyright 2015, 2016 Ethcore (UK) Ltd.
+// This file is part of Parity.
+
+// Parity is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Parity is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Parity.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Proofs of the contents of a contiguous range of keys of a trie.
+//!
+//! A proof consists of all nodes on the path to the first key of the range and of all
+//! nodes below it, up to and including the last item of the range. Walking the trie from
+//! the root using only those nodes yields exactly the items of the range, so a verifier
+//! can check that no item was left out or altered.
+
+use std::cmp::min;
+use common::*;
+use hashdb::HashDB;
+use memorydb::MemoryDB;
+use rlp::*;
+use super::node::Node;
+use super::TrieError;
+
+/// Items of a range of a trie together with the nodes proving them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeProof {
+	/// Keys and values in the range, in key order.
+	pub items: Vec<(Bytes, Bytes)>,
+	/// Trie nodes needed to check the items against the trie root.
+	pub nodes: Vec<Bytes>,
+}
+
+/// Produce a proof of the first `limit` items of the trie with keys not smaller than `from`.
+/// If the proof contains fewer than `limit` items, the range extends to the end of the trie.
+pub fn prove_range(db: &HashDB, root: &H256, from: &[u8], limit: usize) -> Result<RangeProof, TrieError> {
+	let mut walker = RangeWalker::new(db, from, limit);
+	try!(walker.walk_root(root));
+	Ok(RangeProof {
+		items: walker.items,
+		nodes: walker.nodes,
+	})
+}
+
+/// Check that the items of the proof are exactly the items of the trie with given `root`
+/// and keys from `from` up to the key of the last item.
+pub fn verify_range(root: &H256, from: &[u8], proof: &RangeProof) -> Result<(), TrieError> {
+	let mut db = MemoryDB::new();
+	for node in &proof.nodes {
+		db.insert(node);
+	}
+
+	let mut walker = RangeWalker::new(&db, from, proof.items.len());
+	try!(walker.walk_root(root));
+	match walker.items == proof.items {
+		true => Ok(()),
+		false => Err(TrieError::InvalidProof),
+	}
+}
+
+// Check that the node RLP can be decoded without panicking.
+fn is_valid_node(data: &[u8]) -> bool {
+	fn is_complete(rlp: &UntrustedRlp) -> bool {
+		match rlp.payload_info() {
+			Ok(info) => info.header_len + info.value_len == rlp.as_raw().len(),
+			Err(_) => false,
+		}
+	}
+
+	let rlp = UntrustedRlp::new(data);
+	if !is_complete(&rlp) {
+		return false;
+	}
+	if rlp.is_data() {
+		return rlp.is_empty();
+	}
+
+	let items: Vec<_> = match (0..rlp.item_count()).map(|i| rlp.at(i)).collect::<Result<Vec<_>, _>>() {
+		Ok(items) => items,
+		Err(_) => return false,
+	};
+	if !items.iter().all(is_complete) {
+		return false;
+	}
+
+	match items.len() {
+		2 => match items[0].data() {
+			// the flag nibble tells whether it's a leaf, whose value must be data.
+			Ok(partial) if !partial.is_empty() => partial[0] & 0x20 == 0 || items[1].is_data(),
+			_ => false,
+		},
+		17 => items[16].is_data(),
+		_ => false,
+	}
+}
+
+fn nibbles_to_bytes(nibbles: &[u8]) -> Bytes {
+	nibbles.chunks(2).map(|pair| pair[0] * 16 + pair.get(1).cloned().unwrap_or(0)).collect()
+}
+
+/// Walks the trie in key order, collecting items from `from` on and the nodes visited.
+struct RangeWalker<'a> {
+	db: &'a HashDB,
+	from: Vec<u8>,
+	limit: usize,
+	items: Vec<(Bytes, Bytes)>,
+	nodes: Vec<Bytes>,
+}
+
+impl<'a> RangeWalker<'a> {
+	fn new(db: &'a HashDB, from: &[u8], limit: usize) -> Self {
+		RangeWalker {
+			db: db,
+			from: from.iter().flat_map(|b| vec![b >> 4, b & 0x0f]).collect(),
+			limit: limit,
+			items: Vec::new(),
+			nodes: Vec::new(),
+		}
+	}
+
+	fn full(&self) -> bool {
+		self.items.len() >= self.limit
+	}
+
+	// Whether the subtrie under `prefix` may contain keys not smaller than `from`.
+	fn may_contain_range(&self, prefix: &[u8]) -> bool {
+		let common = min(prefix.len(), self.from.len());
+		prefix[..common] >= self.from[..common]
+	}
+
+	fn note_item(&mut self, key: &[u8], value: &[u8]) {
+		if !self.full() && key >= &self.from[..] {
+			self.items.push((nibbles_to_bytes(key), value.to_vec()));
+		}
+	}
+
+	fn walk_root(&mut self, root: &H256) -> Result<(), TrieError> {
+		if self.limit == 0 {
+			return Ok(());
+		}
+		let node = try!(self.lookup(root));
+		self.walk(&node, &mut Vec::new())
+	}
+
+	fn lookup(&mut self, hash: &H256) -> Result<Bytes, TrieError> {
+		match self.db.get(hash) {
+			Some(node) => {
+				self.nodes.push(node.to_vec());
+				Ok(node.to_vec())
+			},
+			None => Err(TrieError::IncompleteDatabase(hash.clone())),
+		}
+	}
+
+	// Walk a child node, which is either stored inline or referenced by its hash.
+	fn walk_child(&mut self, child: &[u8], prefix: &mut Vec<u8>) -> Result<(), TrieError> {
+		let rlp = UntrustedRlp::new(child);
+		match rlp.is_data() && rlp.data().map(|d| d.len() == 32).unwrap_or(false) {
+			true => {
+				let hash: H256 = try!(rlp.as_val().map_err(|_| TrieError::InvalidNode));
+				let node = try!(self.lookup(&hash));
+				self.walk(&node, prefix)
+			},
+			false => self.walk(child, prefix),
+		}
+	}
+
+	fn walk(&mut self, data: &[u8], prefix: &mut Vec<u8>) -> Result<(), TrieError> {
+		if !is_valid_node(data) {
+			return Err(TrieError::InvalidNode);
+		}
+
+		let len = prefix.len();
+		match Node::decoded(data) {
+			Node::Empty => {},
+			Node::Leaf(partial, value) => {
+				prefix.extend(partial.iter());
+				self.note_item(prefix, value);
+			},
+			Node::Extension(partial, child) => {
+				prefix.extend(partial.iter());
+				if !self.full() && self.may_contain_range(prefix) {
+					try!(self.walk_child(child, prefix));
+				}
+			},
+			Node::Branch(children, value) => {
+				if let Some(value) = value {
+					self.note_item(prefix, value);
+				}
+				for (i, child) in children.iter().enumerate() {
+					prefix.push(i as u8);
+					if !self.full() && self.may_contain_range(prefix) {
+						try!(self.walk_child(child, prefix));
+					}
+					prefix.pop();
+				}
+			},
+		}
+		prefix.truncate(len);
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use common::*;
+	use hashdb::HashDB;
+	use memorydb::MemoryDB;
+	use super::super::{TrieDBMut, TrieMut, TrieError};
+	use super::{prove_range, verify_range};
+
+	fn populated() -> (MemoryDB, H256, Vec<(Bytes, Bytes)>) {
+		let items: Vec<(Bytes, Bytes)> = (0..200u32)
+			.map(|i| (format!("key{}", i).as_bytes().sha3().to_vec(), vec![i as u8; 1 + i as usize % 40]))
+			.collect();
+		let mut db = MemoryDB::new();
+		let mut root = H256::new();
+		{
+			let mut t = TrieDBMut::new(&mut db, &mut root);
+			for &(ref k, ref v) in &items {
+				t.insert(k, v);
+			}
+		}
+		let mut sorted = items;
+		sorted.sort();
+		(db, root, sorted)
+	}
+
+	#[test]
+	fn proves_range() {
+		let (db, root, items) = populated();
+		let from = items[50].0.clone();
+		let proof = prove_range(&db, &root, &from, 20).unwrap();
+		assert_eq!(proof.items, &items[50..70]);
+		assert!(proof.nodes.len() < db.keys().len());
+		verify_range(&root, &from, &proof).unwrap();
+
+		// a key between items starts the range at the next item.
+		let mut between = items[10].0.clone();
+		between.push(0);
+		let proof = prove_range(&db, &root, &between, 1000).unwrap();
+		assert_eq!(proof.items, &items[11..]);
+		verify_range(&root, &between, &proof).unwrap();
+	}
+
+	#[test]
+	fn rejects_altered_proofs() {
+		let (db, root, items) = populated();
+		let from = items[50].0.clone();
+		let proof = prove_range(&db, &root, &from, 20).unwrap();
+
+		let mut omitted = proof.clone();
+		omitted.items.remove(5);
+		assert!(verify_range(&root, &from, &omitted).is_err());
+
+		let mut altered = proof.clone();
+		altered.items[3].1 = b"altered".to_vec();
+		assert!(verify_range(&root, &from, &altered).is_err());
+
+		let mut incomplete = proof.clone();
+		incomplete.nodes.pop();
+		match verify_range(&root, &from, &incomplete) {
+			Err(TrieError::IncompleteDatabase(_)) => {},
+			other => panic!("Unexpected verification result: {:?}", other),
+		}
+	}
+}
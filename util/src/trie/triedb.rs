@@ -252,20 +252,45 @@ pub struct TrieDBIterator<'a> {
 	db: &'a TrieDB<'a>,
 	trail: Vec<Crumb<'a>>,
 	key_nibbles: Bytes,
+	first_nibbles: (u8, u8),
 }
 
 impl<'a> TrieDBIterator<'a> {
 	/// Create a new iterator.
 	pub fn new(db: &'a TrieDB) -> TrieDBIterator<'a> {
+		Self::with_first_nibbles(db, 0, 16)
+	}
+
+	/// Create a new iterator going only through the keys whose first nibble is
+	/// in range `from..to`. Subtries outside of the range are not visited.
+	pub fn with_first_nibbles(db: &'a TrieDB, from: u8, to: u8) -> TrieDBIterator<'a> {
 		let mut r = TrieDBIterator {
 			db: db,
 			trail: vec![],
 			key_nibbles: Vec::new(),
+			first_nibbles: (from, to),
 		};
 		r.descend(db.root_data());
 		r
 	}
 
+	/// Check whether the first nibble of the key is in the range being iterated.
+	/// An empty key belongs to the range starting with 0.
+	fn in_range(&self, first_nibble: Option<u8>) -> bool {
+		let (from, to) = self.first_nibbles;
+		match first_nibble {
+			Some(n) => n >= from && n < to,
+			None => from == 0,
+		}
+	}
+
+	/// Check whether child `i` of the branch on top of the trail should be visited.
+	fn visit_child(&self, i: usize) -> bool {
+		// the nibble of the previous child is still at the end of the key.
+		let depth = if i == 0 { self.key_nibbles.len() } else { self.key_nibbles.len() - 1 };
+		depth != 0 || self.in_range(Some(i as u8))
+	}
+
 	/// Descend into a payload.
 	fn descend(&mut self, d: &'a [u8]) {
 		self.trail.push(Crumb {
@@ -309,10 +334,15 @@ impl<'a> Iterator for TrieDBIterator<'a> {
 				self.trail.pop();
 				self.next()
 			},
-			(Status::At, Node::Leaf(_, v)) | (Status::At, Node::Branch(_, Some(v))) => Some((self.key(), v)),
+			(Status::At, Node::Leaf(_, v)) | (Status::At, Node::Branch(_, Some(v))) => {
+				match self.in_range(self.key_nibbles.first().cloned()) {
+					true => Some((self.key(), v)),
+					false => self.next(),
+				}
+			},
 			(Status::At, Node::Extension(_, d)) => self.descend_next(d),
 			(Status::At, Node::Branch(_, _)) => self.next(),
-			(Status::AtChild(i), Node::Branch(children, _)) if children[i].len() > 0 => {
+			(Status::AtChild(i), Node::Branch(children, _)) if children[i].len() > 0 && self.visit_child(i) => {
 				match i {
 					0 => self.key_nibbles.push(0),
 					i => *self.key_nibbles.last_mut().unwrap() = i as u8,
@@ -333,6 +363,11 @@ impl<'db> TrieDB<'db> {
 	pub fn iter(&self) -> TrieDBIterator {
 		TrieDBIterator::new(self)
 	}
+
+	/// Get keys/values stored in the trie whose keys start with a nibble in range `from..to`.
+	pub fn iter_first_nibbles(&self, from: u8, to: u8) -> TrieDBIterator {
+		TrieDBIterator::with_first_nibbles(self, from, to)
+	}
 }
 
 impl<'db> Trie for TrieDB<'db> {
@@ -379,3 +414,28 @@ fn iterator() {
 	assert_eq!(d.iter().map(|i|i.to_vec()).collect::<Vec<_>>(), TrieDB::new(&memdb, &root).unwrap().iter().map(|x|x.0).collect::<Vec<_>>());
 	assert_eq!(d, TrieDB::new(&memdb, &root).unwrap().iter().map(|x|x.1).collect::<Vec<_>>());
 }
+
+#[test]
+fn iterator_first_nibbles() {
+	use super::trietraits::TrieMut;
+	use memorydb::*;
+	use super::triedbmut::*;
+
+	let d = vec![ &b"\x10"[..], &b"\x20\x01"[..], &b"\x21"[..], &b"\x2f"[..], &b"\xf0"[..] ];
+
+	let mut memdb = MemoryDB::new();
+	let mut root = H256::new();
+	{
+		let mut t = TrieDBMut::new(&mut memdb, &mut root);
+		for x in &d {
+			t.insert(&x, &x);
+		}
+	}
+	let t = TrieDB::new(&memdb, &root).unwrap();
+	assert_eq!(t.iter_first_nibbles(0, 2).map(|x| x.0).collect::<Vec<_>>(), vec![b"\x10".to_vec()]);
+	assert_eq!(t.iter_first_nibbles(2, 3).map(|x| x.0).collect::<Vec<_>>(), vec![b"\x20\x01".to_vec(), b"\x21".to_vec(), b"\x2f".to_vec()]);
+	assert_eq!(t.iter_first_nibbles(3, 15).count(), 0);
+
+	let all = (0..16).flat_map(|n| t.iter_first_nibbles(n, n + 1).map(|x| x.0).collect::<Vec<_>>()).collect::<Vec<_>>();
+	assert_eq!(all, t.iter().map(|x| x.0).collect::<Vec<_>>());
+}